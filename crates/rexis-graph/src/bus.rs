@@ -0,0 +1,529 @@
+//! # Event Bus
+//!
+//! Some coordination does not fit passing state from node to node: a
+//! monitoring node may want to see everything that happens, or a producer
+//! may need to notify a consumer running in another parallel branch. Nodes
+//! reach the [`GraphEventBus`] of their run through
+//! [`ExecutionContext::events`](crate::ExecutionContext::events), publish
+//! [`StateValue`] payloads on topics and subscribe to them.
+//!
+//! A subscription receives the events published after it was made. The bus
+//! belongs to one run and is shared with the subgraphs it runs; when the run
+//! finishes, the bus closes and subscriptions end. The engine publishes
+//! [`NODE_STARTED`] and [`NODE_FINISHED`] for every node it runs.
+
+use crate::core::NodeId;
+use crate::state::StateValue;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+/// Topic the engine publishes to when a node starts; the payload is an
+/// object with the `node_id` and, in a parallel branch, the `branch`
+pub const NODE_STARTED: &str = "node.started";
+
+/// Topic the engine publishes to when a node finishes; besides the fields of
+/// [`NODE_STARTED`], the payload has `success`, `duration_ms` and `attempts`
+pub const NODE_FINISHED: &str = "node.finished";
+
+/// How many events a subscription buffers by default
+const DEFAULT_CAPACITY: usize = 256;
+
+/// An event published on the bus
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusEvent {
+    pub topic: String,
+    pub payload: StateValue,
+}
+
+/// Publish/subscribe channel between the nodes of a run; see the
+/// [module docs](self)
+///
+/// Clones share the bus.
+#[derive(Clone)]
+pub struct GraphEventBus {
+    inner: Arc<BusInner>,
+}
+
+struct BusInner {
+    capacity: usize,
+    /// `None` once the bus is closed, which drops every sender
+    channels: Mutex<Option<Channels>>,
+}
+
+struct Channels {
+    topics: HashMap<String, broadcast::Sender<BusEvent>>,
+    /// Sender of the subscriptions to every topic
+    all: broadcast::Sender<BusEvent>,
+}
+
+impl GraphEventBus {
+    /// Create an open bus
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create a bus whose subscriptions buffer up to `capacity` events; a
+    /// subscription falling further behind misses the oldest
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let channels = Channels {
+            topics: HashMap::new(),
+            all: broadcast::channel(capacity).0,
+        };
+        Self {
+            inner: Arc::new(BusInner {
+                capacity,
+                channels: Mutex::new(Some(channels)),
+            }),
+        }
+    }
+
+    /// Publish `payload` on `topic` and return how many subscriptions it
+    /// reached; once the bus is closed, it reaches none
+    pub fn publish(&self, topic: impl Into<String>, payload: impl Into<StateValue>) -> usize {
+        let event = BusEvent {
+            topic: topic.into(),
+            payload: payload.into(),
+        };
+        let channels = self.inner.channels.lock();
+        let Some(channels) = channels.as_ref() else {
+            return 0;
+        };
+
+        let mut reached = channels.all.send(event.clone()).unwrap_or(0);
+        if let Some(sender) = channels.topics.get(&event.topic) {
+            reached += sender.send(event).unwrap_or(0);
+        }
+        reached
+    }
+
+    /// Subscribe to the events published on `topic` from now on
+    pub fn subscribe(&self, topic: impl Into<String>) -> BusSubscription {
+        let mut channels = self.inner.channels.lock();
+        let receiver = match channels.as_mut() {
+            Some(channels) => channels
+                .topics
+                .entry(topic.into())
+                .or_insert_with(|| broadcast::channel(self.inner.capacity).0)
+                .subscribe(),
+            None => closed_receiver(),
+        };
+        BusSubscription { receiver }
+    }
+
+    /// Subscribe to the events of every topic, the engine's included
+    pub fn subscribe_all(&self) -> BusSubscription {
+        let channels = self.inner.channels.lock();
+        let receiver = match channels.as_ref() {
+            Some(channels) => channels.all.subscribe(),
+            None => closed_receiver(),
+        };
+        BusSubscription { receiver }
+    }
+
+    /// How many subscriptions to `topic` are open, leaving out the ones to
+    /// every topic
+    pub fn subscribers(&self, topic: &str) -> usize {
+        self.inner
+            .channels
+            .lock()
+            .as_ref()
+            .and_then(|channels| channels.topics.get(topic))
+            .map_or(0, |sender| sender.receiver_count())
+    }
+
+    /// Close the bus: subscriptions end after the events already published,
+    /// and publishing reaches no one
+    pub fn close(&self) {
+        self.inner.channels.lock().take();
+    }
+
+    /// Check whether the bus is closed
+    pub fn is_closed(&self) -> bool {
+        self.inner.channels.lock().is_none()
+    }
+
+    /// Close the bus when the returned guard is dropped
+    pub(crate) fn close_on_drop(&self) -> BusGuard {
+        BusGuard(self.clone())
+    }
+
+    /// Publish [`NODE_STARTED`] for a node
+    pub(crate) fn node_started(&self, node_id: &NodeId, branch: Option<&NodeId>) {
+        self.publish(NODE_STARTED, node_payload(node_id, branch));
+    }
+
+    /// Publish [`NODE_FINISHED`] for a node
+    pub(crate) fn node_finished(
+        &self,
+        node_id: &NodeId,
+        branch: Option<&NodeId>,
+        duration: Duration,
+        attempts: u32,
+        success: bool,
+    ) {
+        let mut payload = node_payload(node_id, branch);
+        payload.insert("success".to_string(), StateValue::Boolean(success));
+        payload.insert(
+            "duration_ms".to_string(),
+            StateValue::Integer(duration.as_millis() as i64),
+        );
+        payload.insert(
+            "attempts".to_string(),
+            StateValue::Integer(i64::from(attempts)),
+        );
+        self.publish(NODE_FINISHED, payload);
+    }
+}
+
+impl Default for GraphEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for GraphEventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphEventBus")
+            .field("capacity", &self.inner.capacity)
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+/// Closes a run's bus when the run ends, whichever way it ends
+pub(crate) struct BusGuard(GraphEventBus);
+
+impl Drop for BusGuard {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Events of one topic, or of every topic, in the order they were published
+#[derive(Debug)]
+pub struct BusSubscription {
+    receiver: broadcast::Receiver<BusEvent>,
+}
+
+impl BusSubscription {
+    /// Wait for the next event; `None` once the bus is closed and the events
+    /// published before were received
+    ///
+    /// Events missed by a subscription that fell too far behind are skipped.
+    pub async fn recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Event bus subscription missed {} events", missed);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Get the next event if one is waiting
+    pub fn try_recv(&mut self) -> Option<BusEvent> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => return Some(event),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// A receiver whose sender is gone
+fn closed_receiver() -> broadcast::Receiver<BusEvent> {
+    broadcast::channel(1).1
+}
+
+fn node_payload(node_id: &NodeId, branch: Option<&NodeId>) -> HashMap<String, StateValue> {
+    let mut payload = HashMap::new();
+    payload.insert("node_id".to_string(), StateValue::from(node_id.as_str()));
+    if let Some(branch) = branch {
+        payload.insert("branch".to_string(), StateValue::from(branch.as_str()));
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node};
+    use crate::execution::ExecutionEngine;
+    use crate::state::GraphState;
+    use crate::RGraphResult;
+    use async_trait::async_trait;
+    use tokio::task::JoinHandle;
+
+    #[tokio::test]
+    async fn test_subscriptions_get_their_topics() {
+        let bus = GraphEventBus::new();
+        let mut findings = bus.subscribe("findings");
+        let mut everything = bus.subscribe_all();
+
+        assert_eq!(bus.publish("findings", "rust"), 2);
+        assert_eq!(bus.publish("alerts", true), 1);
+        assert_eq!(bus.subscribers("findings"), 1);
+
+        let event = findings.recv().await.unwrap();
+        assert_eq!(event.topic, "findings");
+        assert_eq!(event.payload, StateValue::from("rust"));
+        assert_eq!(findings.try_recv(), None);
+
+        let topics: Vec<_> = [everything.try_recv(), everything.try_recv()]
+            .into_iter()
+            .map(|event| event.unwrap().topic)
+            .collect();
+        assert_eq!(topics, ["findings", "alerts"]);
+    }
+
+    #[tokio::test]
+    async fn test_closing_ends_subscriptions() {
+        let bus = GraphEventBus::new();
+        let mut findings = bus.subscribe("findings");
+        bus.publish("findings", 1);
+        bus.close();
+
+        assert!(bus.is_closed());
+        assert_eq!(bus.publish("findings", 2), 0);
+        assert_eq!(
+            findings.recv().await.unwrap().payload,
+            StateValue::Integer(1)
+        );
+        assert_eq!(findings.recv().await, None);
+        assert_eq!(bus.subscribe("findings").recv().await, None);
+    }
+
+    // Publishes its items once someone listens
+    struct Producer {
+        id: NodeId,
+    }
+
+    #[async_trait]
+    impl Node for Producer {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            while context.events().subscribers("findings") == 0 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            for item in ["a", "b", "c"] {
+                context.events().publish("findings", item);
+            }
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            "Producer"
+        }
+    }
+
+    // Writes the first three findings to `received`
+    struct Consumer {
+        id: NodeId,
+    }
+
+    #[async_trait]
+    impl Node for Consumer {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let mut findings = context.events().subscribe("findings");
+            let mut received = Vec::new();
+            while received.len() < 3 {
+                received.push(findings.recv().await.unwrap().payload);
+            }
+            state.set("received", received);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            "Consumer"
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            vec!["received"]
+        }
+    }
+
+    // Does nothing
+    struct Pass {
+        id: NodeId,
+    }
+
+    #[async_trait]
+    impl Node for Pass {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    fn pass(id: &str) -> Arc<Pass> {
+        Arc::new(Pass { id: id.into() })
+    }
+
+    // Watches every event of the run in a task of its own
+    struct Monitor {
+        id: NodeId,
+        watcher: Mutex<Option<JoinHandle<Vec<String>>>>,
+    }
+
+    #[async_trait]
+    impl Node for Monitor {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let mut events = context.events().subscribe_all();
+            *self.watcher.lock() = Some(tokio::spawn(async move {
+                let mut seen = Vec::new();
+                while let Some(event) = events.recv().await {
+                    match &event.payload {
+                        StateValue::Object(payload) => seen.push(format!(
+                            "{} {}",
+                            event.topic,
+                            payload["node_id"].as_string().unwrap_or_default()
+                        )),
+                        _ => seen.push(event.topic),
+                    }
+                }
+                seen
+            }));
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            "Monitor"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_branches_talk_over_the_bus() {
+        let graph = GraphBuilder::new("bus")
+            .add_node("start", pass("start"))
+            .await
+            .unwrap()
+            .add_node(
+                "producer",
+                Arc::new(Producer {
+                    id: "producer".into(),
+                }),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "consumer",
+                Arc::new(Consumer {
+                    id: "consumer".into(),
+                }),
+            )
+            .await
+            .unwrap()
+            .add_node("done", pass("done"))
+            .await
+            .unwrap()
+            .add_parallel("start", ["producer", "consumer"], "done")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        assert_eq!(
+            results.final_state.get("received").unwrap(),
+            StateValue::from(vec![
+                StateValue::from("a"),
+                StateValue::from("b"),
+                StateValue::from("c"),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_monitor_sees_the_run_until_it_ends() {
+        let monitor = Arc::new(Monitor {
+            id: "monitor".into(),
+            watcher: Mutex::new(None),
+        });
+        let graph = GraphBuilder::new("bus")
+            .add_node("monitor", monitor.clone())
+            .await
+            .unwrap()
+            .add_node("draft", pass("draft"))
+            .await
+            .unwrap()
+            .add_node("review", pass("review"))
+            .await
+            .unwrap()
+            .add_edge("monitor", "draft")
+            .unwrap()
+            .add_edge("draft", "review")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        // The subscription ends with the run, so the watcher does too
+        let watcher = monitor.watcher.lock().take().unwrap();
+        let seen = tokio::time::timeout(Duration::from_secs(1), watcher)
+            .await
+            .expect("subscription outlived the run")
+            .unwrap();
+        assert_eq!(
+            seen,
+            [
+                "node.finished monitor",
+                "node.started draft",
+                "node.finished draft",
+                "node.started review",
+                "node.finished review",
+            ]
+        );
+    }
+}
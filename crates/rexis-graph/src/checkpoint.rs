@@ -0,0 +1,740 @@
+//! # Graph Checkpointing
+//!
+//! Checkpoints let a run survive process restarts. After each node completes,
+//! the engine saves the state, the nodes still to run and the loop counters
+//! to a [`Memory`] backend; a later process can resume the run from there.
+//!
+//! Checkpoints are stored as JSON under
+//! `graph::{graph_id}::run::{run_id}::checkpoint::{seq}`, with the latest
+//! sequence number under `graph::{graph_id}::run::{run_id}::latest` and the
+//! state the run started from under `graph::{graph_id}::run::{run_id}::input`,
+//! for [replays](crate::replay). Graph IDs
+//! are random by default, so a graph that is rebuilt to resume a run needs a
+//! fixed ID set with [`WorkflowGraph::with_id`](crate::WorkflowGraph::with_id).
+//!
+//! Runs waiting for human input leave an [`Interrupt`] under
+//! `graph::{graph_id}::interrupt::{interrupt_id}` until they are resumed.
+//!
+//! Visits of [side-effecting](crate::Node::is_side_effecting) nodes are
+//! recorded as a [`Visit`] under `graph::{graph_id}::visit::{idempotency_key}`,
+//! so that a run reaching them again replays their output rather than
+//! repeating their effects.
+
+use crate::core::{ExecutionResult, NodeId};
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use rexis_rag::storage::{Memory, MemoryQuery, MemoryValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Snapshot of a run taken after a node completed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub graph_id: String,
+    pub run_id: String,
+    /// Position of the checkpoint in the run, starting at 0
+    pub seq: u64,
+    pub state: HashMap<String, StateValue>,
+    /// Nodes still to run, in order
+    pub pending: Vec<NodeId>,
+    pub execution_path: Vec<NodeId>,
+    /// Traversal counts of limited loops as (from, to, count)
+    pub edge_traversals: Vec<(NodeId, NodeId, usize)>,
+    pub nodes_executed: usize,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A run paused until a human provides input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interrupt {
+    pub interrupt_id: String,
+    pub graph_id: String,
+    pub run_id: String,
+    /// The node waiting for the input
+    pub node_id: NodeId,
+    /// What the human is asked
+    pub prompt: String,
+    /// JSON Schema the input must match
+    pub input_schema: serde_json::Value,
+    /// State key the input is written to
+    pub input_key: String,
+    /// The state when the run paused
+    pub state: HashMap<String, StateValue>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// When the interrupt can no longer be resumed, if ever
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Interrupt {
+    /// Whether the interrupt has timed out
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= chrono::Utc::now())
+    }
+}
+
+/// Output of a completed visit of a side-effecting node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    /// See [`ExecutionContext::idempotency_key`](crate::ExecutionContext::idempotency_key)
+    pub idempotency_key: String,
+    pub node_id: NodeId,
+    /// Keys the node added or changed, with their new values
+    pub writes: HashMap<String, StateValue>,
+    /// Keys the node removed
+    pub removed: Vec<String>,
+    /// Writes to keys with reducers as the node made them, for parallel
+    /// branches to merge
+    pub updates: Vec<(String, StateValue)>,
+    pub result: ExecutionResult,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Visit {
+    /// Record what a node that returned `result` changed since `before`
+    pub fn new(
+        idempotency_key: impl Into<String>,
+        node_id: NodeId,
+        before: &HashMap<String, StateValue>,
+        state: &GraphState,
+        result: ExecutionResult,
+    ) -> Self {
+        let after = state.snapshot();
+        let mut removed: Vec<String> = before
+            .keys()
+            .filter(|key| !after.contains_key(*key))
+            .cloned()
+            .collect();
+        removed.sort();
+        let writes = after
+            .into_iter()
+            .filter(|(key, value)| before.get(key) != Some(value))
+            .collect();
+        Self {
+            idempotency_key: idempotency_key.into(),
+            node_id,
+            writes,
+            removed,
+            updates: state.recorded_updates(),
+            result,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Apply the recorded changes to `state`, bypassing its reducers
+    pub fn replay(&self, state: &GraphState) {
+        state.merge(&GraphState::with_data(self.writes.clone()));
+        for key in &self.removed {
+            state.remove(key);
+        }
+        state.extend_updates(self.updates.clone());
+    }
+}
+
+/// Saves and loads checkpoints in a [`Memory`] backend
+#[derive(Clone)]
+pub struct Checkpointer {
+    storage: Arc<dyn Memory>,
+}
+
+impl std::fmt::Debug for Checkpointer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Checkpointer")
+            .field("storage", &self.storage.backend_name())
+            .finish()
+    }
+}
+
+impl Checkpointer {
+    /// Create a checkpointer writing to `storage`
+    pub fn new(storage: Arc<dyn Memory>) -> Self {
+        Self { storage }
+    }
+
+    /// Key of checkpoint `seq` of a run
+    pub fn checkpoint_key(graph_id: &str, run_id: &str, seq: u64) -> String {
+        format!("graph::{}::run::{}::checkpoint::{}", graph_id, run_id, seq)
+    }
+
+    fn latest_key(graph_id: &str, run_id: &str) -> String {
+        format!("graph::{}::run::{}::latest", graph_id, run_id)
+    }
+
+    fn input_key(graph_id: &str, run_id: &str) -> String {
+        format!("graph::{}::run::{}::input", graph_id, run_id)
+    }
+
+    /// Key of an interrupt of a graph
+    pub fn interrupt_key(graph_id: &str, interrupt_id: &str) -> String {
+        format!("{}{}", Self::interrupt_prefix(graph_id), interrupt_id)
+    }
+
+    fn interrupt_prefix(graph_id: &str) -> String {
+        format!("graph::{}::interrupt::", graph_id)
+    }
+
+    /// Key of a recorded visit of a graph's node
+    pub fn visit_key(graph_id: &str, idempotency_key: &str) -> String {
+        format!("graph::{}::visit::{}", graph_id, idempotency_key)
+    }
+
+    /// Save a checkpoint and make it the run's latest
+    ///
+    /// Fails with [`RGraphError::Checkpoint`] naming the key when a state
+    /// value cannot be stored, such as a float that is not finite or bytes.
+    pub async fn save(&self, checkpoint: &Checkpoint) -> RGraphResult<()> {
+        let mut keys: Vec<&String> = checkpoint.state.keys().collect();
+        keys.sort();
+        for key in keys {
+            check_storable(key, &checkpoint.state[key])?;
+        }
+
+        let value = serde_json::to_value(checkpoint)?;
+        self.storage
+            .set(
+                &Self::checkpoint_key(&checkpoint.graph_id, &checkpoint.run_id, checkpoint.seq),
+                MemoryValue::Json(value),
+            )
+            .await?;
+        self.storage
+            .set(
+                &Self::latest_key(&checkpoint.graph_id, &checkpoint.run_id),
+                MemoryValue::Integer(checkpoint.seq as i64),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Load the latest checkpoint of a run, if it has one
+    pub async fn latest(&self, graph_id: &str, run_id: &str) -> RGraphResult<Option<Checkpoint>> {
+        let Some(seq) = self
+            .storage
+            .get(&Self::latest_key(graph_id, run_id))
+            .await?
+            .and_then(|value| value.as_integer())
+        else {
+            return Ok(None);
+        };
+
+        self.load(graph_id, run_id, seq as u64).await
+    }
+
+    /// Load every checkpoint of a run, oldest first
+    pub async fn history(&self, graph_id: &str, run_id: &str) -> RGraphResult<Vec<Checkpoint>> {
+        let Some(latest) = self.latest(graph_id, run_id).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut checkpoints = Vec::with_capacity(latest.seq as usize + 1);
+        for seq in 0..latest.seq {
+            let checkpoint = self.load(graph_id, run_id, seq).await?.ok_or_else(|| {
+                RGraphError::state(format!(
+                    "Checkpoint '{}' is missing",
+                    Self::checkpoint_key(graph_id, run_id, seq)
+                ))
+            })?;
+            checkpoints.push(checkpoint);
+        }
+        checkpoints.push(latest);
+
+        Ok(checkpoints)
+    }
+
+    /// Save the state a run started from
+    ///
+    /// Fails like [`Checkpointer::save`] when a value cannot be stored.
+    pub async fn save_input(
+        &self,
+        graph_id: &str,
+        run_id: &str,
+        state: &HashMap<String, StateValue>,
+    ) -> RGraphResult<()> {
+        let mut keys: Vec<&String> = state.keys().collect();
+        keys.sort();
+        for key in keys {
+            check_storable(key, &state[key])?;
+        }
+
+        let value = serde_json::to_value(state)?;
+        self.storage
+            .set(&Self::input_key(graph_id, run_id), MemoryValue::Json(value))
+            .await?;
+        Ok(())
+    }
+
+    /// Load the state a run started from, if it was saved
+    pub async fn load_input(
+        &self,
+        graph_id: &str,
+        run_id: &str,
+    ) -> RGraphResult<Option<HashMap<String, StateValue>>> {
+        let key = Self::input_key(graph_id, run_id);
+        match self.storage.get(&key).await? {
+            Some(MemoryValue::Json(value)) => Ok(Some(serde_json::from_value(value)?)),
+            Some(_) => Err(RGraphError::state(format!(
+                "Input '{}' is not stored as JSON",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Save an interrupt until it is resumed
+    pub async fn save_interrupt(&self, interrupt: &Interrupt) -> RGraphResult<()> {
+        let value = serde_json::to_value(interrupt)?;
+        self.storage
+            .set(
+                &Self::interrupt_key(&interrupt.graph_id, &interrupt.interrupt_id),
+                MemoryValue::Json(value),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Load an interrupt of a graph, if it is still pending
+    pub async fn load_interrupt(
+        &self,
+        graph_id: &str,
+        interrupt_id: &str,
+    ) -> RGraphResult<Option<Interrupt>> {
+        let key = Self::interrupt_key(graph_id, interrupt_id);
+        match self.storage.get(&key).await? {
+            Some(MemoryValue::Json(value)) => Ok(Some(serde_json::from_value(value)?)),
+            Some(_) => Err(RGraphError::state(format!(
+                "Interrupt '{}' is not stored as JSON",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove an interrupt once it is resumed
+    pub async fn delete_interrupt(&self, graph_id: &str, interrupt_id: &str) -> RGraphResult<()> {
+        self.storage
+            .delete(&Self::interrupt_key(graph_id, interrupt_id))
+            .await?;
+        Ok(())
+    }
+
+    /// Interrupts of a graph that are waiting for input and have not
+    /// expired, oldest first
+    pub async fn pending_interrupts(&self, graph_id: &str) -> RGraphResult<Vec<Interrupt>> {
+        let prefix = Self::interrupt_prefix(graph_id);
+        let keys = self
+            .storage
+            .keys(&MemoryQuery::new().with_pattern(prefix.as_str()))
+            .await?;
+
+        let mut interrupts = Vec::new();
+        for key in keys {
+            let Some(interrupt_id) = key.strip_prefix(&prefix) else {
+                continue;
+            };
+            if let Some(interrupt) = self.load_interrupt(graph_id, interrupt_id).await? {
+                if !interrupt.is_expired() {
+                    interrupts.push(interrupt);
+                }
+            }
+        }
+        interrupts
+            .sort_by(|a, b| (a.created_at, &a.interrupt_id).cmp(&(b.created_at, &b.interrupt_id)));
+
+        Ok(interrupts)
+    }
+
+    /// Record a completed visit of a side-effecting node
+    ///
+    /// Fails like [`Checkpointer::save`] when a written value cannot be
+    /// stored.
+    pub async fn save_visit(&self, graph_id: &str, visit: &Visit) -> RGraphResult<()> {
+        let mut keys: Vec<&String> = visit.writes.keys().collect();
+        keys.sort();
+        for key in keys {
+            check_storable(key, &visit.writes[key])?;
+        }
+
+        let value = serde_json::to_value(visit)?;
+        self.storage
+            .set(
+                &Self::visit_key(graph_id, &visit.idempotency_key),
+                MemoryValue::Json(value),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Load the recorded visit with an idempotency key, if there is one
+    pub async fn load_visit(
+        &self,
+        graph_id: &str,
+        idempotency_key: &str,
+    ) -> RGraphResult<Option<Visit>> {
+        let key = Self::visit_key(graph_id, idempotency_key);
+        match self.storage.get(&key).await? {
+            Some(MemoryValue::Json(value)) => Ok(Some(serde_json::from_value(value)?)),
+            Some(_) => Err(RGraphError::state(format!(
+                "Visit '{}' is not stored as JSON",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Load checkpoint `seq` of a run
+    pub async fn load(
+        &self,
+        graph_id: &str,
+        run_id: &str,
+        seq: u64,
+    ) -> RGraphResult<Option<Checkpoint>> {
+        let key = Self::checkpoint_key(graph_id, run_id, seq);
+        match self.storage.get(&key).await? {
+            Some(MemoryValue::Json(value)) => Ok(Some(serde_json::from_value(value)?)),
+            Some(_) => Err(RGraphError::state(format!(
+                "Checkpoint '{}' is not stored as JSON",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Check that a state value survives a JSON round trip, naming the path of
+/// the first value that does not
+fn check_storable(path: &str, value: &StateValue) -> RGraphResult<()> {
+    match value {
+        StateValue::Float(f) if !f.is_finite() => Err(RGraphError::Checkpoint {
+            key: path.to_string(),
+            message: format!("{} is not a finite number", f),
+        }),
+        StateValue::Bytes(_) => Err(RGraphError::Checkpoint {
+            key: path.to_string(),
+            message: "bytes have no JSON form".to_string(),
+        }),
+        StateValue::Array(items) => items
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, item)| check_storable(&format!("{}[{}]", path, i), item)),
+        StateValue::Object(fields) => fields
+            .iter()
+            .try_for_each(|(name, field)| check_storable(&format!("{}.{}", path, name), field)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node, WorkflowGraph};
+    use crate::execution::{ExecutionConfig, ExecutionEngine};
+    use crate::state::GraphState;
+    use async_trait::async_trait;
+    use rexis_rag::storage::InMemoryStorage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio_util::sync::CancellationToken;
+
+    // Node counting its runs and appending its ID to "steps"
+    struct StepNode {
+        id: NodeId,
+        runs: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Node for StepNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let steps = state
+                .get("steps")
+                .ok()
+                .and_then(|steps| steps.as_string().map(str::to_string))
+                .unwrap_or_default();
+            state.set("steps", format!("{}{}", steps, self.id.as_str()));
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    // The graph a -> b -> c -> d, rebuilt by every "process"
+    async fn pipeline(
+        storage: Arc<dyn Memory>,
+        runs: &HashMap<&str, Arc<AtomicUsize>>,
+    ) -> WorkflowGraph {
+        let mut builder = GraphBuilder::new("pipeline")
+            .id("pipeline")
+            .checkpointer(Checkpointer::new(storage));
+        for id in ["a", "b", "c", "d"] {
+            let node = StepNode {
+                id: NodeId::new(id),
+                runs: runs[id].clone(),
+            };
+            builder = builder.add_node(id, Arc::new(node)).await.unwrap();
+        }
+        builder
+            .add_edge("a", "b")
+            .unwrap()
+            .add_edge("b", "c")
+            .unwrap()
+            .add_edge("c", "d")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resume_runs_the_rest_exactly_once() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let runs: HashMap<&str, Arc<AtomicUsize>> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|id| (id, Arc::new(AtomicUsize::new(0))))
+            .collect();
+
+        // The first process stops after two nodes
+        let run_id = {
+            let graph = pipeline(storage.clone(), &runs).await;
+            let engine = ExecutionEngine::with_config(ExecutionConfig {
+                max_nodes: 2,
+                ..ExecutionConfig::default()
+            });
+            let results = engine
+                .execute(&graph, GraphState::new().with_input("steps", ">"))
+                .await
+                .unwrap();
+            assert_eq!(
+                results.final_state.get("steps").unwrap().as_string(),
+                Some(">ab")
+            );
+            results.run_id
+        };
+
+        let graph = pipeline(storage.clone(), &runs).await;
+        let results = graph.resume(&run_id, storage.clone()).await.unwrap();
+
+        assert!(results.errors.is_empty());
+        assert_eq!(results.run_id, run_id);
+        assert_eq!(
+            results.final_state.get("steps").unwrap().as_string(),
+            Some(">abcd")
+        );
+        for id in ["a", "b", "c", "d"] {
+            assert_eq!(runs[id].load(Ordering::SeqCst), 1, "runs of {}", id);
+        }
+        let resumed: Vec<_> = results
+            .trace
+            .iter()
+            .map(|step| step.node_id.as_str())
+            .collect();
+        assert_eq!(resumed, ["c", "d"]);
+
+        let latest = Checkpointer::new(storage)
+            .latest("pipeline", &run_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.seq, 3);
+        assert!(latest.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unstorable_values_name_their_key() {
+        let checkpointer = Checkpointer::new(Arc::new(InMemoryStorage::new()));
+        let mut scores = HashMap::new();
+        scores.insert("recall".to_string(), StateValue::Float(f64::NAN));
+        let checkpoint = Checkpoint {
+            graph_id: "pipeline".to_string(),
+            run_id: "run".to_string(),
+            seq: 0,
+            state: HashMap::from([(
+                "eval".to_string(),
+                StateValue::Array(vec![StateValue::Object(scores)]),
+            )]),
+            pending: Vec::new(),
+            execution_path: Vec::new(),
+            edge_traversals: Vec::new(),
+            nodes_executed: 0,
+            created_at: chrono::Utc::now(),
+        };
+
+        match checkpointer.save(&checkpoint).await.unwrap_err() {
+            RGraphError::Checkpoint { key, .. } => assert_eq!(key, "eval[0].recall"),
+            other => panic!("expected a checkpoint error, got {other:?}"),
+        }
+        assert!(checkpointer
+            .latest("pipeline", "run")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resume_without_checkpoint_fails() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let runs = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|id| (id, Arc::new(AtomicUsize::new(0))))
+            .collect();
+        let graph = pipeline(storage.clone(), &runs).await;
+
+        let err = graph.resume("missing", storage).await.unwrap_err();
+
+        assert!(err.to_string().contains("No checkpoint of run 'missing'"));
+    }
+
+    // Node "sending" a receipt, which can take down its process right after
+    struct SendNode {
+        id: NodeId,
+        sent: Arc<AtomicUsize>,
+        crash: bool,
+    }
+
+    #[async_trait]
+    impl Node for SendNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let sent = self.sent.fetch_add(1, Ordering::SeqCst) + 1;
+            state.set("receipt", format!("sent-{}", sent));
+            state.remove("outbox");
+            if self.crash {
+                // Stops the run before the checkpoint after this node
+                context.cancellation().cancel();
+            }
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+    }
+
+    // The graph draft -> send -> archive, rebuilt by every "process"
+    async fn mailer(
+        storage: Arc<dyn Memory>,
+        sent: Arc<AtomicUsize>,
+        crash: bool,
+        force_rerun: bool,
+    ) -> WorkflowGraph {
+        let mut builder = GraphBuilder::new("mailer")
+            .id("mailer")
+            .checkpointer(Checkpointer::new(storage));
+        for id in ["draft", "archive"] {
+            let node = StepNode {
+                id: NodeId::new(id),
+                runs: Arc::new(AtomicUsize::new(0)),
+            };
+            builder = builder.add_node(id, Arc::new(node)).await.unwrap();
+        }
+        builder = builder
+            .add_node(
+                "send",
+                Arc::new(SendNode {
+                    id: NodeId::new("send"),
+                    sent,
+                    crash,
+                }),
+            )
+            .await
+            .unwrap()
+            .add_edge("draft", "send")
+            .unwrap()
+            .add_edge("send", "archive")
+            .unwrap();
+        if force_rerun {
+            builder = builder.force_rerun("send").unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    // Run the mailer until it crashes after sending, returning the run ID
+    async fn crash_after_send(storage: Arc<dyn Memory>, sent: Arc<AtomicUsize>) -> String {
+        let graph = mailer(storage.clone(), sent, true, false).await;
+        let initial = GraphState::new()
+            .with_input("steps", ">")
+            .with_input("outbox", "receipt");
+        let err = ExecutionEngine::new()
+            .execute_with_cancellation(&graph, initial, CancellationToken::new())
+            .await
+            .unwrap_err();
+        let run_id = match err {
+            RGraphError::Cancelled { run_id, .. } => run_id,
+            other => panic!("expected the run to stop, got {other:?}"),
+        };
+        let latest = Checkpointer::new(storage)
+            .latest("mailer", &run_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.pending, vec![NodeId::new("send")]);
+        run_id
+    }
+
+    #[tokio::test]
+    async fn test_side_effecting_node_runs_once_across_resume() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let sent = Arc::new(AtomicUsize::new(0));
+        let run_id = crash_after_send(storage.clone(), sent.clone()).await;
+
+        let graph = mailer(storage.clone(), sent.clone(), false, false).await;
+        let results = graph.resume(&run_id, storage.clone()).await.unwrap();
+
+        assert!(results.errors.is_empty());
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        let state = &results.final_state;
+        assert_eq!(state.get("receipt").unwrap().as_string(), Some("sent-1"));
+        assert!(!state.contains_key("outbox"));
+        assert_eq!(
+            state.get("steps").unwrap().as_string(),
+            Some(">draftarchive")
+        );
+        let send = &results.trace[0];
+        assert_eq!(send.node_id.as_str(), "send");
+        assert_eq!(send.attempts, 0);
+
+        let key = format!("{}::send::1", run_id);
+        let visit = Checkpointer::new(storage)
+            .load_visit("mailer", &key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(visit.removed, vec!["outbox".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_force_rerun_repeats_side_effects() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let sent = Arc::new(AtomicUsize::new(0));
+        let run_id = crash_after_send(storage.clone(), sent.clone()).await;
+
+        let graph = mailer(storage.clone(), sent.clone(), false, true).await;
+        let results = graph.resume(&run_id, storage).await.unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            results.final_state.get("receipt").unwrap().as_string(),
+            Some("sent-2")
+        );
+        assert_eq!(results.trace[0].attempts, 1);
+    }
+}
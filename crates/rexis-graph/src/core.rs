@@ -3,17 +3,29 @@
 //! This module contains the fundamental types and traits that form the foundation
 //! of the RGraph system, including the workflow graph, nodes, edges, and execution context.
 
-use crate::state::GraphState;
+use crate::bus::GraphEventBus;
+use crate::execution::TraceStep;
+use crate::limits::{ExecutionLimits, RunSlots};
+use crate::reducer::Reducer;
+use crate::report::NodeUsage;
+use crate::routing::{EdgeRouter, IntoEdgeRouter};
+use crate::schema::StateSchema;
+use crate::state::{GraphState, StateValue};
+use crate::validation::{IssueKind, ValidationIssue};
 use crate::{RGraphError, RGraphResult};
 use async_trait::async_trait;
+use petgraph::graphmap::DiGraphMap;
+use petgraph::visit::EdgeRef;
 use petgraph::{Directed, Graph};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 type NodeIndex = petgraph::graph::NodeIndex;
 #[allow(dead_code)]
 type EdgeIndex = petgraph::graph::EdgeIndex;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -90,6 +102,11 @@ pub trait Node: Send + Sync {
         None
     }
 
+    /// Get the kind of node, shown in diagrams of the graph
+    fn node_type(&self) -> &str {
+        "Custom"
+    }
+
     /// Get the expected input keys from the state
     fn input_keys(&self) -> Vec<&str> {
         vec![]
@@ -100,11 +117,56 @@ pub trait Node: Send + Sync {
         vec![]
     }
 
+    /// Whether running the node has effects outside the state, such as
+    /// sending an email or writing to a database
+    ///
+    /// In a graph with a checkpointer, the engine records the output of each
+    /// visit of such a node. When the run reaches the visit again, as when it
+    /// resumes from a checkpoint saved before the visit, the output is
+    /// replayed into the state instead of running the node twice; see
+    /// [`WorkflowGraph::set_force_rerun`] to run it regardless.
+    fn is_side_effecting(&self) -> bool {
+        false
+    }
+
+    /// Whether the node calls an LLM, so that it counts against
+    /// [`ExecutionLimits::max_concurrent_llm_nodes`]
+    fn calls_llm(&self) -> bool {
+        false
+    }
+
+    /// Whether the node takes one of the run's slots under its
+    /// [`ExecutionLimits`] while it executes
+    ///
+    /// Nodes that only run other nodes, which take slots of their own,
+    /// return `false`.
+    fn occupies_slot(&self) -> bool {
+        true
+    }
+
     /// Validate that the node can execute with the current state
     fn validate(&self, _state: &GraphState) -> RGraphResult<()> {
         Ok(())
     }
 
+    /// Values the node would write given `state`, for a
+    /// [dry run](crate::dry_run) of its graph
+    ///
+    /// `None`, the default, has the dry run write placeholders to the
+    /// node's output keys.
+    fn simulate(&self, _state: &GraphState) -> Option<HashMap<String, StateValue>> {
+        None
+    }
+
+    /// Tokens and cost the node used in the run that left `state`, for its
+    /// [run report](crate::report)
+    ///
+    /// Only asked when the node succeeded and did not write
+    /// [`USAGE_KEY`](crate::execution::USAGE_KEY).
+    fn metrics(&self, _state: &GraphState) -> Option<NodeUsage> {
+        None
+    }
+
     /// Get node metadata for observability
     fn metadata(&self) -> NodeMetadata {
         NodeMetadata {
@@ -153,6 +215,181 @@ pub enum EdgeCondition {
     },
 }
 
+/// Traversal limit on an edge that closes a cycle
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LoopLimit {
+    /// How often the edge may be traversed in one execution
+    pub max_traversals: usize,
+    /// Node to go to instead once the limit is reached; without one the
+    /// execution fails with [`RGraphError::LoopLimitExceeded`]
+    pub exit: Option<NodeId>,
+}
+
+/// Decides whether a failed node attempt is retried, given its error
+pub type RetryPredicate = Arc<dyn Fn(&RGraphError) -> bool + Send + Sync>;
+
+/// Retry policy for a flaky node
+///
+/// Each retry runs the node again on the state as it was before the node's
+/// first attempt, so writes of a failed attempt are discarded.
+#[derive(Clone)]
+pub struct NodeRetryPolicy {
+    /// Attempts in total, the first one included
+    pub max_attempts: u32,
+    /// Wait between attempts
+    pub backoff: Backoff,
+    /// Which errors are retried; `None` retries all
+    pub retry_if: Option<RetryPredicate>,
+}
+
+impl std::fmt::Debug for NodeRetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeRetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("retry_if", &self.retry_if.as_ref().map(|_| "<predicate>"))
+            .finish()
+    }
+}
+
+impl NodeRetryPolicy {
+    /// Retry every error until `max_attempts` attempts were made, without
+    /// waiting in between
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            backoff: Backoff::default(),
+            retry_if: None,
+        }
+    }
+
+    /// Set the wait between attempts
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Only retry errors for which `retry_if` returns true
+    pub fn with_retry_if(
+        mut self,
+        retry_if: impl Fn(&RGraphError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_if = Some(Arc::new(retry_if));
+        self
+    }
+
+    /// Whether another attempt follows attempt `attempt`, which failed with
+    /// `error`
+    pub fn should_retry(&self, attempt: u32, error: &RGraphError) -> bool {
+        attempt < self.max_attempts
+            && match &self.retry_if {
+                Some(retry_if) => retry_if(error),
+                None => true,
+            }
+    }
+}
+
+/// Wait between attempts of a retried node
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Backoff {
+    /// Retry right away
+    #[default]
+    Immediate,
+    /// Wait the same time before each retry
+    Fixed(Duration),
+    /// Wait `initial`, doubling before each further retry up to `max`
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl Backoff {
+    /// Wait after attempt `attempt` failed, counting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match self {
+            Backoff::Immediate => Duration::ZERO,
+            Backoff::Fixed(delay) => *delay,
+            Backoff::Exponential { initial, max } => {
+                let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+                initial.saturating_mul(factor).min(*max)
+            }
+        }
+    }
+}
+
+/// Branches that run concurrently after a node and join at another node
+///
+/// Each branch runs one node on its own copy of the state. Once all have
+/// finished, the keys each branch changed are merged into the shared state
+/// and execution continues at the join node.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParallelBranches {
+    /// Nodes to run concurrently, in merge order
+    pub branches: Vec<NodeId>,
+    /// Node to run once the branches have finished
+    pub join: NodeId,
+    /// What to do when two branches write different values to a key
+    pub conflict_policy: MergeConflictPolicy,
+    /// What to do when some branches fail
+    pub failure_policy: PartialFailure,
+}
+
+impl ParallelBranches {
+    /// Create parallel branches joining at `join`, failing on conflicts and
+    /// on any branch failure
+    pub fn new(
+        branches: impl IntoIterator<Item = impl Into<NodeId>>,
+        join: impl Into<NodeId>,
+    ) -> Self {
+        Self {
+            branches: branches.into_iter().map(Into::into).collect(),
+            join: join.into(),
+            conflict_policy: MergeConflictPolicy::default(),
+            failure_policy: PartialFailure::default(),
+        }
+    }
+
+    /// Set how conflicting writes are merged
+    pub fn with_conflict_policy(mut self, policy: MergeConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Set how branch failures are handled
+    pub fn with_failure_policy(mut self, policy: PartialFailure) -> Self {
+        self.failure_policy = policy;
+        self
+    }
+}
+
+/// How writes of different values to one key by parallel branches are merged
+///
+/// Overlapping declared [`Node::output_keys`] are rejected when the graph is
+/// built; this covers keys written without being declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MergeConflictPolicy {
+    /// Fail the execution with [`RGraphError::MergeConflict`]
+    #[default]
+    Fail,
+    /// Keep the value of the earliest branch in declaration order
+    FirstWins,
+    /// Keep the value of the latest branch in declaration order
+    LastWins,
+}
+
+/// How failures of some parallel branches are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PartialFailure {
+    /// Any failed branch fails the join, which does not run
+    #[default]
+    FailJoin,
+    /// Record the failures and join the branches that succeeded
+    Collect,
+}
+
 /// Result of executing a node
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -165,6 +402,8 @@ pub enum ExecutionResult {
     JumpTo(NodeId),
     /// Conditional routing based on state
     Route(String), // Next node ID based on routing logic
+    /// Pause the run until input arrives for this interrupt
+    Interrupted { interrupt_id: String },
 }
 
 /// Context information available during node execution
@@ -176,10 +415,39 @@ pub struct ExecutionContext {
     pub execution_path: Vec<NodeId>,
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub metadata: HashMap<String, serde_json::Value>,
+    /// How often each edge, keyed by its (from, to) nodes, has been traversed
+    pub edge_traversals: HashMap<(NodeId, NodeId), usize>,
+    /// IDs of the graphs enclosing this one when it runs as a subgraph,
+    /// outermost first
+    pub parent_graphs: Vec<String>,
+    /// Trace of subgraphs run by the current node, collected by the engine
+    pub(crate) subgraph_trace: Arc<Mutex<Vec<TraceStep>>>,
+    /// Where a streamed run reports its events; subgraphs do not report
+    pub(crate) events: Option<crate::events::EventSink>,
+    /// The run's bus for events between nodes, shared with its subgraphs
+    pub(crate) bus: GraphEventBus,
+    /// Cancelled when the run is, shared with its subgraphs
+    pub(crate) cancellation: CancellationToken,
+    /// Where nodes of a top-level run persist interrupts
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub(crate) checkpointer: Option<Arc<crate::checkpoint::Checkpointer>>,
+    /// Where side-effecting nodes of a top-level run record their visits,
+    /// parallel branches included
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub(crate) visits: Option<Arc<crate::checkpoint::Checkpointer>>,
+    /// When the run must finish; subgraphs share the deadline of their parent
+    pub(crate) deadline: Option<crate::execution::RunDeadline>,
+    /// Slots nodes take while they execute, shared with subgraphs
+    pub(crate) slots: Option<Arc<RunSlots>>,
+    /// Priority of the current node when it waits for a slot
+    pub(crate) priority: i32,
 
     /// Optional persistent memory backend for agents
     #[cfg(feature = "rexis-rag-integration")]
     pub memory: Option<Arc<dyn rexis_rag::storage::Memory>>,
+    /// Memory backend of the run, for nodes without one of their own
+    #[cfg(feature = "rexis-rag-integration")]
+    pub(crate) run_memory: Option<Arc<dyn rexis_rag::storage::Memory>>,
 }
 
 impl std::fmt::Debug for ExecutionContext {
@@ -191,7 +459,9 @@ impl std::fmt::Debug for ExecutionContext {
             .field("current_node", &self.current_node)
             .field("execution_path", &self.execution_path)
             .field("start_time", &self.start_time)
-            .field("metadata", &self.metadata);
+            .field("metadata", &self.metadata)
+            .field("edge_traversals", &self.edge_traversals)
+            .field("parent_graphs", &self.parent_graphs);
 
         #[cfg(feature = "rexis-rag-integration")]
         debug_struct.field("memory", &self.memory.as_ref().map(|_| "<Memory>"));
@@ -209,8 +479,23 @@ impl ExecutionContext {
             execution_path: Vec::new(),
             start_time: chrono::Utc::now(),
             metadata: HashMap::new(),
+            edge_traversals: HashMap::new(),
+            parent_graphs: Vec::new(),
+            subgraph_trace: Arc::default(),
+            events: None,
+            bus: GraphEventBus::new(),
+            cancellation: CancellationToken::new(),
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            checkpointer: None,
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            visits: None,
+            deadline: None,
+            slots: None,
+            priority: 0,
             #[cfg(feature = "rexis-rag-integration")]
             memory: None,
+            #[cfg(feature = "rexis-rag-integration")]
+            run_memory: None,
         }
     }
 
@@ -219,6 +504,105 @@ impl ExecutionContext {
         self
     }
 
+    /// Create the context for running `graph_id` as a subgraph of this
+    /// context's graph, sharing its execution ID, metadata and memory
+    pub fn nested(&self, graph_id: String, current_node: NodeId) -> Self {
+        let mut parent_graphs = self.parent_graphs.clone();
+        parent_graphs.push(self.graph_id.clone());
+
+        Self {
+            graph_id,
+            execution_id: self.execution_id.clone(),
+            current_node,
+            execution_path: Vec::new(),
+            start_time: chrono::Utc::now(),
+            metadata: self.metadata.clone(),
+            edge_traversals: HashMap::new(),
+            parent_graphs,
+            subgraph_trace: Arc::default(),
+            events: None,
+            bus: self.bus.clone(),
+            cancellation: self.cancellation.clone(),
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            checkpointer: None,
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            visits: None,
+            deadline: self.deadline,
+            slots: self.slots.clone(),
+            priority: self.priority,
+            #[cfg(feature = "rexis-rag-integration")]
+            memory: self.memory.clone(),
+            #[cfg(feature = "rexis-rag-integration")]
+            run_memory: self.memory.clone(),
+        }
+    }
+
+    /// Nesting depth: 0 for a top-level graph, 1 for its subgraphs and so on
+    pub fn depth(&self) -> usize {
+        self.parent_graphs.len()
+    }
+
+    /// Attach the trace of a subgraph run to the current node's trace step
+    pub fn record_subgraph_trace(&self, steps: Vec<TraceStep>) {
+        self.subgraph_trace.lock().extend(steps);
+    }
+
+    /// Take the subgraph trace recorded by the current node
+    pub(crate) fn take_subgraph_trace(&self) -> Vec<TraceStep> {
+        std::mem::take(&mut *self.subgraph_trace.lock())
+    }
+
+    /// Get the run's event bus, to publish to and subscribe from other nodes
+    pub fn events(&self) -> &GraphEventBus {
+        &self.bus
+    }
+
+    /// Get the token cancelled when the run is cancelled
+    ///
+    /// The engine aborts a running node on cancellation; long-running nodes
+    /// can watch the token to stop cleanly or pass it on, for example to LLM
+    /// calls.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Check whether the run has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Key of the current visit of the current node, the same every time the
+    /// run reaches the visit: the run ID, the node ID and how often the run
+    /// has reached the node, joined by `::`
+    ///
+    /// Nodes can pass it on to services that deduplicate requests.
+    pub fn idempotency_key(&self) -> String {
+        let iteration = self
+            .execution_path
+            .iter()
+            .filter(|visited| **visited == self.current_node)
+            .count();
+        format!(
+            "{}::{}::{}",
+            self.execution_id,
+            self.current_node.as_str(),
+            iteration
+        )
+    }
+
+    /// Time left before the run's deadline, if it has one
+    pub fn remaining_time(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.remaining())
+    }
+
+    /// How often the edge between two nodes has been traversed so far
+    pub fn traversals(&self, from: &NodeId, to: &NodeId) -> usize {
+        self.edge_traversals
+            .get(&(from.clone(), to.clone()))
+            .copied()
+            .unwrap_or(0)
+    }
+
     /// Set persistent memory backend (requires 'rrag-integration' feature)
     #[cfg(feature = "rexis-rag-integration")]
     pub fn with_memory(mut self, memory: Arc<dyn rexis_rag::storage::Memory>) -> Self {
@@ -231,9 +615,29 @@ impl ExecutionContext {
     pub fn memory(&self) -> Option<Arc<dyn rexis_rag::storage::Memory>> {
         self.memory.clone()
     }
+
+    /// Give the node about to run its own memory backend, or else the run's
+    #[cfg(feature = "rexis-rag-integration")]
+    pub(crate) fn assign_memory(&mut self, graph: &WorkflowGraph) {
+        self.memory = graph
+            .node_memory(&self.current_node)
+            .or_else(|| self.run_memory.clone());
+    }
+
+    /// Get the memory backend, failing with
+    /// [`RGraphError::MemoryUnavailable`] when none is configured
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn require_memory(&self) -> RGraphResult<Arc<dyn rexis_rag::storage::Memory>> {
+        self.memory().ok_or_else(|| RGraphError::MemoryUnavailable {
+            node_id: self.current_node.as_str().to_string(),
+        })
+    }
 }
 
 /// The main workflow graph that orchestrates node execution
+///
+/// Clones share the graph's nodes and edges.
+#[derive(Clone)]
 pub struct WorkflowGraph {
     id: String,
     name: String,
@@ -242,6 +646,26 @@ pub struct WorkflowGraph {
     node_lookup: Arc<RwLock<HashMap<NodeId, NodeIndex>>>,
     entry_points: Arc<RwLock<Vec<NodeId>>>,
     exit_points: Arc<RwLock<Vec<NodeId>>>,
+    routers: Arc<RwLock<HashMap<NodeId, Arc<dyn EdgeRouter>>>>,
+    loop_limits: Arc<RwLock<HashMap<(NodeId, NodeId), LoopLimit>>>,
+    parallel: Arc<RwLock<HashMap<NodeId, ParallelBranches>>>,
+    retry_policies: Arc<RwLock<HashMap<NodeId, NodeRetryPolicy>>>,
+    node_timeouts: Arc<RwLock<HashMap<NodeId, Duration>>>,
+    force_rerun: Arc<RwLock<HashSet<NodeId>>>,
+    priorities: Arc<RwLock<HashMap<NodeId, i32>>>,
+    limits: ExecutionLimits,
+    error_handlers: Arc<RwLock<HashMap<NodeId, NodeId>>>,
+    default_error_handler: Arc<RwLock<Option<NodeId>>>,
+    state_schema: Option<Arc<StateSchema>>,
+    reducers: Arc<HashMap<String, Reducer>>,
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    checkpointer: Option<Arc<crate::checkpoint::Checkpointer>>,
+    #[cfg(feature = "rexis-rag-integration")]
+    memory: Option<Arc<dyn rexis_rag::storage::Memory>>,
+    #[cfg(feature = "rexis-rag-integration")]
+    node_memory: Arc<RwLock<HashMap<NodeId, Arc<dyn rexis_rag::storage::Memory>>>>,
+    /// Held for reading by runs in flight, so that changes wait for none
+    runs: Arc<tokio::sync::RwLock<()>>,
 }
 
 impl WorkflowGraph {
@@ -255,6 +679,25 @@ impl WorkflowGraph {
             node_lookup: Arc::new(RwLock::new(HashMap::new())),
             entry_points: Arc::new(RwLock::new(Vec::new())),
             exit_points: Arc::new(RwLock::new(Vec::new())),
+            routers: Arc::new(RwLock::new(HashMap::new())),
+            loop_limits: Arc::new(RwLock::new(HashMap::new())),
+            parallel: Arc::new(RwLock::new(HashMap::new())),
+            retry_policies: Arc::new(RwLock::new(HashMap::new())),
+            node_timeouts: Arc::new(RwLock::new(HashMap::new())),
+            force_rerun: Arc::new(RwLock::new(HashSet::new())),
+            priorities: Arc::new(RwLock::new(HashMap::new())),
+            limits: ExecutionLimits::default(),
+            error_handlers: Arc::new(RwLock::new(HashMap::new())),
+            default_error_handler: Arc::new(RwLock::new(None)),
+            state_schema: None,
+            reducers: Arc::default(),
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            checkpointer: None,
+            #[cfg(feature = "rexis-rag-integration")]
+            memory: None,
+            #[cfg(feature = "rexis-rag-integration")]
+            node_memory: Arc::new(RwLock::new(HashMap::new())),
+            runs: Arc::default(),
         }
     }
 
@@ -264,6 +707,193 @@ impl WorkflowGraph {
         self
     }
 
+    /// Set a fixed graph ID in place of the random one, so that checkpoints
+    /// of a run can be found by a rebuilt graph
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Check the state of runs against `schema`; building the graph checks
+    /// the keys its nodes read and write against it
+    pub fn with_state_schema(mut self, schema: StateSchema) -> Self {
+        self.state_schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Get the schema the state of runs is checked against
+    pub fn state_schema(&self) -> Option<Arc<StateSchema>> {
+        self.state_schema.clone()
+    }
+
+    /// Combine writes to `key` with its value using `reducer`, instead of
+    /// replacing it
+    ///
+    /// Parallel branches may all write to a key with a reducer; building the
+    /// graph does not reject it as an overlap.
+    pub fn with_reducer(mut self, key: impl Into<String>, reducer: Reducer) -> Self {
+        Arc::make_mut(&mut self.reducers).insert(key.into(), reducer);
+        self
+    }
+
+    /// Get the reducer writes to `key` are combined with
+    pub fn reducer(&self, key: &str) -> Option<&Reducer> {
+        self.reducers.get(key)
+    }
+
+    /// Get the reducers of all keys
+    pub(crate) fn reducers(&self) -> Arc<HashMap<String, Reducer>> {
+        self.reducers.clone()
+    }
+
+    /// Checkpoint runs of this graph after each completed node
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub fn with_checkpointer(mut self, checkpointer: crate::checkpoint::Checkpointer) -> Self {
+        self.checkpointer = Some(Arc::new(checkpointer));
+        self
+    }
+
+    /// Bound how many nodes of a run execute at once
+    ///
+    /// Subgraphs run under the limits of the top-level graph.
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Get the limits runs of this graph execute under
+    pub fn limits(&self) -> ExecutionLimits {
+        self.limits
+    }
+
+    /// Get the checkpointer runs of this graph save to
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub fn checkpointer(&self) -> Option<Arc<crate::checkpoint::Checkpointer>> {
+        self.checkpointer.clone()
+    }
+
+    /// Give every node of a run `memory` as its memory backend
+    ///
+    /// A subgraph with a backend of its own uses it in place of its parent's.
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn with_memory(mut self, memory: Arc<dyn rexis_rag::storage::Memory>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Get the memory backend given to the nodes of a run
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn memory(&self) -> Option<Arc<dyn rexis_rag::storage::Memory>> {
+        self.memory.clone()
+    }
+
+    /// Give `node_id` `memory` as its memory backend in place of the graph's
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn set_node_memory(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        memory: Arc<dyn rexis_rag::storage::Memory>,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+        self.node_memory.write().insert(node_id, memory);
+        Ok(())
+    }
+
+    /// Get the memory backend of a node set in place of the graph's
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn node_memory(&self, node_id: &NodeId) -> Option<Arc<dyn rexis_rag::storage::Memory>> {
+        self.node_memory.read().get(node_id).cloned()
+    }
+
+    /// Continue the run `run_id` from its latest checkpoint in `storage`
+    ///
+    /// Runs with the default [`ExecutionEngine`](crate::ExecutionEngine); use
+    /// [`ExecutionEngine::resume`](crate::ExecutionEngine::resume) for another
+    /// configuration.
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub async fn resume(
+        &self,
+        run_id: &str,
+        storage: Arc<dyn rexis_rag::storage::Memory>,
+    ) -> RGraphResult<crate::execution::ExecutionResults> {
+        let checkpointer = crate::checkpoint::Checkpointer::new(storage);
+        crate::execution::ExecutionEngine::new()
+            .resume(self, run_id, &checkpointer)
+            .await
+    }
+
+    /// Replay the run `run_id` from its checkpoints in `storage`, running only
+    /// the nodes `options` makes live
+    ///
+    /// Uses the default [`ExecutionEngine`](crate::ExecutionEngine); see
+    /// [`replay`](crate::replay) for what a replay does.
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub async fn replay(
+        &self,
+        run_id: &str,
+        storage: Arc<dyn rexis_rag::storage::Memory>,
+        options: crate::replay::ReplayOptions,
+    ) -> RGraphResult<crate::replay::ReplayReport> {
+        let checkpointer = crate::checkpoint::Checkpointer::new(storage);
+        crate::execution::ExecutionEngine::new()
+            .replay(self, run_id, &checkpointer, options)
+            .await
+    }
+
+    /// Continue a run paused for human input with `input`
+    ///
+    /// Uses the graph's checkpointer and the default
+    /// [`ExecutionEngine`](crate::ExecutionEngine); see
+    /// [`ExecutionEngine::resume_with_input`](crate::ExecutionEngine::resume_with_input).
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub async fn resume_with_input(
+        &self,
+        interrupt_id: &str,
+        input: serde_json::Value,
+    ) -> RGraphResult<crate::execution::ExecutionResults> {
+        let checkpointer = self.checkpointer().ok_or_else(|| {
+            RGraphError::config(format!(
+                "Graph '{}' has no checkpointer to resume interrupts from",
+                self.name
+            ))
+        })?;
+        crate::execution::ExecutionEngine::new()
+            .resume_with_input(self, interrupt_id, input, &checkpointer)
+            .await
+    }
+
+    /// List the runs of this graph waiting for human input in `storage`,
+    /// oldest first; expired interrupts are left out
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub async fn pending_interrupts(
+        &self,
+        storage: Arc<dyn rexis_rag::storage::Memory>,
+    ) -> RGraphResult<Vec<crate::checkpoint::Interrupt>> {
+        crate::checkpoint::Checkpointer::new(storage)
+            .pending_interrupts(&self.id)
+            .await
+    }
+
+    /// Start executing the graph with the default
+    /// [`ExecutionEngine`](crate::ExecutionEngine), streaming its events
+    pub fn execute_stream(&self, initial_state: GraphState) -> crate::events::GraphRun {
+        crate::execution::ExecutionEngine::new().execute_stream(self, initial_state)
+    }
+
+    /// Start executing the graph in the background with the default
+    /// [`ExecutionEngine`](crate::ExecutionEngine); the returned handle
+    /// cancels the run or waits for its results
+    pub fn spawn(&self, initial_state: GraphState) -> crate::execution::RunHandle {
+        crate::execution::ExecutionEngine::new().spawn(self, initial_state)
+    }
+
+    /// Walk the graph from `initial_state` without running its nodes; see
+    /// [`DryRun`](crate::dry_run::DryRun) for stubbing node outputs
+    pub async fn dry_run(&self, initial_state: GraphState) -> crate::dry_run::DryRunReport {
+        crate::dry_run::DryRun::new().run(self, initial_state).await
+    }
+
     /// Add a node to the graph
     pub async fn add_node(
         &mut self,
@@ -347,107 +977,1171 @@ impl WorkflowGraph {
         Ok(edge_id)
     }
 
-    /// Add a conditional edge with a custom routing function
-    pub fn add_conditional_edge<F>(
+    /// Add a conditional edge: after `from` continues, `router` picks the next node
+    ///
+    /// The router is either a function of the state returning the next node ID
+    /// or a declarative [`StateRouter`](crate::routing::StateRouter) built with
+    /// [`when`](crate::routing::when). A node has at most one conditional edge
+    /// and then no plain edges.
+    pub fn add_conditional_edge(
         &mut self,
         from: impl Into<NodeId>,
-        _condition_fn: F,
-    ) -> RGraphResult<EdgeId>
-    where
-        F: Fn(&GraphState) -> RGraphResult<String> + Send + Sync + 'static,
-    {
-        // In a real implementation, we'd store the condition function
-        // For now, we'll create a placeholder edge
-        let _from_id = from.into();
-        let edge_id = EdgeId::generate();
+        router: impl IntoEdgeRouter,
+    ) -> RGraphResult<()> {
+        let from_id = from.into();
+        self.check_node(&from_id)?;
 
-        // This is a simplified implementation - in reality, we'd need to handle
-        // the conditional routing during execution
-        Ok(edge_id)
-    }
+        let mut routers = self.routers.write();
+        if routers.contains_key(&from_id) {
+            return Err(RGraphError::validation(format!(
+                "Node '{}' already has a conditional edge",
+                from_id.as_str()
+            )));
+        }
+        routers.insert(from_id, router.into_edge_router());
 
-    /// Set entry points for the graph
-    pub fn set_entry_points(&mut self, entry_points: Vec<NodeId>) {
-        *self.entry_points.write() = entry_points;
+        Ok(())
     }
 
-    /// Set exit points for the graph
-    pub fn set_exit_points(&mut self, exit_points: Vec<NodeId>) {
-        *self.exit_points.write() = exit_points;
+    /// Run `branches` concurrently after `from` and join at `join`, with the
+    /// default merge and failure policies
+    pub fn add_parallel(
+        &mut self,
+        from: impl Into<NodeId>,
+        branches: impl IntoIterator<Item = impl Into<NodeId>>,
+        join: impl Into<NodeId>,
+    ) -> RGraphResult<()> {
+        self.add_parallel_branches(from, ParallelBranches::new(branches, join))
     }
 
-    /// Get the graph ID
-    pub fn id(&self) -> &str {
-        &self.id
-    }
+    /// Run parallel branches after `from`
+    ///
+    /// The branches replace `from`'s outgoing edges; branch nodes have no
+    /// outgoing edges of their own since execution continues at the join.
+    pub fn add_parallel_branches(
+        &mut self,
+        from: impl Into<NodeId>,
+        parallel: ParallelBranches,
+    ) -> RGraphResult<()> {
+        let from_id = from.into();
+        self.check_node(&from_id)?;
+        self.check_node(&parallel.join)?;
 
-    /// Get the graph name
-    pub fn name(&self) -> &str {
-        &self.name
-    }
+        if parallel.branches.is_empty() {
+            return Err(RGraphError::validation(format!(
+                "Parallel branches after '{}' are empty",
+                from_id.as_str()
+            )));
+        }
+        for (i, branch) in parallel.branches.iter().enumerate() {
+            self.check_node(branch)?;
+            if parallel.branches[..i].contains(branch) || *branch == parallel.join {
+                return Err(RGraphError::validation(format!(
+                    "Node '{}' is listed twice in the branches and join after '{}'",
+                    branch.as_str(),
+                    from_id.as_str()
+                )));
+            }
+        }
 
-    /// Get the graph description
-    pub fn description(&self) -> Option<&str> {
-        self.description.as_deref()
-    }
+        let mut blocks = self.parallel.write();
+        if blocks.contains_key(&from_id) {
+            return Err(RGraphError::validation(format!(
+                "Node '{}' already has parallel branches",
+                from_id.as_str()
+            )));
+        }
+        blocks.insert(from_id, parallel);
 
-    /// Get all node IDs in the graph
-    pub fn node_ids(&self) -> Vec<NodeId> {
-        self.node_lookup.read().keys().cloned().collect()
+        Ok(())
     }
 
-    /// Get entry points (returns owned values to avoid lifetime issues)
-    pub fn entry_points(&self) -> Vec<NodeId> {
-        self.entry_points.read().clone()
+    /// Get the parallel branches that run after a node
+    pub fn parallel_branches(&self, node_id: &NodeId) -> Option<ParallelBranches> {
+        self.parallel.read().get(node_id).cloned()
     }
 
-    /// Get entry points as owned values
-    pub fn entry_points_owned(&self) -> Vec<NodeId> {
-        self.entry_points.read().clone()
+    /// Add an edge that closes a cycle, traversed at most `max_traversals`
+    /// times per execution
+    pub fn add_edge_cyclic(
+        &mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+        max_traversals: usize,
+    ) -> RGraphResult<EdgeId> {
+        let from_id = from.into();
+        let to_id = to.into();
+        self.limit_loop(from_id.clone(), to_id.clone(), max_traversals)?;
+        self.add_edge(from_id, to_id)
     }
 
-    /// Get a node by ID
-    pub fn get_node(&self, node_id: &NodeId) -> Option<Arc<dyn Node>> {
-        let lookup = self.node_lookup.read();
-        let graph = self.graph.read();
+    /// Limit how often execution may go from `from` to `to`, whichever edge,
+    /// route or jump takes it there
+    ///
+    /// Cycles must contain at least one limited step; this is how a cycle
+    /// through a conditional edge is allowed.
+    pub fn limit_loop(
+        &mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+        max_traversals: usize,
+    ) -> RGraphResult<()> {
+        let from_id = from.into();
+        let to_id = to.into();
+        self.check_node(&from_id)?;
+        self.check_node(&to_id)?;
 
-        if let Some(&node_index) = lookup.get(node_id) {
-            if let Some(node_weight) = graph.node_weight(node_index) {
-                return Some(node_weight.clone());
-            }
-        }
+        if max_traversals == 0 {
+            return Err(RGraphError::validation(format!(
+                "Loop from '{}' to '{}' must allow at least one traversal",
+                from_id.as_str(),
+                to_id.as_str()
+            )));
+        }
+
+        self.loop_limits
+            .write()
+            .entry((from_id, to_id))
+            .and_modify(|limit| limit.max_traversals = max_traversals)
+            .or_insert(LoopLimit {
+                max_traversals,
+                exit: None,
+            });
+
+        Ok(())
+    }
+
+    /// Go to `exit` once the loop from `from` to `to` reaches its limit
+    pub fn set_loop_exit(
+        &mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+        exit: impl Into<NodeId>,
+    ) -> RGraphResult<()> {
+        let from_id = from.into();
+        let to_id = to.into();
+        let exit_id = exit.into();
+        self.check_node(&exit_id)?;
+
+        let mut limits = self.loop_limits.write();
+        let limit = limits
+            .get_mut(&(from_id.clone(), to_id.clone()))
+            .ok_or_else(|| {
+                RGraphError::validation(format!(
+                    "No loop limit from '{}' to '{}'",
+                    from_id.as_str(),
+                    to_id.as_str()
+                ))
+            })?;
+        limit.exit = Some(exit_id);
+
+        Ok(())
+    }
+
+    /// Add a node that is retried according to `policy` when it fails
+    pub async fn add_node_with_policy(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        node: Arc<dyn Node>,
+        policy: NodeRetryPolicy,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        Self::check_retry_policy(&node_id, &policy)?;
+        self.add_node(node_id.clone(), node).await?;
+        self.retry_policies.write().insert(node_id, policy);
+        Ok(())
+    }
+
+    /// Retry a node according to `policy` when it fails
+    pub fn set_retry_policy(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        policy: NodeRetryPolicy,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+        Self::check_retry_policy(&node_id, &policy)?;
+        self.retry_policies.write().insert(node_id, policy);
+        Ok(())
+    }
+
+    /// Get the retry policy of a node
+    pub fn retry_policy(&self, node_id: &NodeId) -> Option<NodeRetryPolicy> {
+        self.retry_policies.read().get(node_id).cloned()
+    }
+
+    fn check_retry_policy(node_id: &NodeId, policy: &NodeRetryPolicy) -> RGraphResult<()> {
+        if policy.max_attempts == 0 {
+            return Err(RGraphError::validation(format!(
+                "Retry policy of '{}' must allow at least one attempt",
+                node_id.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fail a node with [`RGraphError::NodeTimeout`] when one of its attempts
+    /// runs longer than `timeout`
+    ///
+    /// With a retry policy, every attempt gets the full timeout.
+    pub fn set_node_timeout(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        timeout: Duration,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+        if timeout.is_zero() {
+            return Err(RGraphError::validation(format!(
+                "Timeout of '{}' must be longer than zero",
+                node_id.as_str()
+            )));
+        }
+        self.node_timeouts.write().insert(node_id, timeout);
+        Ok(())
+    }
+
+    /// Get the timeout of a node
+    pub fn node_timeout(&self, node_id: &NodeId) -> Option<Duration> {
+        self.node_timeouts.read().get(node_id).copied()
+    }
+
+    /// Run a side-effecting node on every visit, even one whose output was
+    /// recorded by an earlier attempt at the run
+    ///
+    /// Meant for nodes whose effects are known to be lost, or safe to repeat.
+    pub fn set_force_rerun(&mut self, node_id: impl Into<NodeId>, force: bool) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+        if force {
+            self.force_rerun.write().insert(node_id);
+        } else {
+            self.force_rerun.write().remove(&node_id);
+        }
+        Ok(())
+    }
+
+    /// Check whether a node runs on every visit despite side effects
+    pub fn forces_rerun(&self, node_id: &NodeId) -> bool {
+        self.force_rerun.read().contains(node_id)
+    }
+
+    /// Give a node a priority for slots under the graph's
+    /// [`ExecutionLimits`]; waiting nodes with higher priorities go first
+    ///
+    /// Nodes without one take the priority of the node that started them,
+    /// such as the node before parallel branches or a subgraph node, or 0.
+    pub fn set_node_priority(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        priority: i32,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+        self.priorities.write().insert(node_id, priority);
+        Ok(())
+    }
+
+    /// Get the priority set for a node
+    pub fn node_priority(&self, node_id: &NodeId) -> Option<i32> {
+        self.priorities.read().get(node_id).copied()
+    }
+
+    /// Hand failures of `node_id` to `handler` instead of failing the run
+    ///
+    /// The failure is written to the state under
+    /// [`ERROR_KEY`](crate::execution::ERROR_KEY) and execution continues at
+    /// the handler, whose result and edges decide where it goes from there.
+    /// Failures of a node reached through an error edge are not handled again.
+    pub fn set_error_handler(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        handler: impl Into<NodeId>,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        let handler = handler.into();
+        self.check_node(&node_id)?;
+        self.check_node(&handler)?;
+        if node_id == handler {
+            return Err(RGraphError::validation(format!(
+                "Node '{}' cannot handle its own errors",
+                node_id.as_str()
+            )));
+        }
+        self.error_handlers.write().insert(node_id, handler);
+        Ok(())
+    }
+
+    /// Hand failures of nodes without their own handler to `handler`; see
+    /// [`WorkflowGraph::set_error_handler`]
+    pub fn set_default_error_handler(&mut self, handler: impl Into<NodeId>) -> RGraphResult<()> {
+        let handler = handler.into();
+        self.check_node(&handler)?;
+        *self.default_error_handler.write() = Some(handler);
+        Ok(())
+    }
+
+    /// Get the node handling failures of `node_id`, falling back to the
+    /// graph's default handler
+    pub fn error_handler(&self, node_id: &NodeId) -> Option<NodeId> {
+        self.error_handlers
+            .read()
+            .get(node_id)
+            .cloned()
+            .or_else(|| self.default_error_handler.read().clone())
+            .filter(|handler| handler != node_id)
+    }
+
+    /// Get the loop limit on the step from `from` to `to`
+    pub fn loop_limit(&self, from: &NodeId, to: &NodeId) -> Option<LoopLimit> {
+        self.loop_limits
+            .read()
+            .get(&(from.clone(), to.clone()))
+            .cloned()
+    }
+
+    fn check_node(&self, node_id: &NodeId) -> RGraphResult<()> {
+        if self.contains_node(node_id) {
+            Ok(())
+        } else {
+            Err(RGraphError::validation(format!(
+                "Node '{}' not found",
+                node_id.as_str()
+            )))
+        }
+    }
+
+    /// Set entry points for the graph
+    pub fn set_entry_points(&mut self, entry_points: Vec<NodeId>) {
+        *self.entry_points.write() = entry_points;
+    }
+
+    /// Set exit points for the graph
+    pub fn set_exit_points(&mut self, exit_points: Vec<NodeId>) {
+        *self.exit_points.write() = exit_points;
+    }
+
+    /// Get the graph ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the graph name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the graph description
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Get all node IDs in the graph
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        self.node_lookup.read().keys().cloned().collect()
+    }
+
+    /// Get entry points (returns owned values to avoid lifetime issues)
+    pub fn entry_points(&self) -> Vec<NodeId> {
+        self.entry_points.read().clone()
+    }
+
+    /// Get entry points as owned values
+    pub fn entry_points_owned(&self) -> Vec<NodeId> {
+        self.entry_points.read().clone()
+    }
+
+    /// Get the exit points set with [`set_exit_points`](Self::set_exit_points)
+    pub fn exit_points(&self) -> Vec<NodeId> {
+        self.exit_points.read().clone()
+    }
+
+    /// Get a node by ID
+    pub fn get_node(&self, node_id: &NodeId) -> Option<Arc<dyn Node>> {
+        let lookup = self.node_lookup.read();
+        let graph = self.graph.read();
+
+        if let Some(&node_index) = lookup.get(node_id) {
+            if let Some(node_weight) = graph.node_weight(node_index) {
+                return Some(node_weight.clone());
+            }
+        }
         None
     }
 
-    /// Validate the graph structure
+    /// Get the outgoing edges of a node, in the order they were added
+    pub fn edges_from(&self, node_id: &NodeId) -> Vec<Edge> {
+        let lookup = self.node_lookup.read();
+        let graph = self.graph.read();
+
+        let Some(&node_index) = lookup.get(node_id) else {
+            return Vec::new();
+        };
+
+        // petgraph walks outgoing edges newest first
+        let mut edges: Vec<Edge> = graph
+            .edges(node_index)
+            .map(|edge| edge.weight().clone())
+            .collect();
+        edges.reverse();
+        edges
+    }
+
+    /// Get the router of a node's conditional edge
+    pub fn router(&self, node_id: &NodeId) -> Option<Arc<dyn EdgeRouter>> {
+        self.routers.read().get(node_id).cloned()
+    }
+
+    /// Check whether a node exists
+    pub fn contains_node(&self, node_id: &NodeId) -> bool {
+        self.node_lookup.read().contains_key(node_id)
+    }
+
+    /// Copy the graph into a builder, to change it without affecting this
+    /// graph and its clones
+    ///
+    /// The copy keeps the graph's ID, so it finds the graph's checkpoints.
+    pub fn to_builder(&self) -> GraphBuilder {
+        GraphBuilder {
+            graph: self.detached(),
+        }
+    }
+
+    /// Remove a node with its edges, policies and loop limits
+    ///
+    /// Fails when other nodes still depend on it as a parallel branch or
+    /// join, loop exit, error handler or route target, or when the graph
+    /// would be left without entry points.
+    pub fn remove_node(&mut self, node_id: impl Into<NodeId>) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+
+        let staged = self.staged()?;
+        let dependents = staged.dependents(&node_id);
+        if !dependents.is_empty() {
+            return Err(RGraphError::validation(format!(
+                "Node '{}' is still used by {}",
+                node_id.as_str(),
+                dependents.join(", ")
+            )));
+        }
+        staged.detach_node(&node_id);
+        self.commit(staged)
+    }
+
+    /// Run `node` in place of the node `node_id`, keeping its edges and
+    /// policies
+    ///
+    /// Fails when the new node's keys break the state schema or the writes
+    /// of parallel branches.
+    pub fn replace_node(
+        &mut self,
+        node_id: impl Into<NodeId>,
+        node: Arc<dyn Node>,
+    ) -> RGraphResult<()> {
+        let node_id = node_id.into();
+        self.check_node(&node_id)?;
+        node.validate(&GraphState::new())?;
+
+        let staged = self.staged()?;
+        let index = staged.node_lookup.read()[&node_id];
+        staged.graph.write()[index] = node;
+        self.commit(staged)
+    }
+
+    /// Remove the plain edges from `from` to `to`
+    ///
+    /// Loop limits on the step stay, since routes and jumps may still take it.
+    pub fn remove_edge(
+        &mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+    ) -> RGraphResult<()> {
+        let from_id = from.into();
+        let to_id = to.into();
+
+        let staged = self.staged()?;
+        staged.detach_edge(&from_id, &to_id)?;
+        self.commit(staged)
+    }
+
+    /// Put `node` on the plain edge from `from` to `to`, so that execution
+    /// goes from `from` to the new node and on to `to`
+    ///
+    /// The node is added under its own ID; a loop limit on the step moves to
+    /// the step from the new node to `to`.
+    pub async fn insert_between(
+        &mut self,
+        from: impl Into<NodeId>,
+        node: Arc<dyn Node>,
+        to: impl Into<NodeId>,
+    ) -> RGraphResult<()> {
+        let from_id = from.into();
+        let to_id = to.into();
+        let node_id = node.id().clone();
+
+        let mut staged = self.staged()?;
+        staged.detach_edge(&from_id, &to_id)?;
+        staged.add_node(node_id.clone(), node).await?;
+        staged.add_edge(from_id.clone(), node_id.clone())?;
+        staged.add_edge(node_id.clone(), to_id.clone())?;
+
+        let mut limits = staged.loop_limits.write();
+        if let Some(limit) = limits.remove(&(from_id, to_id.clone())) {
+            limits.insert((node_id, to_id), limit);
+        }
+        drop(limits);
+        self.commit(staged)
+    }
+
+    /// Hold off changes to the graph for as long as the guard lives
+    pub(crate) async fn running(&self) -> tokio::sync::OwnedRwLockReadGuard<()> {
+        self.runs.clone().read_owned().await
+    }
+
+    /// Fail when a run of the graph or one of its clones is in flight
+    fn idle(&self) -> RGraphResult<tokio::sync::RwLockWriteGuard<'_, ()>> {
+        self.runs.try_write().map_err(|_| {
+            RGraphError::execution(format!(
+                "Graph '{}' cannot change while it is running",
+                self.name
+            ))
+        })
+    }
+
+    /// Copy the graph to apply a change to, before committing it
+    fn staged(&self) -> RGraphResult<WorkflowGraph> {
+        drop(self.idle()?);
+        Ok(self.detached())
+    }
+
+    /// Take over the contents of `staged`, unless it fails validation with
+    /// errors this graph does not have
+    ///
+    /// Clones of the graph see the change.
+    fn commit(&mut self, staged: WorkflowGraph) -> RGraphResult<()> {
+        let _idle = self.idle()?;
+        let known: HashSet<String> = self
+            .validate_only()
+            .into_iter()
+            .filter(ValidationIssue::is_error)
+            .map(|issue| issue.message)
+            .collect();
+        if let Some(issue) = staged
+            .validate_only()
+            .into_iter()
+            .find(|issue| issue.is_error() && !known.contains(&issue.message))
+        {
+            return Err(RGraphError::validation(issue.message));
+        }
+
+        adopt(&self.graph, &staged.graph);
+        adopt(&self.node_lookup, &staged.node_lookup);
+        adopt(&self.entry_points, &staged.entry_points);
+        adopt(&self.exit_points, &staged.exit_points);
+        adopt(&self.routers, &staged.routers);
+        adopt(&self.loop_limits, &staged.loop_limits);
+        adopt(&self.parallel, &staged.parallel);
+        adopt(&self.retry_policies, &staged.retry_policies);
+        adopt(&self.node_timeouts, &staged.node_timeouts);
+        adopt(&self.force_rerun, &staged.force_rerun);
+        adopt(&self.priorities, &staged.priorities);
+        adopt(&self.error_handlers, &staged.error_handlers);
+        adopt(&self.default_error_handler, &staged.default_error_handler);
+        #[cfg(feature = "rexis-rag-integration")]
+        adopt(&self.node_memory, &staged.node_memory);
+        Ok(())
+    }
+
+    /// Copy the graph without sharing its nodes and edges
+    fn detached(&self) -> WorkflowGraph {
+        WorkflowGraph {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            graph: copied(&self.graph),
+            node_lookup: copied(&self.node_lookup),
+            entry_points: copied(&self.entry_points),
+            exit_points: copied(&self.exit_points),
+            routers: copied(&self.routers),
+            loop_limits: copied(&self.loop_limits),
+            parallel: copied(&self.parallel),
+            retry_policies: copied(&self.retry_policies),
+            node_timeouts: copied(&self.node_timeouts),
+            force_rerun: copied(&self.force_rerun),
+            priorities: copied(&self.priorities),
+            limits: self.limits,
+            error_handlers: copied(&self.error_handlers),
+            default_error_handler: copied(&self.default_error_handler),
+            state_schema: self.state_schema.clone(),
+            reducers: self.reducers.clone(),
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            checkpointer: self.checkpointer.clone(),
+            #[cfg(feature = "rexis-rag-integration")]
+            memory: self.memory.clone(),
+            #[cfg(feature = "rexis-rag-integration")]
+            node_memory: copied(&self.node_memory),
+            runs: Arc::default(),
+        }
+    }
+
+    /// Describe what refers to `node_id` other than edges and its own
+    /// settings, sorted
+    fn dependents(&self, node_id: &NodeId) -> Vec<String> {
+        let mut dependents = Vec::new();
+        for (from, block) in self.parallel.read().iter() {
+            if from != node_id && (block.join == *node_id || block.branches.contains(node_id)) {
+                dependents.push(format!("the parallel branches after '{}'", from.as_str()));
+            }
+        }
+        for ((from, to), limit) in self.loop_limits.read().iter() {
+            if from != node_id && to != node_id && limit.exit.as_ref() == Some(node_id) {
+                dependents.push(format!(
+                    "the exit of the loop from '{}' to '{}'",
+                    from.as_str(),
+                    to.as_str()
+                ));
+            }
+        }
+        for (node, handler) in self.error_handlers.read().iter() {
+            if node != node_id && handler == node_id {
+                dependents.push(format!("the error handler of '{}'", node.as_str()));
+            }
+        }
+        if self.default_error_handler.read().as_ref() == Some(node_id) {
+            dependents.push("the default error handler".to_string());
+        }
+        dependents.sort();
+        dependents
+    }
+
+    /// Remove a node and everything set for it
+    fn detach_node(&self, node_id: &NodeId) {
+        {
+            let mut graph = self.graph.write();
+            let mut lookup = self.node_lookup.write();
+            let Some(index) = lookup.remove(node_id) else {
+                return;
+            };
+            // petgraph moves the last node into the index of the removed one
+            let last = NodeIndex::new(graph.node_count() - 1);
+            graph.remove_node(index);
+            if let Some(moved) = lookup.values_mut().find(|moved| **moved == last) {
+                *moved = index;
+            }
+        }
+
+        self.routers.write().remove(node_id);
+        self.parallel.write().remove(node_id);
+        self.loop_limits
+            .write()
+            .retain(|(from, to), _| from != node_id && to != node_id);
+        self.retry_policies.write().remove(node_id);
+        self.node_timeouts.write().remove(node_id);
+        self.force_rerun.write().remove(node_id);
+        self.priorities.write().remove(node_id);
+        self.error_handlers.write().remove(node_id);
+        #[cfg(feature = "rexis-rag-integration")]
+        self.node_memory.write().remove(node_id);
+        self.entry_points.write().retain(|id| id != node_id);
+        self.exit_points.write().retain(|id| id != node_id);
+    }
+
+    /// Remove the plain edges from `from` to `to`, failing when there are none
+    fn detach_edge(&self, from: &NodeId, to: &NodeId) -> RGraphResult<()> {
+        self.check_node(from)?;
+        self.check_node(to)?;
+        let lookup = self.node_lookup.read();
+        let mut graph = self.graph.write();
+
+        let mut removed = false;
+        while let Some(edge) = graph.find_edge(lookup[from], lookup[to]) {
+            graph.remove_edge(edge);
+            removed = true;
+        }
+        if !removed {
+            return Err(RGraphError::validation(format!(
+                "No edge from '{}' to '{}'",
+                from.as_str(),
+                to.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validate the graph structure, failing on the first error
+    ///
+    /// Warnings are logged; [`validate_only`](Self::validate_only) returns
+    /// the full report.
     pub fn validate(&self) -> RGraphResult<()> {
+        let issues = self.validate_only();
+        for issue in issues.iter().filter(|issue| !issue.is_error()) {
+            tracing::warn!("Graph '{}': {}", self.name, issue.message);
+        }
+
+        match issues.into_iter().find(ValidationIssue::is_error) {
+            Some(issue) => Err(RGraphError::validation(issue.message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the graph structure and report every issue found, errors first
+    ///
+    /// Besides the errors that fail [`validate`](Self::validate), this warns
+    /// about nodes no path from the entry points leads to, nodes without a
+    /// way out when the graph declares exit points but not them, and input
+    /// keys nothing before the node provides. The initial state provides the
+    /// keys the state schema requires; without a schema, only keys some node
+    /// writes, but none before the reader, are reported. Nodes returning
+    /// [`ExecutionResult::JumpTo`] or [`ExecutionResult::Route`] go where
+    /// no edge says, so warnings about them may not apply.
+    pub fn validate_only(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
         let lookup = self.node_lookup.read();
         let entry_points = self.entry_points.read();
 
         // Check that we have nodes
         if lookup.is_empty() {
-            return Err(RGraphError::validation("Graph has no nodes"));
+            issues.push(ValidationIssue::error(
+                IssueKind::NoNodes,
+                [],
+                "Graph has no nodes",
+            ));
+            return issues;
         }
 
         // Check that we have entry points
         if entry_points.is_empty() {
-            return Err(RGraphError::validation("Graph has no entry points"));
+            issues.push(ValidationIssue::error(
+                IssueKind::NoEntryPoints,
+                [],
+                "Graph has no entry points",
+            ));
         }
 
-        // Validate that all entry points exist
+        // Validate that all entry and exit points exist
         for entry_point in entry_points.iter() {
             if !lookup.contains_key(entry_point) {
-                return Err(RGraphError::validation(format!(
-                    "Entry point '{}' does not exist",
-                    entry_point.as_str()
-                )));
+                issues.push(ValidationIssue::error(
+                    IssueKind::UnknownNode,
+                    [entry_point.clone()],
+                    format!("Entry point '{}' does not exist", entry_point.as_str()),
+                ));
+            }
+        }
+        for exit_point in self.exit_points.read().iter() {
+            if !lookup.contains_key(exit_point) {
+                issues.push(ValidationIssue::error(
+                    IssueKind::UnknownNode,
+                    [exit_point.clone()],
+                    format!("Exit point '{}' does not exist", exit_point.as_str()),
+                ));
+            }
+        }
+
+        // Validate conditional edges against the registered nodes
+        let mut routers: Vec<_> = self
+            .routers
+            .read()
+            .iter()
+            .map(|(from, router)| (from.clone(), router.clone()))
+            .collect();
+        routers.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        for (from, router) in routers {
+            for target in router.targets() {
+                if !lookup.contains_key(&target) {
+                    issues.push(ValidationIssue::error(
+                        IssueKind::UnknownNode,
+                        [from.clone(), target.clone()],
+                        format!(
+                            "Conditional edge from '{}' routes to unknown node '{}'",
+                            from.as_str(),
+                            target.as_str()
+                        ),
+                    ));
+                }
+            }
+
+            if !self.edges_from(&from).is_empty() {
+                issues.push(ValidationIssue::error(
+                    IssueKind::ConflictingEdges,
+                    [from.clone()],
+                    format!(
+                        "Node '{}' has both a conditional edge and plain edges",
+                        from.as_str()
+                    ),
+                ));
+            }
+        }
+
+        self.check_state_keys(&mut issues);
+        self.check_parallel(&mut issues);
+        self.check_cycles(&lookup, &mut issues);
+
+        let steps = self.steps();
+        self.check_reachable(&entry_points, &steps, &mut issues);
+        self.check_exits(&mut issues);
+        self.check_inputs(&steps, &mut issues);
+
+        issues.sort_by_key(|issue| !issue.is_error());
+        issues
+    }
+
+    /// Check that the state schema, if any, declares every key nodes read or
+    /// write
+    fn check_state_keys(&self, issues: &mut Vec<ValidationIssue>) {
+        let Some(schema) = &self.state_schema else {
+            return;
+        };
+
+        for id in self.sorted_node_ids() {
+            let Some(node) = self.get_node(&id) else {
+                continue;
+            };
+            let reads = node.input_keys().into_iter().map(|key| ("reads", key));
+            let writes = node.output_keys().into_iter().map(|key| ("writes", key));
+            for (access, key) in reads.chain(writes) {
+                if !schema.declares(key) {
+                    issues.push(ValidationIssue::error(
+                        IssueKind::UndeclaredStateKey,
+                        [id.clone()],
+                        format!(
+                            "Node '{}' {} state key '{}', which the state schema does not declare",
+                            id.as_str(),
+                            access,
+                            key
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Check that parallel branches are the only way out of their nodes and
+    /// do not declare the same output keys
+    fn check_parallel(&self, issues: &mut Vec<ValidationIssue>) {
+        let parallel = self.parallel.read();
+        let has_successors = |node_id: &NodeId| {
+            !self.edges_from(node_id).is_empty()
+                || self.router(node_id).is_some()
+                || parallel.contains_key(node_id)
+        };
+        let mut blocks: Vec<_> = parallel.iter().collect();
+        blocks.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+        for (from, block) in blocks {
+            if !self.edges_from(from).is_empty() || self.router(from).is_some() {
+                issues.push(ValidationIssue::error(
+                    IssueKind::ConflictingEdges,
+                    [from.clone()],
+                    format!(
+                        "Node '{}' has both parallel branches and other edges",
+                        from.as_str()
+                    ),
+                ));
+            }
+
+            let mut writers: HashMap<String, &NodeId> = HashMap::new();
+            for branch in &block.branches {
+                if has_successors(branch) {
+                    issues.push(ValidationIssue::error(
+                        IssueKind::BranchWithEdges,
+                        [branch.clone()],
+                        format!(
+                            "Parallel branch '{}' has outgoing edges; execution continues at '{}'",
+                            branch.as_str(),
+                            block.join.as_str()
+                        ),
+                    ));
+                }
+
+                let Some(node) = self.get_node(branch) else {
+                    continue;
+                };
+                for key in node.output_keys() {
+                    if self.reducers.contains_key(key) {
+                        continue;
+                    }
+                    if let Some(other) = writers.insert(key.to_string(), branch) {
+                        issues.push(ValidationIssue::error(
+                            IssueKind::OverlappingBranchWrites,
+                            [other.clone(), branch.clone()],
+                            format!(
+                                "Parallel branches '{}' and '{}' both write '{}'",
+                                other.as_str(),
+                                branch.as_str(),
+                                key
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reject cycles of plain edges and declared routes without a loop limit
+    ///
+    /// Routing functions do not declare their targets, so cycles through them
+    /// are only bounded by [`ExecutionConfig::max_nodes`](crate::ExecutionConfig).
+    fn check_cycles(&self, lookup: &HashMap<NodeId, NodeIndex>, issues: &mut Vec<ValidationIssue>) {
+        let graph = self.graph.read();
+        let limits = self.loop_limits.read();
+        let is_limited =
+            |from: &NodeId, to: &NodeId| limits.contains_key(&(from.clone(), to.clone()));
+
+        let mut unbounded = DiGraphMap::<NodeIndex, ()>::new();
+        for node_index in graph.node_indices() {
+            unbounded.add_node(node_index);
+        }
+        for edge in graph.edge_references() {
+            if !is_limited(&edge.weight().from, &edge.weight().to) {
+                unbounded.add_edge(edge.source(), edge.target(), ());
+            }
+        }
+        for (from, block) in self.parallel.read().iter() {
+            let steps = block
+                .branches
+                .iter()
+                .flat_map(|branch| [(from, branch), (branch, &block.join)]);
+            for (step_from, step_to) in steps {
+                if let (false, Some(&from_index), Some(&to_index)) = (
+                    is_limited(step_from, step_to),
+                    lookup.get(step_from),
+                    lookup.get(step_to),
+                ) {
+                    unbounded.add_edge(from_index, to_index, ());
+                }
+            }
+        }
+        for (from, router) in self.routers.read().iter() {
+            for target in router.targets() {
+                if let (false, Some(&from_index), Some(&to_index)) = (
+                    is_limited(from, &target),
+                    lookup.get(from),
+                    lookup.get(&target),
+                ) {
+                    unbounded.add_edge(from_index, to_index, ());
+                }
+            }
+        }
+
+        for component in petgraph::algo::tarjan_scc(&unbounded) {
+            if component.len() > 1 || unbounded.contains_edge(component[0], component[0]) {
+                let mut cycle: Vec<&NodeId> = lookup
+                    .iter()
+                    .filter(|(_, index)| component.contains(*index))
+                    .map(|(node_id, _)| node_id)
+                    .collect();
+                cycle.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+                let names: Vec<&str> = cycle.iter().map(|node_id| node_id.as_str()).collect();
+                issues.push(ValidationIssue::error(
+                    IssueKind::UnboundedCycle,
+                    cycle.iter().map(|node_id| (*node_id).clone()),
+                    format!(
+                        "Cycle through '{}' has no loop limit; add one with limit_loop",
+                        names.join("', '")
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Every node execution may go to after each node, whether through an
+    /// edge, a route, parallel branches, an error handler or a loop exit
+    ///
+    /// A routing function may go to any node.
+    fn steps(&self) -> HashMap<NodeId, Vec<NodeId>> {
+        let ids = self.sorted_node_ids();
+        let parallel = self.parallel.read();
+        let default_handler = self.default_error_handler.read().clone();
+        let mut steps: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+
+        for id in &ids {
+            let next = steps.entry(id.clone()).or_default();
+            next.extend(self.edges_from(id).into_iter().map(|edge| edge.to));
+            if let Some(router) = self.router(id) {
+                match router.targets() {
+                    targets if targets.is_empty() => next.extend(ids.iter().cloned()),
+                    targets => next.extend(targets),
+                }
+            }
+            if let Some(block) = parallel.get(id) {
+                next.extend(block.branches.iter().cloned());
+            }
+            next.extend(self.error_handlers.read().get(id).cloned());
+            next.extend(default_handler.clone().filter(|handler| handler != id));
+        }
+        for block in parallel.values() {
+            for branch in &block.branches {
+                steps
+                    .entry(branch.clone())
+                    .or_default()
+                    .push(block.join.clone());
+            }
+        }
+        for ((from, _), limit) in self.loop_limits.read().iter() {
+            if let Some(exit) = &limit.exit {
+                steps.entry(from.clone()).or_default().push(exit.clone());
+            }
+        }
+
+        steps
+    }
+
+    /// Warn about nodes no path from the entry points leads to
+    fn check_reachable(
+        &self,
+        entry_points: &[NodeId],
+        steps: &HashMap<NodeId, Vec<NodeId>>,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        let mut reached: HashSet<&NodeId> = HashSet::new();
+        let mut queue: Vec<&NodeId> = entry_points.iter().collect();
+        while let Some(node_id) = queue.pop() {
+            if reached.insert(node_id) {
+                queue.extend(steps.get(node_id).into_iter().flatten());
+            }
+        }
+
+        for id in self.sorted_node_ids() {
+            if !reached.contains(&id) {
+                issues.push(ValidationIssue::warning(
+                    IssueKind::Unreachable,
+                    [id.clone()],
+                    format!(
+                        "Node '{}' cannot be reached from the entry points",
+                        id.as_str()
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Warn about nodes without a way out that are not exit points, when
+    /// the graph declares exit points
+    fn check_exits(&self, issues: &mut Vec<ValidationIssue>) {
+        let exit_points = self.exit_points.read();
+        if exit_points.is_empty() {
+            return;
+        }
+        let parallel = self.parallel.read();
+        let branches: HashSet<&NodeId> = parallel
+            .values()
+            .flat_map(|block| &block.branches)
+            .collect();
+
+        for id in self.sorted_node_ids() {
+            let has_way_out = !self.edges_from(&id).is_empty()
+                || self.router(&id).is_some()
+                || parallel.contains_key(&id)
+                || branches.contains(&id);
+            if !has_way_out && !exit_points.contains(&id) {
+                issues.push(ValidationIssue::warning(
+                    IssueKind::DeadEnd,
+                    [id.clone()],
+                    format!(
+                        "Node '{}' has no outgoing edge and is not an exit point",
+                        id.as_str()
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Warn about input keys neither the initial state nor a node that may
+    /// run before the reader provides
+    fn check_inputs(
+        &self,
+        steps: &HashMap<NodeId, Vec<NodeId>>,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        fn top_level(key: &str) -> &str {
+            key.split('.').next().unwrap_or(key)
+        }
+
+        let ids = self.sorted_node_ids();
+        let nodes: HashMap<&NodeId, Arc<dyn Node>> = ids
+            .iter()
+            .filter_map(|id| self.get_node(id).map(|node| (id, node)))
+            .collect();
+        let written: HashSet<&str> = nodes
+            .values()
+            .flat_map(|node| node.output_keys())
+            .map(top_level)
+            .collect();
+        let mut before: HashMap<&NodeId, Vec<&NodeId>> = HashMap::new();
+        for (from, next) in steps {
+            for to in next {
+                before.entry(to).or_default().push(from);
+            }
+        }
+
+        for id in &ids {
+            let Some(node) = nodes.get(id) else {
+                continue;
+            };
+
+            // Keys written by the nodes that may run before this one
+            let mut provided = HashSet::new();
+            let mut seen = HashSet::new();
+            let mut queue: Vec<&NodeId> = before.get(id).cloned().unwrap_or_default();
+            while let Some(upstream) = queue.pop() {
+                if !seen.insert(upstream) {
+                    continue;
+                }
+                if let Some(upstream_node) = nodes.get(upstream) {
+                    provided.extend(upstream_node.output_keys().into_iter().map(top_level));
+                }
+                queue.extend(before.get(upstream).into_iter().flatten());
+            }
+
+            for key in node.input_keys() {
+                let key_name = top_level(key);
+                if provided.contains(key_name) {
+                    continue;
+                }
+                let message = match &self.state_schema {
+                    Some(schema) if schema.key(key_name).is_some_and(|key| key.required) => {
+                        continue
+                    }
+                    Some(_) => format!(
+                        "Node '{}' reads state key '{}', which no node before it writes and the state schema does not require",
+                        id.as_str(),
+                        key
+                    ),
+                    None if written.contains(key_name) => format!(
+                        "Node '{}' reads state key '{}', which is only written by nodes that cannot run before it",
+                        id.as_str(),
+                        key
+                    ),
+                    None => continue,
+                };
+                issues.push(ValidationIssue::warning(
+                    IssueKind::MissingInput,
+                    [id.clone()],
+                    message,
+                ));
             }
         }
+    }
 
-        Ok(())
+    fn sorted_node_ids(&self) -> Vec<NodeId> {
+        let mut ids = self.node_ids();
+        ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        ids
     }
 }
 
+/// Copy the contents of `lock` into a lock of their own
+fn copied<T: Clone>(lock: &RwLock<T>) -> Arc<RwLock<T>> {
+    Arc::new(RwLock::new(lock.read().clone()))
+}
+
+/// Move the contents of `staged` into `lock`
+fn adopt<T: Default>(lock: &RwLock<T>, staged: &RwLock<T>) {
+    *lock.write() = std::mem::take(&mut *staged.write());
+}
+
 /// Builder for creating workflow graphs with a fluent API
 pub struct GraphBuilder {
     graph: WorkflowGraph,
@@ -467,6 +2161,59 @@ impl GraphBuilder {
         self
     }
 
+    /// Set a fixed graph ID; see [`WorkflowGraph::with_id`]
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.graph = self.graph.with_id(id);
+        self
+    }
+
+    /// Declare the state keys and their types; see
+    /// [`WorkflowGraph::with_state_schema`]
+    pub fn state_schema(mut self, schema: StateSchema) -> Self {
+        self.graph = self.graph.with_state_schema(schema);
+        self
+    }
+
+    /// Combine writes to `key` with its value; see
+    /// [`WorkflowGraph::with_reducer`]
+    pub fn reducer(mut self, key: impl Into<String>, reducer: Reducer) -> Self {
+        self.graph = self.graph.with_reducer(key, reducer);
+        self
+    }
+
+    /// Bound how many nodes of a run execute at once; see
+    /// [`WorkflowGraph::with_limits`]
+    pub fn limits(mut self, limits: ExecutionLimits) -> Self {
+        self.graph = self.graph.with_limits(limits);
+        self
+    }
+
+    /// Checkpoint runs after each completed node
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub fn checkpointer(mut self, checkpointer: crate::checkpoint::Checkpointer) -> Self {
+        self.graph = self.graph.with_checkpointer(checkpointer);
+        self
+    }
+
+    /// Give every node a memory backend; see [`WorkflowGraph::with_memory`]
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn with_memory(mut self, memory: Arc<dyn rexis_rag::storage::Memory>) -> Self {
+        self.graph = self.graph.with_memory(memory);
+        self
+    }
+
+    /// Give a node its own memory backend; see
+    /// [`WorkflowGraph::set_node_memory`]
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn node_memory(
+        mut self,
+        node_id: impl Into<NodeId>,
+        memory: Arc<dyn rexis_rag::storage::Memory>,
+    ) -> RGraphResult<Self> {
+        self.graph.set_node_memory(node_id, memory)?;
+        Ok(self)
+    }
+
     /// Add a node to the graph
     pub async fn add_node(
         mut self,
@@ -477,6 +2224,20 @@ impl GraphBuilder {
         Ok(self)
     }
 
+    /// Add a node retried according to `policy`; see
+    /// [`WorkflowGraph::add_node_with_policy`]
+    pub async fn add_node_with_policy(
+        mut self,
+        node_id: impl Into<NodeId>,
+        node: Arc<dyn Node>,
+        policy: NodeRetryPolicy,
+    ) -> RGraphResult<Self> {
+        self.graph
+            .add_node_with_policy(node_id, node, policy)
+            .await?;
+        Ok(self)
+    }
+
     /// Add an edge between two nodes
     pub fn add_edge(
         mut self,
@@ -487,13 +2248,180 @@ impl GraphBuilder {
         Ok(self)
     }
 
+    /// Add a conditional edge routing from a node
+    ///
+    /// See [`WorkflowGraph::add_conditional_edge`]; the router's targets are
+    /// checked when the graph is built.
+    pub fn add_conditional_edge(
+        mut self,
+        from: impl Into<NodeId>,
+        router: impl IntoEdgeRouter,
+    ) -> RGraphResult<Self> {
+        self.graph.add_conditional_edge(from, router)?;
+        Ok(self)
+    }
+
+    /// Run branches concurrently; see [`WorkflowGraph::add_parallel`]
+    pub fn add_parallel(
+        mut self,
+        from: impl Into<NodeId>,
+        branches: impl IntoIterator<Item = impl Into<NodeId>>,
+        join: impl Into<NodeId>,
+    ) -> RGraphResult<Self> {
+        self.graph.add_parallel(from, branches, join)?;
+        Ok(self)
+    }
+
+    /// Run branches concurrently with custom policies; see
+    /// [`WorkflowGraph::add_parallel_branches`]
+    pub fn add_parallel_branches(
+        mut self,
+        from: impl Into<NodeId>,
+        parallel: ParallelBranches,
+    ) -> RGraphResult<Self> {
+        self.graph.add_parallel_branches(from, parallel)?;
+        Ok(self)
+    }
+
+    /// Add an edge that closes a cycle; see [`WorkflowGraph::add_edge_cyclic`]
+    pub fn add_edge_cyclic(
+        mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+        max_traversals: usize,
+    ) -> RGraphResult<Self> {
+        self.graph.add_edge_cyclic(from, to, max_traversals)?;
+        Ok(self)
+    }
+
+    /// Limit a step of a cycle; see [`WorkflowGraph::limit_loop`]
+    pub fn limit_loop(
+        mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+        max_traversals: usize,
+    ) -> RGraphResult<Self> {
+        self.graph.limit_loop(from, to, max_traversals)?;
+        Ok(self)
+    }
+
+    /// Bound how long a node may run; see [`WorkflowGraph::set_node_timeout`]
+    pub fn node_timeout(
+        mut self,
+        node_id: impl Into<NodeId>,
+        timeout: Duration,
+    ) -> RGraphResult<Self> {
+        self.graph.set_node_timeout(node_id, timeout)?;
+        Ok(self)
+    }
+
+    /// Give a node a priority for slots; see
+    /// [`WorkflowGraph::set_node_priority`]
+    pub fn node_priority(
+        mut self,
+        node_id: impl Into<NodeId>,
+        priority: i32,
+    ) -> RGraphResult<Self> {
+        self.graph.set_node_priority(node_id, priority)?;
+        Ok(self)
+    }
+
+    /// Run a side-effecting node on every visit; see
+    /// [`WorkflowGraph::set_force_rerun`]
+    pub fn force_rerun(mut self, node_id: impl Into<NodeId>) -> RGraphResult<Self> {
+        self.graph.set_force_rerun(node_id, true)?;
+        Ok(self)
+    }
+
+    /// Hand failures of a node to a handler node; see
+    /// [`WorkflowGraph::set_error_handler`]
+    pub fn on_error(
+        mut self,
+        node_id: impl Into<NodeId>,
+        handler: impl Into<NodeId>,
+    ) -> RGraphResult<Self> {
+        self.graph.set_error_handler(node_id, handler)?;
+        Ok(self)
+    }
+
+    /// Hand failures of nodes without their own handler to a handler node;
+    /// see [`WorkflowGraph::set_default_error_handler`]
+    pub fn on_any_error(mut self, handler: impl Into<NodeId>) -> RGraphResult<Self> {
+        self.graph.set_default_error_handler(handler)?;
+        Ok(self)
+    }
+
+    /// Set where a limited loop exits; see [`WorkflowGraph::set_loop_exit`]
+    pub fn loop_exit(
+        mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+        exit: impl Into<NodeId>,
+    ) -> RGraphResult<Self> {
+        self.graph.set_loop_exit(from, to, exit)?;
+        Ok(self)
+    }
+
+    /// Remove a node; see [`WorkflowGraph::remove_node`]
+    pub fn remove_node(mut self, node_id: impl Into<NodeId>) -> RGraphResult<Self> {
+        self.graph.remove_node(node_id)?;
+        Ok(self)
+    }
+
+    /// Run another node in place of a node; see
+    /// [`WorkflowGraph::replace_node`]
+    pub fn replace_node(
+        mut self,
+        node_id: impl Into<NodeId>,
+        node: Arc<dyn Node>,
+    ) -> RGraphResult<Self> {
+        self.graph.replace_node(node_id, node)?;
+        Ok(self)
+    }
+
+    /// Remove the plain edges between two nodes; see
+    /// [`WorkflowGraph::remove_edge`]
+    pub fn remove_edge(
+        mut self,
+        from: impl Into<NodeId>,
+        to: impl Into<NodeId>,
+    ) -> RGraphResult<Self> {
+        self.graph.remove_edge(from, to)?;
+        Ok(self)
+    }
+
+    /// Put a node on an edge; see [`WorkflowGraph::insert_between`]
+    pub async fn insert_between(
+        mut self,
+        from: impl Into<NodeId>,
+        node: Arc<dyn Node>,
+        to: impl Into<NodeId>,
+    ) -> RGraphResult<Self> {
+        self.graph.insert_between(from, node, to).await?;
+        Ok(self)
+    }
+
     /// Set entry points
     pub fn entry_points(mut self, entry_points: Vec<NodeId>) -> Self {
         self.graph.set_entry_points(entry_points);
         self
     }
 
+    /// Set exit points, the nodes runs are meant to end at
+    pub fn exit_points(mut self, exit_points: Vec<NodeId>) -> Self {
+        self.graph.set_exit_points(exit_points);
+        self
+    }
+
+    /// Report every issue building the graph would find; see
+    /// [`WorkflowGraph::validate_only`]
+    pub fn validate_only(&self) -> Vec<ValidationIssue> {
+        self.graph.validate_only()
+    }
+
     /// Build the workflow graph
+    ///
+    /// Fails on the first validation error and logs warnings.
     pub fn build(self) -> RGraphResult<WorkflowGraph> {
         self.graph.validate()?;
         Ok(self.graph)
@@ -503,7 +2431,6 @@ impl GraphBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::state::StateValue;
 
     // Mock node for testing
     struct TestNode {
@@ -578,6 +2505,241 @@ mod tests {
         assert_eq!(graph.node_ids().len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_conditional_edge_to_unknown_node_fails_build() {
+        let err = GraphBuilder::new("test_graph")
+            .add_node("review", TestNode::new("review", "Review"))
+            .await
+            .unwrap()
+            .add_node("publish", TestNode::new("publish", "Publish"))
+            .await
+            .unwrap()
+            .add_conditional_edge(
+                "review",
+                crate::routing::when("approved")
+                    .equals(true)
+                    .goto("publish")
+                    .otherwise("revise"),
+            )
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+
+        match err {
+            RGraphError::Validation { message } => assert!(message.contains("'revise'")),
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cycles_need_a_loop_limit() {
+        let builder = || async {
+            GraphBuilder::new("test_graph")
+                .add_node("plan", TestNode::new("plan", "Plan"))
+                .await
+                .unwrap()
+                .add_node("act", TestNode::new("act", "Act"))
+                .await
+                .unwrap()
+                .add_edge("plan", "act")
+                .unwrap()
+        };
+
+        let err = builder()
+            .await
+            .add_edge("act", "plan")
+            .unwrap()
+            .build()
+            .err()
+            .unwrap();
+        match err {
+            RGraphError::Validation { message } => {
+                assert!(message.contains("Cycle through 'act', 'plan'"))
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+
+        let graph = builder()
+            .await
+            .add_edge_cyclic("act", "plan", 3)
+            .unwrap()
+            .build()
+            .unwrap();
+        let limit = graph
+            .loop_limit(&NodeId::new("act"), &NodeId::new("plan"))
+            .unwrap();
+        assert_eq!(limit.max_traversals, 3);
+        assert_eq!(limit.exit, None);
+    }
+
+    // Node that waits to be released once it has started
+    struct GateNode {
+        id: NodeId,
+        started: Arc<tokio::sync::Notify>,
+        release: Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait]
+    impl Node for GateNode {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            self.started.notify_one();
+            self.release.notified().await;
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    async fn chain(ids: &[&str]) -> GraphBuilder {
+        let mut builder = GraphBuilder::new("test_graph");
+        for id in ids {
+            builder = builder
+                .add_node(*id, TestNode::new(*id, *id))
+                .await
+                .unwrap();
+        }
+        for pair in ids.windows(2) {
+            builder = builder.add_edge(pair[0], pair[1]).unwrap();
+        }
+        builder
+    }
+
+    async fn path(graph: &WorkflowGraph) -> Vec<String> {
+        let results = crate::execution::ExecutionEngine::new()
+            .execute(graph, GraphState::new())
+            .await
+            .unwrap();
+        results
+            .trace
+            .iter()
+            .map(|step| step.node_id.as_str().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_graph_changes_between_runs() {
+        let mut graph = chain(&["plan", "act", "report"]).await.build().unwrap();
+        assert_eq!(path(&graph).await, ["plan", "act", "report"]);
+
+        graph
+            .insert_between("act", TestNode::new("check", "Check"), "report")
+            .await
+            .unwrap();
+        assert_eq!(path(&graph).await, ["plan", "act", "check", "report"]);
+
+        graph.remove_edge("plan", "act").unwrap();
+        graph.add_edge("plan", "check").unwrap();
+        graph.remove_node("act").unwrap();
+        assert!(!graph.contains_node(&NodeId::new("act")));
+        assert_eq!(path(&graph).await, ["plan", "check", "report"]);
+
+        graph
+            .replace_node("check", TestNode::new("check", "Verify"))
+            .unwrap();
+        let name = |id: &str| graph.get_node(&NodeId::new(id)).unwrap().name().to_string();
+        assert_eq!(name("check"), "Verify");
+        assert_eq!(name("report"), "report");
+    }
+
+    #[tokio::test]
+    async fn test_to_builder_leaves_the_graph_alone() {
+        let graph = chain(&["plan", "act", "report"]).await.build().unwrap();
+        let changed = graph
+            .to_builder()
+            .remove_node("report")
+            .unwrap()
+            .add_node("review", TestNode::new("review", "review"))
+            .await
+            .unwrap()
+            .add_edge("act", "review")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(changed.id(), graph.id());
+        assert_eq!(path(&changed).await, ["plan", "act", "review"]);
+        assert_eq!(path(&graph).await, ["plan", "act", "report"]);
+    }
+
+    #[tokio::test]
+    async fn test_changes_breaking_the_graph_are_rejected() {
+        let mut graph = chain(&["plan", "act", "report"])
+            .await
+            .add_node("fallback", TestNode::new("fallback", "fallback"))
+            .await
+            .unwrap()
+            .on_error("act", "fallback")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let err = graph.remove_node("fallback").unwrap_err();
+        assert!(err.to_string().contains("the error handler of 'act'"));
+        let err = graph.remove_node("plan").unwrap_err();
+        assert!(err.to_string().contains("no entry points"));
+        let err = graph.remove_edge("plan", "report").unwrap_err();
+        assert!(err.to_string().contains("No edge from 'plan' to 'report'"));
+        let err = graph
+            .insert_between("act", TestNode::new("plan", "plan"), "report")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'plan' already exists"));
+
+        assert_eq!(graph.node_ids().len(), 4);
+        assert_eq!(path(&graph).await, ["plan", "act", "report"]);
+    }
+
+    #[tokio::test]
+    async fn test_graph_cannot_change_while_running() {
+        let started = Arc::new(tokio::sync::Notify::new());
+        let release = Arc::new(tokio::sync::Notify::new());
+        let gate = Arc::new(GateNode {
+            id: NodeId::new("gate"),
+            started: started.clone(),
+            release: release.clone(),
+        });
+        let mut graph = GraphBuilder::new("test_graph")
+            .add_node("gate", gate)
+            .await
+            .unwrap()
+            .add_node("report", TestNode::new("report", "report"))
+            .await
+            .unwrap()
+            .add_edge("gate", "report")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let run = tokio::spawn({
+            let graph = graph.clone();
+            async move {
+                crate::execution::ExecutionEngine::new()
+                    .execute(&graph, GraphState::new())
+                    .await
+            }
+        });
+        started.notified().await;
+        let err = graph.remove_edge("gate", "report").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("cannot change while it is running"));
+
+        release.notify_one();
+        run.await.unwrap().unwrap();
+        graph.remove_edge("gate", "report").unwrap();
+    }
+
     #[test]
     fn test_node_id() {
         let id1 = NodeId::new("test");
@@ -598,4 +2760,109 @@ mod tests {
         assert_eq!(context.current_node, NodeId::new("node1"));
         assert!(context.metadata.contains_key("key"));
     }
+
+    #[cfg(feature = "rexis-rag-integration")]
+    mod memory {
+        use super::*;
+        use crate::execution::ExecutionEngine;
+        use rexis_rag::storage::{InMemoryStorage, Memory, MemoryValue};
+
+        /// Writes its own ID to the memory backend under `visited::<id>`
+        struct RememberNode {
+            id: NodeId,
+        }
+
+        #[async_trait]
+        impl Node for RememberNode {
+            async fn execute(
+                &self,
+                _state: &mut GraphState,
+                context: &ExecutionContext,
+            ) -> RGraphResult<ExecutionResult> {
+                let key = format!("visited::{}", self.id.as_str());
+                let value = MemoryValue::from(self.id.as_str());
+                context.require_memory()?.set(&key, value).await?;
+                Ok(ExecutionResult::Continue)
+            }
+
+            fn id(&self) -> &NodeId {
+                &self.id
+            }
+
+            fn name(&self) -> &str {
+                self.id.as_str()
+            }
+        }
+
+        fn remember(id: &str) -> Arc<RememberNode> {
+            Arc::new(RememberNode { id: id.into() })
+        }
+
+        #[tokio::test]
+        async fn test_nodes_see_the_graph_memory_and_their_own() {
+            let shared: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+            let private: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+            let graph = GraphBuilder::new("memory")
+                .with_memory(shared.clone())
+                .add_node("first", remember("first"))
+                .await
+                .unwrap()
+                .add_node("second", remember("second"))
+                .await
+                .unwrap()
+                .add_edge("first", "second")
+                .unwrap()
+                .node_memory("second", private.clone())
+                .unwrap()
+                .build()
+                .unwrap();
+
+            let results = ExecutionEngine::new()
+                .execute(&graph, GraphState::new())
+                .await
+                .unwrap();
+
+            assert!(results.metrics.success);
+            assert!(shared.exists("visited::first").await.unwrap());
+            assert!(!shared.exists("visited::second").await.unwrap());
+            assert!(private.exists("visited::second").await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn test_node_memory_for_unknown_node_is_rejected() {
+            let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+            let result = GraphBuilder::new("memory")
+                .add_node("first", remember("first"))
+                .await
+                .unwrap()
+                .node_memory("missing", storage);
+
+            assert!(result.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_require_memory_fails_without_a_backend() {
+            let context = ExecutionContext::new("graph1".to_string(), NodeId::new("node1"));
+            match context.require_memory() {
+                Err(RGraphError::MemoryUnavailable { node_id }) => assert_eq!(node_id, "node1"),
+                other => panic!("expected MemoryUnavailable, got {:?}", other.map(|_| ())),
+            }
+
+            let graph = GraphBuilder::new("memory")
+                .add_node("first", remember("first"))
+                .await
+                .unwrap()
+                .build()
+                .unwrap();
+            let results = ExecutionEngine::new()
+                .execute(&graph, GraphState::new())
+                .await
+                .unwrap();
+
+            assert!(!results.metrics.success);
+            assert!(results.errors[0]
+                .error_message
+                .contains("'first' needs a memory backend"));
+        }
+    }
 }
@@ -0,0 +1,1087 @@
+//! # Graph Definitions
+//!
+//! Graphs can be written in YAML or JSON and loaded with
+//! [`GraphBuilder::from_yaml`] or [`GraphBuilder::from_json`]. Each node
+//! names a type registered in a [`NodeRegistry`], whose factory builds the
+//! node from the node's `config`. Everything else maps onto the builder:
+//!
+//! ```yaml
+//! name: support
+//! id: support-v1                  # fixed graph ID, optional
+//! description: Answers tickets    # optional
+//! entry_points: [classify]        # default: the first node
+//! exit_points: [reply]            # optional
+//! on_error: apologize             # handler of nodes without their own
+//! state:                          # state schema, optional
+//!   mode: strict                  # or warn
+//!   keys:
+//!     ticket: { type: string, required: true }
+//!     category: { type: string }
+//! nodes:
+//!   - id: classify
+//!     type: agent
+//!     config: { prompt: "Classify the ticket" }
+//!     retry: { max_attempts: 3, delay_ms: 200, max_delay_ms: 2000 }
+//!     timeout_ms: 30000
+//!     on_error: apologize
+//!   # ...
+//! edges:
+//!   - { from: classify, to: billing, when: "category == 'billing'" }
+//!   - { from: classify, to: general }
+//!   - { from: check, to: draft, max_traversals: 3, loop_exit: reply }
+//! parallel:
+//!   - from: research
+//!     branches: [web, papers]
+//!     join: draft
+//!     on_conflict: last_wins      # fail (default), first_wins or last_wins
+//!     on_failure: collect         # fail_join (default) or collect
+//! ```
+//!
+//! State key types are `string`, `integer`, `float`, `boolean`, `array`,
+//! `object`, `bytes` and `any`. A retry with only `delay_ms` waits the same
+//! time before each retry; with `max_delay_ms` the delay doubles up to it.
+//!
+//! All plain edges of a node are followed. Once a node has an edge with a
+//! `when` condition, its edges form one route instead: the conditions, each
+//! a [`StateExpr`], are tried in order, and the node's edge without one is
+//! taken when none holds. `max_traversals` limits the edge as a loop.
+//!
+//! Errors name the offending field and, when the source is known, the line
+//! and column it is at.
+
+use crate::core::{
+    Backoff, GraphBuilder, MergeConflictPolicy, Node, NodeId, NodeRetryPolicy, ParallelBranches,
+    PartialFailure,
+};
+use crate::routing::{ExprRouter, StateExpr};
+use crate::schema::{SchemaMode, StateSchema, StateType};
+use crate::{RGraphError, RGraphResult};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Builds a node from its ID and the `config` of its definition, an object
+pub type NodeFactory =
+    Arc<dyn Fn(&NodeId, &serde_json::Value) -> RGraphResult<Arc<dyn Node>> + Send + Sync>;
+
+/// Node types a graph definition can use, such as `"agent"`,
+/// `"tool:web_search"` or `"transform:template"`, and how to build them
+#[derive(Clone, Default)]
+pub struct NodeRegistry {
+    factories: HashMap<String, NodeFactory>,
+}
+
+impl NodeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build nodes of `node_type` with `factory`, replacing any earlier
+    /// factory of that type
+    pub fn register<F>(&mut self, node_type: impl Into<String>, factory: F) -> &mut Self
+    where
+        F: Fn(&NodeId, &serde_json::Value) -> RGraphResult<Arc<dyn Node>> + Send + Sync + 'static,
+    {
+        self.factories.insert(node_type.into(), Arc::new(factory));
+        self
+    }
+
+    /// Register a factory; see [`register`](Self::register)
+    pub fn with_factory<F>(mut self, node_type: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&NodeId, &serde_json::Value) -> RGraphResult<Arc<dyn Node>> + Send + Sync + 'static,
+    {
+        self.register(node_type, factory);
+        self
+    }
+
+    pub fn contains(&self, node_type: &str) -> bool {
+        self.factories.contains_key(node_type)
+    }
+
+    /// Registered node types, sorted
+    pub fn node_types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        types
+    }
+
+    /// Build a node of `node_type`
+    pub fn create(
+        &self,
+        node_type: &str,
+        node_id: &NodeId,
+        config: &serde_json::Value,
+    ) -> RGraphResult<Arc<dyn Node>> {
+        let factory = self.factories.get(node_type).ok_or_else(|| {
+            RGraphError::config(format!(
+                "unknown node type '{}' (registered: {})",
+                node_type,
+                self.node_types().join(", ")
+            ))
+        })?;
+        factory(node_id, config)
+    }
+}
+
+impl fmt::Debug for NodeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeRegistry")
+            .field("node_types", &self.node_types())
+            .finish()
+    }
+}
+
+/// Why a graph definition could not be loaded, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefinitionError {
+    /// 1-based line, when known
+    pub line: Option<usize>,
+    /// 1-based column, when known
+    pub column: Option<usize>,
+    /// Path of the offending field, such as `edges[2].to`; empty when the
+    /// document itself is malformed
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "line {}, column {}: ", line, column)?;
+        }
+        if !self.field.is_empty() {
+            write!(f, "{}: ", self.field)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+/// A graph as written in YAML or JSON; see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GraphDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub entry_points: Vec<String>,
+    #[serde(default)]
+    pub exit_points: Vec<String>,
+    #[serde(default)]
+    pub on_error: Option<String>,
+    #[serde(default)]
+    pub state: Option<StateDefinition>,
+    pub nodes: Vec<NodeDefinition>,
+    #[serde(default)]
+    pub edges: Vec<EdgeDefinition>,
+    #[serde(default)]
+    pub parallel: Vec<ParallelDefinition>,
+}
+
+/// The state schema of a [`GraphDefinition`]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateDefinition {
+    /// `strict` or `warn`; strict by default
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub keys: BTreeMap<String, StateKeyDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateKeyDefinition {
+    #[serde(rename = "type")]
+    pub value_type: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeDefinition {
+    pub id: String,
+    /// Type registered in the [`NodeRegistry`]
+    #[serde(rename = "type")]
+    pub node_type: String,
+    /// Passed to the type's factory; an empty object when left out
+    #[serde(default)]
+    pub config: Option<serde_json::Value>,
+    #[serde(default)]
+    pub retry: Option<RetryDefinition>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub on_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryDefinition {
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    #[serde(default)]
+    pub max_delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EdgeDefinition {
+    pub from: String,
+    pub to: String,
+    /// Condition routing to `to`; see [`StateExpr`]
+    #[serde(default)]
+    pub when: Option<String>,
+    #[serde(default)]
+    pub max_traversals: Option<usize>,
+    /// Node to go to once `max_traversals` is reached
+    #[serde(default)]
+    pub loop_exit: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParallelDefinition {
+    pub from: String,
+    pub branches: Vec<String>,
+    pub join: String,
+    /// `fail`, `first_wins` or `last_wins`; `fail` by default
+    #[serde(default)]
+    pub on_conflict: Option<String>,
+    /// `fail_join` or `collect`; `fail_join` by default
+    #[serde(default)]
+    pub on_failure: Option<String>,
+}
+
+impl GraphDefinition {
+    /// Parse a definition from YAML
+    pub fn from_yaml(source: &str) -> Result<Self, DefinitionError> {
+        serde_yaml::from_str(source).map_err(|e| {
+            let location = e.location();
+            let (line, column) = (
+                location.as_ref().map(|l| l.line()),
+                location.as_ref().map(|l| l.column()),
+            );
+            parse_error(source, line, column, &e.to_string())
+        })
+    }
+
+    /// Parse a definition from JSON
+    pub fn from_json(source: &str) -> Result<Self, DefinitionError> {
+        serde_json::from_str(source).map_err(|e| {
+            let (line, column) = match e.line() {
+                0 => (None, None),
+                line => (Some(line), Some(e.column())),
+            };
+            parse_error(source, line, column, &e.to_string())
+        })
+    }
+
+    /// Build the graph the definition describes, with nodes from `registry`
+    ///
+    /// Errors have no line and column, since the source is not known; use
+    /// [`GraphBuilder::from_yaml`] or [`GraphBuilder::from_json`] to get them.
+    pub async fn into_builder(
+        self,
+        registry: &NodeRegistry,
+    ) -> Result<GraphBuilder, DefinitionError> {
+        Loader {
+            source: None,
+            registry,
+        }
+        .load(self)
+        .await
+    }
+}
+
+impl GraphBuilder {
+    /// Load a graph written in YAML; see the [definition
+    /// format](crate::definition)
+    pub async fn from_yaml(source: &str, registry: &NodeRegistry) -> RGraphResult<Self> {
+        let definition = GraphDefinition::from_yaml(source)?;
+        Ok(Loader {
+            source: Some(source),
+            registry,
+        }
+        .load(definition)
+        .await?)
+    }
+
+    /// Load a graph written in JSON; see the [definition
+    /// format](crate::definition)
+    pub async fn from_json(source: &str, registry: &NodeRegistry) -> RGraphResult<Self> {
+        let definition = GraphDefinition::from_json(source)?;
+        Ok(Loader {
+            source: Some(source),
+            registry,
+        }
+        .load(definition)
+        .await?)
+    }
+}
+
+/// Error of the YAML or JSON parser, with its location stripped from the
+/// message and the offending field worked out
+fn parse_error(
+    source: &str,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: &str,
+) -> DefinitionError {
+    let message = match (line, message.rfind(" at line ")) {
+        (Some(_), Some(at)) => &message[..at],
+        _ => message,
+    };
+    // YAML errors start with the path of the field, as in `nodes[1].retry: `
+    let (path, message) = match message.split_once(": ") {
+        Some((path, rest)) if !path.contains(' ') => (Some(path.to_string()), rest),
+        _ => (None, message),
+    };
+    let named = ["unknown field `", "missing field `"]
+        .iter()
+        .find_map(|prefix| message.strip_prefix(prefix))
+        .and_then(|rest| rest.split('`').next());
+
+    let field = match (path, named) {
+        (Some(path), Some(name)) => format!("{}.{}", path, name),
+        (Some(path), _) => path,
+        (None, Some(name)) => name.to_string(),
+        (None, None) => match (line, column) {
+            (Some(line), Some(column)) => key_at(source, line, column).unwrap_or_default(),
+            _ => String::new(),
+        },
+    };
+
+    DefinitionError {
+        line,
+        column,
+        field,
+        message: message.to_string(),
+    }
+}
+
+/// Key of the `key: value` pair at or before a position, if any
+fn key_at(source: &str, line: usize, column: usize) -> Option<String> {
+    let text = source.lines().nth(line.checked_sub(1)?)?;
+    let end = text
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(text.len(), |(i, _)| i);
+    let colon = text[..end].rfind(':')?;
+    let key = text[..colon]
+        .rsplit(|c: char| matches!(c, '{' | ',' | '-') || c.is_whitespace())
+        .find(|part| !part.is_empty())?
+        .trim_matches(|c| c == '"' || c == '\'');
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+/// Position of the first `key: value` pair in `source`, each pair after the
+/// one before; falls back to the last pair alone
+fn locate(source: &str, pairs: &[(&str, &str)]) -> Option<(usize, usize)> {
+    let mut from = 0;
+    let mut found = None;
+    for (key, value) in pairs {
+        match find_pair(source, from, key, value) {
+            Some(at) => {
+                from = at;
+                found = Some(at);
+            }
+            None => {
+                found = pairs
+                    .last()
+                    .and_then(|(key, value)| find_pair(source, 0, key, value));
+                break;
+            }
+        }
+    }
+    found.map(|at| {
+        let before = &source[..at];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, column)
+    })
+}
+
+/// Byte offset of `value` after the first `key:` from `from` on that has it,
+/// on the key's line or in the list below; else of the first `key:`
+fn find_pair(source: &str, from: usize, key: &str, value: &str) -> Option<usize> {
+    let word = |text: &str, at: usize, len: usize| {
+        let boundary = |c: Option<char>| !c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        boundary(text[..at].chars().next_back()) && boundary(text[at + len..].chars().next())
+    };
+    let find_word = |text: &str, needle: &str| {
+        text.match_indices(needle)
+            .map(|(at, _)| at)
+            .find(|at| !needle.is_empty() && word(text, *at, needle.len()))
+    };
+
+    let mut first_key = None;
+    let mut search = from;
+    while let Some(found) = source[search..].find(key) {
+        let at = search + found;
+        search = at + key.len();
+        let rest = source[search..].trim_start_matches(['"', '\'']);
+        if !word(source, at, key.len()) || !rest.trim_start_matches(' ').starts_with(':') {
+            continue;
+        }
+        first_key.get_or_insert(at);
+
+        // The value is on the key's line, or items follow on the lines below
+        let end = source[search..]
+            .find('\n')
+            .map_or(source.len(), |end| search + end);
+        if let Some(offset) = find_word(&source[search..end], value) {
+            return Some(search + offset);
+        }
+        let below = &source[end..];
+        let items = below
+            .lines()
+            .skip(1)
+            .take_while(|line| line.trim_start().starts_with('-'))
+            .map(|line| line.len() + 1)
+            .sum::<usize>();
+        if let Some(offset) = find_word(&below[..(items + 1).min(below.len())], value) {
+            return Some(end + offset);
+        }
+    }
+    first_key
+}
+
+/// Builds a graph from a definition, locating errors in `source`
+struct Loader<'a> {
+    source: Option<&'a str>,
+    registry: &'a NodeRegistry,
+}
+
+impl Loader<'_> {
+    fn error(
+        &self,
+        field: impl Into<String>,
+        pairs: &[(&str, &str)],
+        message: impl fmt::Display,
+    ) -> DefinitionError {
+        let location = self.source.and_then(|source| locate(source, pairs));
+        DefinitionError {
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+            field: field.into(),
+            message: message.to_string(),
+        }
+    }
+
+    async fn load(&self, definition: GraphDefinition) -> Result<GraphBuilder, DefinitionError> {
+        let mut builder = GraphBuilder::new(&definition.name);
+        if let Some(id) = &definition.id {
+            builder = builder.id(id);
+        }
+        if let Some(description) = &definition.description {
+            builder = builder.description(description);
+        }
+        if let Some(state) = &definition.state {
+            builder = builder.state_schema(self.schema(state)?);
+        }
+
+        for (i, node) in definition.nodes.iter().enumerate() {
+            builder = self.add_node(builder, i, node).await?;
+        }
+        let nodes: Vec<&str> = definition.nodes.iter().map(|n| n.id.as_str()).collect();
+
+        for (list, points) in [
+            ("entry_points", &definition.entry_points),
+            ("exit_points", &definition.exit_points),
+        ] {
+            for (i, id) in points.iter().enumerate() {
+                let field = format!("{}[{}]", list, i);
+                self.check_node(&nodes, id, field, &[(list, id)])?;
+            }
+        }
+        if !definition.entry_points.is_empty() {
+            builder = builder.entry_points(ids(&definition.entry_points));
+        }
+        if !definition.exit_points.is_empty() {
+            builder = builder.exit_points(ids(&definition.exit_points));
+        }
+
+        builder = self.add_edges(builder, &nodes, &definition.edges)?;
+        for (i, parallel) in definition.parallel.iter().enumerate() {
+            builder = self.add_parallel(builder, i, parallel)?;
+        }
+
+        for (i, node) in definition.nodes.iter().enumerate() {
+            if let Some(handler) = &node.on_error {
+                let pairs = [("id", node.id.as_str()), ("on_error", handler.as_str())];
+                builder = builder
+                    .on_error(node.id.as_str(), handler.as_str())
+                    .map_err(|e| self.error(format!("nodes[{}].on_error", i), &pairs, e))?;
+            }
+        }
+        if let Some(handler) = &definition.on_error {
+            builder = builder
+                .on_any_error(handler.as_str())
+                .map_err(|e| self.error("on_error", &[("on_error", handler)], e))?;
+        }
+
+        Ok(builder)
+    }
+
+    /// Fail unless `id` names a node of the definition
+    fn check_node(
+        &self,
+        nodes: &[&str],
+        id: &str,
+        field: String,
+        pairs: &[(&str, &str)],
+    ) -> Result<(), DefinitionError> {
+        if nodes.contains(&id) {
+            return Ok(());
+        }
+        Err(self.error(field, pairs, format!("no node '{}'", id)))
+    }
+
+    fn schema(&self, state: &StateDefinition) -> Result<StateSchema, DefinitionError> {
+        let mut schema = StateSchema::new();
+        match state.mode.as_deref() {
+            None | Some("strict") => {}
+            Some("warn") => schema = schema.with_mode(SchemaMode::Warn),
+            Some(other) => {
+                let message = format!("unknown mode '{}', expected strict or warn", other);
+                return Err(self.error("state.mode", &[("mode", other)], message));
+            }
+        }
+
+        for (key, declared) in &state.keys {
+            let value_type = match declared.value_type.as_str() {
+                "string" => StateType::String,
+                "integer" => StateType::Integer,
+                "float" => StateType::Float,
+                "boolean" => StateType::Boolean,
+                "array" => StateType::Array,
+                "object" => StateType::Object,
+                "bytes" => StateType::Bytes,
+                "any" => StateType::Any,
+                other => {
+                    let field = format!("state.keys.{}.type", key);
+                    let pairs = [(key.as_str(), "type"), ("type", other)];
+                    let message = format!(
+                        "unknown type '{}', expected string, integer, float, boolean, \
+                         array, object, bytes or any",
+                        other
+                    );
+                    return Err(self.error(field, &pairs, message));
+                }
+            };
+            schema = if declared.required {
+                schema.required(key, value_type)
+            } else {
+                schema.optional(key, value_type)
+            };
+        }
+
+        Ok(schema)
+    }
+
+    async fn add_node(
+        &self,
+        builder: GraphBuilder,
+        i: usize,
+        node: &NodeDefinition,
+    ) -> Result<GraphBuilder, DefinitionError> {
+        let field = |name: &str| format!("nodes[{}].{}", i, name);
+        let id_pair = ("id", node.id.as_str());
+        let node_id = NodeId::new(&node.id);
+
+        if !self.registry.contains(&node.node_type) {
+            let pairs = [id_pair, ("type", node.node_type.as_str())];
+            let message = format!(
+                "unknown node type '{}' (registered: {})",
+                node.node_type,
+                self.registry.node_types().join(", ")
+            );
+            return Err(self.error(field("type"), &pairs, message));
+        }
+        let config = node
+            .config
+            .clone()
+            .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+        let created = self
+            .registry
+            .create(&node.node_type, &node_id, &config)
+            .map_err(|e| self.error(field("config"), &[id_pair, ("config", "")], e))?;
+
+        let mut builder = match &node.retry {
+            Some(retry) => {
+                let policy = self.retry_policy(retry, &field("retry"), &[id_pair])?;
+                builder
+                    .add_node_with_policy(node_id.clone(), created, policy)
+                    .await
+            }
+            None => builder.add_node(node_id.clone(), created).await,
+        }
+        .map_err(|e| self.error(field("id"), &[id_pair], e))?;
+
+        if let Some(timeout) = node.timeout_ms {
+            let pairs = [id_pair, ("timeout_ms", "")];
+            builder = builder
+                .node_timeout(node_id, Duration::from_millis(timeout))
+                .map_err(|e| self.error(field("timeout_ms"), &pairs, e))?;
+        }
+
+        Ok(builder)
+    }
+
+    fn retry_policy(
+        &self,
+        retry: &RetryDefinition,
+        field: &str,
+        pairs: &[(&str, &str)],
+    ) -> Result<NodeRetryPolicy, DefinitionError> {
+        if retry.max_attempts == 0 {
+            let field = format!("{}.max_attempts", field);
+            return Err(self.error(field, pairs, "max_attempts must be at least 1"));
+        }
+        let backoff = match (retry.delay_ms, retry.max_delay_ms) {
+            (None, None) => Backoff::Immediate,
+            (Some(delay), None) => Backoff::Fixed(Duration::from_millis(delay)),
+            (Some(initial), Some(max)) => Backoff::Exponential {
+                initial: Duration::from_millis(initial),
+                max: Duration::from_millis(max),
+            },
+            (None, Some(_)) => {
+                let field = format!("{}.max_delay_ms", field);
+                return Err(self.error(field, pairs, "max_delay_ms needs delay_ms"));
+            }
+        };
+        Ok(NodeRetryPolicy::new(retry.max_attempts).with_backoff(backoff))
+    }
+
+    fn add_edges(
+        &self,
+        mut builder: GraphBuilder,
+        nodes: &[&str],
+        edges: &[EdgeDefinition],
+    ) -> Result<GraphBuilder, DefinitionError> {
+        // Edges by source, in the order sources first appear
+        let mut sources: Vec<(&str, Vec<(usize, &EdgeDefinition)>)> = Vec::new();
+        for (i, edge) in edges.iter().enumerate() {
+            match sources.iter_mut().find(|(from, _)| *from == edge.from) {
+                Some((_, group)) => group.push((i, edge)),
+                None => sources.push((edge.from.as_str(), vec![(i, edge)])),
+            }
+        }
+
+        for (from, group) in sources {
+            let routed = group.iter().any(|(_, edge)| edge.when.is_some());
+            let mut router = ExprRouter::new();
+            let mut otherwise: Option<usize> = None;
+
+            for (i, edge) in &group {
+                let pairs = [("from", from), ("to", edge.to.as_str())];
+                let field = |name: &str| format!("edges[{}].{}", i, name);
+                let fail = |name: &str, e: RGraphError| self.error(field(name), &pairs, e);
+                self.check_node(nodes, from, field("from"), &pairs)?;
+                self.check_node(nodes, &edge.to, field("to"), &pairs)?;
+
+                match (&edge.when, routed) {
+                    (Some(condition), _) => {
+                        let condition = StateExpr::parse(condition).map_err(|e| {
+                            self.error(field("when"), &[("from", from), ("when", "")], e)
+                        })?;
+                        router = router.branch(condition, edge.to.as_str());
+                    }
+                    (None, true) => {
+                        if let Some(first) = otherwise {
+                            let message = format!(
+                                "'{}' routes by condition, so only one of its edges may \
+                                 go without `when` (edges[{}] does too)",
+                                from, first
+                            );
+                            return Err(self.error(field("when"), &pairs, message));
+                        }
+                        otherwise = Some(*i);
+                        router = router.otherwise(edge.to.as_str());
+                    }
+                    (None, false) => {
+                        builder = builder
+                            .add_edge(from, edge.to.as_str())
+                            .map_err(|e| fail("to", e))?;
+                    }
+                }
+
+                if let Some(max) = edge.max_traversals {
+                    builder = builder
+                        .limit_loop(from, edge.to.as_str(), max)
+                        .map_err(|e| fail("max_traversals", e))?;
+                }
+                if let Some(exit) = &edge.loop_exit {
+                    if edge.max_traversals.is_none() {
+                        let message = "loop_exit needs max_traversals";
+                        return Err(self.error(field("loop_exit"), &pairs, message));
+                    }
+                    let exit_pairs = [("from", from), ("loop_exit", exit.as_str())];
+                    self.check_node(nodes, exit, field("loop_exit"), &exit_pairs)?;
+                    builder = builder
+                        .loop_exit(from, edge.to.as_str(), exit.as_str())
+                        .map_err(|e| fail("loop_exit", e))?;
+                }
+            }
+
+            if routed {
+                let (i, edge) = group[0];
+                let pairs = [("from", from), ("to", edge.to.as_str())];
+                builder = builder
+                    .add_conditional_edge(from, router)
+                    .map_err(|e| self.error(format!("edges[{}].from", i), &pairs, e))?;
+            }
+        }
+
+        Ok(builder)
+    }
+
+    fn add_parallel(
+        &self,
+        builder: GraphBuilder,
+        i: usize,
+        parallel: &ParallelDefinition,
+    ) -> Result<GraphBuilder, DefinitionError> {
+        let field = |name: &str| format!("parallel[{}].{}", i, name);
+        let from = ("from", parallel.from.as_str());
+
+        let conflict_policy = match parallel.on_conflict.as_deref() {
+            None | Some("fail") => MergeConflictPolicy::Fail,
+            Some("first_wins") => MergeConflictPolicy::FirstWins,
+            Some("last_wins") => MergeConflictPolicy::LastWins,
+            Some(other) => {
+                let message = format!(
+                    "unknown policy '{}', expected fail, first_wins or last_wins",
+                    other
+                );
+                return Err(self.error(
+                    field("on_conflict"),
+                    &[from, ("on_conflict", other)],
+                    message,
+                ));
+            }
+        };
+        let failure_policy = match parallel.on_failure.as_deref() {
+            None | Some("fail_join") => PartialFailure::FailJoin,
+            Some("collect") => PartialFailure::Collect,
+            Some(other) => {
+                let message = format!("unknown policy '{}', expected fail_join or collect", other);
+                return Err(self.error(
+                    field("on_failure"),
+                    &[from, ("on_failure", other)],
+                    message,
+                ));
+            }
+        };
+
+        let branches = ParallelBranches::new(ids(&parallel.branches), parallel.join.as_str())
+            .with_conflict_policy(conflict_policy)
+            .with_failure_policy(failure_policy);
+        builder
+            .add_parallel_branches(parallel.from.as_str(), branches)
+            .map_err(|e| {
+                // Point at the branch, join or source the error names
+                let message = e.to_string();
+                let names = |id: &str| message.contains(&format!("'{}'", id));
+                let join = ("join", parallel.join.as_str());
+                match parallel.branches.iter().position(|branch| names(branch)) {
+                    Some(j) => self.error(
+                        format!("parallel[{}].branches[{}]", i, j),
+                        &[from, ("branches", parallel.branches[j].as_str())],
+                        e,
+                    ),
+                    None if names(&parallel.join) => self.error(field("join"), &[from, join], e),
+                    None => self.error(field("from"), &[from], e),
+                }
+            })
+    }
+}
+
+fn ids(names: &[String]) -> Vec<NodeId> {
+    names.iter().map(NodeId::new).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::WorkflowGraph;
+    use crate::nodes::test_utils::StubNode;
+    use std::path::Path;
+
+    fn registry() -> NodeRegistry {
+        let mut registry = NodeRegistry::new();
+        for node_type in ["agent", "condition", "tool", "transform"] {
+            registry.register(node_type, move |id: &NodeId, config: &serde_json::Value| {
+                let name = config["name"].as_str().unwrap_or(id.as_str());
+                Ok(StubNode::new(id.clone(), name, node_type) as Arc<dyn Node>)
+            });
+        }
+        registry
+    }
+
+    fn golden(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/definition")
+            .join(name);
+        std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e))
+    }
+
+    // The graph of testdata/definition/review.yaml, built by hand
+    async fn hand_built() -> WorkflowGraph {
+        let schema = StateSchema::new()
+            .required("topic", StateType::String)
+            .optional("verdict", StateType::String)
+            .optional("score", StateType::Float)
+            .optional("draft", StateType::String);
+        let approved = StateExpr::parse("verdict == 'approve' && score >= 0.8").unwrap();
+        let web_retry = NodeRetryPolicy::new(3).with_backoff(Backoff::Exponential {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+        });
+        let papers_retry =
+            NodeRetryPolicy::new(2).with_backoff(Backoff::Fixed(Duration::from_millis(250)));
+
+        GraphBuilder::new("review")
+            .id("review-v1")
+            .description("Researches, drafts and reviews an article")
+            .state_schema(schema)
+            .add_node("research", StubNode::new("research", "Research", "tool"))
+            .await
+            .unwrap()
+            .add_node_with_policy(
+                "web",
+                StubNode::new("web", "Search the web", "tool"),
+                web_retry,
+            )
+            .await
+            .unwrap()
+            .add_node_with_policy(
+                "papers",
+                StubNode::new("papers", "Search papers", "tool"),
+                papers_retry,
+            )
+            .await
+            .unwrap()
+            .add_node("draft", StubNode::new("draft", "Draft", "agent"))
+            .await
+            .unwrap()
+            .add_node("review", StubNode::new("review", "Review", "condition"))
+            .await
+            .unwrap()
+            .add_node("revise", StubNode::new("revise", "Revise", "agent"))
+            .await
+            .unwrap()
+            .add_node("publish", StubNode::new("publish", "Publish", "transform"))
+            .await
+            .unwrap()
+            .add_node(
+                "apologize",
+                StubNode::new("apologize", "Apologize", "transform"),
+            )
+            .await
+            .unwrap()
+            .node_timeout("web", Duration::from_secs(5))
+            .unwrap()
+            .node_timeout("draft", Duration::from_secs(30))
+            .unwrap()
+            .exit_points(vec![NodeId::new("publish")])
+            .add_edge("draft", "review")
+            .unwrap()
+            .add_conditional_edge(
+                "review",
+                ExprRouter::new()
+                    .branch(approved, "publish")
+                    .otherwise("revise"),
+            )
+            .unwrap()
+            .add_edge_cyclic("revise", "review", 3)
+            .unwrap()
+            .loop_exit("revise", "review", "publish")
+            .unwrap()
+            .add_parallel_branches(
+                "research",
+                ParallelBranches::new(["web", "papers"], "draft")
+                    .with_conflict_policy(MergeConflictPolicy::LastWins)
+                    .with_failure_policy(PartialFailure::Collect),
+            )
+            .unwrap()
+            .on_error("revise", "publish")
+            .unwrap()
+            .on_any_error("apologize")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn assert_same_graph(loaded: &WorkflowGraph, expected: &WorkflowGraph) {
+        assert_eq!(loaded.to_dot(), expected.to_dot());
+        assert_eq!(loaded.id(), expected.id());
+        assert_eq!(loaded.name(), expected.name());
+        assert_eq!(loaded.description(), expected.description());
+        assert_eq!(loaded.entry_points(), expected.entry_points());
+        assert_eq!(loaded.exit_points(), expected.exit_points());
+        assert_eq!(loaded.state_schema(), expected.state_schema());
+
+        let mut ids = loaded.node_ids();
+        ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        let mut expected_ids = expected.node_ids();
+        expected_ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(ids, expected_ids);
+
+        for id in &ids {
+            let retry = |graph: &WorkflowGraph| {
+                graph
+                    .retry_policy(id)
+                    .map(|policy| (policy.max_attempts, policy.backoff))
+            };
+            assert_eq!(retry(loaded), retry(expected), "retry of {}", id.as_str());
+            assert_eq!(loaded.node_timeout(id), expected.node_timeout(id));
+            assert_eq!(loaded.error_handler(id), expected.error_handler(id));
+            assert_eq!(loaded.parallel_branches(id), expected.parallel_branches(id));
+            for to in &ids {
+                assert_eq!(loaded.loop_limit(id, to), expected.loop_limit(id, to));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_yaml_definition_builds_the_hand_built_graph() {
+        let loaded = GraphBuilder::from_yaml(&golden("review.yaml"), &registry())
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_same_graph(&loaded, &hand_built().await);
+    }
+
+    #[tokio::test]
+    async fn test_json_definition_builds_the_hand_built_graph() {
+        let loaded = GraphBuilder::from_json(&golden("review.json"), &registry())
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_same_graph(&loaded, &hand_built().await);
+        assert_eq!(
+            GraphDefinition::from_json(&golden("review.json")).unwrap(),
+            GraphDefinition::from_yaml(&golden("review.yaml")).unwrap()
+        );
+    }
+
+    async fn load_error(source: &str, yaml: bool) -> DefinitionError {
+        let loaded = if yaml {
+            GraphBuilder::from_yaml(source, &registry()).await
+        } else {
+            GraphBuilder::from_json(source, &registry()).await
+        };
+        match loaded {
+            Err(RGraphError::Definition(e)) => e,
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("definition loaded"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_node_type_points_at_the_type() {
+        let source = "\
+name: broken
+nodes:
+  - id: fetch
+    type: tool:web_serch
+";
+        let e = load_error(source, true).await;
+
+        assert_eq!((e.line, e.column), (Some(4), Some(11)));
+        assert_eq!(e.field, "nodes[0].type");
+        assert_eq!(
+            e.message,
+            "unknown node type 'tool:web_serch' (registered: agent, condition, tool, transform)"
+        );
+        assert!(e
+            .to_string()
+            .starts_with("line 4, column 11: nodes[0].type: "));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_field_is_named_with_its_path() {
+        let source = "\
+name: broken
+nodes:
+  - id: fetch
+    type: tool
+    retyr: { max_attempts: 3 }
+";
+        let e = load_error(source, true).await;
+
+        assert_eq!(e.line, Some(5));
+        assert!(e.column.is_some());
+        assert_eq!(e.field, "nodes[0].retyr");
+        assert!(
+            e.message.starts_with("unknown field `retyr`"),
+            "{}",
+            e.message
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mistyped_json_value_names_its_key() {
+        let source = r#"{
+  "name": "broken",
+  "nodes": [
+    {"id": "fetch", "type": "tool", "retry": {"max_attempts": "three"}}
+  ]
+}"#;
+        let e = load_error(source, false).await;
+
+        assert_eq!(e.line, Some(4));
+        assert!(e.column.is_some());
+        assert_eq!(e.field, "max_attempts");
+        assert!(e.message.starts_with("invalid type"), "{}", e.message);
+    }
+
+    #[tokio::test]
+    async fn test_edge_errors_point_at_the_edge() {
+        let nodes = "\
+name: broken
+nodes:
+  - { id: fetch, type: tool }
+  - { id: parse, type: tool }
+edges:
+";
+        let e = load_error(&format!("{nodes}  - {{ from: fetch, to: pars }}\n"), true).await;
+        assert_eq!((e.line, e.column), (Some(6), Some(24)));
+        assert_eq!(e.field, "edges[0].to");
+        assert_eq!(e.message, "no node 'pars'");
+
+        let edge = "  - { from: fetch, to: parse, when: \"score >>= 1\" }\n";
+        let e = load_error(&format!("{nodes}{edge}"), true).await;
+        assert_eq!((e.line, e.column), (Some(6), Some(31)));
+        assert_eq!(e.field, "edges[0].when");
+        assert!(
+            e.message.contains("expected a value after `>`"),
+            "{}",
+            e.message
+        );
+
+        let edges = "  - { from: fetch, to: parse, when: \"ready\" }
+  - { from: fetch, to: fetch, max_traversals: 2 }
+  - { from: fetch, to: parse }
+";
+        let e = load_error(&format!("{nodes}{edges}"), true).await;
+        assert_eq!(e.field, "edges[2].when");
+        assert!(e.message.contains("edges[1] does too"), "{}", e.message);
+    }
+}
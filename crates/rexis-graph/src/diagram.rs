@@ -0,0 +1,584 @@
+//! # Graph Diagrams
+//!
+//! Renders workflow graphs as Graphviz DOT or Mermaid flowcharts for review.
+//! Nodes show their name and [`Node::node_type`](crate::Node::node_type),
+//! conditional edges are dashed and labeled with their condition, parallel
+//! branches are grouped, and start and end markers point at the entry and exit
+//! nodes. Nodes are ordered by ID, so the same graph always renders the same.
+//!
+//! The `_with_trace` variants overlay an execution trace: visited nodes are
+//! colored, failed ones in red (even when an error handler took over), and
+//! annotated with how long they ran.
+
+use crate::core::{EdgeCondition, LoopLimit, NodeId, WorkflowGraph};
+use crate::execution::{TraceStep, Transition};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+const START: &str = "__start__";
+const END: &str = "__end__";
+const VISITED_COLOR: &str = "#d4edda";
+const FAILED_COLOR: &str = "#f8d7da";
+
+impl WorkflowGraph {
+    /// Render the graph as a Graphviz DOT digraph
+    pub fn to_dot(&self) -> String {
+        Diagram::new(self, &[]).to_dot()
+    }
+
+    /// Render the graph as DOT with the nodes `trace` ran colored and
+    /// annotated with their durations
+    pub fn to_dot_with_trace(&self, trace: &[TraceStep]) -> String {
+        Diagram::new(self, trace).to_dot()
+    }
+
+    /// Render the graph as a Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        Diagram::new(self, &[]).to_mermaid()
+    }
+
+    /// Render the graph as Mermaid with the nodes `trace` ran colored and
+    /// annotated with their durations
+    pub fn to_mermaid_with_trace(&self, trace: &[TraceStep]) -> String {
+        Diagram::new(self, trace).to_mermaid()
+    }
+}
+
+/// How often and how long a node ran in a trace
+#[derive(Default)]
+struct NodeStats {
+    runs: usize,
+    duration: Duration,
+    failed: bool,
+}
+
+struct DiagramNode {
+    id: NodeId,
+    name: String,
+    node_type: String,
+    stats: Option<NodeStats>,
+}
+
+impl DiagramNode {
+    fn label_lines(&self) -> Vec<String> {
+        let mut lines = vec![self.name.clone(), format!("({})", self.node_type)];
+        match &self.stats {
+            Some(stats) if stats.runs == 1 => lines.push(format!("{:?}", stats.duration)),
+            Some(stats) => lines.push(format!("{} runs, {:?}", stats.runs, stats.duration)),
+            None => {}
+        }
+        lines
+    }
+
+    fn is_condition(&self) -> bool {
+        self.node_type == "Condition"
+    }
+
+    fn class(&self) -> Option<&'static str> {
+        self.stats
+            .as_ref()
+            .map(|stats| if stats.failed { "failed" } else { "visited" })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum EdgeStyle {
+    Plain,
+    Conditional,
+    LoopExit,
+}
+
+struct DiagramEdge {
+    from: String,
+    to: String,
+    label: Option<String>,
+    style: EdgeStyle,
+}
+
+impl DiagramEdge {
+    fn new(from: &str, to: &str, label: Option<String>, style: EdgeStyle) -> Self {
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            label,
+            style,
+        }
+    }
+}
+
+/// Graph laid out for rendering, independent of the output format
+struct Diagram {
+    name: String,
+    nodes: Vec<DiagramNode>,
+    edges: Vec<DiagramEdge>,
+    /// Parallel branches, keyed by the node they fan out from
+    groups: Vec<(NodeId, Vec<NodeId>)>,
+}
+
+impl Diagram {
+    fn new(graph: &WorkflowGraph, trace: &[TraceStep]) -> Self {
+        let mut stats: HashMap<NodeId, NodeStats> = HashMap::new();
+        for step in trace {
+            let node = stats.entry(step.node_id.clone()).or_default();
+            node.runs += 1;
+            node.duration += step.duration;
+            node.failed |= matches!(step.transition, Transition::Failed | Transition::OnError(_));
+        }
+
+        let mut ids = graph.node_ids();
+        ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+        let mut groups = Vec::new();
+        let mut joins = HashMap::new();
+        for id in &ids {
+            if let Some(parallel) = graph.parallel_branches(id) {
+                for branch in &parallel.branches {
+                    joins.insert(branch.clone(), parallel.join.clone());
+                }
+                groups.push((id.clone(), parallel.branches));
+            }
+        }
+
+        let mut nodes = Vec::new();
+        let mut edges: Vec<DiagramEdge> = graph
+            .entry_points()
+            .iter()
+            .map(|entry| DiagramEdge::new(START, entry.as_str(), None, EdgeStyle::Plain))
+            .collect();
+        let mut implicit_exits = Vec::new();
+
+        for id in &ids {
+            let Some(node) = graph.get_node(id) else {
+                continue;
+            };
+            nodes.push(DiagramNode {
+                id: id.clone(),
+                name: node.name().to_string(),
+                node_type: node.node_type().to_string(),
+                stats: stats.remove(id),
+            });
+
+            let mut targets: Vec<(NodeId, Option<String>, EdgeStyle)> = Vec::new();
+            for edge in graph.edges_from(id) {
+                let label = edge.condition.as_ref().and_then(describe_condition);
+                let style = match label {
+                    Some(_) => EdgeStyle::Conditional,
+                    None => EdgeStyle::Plain,
+                };
+                targets.push((edge.to, label, style));
+            }
+            let router = graph.router(id);
+            if let Some(router) = &router {
+                for (target, condition) in router.describe() {
+                    let label = (!condition.is_empty()).then_some(condition);
+                    targets.push((target, label, EdgeStyle::Conditional));
+                }
+            }
+            for (from, branches) in &groups {
+                if from == id {
+                    for branch in branches {
+                        targets.push((branch.clone(), None, EdgeStyle::Plain));
+                    }
+                }
+            }
+            if let Some(join) = joins.get(id) {
+                targets.push((join.clone(), None, EdgeStyle::Plain));
+            }
+
+            // A router with unknown targets may still lead somewhere
+            if targets.is_empty() && router.is_none() {
+                implicit_exits.push(id.clone());
+            }
+
+            for (to, label, style) in targets {
+                let limit = graph.loop_limit(id, &to);
+                let label = match (&limit, label) {
+                    (Some(limit), Some(label)) => {
+                        Some(format!("{}, max {}", label, limit.max_traversals))
+                    }
+                    (Some(limit), None) => Some(format!("max {}", limit.max_traversals)),
+                    (None, label) => label,
+                };
+                edges.push(DiagramEdge::new(id.as_str(), to.as_str(), label, style));
+
+                if let Some(LoopLimit {
+                    max_traversals,
+                    exit: Some(exit),
+                }) = limit
+                {
+                    let label = format!("after {}", max_traversals);
+                    edges.push(DiagramEdge::new(
+                        id.as_str(),
+                        exit.as_str(),
+                        Some(label),
+                        EdgeStyle::LoopExit,
+                    ));
+                }
+            }
+        }
+
+        let mut exits = graph.exit_points();
+        if exits.is_empty() {
+            exits = implicit_exits;
+        }
+        edges.extend(
+            exits
+                .iter()
+                .map(|exit| DiagramEdge::new(exit.as_str(), END, None, EdgeStyle::Plain)),
+        );
+
+        Self {
+            name: graph.name().to_string(),
+            nodes,
+            edges,
+            groups,
+        }
+    }
+
+    fn node(&self, id: &NodeId) -> Option<&DiagramNode> {
+        self.nodes.iter().find(|node| &node.id == id)
+    }
+
+    /// Nodes outside any parallel group
+    fn ungrouped_nodes(&self) -> impl Iterator<Item = &DiagramNode> {
+        let grouped: HashSet<&NodeId> = self
+            .groups
+            .iter()
+            .flat_map(|(_, branches)| branches)
+            .collect();
+        self.nodes
+            .iter()
+            .filter(move |node| !grouped.contains(&node.id))
+    }
+
+    fn to_dot(&self) -> String {
+        let mut lines = vec![
+            format!("digraph {} {{", dot_id(&self.name)),
+            "    rankdir=TB;".to_string(),
+            "    node [shape=box, style=rounded];".to_string(),
+            format!("    {} [label=\"start\", shape=circle];", dot_id(START)),
+            format!("    {} [label=\"end\", shape=doublecircle];", dot_id(END)),
+        ];
+
+        for node in self.ungrouped_nodes() {
+            lines.push(format!("    {}", dot_node(node)));
+        }
+        for (from, branches) in &self.groups {
+            let cluster = format!("cluster_parallel_{}", from.as_str());
+            lines.push(format!("    subgraph {} {{", dot_id(&cluster)));
+            lines.push("        label=\"parallel\";".to_string());
+            lines.push("        style=dashed;".to_string());
+            for branch in branches.iter().filter_map(|branch| self.node(branch)) {
+                lines.push(format!("        {}", dot_node(branch)));
+            }
+            lines.push("    }".to_string());
+        }
+
+        for edge in &self.edges {
+            let mut attributes = Vec::new();
+            if let Some(label) = &edge.label {
+                attributes.push(format!("label=\"{}\"", dot_escape(label)));
+            }
+            match edge.style {
+                EdgeStyle::Plain => {}
+                EdgeStyle::Conditional => attributes.push("style=dashed".to_string()),
+                EdgeStyle::LoopExit => attributes.push("style=dotted".to_string()),
+            }
+            let attributes = if attributes.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attributes.join(", "))
+            };
+            lines.push(format!(
+                "    {} -> {}{};",
+                dot_id(&edge.from),
+                dot_id(&edge.to),
+                attributes
+            ));
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn to_mermaid(&self) -> String {
+        let mut lines = vec![
+            "flowchart TD".to_string(),
+            format!("    {}((start))", START),
+            format!("    {}(((end)))", END),
+        ];
+
+        for node in self.ungrouped_nodes() {
+            lines.push(format!("    {}", mermaid_node(node)));
+        }
+        for (from, branches) in &self.groups {
+            let group = mermaid_id(&format!("parallel_{}", from.as_str()));
+            lines.push(format!("    subgraph {} [parallel]", group));
+            for branch in branches.iter().filter_map(|branch| self.node(branch)) {
+                lines.push(format!("        {}", mermaid_node(branch)));
+            }
+            lines.push("    end".to_string());
+        }
+
+        for edge in &self.edges {
+            let arrow = match edge.style {
+                EdgeStyle::Plain => "-->",
+                EdgeStyle::Conditional | EdgeStyle::LoopExit => "-.->",
+            };
+            let label = edge
+                .label
+                .as_ref()
+                .map(|label| format!("|\"{}\"|", mermaid_escape(label)))
+                .unwrap_or_default();
+            lines.push(format!(
+                "    {} {}{} {}",
+                mermaid_id(&edge.from),
+                arrow,
+                label,
+                mermaid_id(&edge.to)
+            ));
+        }
+
+        for (class, color, stroke) in [
+            ("visited", VISITED_COLOR, "#28a745"),
+            ("failed", FAILED_COLOR, "#dc3545"),
+        ] {
+            let members: Vec<String> = self
+                .nodes
+                .iter()
+                .filter(|node| node.class() == Some(class))
+                .map(|node| mermaid_id(node.id.as_str()))
+                .collect();
+            if !members.is_empty() {
+                lines.push(format!(
+                    "    classDef {} fill:{},stroke:{}",
+                    class, color, stroke
+                ));
+                lines.push(format!("    class {} {}", members.join(","), class));
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+/// Label of an edge condition, if it has one
+fn describe_condition(condition: &EdgeCondition) -> Option<String> {
+    match condition {
+        EdgeCondition::Always => None,
+        EdgeCondition::Conditional(description) => Some(description.clone()),
+        EdgeCondition::StateCondition {
+            key,
+            expected_value,
+        } => Some(format!("{} == {}", key, expected_value)),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn dot_id(id: &str) -> String {
+    format!("\"{}\"", dot_escape(id))
+}
+
+fn dot_node(node: &DiagramNode) -> String {
+    let label: Vec<String> = node
+        .label_lines()
+        .iter()
+        .map(|line| dot_escape(line))
+        .collect();
+    let mut attributes = vec![format!("label=\"{}\"", label.join("\\n"))];
+    if node.is_condition() {
+        attributes.push("shape=diamond".to_string());
+    }
+    match node.class() {
+        Some("failed") => {
+            attributes.push("style=\"rounded,filled\"".to_string());
+            attributes.push(format!("fillcolor=\"{}\"", FAILED_COLOR));
+        }
+        Some(_) => {
+            attributes.push("style=\"rounded,filled\"".to_string());
+            attributes.push(format!("fillcolor=\"{}\"", VISITED_COLOR));
+        }
+        None => {}
+    }
+    format!("{} [{}];", dot_id(node.id.as_str()), attributes.join(", "))
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// Mermaid IDs allow only letters, digits and underscores, and `end` is a
+/// keyword
+fn mermaid_id(id: &str) -> String {
+    let id: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if id.eq_ignore_ascii_case("end") {
+        format!("{}_", id)
+    } else {
+        id
+    }
+}
+
+fn mermaid_node(node: &DiagramNode) -> String {
+    let label: Vec<String> = node
+        .label_lines()
+        .iter()
+        .map(|line| mermaid_escape(line))
+        .collect();
+    let label = label.join("<br/>");
+    let id = mermaid_id(node.id.as_str());
+    if node.is_condition() {
+        format!("{}{{\"{}\"}}", id, label)
+    } else {
+        format!("{}[\"{}\"]", id, label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GraphBuilder, Node};
+    use crate::diff::StateDiff;
+    use crate::nodes::test_utils::StubNode;
+    use crate::routing::when;
+    use std::path::Path;
+
+    // Research fanning out to two searches, then a draft reviewed until it
+    // is approved or has been revised three times
+    async fn review_graph() -> WorkflowGraph {
+        let nodes = [
+            StubNode::new("research", "Research", "Tool"),
+            StubNode::new("web", "Search the web", "Tool"),
+            StubNode::new("papers", "Search papers", "Tool"),
+            StubNode::new("draft", "Draft", "Agent"),
+            StubNode::new("review", "Review", "Condition"),
+            StubNode::new("revise", "Revise", "Agent"),
+            StubNode::new("publish", "Publish", "Transform"),
+        ];
+        let mut builder = GraphBuilder::new("review");
+        for node in nodes {
+            let id = node.id().clone();
+            builder = builder.add_node(id, node).await.unwrap();
+        }
+        builder
+            .add_parallel("research", ["web", "papers"], "draft")
+            .unwrap()
+            .add_edge("draft", "review")
+            .unwrap()
+            .add_conditional_edge(
+                "review",
+                when("verdict")
+                    .equals("approve")
+                    .goto("publish")
+                    .otherwise("revise"),
+            )
+            .unwrap()
+            .add_edge_cyclic("revise", "review", 3)
+            .unwrap()
+            .loop_exit("revise", "review", "publish")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn step(node: &str, millis: u64, transition: Transition) -> TraceStep {
+        TraceStep {
+            node_id: NodeId::new(node),
+            iteration: 1,
+            duration: Duration::from_millis(millis),
+            attempts: 1,
+            transition,
+            usage: None,
+            children: Vec::new(),
+            changes: StateDiff::default(),
+        }
+    }
+
+    // A run revising once, then failing to publish
+    fn review_trace() -> Vec<TraceStep> {
+        let node = NodeId::new;
+        vec![
+            step(
+                "research",
+                5,
+                Transition::Parallel {
+                    branches: vec![node("web"), node("papers")],
+                    join: node("draft"),
+                },
+            ),
+            step("web", 40, Transition::Join(node("draft"))),
+            step("papers", 60, Transition::Join(node("draft"))),
+            step("draft", 100, Transition::Edges(vec![node("review")])),
+            step("review", 10, Transition::Branch(node("revise"))),
+            step("revise", 50, Transition::Edges(vec![node("review")])),
+            step("review", 12, Transition::Branch(node("publish"))),
+            step("publish", 3, Transition::Failed),
+        ]
+    }
+
+    /// Compare against `testdata/diagram/{name}`; set UPDATE_GOLDEN=1 to
+    /// rewrite the file instead
+    fn assert_golden(name: &str, actual: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/diagram")
+            .join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+        assert_eq!(
+            actual, expected,
+            "{} is out of date; rerun with UPDATE_GOLDEN=1",
+            name
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dot_matches_golden_file() {
+        let graph = review_graph().await;
+
+        let dot = graph.to_dot();
+
+        assert_golden("review.dot", &dot);
+        // Rendering does not depend on hash map order
+        assert_eq!(review_graph().await.to_dot(), dot);
+    }
+
+    #[tokio::test]
+    async fn test_mermaid_matches_golden_file() {
+        let graph = review_graph().await;
+
+        assert_golden("review.mmd", &graph.to_mermaid());
+    }
+
+    #[tokio::test]
+    async fn test_trace_overlay_colors_visited_nodes() {
+        let graph = review_graph().await;
+        let trace = review_trace();
+
+        assert_golden("review_trace.mmd", &graph.to_mermaid_with_trace(&trace));
+
+        let dot = graph.to_dot_with_trace(&trace);
+        assert!(dot.contains(
+            "\"review\" [label=\"Review\\n(Condition)\\n2 runs, 22ms\", shape=diamond, \
+             style=\"rounded,filled\", fillcolor=\"#d4edda\"];"
+        ));
+        assert!(dot.contains(
+            "\"publish\" [label=\"Publish\\n(Transform)\\n3ms\", \
+             style=\"rounded,filled\", fillcolor=\"#f8d7da\"];"
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+//! # State Diffs
+//!
+//! The engine compares the state before and after every node it runs. The
+//! [`StateDiff`] it finds is kept in the node's
+//! [`TraceStep`](crate::execution::TraceStep), sent with the
+//! [`StateUpdated`](crate::events::GraphEvent::StateUpdated) event of
+//! streamed runs, and gathered per key by
+//! [`RunReport::changes_for_key`](crate::report::RunReport::changes_for_key),
+//! which answers which node last wrote a key and what it wrote.
+//!
+//! Large values are summarized by their size and a hash, and
+//! [`DiffCapture::KeysOnly`] leaves values out altogether for states holding
+//! sensitive data.
+
+use crate::state::StateValue;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How much of the changed values a diff records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DiffCapture {
+    /// Values, summarized when larger than `max_size` bytes
+    Values { max_size: usize },
+    /// Only which keys changed and how
+    KeysOnly,
+}
+
+impl Default for DiffCapture {
+    fn default() -> Self {
+        DiffCapture::Values { max_size: 1024 }
+    }
+}
+
+/// A value as recorded in a diff
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DiffValue {
+    Full(StateValue),
+    /// A value too large to record: its approximate size in bytes and a hash
+    /// that tells whether two such values are equal
+    Summary {
+        size: usize,
+        hash: u64,
+    },
+}
+
+/// How a key changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// The change of one key
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyChange {
+    pub key: String,
+    pub kind: ChangeKind,
+    /// The value before, unless the key was added or values are not captured
+    pub old: Option<DiffValue>,
+    /// The value after, unless the key was removed or values are not captured
+    pub new: Option<DiffValue>,
+}
+
+/// The keys a node added, changed and removed, sorted by key
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StateDiff {
+    pub changes: Vec<KeyChange>,
+}
+
+impl StateDiff {
+    /// Compare two snapshots of a state
+    pub fn between(
+        before: &HashMap<String, StateValue>,
+        after: &HashMap<String, StateValue>,
+        capture: DiffCapture,
+    ) -> Self {
+        let keys: BTreeMap<&String, (Option<&StateValue>, Option<&StateValue>)> = before
+            .iter()
+            .map(|(key, value)| (key, (Some(value), after.get(key))))
+            .chain(
+                after
+                    .iter()
+                    .filter(|(key, _)| !before.contains_key(*key))
+                    .map(|(key, value)| (key, (None, Some(value)))),
+            )
+            .collect();
+
+        let changes = keys
+            .into_iter()
+            .filter_map(|(key, (old, new))| {
+                let kind = match (old, new) {
+                    (Some(old), Some(new)) if old == new => return None,
+                    (Some(_), Some(_)) => ChangeKind::Changed,
+                    (Some(_), None) => ChangeKind::Removed,
+                    (None, _) => ChangeKind::Added,
+                };
+                Some(KeyChange {
+                    key: key.clone(),
+                    kind,
+                    old: old.and_then(|value| capture.record(value)),
+                    new: new.and_then(|value| capture.record(value)),
+                })
+            })
+            .collect();
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The keys that changed, sorted
+    pub fn keys(&self) -> Vec<String> {
+        self.changes
+            .iter()
+            .map(|change| change.key.clone())
+            .collect()
+    }
+
+    /// The change of `key`, if it changed
+    pub fn get(&self, key: &str) -> Option<&KeyChange> {
+        self.changes.iter().find(|change| change.key == key)
+    }
+}
+
+impl DiffCapture {
+    fn record(&self, value: &StateValue) -> Option<DiffValue> {
+        match *self {
+            DiffCapture::KeysOnly => None,
+            DiffCapture::Values { max_size } => {
+                let mut hasher = DefaultHasher::new();
+                let size = measure(value, &mut hasher);
+                Some(if size > max_size {
+                    DiffValue::Summary {
+                        size,
+                        hash: hasher.finish(),
+                    }
+                } else {
+                    DiffValue::Full(value.clone())
+                })
+            }
+        }
+    }
+}
+
+/// Approximate size of `value` in bytes, hashing it into `hasher`
+fn measure(value: &StateValue, hasher: &mut DefaultHasher) -> usize {
+    std::mem::discriminant(value).hash(hasher);
+    match value {
+        StateValue::String(text) => {
+            text.hash(hasher);
+            text.len()
+        }
+        StateValue::Bytes(bytes) => {
+            bytes.hash(hasher);
+            bytes.len()
+        }
+        StateValue::Integer(number) => {
+            number.hash(hasher);
+            8
+        }
+        StateValue::Float(number) => {
+            number.to_bits().hash(hasher);
+            8
+        }
+        StateValue::Boolean(flag) => {
+            flag.hash(hasher);
+            1
+        }
+        StateValue::Null => 0,
+        StateValue::Array(items) => items.iter().map(|item| measure(item, hasher)).sum(),
+        // Sorted, so that equal objects hash the same
+        StateValue::Object(fields) => fields
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(key, value)| {
+                key.hash(hasher);
+                key.len() + measure(value, hasher)
+            })
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeId};
+    use crate::events::GraphEvent;
+    use crate::execution::{ExecutionConfig, ExecutionEngine, ExecutionResults};
+    use crate::state::GraphState;
+    use crate::RGraphResult;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use std::sync::Arc;
+
+    // Node setting and removing fixed keys
+    struct EditNode {
+        id: NodeId,
+        sets: Vec<(&'static str, StateValue)>,
+        removes: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl Node for EditNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            for (key, value) in &self.sets {
+                state.set(*key, value.clone());
+            }
+            for key in &self.removes {
+                state.remove(key);
+            }
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    fn edit(
+        id: &str,
+        sets: Vec<(&'static str, StateValue)>,
+        removes: Vec<&'static str>,
+    ) -> Arc<EditNode> {
+        Arc::new(EditNode {
+            id: NodeId::new(id),
+            sets,
+            removes,
+        })
+    }
+
+    fn full(value: impl Into<StateValue>) -> Option<DiffValue> {
+        Some(DiffValue::Full(value.into()))
+    }
+
+    // "fetch" adds two keys, "parse" rewrites one and drops the other,
+    // "store" rewrites the first again with a large value
+    async fn run(config: ExecutionConfig) -> ExecutionResults {
+        let graph = GraphBuilder::new("audit")
+            .add_node(
+                "fetch",
+                edit(
+                    "fetch",
+                    vec![("doc", "raw".into()), ("status", "fetched".into())],
+                    vec![],
+                ),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "parse",
+                edit("parse", vec![("doc", "parsed".into())], vec!["status"]),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "store",
+                edit(
+                    "store",
+                    vec![("doc", "x".repeat(2000).into()), ("stored", true.into())],
+                    vec![],
+                ),
+            )
+            .await
+            .unwrap()
+            .add_edge("fetch", "parse")
+            .unwrap()
+            .add_edge("parse", "store")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        ExecutionEngine::with_config(config)
+            .execute(&graph, GraphState::new().with_input("query", "q"))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_report_lists_the_changes_of_a_key() {
+        let results = run(ExecutionConfig::default()).await;
+        let report = results.report();
+
+        let history: Vec<(&str, &KeyChange)> = report
+            .changes_for_key("doc")
+            .into_iter()
+            .map(|(visit, change)| (visit.node_id.as_str(), change))
+            .collect();
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history[0],
+            (
+                "fetch",
+                &KeyChange {
+                    key: "doc".to_string(),
+                    kind: ChangeKind::Added,
+                    old: None,
+                    new: full("raw"),
+                }
+            )
+        );
+        assert_eq!(history[1].0, "parse");
+        assert_eq!(history[1].1.kind, ChangeKind::Changed);
+        assert_eq!(
+            (&history[1].1.old, &history[1].1.new),
+            (&full("raw"), &full("parsed"))
+        );
+        assert_eq!(history[2].0, "store");
+        assert_eq!(history[2].1.old, full("parsed"));
+        assert!(matches!(
+            history[2].1.new,
+            Some(DiffValue::Summary { size: 2000, .. })
+        ));
+
+        let status: Vec<(&str, ChangeKind)> = report
+            .changes_for_key("status")
+            .into_iter()
+            .map(|(visit, change)| (visit.node_id.as_str(), change.kind))
+            .collect();
+        assert_eq!(
+            status,
+            [("fetch", ChangeKind::Added), ("parse", ChangeKind::Removed)]
+        );
+        assert!(report.changes_for_key("query").is_empty());
+        assert_eq!(results.trace[2].changes.keys(), ["doc", "stored"]);
+    }
+
+    #[tokio::test]
+    async fn test_keys_only_capture_leaves_values_out() {
+        let results = run(ExecutionConfig {
+            diff_capture: DiffCapture::KeysOnly,
+            ..ExecutionConfig::default()
+        })
+        .await;
+
+        let changes = &results.trace[1].changes;
+        assert_eq!(changes.keys(), ["doc", "status"]);
+        assert!(changes
+            .changes
+            .iter()
+            .all(|change| change.old.is_none() && change.new.is_none()));
+        assert_eq!(changes.get("status").unwrap().kind, ChangeKind::Removed);
+    }
+
+    #[tokio::test]
+    async fn test_state_updated_events_carry_the_diff() {
+        let graph = GraphBuilder::new("audit")
+            .add_node("count", edit("count", vec![("count", 1.into())], vec![]))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut run = ExecutionEngine::new().execute_stream(&graph, GraphState::new());
+        let mut diffs = Vec::new();
+        while let Some(event) = run.next().await {
+            if let GraphEvent::StateUpdated { keys, changes, .. } = event {
+                diffs.push((keys, changes));
+            }
+        }
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].0, ["count"]);
+        assert_eq!(diffs[0].1.get("count").unwrap().new, full(1));
+    }
+
+    #[test]
+    fn test_summaries_tell_equal_values_apart() {
+        let capture = DiffCapture::Values { max_size: 4 };
+        let summary = |text: &str| capture.record(&StateValue::from(text)).unwrap();
+
+        assert_eq!(summary("long value"), summary("long value"));
+        assert_ne!(summary("long value"), summary("long value!"));
+        assert_eq!(summary("tiny"), DiffValue::Full("tiny".into()));
+    }
+}
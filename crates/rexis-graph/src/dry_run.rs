@@ -0,0 +1,643 @@
+//! # Dry Runs
+//!
+//! A dry run walks a graph without running its nodes. Each visited node
+//! "writes" its declared output keys: values stubbed for it by the caller,
+//! values from [`Node::simulate`], or else placeholders. Routes are taken
+//! against this simulated state where it holds real values; where a route
+//! depends on placeholders, every target it declares is explored as a path
+//! of its own.
+//!
+//! ```rust,no_run
+//! # async fn example(graph: rexis_graph::WorkflowGraph) {
+//! use rexis_graph::{DryRun, GraphState};
+//!
+//! let report = DryRun::new()
+//!     .stub("classify", [("category", "billing")])
+//!     .run(&graph, GraphState::new().with_input("ticket", "Refund please"))
+//!     .await;
+//! for gap in &report.gaps {
+//!     println!("'{}' reads '{}', which nothing wrote", gap.node_id.as_str(), gap.key);
+//! }
+//! # }
+//! ```
+
+use crate::core::{EdgeCondition, Node, NodeId, WorkflowGraph};
+use crate::routing::values_equal;
+use crate::state::{GraphState, StateValue};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Where the values a node wrote in a dry run came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatedOutput {
+    /// Values stubbed with [`DryRun::stub`]
+    Stub,
+    /// Values from the node's [`Node::simulate`]
+    Simulated,
+    /// Placeholders for the node's output keys
+    Placeholder,
+}
+
+/// A node visited by a dry run
+#[derive(Debug, Clone)]
+pub struct DryRunStep {
+    pub node_id: NodeId,
+    /// The path the visit belongs to; 0 unless routes had to be explored,
+    /// and later paths start where they branched off
+    pub path: usize,
+    /// Keys the node declares it reads
+    pub reads: Vec<String>,
+    /// Keys the node wrote
+    pub writes: Vec<String>,
+    pub output: SimulatedOutput,
+}
+
+/// A key a node reads that nothing wrote before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunGap {
+    pub node_id: NodeId,
+    pub key: String,
+    pub path: usize,
+}
+
+/// A route a dry run could not follow, ending its path
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedRoute {
+    pub node_id: NodeId,
+    pub path: usize,
+    pub message: String,
+}
+
+/// What a dry run found
+#[derive(Debug, Clone, Default)]
+pub struct DryRunReport {
+    /// Visits in the order they were made, path after path
+    pub steps: Vec<DryRunStep>,
+    pub gaps: Vec<DryRunGap>,
+    pub unresolved: Vec<UnresolvedRoute>,
+    /// How many paths were walked
+    pub paths: usize,
+    /// Whether the walk stopped at [`DryRun::with_max_steps`]
+    pub truncated: bool,
+}
+
+impl DryRunReport {
+    /// Nodes visited on `path`, in order
+    pub fn visit_order(&self, path: usize) -> Vec<NodeId> {
+        self.steps
+            .iter()
+            .filter(|step| step.path == path)
+            .map(|step| step.node_id.clone())
+            .collect()
+    }
+
+    /// Keys written by visited nodes, in the order first written
+    pub fn produced(&self) -> Vec<&str> {
+        Self::first_seen(self.steps.iter().flat_map(|step| &step.writes))
+    }
+
+    /// Keys read by visited nodes, in the order first read
+    pub fn consumed(&self) -> Vec<&str> {
+        Self::first_seen(self.steps.iter().flat_map(|step| &step.reads))
+    }
+
+    fn first_seen<'a>(keys: impl Iterator<Item = &'a String>) -> Vec<&'a str> {
+        let mut seen = HashSet::new();
+        keys.filter(|key| seen.insert(*key))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Configuration of a dry run
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    stubs: HashMap<NodeId, HashMap<String, StateValue>>,
+    max_steps: usize,
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DryRun {
+    pub fn new() -> Self {
+        Self {
+            stubs: HashMap::new(),
+            max_steps: 1000,
+        }
+    }
+
+    /// Have `node_id` write `outputs` instead of simulated values
+    pub fn stub<K, V>(
+        mut self,
+        node_id: impl Into<NodeId>,
+        outputs: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<StateValue>,
+    {
+        let stub = self.stubs.entry(node_id.into()).or_default();
+        stub.extend(
+            outputs
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        self
+    }
+
+    /// Stop after `max_steps` visits over all paths, 1000 by default
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Walk `graph` from `initial_state`, which is not modified
+    pub async fn run(&self, graph: &WorkflowGraph, initial_state: GraphState) -> DryRunReport {
+        let mut report = DryRunReport::default();
+        let mut paths = VecDeque::from([Path {
+            id: 0,
+            state: initial_state.fork(),
+            placeholders: HashSet::new(),
+            queue: graph.entry_points().into(),
+            traversals: HashMap::new(),
+        }]);
+        report.paths = 1;
+
+        while let Some(mut path) = paths.pop_front() {
+            while let Some(node_id) = path.queue.pop_front() {
+                if report.steps.len() >= self.max_steps {
+                    report.truncated = true;
+                    return report;
+                }
+                let Some(node) = graph.get_node(&node_id) else {
+                    report.unresolved.push(UnresolvedRoute {
+                        node_id: node_id.clone(),
+                        path: path.id,
+                        message: format!("node '{}' does not exist", node_id.as_str()),
+                    });
+                    continue;
+                };
+                self.visit(node.as_ref(), &mut path, &mut report);
+
+                if let Some(parallel) = graph.parallel_branches(&node_id) {
+                    for branch in &parallel.branches {
+                        if let Some(node) = graph.get_node(branch) {
+                            self.visit(node.as_ref(), &mut path, &mut report);
+                        }
+                    }
+                    path.queue.push_back(parallel.join);
+                    continue;
+                }
+
+                let next = match Self::next(graph, &path, &node_id).await {
+                    Ok(next) => next,
+                    Err(message) => {
+                        report.unresolved.push(UnresolvedRoute {
+                            node_id,
+                            path: path.id,
+                            message,
+                        });
+                        continue;
+                    }
+                };
+                match next {
+                    Next::Edges(targets) => {
+                        for target in targets {
+                            path.step(graph, &node_id, target, &mut report);
+                        }
+                    }
+                    Next::Explore(targets) => {
+                        // Every target but the first is explored from a copy
+                        // of this path
+                        let mut targets = targets.into_iter();
+                        let Some(first) = targets.next() else {
+                            continue;
+                        };
+                        for target in targets {
+                            let mut fork = path.fork(report.paths);
+                            report.paths += 1;
+                            if fork.step(graph, &node_id, target, &mut report) {
+                                paths.push_back(fork);
+                            }
+                        }
+                        path.step(graph, &node_id, first, &mut report);
+                    }
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Record a visit to `node` and write its outputs
+    fn visit(&self, node: &dyn Node, path: &mut Path, report: &mut DryRunReport) {
+        let node_id = node.id().clone();
+        let reads: Vec<String> = node.input_keys().into_iter().map(String::from).collect();
+        for key in &reads {
+            if !path.state.contains_key(top_level(key)) {
+                report.gaps.push(DryRunGap {
+                    node_id: node_id.clone(),
+                    key: key.clone(),
+                    path: path.id,
+                });
+            }
+        }
+
+        let (outputs, output) = match self.stubs.get(&node_id) {
+            Some(stub) => (stub.clone(), SimulatedOutput::Stub),
+            None => match node.simulate(&path.state) {
+                Some(outputs) => (outputs, SimulatedOutput::Simulated),
+                None => {
+                    let outputs = node
+                        .output_keys()
+                        .into_iter()
+                        .map(|key| {
+                            let placeholder = format!("<{} from {}>", key, node_id.as_str());
+                            (key.to_string(), StateValue::String(placeholder))
+                        })
+                        .collect();
+                    (outputs, SimulatedOutput::Placeholder)
+                }
+            },
+        };
+
+        let mut writes: Vec<String> = outputs.keys().cloned().collect();
+        writes.sort();
+        for (key, value) in outputs {
+            if output == SimulatedOutput::Placeholder {
+                path.placeholders.insert(top_level(&key).to_string());
+            } else {
+                path.placeholders.remove(top_level(&key));
+            }
+            path.state.set(key, value);
+        }
+
+        report.steps.push(DryRunStep {
+            node_id,
+            path: path.id,
+            reads,
+            writes,
+            output,
+        });
+    }
+
+    /// Nodes that may run after `node_id`
+    async fn next(graph: &WorkflowGraph, path: &Path, node_id: &NodeId) -> Result<Next, String> {
+        let Some(router) = graph.router(node_id) else {
+            return Ok(Next::Edges(
+                graph
+                    .edges_from(node_id)
+                    .into_iter()
+                    .filter(|edge| path.edge_applies(edge.condition.as_ref()))
+                    .map(|edge| edge.to)
+                    .collect(),
+            ));
+        };
+
+        let targets = router.targets();
+        let keys = router.keys();
+        let known = !keys.is_empty() && keys.iter().all(|key| !path.is_placeholder(key));
+        if !targets.is_empty() && !known {
+            let mut explored = Vec::new();
+            for target in targets {
+                if !explored.contains(&target) {
+                    explored.push(target);
+                }
+            }
+            return Ok(Next::Explore(explored));
+        }
+
+        match router.route(&path.state).await {
+            Ok(target) if graph.contains_node(&target) => Ok(Next::Edges(vec![target])),
+            Ok(target) => Err(format!("routed to unknown node '{}'", target.as_str())),
+            Err(e) => Err(format!("routing failed: {}", e)),
+        }
+    }
+}
+
+/// Where a path goes after a node
+enum Next {
+    /// All of these nodes run next, as when several plain edges are taken
+    Edges(Vec<NodeId>),
+    /// Any one of these nodes may run next, each explored on a path
+    Explore(Vec<NodeId>),
+}
+
+/// One way through the graph, with the state simulated along it
+struct Path {
+    id: usize,
+    state: GraphState,
+    /// Top-level keys holding placeholders
+    placeholders: HashSet<String>,
+    queue: VecDeque<NodeId>,
+    traversals: HashMap<(NodeId, NodeId), usize>,
+}
+
+impl Path {
+    fn fork(&self, id: usize) -> Self {
+        Self {
+            id,
+            state: self.state.fork(),
+            placeholders: self.placeholders.clone(),
+            queue: self.queue.clone(),
+            traversals: self.traversals.clone(),
+        }
+    }
+
+    fn is_placeholder(&self, key: &str) -> bool {
+        self.placeholders.contains(top_level(key))
+    }
+
+    /// Whether an edge is taken; conditions on placeholders are taken to hold
+    fn edge_applies(&self, condition: Option<&EdgeCondition>) -> bool {
+        match condition {
+            None | Some(EdgeCondition::Always) => true,
+            Some(EdgeCondition::StateCondition { key, .. }) if self.is_placeholder(key) => true,
+            Some(EdgeCondition::StateCondition {
+                key,
+                expected_value,
+            }) => self
+                .state
+                .get(key)
+                .is_ok_and(|value| values_equal(&value, &StateValue::from(expected_value.clone()))),
+            Some(EdgeCondition::Conditional(_)) => false,
+        }
+    }
+
+    /// Queue the step from `from` to `to`, going to the loop exit once the
+    /// loop limit is reached; false when the path ends at the limit
+    fn step(
+        &mut self,
+        graph: &WorkflowGraph,
+        from: &NodeId,
+        to: NodeId,
+        report: &mut DryRunReport,
+    ) -> bool {
+        let next = match graph.loop_limit(from, &to) {
+            Some(limit) if self.traversal(from, &to) >= limit.max_traversals => match limit.exit {
+                Some(exit) => exit,
+                None => {
+                    report.unresolved.push(UnresolvedRoute {
+                        node_id: from.clone(),
+                        path: self.id,
+                        message: format!(
+                            "loop limit of {} traversals to '{}' reached",
+                            limit.max_traversals,
+                            to.as_str()
+                        ),
+                    });
+                    return false;
+                }
+            },
+            _ => to,
+        };
+
+        *self
+            .traversals
+            .entry((from.clone(), next.clone()))
+            .or_insert(0) += 1;
+        self.queue.push_back(next);
+        true
+    }
+
+    fn traversal(&self, from: &NodeId, to: &NodeId) -> usize {
+        self.traversals
+            .get(&(from.clone(), to.clone()))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+fn top_level(key: &str) -> &str {
+    key.split('.').next().unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder};
+    use crate::execution::ExecutionEngine;
+    use crate::routing::when;
+    use crate::RGraphResult;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    // Node writing fixed values, which it reports when simulated if asked to
+    struct FixedNode {
+        id: NodeId,
+        reads: Vec<&'static str>,
+        writes: Vec<(&'static str, StateValue)>,
+        simulated: bool,
+    }
+
+    impl FixedNode {
+        fn outputs(&self) -> HashMap<String, StateValue> {
+            self.writes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl Node for FixedNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            for (key, value) in self.outputs() {
+                state.set(key, value);
+            }
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn input_keys(&self) -> Vec<&str> {
+            self.reads.clone()
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            self.writes.iter().map(|(key, _)| *key).collect()
+        }
+
+        fn simulate(&self, _state: &GraphState) -> Option<HashMap<String, StateValue>> {
+            self.simulated.then(|| self.outputs())
+        }
+    }
+
+    fn fixed(
+        id: &str,
+        reads: &[&'static str],
+        writes: &[(&'static str, StateValue)],
+        simulated: bool,
+    ) -> Arc<FixedNode> {
+        Arc::new(FixedNode {
+            id: NodeId::new(id),
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            simulated,
+        })
+    }
+
+    /// classify -> (refund | answer) -> reply, routed on `category`
+    async fn support_graph(simulated: bool) -> WorkflowGraph {
+        let category = [("category", StateValue::from("billing"))];
+        GraphBuilder::new("support")
+            .add_node(
+                "classify",
+                fixed("classify", &["ticket"], &category, simulated),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "refund",
+                fixed(
+                    "refund",
+                    &["category"],
+                    &[("draft", "refund".into())],
+                    false,
+                ),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "answer",
+                fixed(
+                    "answer",
+                    &["category"],
+                    &[("draft", "answer".into())],
+                    false,
+                ),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "reply",
+                fixed("reply", &["draft"], &[("reply", "sent".into())], false),
+            )
+            .await
+            .unwrap()
+            .add_conditional_edge(
+                "classify",
+                when("category")
+                    .equals("billing")
+                    .goto("refund")
+                    .otherwise("answer"),
+            )
+            .unwrap()
+            .add_edge("refund", "reply")
+            .unwrap()
+            .add_edge("answer", "reply")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn ids(names: &[&str]) -> Vec<NodeId> {
+        names.iter().map(|name| NodeId::new(*name)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_follows_the_real_execution_order() {
+        let graph = support_graph(true).await;
+        let state = GraphState::new().with_input("ticket", "Refund please");
+
+        let report = graph.dry_run(state.clone()).await;
+        // The caller's state is left alone
+        assert!(!state.contains_key("category"));
+        let results = ExecutionEngine::new().execute(&graph, state).await.unwrap();
+
+        let executed: Vec<NodeId> = results
+            .trace
+            .iter()
+            .map(|step| step.node_id.clone())
+            .collect();
+        assert_eq!(report.paths, 1);
+        assert_eq!(report.visit_order(0), executed);
+        assert_eq!(report.visit_order(0), ids(&["classify", "refund", "reply"]));
+        assert_eq!(report.steps[0].output, SimulatedOutput::Simulated);
+        assert_eq!(report.produced(), ["category", "draft", "reply"]);
+        assert_eq!(report.consumed(), ["ticket", "category", "draft"]);
+        assert!(report.gaps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stub_picks_the_route() {
+        let graph = support_graph(false).await;
+        let report = DryRun::new()
+            .stub("classify", [("category", "shipping")])
+            .run(
+                &graph,
+                GraphState::new().with_input("ticket", "Where is it?"),
+            )
+            .await;
+
+        assert_eq!(report.paths, 1);
+        assert_eq!(report.visit_order(0), ids(&["classify", "answer", "reply"]));
+        assert_eq!(report.steps[0].output, SimulatedOutput::Stub);
+    }
+
+    #[tokio::test]
+    async fn test_route_on_placeholder_explores_every_target() {
+        let graph = support_graph(false).await;
+        let report = graph
+            .dry_run(GraphState::new().with_input("ticket", "Hello"))
+            .await;
+
+        assert_eq!(report.paths, 2);
+        assert_eq!(report.visit_order(0), ids(&["classify", "refund", "reply"]));
+        assert_eq!(report.visit_order(1), ids(&["answer", "reply"]));
+        assert_eq!(report.steps[0].output, SimulatedOutput::Placeholder);
+    }
+
+    #[tokio::test]
+    async fn test_keys_nothing_wrote_are_gaps() {
+        let graph = support_graph(true).await;
+        let report = graph.dry_run(GraphState::new()).await;
+
+        assert_eq!(
+            report.gaps,
+            vec![DryRunGap {
+                node_id: NodeId::new("classify"),
+                key: "ticket".to_string(),
+                path: 0,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loop_limit_ends_at_its_exit() {
+        let graph = GraphBuilder::new("retry")
+            .add_node("try", fixed("try", &[], &[], false))
+            .await
+            .unwrap()
+            .add_node("give_up", fixed("give_up", &[], &[], false))
+            .await
+            .unwrap()
+            .add_edge_cyclic("try", "try", 2)
+            .unwrap()
+            .loop_exit("try", "try", "give_up")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let report = graph.dry_run(GraphState::new()).await;
+
+        assert_eq!(
+            report.visit_order(0),
+            ids(&["try", "try", "try", "give_up"])
+        );
+        assert!(!report.truncated);
+    }
+}
@@ -0,0 +1,435 @@
+//! # Execution Events
+//!
+//! Graph runs started with [`ExecutionEngine::execute_stream`] report their
+//! progress as a stream of [`GraphEvent`]s, for example to show which node is
+//! running. Events arrive in causal order: a node starts after the edge that
+//! led to it was taken and finishes before the edges it leads to are taken.
+//! Events of parallel branches carry the branch they belong to; events of
+//! different branches may interleave.
+
+use crate::core::{ExecutionResult, NodeId};
+use crate::diff::StateDiff;
+use crate::execution::{ExecutionEngine, ExecutionResults, Transition};
+use crate::report::NodeUsage;
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use futures::Stream;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Progress of a graph run
+#[derive(Debug, Clone)]
+pub enum GraphEvent {
+    /// The run started
+    RunStarted { run_id: String, graph_id: String },
+    /// A node started running
+    NodeStarted {
+        node_id: NodeId,
+        /// The parallel branch the node runs in, if any
+        branch: Option<NodeId>,
+    },
+    /// A node finished, with its result or error message
+    NodeFinished {
+        node_id: NodeId,
+        branch: Option<NodeId>,
+        duration: Duration,
+        /// How often the node was attempted; more than 1 when it was retried
+        attempts: u32,
+        /// Tokens and cost the node reported
+        usage: Option<NodeUsage>,
+        result: Result<ExecutionResult, String>,
+    },
+    /// A node changed or removed state keys; for a parallel branch the
+    /// changes are merged into the shared state at the join
+    StateUpdated {
+        node_id: NodeId,
+        branch: Option<NodeId>,
+        keys: Vec<String>,
+        /// How the keys changed, with their values as
+        /// [`ExecutionConfig::diff_capture`](crate::ExecutionConfig::diff_capture)
+        /// records them
+        changes: StateDiff,
+    },
+    /// Execution went from one node to another
+    EdgeTaken { from: NodeId, to: NodeId },
+    /// The run finished; the final state is included as configured by
+    /// [`ExecutionConfig::event_state`](crate::ExecutionConfig::event_state)
+    RunFinished {
+        run_id: String,
+        success: bool,
+        final_state: Option<HashMap<String, StateValue>>,
+    },
+    /// The run stopped with an error
+    RunFailed { error: String },
+}
+
+/// How much of the final state a [`GraphEvent::RunFinished`] carries
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EventStatePolicy {
+    /// No state, since states can be large
+    #[default]
+    Omit,
+    /// Only these keys
+    Keys(Vec<String>),
+    /// The whole state
+    Full,
+}
+
+impl EventStatePolicy {
+    fn apply(&self, state: &GraphState) -> Option<HashMap<String, StateValue>> {
+        match self {
+            EventStatePolicy::Omit => None,
+            EventStatePolicy::Keys(keys) => Some(
+                keys.iter()
+                    .filter_map(|key| state.get(key).ok().map(|value| (key.clone(), value)))
+                    .collect(),
+            ),
+            EventStatePolicy::Full => Some(state.snapshot()),
+        }
+    }
+}
+
+/// Numbers a [`GraphEvent::NodeFinished`] carries
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NodeStats {
+    pub(crate) duration: Duration,
+    pub(crate) attempts: u32,
+    pub(crate) usage: Option<NodeUsage>,
+}
+
+/// Sender half of a run's event stream
+#[derive(Debug, Clone)]
+pub(crate) struct EventSink {
+    sender: mpsc::UnboundedSender<GraphEvent>,
+    state_policy: EventStatePolicy,
+}
+
+impl EventSink {
+    /// Send an event; a dropped stream is not an error for the run
+    pub(crate) fn emit(&self, event: GraphEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub(crate) fn node_finished(
+        &self,
+        node_id: &NodeId,
+        branch: Option<&NodeId>,
+        stats: NodeStats,
+        result: &RGraphResult<ExecutionResult>,
+        changes: &StateDiff,
+    ) {
+        self.emit(GraphEvent::NodeFinished {
+            node_id: node_id.clone(),
+            branch: branch.cloned(),
+            duration: stats.duration,
+            attempts: stats.attempts,
+            usage: stats.usage,
+            result: result.as_ref().cloned().map_err(ToString::to_string),
+        });
+        if !changes.is_empty() {
+            self.emit(GraphEvent::StateUpdated {
+                node_id: node_id.clone(),
+                branch: branch.cloned(),
+                keys: changes.keys(),
+                changes: changes.clone(),
+            });
+        }
+    }
+
+    /// Report the edges `transition` takes out of `from`
+    pub(crate) fn edges_taken(&self, from: &NodeId, transition: &Transition) {
+        let targets = match transition {
+            Transition::Edges(targets)
+            | Transition::Parallel {
+                branches: targets, ..
+            } => targets.clone(),
+            Transition::Branch(target)
+            | Transition::Join(target)
+            | Transition::Jump(target)
+            | Transition::LoopExit { exit: target, .. }
+            | Transition::OnError(target) => vec![target.clone()],
+            Transition::Stop
+            | Transition::Interrupted(_)
+            | Transition::End
+            | Transition::Failed => Vec::new(),
+        };
+        for to in targets {
+            self.emit(GraphEvent::EdgeTaken {
+                from: from.clone(),
+                to,
+            });
+        }
+    }
+
+    pub(crate) fn run_finished(&self, run_id: &str, success: bool, state: &GraphState) {
+        self.emit(GraphEvent::RunFinished {
+            run_id: run_id.to_string(),
+            success,
+            final_state: self.state_policy.apply(state),
+        });
+    }
+}
+
+/// A graph run in progress: a stream of its events, then its result
+///
+/// The run continues when the stream is dropped.
+pub struct GraphRun {
+    events: mpsc::UnboundedReceiver<GraphEvent>,
+    cancellation: CancellationToken,
+    result: JoinHandle<RGraphResult<ExecutionResults>>,
+}
+
+impl GraphRun {
+    pub(crate) fn spawn<F>(
+        state_policy: EventStatePolicy,
+        cancellation: CancellationToken,
+        run: F,
+    ) -> Self
+    where
+        F: FnOnce(EventSink) -> futures::future::BoxFuture<'static, RGraphResult<ExecutionResults>>
+            + Send
+            + 'static,
+    {
+        let (sender, events) = mpsc::unbounded_channel();
+        let sink = EventSink {
+            sender,
+            state_policy,
+        };
+        let result = tokio::spawn(async move {
+            let failed = sink.clone();
+            let result = run(sink).await;
+            if let Err(e) = &result {
+                failed.emit(GraphEvent::RunFailed {
+                    error: e.to_string(),
+                });
+            }
+            result
+        });
+
+        Self {
+            events,
+            cancellation,
+            result,
+        }
+    }
+
+    /// Cancel the run; see [`RunHandle::cancel`](crate::execution::RunHandle::cancel)
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    /// Wait for the run to finish and get its results
+    pub async fn result(self) -> RGraphResult<ExecutionResults> {
+        self.result
+            .await
+            .map_err(|e| RGraphError::execution(format!("Graph run task failed: {}", e)))?
+    }
+}
+
+impl Stream for GraphRun {
+    type Item = GraphEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<GraphEvent>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl ExecutionEngine {
+    /// Start executing a workflow graph in the background, streaming its events
+    pub fn execute_stream(
+        &self,
+        graph: &crate::core::WorkflowGraph,
+        state: GraphState,
+    ) -> GraphRun {
+        let engine = self.clone();
+        let graph = graph.clone();
+        let cancellation = CancellationToken::new();
+        let token = cancellation.clone();
+        GraphRun::spawn(
+            self.config().event_state.clone(),
+            cancellation,
+            move |sink| {
+                Box::pin(
+                    async move { engine.execute_with_events(&graph, state, sink, token).await },
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GraphBuilder, WorkflowGraph};
+    use crate::execution::ExecutionConfig;
+    use crate::nodes::test_utils::WriteNode;
+    use futures::StreamExt;
+
+    async fn three_nodes() -> WorkflowGraph {
+        GraphBuilder::new("pipeline")
+            .add_node("fetch", WriteNode::new("fetch", "page").into())
+            .await
+            .unwrap()
+            .add_node("parse", WriteNode::new("parse", "text").into())
+            .await
+            .unwrap()
+            .add_node("store", WriteNode::new("store", "stored").into())
+            .await
+            .unwrap()
+            .add_edge("fetch", "parse")
+            .unwrap()
+            .add_edge("parse", "store")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    // Compact description of an event for asserting sequences
+    fn describe(event: &GraphEvent) -> String {
+        match event {
+            GraphEvent::RunStarted { .. } => "run started".to_string(),
+            GraphEvent::NodeStarted { node_id, .. } => format!("start {}", node_id.as_str()),
+            GraphEvent::NodeFinished {
+                node_id, result, ..
+            } => format!("finish {} ok={}", node_id.as_str(), result.is_ok()),
+            GraphEvent::StateUpdated { node_id, keys, .. } => {
+                format!("update {} {}", node_id.as_str(), keys.join(","))
+            }
+            GraphEvent::EdgeTaken { from, to } => {
+                format!("edge {}->{}", from.as_str(), to.as_str())
+            }
+            GraphEvent::RunFinished { success, .. } => format!("run finished ok={}", success),
+            GraphEvent::RunFailed { error } => format!("run failed {}", error),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_of_a_three_node_graph_are_ordered() {
+        let graph = three_nodes().await;
+        let engine = ExecutionEngine::with_config(ExecutionConfig {
+            event_state: EventStatePolicy::Keys(vec!["stored".to_string()]),
+            ..ExecutionConfig::default()
+        });
+
+        let mut run = engine.execute_stream(&graph, GraphState::new());
+        let mut events = Vec::new();
+        while let Some(event) = run.next().await {
+            events.push(event);
+        }
+        let results = run.result().await.unwrap();
+
+        let described: Vec<_> = events.iter().map(describe).collect();
+        assert_eq!(
+            described,
+            [
+                "run started",
+                "start fetch",
+                "finish fetch ok=true",
+                "update fetch page",
+                "edge fetch->parse",
+                "start parse",
+                "finish parse ok=true",
+                "update parse text",
+                "edge parse->store",
+                "start store",
+                "finish store ok=true",
+                "update store stored",
+                "run finished ok=true",
+            ]
+        );
+
+        match &events[0] {
+            GraphEvent::RunStarted { run_id, graph_id } => {
+                assert_eq!(run_id, &results.run_id);
+                assert_eq!(graph_id, graph.id());
+            }
+            other => panic!("unexpected first event: {other:?}"),
+        }
+        match &events[2] {
+            GraphEvent::NodeFinished { branch, result, .. } => {
+                assert_eq!(branch, &None);
+                assert!(matches!(result, Ok(ExecutionResult::Continue)));
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        match events.last().unwrap() {
+            GraphEvent::RunFinished { final_state, .. } => {
+                let final_state = final_state.as_ref().unwrap();
+                assert_eq!(final_state.len(), 1);
+                assert_eq!(final_state["stored"], StateValue::from("store"));
+            }
+            other => panic!("unexpected last event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_branch_events_are_causally_ordered() {
+        let graph = GraphBuilder::new("fan_out")
+            .add_node("split", WriteNode::new("split", "input").into())
+            .await
+            .unwrap()
+            .add_node(
+                "slow",
+                WriteNode::new("slow", "slow")
+                    .with_delay(Duration::from_millis(50))
+                    .into(),
+            )
+            .await
+            .unwrap()
+            .add_node("fast", WriteNode::new("fast", "fast").into())
+            .await
+            .unwrap()
+            .add_node("join", WriteNode::new("join", "joined").into())
+            .await
+            .unwrap()
+            .add_parallel("split", ["slow", "fast"], "join")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let events: Vec<_> = ExecutionEngine::new()
+            .execute_stream(&graph, GraphState::new())
+            .collect()
+            .await;
+        let described: Vec<_> = events.iter().map(describe).collect();
+        let position = |text: &str| {
+            described
+                .iter()
+                .position(|event| event == text)
+                .unwrap_or_else(|| panic!("missing '{}' in {:?}", text, described))
+        };
+
+        for branch in ["slow", "fast"] {
+            let taken = position(&format!("edge split->{}", branch));
+            let started = position(&format!("start {}", branch));
+            let finished = position(&format!("finish {} ok=true", branch));
+            let joined = position(&format!("edge {}->join", branch));
+            assert!(position("finish split ok=true") < taken);
+            assert!(taken < started && started < finished && finished < joined);
+            assert!(joined < position("start join"));
+        }
+        // The fast branch finishes while the slow one is still running
+        assert!(position("finish fast ok=true") < position("finish slow ok=true"));
+
+        let branch_of = |node: &str| {
+            events.iter().find_map(|event| match event {
+                GraphEvent::NodeStarted { node_id, branch } if node_id.as_str() == node => {
+                    Some(branch.clone())
+                }
+                _ => None,
+            })
+        };
+        assert_eq!(branch_of("slow"), Some(Some(NodeId::new("slow"))));
+        assert_eq!(branch_of("join"), Some(None));
+        assert_eq!(described.last().unwrap(), "run finished ok=true");
+    }
+}
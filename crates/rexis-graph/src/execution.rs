@@ -2,15 +2,43 @@
 //!
 //! A simplified execution engine that avoids complex lifetime issues.
 
-use crate::core::{ExecutionContext, ExecutionResult, NodeId, WorkflowGraph};
-use crate::state::GraphState;
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+use crate::checkpoint::{Checkpoint, Checkpointer, Visit};
+use crate::core::{
+    EdgeCondition, ExecutionContext, ExecutionResult, MergeConflictPolicy, Node, NodeId,
+    NodeRetryPolicy, ParallelBranches, PartialFailure, WorkflowGraph,
+};
+use crate::diff::{DiffCapture, StateDiff};
+use crate::events::{EventSink, EventStatePolicy, GraphEvent, NodeStats};
+use crate::limits::{RunSlots, SlotPermit};
+use crate::report::{NodeUsage, RunReport};
+use crate::routing::values_equal;
+use crate::schema::SchemaMode;
+use crate::state::{GraphState, StateValue};
 use crate::{RGraphError, RGraphResult};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// State key under which a failed node's error is passed to its error handler
+///
+/// The value is an object with the failed `node_id`, the error `message`, the
+/// `error_type` and the number of `attempts` made.
+pub const ERROR_KEY: &str = "__error";
+
+/// State key under which a node reports the tokens and cost it used
+///
+/// The value is an object with `prompt_tokens`, `completion_tokens` and
+/// `cost`, each optional. The engine removes it after the node ran and
+/// records it in the node's [`TraceStep`].
+pub const USAGE_KEY: &str = "__usage";
+
 /// Configuration for the execution engine
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -21,10 +49,15 @@ pub struct ExecutionConfig {
     pub continue_on_error: bool,
     /// Enable verbose logging
     pub verbose_logging: bool,
-    /// Execution timeout in seconds
+    /// Deadline of a whole run in seconds, subgraphs and parallel branches
+    /// included
     pub timeout_seconds: Option<u64>,
     /// Maximum execution depth to prevent infinite loops
     pub max_execution_depth: usize,
+    /// How much of the final state streamed runs report when they finish
+    pub event_state: EventStatePolicy,
+    /// How much of the changed values the state diff of each node records
+    pub diff_capture: DiffCapture,
 }
 
 impl Default for ExecutionConfig {
@@ -35,6 +68,8 @@ impl Default for ExecutionConfig {
             verbose_logging: false,
             timeout_seconds: Some(300), // 5 minutes
             max_execution_depth: 100,
+            event_state: EventStatePolicy::default(),
+            diff_capture: DiffCapture::default(),
         }
     }
 }
@@ -58,12 +93,78 @@ impl Default for ExecutionMode {
 /// Results from graph execution
 #[derive(Debug, Clone)]
 pub struct ExecutionResults {
+    /// ID of the run, shared by the subgraphs it ran; used to resume it
+    pub run_id: String,
     /// The final state after execution
     pub final_state: GraphState,
     /// Execution metrics
     pub metrics: ExecutionMetrics,
     /// Any errors that occurred
     pub errors: Vec<ExecutionError>,
+    /// The nodes that ran, in order, and where execution went after each
+    pub trace: Vec<TraceStep>,
+    /// Set when a node paused the run to wait for input; continue it with
+    /// [`ExecutionEngine::resume_with_input`]
+    pub interrupt_id: Option<String>,
+}
+
+impl ExecutionResults {
+    /// Sum up the run's trace into a report of durations, retries and usage
+    pub fn report(&self) -> RunReport {
+        RunReport::from_results(self)
+    }
+}
+
+/// One executed node in an [`ExecutionResults`] trace
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    /// ID of the node that ran
+    pub node_id: NodeId,
+    /// How many times the node has run in this execution, counting this run
+    pub iteration: usize,
+    /// How long the node and its routing took
+    pub duration: Duration,
+    /// How often the node was attempted; more than 1 when it was retried,
+    /// 0 when the output of an earlier attempt at the run was replayed
+    pub attempts: u32,
+    /// Where execution went next
+    pub transition: Transition,
+    /// Tokens and cost the node reported
+    pub usage: Option<NodeUsage>,
+    /// Steps of the subgraphs the node ran, nested under it
+    pub children: Vec<TraceStep>,
+    /// How the node changed the state; for a parallel branch, its own copy
+    /// of the state
+    pub changes: StateDiff,
+}
+
+/// Where execution went after a node
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Transition {
+    /// Followed the node's plain edges whose conditions held
+    Edges(Vec<NodeId>),
+    /// The node's conditional edge routed to this branch
+    Branch(NodeId),
+    /// The node fanned out to parallel branches, which joined at `join`
+    Parallel { branches: Vec<NodeId>, join: NodeId },
+    /// The parallel branch finished and was merged at this join node
+    Join(NodeId),
+    /// The node itself jumped or routed to this node
+    Jump(NodeId),
+    /// The step to `capped` reached its loop limit, so execution went to the
+    /// loop's `exit` node instead
+    LoopExit { capped: NodeId, exit: NodeId },
+    /// The node stopped execution
+    Stop,
+    /// The node paused the run until input arrives for this interrupt
+    Interrupted(String),
+    /// The node had no edge to follow
+    End,
+    /// The node or its routing failed, and this error handler took over
+    OnError(NodeId),
+    /// The node or its routing failed
+    Failed,
 }
 
 /// Metrics collected during execution
@@ -75,6 +176,15 @@ pub struct ExecutionMetrics {
     pub total_duration: Duration,
     /// Success indicator
     pub success: bool,
+    /// Most nodes that executed at once
+    pub peak_concurrency: usize,
+    /// Most nodes calling an LLM that executed at once
+    pub peak_llm_concurrency: usize,
+    /// Time nodes spent waiting for a slot under the graph's
+    /// [`ExecutionLimits`](crate::ExecutionLimits), summed up
+    pub total_queue_wait: Duration,
+    /// Longest time a node waited for a slot
+    pub max_queue_wait: Duration,
 }
 
 /// Error that occurred during execution
@@ -90,6 +200,63 @@ pub struct ExecutionError {
     pub error_type: String,
 }
 
+/// Position to continue a run from, restored from a checkpoint
+#[cfg_attr(
+    not(all(feature = "rexis-rag-integration", feature = "serde")),
+    allow(dead_code)
+)]
+struct Resume {
+    run_id: String,
+    pending: Vec<NodeId>,
+    execution_path: Vec<NodeId>,
+    edge_traversals: HashMap<(NodeId, NodeId), usize>,
+    nodes_executed: usize,
+    next_seq: u64,
+    /// Node that waited for input, which continues as if it had returned
+    /// [`ExecutionResult::Continue`] rather than running again
+    answered: Option<NodeId>,
+}
+
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+impl Resume {
+    fn from_checkpoint(checkpoint: Checkpoint, answered: Option<NodeId>) -> (GraphState, Self) {
+        let resume = Resume {
+            run_id: checkpoint.run_id,
+            pending: checkpoint.pending,
+            execution_path: checkpoint.execution_path,
+            edge_traversals: checkpoint
+                .edge_traversals
+                .into_iter()
+                .map(|(from, to, count)| ((from, to), count))
+                .collect(),
+            nodes_executed: checkpoint.nodes_executed,
+            next_seq: checkpoint.seq + 1,
+            answered,
+        };
+        (GraphState::with_data(checkpoint.state), resume)
+    }
+}
+
+/// How a run starts, besides its graph and initial state
+#[derive(Default)]
+struct RunOptions<'a> {
+    /// Context of the graph running this one as a subgraph
+    parent: Option<&'a ExecutionContext>,
+    resume: Option<Resume>,
+    events: Option<EventSink>,
+    deadline: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+}
+
+/// What running a node's parallel branches added to the execution
+#[derive(Default)]
+struct ParallelOutcome {
+    steps: Vec<TraceStep>,
+    errors: Vec<ExecutionError>,
+    nodes_executed: usize,
+    joined: bool,
+}
+
 /// Simple execution engine
 #[derive(Debug, Clone)]
 pub struct ExecutionEngine {
@@ -109,12 +276,240 @@ impl ExecutionEngine {
         Self { config }
     }
 
+    /// Get the engine's configuration
+    pub fn config(&self) -> &ExecutionConfig {
+        &self.config
+    }
+
     /// Execute a workflow graph
     pub async fn execute(
+        &self,
+        graph: &WorkflowGraph,
+        state: GraphState,
+    ) -> RGraphResult<ExecutionResults> {
+        self.run(graph, state, RunOptions::default()).await
+    }
+
+    /// Execute a workflow graph that must finish within `deadline`
+    ///
+    /// This overrides [`ExecutionConfig::timeout_seconds`]. A node still running
+    /// when the deadline passes is cancelled, and the run fails with
+    /// [`RGraphError::RunDeadlineExceeded`].
+    pub async fn execute_with_deadline(
+        &self,
+        graph: &WorkflowGraph,
+        state: GraphState,
+        deadline: Duration,
+    ) -> RGraphResult<ExecutionResults> {
+        let options = RunOptions {
+            deadline: Some(deadline),
+            ..Default::default()
+        };
+        self.run(graph, state, options).await
+    }
+
+    /// Execute a workflow graph until it finishes or `cancellation` is
+    /// cancelled
+    ///
+    /// The engine checks the token between nodes and aborts the nodes running
+    /// when it is cancelled, parallel branches and subgraphs included; nodes
+    /// can watch it through [`ExecutionContext::cancellation`] to stop
+    /// cleanly. A cancelled run fails with [`RGraphError::Cancelled`], which
+    /// carries the state after the last node that completed; with a
+    /// checkpointer, the run resumes from its last checkpoint.
+    pub async fn execute_with_cancellation(
+        &self,
+        graph: &WorkflowGraph,
+        state: GraphState,
+        cancellation: CancellationToken,
+    ) -> RGraphResult<ExecutionResults> {
+        let options = RunOptions {
+            cancellation: Some(cancellation),
+            ..Default::default()
+        };
+        self.run(graph, state, options).await
+    }
+
+    /// Start executing a workflow graph in the background, returning a
+    /// handle that cancels the run or waits for its results
+    pub fn spawn(&self, graph: &WorkflowGraph, state: GraphState) -> RunHandle {
+        let engine = self.clone();
+        let graph = graph.clone();
+        let cancellation = CancellationToken::new();
+        let token = cancellation.clone();
+        let result =
+            tokio::spawn(
+                async move { engine.execute_with_cancellation(&graph, state, token).await },
+            );
+        RunHandle {
+            cancellation,
+            result,
+        }
+    }
+
+    /// Execute a workflow graph, reporting its progress to `events`
+    pub(crate) async fn execute_with_events(
+        &self,
+        graph: &WorkflowGraph,
+        state: GraphState,
+        events: EventSink,
+        cancellation: CancellationToken,
+    ) -> RGraphResult<ExecutionResults> {
+        let options = RunOptions {
+            events: Some(events),
+            cancellation: Some(cancellation),
+            ..Default::default()
+        };
+        self.run(graph, state, options).await
+    }
+
+    /// Continue the run `run_id` of `graph` from its latest checkpoint
+    ///
+    /// The state, the nodes still to run and the loop counters are restored;
+    /// nodes that completed before the checkpoint do not run again.
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub async fn resume(
+        &self,
+        graph: &WorkflowGraph,
+        run_id: &str,
+        checkpointer: &Checkpointer,
+    ) -> RGraphResult<ExecutionResults> {
+        let checkpoint = Self::latest_checkpoint(graph, run_id, checkpointer).await?;
+        let (state, resume) = Resume::from_checkpoint(checkpoint, None);
+
+        let options = RunOptions {
+            resume: Some(resume),
+            ..Default::default()
+        };
+        self.run(graph, state, options).await
+    }
+
+    /// Continue a run paused by interrupt `interrupt_id` with `input`
+    ///
+    /// The input must match the interrupt's schema; it is written to the
+    /// interrupt's state key and execution continues along the edges of the
+    /// node that waited for it. Expired interrupts cannot be resumed, and each
+    /// interrupt is resumed at most once.
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    pub async fn resume_with_input(
+        &self,
+        graph: &WorkflowGraph,
+        interrupt_id: &str,
+        input: serde_json::Value,
+        checkpointer: &Checkpointer,
+    ) -> RGraphResult<ExecutionResults> {
+        let interrupt = checkpointer
+            .load_interrupt(graph.id(), interrupt_id)
+            .await?
+            .ok_or_else(|| {
+                RGraphError::execution(format!(
+                    "No pending interrupt '{}' for graph '{}'",
+                    interrupt_id,
+                    graph.id()
+                ))
+            })?;
+        match interrupt.expires_at {
+            Some(expires_at) if interrupt.is_expired() => {
+                return Err(RGraphError::execution(format!(
+                    "Interrupt '{}' expired at {}",
+                    interrupt_id, expires_at
+                )))
+            }
+            _ => {}
+        }
+
+        let violations = rexis_rag::agent::validate_arguments(&interrupt.input_schema, &input);
+        if !violations.is_empty() {
+            return Err(RGraphError::validation(format!(
+                "Input for interrupt '{}' does not match its schema: {}",
+                interrupt_id,
+                violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            )));
+        }
+
+        let checkpoint = Self::latest_checkpoint(graph, &interrupt.run_id, checkpointer).await?;
+        let (state, resume) = Resume::from_checkpoint(checkpoint, Some(interrupt.node_id));
+        state.set(interrupt.input_key, StateValue::from(input));
+        checkpointer
+            .delete_interrupt(graph.id(), interrupt_id)
+            .await?;
+
+        let options = RunOptions {
+            resume: Some(resume),
+            ..Default::default()
+        };
+        self.run(graph, state, options).await
+    }
+
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    async fn latest_checkpoint(
+        graph: &WorkflowGraph,
+        run_id: &str,
+        checkpointer: &Checkpointer,
+    ) -> RGraphResult<Checkpoint> {
+        checkpointer
+            .latest(graph.id(), run_id)
+            .await?
+            .ok_or_else(|| {
+                RGraphError::execution(format!(
+                    "No checkpoint of run '{}' for graph '{}'",
+                    run_id,
+                    graph.id()
+                ))
+            })
+    }
+
+    /// Execute a workflow graph as a subgraph of the graph running in `parent`
+    ///
+    /// The run shares the parent's execution ID, metadata and memory. It fails
+    /// when `graph` already encloses the parent, or when nesting would exceed
+    /// [`ExecutionConfig::max_execution_depth`].
+    pub async fn execute_nested(
+        &self,
+        graph: &WorkflowGraph,
+        state: GraphState,
+        parent: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResults> {
+        if parent.graph_id == graph.id() || parent.parent_graphs.iter().any(|id| id == graph.id()) {
+            return Err(RGraphError::execution(format!(
+                "Graph '{}' cannot run inside itself",
+                graph.name()
+            )));
+        }
+        if parent.depth() >= self.config.max_execution_depth {
+            return Err(RGraphError::execution(format!(
+                "Running graph '{}' would exceed the maximum nesting depth of {}",
+                graph.name(),
+                self.config.max_execution_depth
+            )));
+        }
+
+        let options = RunOptions {
+            parent: Some(parent),
+            ..Default::default()
+        };
+        self.run(graph, state, options).await
+    }
+
+    async fn run(
         &self,
         graph: &WorkflowGraph,
         mut state: GraphState,
+        options: RunOptions<'_>,
     ) -> RGraphResult<ExecutionResults> {
+        let RunOptions {
+            parent,
+            resume,
+            events,
+            deadline,
+            cancellation,
+        } = options;
+        // The graph cannot change under a run
+        let _running = graph.running().await;
         let start_time = Instant::now();
         let mut errors = Vec::new();
         let mut nodes_executed = 0;
@@ -132,31 +527,269 @@ impl ExecutionEngine {
             return Err(RGraphError::execution("No entry points defined for graph"));
         }
 
-        // Execute each entry point
-        for entry_node_id in &entry_points {
-            match self
-                .execute_single_node(graph, &mut state, entry_node_id)
-                .await
-            {
-                Ok(_) => {
-                    nodes_executed += 1;
+        let mut context = match parent {
+            Some(parent) => parent.nested(graph.id().to_string(), entry_points[0].clone()),
+            None => ExecutionContext::new(graph.id().to_string(), entry_points[0].clone()),
+        };
+        // Subgraphs share the cancellation token of their parent
+        if let Some(cancellation) = cancellation {
+            context.cancellation = cancellation;
+        }
+        // The bus of a top-level run closes when the run ends
+        let _bus = parent.is_none().then(|| context.bus.close_on_drop());
+        let mut trace = Vec::new();
+        let mut queue: VecDeque<NodeId> = entry_points.into();
+        let mut seq = 0;
+        let mut answered = None;
+        let mut interrupt_id = None;
+        // Handlers reached through an error edge, whose failures are final
+        let mut recovering = HashSet::new();
+
+        state.set_schema(graph.state_schema());
+        state.set_reducers(graph.reducers());
+        if let (Some(schema), None) = (graph.state_schema(), &resume) {
+            if let Err(e) = schema.check_state(&state) {
+                match schema.mode() {
+                    SchemaMode::Strict => return Err(e),
+                    SchemaMode::Warn => tracing::warn!("Initial state of '{}': {}", graph.id(), e),
                 }
-                Err(e) => {
-                    let error = ExecutionError {
-                        node_id: entry_node_id.as_str().to_string(),
-                        error_message: e.to_string(),
-                        timestamp: chrono::Utc::now(),
-                        error_type: "NodeExecutionError".to_string(),
-                    };
-                    errors.push(error);
+            }
+        }
+        // Replays start from the state a fresh run started from
+        #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+        if let (Some(checkpointer), None, None) = (graph.checkpointer(), parent, &resume) {
+            checkpointer
+                .save_input(graph.id(), &context.execution_id, &state.snapshot())
+                .await?;
+        }
+        if let Some(resume) = resume {
+            context.execution_id = resume.run_id;
+            context.execution_path = resume.execution_path;
+            context.edge_traversals = resume.edge_traversals;
+            queue = resume.pending.into();
+            nodes_executed = resume.nodes_executed;
+            seq = resume.next_seq;
+            if let Some(node_id) = resume.answered {
+                queue.push_front(node_id.clone());
+                answered = Some(node_id);
+            }
+        }
+        #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+        if parent.is_none() {
+            context.checkpointer = graph.checkpointer();
+            context.visits = graph.checkpointer();
+        }
+        #[cfg(feature = "rexis-rag-integration")]
+        if let Some(memory) = graph.memory() {
+            context.run_memory = Some(memory);
+        }
+        if parent.is_none() {
+            context.slots = Some(Arc::new(RunSlots::new(graph.limits())));
+        }
+        // Nodes without a priority of their own take the one of the run
+        let run_priority = context.priority;
+        if parent.is_none() {
+            context.deadline = deadline
+                .or(self.config.timeout_seconds.map(Duration::from_secs))
+                .and_then(RunDeadline::new);
+        }
+        if let Some(events) = events {
+            events.emit(GraphEvent::RunStarted {
+                run_id: context.execution_id.clone(),
+                graph_id: graph.id().to_string(),
+            });
+            context.events = Some(events);
+        }
+
+        // Run nodes from the entry points, following edges until none is left
+        while let Some(node_id) = queue.pop_front() {
+            if nodes_executed >= self.config.max_nodes {
+                errors.push(ExecutionError {
+                    node_id: node_id.as_str().to_string(),
+                    error_message: format!(
+                        "Stopped before running '{}': the limit of {} nodes was reached",
+                        node_id.as_str(),
+                        self.config.max_nodes
+                    ),
+                    timestamp: chrono::Utc::now(),
+                    error_type: "MaxNodesExceeded".to_string(),
+                });
+                break;
+            }
+            if let Some(deadline) = context.deadline.filter(RunDeadline::passed) {
+                return Err(deadline.exceeded());
+            }
+            if context.cancellation.is_cancelled() {
+                return Err(cancelled(&context, state.snapshot()));
+            }
+
+            let step_start = Instant::now();
+            let handles_error = recovering.remove(&node_id);
+            context.current_node = node_id.clone();
+            context.priority = graph.node_priority(&node_id).unwrap_or(run_priority);
+            #[cfg(feature = "rexis-rag-integration")]
+            context.assign_memory(graph);
+            // An answered node already ran up to its interrupt
+            let resumed = answered.as_ref() == Some(&node_id);
+            if resumed {
+                answered = None;
+            } else {
+                context.execution_path.push(node_id.clone());
+            }
+
+            context.bus.node_started(&node_id, None);
+            let before = state.snapshot();
+            if let Some(events) = &context.events {
+                events.emit(GraphEvent::NodeStarted {
+                    node_id: node_id.clone(),
+                    branch: None,
+                });
+            }
+            let (executed, attempts) = if resumed {
+                (Ok(ExecutionResult::Continue), 1)
+            } else {
+                self.execute_single_node(graph, &mut state, &context).await
+            };
+            let usage = if resumed {
+                None
+            } else {
+                take_usage(graph.get_node(&node_id).as_deref(), &state, &executed)
+            };
+            let changes = StateDiff::between(&before, &state.snapshot(), self.config.diff_capture);
+            if let Some(events) = &context.events {
+                let stats = NodeStats {
+                    duration: step_start.elapsed(),
+                    attempts,
+                    usage,
+                };
+                events.node_finished(&node_id, None, stats, &executed, &changes);
+            }
+            let duration = step_start.elapsed();
+            let success = executed.is_ok();
+            context
+                .bus
+                .node_finished(&node_id, None, duration, attempts, success);
+            // A node cancelled while it ran may have written part of its output
+            if context.cancellation.is_cancelled() {
+                return Err(cancelled(&context, before));
+            }
+
+            let outcome = match executed {
+                Ok(result) => {
+                    if !resumed {
+                        nodes_executed += 1;
+                    }
+                    self.next_transition(graph, &state, &node_id, result)
+                        .await
+                        .map_err(|e| (e, "RoutingError"))
+                }
+                Err(e @ RGraphError::RunDeadlineExceeded { .. }) => return Err(e),
+                Err(e @ RGraphError::NodeTimeout { .. }) => Err((e, "NodeTimeout")),
+                Err(e) => Err((e, "NodeExecutionError")),
+            };
 
+            let transition = match outcome {
+                Ok(transition) => {
+                    self.apply_loop_limits(graph, &mut context, &state, &node_id, transition)?
+                }
+                Err((e, error_type)) => match graph.error_handler(&node_id) {
+                    Some(handler) if !handles_error => {
+                        tracing::debug!(
+                            "Node '{}' failed, handing over to '{}': {}",
+                            node_id.as_str(),
+                            handler.as_str(),
+                            e
+                        );
+                        let error = HashMap::from([
+                            ("node_id".to_string(), StateValue::from(node_id.as_str())),
+                            ("message".to_string(), StateValue::from(e.to_string())),
+                            ("error_type".to_string(), StateValue::from(error_type)),
+                            ("attempts".to_string(), StateValue::from(attempts as i64)),
+                        ]);
+                        state.set(ERROR_KEY, StateValue::Object(error));
+                        recovering.insert(handler.clone());
+                        Transition::OnError(handler)
+                    }
+                    _ => {
+                        errors.push(ExecutionError {
+                            node_id: node_id.as_str().to_string(),
+                            error_message: e.to_string(),
+                            timestamp: chrono::Utc::now(),
+                            error_type: error_type.to_string(),
+                        });
+                        Transition::Failed
+                    }
+                },
+            };
+
+            match &transition {
+                Transition::Edges(targets) => queue.extend(targets.iter().cloned()),
+                Transition::Branch(target)
+                | Transition::Jump(target)
+                | Transition::LoopExit { exit: target, .. }
+                | Transition::OnError(target) => queue.push_back(target.clone()),
+                Transition::Stop => queue.clear(),
+                Transition::Interrupted(id) => interrupt_id = Some(id.clone()),
+                Transition::End | Transition::Parallel { .. } | Transition::Join(_) => {}
+                Transition::Failed => {
                     if !self.config.continue_on_error {
-                        break;
+                        queue.clear();
                     }
                 }
             }
 
-            if nodes_executed >= self.config.max_nodes {
+            if let Some(events) = &context.events {
+                events.edges_taken(&node_id, &transition);
+            }
+
+            let iteration = context
+                .execution_path
+                .iter()
+                .filter(|visited| **visited == node_id)
+                .count();
+            let mut completed = transition != Transition::Failed;
+            let parallel = match &transition {
+                Transition::Parallel { .. } => graph.parallel_branches(&node_id),
+                _ => None,
+            };
+            trace.push(TraceStep {
+                node_id,
+                iteration,
+                duration: step_start.elapsed(),
+                attempts,
+                transition,
+                usage,
+                children: context.take_subgraph_trace(),
+                changes,
+            });
+
+            if let Some(parallel) = parallel {
+                let outcome = self
+                    .execute_parallel(graph, &state, &mut context, &parallel)
+                    .await?;
+                if context.cancellation.is_cancelled() {
+                    return Err(cancelled(&context, state.snapshot()));
+                }
+                nodes_executed += outcome.nodes_executed;
+                trace.extend(outcome.steps);
+                errors.extend(outcome.errors);
+
+                if outcome.joined {
+                    queue.push_back(parallel.join);
+                } else {
+                    completed = false;
+                    if !self.config.continue_on_error {
+                        queue.clear();
+                    }
+                }
+            }
+
+            if completed {
+                seq = self
+                    .checkpoint(graph, &context, &state, &queue, nodes_executed, seq)
+                    .await?;
+            }
+            if interrupt_id.is_some() {
                 break;
             }
         }
@@ -181,31 +814,413 @@ impl ExecutionEngine {
             );
         }
 
+        if let Some(events) = &context.events {
+            events.run_finished(&context.execution_id, success, &state);
+        }
+
+        let slots = context.slots.as_deref();
+        let waits = slots.map(RunSlots::waits).unwrap_or_default();
         Ok(ExecutionResults {
+            run_id: context.execution_id,
             final_state: state,
             metrics: ExecutionMetrics {
                 nodes_executed,
                 total_duration,
                 success,
+                peak_concurrency: slots.map_or(0, RunSlots::peak),
+                peak_llm_concurrency: slots.map_or(0, RunSlots::peak_llm),
+                total_queue_wait: waits.total,
+                max_queue_wait: waits.longest,
             },
             errors,
+            trace,
+            interrupt_id,
+        })
+    }
+
+    /// Save checkpoint `seq` when the graph has a checkpointer, returning the
+    /// next sequence number
+    #[cfg_attr(
+        not(all(feature = "rexis-rag-integration", feature = "serde")),
+        allow(unused_variables)
+    )]
+    async fn checkpoint(
+        &self,
+        graph: &WorkflowGraph,
+        context: &ExecutionContext,
+        state: &GraphState,
+        pending: &VecDeque<NodeId>,
+        nodes_executed: usize,
+        seq: u64,
+    ) -> RGraphResult<u64> {
+        #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+        if let Some(checkpointer) = graph.checkpointer() {
+            let checkpoint = Checkpoint {
+                graph_id: graph.id().to_string(),
+                run_id: context.execution_id.clone(),
+                seq,
+                state: state.snapshot(),
+                pending: pending.iter().cloned().collect(),
+                execution_path: context.execution_path.clone(),
+                edge_traversals: context
+                    .edge_traversals
+                    .iter()
+                    .map(|((from, to), count)| (from.clone(), to.clone(), *count))
+                    .collect(),
+                nodes_executed,
+                created_at: chrono::Utc::now(),
+            };
+            checkpointer.save(&checkpoint).await?;
+            return Ok(seq + 1);
+        }
+
+        Ok(seq)
+    }
+
+    /// Run parallel branches concurrently on copies of the state, then merge
+    /// the keys they changed into `state`
+    async fn execute_parallel(
+        &self,
+        graph: &WorkflowGraph,
+        state: &GraphState,
+        context: &mut ExecutionContext,
+        parallel: &ParallelBranches,
+    ) -> RGraphResult<ParallelOutcome> {
+        let mut tasks = Vec::with_capacity(parallel.branches.len());
+        for branch in &parallel.branches {
+            let node = graph.get_node(branch).ok_or_else(|| {
+                RGraphError::execution(format!("Node '{}' not found", branch.as_str()))
+            })?;
+            let mut branch_state = state.fork();
+            branch_state.record_updates();
+            let mut branch_context = context.clone();
+            branch_context.current_node = branch.clone();
+            branch_context.priority = graph.node_priority(branch).unwrap_or(context.priority);
+            #[cfg(feature = "rexis-rag-integration")]
+            branch_context.assign_memory(graph);
+            branch_context.execution_path.push(branch.clone());
+            branch_context.subgraph_trace = Arc::default();
+            #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+            {
+                branch_context.checkpointer = None;
+            }
+            let branch = branch.clone();
+            let policy = graph.retry_policy(&branch);
+            let timeout = graph.node_timeout(&branch);
+            let rerun = graph.forces_rerun(&branch);
+            let capture = self.config.diff_capture;
+
+            tasks.push(tokio::spawn(async move {
+                let start = Instant::now();
+                branch_context.bus.node_started(&branch, Some(&branch));
+                if let Some(events) = &branch_context.events {
+                    events.emit(GraphEvent::NodeStarted {
+                        node_id: branch.clone(),
+                        branch: Some(branch.clone()),
+                    });
+                }
+                let before = branch_state.snapshot();
+                let (result, attempts) = execute_once(
+                    node.as_ref(),
+                    policy.as_ref(),
+                    timeout,
+                    rerun,
+                    &mut branch_state,
+                    &branch_context,
+                )
+                .await;
+                let usage = take_usage(Some(node.as_ref()), &branch_state, &result);
+                let changes = StateDiff::between(&before, &branch_state.snapshot(), capture);
+                if let Some(events) = &branch_context.events {
+                    let stats = NodeStats {
+                        duration: start.elapsed(),
+                        attempts,
+                        usage,
+                    };
+                    events.node_finished(&branch, Some(&branch), stats, &result, &changes);
+                }
+                let (duration, success) = (start.elapsed(), result.is_ok());
+                branch_context.bus.node_finished(
+                    &branch,
+                    Some(&branch),
+                    duration,
+                    attempts,
+                    success,
+                );
+                let children = branch_context.take_subgraph_trace();
+                let result = result.and_then(|result| match result {
+                    ExecutionResult::Interrupted { .. } => Err(RGraphError::node(
+                        branch.as_str(),
+                        "parallel branches cannot wait for input",
+                    )),
+                    _ => Ok(branch_state),
+                });
+                (result, start.elapsed(), attempts, usage, children, changes)
+            }));
+        }
+        let finished = futures::future::join_all(tasks).await;
+
+        let base = state.snapshot();
+        let mut merged: HashMap<String, (&NodeId, StateValue)> = HashMap::new();
+        // Updates to keys with reducers, applied in branch order
+        let mut updates = Vec::new();
+        let mut outcome = ParallelOutcome::default();
+
+        for (branch, finished) in parallel.branches.iter().zip(finished) {
+            context.execution_path.push(branch.clone());
+            let (result, duration, attempts, usage, children, changes) =
+                finished.unwrap_or_else(|e| {
+                    let message = format!("branch task failed: {}", e);
+                    let error = RGraphError::node(branch.as_str(), message);
+                    let changes = StateDiff::default();
+                    (Err(error), Duration::ZERO, 1, None, Vec::new(), changes)
+                });
+
+            let transition = match result {
+                Ok(branch_state) => {
+                    outcome.nodes_executed += 1;
+                    updates.push((branch, branch_state.take_updates()));
+                    for (key, value) in branch_state.snapshot() {
+                        if base.get(&key) == Some(&value) || graph.reducer(&key).is_some() {
+                            continue;
+                        }
+                        if let Some((first, existing)) = merged.get(&key) {
+                            if *existing != value {
+                                match parallel.conflict_policy {
+                                    MergeConflictPolicy::Fail => {
+                                        return Err(RGraphError::MergeConflict {
+                                            key,
+                                            first: first.as_str().to_string(),
+                                            second: branch.as_str().to_string(),
+                                        })
+                                    }
+                                    MergeConflictPolicy::FirstWins => continue,
+                                    MergeConflictPolicy::LastWins => {}
+                                }
+                            }
+                        }
+                        merged.insert(key, (branch, value));
+                    }
+                    Transition::Join(parallel.join.clone())
+                }
+                Err(e @ RGraphError::RunDeadlineExceeded { .. }) => return Err(e),
+                Err(e) => {
+                    outcome.errors.push(ExecutionError {
+                        node_id: branch.as_str().to_string(),
+                        error_message: e.to_string(),
+                        timestamp: chrono::Utc::now(),
+                        error_type: "BranchError".to_string(),
+                    });
+                    Transition::Failed
+                }
+            };
+
+            let iteration = context
+                .execution_path
+                .iter()
+                .filter(|visited| *visited == branch)
+                .count();
+            outcome.steps.push(TraceStep {
+                node_id: branch.clone(),
+                iteration,
+                duration,
+                attempts,
+                transition,
+                usage,
+                children,
+                changes,
+            });
+        }
+
+        outcome.joined =
+            outcome.errors.is_empty() || parallel.failure_policy == PartialFailure::Collect;
+        if outcome.joined {
+            for (key, (_, value)) in merged {
+                state.set(key, value);
+            }
+            for (branch, updates) in updates {
+                for (key, value) in updates {
+                    state.set(key, value);
+                }
+                state.check_writes(branch.as_str())?;
+            }
+            if let Some(events) = &context.events {
+                for step in &outcome.steps {
+                    events.edges_taken(&step.node_id, &step.transition);
+                }
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Work out where execution goes after a node returned `result`
+    async fn next_transition(
+        &self,
+        graph: &WorkflowGraph,
+        state: &GraphState,
+        node_id: &NodeId,
+        result: ExecutionResult,
+    ) -> RGraphResult<Transition> {
+        let target = match result {
+            ExecutionResult::Stop => return Ok(Transition::Stop),
+            ExecutionResult::Interrupted { interrupt_id } => {
+                return Ok(Transition::Interrupted(interrupt_id))
+            }
+            ExecutionResult::JumpTo(target) => target,
+            ExecutionResult::Route(target) => NodeId::new(target),
+            ExecutionResult::Continue => {
+                if let Some(parallel) = graph.parallel_branches(node_id) {
+                    return Ok(Transition::Parallel {
+                        branches: parallel.branches,
+                        join: parallel.join,
+                    });
+                }
+
+                if let Some(router) = graph.router(node_id) {
+                    let target = router.route(state).await?;
+                    Self::check_target(graph, node_id, &target)?;
+                    return Ok(Transition::Branch(target));
+                }
+
+                let targets: Vec<NodeId> = graph
+                    .edges_from(node_id)
+                    .into_iter()
+                    .filter(|edge| Self::edge_applies(edge.condition.as_ref(), state))
+                    .map(|edge| edge.to)
+                    .collect();
+                return Ok(if targets.is_empty() {
+                    Transition::End
+                } else {
+                    Transition::Edges(targets)
+                });
+            }
+        };
+
+        Self::check_target(graph, node_id, &target)?;
+        Ok(Transition::Jump(target))
+    }
+
+    /// Count the steps a transition takes, diverting steps over their loop
+    /// limit to the loop's exit node
+    ///
+    /// A step over its limit without an exit node fails the execution with
+    /// [`RGraphError::LoopLimitExceeded`].
+    fn apply_loop_limits(
+        &self,
+        graph: &WorkflowGraph,
+        context: &mut ExecutionContext,
+        state: &GraphState,
+        from: &NodeId,
+        transition: Transition,
+    ) -> RGraphResult<Transition> {
+        Ok(match transition {
+            Transition::Edges(targets) => Transition::Edges(
+                targets
+                    .into_iter()
+                    .map(|to| Self::traverse(graph, context, state, from, to))
+                    .collect::<RGraphResult<_>>()?,
+            ),
+            Transition::Branch(to) => {
+                let next = Self::traverse(graph, context, state, from, to.clone())?;
+                if next == to {
+                    Transition::Branch(to)
+                } else {
+                    Transition::LoopExit {
+                        capped: to,
+                        exit: next,
+                    }
+                }
+            }
+            Transition::Jump(to) => {
+                let next = Self::traverse(graph, context, state, from, to.clone())?;
+                if next == to {
+                    Transition::Jump(to)
+                } else {
+                    Transition::LoopExit {
+                        capped: to,
+                        exit: next,
+                    }
+                }
+            }
+            other => other,
         })
     }
 
+    /// Count one step from `from` to `to` and return the node it leads to
+    fn traverse(
+        graph: &WorkflowGraph,
+        context: &mut ExecutionContext,
+        state: &GraphState,
+        from: &NodeId,
+        to: NodeId,
+    ) -> RGraphResult<NodeId> {
+        let next = match graph.loop_limit(from, &to) {
+            Some(limit) if context.traversals(from, &to) >= limit.max_traversals => {
+                match limit.exit {
+                    Some(exit) => exit,
+                    None => {
+                        return Err(RGraphError::LoopLimitExceeded {
+                            from: from.as_str().to_string(),
+                            to: to.as_str().to_string(),
+                            max_traversals: limit.max_traversals,
+                            state: state.snapshot(),
+                        })
+                    }
+                }
+            }
+            _ => to,
+        };
+
+        *context
+            .edge_traversals
+            .entry((from.clone(), next.clone()))
+            .or_insert(0) += 1;
+        Ok(next)
+    }
+
+    /// Fail routing from `from` to a node that is not in the graph
+    fn check_target(graph: &WorkflowGraph, from: &NodeId, target: &NodeId) -> RGraphResult<()> {
+        if graph.contains_node(target) {
+            Ok(())
+        } else {
+            Err(RGraphError::routing(format!(
+                "Node '{}' routed to unknown node '{}'",
+                from.as_str(),
+                target.as_str()
+            )))
+        }
+    }
+
+    /// Whether an edge with this condition is followed; serialized condition
+    /// functions cannot be evaluated and are never followed
+    fn edge_applies(condition: Option<&EdgeCondition>, state: &GraphState) -> bool {
+        match condition {
+            None | Some(EdgeCondition::Always) => true,
+            Some(EdgeCondition::StateCondition {
+                key,
+                expected_value,
+            }) => state
+                .get(key)
+                .is_ok_and(|value| values_equal(&value, &StateValue::from(expected_value.clone()))),
+            Some(EdgeCondition::Conditional(_)) => false,
+        }
+    }
+
     /// Execute a single node
-    async fn execute_single_node(
+    pub(crate) async fn execute_single_node(
         &self,
         graph: &WorkflowGraph,
         state: &mut GraphState,
-        node_id: &NodeId,
-    ) -> RGraphResult<()> {
-        // Get the node
-        let node = graph.get_node(node_id).ok_or_else(|| {
-            RGraphError::execution(format!("Node '{}' not found", node_id.as_str()))
-        })?;
+        context: &ExecutionContext,
+    ) -> (RGraphResult<ExecutionResult>, u32) {
+        let node_id = &context.current_node;
 
-        // Create execution context
-        let context = ExecutionContext::new(graph.id().to_string(), node_id.clone());
+        // Get the node
+        let Some(node) = graph.get_node(node_id) else {
+            let error = RGraphError::execution(format!("Node '{}' not found", node_id.as_str()));
+            return (Err(error), 1);
+        };
 
         if self.config.verbose_logging {
             #[cfg(feature = "observability")]
@@ -215,57 +1230,325 @@ impl ExecutionEngine {
         }
 
         // Execute the node
-        match node.execute(state, &context).await {
-            Ok(ExecutionResult::Continue) => {
+        let policy = graph.retry_policy(node_id);
+        let timeout = graph.node_timeout(node_id);
+        let rerun = graph.forces_rerun(node_id);
+        let (result, attempts) = execute_once(
+            node.as_ref(),
+            policy.as_ref(),
+            timeout,
+            rerun,
+            state,
+            context,
+        )
+        .await;
+        match &result {
+            Ok(result) => {
                 if self.config.verbose_logging {
                     #[cfg(feature = "observability")]
-                    tracing::debug!("Node '{}' completed successfully", node_id.as_str());
+                    tracing::debug!("Node '{}' completed: {:?}", node_id.as_str(), result);
                     #[cfg(not(feature = "observability"))]
-                    tracing::debug!("Node '{}' completed successfully", node_id.as_str());
+                    tracing::debug!("Node '{}' completed: {:?}", node_id.as_str(), result);
                 }
-                Ok(())
             }
-            Ok(ExecutionResult::Stop) => {
+            Err(e) => {
                 if self.config.verbose_logging {
                     #[cfg(feature = "observability")]
-                    tracing::info!("Node '{}' requested execution stop", node_id.as_str());
+                    tracing::error!("Node '{}' failed: {}", node_id.as_str(), e);
                     #[cfg(not(feature = "observability"))]
-                    tracing::debug!("Node '{}' requested execution stop", node_id.as_str());
+                    tracing::debug!("Node '{}' failed: {}", node_id.as_str(), e);
                 }
-                Ok(())
             }
-            Ok(ExecutionResult::Route(_next_node)) => {
-                // For now, we'll treat routing as completion
-                // In a more complex implementation, we'd follow the route
-                if self.config.verbose_logging {
-                    #[cfg(feature = "observability")]
-                    tracing::debug!("Node '{}' requested routing", node_id.as_str());
-                    #[cfg(not(feature = "observability"))]
-                    tracing::debug!("Node '{}' requested routing", node_id.as_str());
+        }
+        (result, attempts)
+    }
+}
+
+/// Take the usage a node reported under [`USAGE_KEY`] out of the state, or
+/// ask the node itself when it succeeded
+pub(crate) fn take_usage(
+    node: Option<&dyn Node>,
+    state: &GraphState,
+    result: &RGraphResult<ExecutionResult>,
+) -> Option<NodeUsage> {
+    if state.contains_key(USAGE_KEY) {
+        return state
+            .remove(USAGE_KEY)
+            .and_then(|value| NodeUsage::from_state_value(&value));
+    }
+    match (node, result) {
+        (Some(node), Ok(_)) => node.metrics(state),
+        _ => None,
+    }
+}
+
+/// Point in time by which a run must finish
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RunDeadline {
+    at: Instant,
+    limit: Duration,
+}
+
+impl RunDeadline {
+    /// Deadline `limit` from now, or none when that is out of range
+    fn new(limit: Duration) -> Option<Self> {
+        Instant::now()
+            .checked_add(limit)
+            .map(|at| Self { at, limit })
+    }
+
+    pub(crate) fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    pub(crate) fn passed(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    pub(crate) fn exceeded(&self) -> RGraphError {
+        RGraphError::RunDeadlineExceeded {
+            deadline: self.limit,
+        }
+    }
+}
+
+/// Run a node with [`execute_with_retry`], unless it has side effects and
+/// an earlier attempt at the run recorded this visit of it, in which case the
+/// recorded output is replayed into the state and no attempt is made
+#[cfg_attr(
+    not(all(feature = "rexis-rag-integration", feature = "serde")),
+    allow(unused_variables)
+)]
+async fn execute_once(
+    node: &dyn Node,
+    policy: Option<&NodeRetryPolicy>,
+    timeout: Option<Duration>,
+    rerun: bool,
+    state: &mut GraphState,
+    context: &ExecutionContext,
+) -> (RGraphResult<ExecutionResult>, u32) {
+    #[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+    if let Some(visits) = context.visits.as_ref().filter(|_| node.is_side_effecting()) {
+        let key = context.idempotency_key();
+        if !rerun {
+            match visits.load_visit(&context.graph_id, &key).await {
+                Ok(Some(visit)) => {
+                    tracing::debug!("Replaying visit '{}' instead of running it again", key);
+                    visit.replay(state);
+                    return (Ok(visit.result), 0);
                 }
-                Ok(())
+                Ok(None) => {}
+                Err(e) => return (Err(e), 0),
             }
-            Ok(ExecutionResult::JumpTo(_target_node)) => {
-                // For now, we'll treat jump as completion
-                // In a more complex implementation, we'd jump to the target
-                if self.config.verbose_logging {
-                    #[cfg(feature = "observability")]
-                    tracing::debug!("Node '{}' requested jump", node_id.as_str());
-                    #[cfg(not(feature = "observability"))]
-                    tracing::debug!("Node '{}' requested jump", node_id.as_str());
+        }
+
+        let before = state.snapshot();
+        let (result, attempts) = execute_with_retry(node, policy, timeout, state, context).await;
+        match &result {
+            // Runs waiting for input continue from their interrupt instead
+            Ok(ExecutionResult::Interrupted { .. }) | Err(_) => {}
+            Ok(executed) => {
+                let node_id = context.current_node.clone();
+                let visit = Visit::new(key, node_id, &before, state, executed.clone());
+                if let Err(e) = visits.save_visit(&context.graph_id, &visit).await {
+                    tracing::warn!(
+                        "Could not record visit '{}'; it runs again if the run does: {}",
+                        visit.idempotency_key,
+                        e
+                    );
                 }
-                Ok(())
             }
-            Err(e) => {
-                if self.config.verbose_logging {
-                    #[cfg(feature = "observability")]
-                    tracing::error!("Node '{}' failed: {}", node_id.as_str(), e);
-                    #[cfg(not(feature = "observability"))]
-                    tracing::debug!("Node '{}' failed: {}", node_id.as_str(), e);
-                }
-                Err(e)
+        }
+        return (result, attempts);
+    }
+
+    execute_with_retry(node, policy, timeout, state, context).await
+}
+
+/// Run a node, retrying it per `policy` on the state from before its first
+/// attempt; returns the result and the number of attempts made
+///
+/// Each attempt is bounded by `timeout` and by the run deadline, which also
+/// ends any further retries.
+async fn execute_with_retry(
+    node: &dyn Node,
+    policy: Option<&NodeRetryPolicy>,
+    timeout: Option<Duration>,
+    state: &mut GraphState,
+    context: &ExecutionContext,
+) -> (RGraphResult<ExecutionResult>, u32) {
+    let Some(policy) = policy else {
+        return (execute_attempt(node, timeout, state, context).await, 1);
+    };
+
+    let before = state.fork();
+    let mut attempt = 1;
+    loop {
+        let error = match execute_attempt(node, timeout, state, context).await {
+            Ok(result) => return (Ok(result), attempt),
+            Err(e) => e,
+        };
+        if matches!(
+            error,
+            RGraphError::RunDeadlineExceeded { .. } | RGraphError::Cancelled { .. }
+        ) {
+            return (Err(error), attempt);
+        }
+        if !policy.should_retry(attempt, &error) {
+            let message = format!(
+                "failed after {} attempt{}: {}",
+                attempt,
+                if attempt == 1 { "" } else { "s" },
+                error
+            );
+            return (Err(RGraphError::node(node.id().as_str(), message)), attempt);
+        }
+
+        tracing::debug!(
+            "Node '{}' failed on attempt {} of {}, retrying: {}",
+            node.id().as_str(),
+            attempt,
+            policy.max_attempts,
+            error
+        );
+        *state = before.fork();
+        let delay = policy.backoff.delay(attempt);
+        let delay = context
+            .deadline
+            .map_or(delay, |deadline| delay.min(deadline.remaining()));
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = context.cancellation.cancelled() => {
+                return (Err(cancelled(context, HashMap::new())), attempt);
+            }
+        }
+        attempt += 1;
+    }
+}
+
+/// Run one attempt of a node, cancelling it after `timeout`, when the run
+/// deadline passes or when the run is cancelled, whichever comes first
+async fn execute_attempt(
+    node: &dyn Node,
+    timeout: Option<Duration>,
+    state: &mut GraphState,
+    context: &ExecutionContext,
+) -> RGraphResult<ExecutionResult> {
+    let _slot = acquire_slot(node, context).await?;
+    if let Some(deadline) = context.deadline.filter(RunDeadline::passed) {
+        return Err(deadline.exceeded());
+    }
+    // The deadline is kept when it comes before the node's own timeout
+    let limit = match (timeout, context.deadline) {
+        (Some(timeout), Some(deadline)) if deadline.remaining() < timeout => {
+            Some((deadline.remaining(), Some(deadline)))
+        }
+        (Some(timeout), _) => Some((timeout, None)),
+        (None, Some(deadline)) => Some((deadline.remaining(), Some(deadline))),
+        (None, None) => None,
+    };
+
+    let start = Instant::now();
+    let execution = async {
+        let Some((limit, deadline)) = limit else {
+            return node.execute(state, context).await;
+        };
+        match tokio::time::timeout(limit, node.execute(state, context)).await {
+            Ok(result) => result,
+            Err(_) => Err(match deadline {
+                Some(deadline) => deadline.exceeded(),
+                None => RGraphError::NodeTimeout {
+                    node_id: node.id().as_str().to_string(),
+                    elapsed: start.elapsed(),
+                },
+            }),
+        }
+    };
+    // Cancelling the run drops the node's future, which aborts it
+    let result = tokio::select! {
+        result = execution => result,
+        _ = context.cancellation.cancelled() => Err(cancelled(context, HashMap::new())),
+    };
+    checked_writes(node, state, result)
+}
+
+/// Wait for the slots `node` takes under the run's limits, unless the run is
+/// cancelled or its deadline passes first
+pub(crate) async fn acquire_slot(
+    node: &dyn Node,
+    context: &ExecutionContext,
+) -> RGraphResult<Option<SlotPermit>> {
+    let Some(slots) = context.slots.as_ref().filter(|_| node.occupies_slot()) else {
+        return Ok(None);
+    };
+    let deadline = context.deadline;
+    let expired = async move {
+        match deadline {
+            Some(deadline) => {
+                tokio::time::sleep(deadline.remaining()).await;
+                deadline.exceeded()
             }
+            None => std::future::pending().await,
         }
+    };
+
+    tokio::select! {
+        permit = slots.acquire(node.calls_llm(), context.priority) => Ok(Some(permit)),
+        _ = context.cancellation.cancelled() => Err(cancelled(context, HashMap::new())),
+        error = expired => Err(error),
+    }
+}
+
+/// Error of a run cancelled after its last completed node left `state`
+fn cancelled(context: &ExecutionContext, state: HashMap<String, StateValue>) -> RGraphError {
+    RGraphError::Cancelled {
+        run_id: context.execution_id.clone(),
+        state,
+    }
+}
+
+/// Fail a node that wrote to the state against its strict schema
+fn checked_writes(
+    node: &dyn Node,
+    state: &GraphState,
+    result: RGraphResult<ExecutionResult>,
+) -> RGraphResult<ExecutionResult> {
+    let writes = state.check_writes(node.id().as_str());
+    let result = result?;
+    writes.map(|_| result)
+}
+
+/// A graph run in the background; see [`ExecutionEngine::spawn`]
+///
+/// The run continues when the handle is dropped.
+#[derive(Debug)]
+pub struct RunHandle {
+    cancellation: CancellationToken,
+    result: JoinHandle<RGraphResult<ExecutionResults>>,
+}
+
+impl RunHandle {
+    /// Cancel the run; unless it already finished, it fails with
+    /// [`RGraphError::Cancelled`]
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    /// Get the token that cancels the run, to cancel it from elsewhere
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Check whether the run has finished
+    pub fn is_finished(&self) -> bool {
+        self.result.is_finished()
+    }
+
+    /// Wait for the run to finish and get its results
+    pub async fn result(self) -> RGraphResult<ExecutionResults> {
+        self.result
+            .await
+            .map_err(|e| RGraphError::execution(format!("Graph run task failed: {}", e)))?
     }
 }
 
@@ -274,3 +1557,1121 @@ impl Default for ExecutionEngine {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Backoff, GraphBuilder, Node};
+    use crate::routing::when;
+    use async_trait::async_trait;
+
+    // Node appending its ID to the "visited" list, then running its step
+    struct RecordingNode {
+        id: NodeId,
+        step: fn(&GraphState),
+    }
+
+    impl RecordingNode {
+        fn new(id: &str) -> Arc<Self> {
+            Self::with_step(id, |_| {})
+        }
+
+        fn with_step(id: &str, step: fn(&GraphState)) -> Arc<Self> {
+            Arc::new(Self {
+                id: NodeId::new(id),
+                step,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Node for RecordingNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let mut visited = match state.get("visited") {
+                Ok(StateValue::Array(visited)) => visited,
+                _ => Vec::new(),
+            };
+            visited.push(StateValue::String(self.id.0.clone()));
+            state.set("visited", visited);
+            (self.step)(state);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    // Node that waits, then writes its ID to each of its keys or fails
+    struct BranchNode {
+        id: NodeId,
+        keys: Vec<&'static str>,
+        declared: bool,
+        fail: bool,
+    }
+
+    impl BranchNode {
+        fn new(id: &str, keys: &[&'static str], declared: bool) -> Self {
+            Self {
+                id: NodeId::new(id),
+                keys: keys.to_vec(),
+                declared,
+                fail: false,
+            }
+        }
+
+        fn failing(mut self) -> Self {
+            self.fail = true;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl Node for BranchNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if self.fail {
+                return Err(RGraphError::node(self.id.as_str(), "analysis failed"));
+            }
+            for key in &self.keys {
+                state.set(*key, self.id.as_str());
+            }
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            if self.declared {
+                self.keys.clone()
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    async fn fan_out(branches: Vec<BranchNode>, parallel: ParallelBranches) -> GraphBuilder {
+        let mut builder = GraphBuilder::new("fan_out")
+            .add_node("split", RecordingNode::new("split"))
+            .await
+            .unwrap()
+            .add_node("merge", RecordingNode::new("merge"))
+            .await
+            .unwrap();
+        for branch in branches {
+            let id = branch.id.clone();
+            builder = builder.add_node(id, Arc::new(branch)).await.unwrap();
+        }
+        builder.add_parallel_branches("split", parallel).unwrap()
+    }
+
+    fn visited(results: &ExecutionResults) -> Vec<String> {
+        match results.final_state.get("visited").unwrap() {
+            StateValue::Array(visited) => visited
+                .into_iter()
+                .map(|id| id.as_string().unwrap().to_string())
+                .collect(),
+            other => panic!("unexpected visited value: {other:?}"),
+        }
+    }
+
+    async fn score_graph() -> WorkflowGraph {
+        GraphBuilder::new("scoring")
+            .add_node("grade", RecordingNode::new("grade"))
+            .await
+            .unwrap()
+            .add_node("accept", RecordingNode::new("accept"))
+            .await
+            .unwrap()
+            .add_node("reject", RecordingNode::new("reject"))
+            .await
+            .unwrap()
+            .add_node("notify", RecordingNode::new("notify"))
+            .await
+            .unwrap()
+            .add_conditional_edge(
+                "grade",
+                |state: &GraphState| -> RGraphResult<&'static str> {
+                    let score = state.get("score")?.as_float().unwrap_or_default();
+                    Ok(if score > 0.8 { "accept" } else { "reject" })
+                },
+            )
+            .unwrap()
+            .add_edge("accept", "notify")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_routing_function_picks_each_branch() {
+        let graph = score_graph().await;
+        let engine = ExecutionEngine::new();
+
+        let high = engine
+            .execute(&graph, GraphState::new().with_input("score", 0.9))
+            .await
+            .unwrap();
+        assert!(high.errors.is_empty());
+        assert_eq!(visited(&high), ["grade", "accept", "notify"]);
+        assert_eq!(
+            high.trace[0].transition,
+            Transition::Branch(NodeId::new("accept"))
+        );
+        assert_eq!(
+            high.trace[1].transition,
+            Transition::Edges(vec![NodeId::new("notify")])
+        );
+        assert_eq!(high.trace[2].transition, Transition::End);
+
+        let low = engine
+            .execute(&graph, GraphState::new().with_input("score", 0.3))
+            .await
+            .unwrap();
+        assert_eq!(visited(&low), ["grade", "reject"]);
+        assert_eq!(low.metrics.nodes_executed, 2);
+        assert_eq!(
+            low.trace[0].transition,
+            Transition::Branch(NodeId::new("reject"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_declarative_router_picks_each_branch() {
+        let graph = GraphBuilder::new("review")
+            .add_node("review", RecordingNode::new("review"))
+            .await
+            .unwrap()
+            .add_node("publish", RecordingNode::new("publish"))
+            .await
+            .unwrap()
+            .add_node("revise", RecordingNode::new("revise"))
+            .await
+            .unwrap()
+            .add_conditional_edge(
+                "review",
+                when("approved")
+                    .equals(true)
+                    .goto("publish")
+                    .otherwise("revise"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let engine = ExecutionEngine::new();
+
+        let approved = engine
+            .execute(&graph, GraphState::new().with_input("approved", true))
+            .await
+            .unwrap();
+        assert_eq!(visited(&approved), ["review", "publish"]);
+
+        let rejected = engine
+            .execute(&graph, GraphState::new().with_input("approved", false))
+            .await
+            .unwrap();
+        assert_eq!(visited(&rejected), ["review", "revise"]);
+        assert_eq!(
+            rejected.trace[0].transition,
+            Transition::Branch(NodeId::new("revise"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routing_failure_is_recorded() {
+        let graph = score_graph().await;
+
+        // No score in the state, so the router fails
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert_eq!(results.errors[0].error_type, "RoutingError");
+        assert_eq!(results.trace.len(), 1);
+        assert_eq!(results.trace[0].transition, Transition::Failed);
+    }
+
+    // Drafts until the critique approves attempt number "approve_at"
+    async fn critique_loop() -> GraphBuilder {
+        let draft = RecordingNode::with_step("draft", |state| {
+            let attempts = state.get("attempts").map_or(0, |v| v.as_integer().unwrap());
+            state.set("attempts", attempts + 1);
+        });
+        let critique = RecordingNode::with_step("critique", |state| {
+            let attempts = state.get("attempts").unwrap().as_integer().unwrap();
+            let approve_at = state.get("approve_at").unwrap().as_integer().unwrap();
+            state.set("approved", attempts >= approve_at);
+        });
+
+        GraphBuilder::new("self_correcting")
+            .add_node("draft", draft)
+            .await
+            .unwrap()
+            .add_node("critique", critique)
+            .await
+            .unwrap()
+            .add_node("done", RecordingNode::new("done"))
+            .await
+            .unwrap()
+            .add_node("give_up", RecordingNode::new("give_up"))
+            .await
+            .unwrap()
+            .add_edge("draft", "critique")
+            .unwrap()
+            .add_conditional_edge(
+                "critique",
+                when("approved")
+                    .equals(true)
+                    .goto("done")
+                    .otherwise("draft"),
+            )
+            .unwrap()
+            .limit_loop("critique", "draft", 2)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_loop_exits_on_state_before_the_limit() {
+        let graph = critique_loop().await.build().unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("approve_at", 2))
+            .await
+            .unwrap();
+
+        assert!(results.errors.is_empty());
+        assert_eq!(
+            visited(&results),
+            ["draft", "critique", "draft", "critique", "done"]
+        );
+        let iterations: Vec<_> = results.trace.iter().map(|step| step.iteration).collect();
+        assert_eq!(iterations, [1, 1, 2, 2, 1]);
+        assert_eq!(
+            results.trace[3].transition,
+            Transition::Branch(NodeId::new("done"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loop_limit_fails_with_the_state() {
+        let graph = critique_loop().await.build().unwrap();
+
+        let err = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("approve_at", 10))
+            .await
+            .unwrap_err();
+
+        match err {
+            RGraphError::LoopLimitExceeded {
+                from,
+                to,
+                max_traversals,
+                state,
+            } => {
+                assert_eq!((from.as_str(), to.as_str()), ("critique", "draft"));
+                assert_eq!(max_traversals, 2);
+                assert_eq!(state["attempts"], StateValue::Integer(3));
+            }
+            other => panic!("expected a loop limit error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loop_limit_follows_the_exit() {
+        let graph = critique_loop()
+            .await
+            .loop_exit("critique", "draft", "give_up")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("approve_at", 10))
+            .await
+            .unwrap();
+
+        assert_eq!(visited(&results).last().unwrap(), "give_up");
+        assert_eq!(results.metrics.nodes_executed, 7);
+        assert_eq!(
+            results.trace[5].transition,
+            Transition::LoopExit {
+                capped: NodeId::new("draft"),
+                exit: NodeId::new("give_up"),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_branches_overlap_in_time() {
+        let branches = vec![
+            BranchNode::new("sentiment", &["sentiment"], true),
+            BranchNode::new("entities", &["entities"], true),
+            BranchNode::new("topics", &["topics"], true),
+        ];
+        let graph = fan_out(
+            branches,
+            ParallelBranches::new(["sentiment", "entities", "topics"], "merge"),
+        )
+        .await
+        .build()
+        .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        // Three 100ms branches in sequence would take at least 300ms
+        assert!(results.metrics.total_duration < Duration::from_millis(250));
+        assert!(results.errors.is_empty());
+        assert_eq!(results.metrics.nodes_executed, 5);
+        assert_eq!(visited(&results), ["split", "merge"]);
+        for key in ["sentiment", "entities", "topics"] {
+            assert_eq!(results.final_state.get(key).unwrap().as_string(), Some(key));
+        }
+
+        let transitions: Vec<_> = results.trace.iter().map(|step| &step.transition).collect();
+        assert!(
+            matches!(transitions[0], Transition::Parallel { branches, .. } if branches.len() == 3)
+        );
+        assert_eq!(transitions[1], &Transition::Join(NodeId::new("merge")));
+        assert_eq!(transitions[4], &Transition::End);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_merge_conflicts_are_detected() {
+        let branches = || {
+            vec![
+                BranchNode::new("draft_a", &["summary"], false),
+                BranchNode::new("draft_b", &["summary"], false),
+            ]
+        };
+        let parallel = ParallelBranches::new(["draft_a", "draft_b"], "merge");
+
+        let graph = fan_out(branches(), parallel.clone()).await.build().unwrap();
+        let err = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap_err();
+        match err {
+            RGraphError::MergeConflict { key, first, second } => {
+                assert_eq!((key.as_str(), first.as_str()), ("summary", "draft_a"));
+                assert_eq!(second, "draft_b");
+            }
+            other => panic!("expected a merge conflict, got {other:?}"),
+        }
+
+        let last_wins = parallel.with_conflict_policy(MergeConflictPolicy::LastWins);
+        let graph = fan_out(branches(), last_wins).await.build().unwrap();
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            results.final_state.get("summary").unwrap().as_string(),
+            Some("draft_b")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_output_keys_fail_build() {
+        let branches = vec![
+            BranchNode::new("draft_a", &["summary"], true),
+            BranchNode::new("draft_b", &["summary"], true),
+        ];
+        let err = fan_out(
+            branches,
+            ParallelBranches::new(["draft_a", "draft_b"], "merge"),
+        )
+        .await
+        .build()
+        .err()
+        .unwrap();
+
+        match err {
+            RGraphError::Validation { message } => {
+                assert!(message.contains("'draft_a' and 'draft_b' both write 'summary'"))
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_branch_failures_follow_the_failure_policy() {
+        let branches = || {
+            vec![
+                BranchNode::new("sentiment", &["sentiment"], true),
+                BranchNode::new("entities", &["entities"], true).failing(),
+            ]
+        };
+        let parallel = ParallelBranches::new(["sentiment", "entities"], "merge");
+
+        let graph = fan_out(branches(), parallel.clone()).await.build().unwrap();
+        let failed = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        assert!(!failed.metrics.success);
+        assert_eq!(visited(&failed), ["split"]);
+        assert_eq!(failed.errors[0].node_id, "entities");
+        assert_eq!(failed.errors[0].error_type, "BranchError");
+        assert!(!failed.final_state.contains_key("sentiment"));
+
+        let collect = parallel.with_failure_policy(PartialFailure::Collect);
+        let graph = fan_out(branches(), collect).await.build().unwrap();
+        let collected = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        assert_eq!(visited(&collected), ["split", "merge"]);
+        assert_eq!(collected.errors.len(), 1);
+        assert!(collected.final_state.contains_key("sentiment"));
+    }
+
+    // Node failing its first `failures` attempts, writing its progress first
+    struct FlakyNode {
+        id: NodeId,
+        failures: u32,
+        attempts: std::sync::atomic::AtomicU32,
+        error: &'static str,
+    }
+
+    impl FlakyNode {
+        fn new(id: &str, failures: u32) -> Self {
+            Self {
+                id: NodeId::new(id),
+                failures,
+                attempts: std::sync::atomic::AtomicU32::new(0),
+                error: "connection reset",
+            }
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Node for FlakyNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let attempt = self
+                .attempts
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+                + 1;
+            // A failed attempt must not leak this write into the next one
+            assert!(!state.contains_key("partial"));
+            state.set("partial", attempt as i64);
+            if attempt <= self.failures {
+                return Err(RGraphError::node(self.id.as_str(), self.error));
+            }
+            state.set("fetched", attempt as i64);
+            state.remove("partial");
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    async fn retry_graph(fetch: Arc<FlakyNode>, policy: NodeRetryPolicy) -> WorkflowGraph {
+        GraphBuilder::new("retry")
+            .add_node_with_policy("fetch", fetch, policy)
+            .await
+            .unwrap()
+            .add_node("store", RecordingNode::new("store"))
+            .await
+            .unwrap()
+            .add_edge("fetch", "store")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retried_node_succeeds_on_a_clean_state() {
+        let fetch = Arc::new(FlakyNode::new("fetch", 2));
+        let policy = NodeRetryPolicy::new(3).with_backoff(Backoff::Fixed(Duration::from_millis(1)));
+        let graph = retry_graph(fetch.clone(), policy).await;
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        assert_eq!(fetch.attempts(), 3);
+        let state = &results.final_state;
+        assert_eq!(state.get("fetched").unwrap().as_integer(), Some(3));
+        assert!(!state.contains_key("partial"));
+        let attempts: Vec<_> = results.trace.iter().map(|step| step.attempts).collect();
+        assert_eq!(attempts, [3, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_report_the_last_error() {
+        let fetch = Arc::new(FlakyNode::new("fetch", 5));
+        let graph = retry_graph(fetch.clone(), NodeRetryPolicy::new(2)).await;
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert_eq!(fetch.attempts(), 2);
+        assert_eq!(results.errors[0].node_id, "fetch");
+        let message = &results.errors[0].error_message;
+        assert!(message.contains("'fetch'"));
+        assert!(message.contains("failed after 2 attempts"));
+        assert!(message.contains("connection reset"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_predicate_stops_retries() {
+        let fetch = Arc::new(FlakyNode {
+            error: "invalid credentials",
+            ..FlakyNode::new("fetch", 1)
+        });
+        let policy =
+            NodeRetryPolicy::new(3).with_retry_if(|error| error.to_string().contains("connection"));
+        let graph = retry_graph(fetch.clone(), policy).await;
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert_eq!(fetch.attempts(), 1);
+        assert!(results.errors[0]
+            .error_message
+            .contains("failed after 1 attempt:"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_needs_an_attempt() {
+        let fetch = Arc::new(FlakyNode::new("fetch", 0));
+        let result = GraphBuilder::new("retry")
+            .add_node_with_policy("fetch", fetch, NodeRetryPolicy::new(0))
+            .await;
+
+        assert!(matches!(result, Err(RGraphError::Validation { .. })));
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let backoff = Backoff::Exponential {
+            initial: Duration::from_millis(100),
+            max: Duration::from_millis(300),
+        };
+
+        let delays: Vec<_> = (1..=4).map(|attempt| backoff.delay(attempt)).collect();
+        assert_eq!(
+            delays,
+            [100, 200, 300, 300].map(Duration::from_millis).to_vec()
+        );
+    }
+
+    // Node sleeping for the next of its naps before writing "done"; the last
+    // nap repeats
+    struct SleepNode {
+        id: NodeId,
+        naps: Vec<u64>,
+        attempts: std::sync::atomic::AtomicU32,
+    }
+
+    impl SleepNode {
+        fn new(id: &str, naps: &[u64]) -> Arc<Self> {
+            Arc::new(Self {
+                id: NodeId::new(id),
+                naps: naps.to_vec(),
+                attempts: std::sync::atomic::AtomicU32::new(0),
+            })
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Node for SleepNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let attempt =
+                self.attempts
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst) as usize;
+            let nap = self.naps[attempt.min(self.naps.len() - 1)];
+            tokio::time::sleep(Duration::from_millis(nap)).await;
+            state.set("done", self.id.as_str());
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout_cancels_the_node() {
+        let slow = SleepNode::new("slow", &[500]);
+        let graph = GraphBuilder::new("timeouts")
+            .add_node("slow", slow)
+            .await
+            .unwrap()
+            .add_node("after", RecordingNode::new("after"))
+            .await
+            .unwrap()
+            .add_edge("slow", "after")
+            .unwrap()
+            .node_timeout("slow", Duration::from_millis(50))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert!(results.metrics.total_duration < Duration::from_millis(400));
+        assert!(!results.final_state.contains_key("done"));
+        assert!(!results.final_state.contains_key("visited"));
+        assert_eq!(results.errors[0].error_type, "NodeTimeout");
+        assert!(results.errors[0].error_message.contains("'slow' timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout_is_a_typed_error() {
+        let slow = SleepNode::new("slow", &[500]);
+        let context = ExecutionContext::new("timeouts".to_string(), NodeId::new("slow"));
+        let timeout = Duration::from_millis(50);
+
+        let (result, attempts) = execute_with_retry(
+            slow.as_ref(),
+            None,
+            Some(timeout),
+            &mut GraphState::new(),
+            &context,
+        )
+        .await;
+
+        assert_eq!(attempts, 1);
+        match result {
+            Err(RGraphError::NodeTimeout { node_id, elapsed }) => {
+                assert_eq!(node_id, "slow");
+                assert!(elapsed >= timeout && elapsed < Duration::from_millis(400));
+            }
+            other => panic!("expected a node timeout, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_each_retry_gets_the_node_timeout() {
+        // Too slow at first, then well within the timeout
+        let fetch = SleepNode::new("fetch", &[500, 10]);
+        let graph = GraphBuilder::new("timeouts")
+            .add_node_with_policy("fetch", fetch.clone(), NodeRetryPolicy::new(3))
+            .await
+            .unwrap()
+            .node_timeout("fetch", Duration::from_millis(50))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        assert_eq!(fetch.attempts(), 2);
+        assert_eq!(results.trace[0].attempts, 2);
+        assert_eq!(
+            results.final_state.get("done").unwrap().as_string(),
+            Some("fetch")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_deadline_spans_nodes() {
+        // Each node is within its own timeout, but not all fit the deadline
+        let graph = GraphBuilder::new("deadline")
+            .add_node("first", SleepNode::new("first", &[60]))
+            .await
+            .unwrap()
+            .add_node("second", SleepNode::new("second", &[60]))
+            .await
+            .unwrap()
+            .add_node("third", SleepNode::new("third", &[60]))
+            .await
+            .unwrap()
+            .add_edge("first", "second")
+            .unwrap()
+            .add_edge("second", "third")
+            .unwrap()
+            .node_timeout("second", Duration::from_secs(5))
+            .unwrap()
+            .build()
+            .unwrap();
+        let deadline = Duration::from_millis(100);
+
+        let start = Instant::now();
+        let err = ExecutionEngine::new()
+            .execute_with_deadline(&graph, GraphState::new(), deadline)
+            .await
+            .unwrap_err();
+
+        assert!(start.elapsed() < Duration::from_millis(150));
+        assert!(matches!(err, RGraphError::RunDeadlineExceeded { deadline: d } if d == deadline));
+    }
+
+    #[tokio::test]
+    async fn test_run_deadline_caps_retries() {
+        let fetch = SleepNode::new("fetch", &[500]);
+        let policy =
+            NodeRetryPolicy::new(10).with_backoff(Backoff::Fixed(Duration::from_millis(20)));
+        let graph = GraphBuilder::new("deadline")
+            .add_node_with_policy("fetch", fetch.clone(), policy)
+            .await
+            .unwrap()
+            .node_timeout("fetch", Duration::from_millis(40))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let err = ExecutionEngine::new()
+            .execute_with_deadline(&graph, GraphState::new(), Duration::from_millis(150))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RGraphError::RunDeadlineExceeded { .. }));
+        assert!((2..10).contains(&fetch.attempts()));
+    }
+
+    #[tokio::test]
+    async fn test_run_deadline_cancels_parallel_branches() {
+        let branches = vec![
+            BranchNode::new("sentiment", &["sentiment"], true),
+            BranchNode::new("entities", &["entities"], true),
+        ];
+        let graph = fan_out(
+            branches,
+            ParallelBranches::new(["sentiment", "entities"], "merge"),
+        )
+        .await
+        .build()
+        .unwrap();
+
+        // The branches take 100ms each
+        let start = Instant::now();
+        let err = ExecutionEngine::new()
+            .execute_with_deadline(&graph, GraphState::new(), Duration::from_millis(30))
+            .await
+            .unwrap_err();
+
+        assert!(start.elapsed() < Duration::from_millis(90));
+        assert!(matches!(err, RGraphError::RunDeadlineExceeded { .. }));
+    }
+
+    // fetch -> respond, with fetch failing until "fallback" takes over
+    async fn fallback_graph() -> GraphBuilder {
+        let fallback = RecordingNode::with_step("fallback", |state| {
+            state.set("answer", "cached answer");
+        });
+        let respond = RecordingNode::with_step("respond", |state| {
+            if !state.contains_key("answer") {
+                state.set("answer", "fresh answer");
+            }
+        });
+        GraphBuilder::new("fallback")
+            .add_node_with_policy(
+                "fetch",
+                Arc::new(FlakyNode::new("fetch", 5)),
+                NodeRetryPolicy::new(2),
+            )
+            .await
+            .unwrap()
+            .add_node("fallback", fallback)
+            .await
+            .unwrap()
+            .add_node("respond", respond)
+            .await
+            .unwrap()
+            .add_edge("fetch", "respond")
+            .unwrap()
+            .add_edge("fallback", "respond")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_handler_recovers_from_a_failure() {
+        let graph = fallback_graph()
+            .await
+            .on_error("fetch", "fallback")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        assert!(results.errors.is_empty());
+        assert_eq!(visited(&results), ["fallback", "respond"]);
+        let state = &results.final_state;
+        assert_eq!(
+            state.get("answer").unwrap().as_string(),
+            Some("cached answer")
+        );
+
+        let error = match state.get(ERROR_KEY).unwrap() {
+            StateValue::Object(error) => error,
+            other => panic!("unexpected error value: {other:?}"),
+        };
+        assert_eq!(error["node_id"].as_string(), Some("fetch"));
+        assert_eq!(error["error_type"].as_string(), Some("NodeExecutionError"));
+        assert_eq!(error["attempts"].as_integer(), Some(2));
+        assert!(error["message"]
+            .as_string()
+            .unwrap()
+            .contains("connection reset"));
+
+        let transitions: Vec<_> = results.trace.iter().map(|step| &step.transition).collect();
+        assert_eq!(
+            transitions[0],
+            &Transition::OnError(NodeId::new("fallback"))
+        );
+        assert_eq!(results.trace[0].attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_failing_error_handler_ends_the_run() {
+        let graph = fallback_graph()
+            .await
+            .add_node(
+                "recover",
+                Arc::new(BranchNode::new("recover", &[], true).failing()),
+            )
+            .await
+            .unwrap()
+            .on_error("fetch", "recover")
+            .unwrap()
+            // Would take over the failure of "recover" if it were handled
+            .on_any_error("fallback")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert_eq!(results.errors.len(), 1);
+        assert_eq!(results.errors[0].node_id, "recover");
+        let steps: Vec<_> = results
+            .trace
+            .iter()
+            .map(|step| (step.node_id.as_str(), &step.transition))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("fetch", &Transition::OnError(NodeId::new("recover"))),
+                ("recover", &Transition::Failed),
+            ]
+        );
+        assert!(!results.final_state.contains_key("answer"));
+    }
+
+    #[tokio::test]
+    async fn test_error_handler_cannot_handle_itself() {
+        let err = fallback_graph()
+            .await
+            .on_error("fallback", "fallback")
+            .err()
+            .expect("a node cannot handle its own errors");
+        assert!(matches!(err, RGraphError::Validation { .. }));
+
+        let err = fallback_graph()
+            .await
+            .on_error("fetch", "missing")
+            .err()
+            .expect("the error handler must exist");
+        assert!(err.to_string().contains("'missing' not found"));
+    }
+
+    // Node that counts its starts, then waits for an hour; counts the
+    // attempts dropped before they finished
+    struct HangingNode {
+        id: NodeId,
+        started: Arc<std::sync::atomic::AtomicUsize>,
+        aborted: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    // Counts itself when dropped
+    struct DropCounter(Arc<std::sync::atomic::AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl Node for HangingNode {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let aborted = DropCounter(self.aborted.clone());
+            self.started
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            std::mem::forget(aborted);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    type Counter = Arc<std::sync::atomic::AtomicUsize>;
+
+    fn hanging(id: &str, started: &Counter, aborted: &Counter) -> Arc<HangingNode> {
+        Arc::new(HangingNode {
+            id: NodeId::new(id),
+            started: started.clone(),
+            aborted: aborted.clone(),
+        })
+    }
+
+    async fn wait_for(counter: &Counter, count: usize) {
+        while counter.load(std::sync::atomic::Ordering::SeqCst) < count {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_run_stops_at_the_running_node() {
+        let (started, aborted) = (Counter::default(), Counter::default());
+        let graph = GraphBuilder::new("cancel")
+            .add_node("plan", RecordingNode::new("plan"))
+            .await
+            .unwrap()
+            .add_node("search", hanging("search", &started, &aborted))
+            .await
+            .unwrap()
+            .add_node("answer", RecordingNode::new("answer"))
+            .await
+            .unwrap()
+            .add_edge("plan", "search")
+            .unwrap()
+            .add_edge("search", "answer")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let run = ExecutionEngine::new().spawn(&graph, GraphState::new());
+        wait_for(&started, 1).await;
+        run.cancel();
+        let result = tokio::time::timeout(Duration::from_secs(1), run.result())
+            .await
+            .expect("the run outlived its cancellation");
+
+        match result {
+            Err(RGraphError::Cancelled { state, .. }) => {
+                // `answer` never ran
+                assert_eq!(
+                    state["visited"],
+                    StateValue::Array(vec![StateValue::from("plan")])
+                );
+            }
+            other => panic!("expected a cancelled run, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(aborted.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_aborts_every_parallel_branch() {
+        let (started, aborted) = (Counter::default(), Counter::default());
+        let graph = GraphBuilder::new("cancel")
+            .add_node("split", RecordingNode::new("split"))
+            .await
+            .unwrap()
+            .add_node("web", hanging("web", &started, &aborted))
+            .await
+            .unwrap()
+            .add_node("papers", hanging("papers", &started, &aborted))
+            .await
+            .unwrap()
+            .add_node("merge", RecordingNode::new("merge"))
+            .await
+            .unwrap()
+            .add_parallel("split", ["web", "papers"], "merge")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let mut run = graph.execute_stream(GraphState::new());
+        wait_for(&started, 2).await;
+        run.cancel();
+        let mut finished = Vec::new();
+        while let Some(event) = futures::StreamExt::next(&mut run).await {
+            if let GraphEvent::NodeFinished { node_id, .. } = event {
+                finished.push(node_id.as_str().to_string());
+            }
+        }
+        let result = run.result().await;
+
+        assert!(matches!(result, Err(RGraphError::Cancelled { .. })));
+        assert_eq!(aborted.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(!finished.contains(&"merge".to_string()));
+    }
+}
@@ -172,34 +172,89 @@
 //! - Multi-modal processing support
 
 pub mod agents;
+pub mod bus;
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub mod checkpoint;
 pub mod core;
+#[cfg(feature = "serde")]
+pub mod definition;
+pub mod diagram;
+pub mod diff;
+pub mod dry_run;
+pub mod events;
 pub mod execution;
+pub mod limits;
 pub mod nodes;
 pub mod observability;
 pub mod prelude;
+pub mod reducer;
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub mod replay;
+pub mod report;
 pub mod routing;
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub mod schedule;
+pub mod schema;
 pub mod state;
+#[cfg(feature = "rexis-rag-integration")]
+pub mod supervisor;
 pub mod tools;
+pub mod validation;
 
 #[cfg(feature = "rexis-rag-integration")]
 pub mod rrag_integration;
 
 // Re-export core types for easy access
+pub use crate::bus::{BusEvent, BusSubscription, GraphEventBus, NODE_FINISHED, NODE_STARTED};
 pub use crate::core::{
-    Edge, EdgeId, ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeId, WorkflowGraph,
+    Backoff, Edge, EdgeId, ExecutionContext, ExecutionResult, GraphBuilder, LoopLimit,
+    MergeConflictPolicy, Node, NodeId, NodeRetryPolicy, ParallelBranches, PartialFailure,
+    RetryPredicate, WorkflowGraph,
+};
+#[cfg(feature = "serde")]
+pub use crate::definition::{DefinitionError, GraphDefinition, NodeFactory, NodeRegistry};
+pub use crate::diff::{ChangeKind, DiffCapture, DiffValue, KeyChange, StateDiff};
+pub use crate::dry_run::{
+    DryRun, DryRunGap, DryRunReport, DryRunStep, SimulatedOutput, UnresolvedRoute,
 };
+pub use crate::events::{EventStatePolicy, GraphEvent, GraphRun};
 pub use crate::execution::{
     ExecutionConfig, ExecutionEngine, ExecutionError, ExecutionMetrics, ExecutionMode,
-    ExecutionResults,
+    ExecutionResults, RunHandle, TraceStep, Transition, ERROR_KEY, USAGE_KEY,
 };
-pub use crate::nodes::{AgentNode, ConditionNode, ToolNode, TransformNode};
-pub use crate::state::{GraphState, StatePath, StateValue};
-
+pub use crate::limits::ExecutionLimits;
+pub use crate::nodes::{
+    AgentNode, ArgMapping, ArgSource, ConditionNode, MapFailurePolicy, MapNode, SubgraphNode,
+    ToolNode, TransformNode,
+};
+pub use crate::reducer::{Reducer, ReducerFn};
+pub use crate::report::{NodeSummary, NodeUsage, NodeVisit, RunReport};
+pub use crate::routing::{when, EdgeRouter, ExprRouter, StateExpr, StateRouter};
+pub use crate::schema::{SchemaMode, StateKey, StateSchema, StateType};
+pub use crate::state::{GraphState, StatePath, StateValue, UnrepresentableValues};
+pub use crate::validation::{IssueKind, Severity, ValidationIssue};
+pub use tokio_util::sync::CancellationToken;
+
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub use crate::checkpoint::{Checkpoint, Checkpointer, Interrupt, Visit};
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub use crate::nodes::HumanInputNode;
+#[cfg(feature = "rexis-rag-integration")]
+pub use crate::nodes::{LlmAgentNode, LlmRouterNode, MemoryReadNode, MemoryWriteNode, RouteSpec};
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub use crate::replay::{Divergence, ReplayOptions, ReplayReport, ReplayStep};
 #[cfg(feature = "rexis-rag-integration")]
 pub use crate::rrag_integration::{
     ContextEvaluationConfig, ContextEvaluationNode, RagGenerationConfig, RagGenerationNode,
     RagRetrievalConfig, RagRetrievalNode, RagWorkflowBuilder,
 };
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub use crate::schedule::{
+    CatchUpPolicy, GraphScheduler, LastRun, OverlapPolicy, RunStatus, ScheduleConfig, ScheduleInfo,
+    Trigger,
+};
+#[cfg(feature = "rexis-rag-integration")]
+pub use crate::supervisor::{SupervisorBuilder, SupervisorModel};
 
 // Error handling
 use thiserror::Error;
@@ -234,10 +289,53 @@ pub enum RGraphError {
     #[error("Routing error: {message}")]
     Routing { message: String },
 
+    #[error("Loop limit of {max_traversals} traversals exceeded from '{from}' to '{to}'")]
+    LoopLimitExceeded {
+        from: String,
+        to: String,
+        max_traversals: usize,
+        /// The state when the limit was hit
+        state: std::collections::HashMap<String, StateValue>,
+    },
+
+    #[error("Node '{node_id}' timed out after {elapsed:?}")]
+    NodeTimeout {
+        node_id: String,
+        elapsed: std::time::Duration,
+    },
+
+    #[error("Run exceeded its deadline of {deadline:?}")]
+    RunDeadlineExceeded { deadline: std::time::Duration },
+
+    #[error("Run '{run_id}' was cancelled")]
+    Cancelled {
+        run_id: String,
+        /// The state after the last node that completed
+        state: std::collections::HashMap<String, StateValue>,
+    },
+
+    #[error("Cannot checkpoint state key '{key}': {message}")]
+    Checkpoint { key: String, message: String },
+
+    #[error("Parallel branches '{first}' and '{second}' both wrote '{key}'")]
+    MergeConflict {
+        key: String,
+        first: String,
+        second: String,
+    },
+
+    #[cfg(feature = "rexis-rag-integration")]
+    #[error("Node '{node_id}' needs a memory backend, but none is configured")]
+    MemoryUnavailable { node_id: String },
+
     #[cfg(feature = "rexis-rag-integration")]
     #[error("RRAG integration error: {0}")]
     Rrag(#[from] rexis_rag::RragError),
 
+    #[cfg(feature = "serde")]
+    #[error("Graph definition error: {0}")]
+    Definition(#[from] crate::definition::DefinitionError),
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -0,0 +1,499 @@
+//! # Execution Limits
+//!
+//! [`ExecutionLimits`] bound how many nodes of a run execute at once, across
+//! parallel branches, map items and subgraphs, with a tighter bound for nodes
+//! that [call an LLM](crate::Node::calls_llm). A node over the limit waits for
+//! a slot; waiting nodes get slots by
+//! [priority](crate::WorkflowGraph::set_node_priority), then in the order they
+//! started waiting.
+//!
+//! Nodes that only run other nodes, such as map and subgraph nodes, take no
+//! slot of their own (see [`Node::occupies_slot`](crate::Node::occupies_slot)),
+//! so they cannot starve the nodes they run.
+
+use parking_lot::Mutex;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How many nodes of a run may execute at once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExecutionLimits {
+    /// Most nodes executing at once; unbounded when `None`
+    pub max_concurrent_nodes: Option<usize>,
+    /// Most nodes calling an LLM executing at once; unbounded when `None`
+    pub max_concurrent_llm_nodes: Option<usize>,
+}
+
+impl ExecutionLimits {
+    /// Limits that bound nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let at most `max` nodes execute at once
+    pub fn with_max_concurrent_nodes(mut self, max: usize) -> Self {
+        self.max_concurrent_nodes = Some(max);
+        self
+    }
+
+    /// Let at most `max` nodes calling an LLM execute at once
+    pub fn with_max_concurrent_llm_nodes(mut self, max: usize) -> Self {
+        self.max_concurrent_llm_nodes = Some(max);
+        self
+    }
+}
+
+/// The slots of a run, shared with its branches and subgraphs
+#[derive(Debug)]
+pub(crate) struct RunSlots {
+    nodes: Arc<Slots>,
+    llm: Arc<Slots>,
+    waits: Mutex<WaitStats>,
+}
+
+/// A node's hold on its slots, released when dropped
+#[derive(Debug)]
+pub(crate) struct SlotPermit {
+    _llm: Option<SlotGuard>,
+    _node: SlotGuard,
+}
+
+/// How long nodes waited for slots
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct WaitStats {
+    pub(crate) total: Duration,
+    pub(crate) longest: Duration,
+}
+
+impl RunSlots {
+    pub(crate) fn new(limits: ExecutionLimits) -> Self {
+        Self {
+            nodes: Arc::new(Slots::new(limits.max_concurrent_nodes)),
+            llm: Arc::new(Slots::new(limits.max_concurrent_llm_nodes)),
+            waits: Mutex::new(WaitStats::default()),
+        }
+    }
+
+    /// Wait for a slot, and an LLM slot when `llm` is set
+    ///
+    /// The LLM slot is taken first, so that nodes waiting for one hold no
+    /// other slot meanwhile.
+    pub(crate) async fn acquire(&self, llm: bool, priority: i32) -> SlotPermit {
+        let start = Instant::now();
+        let llm = if llm {
+            Some(self.llm.acquire(priority).await)
+        } else {
+            None
+        };
+        let node = self.nodes.acquire(priority).await;
+
+        let waited = start.elapsed();
+        let mut waits = self.waits.lock();
+        waits.total += waited;
+        waits.longest = waits.longest.max(waited);
+
+        SlotPermit {
+            _llm: llm,
+            _node: node,
+        }
+    }
+
+    /// Most nodes that executed at once so far
+    pub(crate) fn peak(&self) -> usize {
+        self.nodes.state.lock().peak
+    }
+
+    /// Most nodes calling an LLM that executed at once so far
+    pub(crate) fn peak_llm(&self) -> usize {
+        self.llm.state.lock().peak
+    }
+
+    pub(crate) fn waits(&self) -> WaitStats {
+        *self.waits.lock()
+    }
+}
+
+/// A counted pool of slots, handed to waiters by priority
+#[derive(Debug)]
+struct Slots {
+    limit: Option<usize>,
+    state: Mutex<SlotState>,
+}
+
+#[derive(Debug, Default)]
+struct SlotState {
+    taken: usize,
+    peak: usize,
+    waiting: BinaryHeap<Waiter>,
+    next_seq: u64,
+}
+
+impl SlotState {
+    fn take(&mut self) {
+        self.taken += 1;
+        self.peak = self.peak.max(self.taken);
+    }
+}
+
+/// A node waiting for a slot; higher priorities, then earlier arrivals, go
+/// first
+#[derive(Debug)]
+struct Waiter {
+    priority: i32,
+    seq: u64,
+    ready: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl Slots {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(SlotState::default()),
+        }
+    }
+
+    async fn acquire(self: &Arc<Self>, priority: i32) -> SlotGuard {
+        let ready = {
+            let mut state = self.state.lock();
+            let free = self.limit.is_none_or(|limit| state.taken < limit);
+            if free && state.waiting.is_empty() {
+                state.take();
+                return SlotGuard {
+                    slots: self.clone(),
+                };
+            }
+            let (ready, waiting) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Waiter {
+                priority,
+                seq,
+                ready,
+            });
+            waiting
+        };
+
+        let mut waiting = Waiting {
+            slots: self,
+            ready: Some(ready),
+        };
+        if let Some(ready) = waiting.ready.as_mut() {
+            // The sender is only dropped after a send
+            let _ = ready.await;
+        }
+        waiting.ready = None;
+        SlotGuard {
+            slots: self.clone(),
+        }
+    }
+
+    /// Hand a slot to the first waiter still waiting, or free it
+    fn release(&self) {
+        let mut state = self.state.lock();
+        while let Some(waiter) = state.waiting.pop() {
+            if waiter.ready.send(()).is_ok() {
+                return;
+            }
+        }
+        state.taken -= 1;
+    }
+}
+
+/// A waiter that gives its slot on if it stops waiting after getting one
+struct Waiting<'a> {
+    slots: &'a Slots,
+    ready: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(mut ready) = self.ready.take() {
+            ready.close();
+            if ready.try_recv().is_ok() {
+                self.slots.release();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SlotGuard {
+    slots: Arc<Slots>,
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        self.slots.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeId};
+    use crate::execution::{ExecutionEngine, ExecutionResults};
+    use crate::nodes::MapNode;
+    use crate::state::{GraphState, StateValue};
+    use crate::RGraphResult;
+    use async_trait::async_trait;
+
+    // Counts of the nodes running, overall and calling an LLM, with their
+    // peaks, and the order nodes started in
+    #[derive(Default)]
+    struct Gauge {
+        running: Mutex<(usize, usize)>,
+        llm: Mutex<(usize, usize)>,
+        started: Mutex<Vec<String>>,
+    }
+
+    impl Gauge {
+        fn peak(&self) -> usize {
+            self.running.lock().1
+        }
+
+        fn peak_llm(&self) -> usize {
+            self.llm.lock().1
+        }
+    }
+
+    fn enter(count: &Mutex<(usize, usize)>) {
+        let mut count = count.lock();
+        count.0 += 1;
+        count.1 = count.1.max(count.0);
+    }
+
+    // Node taking 10ms, measured by a gauge
+    struct ProbeNode {
+        id: NodeId,
+        gauge: Arc<Gauge>,
+        llm: bool,
+    }
+
+    #[async_trait]
+    impl Node for ProbeNode {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            self.gauge.started.lock().push(self.id.as_str().to_string());
+            enter(&self.gauge.running);
+            if self.llm {
+                enter(&self.gauge.llm);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.gauge.running.lock().0 -= 1;
+            if self.llm {
+                self.gauge.llm.lock().0 -= 1;
+            }
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn calls_llm(&self) -> bool {
+            self.llm
+        }
+    }
+
+    fn probe(id: &str, gauge: &Arc<Gauge>, llm: bool) -> Arc<ProbeNode> {
+        Arc::new(ProbeNode {
+            id: NodeId::new(id),
+            gauge: gauge.clone(),
+            llm,
+        })
+    }
+
+    // Run "fan" -> branches -> "done", where each branch is (id, calls LLM,
+    // priority)
+    async fn fan_out(
+        limits: ExecutionLimits,
+        branches: &[(&str, bool, i32)],
+        gauge: &Arc<Gauge>,
+    ) -> ExecutionResults {
+        let mut builder = GraphBuilder::new("fan_out")
+            .limits(limits)
+            .add_node("fan", probe("fan", gauge, false))
+            .await
+            .unwrap()
+            .add_node("done", probe("done", gauge, false))
+            .await
+            .unwrap();
+        for (id, llm, priority) in branches {
+            builder = builder
+                .add_node(*id, probe(id, gauge, *llm))
+                .await
+                .unwrap()
+                .node_priority(*id, *priority)
+                .unwrap();
+        }
+        let ids: Vec<&str> = branches.iter().map(|(id, _, _)| *id).collect();
+        let graph = builder
+            .add_parallel("fan", ids, "done")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        assert!(results.errors.is_empty());
+        results
+    }
+
+    const BRANCHES: [(&str, bool, i32); 8] = [
+        ("b0", false, 0),
+        ("b1", false, 0),
+        ("b2", false, 0),
+        ("b3", false, 0),
+        ("b4", false, 0),
+        ("b5", false, 0),
+        ("b6", false, 0),
+        ("b7", false, 0),
+    ];
+
+    #[tokio::test(start_paused = true)]
+    async fn test_parallel_branches_stay_within_limit() {
+        let gauge = Arc::new(Gauge::default());
+        let limits = ExecutionLimits::new().with_max_concurrent_nodes(3);
+
+        let results = fan_out(limits, &BRANCHES, &gauge).await;
+
+        assert_eq!(gauge.peak(), 3);
+        assert_eq!(gauge.started.lock().len(), 10);
+        assert_eq!(results.metrics.peak_concurrency, 3);
+        // Two rounds of branches waited for the first three
+        assert_eq!(results.metrics.max_queue_wait, Duration::from_millis(20));
+        assert_eq!(results.metrics.total_queue_wait, Duration::from_millis(70));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unlimited_runs_report_peak_concurrency() {
+        let gauge = Arc::new(Gauge::default());
+
+        let results = fan_out(ExecutionLimits::new(), &BRANCHES, &gauge).await;
+
+        assert_eq!(gauge.peak(), 8);
+        assert_eq!(results.metrics.peak_concurrency, 8);
+        assert_eq!(results.metrics.total_queue_wait, Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_llm_nodes_have_a_limit_of_their_own() {
+        let gauge = Arc::new(Gauge::default());
+        let limits = ExecutionLimits::new()
+            .with_max_concurrent_nodes(4)
+            .with_max_concurrent_llm_nodes(1);
+        let branches = [
+            ("draft_a", true, 0),
+            ("draft_b", true, 0),
+            ("draft_c", true, 0),
+            ("lookup_a", false, 0),
+            ("lookup_b", false, 0),
+        ];
+
+        let results = fan_out(limits, &branches, &gauge).await;
+
+        assert_eq!(gauge.peak_llm(), 1);
+        // Both lookups ran beside the first draft
+        assert_eq!(gauge.peak(), 3);
+        assert_eq!(results.metrics.peak_llm_concurrency, 1);
+        assert_eq!(results.metrics.peak_concurrency, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_higher_priority_goes_first() {
+        let gauge = Arc::new(Gauge::default());
+        let limits = ExecutionLimits::new().with_max_concurrent_nodes(1);
+        let branches = [
+            ("batch_a", false, 0),
+            ("batch_b", false, 0),
+            ("batch_c", false, 0),
+            ("interactive", false, 10),
+        ];
+
+        fan_out(limits, &branches, &gauge).await;
+
+        // The first branch took the free slot; the rest waited for it
+        let started = gauge.started.lock().clone();
+        assert_eq!(
+            started,
+            [
+                "fan",
+                "batch_a",
+                "interactive",
+                "batch_b",
+                "batch_c",
+                "done"
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_map_items_take_slots() {
+        let gauge = Arc::new(Gauge::default());
+        let map = MapNode::new(
+            "map",
+            "items",
+            "item",
+            probe("item", &gauge, false),
+            "out",
+            8,
+        );
+        let graph = GraphBuilder::new("map")
+            .limits(ExecutionLimits::new().with_max_concurrent_nodes(2))
+            .add_node("map", Arc::new(map))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let items: Vec<StateValue> = (0..10).map(StateValue::from).collect();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("items", items))
+            .await
+            .unwrap();
+
+        assert!(results.errors.is_empty());
+        assert_eq!(gauge.peak(), 2);
+        assert_eq!(gauge.started.lock().len(), 10);
+        assert_eq!(results.metrics.peak_concurrency, 2);
+    }
+}
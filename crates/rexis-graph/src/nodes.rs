@@ -5,13 +5,33 @@
 
 pub mod agent;
 pub mod condition;
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub mod human_input;
+#[cfg(feature = "rexis-rag-integration")]
+pub mod llm_agent;
+#[cfg(feature = "rexis-rag-integration")]
+pub mod llm_router;
+pub mod map;
+#[cfg(feature = "rexis-rag-integration")]
+pub mod memory;
+pub mod subgraph;
 pub mod tool;
 pub mod transform;
 
 // Re-export node types
 pub use agent::{AgentNode, AgentNodeConfig};
 pub use condition::{ConditionNode, ConditionNodeConfig};
-pub use tool::{ToolNode, ToolNodeConfig};
+#[cfg(all(feature = "rexis-rag-integration", feature = "serde"))]
+pub use human_input::HumanInputNode;
+#[cfg(feature = "rexis-rag-integration")]
+pub use llm_agent::LlmAgentNode;
+#[cfg(feature = "rexis-rag-integration")]
+pub use llm_router::{LlmRouterNode, RouteSpec, OTHERWISE_ROUTE};
+pub use map::{MapFailurePolicy, MapNode};
+#[cfg(feature = "rexis-rag-integration")]
+pub use memory::{MemoryReadNode, MemoryWriteNode};
+pub use subgraph::SubgraphNode;
+pub use tool::{ArgMapping, ArgSource, ToolNode, ToolNodeConfig};
 pub use transform::{TransformNode, TransformNodeConfig};
 
 use crate::core::NodeId;
@@ -211,9 +231,10 @@ impl Default for NodeBuilderRegistry {
 pub mod test_utils {
     use super::*;
     use crate::core::{ExecutionContext, ExecutionResult, Node};
-    use crate::state::GraphState;
+    use crate::state::{GraphState, StateValue};
     use async_trait::async_trait;
     use std::sync::Arc;
+    use std::time::Duration;
 
     pub struct PassThroughNode {
         id: NodeId,
@@ -245,7 +266,7 @@ pub mod test_utils {
             state: &mut GraphState,
             _context: &ExecutionContext,
         ) -> crate::RGraphResult<ExecutionResult> {
-            state.set(&self.output_key, &self.output_value);
+            state.set(&self.output_key, self.output_value.as_str());
             Ok(ExecutionResult::Continue)
         }
 
@@ -261,6 +282,118 @@ pub mod test_utils {
             vec![&self.output_key]
         }
     }
+
+    /// Node writing one value to one key, after an optional delay
+    ///
+    /// Writes its own ID unless given a value.
+    pub struct WriteNode {
+        id: NodeId,
+        key: String,
+        value: Option<StateValue>,
+        delay: Duration,
+    }
+
+    impl WriteNode {
+        pub fn new(id: impl Into<NodeId>, key: impl Into<String>) -> Self {
+            Self {
+                id: id.into(),
+                key: key.into(),
+                value: None,
+                delay: Duration::ZERO,
+            }
+        }
+
+        /// Write `value` instead of the node's ID
+        pub fn with_value(mut self, value: impl Into<StateValue>) -> Self {
+            self.value = Some(value.into());
+            self
+        }
+
+        /// Sleep for `delay` before writing
+        pub fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+    }
+
+    impl From<WriteNode> for Arc<dyn Node> {
+        fn from(node: WriteNode) -> Self {
+            Arc::new(node)
+        }
+    }
+
+    #[async_trait]
+    impl Node for WriteNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> crate::RGraphResult<ExecutionResult> {
+            tokio::time::sleep(self.delay).await;
+            let value = self
+                .value
+                .clone()
+                .unwrap_or_else(|| self.id.as_str().into());
+            state.set(&self.key, value);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            vec![&self.key]
+        }
+    }
+
+    /// Node that does nothing, with a name and type of its own
+    pub struct StubNode {
+        id: NodeId,
+        name: String,
+        node_type: String,
+    }
+
+    impl StubNode {
+        pub fn new(
+            id: impl Into<NodeId>,
+            name: impl Into<String>,
+            node_type: impl Into<String>,
+        ) -> Arc<Self> {
+            Arc::new(Self {
+                id: id.into(),
+                name: name.into(),
+                node_type: node_type.into(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Node for StubNode {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> crate::RGraphResult<ExecutionResult> {
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn node_type(&self) -> &str {
+            &self.node_type
+        }
+    }
 }
 
 #[cfg(test)]
@@ -328,7 +461,7 @@ mod tests {
     #[cfg(test)]
     #[tokio::test]
     async fn test_pass_through_node() {
-        use crate::core::ExecutionContext;
+        use crate::core::{ExecutionContext, ExecutionResult, Node};
         use crate::state::{GraphState, StateValue};
         use test_utils::PassThroughNode;
 
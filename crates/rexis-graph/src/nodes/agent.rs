@@ -395,6 +395,14 @@ impl Node for AgentNode {
         &self.config.name
     }
 
+    fn node_type(&self) -> &str {
+        "Agent"
+    }
+
+    fn calls_llm(&self) -> bool {
+        true
+    }
+
     fn input_keys(&self) -> Vec<&str> {
         vec!["user_input", "query", "prompt"]
     }
@@ -69,6 +69,10 @@ impl Node for ConditionNode {
         &self.name
     }
 
+    fn node_type(&self) -> &str {
+        "Condition"
+    }
+
     fn input_keys(&self) -> Vec<&str> {
         vec![&self.config.condition_key]
     }
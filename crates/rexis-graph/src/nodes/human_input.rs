@@ -0,0 +1,289 @@
+//! # Human Input Node Implementation
+//!
+//! Human input nodes pause a run until someone responds, for example to
+//! approve a draft. The run is checkpointed and continues with
+//! [`WorkflowGraph::resume_with_input`](crate::WorkflowGraph::resume_with_input).
+
+use crate::checkpoint::Interrupt;
+use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId};
+use crate::state::GraphState;
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// A node that interrupts the run to wait for input
+///
+/// When reached, it saves an [`Interrupt`] with its prompt, input schema and
+/// the current state through the graph's checkpointer and pauses the run.
+/// The input is written to `input_key` when the run is resumed. The graph
+/// needs a checkpointer, and the node cannot run in a subgraph or a parallel
+/// branch.
+pub struct HumanInputNode {
+    id: NodeId,
+    name: String,
+    prompt: String,
+    input_key: String,
+    input_schema: serde_json::Value,
+    timeout: Option<Duration>,
+}
+
+impl HumanInputNode {
+    pub fn new(
+        id: impl Into<NodeId>,
+        prompt: impl Into<String>,
+        input_key: impl Into<String>,
+        input_schema: serde_json::Value,
+    ) -> Self {
+        let id = id.into();
+        Self {
+            name: id.as_str().to_string(),
+            id,
+            prompt: prompt.into(),
+            input_key: input_key.into(),
+            input_schema,
+            timeout: None,
+        }
+    }
+
+    /// Set the display name, which defaults to the node ID
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Let the interrupt expire when no input arrives within `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl Node for HumanInputNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let checkpointer = context.checkpointer.clone().ok_or_else(|| {
+            RGraphError::node(
+                self.id.as_str(),
+                "waiting for input needs a top-level graph with a checkpointer",
+            )
+        })?;
+
+        let created_at = chrono::Utc::now();
+        let expires_at = self
+            .timeout
+            .and_then(|timeout| chrono::Duration::from_std(timeout).ok())
+            .and_then(|timeout| created_at.checked_add_signed(timeout));
+        let interrupt = Interrupt {
+            interrupt_id: Uuid::new_v4().to_string(),
+            graph_id: context.graph_id.clone(),
+            run_id: context.execution_id.clone(),
+            node_id: self.id.clone(),
+            prompt: self.prompt.clone(),
+            input_schema: self.input_schema.clone(),
+            input_key: self.input_key.clone(),
+            state: state.snapshot(),
+            created_at,
+            expires_at,
+        };
+        checkpointer.save_interrupt(&interrupt).await?;
+
+        Ok(ExecutionResult::Interrupted {
+            interrupt_id: interrupt.interrupt_id,
+        })
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "HumanInput"
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some(&self.prompt)
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        vec![&self.input_key]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::Checkpointer;
+    use crate::core::{GraphBuilder, WorkflowGraph};
+    use crate::execution::{ExecutionEngine, Transition};
+    use crate::nodes::test_utils::WriteNode;
+    use rexis_rag::storage::{InMemoryStorage, Memory};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn approval_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["approved"],
+            "properties": {
+                "approved": { "type": "boolean" },
+                "comment": { "type": "string" }
+            }
+        })
+    }
+
+    // draft -> approve -> publish, with the approval waiting for a human
+    async fn approval_graph(storage: Arc<dyn Memory>, approve: HumanInputNode) -> WorkflowGraph {
+        GraphBuilder::new("approval")
+            .checkpointer(Checkpointer::new(storage))
+            .add_node("draft", WriteNode::new("draft", "draft").into())
+            .await
+            .unwrap()
+            .add_node("approve", Arc::new(approve))
+            .await
+            .unwrap()
+            .add_node("publish", WriteNode::new("publish", "published").into())
+            .await
+            .unwrap()
+            .add_edge("draft", "approve")
+            .unwrap()
+            .add_edge("approve", "publish")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn approve_node() -> HumanInputNode {
+        HumanInputNode::new(
+            "approve",
+            "Publish this draft?",
+            "approval",
+            approval_schema(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_human_input_interrupts_the_run() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let graph = approval_graph(storage.clone(), approve_node()).await;
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        let interrupt_id = results.interrupt_id.clone().unwrap();
+        assert!(results.metrics.success);
+        assert!(!results.final_state.contains_key("published"));
+        let last = results.trace.last().unwrap();
+        assert_eq!(last.node_id, NodeId::new("approve"));
+        assert_eq!(
+            last.transition,
+            Transition::Interrupted(interrupt_id.clone())
+        );
+
+        let pending = graph.pending_interrupts(storage).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        let interrupt = &pending[0];
+        assert_eq!(interrupt.interrupt_id, interrupt_id);
+        assert_eq!(interrupt.run_id, results.run_id);
+        assert_eq!(interrupt.prompt, "Publish this draft?");
+        assert_eq!(interrupt.input_schema, approval_schema());
+        assert!(interrupt.state.contains_key("draft"));
+        assert_eq!(interrupt.expires_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_resume_with_valid_input_continues_the_run() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let graph = approval_graph(storage.clone(), approve_node()).await;
+        let paused = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        let interrupt_id = paused.interrupt_id.unwrap();
+
+        let input = json!({ "approved": true, "comment": "ship it" });
+        let results = graph
+            .resume_with_input(&interrupt_id, input.clone())
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        assert_eq!(results.run_id, paused.run_id);
+        assert_eq!(results.interrupt_id, None);
+        let state = &results.final_state;
+        assert_eq!(
+            serde_json::Value::from(state.get("approval").unwrap()),
+            input
+        );
+        assert_eq!(state.get("published").unwrap().as_string(), Some("publish"));
+        let steps: Vec<_> = results
+            .trace
+            .iter()
+            .map(|step| step.node_id.as_str())
+            .collect();
+        assert_eq!(steps, ["approve", "publish"]);
+
+        // An interrupt is resumed at most once
+        assert!(graph.pending_interrupts(storage).await.unwrap().is_empty());
+        let err = graph
+            .resume_with_input(&interrupt_id, input)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No pending interrupt"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_with_invalid_input_is_rejected() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let graph = approval_graph(storage.clone(), approve_node()).await;
+        let paused = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        let interrupt_id = paused.interrupt_id.unwrap();
+
+        let err = graph
+            .resume_with_input(&interrupt_id, json!({ "approved": "yes" }))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RGraphError::Validation { .. }));
+        assert!(err.to_string().contains("$.approved"));
+        // The interrupt stays open for a valid answer
+        assert_eq!(graph.pending_interrupts(storage).await.unwrap().len(), 1);
+        let results = graph
+            .resume_with_input(&interrupt_id, json!({ "approved": false }))
+            .await
+            .unwrap();
+        assert!(results.final_state.contains_key("published"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_interrupt_cannot_be_resumed() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let approve = approve_node().with_timeout(Duration::ZERO);
+        let graph = approval_graph(storage.clone(), approve).await;
+        let paused = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        let err = graph
+            .resume_with_input(&paused.interrupt_id.unwrap(), json!({ "approved": true }))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("expired"));
+        assert!(graph.pending_interrupts(storage).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,422 @@
+//! # LLM Agent Node Implementation
+//!
+//! LLM agent nodes run a [`rexis_rag::Agent`] as one step of a graph, so the
+//! agent's tool loop, memory and guardrails are available in workflows.
+
+use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId};
+use crate::execution::USAGE_KEY;
+use crate::report::NodeUsage;
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use rexis_rag::agent::Tool as AgentTool;
+use rexis_rag::rexis_llm::Client;
+use rexis_rag::{Agent, AgentConfig, RunControl, RunResult, ToolExecutor};
+use std::sync::Arc;
+
+/// A node that answers the text at its input key with an agent run
+///
+/// The answer is written to `output_key` and, unless disabled, the run's
+/// iterations, tool calls and usage to `metadata_key`; the usage is also
+/// reported to the [run report](crate::report). The run is bounded by
+/// what is left of the graph run's deadline. With a session key the agent
+/// runs in the memory session named by that state key, or in one session per
+/// graph run when the key is absent, so one agent can serve many runs at once.
+pub struct LlmAgentNode {
+    id: NodeId,
+    name: String,
+    agent: Arc<Agent>,
+    input_key: String,
+    output_key: String,
+    metadata_key: Option<String>,
+    session_key: Option<String>,
+}
+
+impl LlmAgentNode {
+    pub fn new(id: impl Into<NodeId>, agent: Arc<Agent>) -> Self {
+        let id = id.into();
+        Self {
+            name: id.as_str().to_string(),
+            id,
+            agent,
+            input_key: "user_input".to_string(),
+            output_key: "agent_response".to_string(),
+            metadata_key: Some("agent_run".to_string()),
+            session_key: None,
+        }
+    }
+
+    /// Create a node around a new agent using `client` and `tools`
+    pub fn from_client(
+        id: impl Into<NodeId>,
+        client: Client,
+        config: AgentConfig,
+        tools: Vec<Box<dyn AgentTool>>,
+    ) -> RGraphResult<Self> {
+        let mut executor = ToolExecutor::empty();
+        for tool in tools {
+            executor.register(tool)?;
+        }
+        let agent = Agent::new(client, executor, config)?;
+        Ok(Self::new(id, Arc::new(agent)))
+    }
+
+    /// Set the display name, which defaults to the node ID
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the key the user input is read from, `user_input` by default
+    pub fn with_input_key(mut self, key: impl Into<String>) -> Self {
+        self.input_key = key.into();
+        self
+    }
+
+    /// Set the key the answer is written to, `agent_response` by default
+    pub fn with_output_key(mut self, key: impl Into<String>) -> Self {
+        self.output_key = key.into();
+        self
+    }
+
+    /// Set the key the run metadata is written to, `agent_run` by default;
+    /// `None` leaves it out of the state
+    pub fn with_metadata_key(mut self, key: Option<String>) -> Self {
+        self.metadata_key = key;
+        self
+    }
+
+    /// Run the agent in the memory session named by the string at `key`
+    pub fn with_session_key(mut self, key: impl Into<String>) -> Self {
+        self.session_key = Some(key.into());
+        self
+    }
+
+    /// The agent this node runs
+    pub fn agent(&self) -> &Arc<Agent> {
+        &self.agent
+    }
+
+    fn read_string(&self, state: &GraphState, key: &str) -> RGraphResult<Option<String>> {
+        match state.get(key) {
+            Ok(StateValue::String(text)) => Ok(Some(text)),
+            Ok(_) => Err(RGraphError::node(
+                self.id.as_str(),
+                format!("'{}' must be a string", key),
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Iterations, tool calls and usage of a run, as stored in the state
+fn run_metadata(result: &RunResult) -> serde_json::Value {
+    let tool_calls: Vec<_> = result
+        .tool_invocations
+        .iter()
+        .map(|call| {
+            serde_json::json!({
+                "name": call.name,
+                "args": call.args,
+                "is_error": call.is_error(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "run_id": result.run_id,
+        "iterations": result.iterations,
+        "tool_calls": tool_calls,
+        "usage": result.usage,
+        "stop_reason": result.stop_reason,
+        "session_id": result.session_id,
+        "duration_ms": result.duration.as_millis() as u64,
+    })
+}
+
+#[async_trait]
+impl Node for LlmAgentNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let input = self.read_string(state, &self.input_key)?.ok_or_else(|| {
+            RGraphError::node(
+                self.id.as_str(),
+                format!("input '{}' is missing from the state", self.input_key),
+            )
+        })?;
+
+        let mut control = RunControl::new().with_cancel(context.cancellation().clone());
+        if let Some(remaining) = context.remaining_time() {
+            control = control.with_deadline(remaining);
+        }
+
+        let run = match &self.session_key {
+            Some(key) => {
+                let session_id = self
+                    .read_string(state, key)?
+                    .unwrap_or_else(|| context.execution_id.clone());
+                self.agent
+                    .run_detailed_for_session_with(&session_id, input, control)
+                    .await
+            }
+            None => self.agent.run_detailed_with(input, control).await,
+        };
+        let result = run.map_err(|e| match context.deadline {
+            Some(deadline) if deadline.passed() => deadline.exceeded(),
+            _ if context.is_cancelled() => {
+                RGraphError::node(self.id.as_str(), "agent run was cancelled")
+            }
+            _ => RGraphError::node(self.id.as_str(), format!("agent run failed: {}", e)),
+        })?;
+
+        let node = context.current_node.as_str();
+        let mut usage = NodeUsage::new(result.usage.prompt_tokens, result.usage.completion_tokens);
+        if let Some(cost) = result.usage.estimated_cost_usd() {
+            usage = usage.with_cost(cost);
+        }
+        state.set_with_context(node, USAGE_KEY, usage);
+        if let Some(key) = &self.metadata_key {
+            state.set_with_context(node, key, StateValue::from(run_metadata(&result)));
+        }
+        state.set_with_context(node, &self.output_key, result.output);
+
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "LlmAgent"
+    }
+
+    fn calls_llm(&self) -> bool {
+        true
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        let mut keys = vec![self.input_key.as_str()];
+        keys.extend(self.session_key.as_deref());
+        keys
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        let mut keys = vec![self.output_key.as_str()];
+        keys.extend(self.metadata_key.as_deref());
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::GraphBuilder;
+    use crate::execution::ExecutionEngine;
+    use rexis_llm::testing::{respond_text, respond_with_tool_call, MockClient};
+    use rexis_llm::tools::ToolRegistry;
+    use rexis_llm::Usage;
+    use rexis_rag::agent::memory::{AgentMemoryManager, MemoryConfig};
+    use rexis_rag::storage::{InMemoryStorage, Memory};
+    use std::time::Duration;
+
+    struct WeatherTool;
+
+    impl rexis_llm::tools::Tool for WeatherTool {
+        fn name(&self) -> &str {
+            "get_weather"
+        }
+
+        fn description(&self) -> &str {
+            "Current weather for a city"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"city": {"type": "string"}}})
+        }
+
+        fn execute(
+            &self,
+            args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(serde_json::json!({"city": args["city"], "sky": "sunny"}))
+        }
+    }
+
+    fn weather_agent(mock: &MockClient) -> Arc<Agent> {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(WeatherTool)).unwrap();
+        let agent = Agent::new(
+            mock.client(),
+            ToolExecutor::new(registry),
+            AgentConfig::default(),
+        )
+        .unwrap();
+        Arc::new(agent)
+    }
+
+    #[tokio::test]
+    async fn test_two_agents_run_end_to_end() {
+        let mock = MockClient::builder()
+            .on_user_message_containing(
+                "weather",
+                respond_with_tool_call("get_weather", serde_json::json!({"city": "Paris"}))
+                    .with_usage(Usage::new(10, 5)),
+            )
+            .on_tool_result(
+                "get_weather",
+                respond_text("It is sunny in Paris.").with_usage(Usage::new(20, 8)),
+            )
+            .on_user_message_containing("sunny", respond_text("Pack sunglasses."))
+            .build();
+
+        let researcher = LlmAgentNode::new("researcher", weather_agent(&mock))
+            .with_input_key("question")
+            .with_output_key("forecast");
+        let advisor =
+            LlmAgentNode::from_client("advisor", mock.client(), AgentConfig::default(), Vec::new())
+                .unwrap()
+                .with_input_key("forecast")
+                .with_output_key("advice")
+                .with_metadata_key(None);
+
+        let graph = GraphBuilder::new("trip")
+            .add_node("researcher", Arc::new(researcher))
+            .await
+            .unwrap()
+            .add_node("advisor", Arc::new(advisor))
+            .await
+            .unwrap()
+            .add_edge("researcher", "advisor")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(
+                &graph,
+                GraphState::new().with_input("question", "What's the weather in Paris?"),
+            )
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        let state = &results.final_state;
+        assert_eq!(
+            state.get("forecast").unwrap().as_string(),
+            Some("It is sunny in Paris.")
+        );
+        assert_eq!(
+            state.get("advice").unwrap().as_string(),
+            Some("Pack sunglasses.")
+        );
+
+        let metadata: serde_json::Value = state.get("agent_run").unwrap().into();
+        assert_eq!(metadata["iterations"], 2);
+        assert_eq!(metadata["tool_calls"][0]["name"], "get_weather");
+        assert_eq!(metadata["tool_calls"][0]["args"]["city"], "Paris");
+        assert_eq!(metadata["usage"]["total_tokens"], 43);
+        assert_eq!(metadata["stop_reason"], "final_answer");
+        assert_eq!(mock.requests().len(), 3);
+
+        let report = results.report();
+        let researcher = &report.visits[0];
+        assert_eq!(researcher.node_id.as_str(), "researcher");
+        assert_eq!(researcher.usage.unwrap().total_tokens(), 43);
+        assert!(!state.contains_key(USAGE_KEY));
+    }
+
+    #[tokio::test]
+    async fn test_session_is_read_from_state_or_defaults_to_the_run() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let memory = AgentMemoryManager::new(MemoryConfig::new(storage, "assistant"));
+        let agent = Agent::new_with_memory(
+            mock.client(),
+            ToolExecutor::empty(),
+            memory,
+            AgentConfig::default(),
+        )
+        .unwrap();
+        let node = LlmAgentNode::new("agent", Arc::new(agent)).with_session_key("session");
+        let context = ExecutionContext::new("graph".to_string(), NodeId::new("agent"));
+        let session_of = |state: &GraphState| {
+            let metadata: serde_json::Value = state.get("agent_run").unwrap().into();
+            metadata["session_id"].as_str().map(str::to_string)
+        };
+
+        let mut state = GraphState::new();
+        state.set("user_input", "hello");
+        state.set("session", "user-42");
+        node.execute(&mut state, &context).await.unwrap();
+        assert_eq!(session_of(&state).as_deref(), Some("user-42"));
+
+        state.remove("session");
+        node.execute(&mut state, &context).await.unwrap();
+        assert_eq!(session_of(&state), Some(context.execution_id.clone()));
+        assert_eq!(node.input_keys(), ["user_input", "session"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_input_fails_with_node_context() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+        let node = LlmAgentNode::new("agent", weather_agent(&mock)).with_input_key("question");
+
+        let mut state = GraphState::new();
+        let context = ExecutionContext::new("graph".to_string(), NodeId::new("agent"));
+        let err = node.execute(&mut state, &context).await.unwrap_err();
+
+        assert!(matches!(&err, RGraphError::Node { node_id, .. } if node_id == "agent"));
+        assert!(err.to_string().contains("input 'question'"));
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_agent_errors_fail_the_node() {
+        let mock = MockClient::builder()
+            .otherwise(respond_with_tool_call(
+                "get_weather",
+                serde_json::json!({"city": "Oslo"}),
+            ))
+            .build();
+        let agent = Agent::new(
+            mock.client(),
+            ToolExecutor::new({
+                let mut registry = ToolRegistry::new();
+                registry.register(Box::new(WeatherTool)).unwrap();
+                registry
+            }),
+            AgentConfig::default().with_max_iterations(1),
+        )
+        .unwrap();
+        let graph = GraphBuilder::new("weather")
+            .add_node(
+                "agent",
+                Arc::new(LlmAgentNode::new("agent", Arc::new(agent))),
+            )
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute_with_deadline(
+                &graph,
+                GraphState::new().with_input("user_input", "weather?"),
+                Duration::from_secs(30),
+            )
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert_eq!(results.errors[0].node_id, "agent");
+        assert!(results.errors[0].error_message.contains("agent run failed"));
+    }
+}
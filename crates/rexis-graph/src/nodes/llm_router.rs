@@ -0,0 +1,449 @@
+//! # LLM Router Node Implementation
+//!
+//! LLM router nodes make routing decisions that need an understanding of the
+//! input, such as telling a billing question from a technical one, by asking a
+//! model to pick one of a closed list of routes.
+
+use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId};
+use crate::execution::USAGE_KEY;
+use crate::report::NodeUsage;
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use rexis_rag::rexis_llm::{ChatMessage, ChatResponse, Client, GenerationParams, RequestOptions};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Route name written to the state when an [`LlmRouterNode`] falls back to
+/// its `otherwise` target
+pub const OTHERWISE_ROUTE: &str = "otherwise";
+
+/// A route an [`LlmRouterNode`] can pick
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RouteSpec {
+    /// Label the model answers with
+    pub name: String,
+    /// What belongs on this route, shown to the model
+    pub description: String,
+    /// Node execution continues at when the route is picked
+    pub target_node: NodeId,
+}
+
+impl RouteSpec {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        target_node: impl Into<NodeId>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            target_node: target_node.into(),
+        }
+    }
+}
+
+/// A node that has a model pick one route for the text at its input key
+///
+/// The model is asked to answer with exactly one route name. The picked route
+/// is written to `route_key` and its confidence to `confidence_key`, and
+/// execution continues at the route's target node. With
+/// [logprobs](Self::with_logprobs), the confidence is the probability the
+/// model gave the route among all routes; without, a route named unambiguously
+/// has confidence 1.0. When the answer names no single route or its
+/// confidence is below the [minimum](Self::with_min_confidence), the node
+/// routes to its [`otherwise`](Self::with_otherwise) target, writing
+/// [`OTHERWISE_ROUTE`] as the route, or fails when it has none.
+pub struct LlmRouterNode {
+    id: NodeId,
+    name: String,
+    client: Client,
+    routes: Vec<RouteSpec>,
+    input_key: String,
+    route_key: String,
+    confidence_key: String,
+    otherwise: Option<NodeId>,
+    min_confidence: f64,
+    logprobs: Option<u8>,
+}
+
+impl LlmRouterNode {
+    pub fn new(
+        id: impl Into<NodeId>,
+        client: Client,
+        routes: Vec<RouteSpec>,
+        input_key: impl Into<String>,
+    ) -> Self {
+        let id = id.into();
+        Self {
+            name: id.as_str().to_string(),
+            id,
+            client,
+            routes,
+            input_key: input_key.into(),
+            route_key: "route".to_string(),
+            confidence_key: "route_confidence".to_string(),
+            otherwise: None,
+            min_confidence: 0.0,
+            logprobs: None,
+        }
+    }
+
+    /// Set the display name, which defaults to the node ID
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the key the picked route is written to, `route` by default
+    pub fn with_route_key(mut self, key: impl Into<String>) -> Self {
+        self.route_key = key.into();
+        self
+    }
+
+    /// Set the key the confidence is written to, `route_confidence` by default
+    pub fn with_confidence_key(mut self, key: impl Into<String>) -> Self {
+        self.confidence_key = key.into();
+        self
+    }
+
+    /// Route to `target` when the model picks no route with enough confidence
+    pub fn with_otherwise(mut self, target: impl Into<NodeId>) -> Self {
+        self.otherwise = Some(target.into());
+        self
+    }
+
+    /// Fall back when the picked route's confidence is below `confidence`
+    pub fn with_min_confidence(mut self, confidence: f64) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+
+    /// Request logprobs with `top_k` alternatives to measure the confidence
+    ///
+    /// Only for providers that return logprobs; others fail the request.
+    pub fn with_logprobs(mut self, top_k: u8) -> Self {
+        self.logprobs = Some(top_k);
+        self
+    }
+
+    /// The routes the model picks from
+    pub fn routes(&self) -> &[RouteSpec] {
+        &self.routes
+    }
+
+    fn prompt(&self) -> String {
+        let routes: Vec<String> = self
+            .routes
+            .iter()
+            .map(|route| format!("- {}: {}", route.name, route.description))
+            .collect();
+        format!(
+            "Decide which route the input belongs on. The routes are:\n{}\n\n\
+             Answer with the name of exactly one route and nothing else.",
+            routes.join("\n")
+        )
+    }
+
+    /// The route the answer names: the whole answer, or else the only route
+    /// named in it
+    fn parse(&self, answer: &str) -> Option<&RouteSpec> {
+        let answer = answer
+            .trim()
+            .trim_matches(|c: char| c.is_whitespace() || "\"'`.*".contains(c))
+            .to_lowercase();
+        if let Some(route) = self
+            .routes
+            .iter()
+            .find(|route| route.name.to_lowercase() == answer)
+        {
+            return Some(route);
+        }
+
+        let mut named = self.routes.iter().filter(|route| {
+            answer
+                .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+                .any(|word| word == route.name.to_lowercase())
+        });
+        match (named.next(), named.next()) {
+            (Some(route), None) => Some(route),
+            _ => None,
+        }
+    }
+
+    /// Confidence in `route`, or `None` without logprobs
+    fn confidence(&self, response: &ChatResponse, route: &str) -> Option<f64> {
+        let names: Vec<&str> = self
+            .routes
+            .iter()
+            .map(|route| route.name.as_str())
+            .collect();
+        response
+            .label_confidence(&names)
+            .map(|confidence| confidence.get(route).copied().unwrap_or(0.0))
+    }
+}
+
+#[async_trait]
+impl Node for LlmRouterNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let input = match state.get(&self.input_key) {
+            Ok(StateValue::String(text)) => text,
+            Ok(_) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!("'{}' must be a string", self.input_key),
+                ))
+            }
+            Err(_) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!("input '{}' is missing from the state", self.input_key),
+                ))
+            }
+        };
+
+        let mut options = RequestOptions::new()
+            .with_cancellation(context.cancellation().clone())
+            .with_params(GenerationParams::new().with_temperature(0.0));
+        if let Some(remaining) = context.remaining_time() {
+            options = options.with_timeout(remaining);
+        }
+        if let Some(top_k) = self.logprobs {
+            options = options.with_logprobs(top_k);
+        }
+        let messages = vec![ChatMessage::system(self.prompt()), ChatMessage::user(input)];
+        let response = self
+            .client
+            .chat_completion_with(messages, options)
+            .await
+            .map_err(|e| match context.deadline {
+                Some(deadline) if deadline.passed() => deadline.exceeded(),
+                _ if context.is_cancelled() => {
+                    RGraphError::node(self.id.as_str(), "route selection was cancelled")
+                }
+                _ => RGraphError::node(self.id.as_str(), format!("route selection failed: {}", e)),
+            })?;
+
+        let node = context.current_node.as_str();
+        if let Some(usage) = &response.usage {
+            let mut node_usage =
+                NodeUsage::new(usage.prompt_tokens as u64, usage.completion_tokens as u64);
+            if let Some(cost) = self.client.pricing().cost(&response.model, usage) {
+                node_usage = node_usage.with_cost(cost);
+            }
+            state.set_with_context(node, USAGE_KEY, node_usage);
+        }
+
+        let picked = self.parse(&response.content).map(|route| {
+            let confidence = self.confidence(&response, &route.name).unwrap_or(1.0);
+            (route, confidence)
+        });
+        let (route, target, confidence) = match (picked, &self.otherwise) {
+            (Some((route, confidence)), _) if confidence >= self.min_confidence => {
+                (route.name.as_str(), &route.target_node, confidence)
+            }
+            (picked, Some(otherwise)) => {
+                let confidence = picked.map_or(0.0, |(_, confidence)| confidence);
+                (OTHERWISE_ROUTE, otherwise, confidence)
+            }
+            (Some((route, confidence)), None) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!(
+                        "route '{}' was picked with confidence {:.2}, below {:.2}",
+                        route.name, confidence, self.min_confidence
+                    ),
+                ))
+            }
+            (None, None) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!("answer '{}' names no single route", response.content),
+                ))
+            }
+        };
+
+        state.set_with_context(node, &self.route_key, route);
+        state.set_with_context(node, &self.confidence_key, confidence);
+        Ok(ExecutionResult::Route(target.as_str().to_string()))
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "LlmRouter"
+    }
+
+    fn calls_llm(&self) -> bool {
+        true
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        vec![&self.input_key]
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        vec![&self.route_key, &self.confidence_key]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::GraphBuilder;
+    use crate::execution::{ExecutionEngine, ExecutionResults};
+    use crate::nodes::test_utils::PassThroughNode;
+    use rexis_llm::testing::{respond_text, MockClient, MockResponse};
+    use rexis_llm::{TokenLogprob, TopLogprob};
+
+    fn routes() -> Vec<RouteSpec> {
+        vec![
+            RouteSpec::new("billing", "Invoices, payments and refunds", "billing_desk"),
+            RouteSpec::new("technical", "Bugs, errors and outages", "tech_desk"),
+            RouteSpec::new("sales", "Plans, pricing and upgrades", "sales_desk"),
+        ]
+    }
+
+    // Answer `token`, with the probabilities of the first token's alternatives
+    fn respond_with_confidence(token: &str, alternatives: &[(&str, f64)]) -> MockResponse {
+        respond_text(token).with_logprobs(vec![TokenLogprob {
+            token: token.to_string(),
+            logprob: alternatives[0].1.ln(),
+            top_logprobs: alternatives
+                .iter()
+                .map(|(token, probability)| TopLogprob {
+                    token: token.to_string(),
+                    logprob: probability.ln(),
+                })
+                .collect(),
+        }])
+    }
+
+    fn mock() -> MockClient {
+        MockClient::builder()
+            .on_user_message_containing("charged twice", respond_text("billing"))
+            .on_user_message_containing("crashes", respond_text(" Technical."))
+            .on_user_message_containing(
+                "enterprise",
+                respond_with_confidence("sales", &[("sales", 0.9), ("billing", 0.1)]),
+            )
+            .on_user_message_containing(
+                "account",
+                respond_with_confidence("billing", &[("billing", 0.4), ("technical", 0.35)]),
+            )
+            .otherwise(respond_text("I am not sure."))
+            .build()
+    }
+
+    async fn route(mock: &MockClient, question: &str) -> ExecutionResults {
+        let router = LlmRouterNode::new("router", mock.client(), routes(), "question")
+            .with_otherwise("human")
+            .with_min_confidence(0.6)
+            .with_logprobs(5);
+        let mut builder = GraphBuilder::new("support")
+            .add_node("router", std::sync::Arc::new(router))
+            .await
+            .unwrap();
+        for desk in ["billing_desk", "tech_desk", "sales_desk", "human"] {
+            builder = builder
+                .add_node(desk, PassThroughNode::new(desk, desk, "desk", desk))
+                .await
+                .unwrap()
+                .add_edge("router", desk)
+                .unwrap();
+        }
+        let graph = builder.build().unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("question", question))
+            .await
+            .unwrap();
+        assert!(results.errors.is_empty());
+        results
+    }
+
+    fn routed(results: &ExecutionResults) -> (String, String, f64) {
+        let state = &results.final_state;
+        (
+            state.get("route").unwrap().as_string().unwrap().to_string(),
+            state.get("desk").unwrap().as_string().unwrap().to_string(),
+            state.get("route_confidence").unwrap().as_float().unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_routes_three_ways() {
+        let mock = mock();
+
+        let results = route(&mock, "I was charged twice this month").await;
+        assert_eq!(
+            routed(&results),
+            ("billing".into(), "billing_desk".into(), 1.0)
+        );
+
+        let results = route(&mock, "The app crashes on start").await;
+        assert_eq!(
+            routed(&results),
+            ("technical".into(), "tech_desk".into(), 1.0)
+        );
+
+        let results = route(&mock, "Do you have an enterprise plan?").await;
+        let (chosen, desk, confidence) = routed(&results);
+        assert_eq!((chosen.as_str(), desk.as_str()), ("sales", "sales_desk"));
+        assert!((confidence - 0.9).abs() < 1e-9);
+
+        let request = &mock.requests()[0];
+        let prompt = request.messages[0].text().unwrap();
+        assert!(prompt.contains("- technical: Bugs, errors and outages"));
+        assert_eq!(request.params.logprobs.unwrap().top_k, 5);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_low_confidence_or_unparsable_answers() {
+        let mock = mock();
+
+        let results = route(&mock, "Something is wrong with my account").await;
+        let (chosen, desk, confidence) = routed(&results);
+        assert_eq!((chosen.as_str(), desk.as_str()), (OTHERWISE_ROUTE, "human"));
+        assert!((confidence - 0.4 / 0.75).abs() < 1e-9);
+
+        let results = route(&mock, "Hello?").await;
+        assert_eq!(
+            routed(&results),
+            (OTHERWISE_ROUTE.into(), "human".into(), 0.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fails_without_a_fallback() {
+        let mock = mock();
+        let router = LlmRouterNode::new("router", mock.client(), routes(), "question");
+        let context = ExecutionContext::new("support".to_string(), NodeId::new("router"));
+
+        let mut state = GraphState::new().with_input("question", "Hello?");
+        let err = router.execute(&mut state, &context).await.unwrap_err();
+        assert!(err.to_string().contains("names no single route"));
+
+        // Several routes named are as good as none
+        assert!(router.parse("billing or sales").is_none());
+        assert_eq!(
+            router
+                .parse("Route: sales")
+                .map(|route| route.name.as_str()),
+            Some("sales")
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! # Map Node Implementation
+//!
+//! Map nodes run an inner node once per item of a list in the state, for
+//! example to summarize each retrieved document, and collect the results in
+//! input order.
+
+use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId};
+use crate::execution::acquire_slot;
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How a map node handles items whose inner run failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MapFailurePolicy {
+    /// The first failed item fails the node, cancelling the items in flight
+    #[default]
+    FailFast,
+    /// Run every item; failed ones leave `Null` in the output and are listed
+    /// under the errors key
+    Collect,
+}
+
+/// A node that runs an inner node for each item of a list
+///
+/// Each item runs on its own copy of the state with the item under
+/// `item_key`. Once it finished, the value of the result key, which defaults
+/// to `item_key`, is collected. The results are written to `output_key` in the
+/// order of the input list, however the runs complete. At most `concurrency`
+/// items run at a time, and fewer when the graph's
+/// [`ExecutionLimits`](crate::ExecutionLimits) leave no slot for them. The
+/// inner node can be a [`SubgraphNode`](crate::SubgraphNode) to run a whole
+/// pipeline per item.
+pub struct MapNode {
+    id: NodeId,
+    name: String,
+    input_key: String,
+    item_key: String,
+    result_key: String,
+    inner: Arc<dyn Node>,
+    output_key: String,
+    errors_key: String,
+    concurrency: usize,
+    failure_policy: MapFailurePolicy,
+}
+
+impl MapNode {
+    /// Create a map node; a `concurrency` of 0 runs one item at a time
+    pub fn new(
+        id: impl Into<NodeId>,
+        input_key: impl Into<String>,
+        item_key: impl Into<String>,
+        inner: Arc<dyn Node>,
+        output_key: impl Into<String>,
+        concurrency: usize,
+    ) -> Self {
+        let id = id.into();
+        let item_key = item_key.into();
+        let output_key = output_key.into();
+        Self {
+            name: id.as_str().to_string(),
+            id,
+            input_key: input_key.into(),
+            result_key: item_key.clone(),
+            item_key,
+            inner,
+            errors_key: format!("{}_errors", output_key),
+            output_key,
+            concurrency: concurrency.max(1),
+            failure_policy: MapFailurePolicy::default(),
+        }
+    }
+
+    /// Set the display name, which defaults to the node ID
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Collect the state key the inner node writes its result to, instead of
+    /// `item_key`
+    pub fn with_result_key(mut self, result_key: impl Into<String>) -> Self {
+        self.result_key = result_key.into();
+        self
+    }
+
+    /// Set how failed items are handled
+    pub fn with_failure_policy(mut self, failure_policy: MapFailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Set where [`MapFailurePolicy::Collect`] lists failed items, which
+    /// defaults to `<output_key>_errors`
+    ///
+    /// Each entry is an object with the item's `index` and the error
+    /// `message`.
+    pub fn with_errors_key(mut self, errors_key: impl Into<String>) -> Self {
+        self.errors_key = errors_key.into();
+        self
+    }
+
+    /// Run the inner node on a copy of `state` holding `item`
+    async fn run_item(
+        &self,
+        state: &GraphState,
+        item: StateValue,
+        context: &ExecutionContext,
+    ) -> RGraphResult<StateValue> {
+        let mut item_state = state.fork();
+        item_state.set(self.item_key.clone(), item);
+
+        let _slot = acquire_slot(self.inner.as_ref(), context).await?;
+        let result = self.inner.execute(&mut item_state, context).await?;
+        item_state.check_writes(self.inner.id().as_str())?;
+        if let ExecutionResult::Interrupted { .. } = result {
+            return Err(RGraphError::node(
+                self.inner.id().as_str(),
+                "mapped nodes cannot wait for input",
+            ));
+        }
+        item_state.get(&self.result_key).map_err(|_| {
+            RGraphError::node(
+                self.inner.id().as_str(),
+                format!("did not produce result '{}'", self.result_key),
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl Node for MapNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let items = match state.get(&self.input_key) {
+            Ok(StateValue::Array(items)) => items,
+            Ok(_) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!("input '{}' is not a list", self.input_key),
+                ))
+            }
+            Err(_) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!("input '{}' is missing from the state", self.input_key),
+                ))
+            }
+        };
+
+        let shared: &GraphState = state;
+        let mut runs = futures::stream::iter(items.into_iter().enumerate())
+            .map(|(index, item)| async move { (index, self.run_item(shared, item, context).await) })
+            .buffer_unordered(self.concurrency);
+
+        let mut outputs = Vec::new();
+        let mut errors = Vec::new();
+        while let Some((index, result)) = runs.next().await {
+            match result {
+                Ok(output) => outputs.push((index, output)),
+                Err(e) if self.failure_policy == MapFailurePolicy::FailFast => {
+                    return Err(RGraphError::node(
+                        self.id.as_str(),
+                        format!("item {} failed: {}", index, e),
+                    ));
+                }
+                Err(e) => {
+                    tracing::debug!(
+                        "Map node '{}' item {} failed: {}",
+                        self.id.as_str(),
+                        index,
+                        e
+                    );
+                    outputs.push((index, StateValue::Null));
+                    errors.push((index, e.to_string()));
+                }
+            }
+        }
+
+        outputs.sort_by_key(|(index, _)| *index);
+        errors.sort_by_key(|(index, _)| *index);
+        let outputs = outputs
+            .into_iter()
+            .map(|(_, output)| output)
+            .collect::<Vec<_>>();
+        state.set(self.output_key.clone(), outputs);
+
+        if self.failure_policy == MapFailurePolicy::Collect {
+            let errors = errors
+                .into_iter()
+                .map(|(index, message)| {
+                    StateValue::Object(HashMap::from([
+                        ("index".to_string(), StateValue::from(index as i64)),
+                        ("message".to_string(), StateValue::from(message)),
+                    ]))
+                })
+                .collect::<Vec<_>>();
+            state.set(self.errors_key.clone(), errors);
+        }
+
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "Map"
+    }
+
+    fn occupies_slot(&self) -> bool {
+        false
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        vec![&self.input_key]
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        match self.failure_policy {
+            MapFailurePolicy::FailFast => vec![&self.output_key],
+            MapFailurePolicy::Collect => vec![&self.output_key, &self.errors_key],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::GraphBuilder;
+    use crate::execution::ExecutionEngine;
+    use parking_lot::Mutex;
+    use std::time::Duration;
+
+    // Node squaring the integer under "item", failing on `fail_on`, and
+    // recording how many items run at once
+    struct SquareNode {
+        id: NodeId,
+        fail_on: Option<i64>,
+        running: Mutex<(usize, usize)>,
+    }
+
+    impl SquareNode {
+        fn new(fail_on: Option<i64>) -> Arc<Self> {
+            Arc::new(Self {
+                id: NodeId::new("square"),
+                fail_on,
+                running: Mutex::new((0, 0)),
+            })
+        }
+
+        fn peak_concurrency(&self) -> usize {
+            self.running.lock().1
+        }
+    }
+
+    #[async_trait]
+    impl Node for SquareNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let item = state.get("item")?.as_integer().unwrap();
+            {
+                let mut running = self.running.lock();
+                running.0 += 1;
+                running.1 = running.1.max(running.0);
+            }
+            // Later items finish first
+            tokio::time::sleep(Duration::from_millis(5 * (10 - item as u64))).await;
+            self.running.lock().0 -= 1;
+
+            if self.fail_on == Some(item) {
+                return Err(RGraphError::node(self.id.as_str(), "odd one out"));
+            }
+            state.set("item", item * item);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    fn numbers() -> GraphState {
+        let state = GraphState::new();
+        state.set("numbers", (0..10).map(StateValue::from).collect::<Vec<_>>());
+        state
+    }
+
+    async fn execute(map: MapNode) -> RGraphResult<GraphState> {
+        let graph = GraphBuilder::new("map")
+            .add_node("squares", Arc::new(map))
+            .await?
+            .build()?;
+        let results = ExecutionEngine::new().execute(&graph, numbers()).await?;
+        match results.errors.first() {
+            Some(error) => Err(RGraphError::execution(error.error_message.clone())),
+            None => Ok(results.final_state),
+        }
+    }
+
+    fn integers(value: StateValue) -> Vec<Option<i64>> {
+        match value {
+            StateValue::Array(values) => values.iter().map(StateValue::as_integer).collect(),
+            other => panic!("unexpected output: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_results_keep_the_input_order() {
+        let square = SquareNode::new(None);
+        let map = MapNode::new("squares", "numbers", "item", square.clone(), "squares", 3);
+
+        let state = execute(map).await.unwrap();
+
+        let expected: Vec<_> = (0..10).map(|n| Some(n * n)).collect();
+        assert_eq!(integers(state.get("squares").unwrap()), expected);
+        assert_eq!(square.peak_concurrency(), 3);
+        // Items ran on copies of the state
+        assert!(!state.contains_key("item"));
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_fails_the_node() {
+        let square = SquareNode::new(Some(4));
+        let map = MapNode::new("squares", "numbers", "item", square, "squares", 3);
+
+        let err = execute(map).await.unwrap_err();
+
+        assert!(err.to_string().contains("item 4 failed"));
+        assert!(err.to_string().contains("odd one out"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_keeps_the_other_results() {
+        let square = SquareNode::new(Some(4));
+        let map = MapNode::new("squares", "numbers", "item", square, "squares", 3)
+            .with_failure_policy(MapFailurePolicy::Collect);
+
+        let state = execute(map).await.unwrap();
+
+        let expected: Vec<_> = (0..10)
+            .map(|n| if n == 4 { None } else { Some(n * n) })
+            .collect();
+        assert_eq!(integers(state.get("squares").unwrap()), expected);
+        let errors = match state.get("squares_errors").unwrap() {
+            StateValue::Array(errors) => errors,
+            other => panic!("unexpected errors: {other:?}"),
+        };
+        assert_eq!(errors.len(), 1);
+        let StateValue::Object(error) = &errors[0] else {
+            panic!("unexpected error entry: {:?}", errors[0]);
+        };
+        assert_eq!(error["index"].as_integer(), Some(4));
+        assert!(error["message"]
+            .as_string()
+            .unwrap()
+            .contains("odd one out"));
+    }
+
+    #[tokio::test]
+    async fn test_input_must_be_a_list() {
+        let map = MapNode::new(
+            "squares",
+            "numbers",
+            "item",
+            SquareNode::new(None),
+            "out",
+            3,
+        );
+        let graph = GraphBuilder::new("map")
+            .add_node("squares", Arc::new(map))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let state = GraphState::new();
+        state.set("numbers", 7);
+
+        let results = ExecutionEngine::new().execute(&graph, state).await.unwrap();
+
+        assert!(results.errors[0].error_message.contains("not a list"));
+    }
+}
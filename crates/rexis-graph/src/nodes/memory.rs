@@ -0,0 +1,641 @@
+//! # Memory Node Implementation
+//!
+//! Memory nodes read from and write to an agent's memory through the typed
+//! APIs of [`AgentMemoryManager`]: facts, episodes, conversation messages and
+//! working memory.
+
+use super::tool::{render, ArgSource};
+use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId};
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use rexis_rag::agent::memory::{
+    AgentMemoryManager, EpisodicMemory, Fact, MemoryConfig, SemanticMemory, WorkingMemory,
+};
+use rexis_rag::rexis_llm::{ChatMessage, MessageRole};
+use rexis_rag::storage::MemoryValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The memory a node works on
+enum MemorySource {
+    /// A manager given when the node was built
+    Manager(Arc<AgentMemoryManager>),
+    /// The run's memory backend, for `agent_id` and `session_id` or else a
+    /// session per graph run
+    Context {
+        agent_id: String,
+        session_id: Option<String>,
+    },
+}
+
+impl MemorySource {
+    fn manager(&self, context: &ExecutionContext) -> RGraphResult<Arc<AgentMemoryManager>> {
+        match self {
+            MemorySource::Manager(manager) => Ok(manager.clone()),
+            MemorySource::Context {
+                agent_id,
+                session_id,
+            } => {
+                let backend = context.require_memory()?;
+                let session_id = session_id
+                    .clone()
+                    .unwrap_or_else(|| context.execution_id.clone());
+                let config = MemoryConfig::new(backend, agent_id.clone())
+                    .with_session_id(session_id)
+                    .with_persistence(true);
+                Ok(Arc::new(AgentMemoryManager::new(config)))
+            }
+        }
+    }
+}
+
+/// Text of a source, or the state key it is missing
+fn source_text(source: &ArgSource, state: &GraphState) -> Result<String, String> {
+    match source {
+        ArgSource::Value(serde_json::Value::String(text)) => Ok(text.clone()),
+        ArgSource::Value(value) => Ok(value.to_string()),
+        ArgSource::Key(key) => match state.get(key) {
+            Ok(StateValue::String(text)) => Ok(text),
+            Ok(value) => Ok(serde_json::Value::from(value).to_string()),
+            Err(_) => Err(key.clone()),
+        },
+        ArgSource::Template(template) => render(template, state),
+    }
+}
+
+/// Value of a state key as stored in memory
+fn memory_value(value: StateValue) -> MemoryValue {
+    match value {
+        StateValue::String(text) => MemoryValue::String(text),
+        StateValue::Integer(integer) => MemoryValue::Integer(integer),
+        StateValue::Float(float) => MemoryValue::Float(float),
+        StateValue::Boolean(flag) => MemoryValue::Boolean(flag),
+        StateValue::Bytes(bytes) => MemoryValue::Bytes(bytes),
+        other => MemoryValue::Json(other.into()),
+    }
+}
+
+/// Value read from memory as stored in the state
+fn state_value(value: MemoryValue) -> StateValue {
+    match value {
+        MemoryValue::String(text) => StateValue::String(text),
+        MemoryValue::Integer(integer) => StateValue::Integer(integer),
+        MemoryValue::Float(float) => StateValue::Float(float),
+        MemoryValue::Boolean(flag) => StateValue::Boolean(flag),
+        MemoryValue::Json(json) => StateValue::from(json),
+        MemoryValue::Bytes(bytes) => StateValue::Bytes(bytes),
+        MemoryValue::List(items) => StateValue::Array(items.into_iter().map(state_value).collect()),
+        MemoryValue::Map(entries) => StateValue::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, state_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// A fact as stored in the state
+fn fact_value(fact: Fact) -> StateValue {
+    let mut entry = HashMap::new();
+    entry.insert("subject".to_string(), StateValue::String(fact.subject));
+    entry.insert("predicate".to_string(), StateValue::String(fact.predicate));
+    entry.insert("object".to_string(), state_value(fact.object));
+    entry.insert("confidence".to_string(), StateValue::Float(fact.confidence));
+    StateValue::Object(entry)
+}
+
+/// A read performed by a [`MemoryReadNode`]
+enum ReadOp {
+    Facts {
+        subject: ArgSource,
+        output_key: String,
+    },
+    Episodes {
+        limit: usize,
+        output_key: String,
+    },
+    Working {
+        memory_key: String,
+        output_key: String,
+    },
+}
+
+/// A node that copies memory contents into the state
+///
+/// Reads run in the order they were declared. Facts are written as a list of
+/// objects with `subject`, `predicate`, `object` and `confidence`; a working
+/// memory key that is not set is written as null.
+pub struct MemoryReadNode {
+    id: NodeId,
+    name: String,
+    source: MemorySource,
+    reads: Vec<ReadOp>,
+}
+
+impl MemoryReadNode {
+    pub fn new(id: impl Into<NodeId>, manager: Arc<AgentMemoryManager>) -> Self {
+        Self::with_source(id.into(), MemorySource::Manager(manager))
+    }
+
+    /// Create a node reading the memory of `agent_id` from the run's memory
+    /// backend, in a session per graph run unless
+    /// [`with_session_id`](Self::with_session_id) names one
+    pub fn from_context(id: impl Into<NodeId>, agent_id: impl Into<String>) -> Self {
+        let source = MemorySource::Context {
+            agent_id: agent_id.into(),
+            session_id: None,
+        };
+        Self::with_source(id.into(), source)
+    }
+
+    fn with_source(id: NodeId, source: MemorySource) -> Self {
+        Self {
+            name: id.as_str().to_string(),
+            id,
+            source,
+            reads: Vec::new(),
+        }
+    }
+
+    /// Set the display name, which defaults to the node ID
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Use the session `session_id` of the run's memory backend; ignored
+    /// for nodes built with a manager
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        if let MemorySource::Context { session_id: id, .. } = &mut self.source {
+            *id = Some(session_id.into());
+        }
+        self
+    }
+
+    /// Write the facts about `subject` to `output_key`
+    pub fn facts_about(mut self, subject: ArgSource, output_key: impl Into<String>) -> Self {
+        self.reads.push(ReadOp::Facts {
+            subject,
+            output_key: output_key.into(),
+        });
+        self
+    }
+
+    /// Write a summary of the `limit` most recent episodes to `output_key`,
+    /// ready to be added to a prompt
+    pub fn recent_episodes(mut self, limit: usize, output_key: impl Into<String>) -> Self {
+        self.reads.push(ReadOp::Episodes {
+            limit,
+            output_key: output_key.into(),
+        });
+        self
+    }
+
+    /// Write the working memory key `memory_key` to `output_key`
+    pub fn working_key(
+        mut self,
+        memory_key: impl Into<String>,
+        output_key: impl Into<String>,
+    ) -> Self {
+        self.reads.push(ReadOp::Working {
+            memory_key: memory_key.into(),
+            output_key: output_key.into(),
+        });
+        self
+    }
+}
+
+#[async_trait]
+impl Node for MemoryReadNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let manager = self.source.manager(context)?;
+        let failed = |e: rexis_rag::RragError| {
+            RGraphError::node(self.id.as_str(), format!("memory read failed: {}", e))
+        };
+        let node = context.current_node.as_str();
+
+        for read in &self.reads {
+            match read {
+                ReadOp::Facts {
+                    subject,
+                    output_key,
+                } => {
+                    let subject = source_text(subject, state).map_err(|key| {
+                        RGraphError::node(
+                            self.id.as_str(),
+                            format!("state key '{}' for the fact subject is missing", key),
+                        )
+                    })?;
+                    let semantic =
+                        SemanticMemory::new(manager.storage(), manager.agent_id().to_string());
+                    let facts = semantic.find_by_subject(&subject).await.map_err(failed)?;
+                    let facts = facts.into_iter().map(fact_value).collect::<Vec<_>>();
+                    state.set_with_context(node, output_key, StateValue::Array(facts));
+                }
+                ReadOp::Episodes { limit, output_key } => {
+                    let episodic =
+                        EpisodicMemory::new(manager.storage(), manager.agent_id().to_string());
+                    let summary = episodic
+                        .generate_context_summary(*limit)
+                        .await
+                        .map_err(failed)?;
+                    state.set_with_context(node, output_key, summary);
+                }
+                ReadOp::Working {
+                    memory_key,
+                    output_key,
+                } => {
+                    let working = WorkingMemory::new_persistent(
+                        manager.storage(),
+                        manager.session_id().to_string(),
+                    );
+                    let value = working.get(memory_key).await.map_err(failed)?;
+                    let value = value.map(state_value).unwrap_or(StateValue::Null);
+                    state.set_with_context(node, output_key, value);
+                }
+            }
+        }
+
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "MemoryRead"
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        self.reads
+            .iter()
+            .filter_map(|read| match read {
+                ReadOp::Facts {
+                    subject: ArgSource::Key(key),
+                    ..
+                } => Some(key.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        self.reads
+            .iter()
+            .map(|read| match read {
+                ReadOp::Facts { output_key, .. }
+                | ReadOp::Episodes { output_key, .. }
+                | ReadOp::Working { output_key, .. } => output_key.as_str(),
+            })
+            .collect()
+    }
+}
+
+/// A write performed by a [`MemoryWriteNode`]
+enum WriteOp {
+    Fact {
+        subject: ArgSource,
+        predicate: String,
+        object_key: String,
+    },
+    Message {
+        role: MessageRole,
+        content_key: String,
+    },
+    Working {
+        memory_key: String,
+        value_key: String,
+    },
+}
+
+impl WriteOp {
+    fn input_key(&self) -> &str {
+        match self {
+            WriteOp::Fact { object_key, .. } => object_key,
+            WriteOp::Message { content_key, .. } => content_key,
+            WriteOp::Working { value_key, .. } => value_key,
+        }
+    }
+}
+
+/// A node that stores state values in memory
+///
+/// Writes run in the order they were declared, and fail the node when a
+/// state key they read is missing. Storing a fact that is already known
+/// refreshes it instead of adding a copy.
+pub struct MemoryWriteNode {
+    id: NodeId,
+    name: String,
+    source: MemorySource,
+    writes: Vec<WriteOp>,
+}
+
+impl MemoryWriteNode {
+    pub fn new(id: impl Into<NodeId>, manager: Arc<AgentMemoryManager>) -> Self {
+        Self::with_source(id.into(), MemorySource::Manager(manager))
+    }
+
+    /// Create a node writing the memory of `agent_id` to the run's memory
+    /// backend, in a session per graph run unless
+    /// [`with_session_id`](Self::with_session_id) names one
+    pub fn from_context(id: impl Into<NodeId>, agent_id: impl Into<String>) -> Self {
+        let source = MemorySource::Context {
+            agent_id: agent_id.into(),
+            session_id: None,
+        };
+        Self::with_source(id.into(), source)
+    }
+
+    fn with_source(id: NodeId, source: MemorySource) -> Self {
+        Self {
+            name: id.as_str().to_string(),
+            id,
+            source,
+            writes: Vec::new(),
+        }
+    }
+
+    /// Set the display name, which defaults to the node ID
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Use the session `session_id` of the run's memory backend; ignored
+    /// for nodes built with a manager
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        if let MemorySource::Context { session_id: id, .. } = &mut self.source {
+            *id = Some(session_id.into());
+        }
+        self
+    }
+
+    /// Store the fact that `subject` relates to the value at `object_key`
+    /// through `predicate`
+    pub fn store_fact(
+        mut self,
+        subject: ArgSource,
+        predicate: impl Into<String>,
+        object_key: impl Into<String>,
+    ) -> Self {
+        self.writes.push(WriteOp::Fact {
+            subject,
+            predicate: predicate.into(),
+            object_key: object_key.into(),
+        });
+        self
+    }
+
+    /// Append the text at `content_key` to the conversation as a `role`
+    /// message
+    pub fn append_message(mut self, role: MessageRole, content_key: impl Into<String>) -> Self {
+        self.writes.push(WriteOp::Message {
+            role,
+            content_key: content_key.into(),
+        });
+        self
+    }
+
+    /// Set the working memory key `memory_key` to the value at `value_key`
+    pub fn set_working_key(
+        mut self,
+        memory_key: impl Into<String>,
+        value_key: impl Into<String>,
+    ) -> Self {
+        self.writes.push(WriteOp::Working {
+            memory_key: memory_key.into(),
+            value_key: value_key.into(),
+        });
+        self
+    }
+
+    fn missing(&self, key: &str) -> RGraphError {
+        RGraphError::node(self.id.as_str(), format!("state key '{}' is missing", key))
+    }
+}
+
+#[async_trait]
+impl Node for MemoryWriteNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let manager = self.source.manager(context)?;
+        let failed = |e: rexis_rag::RragError| {
+            RGraphError::node(self.id.as_str(), format!("memory write failed: {}", e))
+        };
+
+        for write in &self.writes {
+            let value = state
+                .get(write.input_key())
+                .map_err(|_| self.missing(write.input_key()))?;
+            match write {
+                WriteOp::Fact {
+                    subject, predicate, ..
+                } => {
+                    let subject = source_text(subject, state).map_err(|key| self.missing(&key))?;
+                    let fact = Fact::new(subject, predicate.clone(), memory_value(value))
+                        .with_metadata("source", "graph")
+                        .with_metadata("session_id", manager.session_id());
+                    let semantic =
+                        SemanticMemory::new(manager.storage(), manager.agent_id().to_string());
+                    semantic.upsert_fact(fact).await.map_err(failed)?;
+                }
+                WriteOp::Message { role, content_key } => {
+                    let content = match value {
+                        StateValue::String(text) => text,
+                        _ => {
+                            return Err(RGraphError::node(
+                                self.id.as_str(),
+                                format!("message content '{}' must be a string", content_key),
+                            ))
+                        }
+                    };
+                    manager
+                        .add_conversation_message(ChatMessage::new(*role, content))
+                        .await
+                        .map_err(failed)?;
+                }
+                WriteOp::Working { memory_key, .. } => {
+                    let working = WorkingMemory::new_persistent(
+                        manager.storage(),
+                        manager.session_id().to_string(),
+                    );
+                    working
+                        .set(memory_key, memory_value(value))
+                        .await
+                        .map_err(failed)?;
+                }
+            }
+        }
+
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "MemoryWrite"
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        for write in &self.writes {
+            if let WriteOp::Fact {
+                subject: ArgSource::Key(key),
+                ..
+            } = write
+            {
+                keys.push(key.as_str());
+            }
+            keys.push(write.input_key());
+        }
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::GraphBuilder;
+    use crate::execution::ExecutionEngine;
+    use rexis_rag::agent::memory::Episode;
+    use rexis_rag::storage::{InMemoryStorage, Memory};
+
+    fn manager() -> Arc<AgentMemoryManager> {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let config = MemoryConfig::new(storage, "assistant").with_session_id("session-1");
+        Arc::new(AgentMemoryManager::new(config))
+    }
+
+    #[tokio::test]
+    async fn test_fact_written_in_one_node_is_read_in_a_later_one() {
+        let manager = manager();
+        let remember = MemoryWriteNode::new("remember", manager.clone()).store_fact(
+            ArgSource::Template("user:{user_id}".to_string()),
+            "prefers",
+            "preference",
+        );
+        let recall = MemoryReadNode::new("recall", manager.clone())
+            .facts_about(ArgSource::Template("user:{user_id}".to_string()), "facts");
+        let graph = GraphBuilder::new("preferences")
+            .add_node("remember", Arc::new(remember))
+            .await
+            .unwrap()
+            .add_node("recall", Arc::new(recall))
+            .await
+            .unwrap()
+            .add_edge("remember", "recall")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let state = GraphState::new()
+            .with_input("user_id", "alice")
+            .with_input("preference", "window seats");
+        let results = ExecutionEngine::new().execute(&graph, state).await.unwrap();
+
+        assert!(results.metrics.success);
+        let facts = match results.final_state.get("facts").unwrap() {
+            StateValue::Array(facts) => facts,
+            other => panic!("expected a list of facts, got {:?}", other),
+        };
+        assert_eq!(facts.len(), 1);
+        let fact: serde_json::Value = facts[0].clone().into();
+        assert_eq!(fact["subject"], "user:alice");
+        assert_eq!(fact["predicate"], "prefers");
+        assert_eq!(fact["object"], "window seats");
+
+        // The fact went through the typed API
+        let semantic = SemanticMemory::new(manager.storage(), "assistant".to_string());
+        let stored = semantic.find_by_subject("user:alice").await.unwrap();
+        assert_eq!(stored[0].metadata.get("source").unwrap(), "graph");
+    }
+
+    #[tokio::test]
+    async fn test_working_memory_and_messages_use_the_context_backend() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let write = MemoryWriteNode::from_context("write", "assistant")
+            .with_session_id("session-1")
+            .set_working_key("draft", "text")
+            .append_message(MessageRole::User, "text");
+        let read = MemoryReadNode::from_context("read", "assistant")
+            .with_session_id("session-1")
+            .working_key("draft", "restored")
+            .working_key("unset", "nothing");
+        assert_eq!(write.input_keys(), ["text", "text"]);
+
+        let context = ExecutionContext::new("graph".to_string(), NodeId::new("write"))
+            .with_memory(storage.clone());
+        let mut state = GraphState::new().with_input("text", "hello");
+        write.execute(&mut state, &context).await.unwrap();
+        read.execute(&mut state, &context).await.unwrap();
+
+        assert_eq!(state.get("restored").unwrap().as_string(), Some("hello"));
+        assert_eq!(state.get("nothing").unwrap(), StateValue::Null);
+        let config = MemoryConfig::new(storage, "assistant")
+            .with_session_id("session-1")
+            .with_persistence(true);
+        let messages = AgentMemoryManager::new(config)
+            .get_conversation_messages()
+            .await
+            .unwrap();
+        assert_eq!(messages.last().unwrap().text(), Some("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_recent_episodes_become_a_context_string() {
+        let manager = manager();
+        let episodic = EpisodicMemory::new(manager.storage(), "assistant".to_string());
+        episodic
+            .store_episode(Episode::new("Booked a flight to Oslo"))
+            .await
+            .unwrap();
+        let read = MemoryReadNode::new("read", manager).recent_episodes(3, "history");
+
+        let mut state = GraphState::new();
+        let context = ExecutionContext::new("graph".to_string(), NodeId::new("read"));
+        read.execute(&mut state, &context).await.unwrap();
+
+        let history = state.get("history").unwrap();
+        assert!(history
+            .as_string()
+            .unwrap()
+            .contains("Booked a flight to Oslo"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_state_key_and_backend_fail_the_node() {
+        let write = MemoryWriteNode::new("write", manager()).store_fact(
+            ArgSource::Key("user".to_string()),
+            "prefers",
+            "preference",
+        );
+        let context = ExecutionContext::new("graph".to_string(), NodeId::new("write"));
+        let mut state = GraphState::new().with_input("preference", "tea");
+
+        let err = write.execute(&mut state, &context).await.unwrap_err();
+        assert!(err.to_string().contains("state key 'user' is missing"));
+
+        let read = MemoryReadNode::from_context("read", "assistant").working_key("a", "b");
+        let err = read.execute(&mut state, &context).await.unwrap_err();
+        assert!(matches!(err, RGraphError::MemoryUnavailable { .. }));
+    }
+}
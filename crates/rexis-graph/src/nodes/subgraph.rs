@@ -0,0 +1,352 @@
+//! # Subgraph Node Implementation
+//!
+//! Subgraph nodes run a whole workflow graph as one step of another graph.
+
+use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId, WorkflowGraph};
+use crate::execution::{ExecutionConfig, ExecutionEngine};
+use crate::state::GraphState;
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A node that runs an inner graph to completion
+///
+/// The inner graph starts from a fresh state holding the mapped parent keys,
+/// and only the mapped outputs are copied back, so the same graph can be
+/// embedded in several parents. Its trace is nested under this node's step.
+pub struct SubgraphNode {
+    id: NodeId,
+    name: String,
+    graph: Arc<WorkflowGraph>,
+    /// Parent state key -> inner state key
+    input_mapping: HashMap<String, String>,
+    /// Inner state key -> parent state key
+    output_mapping: HashMap<String, String>,
+    engine: ExecutionEngine,
+}
+
+impl SubgraphNode {
+    pub fn new(
+        id: impl Into<NodeId>,
+        graph: Arc<WorkflowGraph>,
+        input_mapping: HashMap<String, String>,
+        output_mapping: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: graph.name().to_string(),
+            graph,
+            input_mapping,
+            output_mapping,
+            engine: ExecutionEngine::new(),
+        }
+    }
+
+    /// Set the display name, which defaults to the inner graph's name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the configuration the inner graph runs with; its
+    /// `max_execution_depth` limits how deeply subgraphs nest
+    pub fn with_config(mut self, config: ExecutionConfig) -> Self {
+        self.engine = ExecutionEngine::with_config(config);
+        self
+    }
+}
+
+#[async_trait]
+impl Node for SubgraphNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let inner_state = GraphState::new();
+        for (parent_key, inner_key) in &self.input_mapping {
+            let value = state.get(parent_key).map_err(|_| {
+                RGraphError::node(
+                    self.id.as_str(),
+                    format!("input '{}' is missing from the state", parent_key),
+                )
+            })?;
+            inner_state.set(inner_key.clone(), value);
+        }
+
+        let results = self
+            .engine
+            .execute_nested(&self.graph, inner_state, context)
+            .await?;
+        context.record_subgraph_trace(results.trace);
+
+        if !results.metrics.success {
+            let cause = results
+                .errors
+                .first()
+                .map(|e| format!("'{}' failed: {}", e.node_id, e.error_message))
+                .unwrap_or_else(|| "execution failed".to_string());
+            return Err(RGraphError::node(
+                self.id.as_str(),
+                format!("subgraph '{}' {}", self.graph.name(), cause),
+            ));
+        }
+
+        for (inner_key, parent_key) in &self.output_mapping {
+            let value = results.final_state.get(inner_key).map_err(|_| {
+                RGraphError::node(
+                    self.id.as_str(),
+                    format!(
+                        "subgraph '{}' did not produce output '{}'",
+                        self.graph.name(),
+                        inner_key
+                    ),
+                )
+            })?;
+            state.set(parent_key.clone(), value);
+        }
+
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "Subgraph"
+    }
+
+    fn occupies_slot(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.graph.description()
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        self.input_mapping.keys().map(String::as_str).collect()
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        self.output_mapping.values().map(String::as_str).collect()
+    }
+
+    fn validate(&self, _state: &GraphState) -> RGraphResult<()> {
+        self.graph.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::GraphBuilder;
+    use crate::execution::Transition;
+    use crate::state::StateValue;
+
+    // Node copying `from` to `to` with a suffix appended
+    struct AppendNode {
+        id: NodeId,
+        from: &'static str,
+        to: &'static str,
+    }
+
+    impl AppendNode {
+        fn new(id: &str, from: &'static str, to: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                id: NodeId::new(id),
+                from,
+                to,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Node for AppendNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let value = state.get(self.from)?;
+            let text = value.as_string().unwrap_or_default();
+            state.set(self.to, format!("{} > {}", text, self.id.as_str()));
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    fn mapping(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    }
+
+    async fn research_graph() -> Arc<WorkflowGraph> {
+        let graph = GraphBuilder::new("research")
+            .add_node("search", AppendNode::new("search", "query", "results"))
+            .await
+            .unwrap()
+            .add_node("read", AppendNode::new("read", "results", "notes"))
+            .await
+            .unwrap()
+            .add_node(
+                "summarize",
+                AppendNode::new("summarize", "notes", "summary"),
+            )
+            .await
+            .unwrap()
+            .add_edge("search", "read")
+            .unwrap()
+            .add_edge("read", "summarize")
+            .unwrap()
+            .build()
+            .unwrap();
+        Arc::new(graph)
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_maps_state_and_nests_its_trace() {
+        let research = SubgraphNode::new(
+            "research",
+            research_graph().await,
+            mapping(&[("topic", "query")]),
+            mapping(&[("summary", "findings")]),
+        );
+        let graph = GraphBuilder::new("report")
+            .add_node("research", Arc::new(research))
+            .await
+            .unwrap()
+            .add_node("write", AppendNode::new("write", "findings", "report"))
+            .await
+            .unwrap()
+            .add_edge("research", "write")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("topic", "graphs"))
+            .await
+            .unwrap();
+
+        let state = &results.final_state;
+        assert_eq!(
+            state.get("findings").unwrap(),
+            StateValue::from("graphs > search > read > summarize")
+        );
+        assert_eq!(
+            state.get("report").unwrap().as_string(),
+            Some("graphs > search > read > summarize > write")
+        );
+        // Inner keys stay inside the subgraph
+        assert!(!state.contains_key("notes"));
+        assert!(!state.contains_key("query"));
+
+        assert_eq!(results.trace.len(), 2);
+        let step = &results.trace[0];
+        assert_eq!(step.node_id, NodeId::new("research"));
+        assert_eq!(
+            step.transition,
+            Transition::Edges(vec![NodeId::new("write")])
+        );
+        let inner: Vec<_> = step.children.iter().map(|c| c.node_id.as_str()).collect();
+        assert_eq!(inner, ["search", "read", "summarize"]);
+        assert!(results.trace[1].children.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_missing_input_fails_the_node() {
+        let research = SubgraphNode::new(
+            "research",
+            research_graph().await,
+            mapping(&[("topic", "query")]),
+            HashMap::new(),
+        );
+        let graph = GraphBuilder::new("report")
+            .add_node("research", Arc::new(research))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert!(results.errors[0].error_message.contains("input 'topic'"));
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_nesting_depth_is_limited() {
+        let config = ExecutionConfig {
+            max_execution_depth: 1,
+            ..ExecutionConfig::default()
+        };
+        let inner = SubgraphNode::new(
+            "research",
+            research_graph().await,
+            mapping(&[("topic", "query")]),
+            HashMap::new(),
+        )
+        .with_config(config.clone());
+        let middle = GraphBuilder::new("middle")
+            .add_node("research", Arc::new(inner))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let outer = SubgraphNode::new(
+            "middle",
+            Arc::new(middle),
+            mapping(&[("topic", "topic")]),
+            HashMap::new(),
+        )
+        .with_config(config);
+        let graph = GraphBuilder::new("outer")
+            .add_node("middle", Arc::new(outer))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("topic", "graphs"))
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        assert!(results.errors[0]
+            .error_message
+            .contains("maximum nesting depth of 1"));
+    }
+
+    #[tokio::test]
+    async fn test_graph_cannot_run_inside_itself() {
+        let research = research_graph().await;
+        let context = ExecutionContext::new(research.id().to_string(), NodeId::new("search"));
+
+        let err = ExecutionEngine::new()
+            .execute_nested(&research, GraphState::new(), &context)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("cannot run inside itself"));
+    }
+}
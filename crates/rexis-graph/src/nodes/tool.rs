@@ -1,6 +1,10 @@
 //! # Tool Node Implementation
 //!
 //! Tool nodes directly execute tools without agent reasoning.
+//!
+//! A node either calls a graph [`Tool`] or, with the `rexis-rag-integration`
+//! feature, an agent tool through a [`rexis_rag::ToolExecutor`], whose
+//! timeouts, retries and argument validation then apply.
 
 use crate::core::{ExecutionContext, ExecutionResult, Node, NodeId};
 use crate::state::{GraphState, StateValue};
@@ -9,6 +13,11 @@ use crate::{RGraphError, RGraphResult};
 use async_trait::async_trait;
 use std::sync::Arc;
 
+#[cfg(feature = "rexis-rag-integration")]
+use rexis_rag::agent::Tool as AgentTool;
+#[cfg(feature = "rexis-rag-integration")]
+use rexis_rag::{ArgumentViolation, ToolExecutor, ToolFailure};
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -21,12 +30,178 @@ pub struct ToolNodeConfig {
     pub output_key: String,
 }
 
+/// Where the value of a tool argument comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgSource {
+    /// A fixed value
+    Value(serde_json::Value),
+    /// The value of a state key, left out when the key is missing
+    Key(String),
+    /// Text with `{key}` placeholders filled from the state; `{{` and `}}`
+    /// stand for literal braces
+    Template(String),
+}
+
+/// How a tool's JSON arguments are built from the state
+#[derive(Debug, Clone, Default)]
+pub struct ArgMapping {
+    args: Vec<(String, ArgSource)>,
+}
+
+impl ArgMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pass `value` as the argument `arg`
+    pub fn with_value(
+        mut self,
+        arg: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.args.push((arg.into(), ArgSource::Value(value.into())));
+        self
+    }
+
+    /// Pass the value of `state_key` as the argument `arg`
+    pub fn with_key(mut self, arg: impl Into<String>, state_key: impl Into<String>) -> Self {
+        self.args
+            .push((arg.into(), ArgSource::Key(state_key.into())));
+        self
+    }
+
+    /// Pass `template` filled from the state as the argument `arg`
+    pub fn with_template(mut self, arg: impl Into<String>, template: impl Into<String>) -> Self {
+        self.args
+            .push((arg.into(), ArgSource::Template(template.into())));
+        self
+    }
+
+    /// Where the argument `arg` comes from
+    pub fn source(&self, arg: &str) -> Option<&ArgSource> {
+        self.args
+            .iter()
+            .find(|(name, _)| name == arg)
+            .map(|(_, source)| source)
+    }
+
+    /// State keys the arguments are read from
+    pub fn state_keys(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        for (_, source) in &self.args {
+            match source {
+                ArgSource::Value(_) => {}
+                ArgSource::Key(key) => keys.push(key.as_str()),
+                ArgSource::Template(template) => keys.extend(placeholders(template)),
+            }
+        }
+        keys
+    }
+
+    /// Build the arguments object from `state`
+    ///
+    /// Fails when a template refers to a missing state key.
+    pub fn build(&self, state: &GraphState) -> RGraphResult<serde_json::Value> {
+        let mut arguments = serde_json::Map::new();
+        for (arg, source) in &self.args {
+            let value = match source {
+                ArgSource::Value(value) => value.clone(),
+                ArgSource::Key(key) => match state.get(key) {
+                    Ok(value) => value.into(),
+                    Err(_) => continue,
+                },
+                ArgSource::Template(template) => {
+                    serde_json::Value::String(render(template, state).map_err(|key| {
+                        RGraphError::state(format!(
+                            "state key '{}' used by argument '{}' is missing",
+                            key, arg
+                        ))
+                    })?)
+                }
+            };
+            arguments.insert(arg.clone(), value);
+        }
+        Ok(serde_json::Value::Object(arguments))
+    }
+}
+
+/// Split a template into literal text and placeholder names
+fn parse_template(template: &str) -> Vec<(bool, &str)> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix("{{") {
+            parts.push((false, "{"));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("}}") {
+            parts.push((false, "}"));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('{') {
+            match tail.find('}') {
+                Some(end) => {
+                    parts.push((true, &tail[..end]));
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    parts.push((false, rest));
+                    rest = "";
+                }
+            }
+        } else {
+            let end = rest.find(['{', '}']).unwrap_or(rest.len()).max(1);
+            parts.push((false, &rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    parts
+}
+
+/// State keys a template refers to
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    parse_template(template)
+        .into_iter()
+        .filter_map(|(is_key, text)| is_key.then_some(text))
+}
+
+/// Fill a template from the state, or return the first missing key
+pub(super) fn render(template: &str, state: &GraphState) -> Result<String, String> {
+    let mut text = String::new();
+    for (is_key, part) in parse_template(template) {
+        if !is_key {
+            text.push_str(part);
+            continue;
+        }
+        match state.get(part) {
+            Ok(StateValue::String(value)) => text.push_str(&value),
+            Ok(value) => text.push_str(&serde_json::Value::from(value).to_string()),
+            Err(_) => return Err(part.to_string()),
+        }
+    }
+    Ok(text)
+}
+
+/// How a tool node runs its tool
+enum ToolRunner {
+    /// A graph tool called with state keys copied to arguments
+    Graph {
+        tool: Arc<dyn Tool>,
+        config: ToolNodeConfig,
+    },
+    /// An agent tool run through an executor
+    #[cfg(feature = "rexis-rag-integration")]
+    Executor {
+        executor: Box<ToolExecutor>,
+        tool_name: String,
+        arguments: ArgMapping,
+        output_key: String,
+    },
+}
+
 /// A node that executes a specific tool
 pub struct ToolNode {
     id: NodeId,
     name: String,
-    tool: Arc<dyn Tool>,
-    config: ToolNodeConfig,
+    runner: ToolRunner,
 }
 
 impl ToolNode {
@@ -39,10 +214,159 @@ impl ToolNode {
         Self {
             id: id.into(),
             name: name.into(),
-            tool,
-            config,
+            runner: ToolRunner::Graph { tool, config },
+        }
+    }
+
+    /// Create a node running an agent tool with arguments built by
+    /// `arguments`, writing its output to `output_key`
+    ///
+    /// The tool runs in an executor of its own with the default policies; use
+    /// [`from_executor`](Self::from_executor) for timeouts and retries.
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn from_tool(
+        id: impl Into<NodeId>,
+        tool: Box<dyn AgentTool>,
+        arguments: ArgMapping,
+        output_key: impl Into<String>,
+    ) -> Self {
+        let tool_name = tool.name().to_string();
+        let mut executor = ToolExecutor::empty();
+        executor
+            .register(tool)
+            .expect("an empty executor has no tool of the same name");
+        Self::with_executor(id, executor, tool_name, arguments, output_key.into())
+    }
+
+    /// Create a node running the tool `tool_name` of `executor` under the
+    /// executor's timeouts, retry policy and argument validation
+    #[cfg(feature = "rexis-rag-integration")]
+    pub fn from_executor(
+        id: impl Into<NodeId>,
+        executor: ToolExecutor,
+        tool_name: impl Into<String>,
+        arguments: ArgMapping,
+        output_key: impl Into<String>,
+    ) -> RGraphResult<Self> {
+        let tool_name = tool_name.into();
+        if executor.get(&tool_name).is_none() {
+            return Err(RGraphError::config(format!(
+                "the executor has no tool named '{}'",
+                tool_name
+            )));
+        }
+        Ok(Self::with_executor(
+            id,
+            executor,
+            tool_name,
+            arguments,
+            output_key.into(),
+        ))
+    }
+
+    #[cfg(feature = "rexis-rag-integration")]
+    fn with_executor(
+        id: impl Into<NodeId>,
+        executor: ToolExecutor,
+        tool_name: String,
+        arguments: ArgMapping,
+        output_key: String,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: tool_name.clone(),
+            runner: ToolRunner::Executor {
+                executor: Box::new(executor),
+                tool_name,
+                arguments,
+                output_key,
+            },
         }
     }
+
+    /// Set the display name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Explain a violation of the tool's schema in terms of the state key
+    /// the offending argument came from
+    #[cfg(feature = "rexis-rag-integration")]
+    fn describe_violation(&self, arguments: &ArgMapping, violation: &ArgumentViolation) -> String {
+        let path = violation.path.strip_prefix("$.").unwrap_or("");
+        let arg = path.split(['.', '[']).next().unwrap_or_default();
+        let source = match arguments.source(arg) {
+            Some(ArgSource::Key(key)) => format!("state key '{}' for argument '{}'", key, arg),
+            Some(ArgSource::Template(template)) => {
+                format!("template '{}' for argument '{}'", template, arg)
+            }
+            Some(ArgSource::Value(_)) => format!("fixed value for argument '{}'", arg),
+            None if arg.is_empty() => "arguments".to_string(),
+            None => format!("unmapped argument '{}'", arg),
+        };
+        format!("{}: {}", source, violation.message)
+    }
+
+    #[cfg(feature = "rexis-rag-integration")]
+    async fn execute_with_executor(
+        &self,
+        executor: &ToolExecutor,
+        tool_name: &str,
+        arguments: &ArgMapping,
+        output_key: &str,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<()> {
+        let args = arguments.build(state).map_err(|e| match e {
+            RGraphError::State { message } => RGraphError::node(self.id.as_str(), message),
+            other => other,
+        })?;
+        let call = rexis_rag::rexis_llm::ToolCall::function(
+            format!("{}-{}", context.execution_id, self.id.as_str()),
+            tool_name,
+            args,
+        );
+
+        let execution = executor.execute_with_policy(&call).await;
+        match execution.failure {
+            None => {}
+            Some(ToolFailure::InvalidArguments { violations }) => {
+                let problems: Vec<_> = violations
+                    .iter()
+                    .map(|violation| self.describe_violation(arguments, violation))
+                    .collect();
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!(
+                        "arguments for tool '{}' are invalid: {}",
+                        tool_name,
+                        problems.join("; ")
+                    ),
+                ));
+            }
+            Some(failure) => {
+                return Err(RGraphError::node(
+                    self.id.as_str(),
+                    format!(
+                        "tool '{}' failed after {} attempt(s): {}",
+                        tool_name, execution.attempts, failure
+                    ),
+                ));
+            }
+        }
+
+        // Structured outputs are stored as JSON, anything else as text
+        let content = execution.message.text().unwrap_or_default();
+        let output = serde_json::from_str(content)
+            .unwrap_or_else(|_| serde_json::Value::String(content.to_string()));
+        state.set_with_context(
+            context.current_node.as_str(),
+            output_key,
+            StateValue::from(output),
+        );
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -52,10 +376,27 @@ impl Node for ToolNode {
         state: &mut GraphState,
         context: &ExecutionContext,
     ) -> RGraphResult<ExecutionResult> {
+        let (tool, config) = match &self.runner {
+            ToolRunner::Graph { tool, config } => (tool, config),
+            #[cfg(feature = "rexis-rag-integration")]
+            ToolRunner::Executor {
+                executor,
+                tool_name,
+                arguments,
+                output_key,
+            } => {
+                self.execute_with_executor(
+                    executor, tool_name, arguments, output_key, state, context,
+                )
+                .await?;
+                return Ok(ExecutionResult::Continue);
+            }
+        };
+
         // Build arguments from state using mappings
         let mut arguments = serde_json::Map::new();
 
-        for (state_key, arg_key) in &self.config.argument_mappings {
+        for (state_key, arg_key) in &config.argument_mappings {
             if let Ok(value) = state.get(state_key) {
                 let json_value: serde_json::Value = value.into();
                 arguments.insert(arg_key.clone(), json_value);
@@ -65,12 +406,12 @@ impl Node for ToolNode {
         let arguments_json = serde_json::Value::Object(arguments);
 
         // Execute the tool
-        match self.tool.execute(&arguments_json, state).await {
+        match tool.execute(&arguments_json, state).await {
             Ok(result) => {
                 // Store result in state
                 state.set_with_context(
                     context.current_node.as_str(),
-                    &self.config.output_key,
+                    &config.output_key,
                     StateValue::from(result.output),
                 );
                 Ok(ExecutionResult::Continue)
@@ -87,15 +428,162 @@ impl Node for ToolNode {
         &self.name
     }
 
+    fn node_type(&self) -> &str {
+        "Tool"
+    }
+
     fn input_keys(&self) -> Vec<&str> {
-        self.config
-            .argument_mappings
-            .keys()
-            .map(|s| s.as_str())
-            .collect()
+        match &self.runner {
+            ToolRunner::Graph { config, .. } => config
+                .argument_mappings
+                .keys()
+                .map(|s| s.as_str())
+                .collect(),
+            #[cfg(feature = "rexis-rag-integration")]
+            ToolRunner::Executor { arguments, .. } => arguments.state_keys(),
+        }
     }
 
     fn output_keys(&self) -> Vec<&str> {
-        vec![&self.config.output_key]
+        match &self.runner {
+            ToolRunner::Graph { config, .. } => vec![&config.output_key],
+            #[cfg(feature = "rexis-rag-integration")]
+            ToolRunner::Executor { output_key, .. } => vec![output_key],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_fill_placeholders_from_state() {
+        let state = GraphState::new()
+            .with_input("host", "example.com")
+            .with_input("id", 7);
+
+        assert_eq!(
+            render("https://{host}/items/{id}?q={{x}}", &state).unwrap(),
+            "https://example.com/items/7?q={x}"
+        );
+        assert_eq!(render("{host}/{missing}", &state).unwrap_err(), "missing");
+        assert_eq!(
+            placeholders("{host}/{{literal}}/{id}").collect::<Vec<_>>(),
+            ["host", "id"]
+        );
+    }
+
+    #[test]
+    fn test_arg_mapping_builds_arguments() {
+        let mapping = ArgMapping::new()
+            .with_value("method", "GET")
+            .with_key("limit", "page_size")
+            .with_key("cursor", "next_cursor")
+            .with_template("url", "https://{host}/search");
+        let state = GraphState::new()
+            .with_input("page_size", 20)
+            .with_input("host", "example.com");
+
+        assert_eq!(
+            mapping.build(&state).unwrap(),
+            serde_json::json!({
+                "method": "GET",
+                "limit": 20,
+                "url": "https://example.com/search",
+            })
+        );
+        assert_eq!(mapping.state_keys(), ["page_size", "next_cursor", "host"]);
+
+        let err = mapping.build(&GraphState::new()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("state key 'host' used by argument 'url' is missing"));
+    }
+
+    #[cfg(feature = "rexis-rag-integration")]
+    mod executor {
+        use super::*;
+        use crate::core::GraphBuilder;
+        use crate::execution::ExecutionEngine;
+        use rexis_rag::agent::tools::HttpTool;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        /// HTTP tool reaching the mock server, which listens on loopback
+        fn http_node(arguments: ArgMapping) -> ToolNode {
+            let tool = HttpTool::new().allow_private_networks(true);
+            ToolNode::from_tool("fetch", Box::new(tool), arguments, "response")
+        }
+
+        fn item_arguments() -> ArgMapping {
+            ArgMapping::new()
+                .with_value("method", "GET")
+                .with_template("url", "{base_url}/items/{item_id}")
+                .with_key("timeout_secs", "timeout")
+        }
+
+        #[tokio::test]
+        async fn test_state_keys_are_mapped_into_the_tool_call() {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/items/7"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":7}"#))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let node = http_node(item_arguments());
+            assert_eq!(node.input_keys(), ["base_url", "item_id", "timeout"]);
+            let graph = GraphBuilder::new("fetch_item")
+                .add_node("fetch", Arc::new(node))
+                .await
+                .unwrap()
+                .build()
+                .unwrap();
+
+            let state = GraphState::new()
+                .with_input("base_url", server.uri())
+                .with_input("item_id", 7);
+            let results = ExecutionEngine::new().execute(&graph, state).await.unwrap();
+
+            assert!(results.metrics.success);
+            let response: serde_json::Value = results.final_state.get("response").unwrap().into();
+            assert_eq!(response["status"], 200);
+            assert_eq!(response["body"], r#"{"id":7}"#);
+        }
+
+        #[tokio::test]
+        async fn test_invalid_arguments_name_the_state_key() {
+            let node = http_node(item_arguments());
+            let context = ExecutionContext::new("graph".to_string(), NodeId::new("fetch"));
+            let mut state = GraphState::new()
+                .with_input("base_url", "http://127.0.0.1:9")
+                .with_input("item_id", 7)
+                .with_input("timeout", "soon");
+
+            let err = node.execute(&mut state, &context).await.unwrap_err();
+
+            assert!(matches!(&err, RGraphError::Node { node_id, .. } if node_id == "fetch"));
+            assert!(err
+                .to_string()
+                .contains("state key 'timeout' for argument 'timeout_secs': expected number"));
+            assert!(!state.contains_key("response"));
+        }
+
+        #[tokio::test]
+        async fn test_unmapped_required_argument_is_reported() {
+            let node = http_node(ArgMapping::new().with_value("method", "GET"));
+            let context = ExecutionContext::new("graph".to_string(), NodeId::new("fetch"));
+
+            let err = node
+                .execute(&mut GraphState::new(), &context)
+                .await
+                .unwrap_err();
+
+            assert!(err
+                .to_string()
+                .contains("unmapped argument 'url': is required"));
+        }
     }
 }
@@ -162,6 +162,10 @@ impl Node for TransformNode {
         &self.name
     }
 
+    fn node_type(&self) -> &str {
+        "Transform"
+    }
+
     fn input_keys(&self) -> Vec<&str> {
         vec![&self.config.input_key]
     }
@@ -12,14 +12,23 @@ pub use crate::core::{
 };
 
 // State management
+pub use crate::reducer::Reducer;
+pub use crate::schema::{SchemaMode, StateSchema, StateType};
 pub use crate::state::{GraphState, StatePath, StateValue};
 
 // Execution engine
-pub use crate::execution::{ExecutionConfig, ExecutionEngine, ExecutionMode};
+pub use crate::bus::{BusEvent, GraphEventBus};
+pub use crate::events::{GraphEvent, GraphRun};
+pub use crate::execution::{
+    ExecutionConfig, ExecutionEngine, ExecutionMode, RunHandle, Transition,
+};
+pub use crate::limits::ExecutionLimits;
+pub use tokio_util::sync::CancellationToken;
 
 // Node types
 pub use crate::nodes::{
-    AgentNode, ConditionNode, NodeConfig, NodeMetadata, ToolNode, TransformNode,
+    AgentNode, ArgMapping, ConditionNode, MapFailurePolicy, MapNode, NodeConfig, NodeMetadata,
+    SubgraphNode, ToolNode, TransformNode,
 };
 
 // Agent system
@@ -29,7 +38,10 @@ pub use crate::agents::{Agent, AgentBuilder, AgentConfig};
 pub use crate::tools::{Tool, ToolConfig, ToolError, ToolResult};
 
 // Routing
-pub use crate::routing::{ConditionalEdge, Router, RoutingCondition, RoutingDecision};
+pub use crate::routing::{
+    when, ConditionalEdge, EdgeRouter, ExprRouter, Router, RoutingCondition, RoutingDecision,
+    StateExpr, StateRouter,
+};
 
 // Error handling
 pub use crate::{RGraphError, RGraphResult};
@@ -45,10 +57,14 @@ pub use futures::{Future, FutureExt, Stream, StreamExt};
 
 // RRAG integration (when feature is enabled)
 #[cfg(feature = "rexis-rag-integration")]
+pub use crate::nodes::{LlmAgentNode, LlmRouterNode, MemoryReadNode, MemoryWriteNode, RouteSpec};
+#[cfg(feature = "rexis-rag-integration")]
 pub use crate::rrag_integration::{
     ContextEvaluationConfig, ContextEvaluationNode, RagGenerationConfig, RagGenerationNode,
     RagRetrievalConfig, RagRetrievalNode, RagWorkflowBuilder,
 };
+#[cfg(feature = "rexis-rag-integration")]
+pub use crate::supervisor::{SupervisorBuilder, SupervisorModel};
 
 // Observability (when feature is enabled)
 #[cfg(feature = "observability")]
@@ -0,0 +1,340 @@
+//! # State Reducers
+//!
+//! By default a write to a state key replaces its value, so when parallel
+//! branches or loop iterations each add to a key, all but the last addition
+//! are lost. A [`Reducer`] declared for a key on the graph turns writes to it
+//! into updates, combined with the value the key holds.
+
+use crate::state::StateValue;
+use crate::{RGraphError, RGraphResult};
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+/// Function combining the current value of a key, if any, with an update
+pub type ReducerFn =
+    Arc<dyn Fn(Option<&StateValue>, StateValue) -> RGraphResult<StateValue> + Send + Sync>;
+
+/// How writes to a state key are combined with its value
+///
+/// Parallel branches writing to a key with a reducer do not conflict: their
+/// updates are applied to the joined state one after another, in the order
+/// the branches are declared.
+#[derive(Clone)]
+pub enum Reducer {
+    /// Append to a list; a list update appends each of its items
+    AppendList,
+    /// Add numbers; integers stay integers until a float is added
+    Sum,
+    /// Keep the greater number
+    Max,
+    /// Insert the fields of an object update, replacing fields of the same name
+    MergeMap,
+    /// Combine values with a function
+    Custom(ReducerFn),
+}
+
+impl Reducer {
+    /// Create a reducer from a function combining the current value of a key,
+    /// if any, with an update
+    pub fn custom(
+        reduce: impl Fn(Option<&StateValue>, StateValue) -> RGraphResult<StateValue>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Reducer::Custom(Arc::new(reduce))
+    }
+
+    /// Combine `current`, the value of `key`, with `update`
+    pub fn reduce(
+        &self,
+        key: &str,
+        current: Option<&StateValue>,
+        update: StateValue,
+    ) -> RGraphResult<StateValue> {
+        let current = current.filter(|value| !matches!(value, StateValue::Null));
+        match (self, current) {
+            (Reducer::Custom(reduce), current) => reduce(current, update),
+            (Reducer::AppendList, None) => Ok(match update {
+                StateValue::Array(items) => StateValue::Array(items),
+                item => StateValue::Array(vec![item]),
+            }),
+            (Reducer::AppendList, Some(StateValue::Array(items))) => {
+                let mut items = items.clone();
+                match update {
+                    StateValue::Array(more) => items.extend(more),
+                    item => items.push(item),
+                }
+                Ok(StateValue::Array(items))
+            }
+            (Reducer::Sum | Reducer::Max, None) if is_number(&update) => Ok(update),
+            (Reducer::Sum, Some(current)) => match (current, &update) {
+                (StateValue::Integer(a), StateValue::Integer(b)) => {
+                    a.checked_add(*b).map(StateValue::Integer).ok_or_else(|| {
+                        RGraphError::state(format!("Sum of state key '{}' overflows", key))
+                    })
+                }
+                _ => match (as_float(current), as_float(&update)) {
+                    (Some(a), Some(b)) => Ok(StateValue::Float(a + b)),
+                    _ => Err(mismatch(self, key, current, &update)),
+                },
+            },
+            (Reducer::Max, Some(current)) => {
+                let order = match (current, &update) {
+                    (StateValue::Integer(a), StateValue::Integer(b)) => Some(a.cmp(b)),
+                    _ => as_float(current)
+                        .zip(as_float(&update))
+                        .and_then(|(a, b)| a.partial_cmp(&b)),
+                };
+                match order {
+                    Some(Ordering::Less) => Ok(update),
+                    Some(_) => Ok(current.clone()),
+                    None => Err(mismatch(self, key, current, &update)),
+                }
+            }
+            (Reducer::MergeMap, None) if matches!(update, StateValue::Object(_)) => Ok(update),
+            (Reducer::MergeMap, Some(StateValue::Object(fields))) => match update {
+                StateValue::Object(more) => {
+                    let mut fields = fields.clone();
+                    fields.extend(more);
+                    Ok(StateValue::Object(fields))
+                }
+                update => Err(mismatch(
+                    self,
+                    key,
+                    &StateValue::Object(fields.clone()),
+                    &update,
+                )),
+            },
+            (_, current) => Err(mismatch(
+                self,
+                key,
+                current.unwrap_or(&StateValue::Null),
+                &update,
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for Reducer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reducer::AppendList => f.write_str("AppendList"),
+            Reducer::Sum => f.write_str("Sum"),
+            Reducer::Max => f.write_str("Max"),
+            Reducer::MergeMap => f.write_str("MergeMap"),
+            Reducer::Custom(_) => f.write_str("Custom(<fn>)"),
+        }
+    }
+}
+
+fn is_number(value: &StateValue) -> bool {
+    matches!(value, StateValue::Integer(_) | StateValue::Float(_))
+}
+
+fn as_float(value: &StateValue) -> Option<f64> {
+    match value {
+        StateValue::Integer(i) => Some(*i as f64),
+        StateValue::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn mismatch(
+    reducer: &Reducer,
+    key: &str,
+    current: &StateValue,
+    update: &StateValue,
+) -> RGraphError {
+    RGraphError::state(format!(
+        "{:?} reducer of state key '{}' cannot combine {} with {}",
+        reducer,
+        key,
+        current.type_name(),
+        update.type_name()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GraphBuilder, Node, ParallelBranches};
+    use crate::execution::ExecutionEngine;
+    use crate::nodes::test_utils::WriteNode;
+    use crate::state::GraphState;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    // Node writing `value` to `key` after a delay, so that branches overlap
+    fn write(id: &str, key: &str, value: impl Into<StateValue>) -> WriteNode {
+        WriteNode::new(id, key)
+            .with_value(value)
+            .with_delay(Duration::from_millis(20))
+    }
+
+    fn strings(value: StateValue) -> Vec<String> {
+        match value {
+            StateValue::Array(items) => items
+                .iter()
+                .map(|item| item.as_string().unwrap().to_string())
+                .collect(),
+            other => panic!("unexpected list: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_built_in_reducers() {
+        let reduce = |reducer: Reducer, current: Option<StateValue>, update: StateValue| {
+            reducer.reduce("key", current.as_ref(), update).unwrap()
+        };
+
+        assert_eq!(
+            reduce(Reducer::AppendList, None, "a".into()),
+            StateValue::Array(vec!["a".into()])
+        );
+        assert_eq!(
+            reduce(
+                Reducer::AppendList,
+                Some(StateValue::Array(vec!["a".into()])),
+                StateValue::Array(vec!["b".into(), "c".into()])
+            ),
+            StateValue::Array(vec!["a".into(), "b".into(), "c".into()])
+        );
+        assert_eq!(
+            reduce(Reducer::Sum, Some(StateValue::Integer(2)), 3.into()),
+            StateValue::Integer(5)
+        );
+        assert_eq!(
+            reduce(Reducer::Sum, Some(StateValue::Integer(2)), 0.5.into()),
+            StateValue::Float(2.5)
+        );
+        assert_eq!(
+            reduce(Reducer::Max, Some(StateValue::Float(2.5)), 2.into()),
+            StateValue::Float(2.5)
+        );
+
+        let current = HashMap::from([
+            ("a".to_string(), StateValue::Integer(1)),
+            ("b".to_string(), StateValue::Integer(2)),
+        ]);
+        let update = HashMap::from([("b".to_string(), StateValue::Integer(3))]);
+        let merged = reduce(Reducer::MergeMap, Some(current.into()), update.into());
+        let merged = merged.as_object().unwrap();
+        assert_eq!(merged["a"], StateValue::Integer(1));
+        assert_eq!(merged["b"], StateValue::Integer(3));
+    }
+
+    #[test]
+    fn test_mismatched_values_are_rejected() {
+        let err = Reducer::Sum
+            .reduce("total", Some(&StateValue::Integer(1)), "two".into())
+            .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("Sum reducer of state key 'total' cannot combine integer with string"));
+    }
+
+    #[tokio::test]
+    async fn test_node_writes_are_reduced() {
+        let graph = GraphBuilder::new("findings")
+            .reducer("findings", Reducer::AppendList)
+            .add_node("first", write("first", "findings", "cache miss").into())
+            .await
+            .unwrap()
+            .add_node("second", write("second", "findings", "slow query").into())
+            .await
+            .unwrap()
+            .add_edge("first", "second")
+            .unwrap()
+            .build()
+            .unwrap();
+        let state = GraphState::new().with_input("findings", vec![StateValue::from("stale index")]);
+
+        let results = ExecutionEngine::new().execute(&graph, state).await.unwrap();
+
+        assert_eq!(
+            strings(results.final_state.get("findings").unwrap()),
+            ["stale index", "cache miss", "slow query"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_branches_append_to_a_list() {
+        let mut builder = GraphBuilder::new("findings").reducer("findings", Reducer::AppendList);
+        let nodes = [
+            write("split", "topic", "latency"),
+            write("logs", "findings", "timeouts in logs"),
+            write("metrics", "findings", "p99 doubled"),
+            write("traces", "findings", "slow query"),
+            write("report", "reported", true),
+        ];
+        for node in nodes {
+            let id = node.id().clone();
+            builder = builder.add_node(id, node.into()).await.unwrap();
+        }
+        let graph = builder
+            .add_parallel_branches(
+                "split",
+                ParallelBranches::new(["logs", "metrics", "traces"], "report"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+
+        assert!(results.errors.is_empty());
+        assert_eq!(
+            strings(results.final_state.get("findings").unwrap()),
+            ["timeouts in logs", "p99 doubled", "slow query"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_reducer_keeps_a_running_max() {
+        let running_max = Reducer::custom(|current, update| {
+            let best = current.and_then(StateValue::as_integer).unwrap_or(i64::MIN);
+            let score = update.as_integer().ok_or_else(|| {
+                RGraphError::state(format!(
+                    "score must be an integer, not {}",
+                    update.type_name()
+                ))
+            })?;
+            Ok(StateValue::Integer(best.max(score)))
+        });
+        let mut builder = GraphBuilder::new("scores").reducer("best", running_max);
+        let nodes = [
+            write("split", "topic", "drafts"),
+            write("draft_a", "best", 7),
+            write("draft_b", "best", 12),
+            write("draft_c", "best", 9),
+            write("pick", "picked", true),
+        ];
+        for node in nodes {
+            let id = node.id().clone();
+            builder = builder.add_node(id, node.into()).await.unwrap();
+        }
+        let graph = builder
+            .add_parallel_branches(
+                "split",
+                ParallelBranches::new(["draft_a", "draft_b", "draft_c"], "pick"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let state = GraphState::new().with_input("best", 10);
+
+        let results = ExecutionEngine::new().execute(&graph, state).await.unwrap();
+
+        assert!(results.errors.is_empty());
+        assert_eq!(
+            results.final_state.get("best").unwrap(),
+            StateValue::Integer(12)
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! # Run Replays
+//!
+//! A replay steps through a recorded run checkpoint by checkpoint, starting
+//! from the state the run started from. By default no node runs: each step
+//! applies the changes its checkpoint recorded, so the state after every step
+//! is the state of the original run. Nodes made live with
+//! [`ReplayOptions::live`] run instead, on the state replayed up to them, and
+//! a live node that writes other keys than the recorded run did is reported
+//! as a [`Divergence`]. Later steps apply their recorded changes on top of
+//! what live nodes wrote.
+//!
+//! Replays follow the recorded path rather than routing again, and do not
+//! save checkpoints or recorded visits; live nodes with side effects repeat
+//! them.
+//!
+//! ```rust,no_run
+//! # async fn example(
+//! #     graph: rexis_graph::WorkflowGraph,
+//! #     storage: std::sync::Arc<dyn rexis_rag::storage::Memory>,
+//! # ) -> rexis_graph::RGraphResult<()> {
+//! use rexis_graph::ReplayOptions;
+//!
+//! let report = graph
+//!     .replay("run-id", storage, ReplayOptions::new().live("classify"))
+//!     .await?;
+//! for divergence in &report.divergences {
+//!     println!("'{}' no longer writes {:?}", divergence.node_id.as_str(), divergence.missing);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::checkpoint::{Checkpoint, Checkpointer};
+use crate::core::{ExecutionContext, ExecutionResult, NodeId, WorkflowGraph};
+use crate::diff::StateDiff;
+use crate::execution::{take_usage, ExecutionEngine};
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use std::collections::{HashMap, HashSet};
+
+/// Which nodes of a replay run for real
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    live: HashSet<NodeId>,
+}
+
+impl ReplayOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `node_id` instead of applying its recorded changes
+    pub fn live(mut self, node_id: impl Into<NodeId>) -> Self {
+        self.live.insert(node_id.into());
+        self
+    }
+}
+
+/// One checkpoint of the recorded run, as replayed
+#[derive(Debug, Clone)]
+pub struct ReplayStep {
+    /// Sequence number of the checkpoint
+    pub seq: u64,
+    /// Nodes that ran before the checkpoint: one node, or a node and its
+    /// parallel branches; none for the input a run was resumed with
+    pub node_ids: Vec<NodeId>,
+    /// Whether the node ran rather than being replayed
+    pub live: bool,
+    /// What the recorded run changed
+    pub recorded: StateDiff,
+    /// What the replay changed, the recorded changes unless the node ran
+    pub replayed: StateDiff,
+    /// What the node returned when it ran live
+    pub result: Option<ExecutionResult>,
+    /// The state after the step
+    pub state: HashMap<String, StateValue>,
+}
+
+/// A live node that wrote other keys than it did in the recorded run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub seq: u64,
+    pub node_id: NodeId,
+    /// Keys the recorded run changed that the live node did not
+    pub missing: Vec<String>,
+    /// Keys the live node changed that the recorded run did not
+    pub unexpected: Vec<String>,
+}
+
+/// What a replay found
+#[derive(Debug, Clone)]
+pub struct ReplayReport {
+    pub run_id: String,
+    pub steps: Vec<ReplayStep>,
+    pub divergences: Vec<Divergence>,
+    pub final_state: GraphState,
+}
+
+impl ReplayReport {
+    /// Whether a live node diverged from the recorded run
+    pub fn diverged(&self) -> bool {
+        !self.divergences.is_empty()
+    }
+}
+
+impl ExecutionEngine {
+    /// Replay the run `run_id` of `graph` from its checkpoints
+    ///
+    /// Runs recorded without their input, such as those started before
+    /// inputs were saved, replay their first step as adding every key it
+    /// left, and cannot run that step live.
+    pub async fn replay(
+        &self,
+        graph: &WorkflowGraph,
+        run_id: &str,
+        checkpointer: &Checkpointer,
+        options: ReplayOptions,
+    ) -> RGraphResult<ReplayReport> {
+        for node_id in &options.live {
+            if !graph.contains_node(node_id) {
+                return Err(RGraphError::config(format!(
+                    "Cannot run '{}' live: it is not a node of graph '{}'",
+                    node_id.as_str(),
+                    graph.id()
+                )));
+            }
+        }
+        let checkpoints = checkpointer.history(graph.id(), run_id).await?;
+        if checkpoints.is_empty() {
+            return Err(RGraphError::execution(format!(
+                "No checkpoint of run '{}' for graph '{}'",
+                run_id,
+                graph.id()
+            )));
+        }
+
+        let input = checkpointer.load_input(graph.id(), run_id).await?;
+        let has_input = input.is_some();
+        let mut recorded_before = input.unwrap_or_default();
+        let mut state = GraphState::with_data(recorded_before.clone());
+        state.set_schema(graph.state_schema());
+        state.set_reducers(graph.reducers());
+
+        let capture = self.config().diff_capture;
+        let mut steps = Vec::with_capacity(checkpoints.len());
+        let mut divergences = Vec::new();
+        let mut previous: Option<&Checkpoint> = None;
+
+        for checkpoint in &checkpoints {
+            let ran = previous.map_or(0, |previous| previous.execution_path.len());
+            let node_ids =
+                checkpoint.execution_path[ran.min(checkpoint.execution_path.len())..].to_vec();
+            let recorded = StateDiff::between(&recorded_before, &checkpoint.state, capture);
+            let before = state.snapshot();
+
+            let live = node_ids
+                .iter()
+                .any(|node_id| options.live.contains(node_id));
+            let result = if live {
+                let [node_id] = node_ids.as_slice() else {
+                    return Err(RGraphError::config(format!(
+                        "Cannot run {} live: they share checkpoint {}",
+                        quoted(&node_ids),
+                        checkpoint.seq
+                    )));
+                };
+                if previous.is_none() && !has_input {
+                    return Err(RGraphError::config(format!(
+                        "Cannot run '{}' live: run '{}' has no saved input",
+                        node_id.as_str(),
+                        run_id
+                    )));
+                }
+
+                let mut context = ExecutionContext::new(graph.id().to_string(), node_id.clone());
+                context.execution_id = run_id.to_string();
+                context.execution_path = checkpoint.execution_path.clone();
+                if let Some(previous) = previous {
+                    context.edge_traversals = previous
+                        .edge_traversals
+                        .iter()
+                        .map(|(from, to, count)| ((from.clone(), to.clone()), *count))
+                        .collect();
+                }
+                context.run_memory = graph.memory();
+                context.assign_memory(graph);
+
+                let (executed, _) = self.execute_single_node(graph, &mut state, &context).await;
+                take_usage(graph.get_node(node_id).as_deref(), &state, &executed);
+                Some(executed?)
+            } else {
+                apply(&state, &recorded_before, &checkpoint.state);
+                None
+            };
+
+            let after = state.snapshot();
+            let replayed = StateDiff::between(&before, &after, capture);
+            if live {
+                let recorded_keys: HashSet<String> = recorded.keys().into_iter().collect();
+                let replayed_keys: HashSet<String> = replayed.keys().into_iter().collect();
+                let mut missing: Vec<String> =
+                    recorded_keys.difference(&replayed_keys).cloned().collect();
+                let mut unexpected: Vec<String> =
+                    replayed_keys.difference(&recorded_keys).cloned().collect();
+                if !missing.is_empty() || !unexpected.is_empty() {
+                    missing.sort();
+                    unexpected.sort();
+                    divergences.push(Divergence {
+                        seq: checkpoint.seq,
+                        node_id: node_ids[0].clone(),
+                        missing,
+                        unexpected,
+                    });
+                }
+            }
+
+            steps.push(ReplayStep {
+                seq: checkpoint.seq,
+                node_ids,
+                live,
+                recorded,
+                replayed,
+                result,
+                state: after,
+            });
+            recorded_before = checkpoint.state.clone();
+            previous = Some(checkpoint);
+        }
+
+        Ok(ReplayReport {
+            run_id: run_id.to_string(),
+            steps,
+            divergences,
+            final_state: state,
+        })
+    }
+}
+
+/// Apply the changes from `before` to `after` to `state`, bypassing its
+/// reducers
+fn apply(
+    state: &GraphState,
+    before: &HashMap<String, StateValue>,
+    after: &HashMap<String, StateValue>,
+) {
+    let writes = after
+        .iter()
+        .filter(|(key, value)| before.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    state.merge(&GraphState::with_data(writes));
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        state.remove(key);
+    }
+}
+
+fn quoted(node_ids: &[NodeId]) -> String {
+    node_ids
+        .iter()
+        .map(|node_id| format!("'{}'", node_id.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GraphBuilder, Node};
+    use async_trait::async_trait;
+    use rexis_rag::storage::{InMemoryStorage, Memory};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Node writing `output` as its input with `suffix` appended
+    struct StageNode {
+        id: NodeId,
+        input: &'static str,
+        output: &'static str,
+        suffix: &'static str,
+        runs: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Node for StageNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let input = state.get(self.input)?;
+            let input = input.as_string().unwrap_or_default();
+            state.set(self.output, format!("{}{}", input, self.suffix));
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    // fetch -> parse -> enrich -> store, with `enrich` swappable for a fix
+    async fn incident_graph(
+        enrich: (&'static str, &'static str),
+        runs: &Arc<AtomicUsize>,
+    ) -> WorkflowGraph {
+        let stages = [
+            ("fetch", "ticket", "body", "/fetched"),
+            ("parse", "body", "fields", "/parsed"),
+            ("enrich", "fields", enrich.0, enrich.1),
+            ("store", "fields", "stored", "/stored"),
+        ];
+        let mut builder = GraphBuilder::new("incident").id("incident");
+        for (id, input, output, suffix) in stages {
+            let node = StageNode {
+                id: NodeId::new(id),
+                input,
+                output,
+                suffix,
+                runs: runs.clone(),
+            };
+            builder = builder.add_node(id, Arc::new(node)).await.unwrap();
+        }
+        builder
+            .add_edge("fetch", "parse")
+            .unwrap()
+            .add_edge("parse", "enrich")
+            .unwrap()
+            .add_edge("enrich", "store")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    // Record a run of the original graph, returning its ID
+    async fn record(storage: Arc<dyn Memory>) -> String {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let graph = incident_graph(("labels", "/enriched"), &runs).await;
+        let graph = graph.with_checkpointer(Checkpointer::new(storage));
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new().with_input("ticket", "T-1"))
+            .await
+            .unwrap();
+        assert!(results.errors.is_empty());
+        results.run_id
+    }
+
+    fn text(state: &HashMap<String, StateValue>, key: &str) -> String {
+        state[key].as_string().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_live_nodes_runs_nothing() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let run_id = record(storage.clone()).await;
+        let runs = Arc::new(AtomicUsize::new(0));
+        let graph = incident_graph(("labels", "/enriched"), &runs).await;
+
+        let report = graph
+            .replay(&run_id, storage.clone(), ReplayOptions::new())
+            .await
+            .unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        let history = Checkpointer::new(storage)
+            .history("incident", &run_id)
+            .await
+            .unwrap();
+        assert_eq!(report.steps.len(), 4);
+        for (step, checkpoint) in report.steps.iter().zip(&history) {
+            assert_eq!(step.state, checkpoint.state);
+            assert_eq!(step.recorded, step.replayed);
+            assert!(!step.live);
+        }
+        let order: Vec<&str> = report
+            .steps
+            .iter()
+            .flat_map(|step| step.node_ids.iter().map(NodeId::as_str))
+            .collect();
+        assert_eq!(order, ["fetch", "parse", "enrich", "store"]);
+        assert_eq!(report.steps[0].recorded.keys(), ["body"]);
+        assert!(!report.diverged());
+    }
+
+    #[tokio::test]
+    async fn test_live_node_runs_on_recorded_upstream_state() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let run_id = record(storage.clone()).await;
+        let runs = Arc::new(AtomicUsize::new(0));
+        let graph = incident_graph(("labels", "/enriched-v2"), &runs).await;
+
+        let report = graph
+            .replay(&run_id, storage, ReplayOptions::new().live("enrich"))
+            .await
+            .unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        let enrich = &report.steps[2];
+        assert!(enrich.live);
+        assert!(matches!(enrich.result, Some(ExecutionResult::Continue)));
+        assert_eq!(
+            text(&enrich.state, "labels"),
+            "T-1/fetched/parsed/enriched-v2"
+        );
+        assert_eq!(
+            enrich.recorded.get("labels").unwrap().new,
+            Some(crate::diff::DiffValue::Full(
+                "T-1/fetched/parsed/enriched".into()
+            ))
+        );
+        // The recorded store step still applies on top of the fix
+        let last = &report.steps[3];
+        assert_eq!(text(&last.state, "stored"), "T-1/fetched/parsed/stored");
+        assert_eq!(
+            text(&last.state, "labels"),
+            "T-1/fetched/parsed/enriched-v2"
+        );
+        assert!(!report.diverged());
+    }
+
+    #[tokio::test]
+    async fn test_live_node_writing_other_keys_diverges() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let run_id = record(storage.clone()).await;
+        let runs = Arc::new(AtomicUsize::new(0));
+        let graph = incident_graph(("tags", "/enriched"), &runs).await;
+
+        let report = graph
+            .replay(&run_id, storage, ReplayOptions::new().live("enrich"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report.divergences,
+            vec![Divergence {
+                seq: 2,
+                node_id: NodeId::new("enrich"),
+                missing: vec!["labels".to_string()],
+                unexpected: vec!["tags".to_string()],
+            }]
+        );
+        assert!(report.final_state.contains_key("tags"));
+        assert!(!report.final_state.contains_key("labels"));
+    }
+
+    #[tokio::test]
+    async fn test_live_node_must_exist() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let run_id = record(storage.clone()).await;
+        let runs = Arc::new(AtomicUsize::new(0));
+        let graph = incident_graph(("labels", "/enriched"), &runs).await;
+
+        let err = graph
+            .replay(&run_id, storage, ReplayOptions::new().live("classify"))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("'classify'"));
+    }
+}
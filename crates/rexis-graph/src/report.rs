@@ -0,0 +1,475 @@
+//! # Run Reports
+//!
+//! A [`RunReport`] sums up where the time and tokens of a run went: every
+//! node visit with its duration, attempts, where execution went next and
+//! the usage the node reported, plus totals and a per-node breakdown.
+//!
+//! Nodes report usage by writing it to [`USAGE_KEY`](crate::USAGE_KEY), which
+//! the engine takes out of the state after the node ran, or through
+//! [`Node::metrics`](crate::Node::metrics).
+
+use crate::core::NodeId;
+use crate::diff::{KeyChange, StateDiff};
+use crate::execution::{ExecutionResults, TraceStep, Transition};
+use crate::state::StateValue;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Tokens and cost a node used
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Cost in US dollars, when known
+    pub cost: Option<f64>,
+}
+
+impl NodeUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            cost: None,
+        }
+    }
+
+    pub fn with_cost(mut self, cost: f64) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Add `other` to this usage; the cost is known when either is
+    pub fn add(&mut self, other: &NodeUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost = match (self.cost, other.cost) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Read usage from an object with `prompt_tokens`, `completion_tokens`
+    /// and `cost`, each optional
+    pub fn from_state_value(value: &StateValue) -> Option<Self> {
+        let StateValue::Object(fields) = value else {
+            return None;
+        };
+        let tokens = |key: &str| match fields.get(key) {
+            Some(StateValue::Integer(count)) => u64::try_from(*count).ok(),
+            _ => None,
+        };
+        let cost = match fields.get("cost") {
+            Some(StateValue::Float(cost)) => Some(*cost),
+            Some(StateValue::Integer(cost)) => Some(*cost as f64),
+            _ => None,
+        };
+
+        Some(Self {
+            prompt_tokens: tokens("prompt_tokens").unwrap_or(0),
+            completion_tokens: tokens("completion_tokens").unwrap_or(0),
+            cost,
+        })
+    }
+}
+
+impl From<NodeUsage> for StateValue {
+    fn from(usage: NodeUsage) -> Self {
+        let mut fields = HashMap::from([
+            (
+                "prompt_tokens".to_string(),
+                StateValue::Integer(usage.prompt_tokens as i64),
+            ),
+            (
+                "completion_tokens".to_string(),
+                StateValue::Integer(usage.completion_tokens as i64),
+            ),
+        ]);
+        if let Some(cost) = usage.cost {
+            fields.insert("cost".to_string(), StateValue::Float(cost));
+        }
+        StateValue::Object(fields)
+    }
+}
+
+/// One node visit of a run
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeVisit {
+    pub node_id: NodeId,
+    /// How many times the node had run, counting this visit
+    pub iteration: usize,
+    pub duration: Duration,
+    /// How often the node was attempted; more than 1 when it was retried
+    pub attempts: u32,
+    /// Where execution went next, which tells how the node finished
+    pub transition: Transition,
+    /// Usage the node reported, including that of the subgraphs it ran
+    pub usage: Option<NodeUsage>,
+    /// How the node changed the state
+    pub changes: StateDiff,
+}
+
+impl NodeVisit {
+    pub fn failed(&self) -> bool {
+        matches!(self.transition, Transition::Failed | Transition::OnError(_))
+    }
+}
+
+/// All visits of one node in a run
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeSummary {
+    pub node_id: NodeId,
+    pub visits: usize,
+    pub duration: Duration,
+    pub attempts: u32,
+    pub failures: usize,
+    pub usage: NodeUsage,
+}
+
+/// Where the time and tokens of a run went
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RunReport {
+    pub run_id: String,
+    pub success: bool,
+    pub total_duration: Duration,
+    pub nodes_executed: usize,
+    /// Attempts beyond the first, over all visits
+    pub retries: u32,
+    /// Usage over all visits
+    pub usage: NodeUsage,
+    /// Visits in the order they were made
+    pub visits: Vec<NodeVisit>,
+    /// Visits per node, longest total duration first
+    pub nodes: Vec<NodeSummary>,
+}
+
+impl RunReport {
+    pub fn from_results(results: &ExecutionResults) -> Self {
+        let visits: Vec<NodeVisit> = results
+            .trace
+            .iter()
+            .map(|step| NodeVisit {
+                node_id: step.node_id.clone(),
+                iteration: step.iteration,
+                duration: step.duration,
+                attempts: step.attempts,
+                transition: step.transition.clone(),
+                usage: step_usage(step),
+                changes: step.changes.clone(),
+            })
+            .collect();
+
+        let mut usage = NodeUsage::default();
+        let mut nodes: Vec<NodeSummary> = Vec::new();
+        for visit in &visits {
+            let index = match nodes.iter().position(|node| node.node_id == visit.node_id) {
+                Some(index) => index,
+                None => {
+                    nodes.push(NodeSummary {
+                        node_id: visit.node_id.clone(),
+                        visits: 0,
+                        duration: Duration::ZERO,
+                        attempts: 0,
+                        failures: 0,
+                        usage: NodeUsage::default(),
+                    });
+                    nodes.len() - 1
+                }
+            };
+            let node = &mut nodes[index];
+            node.visits += 1;
+            node.duration += visit.duration;
+            node.attempts += visit.attempts;
+            node.failures += usize::from(visit.failed());
+            if let Some(visit_usage) = &visit.usage {
+                node.usage.add(visit_usage);
+                usage.add(visit_usage);
+            }
+        }
+        // Stable, so nodes that took as long stay in the order they first ran
+        nodes.sort_by_key(|node| std::cmp::Reverse(node.duration));
+
+        Self {
+            run_id: results.run_id.clone(),
+            success: results.metrics.success,
+            total_duration: results.metrics.total_duration,
+            nodes_executed: results.metrics.nodes_executed,
+            retries: visits
+                .iter()
+                .map(|visit| visit.attempts.saturating_sub(1))
+                .sum(),
+            usage,
+            visits,
+            nodes,
+        }
+    }
+
+    /// The visits that changed `key`, in the order they were made, each with
+    /// its change
+    pub fn changes_for_key(&self, key: &str) -> Vec<(&NodeVisit, &KeyChange)> {
+        self.visits
+            .iter()
+            .filter_map(|visit| visit.changes.get(key).map(|change| (visit, change)))
+            .collect()
+    }
+
+    /// Render the report as JSON
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> crate::RGraphResult<serde_json::Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+}
+
+/// Usage of a step and the subgraph steps nested under it
+fn step_usage(step: &TraceStep) -> Option<NodeUsage> {
+    let mut total = step.usage;
+    for child in &step.children {
+        if let Some(usage) = step_usage(child) {
+            total.get_or_insert_with(NodeUsage::default).add(&usage);
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeRetryPolicy, WorkflowGraph,
+    };
+    use crate::events::GraphEvent;
+    use crate::execution::{ExecutionEngine, USAGE_KEY};
+    use crate::state::GraphState;
+    use crate::{RGraphError, RGraphResult};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    // Node sleeping for a known time, reporting usage under USAGE_KEY or
+    // through its metrics hook, and failing its first `failures` attempts
+    struct TimedNode {
+        id: NodeId,
+        delay: Duration,
+        written: Option<NodeUsage>,
+        hooked: Option<NodeUsage>,
+        failures: AtomicU32,
+    }
+
+    impl TimedNode {
+        fn new(id: &str, delay_ms: u64) -> Self {
+            Self {
+                id: NodeId::new(id),
+                delay: Duration::from_millis(delay_ms),
+                written: None,
+                hooked: None,
+                failures: AtomicU32::new(0),
+            }
+        }
+
+        fn writing(mut self, usage: NodeUsage) -> Self {
+            self.written = Some(usage);
+            self
+        }
+
+        fn hooked(mut self, usage: NodeUsage) -> Self {
+            self.hooked = Some(usage);
+            self
+        }
+
+        fn failing(self, failures: u32) -> Self {
+            self.failures.store(failures, Ordering::SeqCst);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl Node for TimedNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            tokio::time::sleep(self.delay).await;
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(RGraphError::node(self.id.as_str(), "flaky"));
+            }
+            if let Some(usage) = self.written {
+                state.set(USAGE_KEY, usage);
+            }
+            state.set(self.id.as_str(), true);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn metrics(&self, _state: &GraphState) -> Option<NodeUsage> {
+            self.hooked
+        }
+    }
+
+    async fn pipeline() -> WorkflowGraph {
+        GraphBuilder::new("report")
+            .add_node("fetch", Arc::new(TimedNode::new("fetch", 10)))
+            .await
+            .unwrap()
+            .add_node(
+                "think",
+                Arc::new(
+                    TimedNode::new("think", 60).writing(NodeUsage::new(100, 20).with_cost(0.01)),
+                ),
+            )
+            .await
+            .unwrap()
+            .add_node(
+                "answer",
+                Arc::new(TimedNode::new("answer", 30).hooked(NodeUsage::new(30, 10))),
+            )
+            .await
+            .unwrap()
+            .add_edge("fetch", "think")
+            .unwrap()
+            .add_edge("think", "answer")
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn ids(report: &RunReport) -> Vec<&str> {
+        report
+            .nodes
+            .iter()
+            .map(|node| node.node_id.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_report_totals_and_sorts_nodes_by_duration() {
+        let graph = pipeline().await;
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        let report = results.report();
+
+        assert!(report.success);
+        assert_eq!(report.run_id, results.run_id);
+        assert_eq!(report.nodes_executed, 3);
+        assert_eq!(ids(&report), ["think", "answer", "fetch"]);
+        let visited: Vec<_> = report.visits.iter().map(|v| v.node_id.as_str()).collect();
+        assert_eq!(visited, ["fetch", "think", "answer"]);
+        for (visit, delay) in report.visits.iter().zip([10, 60, 30]) {
+            assert!(visit.duration >= Duration::from_millis(delay));
+        }
+        assert!(report.total_duration >= Duration::from_millis(100));
+
+        assert_eq!(report.visits[0].usage, None);
+        assert_eq!(
+            report.visits[1].usage,
+            Some(NodeUsage::new(100, 20).with_cost(0.01))
+        );
+        assert_eq!(report.visits[2].usage, Some(NodeUsage::new(30, 10)));
+        assert_eq!(report.usage.prompt_tokens, 130);
+        assert_eq!(report.usage.completion_tokens, 30);
+        assert_eq!(report.usage.total_tokens(), 160);
+        assert_eq!(report.usage.cost, Some(0.01));
+        assert_eq!(report.retries, 0);
+
+        // The reserved key does not leak into the final state
+        assert!(!results.final_state.contains_key(USAGE_KEY));
+    }
+
+    #[tokio::test]
+    async fn test_retries_and_repeat_visits_are_summed_per_node() {
+        let graph = GraphBuilder::new("report")
+            .add_node_with_policy(
+                "flaky",
+                Arc::new(
+                    TimedNode::new("flaky", 5)
+                        .failing(2)
+                        .hooked(NodeUsage::new(5, 5)),
+                ),
+                NodeRetryPolicy::new(3),
+            )
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        let report = results.report();
+
+        assert_eq!(report.retries, 2);
+        assert_eq!(report.nodes.len(), 1);
+        let flaky = &report.nodes[0];
+        assert_eq!(flaky.visits, 1);
+        assert_eq!(flaky.attempts, 3);
+        assert_eq!(flaky.failures, 0);
+        assert!(flaky.duration >= Duration::from_millis(15));
+        assert_eq!(flaky.usage.total_tokens(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_finished_events_carry_the_same_numbers() {
+        let graph = pipeline().await;
+        let mut run = ExecutionEngine::new().execute_stream(&graph, GraphState::new());
+        let mut finished = Vec::new();
+        while let Some(event) = run.next().await {
+            if let GraphEvent::NodeFinished {
+                node_id,
+                attempts,
+                usage,
+                ..
+            } = event
+            {
+                finished.push((node_id, attempts, usage));
+            }
+        }
+        let report = run.result().await.unwrap().report();
+
+        assert_eq!(finished.len(), report.visits.len());
+        for ((node_id, attempts, usage), visit) in finished.iter().zip(&report.visits) {
+            assert_eq!(node_id, &visit.node_id);
+            assert_eq!(*attempts, visit.attempts);
+            assert_eq!(*usage, visit.usage);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_report_serializes_to_json() {
+        let graph = pipeline().await;
+        let results = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap();
+        let json = results.report().to_json().unwrap();
+
+        assert_eq!(json["success"], true);
+        assert_eq!(json["usage"]["prompt_tokens"], 130);
+        assert_eq!(json["visits"].as_array().unwrap().len(), 3);
+        assert_eq!(json["nodes"][0]["usage"]["completion_tokens"], 20);
+
+        let parsed: RunReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, results.report());
+    }
+}
@@ -3,9 +3,10 @@
 //! Conditional routing and decision-making for graph execution.
 
 use crate::core::NodeId;
-use crate::state::GraphState;
-use crate::RGraphResult;
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
 use async_trait::async_trait;
+use std::sync::Arc;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -126,3 +127,850 @@ impl Default for Router {
         Self::new()
     }
 }
+
+/// Picks the node that runs after a node with a conditional edge
+///
+/// Registered with
+/// [`WorkflowGraph::add_conditional_edge`](crate::core::WorkflowGraph::add_conditional_edge).
+#[async_trait]
+pub trait EdgeRouter: Send + Sync {
+    /// Node to run next, given the state after the source node ran
+    async fn route(&self, state: &GraphState) -> RGraphResult<NodeId>;
+
+    /// Every node the router can pick, checked against the graph when it is
+    /// built; empty when they are not known up front
+    fn targets(&self) -> Vec<NodeId> {
+        Vec::new()
+    }
+
+    /// State keys the route depends on; empty when they are not known up
+    /// front
+    fn keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Targets with a description of when each is picked, for diagrams;
+    /// an empty description leaves the edge unlabeled
+    fn describe(&self) -> Vec<(NodeId, String)> {
+        self.targets()
+            .into_iter()
+            .map(|target| (target, String::new()))
+            .collect()
+    }
+}
+
+/// Router calling a function
+///
+/// Its targets are not known up front, so a route to a missing node fails
+/// the run rather than the build.
+pub struct FnRouter<F> {
+    router: F,
+}
+
+impl<F> FnRouter<F> {
+    pub fn new(router: F) -> Self {
+        Self { router }
+    }
+}
+
+#[async_trait]
+impl<F, T> EdgeRouter for FnRouter<F>
+where
+    F: Fn(&GraphState) -> RGraphResult<T> + Send + Sync,
+    T: Into<NodeId>,
+{
+    async fn route(&self, state: &GraphState) -> RGraphResult<NodeId> {
+        (self.router)(state).map(Into::into)
+    }
+}
+
+/// Conversion into a shared [`EdgeRouter`], implemented for routing
+/// functions and routers
+pub trait IntoEdgeRouter {
+    fn into_edge_router(self) -> Arc<dyn EdgeRouter>;
+}
+
+impl<F, T> IntoEdgeRouter for F
+where
+    F: Fn(&GraphState) -> RGraphResult<T> + Send + Sync + 'static,
+    T: Into<NodeId> + 'static,
+{
+    fn into_edge_router(self) -> Arc<dyn EdgeRouter> {
+        Arc::new(FnRouter::new(self))
+    }
+}
+
+impl IntoEdgeRouter for StateRouter {
+    fn into_edge_router(self) -> Arc<dyn EdgeRouter> {
+        Arc::new(self)
+    }
+}
+
+impl IntoEdgeRouter for Arc<dyn EdgeRouter> {
+    fn into_edge_router(self) -> Arc<dyn EdgeRouter> {
+        self
+    }
+}
+
+/// Start a [`StateRouter`] with a branch taken when the state value under `key`
+/// matches
+///
+/// ```rust
+/// use rexis_graph::routing::when;
+///
+/// let router = when("approved").equals(true).goto("publish").otherwise("revise");
+/// ```
+pub fn when(key: impl Into<String>) -> When {
+    When {
+        branches: Vec::new(),
+        key: key.into(),
+    }
+}
+
+/// A branch of a [`StateRouter`] waiting for its comparison
+pub struct When {
+    branches: Vec<Branch>,
+    key: String,
+}
+
+impl When {
+    /// Take the branch when the value equals `value`; integers and floats
+    /// compare by number
+    pub fn equals(self, value: impl Into<StateValue>) -> WhenEquals {
+        WhenEquals {
+            branches: self.branches,
+            key: self.key,
+            value: value.into(),
+        }
+    }
+}
+
+/// A branch of a [`StateRouter`] waiting for its target
+pub struct WhenEquals {
+    branches: Vec<Branch>,
+    key: String,
+    value: StateValue,
+}
+
+impl WhenEquals {
+    /// Go to `node` when the branch is taken
+    pub fn goto(mut self, node: impl Into<NodeId>) -> StateRouter {
+        self.branches.push(Branch {
+            key: self.key,
+            value: self.value,
+            target: node.into(),
+        });
+        StateRouter {
+            branches: self.branches,
+            otherwise: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Branch {
+    key: String,
+    value: StateValue,
+    target: NodeId,
+}
+
+/// Router comparing state values, built with [`when`]
+///
+/// Branches are tried in the order they were added; the first that matches
+/// wins. A missing key matches no branch. When none matches, execution goes to
+/// the [`otherwise`](Self::otherwise) node, and without one the run fails.
+#[derive(Debug, Clone)]
+pub struct StateRouter {
+    branches: Vec<Branch>,
+    otherwise: Option<NodeId>,
+}
+
+impl StateRouter {
+    /// Add a branch taken when the value under `key` matches
+    pub fn when(self, key: impl Into<String>) -> When {
+        When {
+            branches: self.branches,
+            key: key.into(),
+        }
+    }
+
+    /// Go to `node` when no branch matches
+    pub fn otherwise(mut self, node: impl Into<NodeId>) -> Self {
+        self.otherwise = Some(node.into());
+        self
+    }
+}
+
+#[async_trait]
+impl EdgeRouter for StateRouter {
+    async fn route(&self, state: &GraphState) -> RGraphResult<NodeId> {
+        let taken = self.branches.iter().find(|branch| {
+            state
+                .get(&branch.key)
+                .is_ok_and(|value| values_equal(&value, &branch.value))
+        });
+        match (taken, &self.otherwise) {
+            (Some(branch), _) => Ok(branch.target.clone()),
+            (None, Some(otherwise)) => Ok(otherwise.clone()),
+            (None, None) => Err(RGraphError::routing(format!(
+                "no branch matched and there is no otherwise node (keys: {})",
+                self.branches
+                    .iter()
+                    .map(|branch| branch.key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    fn targets(&self) -> Vec<NodeId> {
+        self.branches
+            .iter()
+            .map(|branch| branch.target.clone())
+            .chain(self.otherwise.clone())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for branch in &self.branches {
+            if !keys.contains(&branch.key) {
+                keys.push(branch.key.clone());
+            }
+        }
+        keys
+    }
+
+    fn describe(&self) -> Vec<(NodeId, String)> {
+        self.branches
+            .iter()
+            .map(|branch| {
+                let condition = format!("{} == {}", branch.key, describe_value(&branch.value));
+                (branch.target.clone(), condition)
+            })
+            .chain(
+                self.otherwise
+                    .iter()
+                    .map(|target| (target.clone(), "otherwise".to_string())),
+            )
+            .collect()
+    }
+}
+
+/// Condition over state values, parsed from an expression
+///
+/// An expression compares a state key, or a dotted path into one, with a
+/// literal: `score >= 0.8`, `verdict == "approve"`, `tier != 'free'`. A bare
+/// key holds when its value is truthy: not `false`, `null`, zero or empty.
+/// `!` negates, `&&` and `||` combine and parentheses group. Literals are
+/// numbers, quoted strings, `true`, `false` and `null`. A comparison with a
+/// missing key is false; `<`, `<=`, `>` and `>=` compare numbers with numbers
+/// and strings with strings, and are false otherwise.
+///
+/// ```rust
+/// use rexis_graph::routing::StateExpr;
+///
+/// let condition = StateExpr::parse("score >= 0.8 && !flagged").unwrap();
+/// assert_eq!(condition.to_string(), "score >= 0.8 && !flagged");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateExpr {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Compare {
+        key: String,
+        op: CompareOp,
+        value: StateValue,
+    },
+    Truthy(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+impl StateExpr {
+    /// Parse an expression; the error names the column where parsing failed
+    pub fn parse(source: &str) -> RGraphResult<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = ExprParser {
+            source,
+            tokens,
+            next: 0,
+        };
+        let expr = parser.or()?;
+        if let Some((column, _)) = parser.tokens.get(parser.next) {
+            return Err(parser.error(*column, "expected `&&`, `||` or the end"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether the condition holds for `state`
+    pub fn evaluate(&self, state: &GraphState) -> bool {
+        evaluate(&self.expr, state)
+    }
+
+    /// State keys the condition reads, in the order it names them
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        collect_keys(&self.expr, &mut keys);
+        keys
+    }
+}
+
+impl std::str::FromStr for StateExpr {
+    type Err = RGraphError;
+
+    fn from_str(source: &str) -> RGraphResult<Self> {
+        Self::parse(source)
+    }
+}
+
+impl std::fmt::Display for StateExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_expr(&self.expr, 0, f)
+    }
+}
+
+/// Write `expr`, in parentheses when it binds looser than its parent
+fn write_expr(expr: &Expr, parent: u8, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let precedence = match expr {
+        Expr::Or(..) => 1,
+        Expr::And(..) => 2,
+        Expr::Compare { .. } => 3,
+        Expr::Truthy(_) | Expr::Not(_) => 4,
+    };
+    if precedence < parent {
+        f.write_str("(")?;
+    }
+    match expr {
+        Expr::Compare { key, op, value } => {
+            write!(f, "{} {} {}", key, op.symbol(), describe_value(value))?
+        }
+        Expr::Truthy(key) => f.write_str(key)?,
+        Expr::Not(inner) => {
+            f.write_str("!")?;
+            write_expr(inner, 4, f)?;
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            write_expr(left, precedence, f)?;
+            f.write_str(if precedence == 1 { " || " } else { " && " })?;
+            write_expr(right, precedence + 1, f)?;
+        }
+    }
+    if precedence < parent {
+        f.write_str(")")?;
+    }
+    Ok(())
+}
+
+fn evaluate(expr: &Expr, state: &GraphState) -> bool {
+    match expr {
+        Expr::Compare { key, op, value } => state
+            .get(key)
+            .is_ok_and(|actual| compare(&actual, *op, value)),
+        Expr::Truthy(key) => state.get(key).is_ok_and(|value| truthy(&value)),
+        Expr::Not(inner) => !evaluate(inner, state),
+        Expr::And(left, right) => evaluate(left, state) && evaluate(right, state),
+        Expr::Or(left, right) => evaluate(left, state) || evaluate(right, state),
+    }
+}
+
+fn compare(actual: &StateValue, op: CompareOp, expected: &StateValue) -> bool {
+    let ordering = match (actual, expected) {
+        (StateValue::String(a), StateValue::String(b)) => Some(a.cmp(b)),
+        _ => match (number(actual), number(expected)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+    };
+    match op {
+        CompareOp::Eq => values_equal(actual, expected),
+        CompareOp::Ne => !values_equal(actual, expected),
+        CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+        CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+    }
+}
+
+fn number(value: &StateValue) -> Option<f64> {
+    match value {
+        StateValue::Integer(i) => Some(*i as f64),
+        StateValue::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn truthy(value: &StateValue) -> bool {
+    match value {
+        StateValue::Null => false,
+        StateValue::Boolean(b) => *b,
+        StateValue::Integer(i) => *i != 0,
+        StateValue::Float(f) => *f != 0.0,
+        StateValue::String(s) => !s.is_empty(),
+        StateValue::Array(items) => !items.is_empty(),
+        StateValue::Object(fields) => !fields.is_empty(),
+        _ => true,
+    }
+}
+
+fn collect_keys(expr: &Expr, keys: &mut Vec<String>) {
+    match expr {
+        Expr::Compare { key, .. } | Expr::Truthy(key) => {
+            let key = key.split('.').next().unwrap_or(key);
+            if !keys.iter().any(|known| known == key) {
+                keys.push(key.to_string());
+            }
+        }
+        Expr::Not(inner) => collect_keys(inner, keys),
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            collect_keys(left, keys);
+            collect_keys(right, keys);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Key(String),
+    Literal(StateValue),
+    Compare(CompareOp),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+/// Split an expression into tokens, each with its 1-based column
+fn tokenize(source: &str) -> RGraphResult<Vec<(usize, Token)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let error = |column: usize, message: &str| {
+        RGraphError::routing(format!(
+            "invalid condition `{}`: {} at column {}",
+            source, message, column
+        ))
+    };
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let pair = chars.get(i + 1).map(|next| (c, *next));
+        let (token, width) = match (c, pair) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            (_, Some(('&', '&'))) => (Token::And, 2),
+            (_, Some(('|', '|'))) => (Token::Or, 2),
+            (_, Some(('=', '='))) => (Token::Compare(CompareOp::Eq), 2),
+            (_, Some(('!', '='))) => (Token::Compare(CompareOp::Ne), 2),
+            (_, Some(('<', '='))) => (Token::Compare(CompareOp::Le), 2),
+            (_, Some(('>', '='))) => (Token::Compare(CompareOp::Ge), 2),
+            ('<', _) => (Token::Compare(CompareOp::Lt), 1),
+            ('>', _) => (Token::Compare(CompareOp::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('"' | '\'', _) => {
+                let mut text = String::new();
+                let mut end = i + 1;
+                loop {
+                    match chars.get(end) {
+                        None => return Err(error(column, "unterminated string")),
+                        Some('\\') if end + 1 < chars.len() => {
+                            text.push(chars[end + 1]);
+                            end += 2;
+                        }
+                        Some(quote) if *quote == c => break,
+                        Some(other) => {
+                            text.push(*other);
+                            end += 1;
+                        }
+                    }
+                }
+                (Token::Literal(StateValue::String(text)), end + 1 - i)
+            }
+            (c, _)
+                if c.is_ascii_digit()
+                    || (c == '-' && pair.is_some_and(|(_, n)| n.is_ascii_digit())) =>
+            {
+                let mut end = i + 1;
+                while end < chars.len()
+                    && (chars[end].is_ascii_alphanumeric()
+                        || chars[end] == '.'
+                        || (matches!(chars[end], '+' | '-') && matches!(chars[end - 1], 'e' | 'E')))
+                {
+                    end += 1;
+                }
+                let text: String = chars[i..end].iter().collect();
+                let value = match text.parse::<i64>() {
+                    Ok(integer) => StateValue::Integer(integer),
+                    Err(_) => match text.parse::<f64>() {
+                        Ok(float) => StateValue::Float(float),
+                        Err(_) => return Err(error(column, &format!("invalid number `{}`", text))),
+                    },
+                };
+                (Token::Literal(value), end - i)
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                let mut end = i + 1;
+                while end < chars.len()
+                    && (chars[end].is_alphanumeric() || matches!(chars[end], '_' | '.'))
+                {
+                    end += 1;
+                }
+                let word: String = chars[i..end].iter().collect();
+                let token = match word.as_str() {
+                    "true" => Token::Literal(StateValue::Boolean(true)),
+                    "false" => Token::Literal(StateValue::Boolean(false)),
+                    "null" => Token::Literal(StateValue::Null),
+                    _ => Token::Key(word),
+                };
+                (token, end - i)
+            }
+            (c, _) => return Err(error(column, &format!("unexpected `{}`", c))),
+        };
+        tokens.push((column, token));
+        i += width;
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens of an expression
+struct ExprParser<'a> {
+    source: &'a str,
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+impl ExprParser<'_> {
+    fn error(&self, column: usize, message: &str) -> RGraphError {
+        RGraphError::routing(format!(
+            "invalid condition `{}`: {} at column {}",
+            self.source, message, column
+        ))
+    }
+
+    /// Column of the next token, or just past the end
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map(|(column, _)| *column)
+            .unwrap_or(self.source.chars().count() + 1)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self.tokens.get(self.next).is_some_and(|(_, t)| t == token);
+        if matched {
+            self.next += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> RGraphResult<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> RGraphResult<Expr> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> RGraphResult<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Open) {
+            let expr = self.or()?;
+            if !self.eat(&Token::Close) {
+                return Err(self.error(self.column(), "expected `)`"));
+            }
+            return Ok(expr);
+        }
+
+        let column = self.column();
+        let Some((_, Token::Key(key))) = self.tokens.get(self.next).cloned() else {
+            return Err(self.error(column, "expected a state key"));
+        };
+        self.next += 1;
+        let Some((_, Token::Compare(op))) = self.tokens.get(self.next).cloned() else {
+            return Ok(Expr::Truthy(key));
+        };
+        self.next += 1;
+        let column = self.column();
+        let Some((_, Token::Literal(value))) = self.tokens.get(self.next).cloned() else {
+            return Err(self.error(column, &format!("expected a value after `{}`", op.symbol())));
+        };
+        self.next += 1;
+        Ok(Expr::Compare { key, op, value })
+    }
+}
+
+/// Router taking the first branch whose [`StateExpr`] holds
+///
+/// Like a [`StateRouter`], it goes to the [`otherwise`](Self::otherwise)
+/// node when no branch holds, and without one the run fails.
+#[derive(Debug, Clone, Default)]
+pub struct ExprRouter {
+    branches: Vec<(StateExpr, NodeId)>,
+    otherwise: Option<NodeId>,
+}
+
+impl ExprRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Go to `node` when `condition` holds and no earlier branch did
+    pub fn branch(mut self, condition: StateExpr, node: impl Into<NodeId>) -> Self {
+        self.branches.push((condition, node.into()));
+        self
+    }
+
+    /// Go to `node` when no branch holds
+    pub fn otherwise(mut self, node: impl Into<NodeId>) -> Self {
+        self.otherwise = Some(node.into());
+        self
+    }
+}
+
+#[async_trait]
+impl EdgeRouter for ExprRouter {
+    async fn route(&self, state: &GraphState) -> RGraphResult<NodeId> {
+        let taken = self
+            .branches
+            .iter()
+            .find(|(condition, _)| condition.evaluate(state));
+        match (taken, &self.otherwise) {
+            (Some((_, target)), _) => Ok(target.clone()),
+            (None, Some(otherwise)) => Ok(otherwise.clone()),
+            (None, None) => Err(RGraphError::routing(format!(
+                "no condition held and there is no otherwise node (conditions: {})",
+                self.branches
+                    .iter()
+                    .map(|(condition, _)| condition.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ))),
+        }
+    }
+
+    fn targets(&self) -> Vec<NodeId> {
+        self.branches
+            .iter()
+            .map(|(_, target)| target.clone())
+            .chain(self.otherwise.clone())
+            .collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for key in self
+            .branches
+            .iter()
+            .flat_map(|(condition, _)| condition.keys())
+        {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    fn describe(&self) -> Vec<(NodeId, String)> {
+        self.branches
+            .iter()
+            .map(|(condition, target)| (target.clone(), condition.to_string()))
+            .chain(
+                self.otherwise
+                    .iter()
+                    .map(|target| (target.clone(), "otherwise".to_string())),
+            )
+            .collect()
+    }
+}
+
+impl IntoEdgeRouter for ExprRouter {
+    fn into_edge_router(self) -> Arc<dyn EdgeRouter> {
+        Arc::new(self)
+    }
+}
+
+/// Short rendering of a value compared by a router
+fn describe_value(value: &StateValue) -> String {
+    match value {
+        StateValue::String(s) => format!("{:?}", s),
+        StateValue::Integer(i) => i.to_string(),
+        StateValue::Float(f) => f.to_string(),
+        StateValue::Boolean(b) => b.to_string(),
+        StateValue::Null => "null".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Equality of state values, with integers and floats compared by number
+pub(crate) fn values_equal(a: &StateValue, b: &StateValue) -> bool {
+    match (a, b) {
+        (StateValue::Integer(i), StateValue::Float(f))
+        | (StateValue::Float(f), StateValue::Integer(i)) => *i as f64 == *f,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_state_router_takes_first_matching_branch() {
+        let router = when("verdict")
+            .equals("approve")
+            .goto("publish")
+            .when("score")
+            .equals(1)
+            .goto("review")
+            .otherwise("revise");
+        let state = GraphState::new();
+
+        assert_eq!(router.route(&state).await.unwrap(), NodeId::new("revise"));
+
+        state.set("score", 1.0);
+        assert_eq!(router.route(&state).await.unwrap(), NodeId::new("review"));
+
+        state.set("verdict", "approve");
+        assert_eq!(router.route(&state).await.unwrap(), NodeId::new("publish"));
+
+        let targets: Vec<_> = router.targets().into_iter().map(|id| id.0).collect();
+        assert_eq!(targets, ["publish", "review", "revise"]);
+    }
+
+    #[tokio::test]
+    async fn test_state_router_without_otherwise_fails_when_nothing_matches() {
+        let router = when("approved").equals(true).goto("publish");
+        let err = router.route(&GraphState::new()).await.unwrap_err();
+        assert!(matches!(err, RGraphError::Routing { .. }));
+    }
+
+    #[test]
+    fn test_expression_parses_to_its_canonical_form() {
+        let cases = [
+            ("score>=0.8", "score >= 0.8"),
+            ("verdict == 'approve'", "verdict == \"approve\""),
+            ("a || b && !c", "a || b && !c"),
+            ("(a || b) && c", "(a || b) && c"),
+            ("!(done == true)", "!(done == true)"),
+            ("user.tier != null", "user.tier != null"),
+        ];
+        for (source, canonical) in cases {
+            let expr = StateExpr::parse(source).unwrap();
+            assert_eq!(expr.to_string(), canonical);
+            assert_eq!(StateExpr::parse(canonical).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_expression_errors_name_the_column() {
+        let cases = [
+            ("score >=", "expected a value after `>=` at column 9"),
+            ("score > 1 &&", "expected a state key at column 13"),
+            ("(a || b", "expected `)` at column 8"),
+            ("a b", "expected `&&`, `||` or the end at column 3"),
+            ("tier == 'gold", "unterminated string at column 9"),
+            ("a # b", "unexpected `#` at column 3"),
+        ];
+        for (source, message) in cases {
+            let err = StateExpr::parse(source).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", source, err);
+        }
+    }
+
+    #[test]
+    fn test_expression_evaluates_against_state() {
+        let state = GraphState::new()
+            .with_input("score", 0.9)
+            .with_input("attempts", 3)
+            .with_input("tier", "gold")
+            .with_input("tags", StateValue::Array(Vec::new()));
+        let holds = |source: &str| StateExpr::parse(source).unwrap().evaluate(&state);
+
+        assert!(holds("score >= 0.8 && attempts < 5"));
+        assert!(holds("attempts == 3.0"));
+        assert!(holds("tier > 'basic'"));
+        assert!(!holds("tier > 1"));
+        assert!(!holds("tags"));
+        assert!(holds("!tags || missing"));
+        // Comparisons with a missing key are false, whichever the operator
+        assert!(!holds("missing == null"));
+        assert!(!holds("missing != 1"));
+    }
+
+    #[tokio::test]
+    async fn test_expr_router_takes_first_condition_that_holds() {
+        let router = ExprRouter::new()
+            .branch(StateExpr::parse("score >= 0.8").unwrap(), "publish")
+            .branch(
+                StateExpr::parse("score >= 0.5 || override").unwrap(),
+                "review",
+            )
+            .otherwise("revise");
+        let state = GraphState::new().with_input("score", 0.6);
+
+        assert_eq!(router.route(&state).await.unwrap(), NodeId::new("review"));
+        state.set("score", 0.95);
+        assert_eq!(router.route(&state).await.unwrap(), NodeId::new("publish"));
+        state.set("score", 0.1);
+        assert_eq!(router.route(&state).await.unwrap(), NodeId::new("revise"));
+
+        assert_eq!(router.keys(), ["score", "override"]);
+        let described: Vec<_> = router
+            .describe()
+            .into_iter()
+            .map(|(target, condition)| format!("{} if {}", target.as_str(), condition))
+            .collect();
+        assert_eq!(
+            described,
+            [
+                "publish if score >= 0.8",
+                "review if score >= 0.5 || override",
+                "revise if otherwise",
+            ]
+        );
+    }
+}
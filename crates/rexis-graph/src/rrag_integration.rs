@@ -288,6 +288,10 @@ impl Node for RagGenerationNode {
     fn output_keys(&self) -> Vec<&str> {
         vec![&self.config.response_key, "generation_metadata"]
     }
+
+    fn calls_llm(&self) -> bool {
+        true
+    }
 }
 
 /// A node that evaluates context relevance
@@ -0,0 +1,990 @@
+//! # Scheduled Runs
+//!
+//! A [`GraphScheduler`] runs graphs when a [`Trigger`] fires: every fixed
+//! interval, or on the minutes a cron expression matches. Each run starts
+//! from a fresh initial state made by the schedule's factory.
+//!
+//! Schedules and the status of their last run are stored as JSON under
+//! `schedule::{schedule_id}` in a [`Memory`] backend. Graphs cannot be
+//! stored, so a process that restarts schedules its graphs again; a schedule
+//! with the ID of a stored one continues it, and the runs missed while no
+//! process ran it follow its [`CatchUpPolicy`].
+//!
+//! Cron expressions have five fields, evaluated in UTC: minute, hour, day of
+//! month, month and day of week (0 or 7 for Sunday). Fields take `*`, values,
+//! ranges, lists and steps, as in `*/15 9-17 * * 1-5`. When both day fields
+//! are restricted, a day matching either one fires.
+
+use crate::core::WorkflowGraph;
+use crate::execution::ExecutionEngine;
+use crate::state::GraphState;
+use crate::{RGraphError, RGraphResult};
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+use parking_lot::Mutex;
+use rexis_rag::storage::{Memory, MemoryQuery, MemoryValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Most runs a catch-up counts, so an old schedule does not loop for long
+const MAX_MISSED_RUNS: usize = 10_000;
+
+/// When a schedule fires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger {
+    /// Every interval, the first one interval after the graph is scheduled
+    Interval(Duration),
+    /// On the minutes a five-field cron expression matches, in UTC
+    Cron(String),
+}
+
+/// What to do when a schedule fires while its last run is still going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverlapPolicy {
+    /// Drop the new run and count it as skipped
+    #[default]
+    Skip,
+    /// Start the new run once the runs before it finish
+    Queue,
+}
+
+/// What to do with the runs a schedule missed while no process ran it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CatchUpPolicy {
+    /// Drop them and wait for the next time the trigger fires
+    #[default]
+    Skip,
+    /// Run once for all of them
+    RunOnce,
+    /// Run once for each of them, one after another, up to `max` runs
+    RunAll { max: usize },
+}
+
+/// How a scheduled run went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    /// The run failed, or finished with node errors; holds the first error
+    Failed(String),
+}
+
+/// The last run a schedule started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastRun {
+    /// ID of the run, once it finished
+    pub run_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: RunStatus,
+}
+
+/// A schedule as stored in the [`Memory`] backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleInfo {
+    pub schedule_id: String,
+    pub graph_id: String,
+    pub trigger: Trigger,
+    pub overlap: OverlapPolicy,
+    pub catch_up: CatchUpPolicy,
+    /// Paused schedules only run through [`GraphScheduler::trigger_now`]
+    pub paused: bool,
+    /// When the trigger fires next, if it ever does
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run: Option<LastRun>,
+    /// Runs started so far
+    pub runs: u64,
+    /// Runs dropped by [`OverlapPolicy::Skip`]
+    pub skipped: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Options of a schedule
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleConfig {
+    /// ID of the schedule; defaults to the ID of its graph
+    pub id: Option<String>,
+    pub overlap: OverlapPolicy,
+    pub catch_up: CatchUpPolicy,
+}
+
+type StateFactory = Arc<dyn Fn() -> GraphState + Send + Sync>;
+
+/// Runs graphs on triggers, keeping their schedules in a [`Memory`] backend
+///
+/// Dropping the scheduler stops its schedules; runs already going finish.
+pub struct GraphScheduler {
+    storage: Arc<dyn Memory>,
+    engine: ExecutionEngine,
+    schedules: Mutex<HashMap<String, Arc<Schedule>>>,
+}
+
+impl std::fmt::Debug for GraphScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ids: Vec<String> = self.schedules.lock().keys().cloned().collect();
+        ids.sort();
+        f.debug_struct("GraphScheduler")
+            .field("storage", &self.storage.backend_name())
+            .field("schedules", &ids)
+            .finish()
+    }
+}
+
+impl GraphScheduler {
+    /// Create a scheduler storing its schedules in `storage`
+    pub fn new(storage: Arc<dyn Memory>) -> Self {
+        Self {
+            storage,
+            engine: ExecutionEngine::new(),
+            schedules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run the graphs with this engine
+    pub fn with_engine(mut self, engine: ExecutionEngine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Key a schedule is stored under
+    pub fn schedule_key(schedule_id: &str) -> String {
+        format!("{}{}", SCHEDULE_PREFIX, schedule_id)
+    }
+
+    /// Run `graph` whenever `trigger` fires, starting from the state
+    /// `initial_state` makes, with the default [`ScheduleConfig`]
+    ///
+    /// Returns the ID of the schedule, which is the ID of the graph.
+    pub async fn schedule(
+        &self,
+        graph: &WorkflowGraph,
+        trigger: Trigger,
+        initial_state: impl Fn() -> GraphState + Send + Sync + 'static,
+    ) -> RGraphResult<String> {
+        self.schedule_with(graph, trigger, initial_state, ScheduleConfig::default())
+            .await
+    }
+
+    /// Run `graph` whenever `trigger` fires, with the given options
+    ///
+    /// A stored schedule with the same ID keeps its history and paused flag.
+    /// If its trigger is unchanged, it also keeps its next run, and the runs
+    /// it missed since follow `config.catch_up`. Fails with
+    /// [`RGraphError::Config`] when the trigger is invalid or this scheduler
+    /// already runs a schedule with the ID.
+    pub async fn schedule_with(
+        &self,
+        graph: &WorkflowGraph,
+        trigger: Trigger,
+        initial_state: impl Fn() -> GraphState + Send + Sync + 'static,
+        config: ScheduleConfig,
+    ) -> RGraphResult<String> {
+        let timer = Timer::new(&trigger)?;
+        let id = config.id.unwrap_or_else(|| graph.id().to_string());
+        if self.schedules.lock().contains_key(&id) {
+            return Err(RGraphError::config(format!(
+                "Schedule '{}' already exists",
+                id
+            )));
+        }
+
+        let now = Utc::now();
+        let stored = self.load(&id).await?;
+        let resumed = stored.as_ref().is_some_and(|info| info.trigger == trigger);
+        let mut info = stored.unwrap_or_else(|| ScheduleInfo {
+            schedule_id: id.clone(),
+            graph_id: graph.id().to_string(),
+            trigger: trigger.clone(),
+            overlap: config.overlap,
+            catch_up: config.catch_up,
+            paused: false,
+            next_run_at: None,
+            last_run: None,
+            runs: 0,
+            skipped: 0,
+            created_at: now,
+        });
+
+        let limit = match config.catch_up {
+            CatchUpPolicy::Skip => 0,
+            CatchUpPolicy::RunOnce => 1,
+            CatchUpPolicy::RunAll { max } => max.min(MAX_MISSED_RUNS),
+        };
+        let (missed, next_run_at) = match info.next_run_at {
+            Some(next) if resumed && next > now => (0, Some(next)),
+            Some(next) if resumed && !info.paused => {
+                (timer.missed(next, now, limit), timer.next_after(now))
+            }
+            _ => (0, timer.next_after(now)),
+        };
+        info.graph_id = graph.id().to_string();
+        info.trigger = trigger;
+        info.overlap = config.overlap;
+        info.catch_up = config.catch_up;
+        info.next_run_at = next_run_at;
+        save(self.storage.as_ref(), &info).await?;
+
+        let schedule = Arc::new(Schedule {
+            id: id.clone(),
+            graph: graph.clone(),
+            timer,
+            overlap: config.overlap,
+            initial_state: Arc::new(initial_state),
+            engine: self.engine.clone(),
+            storage: self.storage.clone(),
+            paused: AtomicBool::new(info.paused),
+            slot: Mutex::new(Slot::default()),
+            info: tokio::sync::Mutex::new(info),
+            ticker: Mutex::new(None),
+        });
+        {
+            let mut schedules = self.schedules.lock();
+            if schedules.contains_key(&id) {
+                return Err(RGraphError::config(format!(
+                    "Schedule '{}' already exists",
+                    id
+                )));
+            }
+            schedules.insert(id.clone(), schedule.clone());
+        }
+
+        if missed > 0 {
+            schedule.start(missed);
+        }
+        if let Some(next) = next_run_at {
+            let first = (next - now).to_std().unwrap_or_default();
+            let ticker = tokio::spawn(schedule.clone().tick(first));
+            *schedule.ticker.lock() = Some(ticker);
+        }
+
+        Ok(id)
+    }
+
+    /// Every stored schedule, by ID, including those of earlier processes
+    /// that this scheduler does not run
+    pub async fn list_schedules(&self) -> RGraphResult<Vec<ScheduleInfo>> {
+        let keys = self
+            .storage
+            .keys(&MemoryQuery::new().with_pattern(SCHEDULE_PREFIX))
+            .await?;
+
+        let mut schedules = Vec::new();
+        for key in keys {
+            let Some(id) = key.strip_prefix(SCHEDULE_PREFIX) else {
+                continue;
+            };
+            if let Some(info) = self.load(id).await? {
+                schedules.push(info);
+            }
+        }
+        schedules.sort_by(|a, b| a.schedule_id.cmp(&b.schedule_id));
+
+        Ok(schedules)
+    }
+
+    /// Stop a schedule from firing until it is resumed; a run already going
+    /// finishes
+    pub async fn pause(&self, id: &str) -> RGraphResult<()> {
+        let schedule = self.get(id)?;
+        schedule.paused.store(true, Ordering::SeqCst);
+        schedule.update(|info| info.paused = true).await
+    }
+
+    /// Let a paused schedule fire again
+    pub async fn resume(&self, id: &str) -> RGraphResult<()> {
+        let schedule = self.get(id)?;
+        schedule.paused.store(false, Ordering::SeqCst);
+        schedule.update(|info| info.paused = false).await
+    }
+
+    /// Fire a schedule now, even if it is paused; a run already going is
+    /// handled by its overlap policy
+    pub async fn trigger_now(&self, id: &str) -> RGraphResult<()> {
+        self.get(id)?.fire().await
+    }
+
+    /// Stop a schedule and remove it from storage; a run already going
+    /// finishes
+    pub async fn unschedule(&self, id: &str) -> RGraphResult<()> {
+        let schedule = self.schedules.lock().remove(id);
+        if let Some(schedule) = schedule {
+            schedule.stop();
+        }
+        self.storage.delete(&Self::schedule_key(id)).await?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> RGraphResult<Arc<Schedule>> {
+        self.schedules
+            .lock()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| RGraphError::config(format!("No schedule '{}'", id)))
+    }
+
+    async fn load(&self, id: &str) -> RGraphResult<Option<ScheduleInfo>> {
+        let key = Self::schedule_key(id);
+        match self.storage.get(&key).await? {
+            Some(MemoryValue::Json(value)) => Ok(Some(serde_json::from_value(value)?)),
+            Some(_) => Err(RGraphError::state(format!(
+                "Schedule '{}' is not stored as JSON",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for GraphScheduler {
+    fn drop(&mut self) {
+        for schedule in self.schedules.lock().values() {
+            schedule.stop();
+        }
+    }
+}
+
+const SCHEDULE_PREFIX: &str = "schedule::";
+
+async fn save(storage: &dyn Memory, info: &ScheduleInfo) -> RGraphResult<()> {
+    let value = serde_json::to_value(info)?;
+    storage
+        .set(
+            &GraphScheduler::schedule_key(&info.schedule_id),
+            MemoryValue::Json(value),
+        )
+        .await?;
+    Ok(())
+}
+
+/// A schedule this process runs
+struct Schedule {
+    id: String,
+    graph: WorkflowGraph,
+    timer: Timer,
+    overlap: OverlapPolicy,
+    initial_state: StateFactory,
+    engine: ExecutionEngine,
+    storage: Arc<dyn Memory>,
+    paused: AtomicBool,
+    slot: Mutex<Slot>,
+    /// The stored schedule, locked while it is saved so saves keep order
+    info: tokio::sync::Mutex<ScheduleInfo>,
+    ticker: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Whether a schedule is running, and how many runs wait behind it
+#[derive(Default)]
+struct Slot {
+    running: bool,
+    queued: usize,
+}
+
+impl Schedule {
+    /// Wait for the trigger to fire, over and over
+    async fn tick(self: Arc<Self>, first: Duration) {
+        let mut at = Instant::now() + first;
+        loop {
+            tokio::time::sleep_until(at).await;
+            if !self.paused.load(Ordering::SeqCst) {
+                if let Err(e) = self.fire().await {
+                    tracing::warn!("Schedule '{}' failed to fire: {}", self.id, e);
+                }
+            }
+
+            let now = Utc::now();
+            let next = self.timer.next_after(now);
+            if let Err(e) = self.update(|info| info.next_run_at = next).await {
+                tracing::warn!("Schedule '{}' failed to save: {}", self.id, e);
+            }
+            at = match (&self.timer, next) {
+                // Intervals count from the last tick, so they do not drift
+                (Timer::Interval(period), _) => at + *period,
+                (Timer::Cron(_), Some(next)) => {
+                    Instant::now() + (next - now).to_std().unwrap_or_default()
+                }
+                (Timer::Cron(_), None) => return,
+            };
+        }
+    }
+
+    /// Start a run, or apply the overlap policy when one is going
+    async fn fire(self: &Arc<Self>) -> RGraphResult<()> {
+        if self.start(1) {
+            return Ok(());
+        }
+        tracing::warn!(
+            "Schedule '{}' skipped a run because the last one is still going",
+            self.id
+        );
+        self.update(|info| info.skipped += 1).await
+    }
+
+    /// Start `runs` runs one after another, returning false when the overlap
+    /// policy drops them
+    fn start(self: &Arc<Self>, runs: usize) -> bool {
+        let mut slot = self.slot.lock();
+        if slot.running {
+            if self.overlap == OverlapPolicy::Skip {
+                return false;
+            }
+            slot.queued += runs;
+            return true;
+        }
+        slot.running = true;
+        slot.queued = runs - 1;
+        tokio::spawn(self.clone().drain());
+        true
+    }
+
+    /// Run until no runs are queued
+    async fn drain(self: Arc<Self>) {
+        loop {
+            self.run().await;
+            let mut slot = self.slot.lock();
+            if slot.queued == 0 {
+                slot.running = false;
+                return;
+            }
+            slot.queued -= 1;
+        }
+    }
+
+    async fn run(&self) {
+        let started = self
+            .update(|info| {
+                info.runs += 1;
+                info.last_run = Some(LastRun {
+                    run_id: None,
+                    started_at: Utc::now(),
+                    finished_at: None,
+                    status: RunStatus::Running,
+                });
+            })
+            .await;
+        if let Err(e) = started {
+            tracing::warn!("Schedule '{}' failed to save: {}", self.id, e);
+        }
+
+        let state = (self.initial_state)();
+        let (run_id, status) = match self.engine.execute(&self.graph, state).await {
+            Ok(results) => {
+                let status = match results.errors.first() {
+                    Some(error) => RunStatus::Failed(error.error_message.clone()),
+                    None => RunStatus::Succeeded,
+                };
+                (Some(results.run_id), status)
+            }
+            Err(e) => (None, RunStatus::Failed(e.to_string())),
+        };
+
+        let finished = self
+            .update(|info| {
+                if let Some(last_run) = &mut info.last_run {
+                    last_run.run_id = run_id;
+                    last_run.finished_at = Some(Utc::now());
+                    last_run.status = status;
+                }
+            })
+            .await;
+        if let Err(e) = finished {
+            tracing::warn!("Schedule '{}' failed to save: {}", self.id, e);
+        }
+    }
+
+    /// Change the stored schedule and save it
+    async fn update(&self, change: impl FnOnce(&mut ScheduleInfo)) -> RGraphResult<()> {
+        let mut info = self.info.lock().await;
+        change(&mut info);
+        save(self.storage.as_ref(), &info).await
+    }
+
+    fn stop(&self) {
+        if let Some(ticker) = self.ticker.lock().take() {
+            ticker.abort();
+        }
+    }
+}
+
+/// A trigger, ready to compute when it fires
+enum Timer {
+    Interval(Duration),
+    Cron(CronExpr),
+}
+
+impl Timer {
+    fn new(trigger: &Trigger) -> RGraphResult<Self> {
+        match trigger {
+            Trigger::Interval(period) if period.is_zero() => Err(RGraphError::config(
+                "Schedule interval must be longer than zero",
+            )),
+            Trigger::Interval(period) => Ok(Timer::Interval(*period)),
+            Trigger::Cron(expr) => CronExpr::parse(expr).map(Timer::Cron),
+        }
+    }
+
+    /// The first time after `after` the trigger fires, if it ever does
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Timer::Interval(period) => chrono::Duration::from_std(*period)
+                .ok()
+                .and_then(|period| after.checked_add_signed(period)),
+            Timer::Cron(cron) => cron.next_after(after),
+        }
+    }
+
+    /// How many times the trigger fired from `first` up to `now`, counting
+    /// at most `limit`
+    fn missed(&self, first: DateTime<Utc>, now: DateTime<Utc>, limit: usize) -> usize {
+        let mut missed = 0;
+        let mut at = Some(first);
+        while let Some(time) = at {
+            if time > now || missed == limit {
+                break;
+            }
+            missed += 1;
+            at = self.next_after(time);
+        }
+        missed
+    }
+}
+
+/// A parsed five-field cron expression
+#[derive(Debug, Clone)]
+struct CronExpr {
+    minutes: CronField,
+    hours: CronField,
+    days: CronField,
+    months: CronField,
+    weekdays: CronField,
+}
+
+impl CronExpr {
+    fn parse(expr: &str) -> RGraphResult<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(RGraphError::config(format!(
+                "Invalid cron expression '{}': expected 5 fields, found {}",
+                expr,
+                fields.len()
+            )));
+        };
+
+        let mut weekdays = CronField::parse(expr, weekdays, 0, 7)?;
+        // Sunday is both 0 and 7
+        if weekdays.contains(7) {
+            weekdays.allowed |= 1;
+        }
+        Ok(Self {
+            minutes: CronField::parse(expr, minutes, 0, 59)?,
+            hours: CronField::parse(expr, hours, 0, 23)?,
+            days: CronField::parse(expr, days, 1, 31)?,
+            months: CronField::parse(expr, months, 1, 12)?,
+            weekdays,
+        })
+    }
+
+    /// The first matching minute after `after`, searching five years ahead
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let end = time + chrono::Duration::days(5 * 366);
+        while time < end {
+            if !self.months.contains(time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.day_matches(&time) {
+                time = time
+                    .date_naive()
+                    .succ_opt()?
+                    .and_hms_opt(0, 0, 0)?
+                    .and_utc();
+            } else if !self.hours.contains(time.hour()) {
+                time = time.with_minute(0)? + chrono::Duration::hours(1);
+            } else if !self.minutes.contains(time.minute()) {
+                time += chrono::Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, time: &DateTime<Utc>) -> bool {
+        let day = self.days.contains(time.day());
+        let weekday = self
+            .weekdays
+            .contains(time.weekday().num_days_from_sunday());
+        if self.days.any || self.weekdays.any {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+/// The values one cron field allows
+#[derive(Debug, Clone, Copy)]
+struct CronField {
+    allowed: u64,
+    /// Whether the field starts with `*`, which matters for the day fields
+    any: bool,
+}
+
+impl CronField {
+    fn parse(expr: &str, field: &str, min: u32, max: u32) -> RGraphResult<Self> {
+        let invalid = || {
+            RGraphError::config(format!(
+                "Invalid cron expression '{}': field '{}' must hold values from {} to {}",
+                expr, field, min, max
+            ))
+        };
+        let value = |text: &str| {
+            text.parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(invalid)
+        };
+
+        let mut allowed = 0;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => {
+                    let step = step.parse::<usize>().ok().filter(|step| *step > 0);
+                    (range, Some(step.ok_or_else(invalid)?))
+                }
+                None => (part, None),
+            };
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((start, end)) => (value(start)?, value(end)?),
+                // A single value with a step runs to the end, as in `5/15`
+                None if step.is_some() => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            };
+            if start > end {
+                return Err(invalid());
+            }
+            for bit in (start..=end).step_by(step.unwrap_or(1)) {
+                allowed |= 1 << bit;
+            }
+        }
+
+        Ok(Self {
+            allowed,
+            any: field.starts_with('*'),
+        })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.allowed & (1 << value) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeId};
+    use async_trait::async_trait;
+    use rexis_rag::storage::InMemoryStorage;
+    use std::sync::atomic::AtomicUsize;
+
+    // Node counting its runs, each of which takes `delay`
+    struct TickNode {
+        id: NodeId,
+        runs: Arc<AtomicUsize>,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Node for TickNode {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+    }
+
+    async fn graph(runs: Arc<AtomicUsize>, delay: Duration) -> WorkflowGraph {
+        let node = TickNode {
+            id: NodeId::new("tick"),
+            runs,
+            delay,
+        };
+        GraphBuilder::new("report")
+            .id("report")
+            .add_node("tick", Arc::new(node))
+            .await
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn minutes(count: u64) -> Duration {
+        Duration::from_secs(60 * count)
+    }
+
+    async fn sleep_until_minute(start: Instant, minute: u64, seconds: u64) {
+        tokio::time::sleep_until(start + minutes(minute) + Duration::from_secs(seconds)).await;
+    }
+
+    async fn only_schedule(scheduler: &GraphScheduler) -> ScheduleInfo {
+        let mut schedules = scheduler.list_schedules().await.unwrap();
+        assert_eq!(schedules.len(), 1);
+        schedules.remove(0)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interval_fires_every_period() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = GraphScheduler::new(Arc::new(InMemoryStorage::new()));
+        let start = Instant::now();
+        let id = scheduler
+            .schedule(
+                &graph(runs.clone(), Duration::ZERO).await,
+                Trigger::Interval(minutes(1)),
+                GraphState::new,
+            )
+            .await
+            .unwrap();
+        assert_eq!(id, "report");
+
+        sleep_until_minute(start, 0, 59).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        sleep_until_minute(start, 1, 1).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        sleep_until_minute(start, 3, 1).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_overlapping_run_is_skipped() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = GraphScheduler::new(Arc::new(InMemoryStorage::new()));
+        let start = Instant::now();
+        // Runs take 90 seconds, so the one due at minute 2 overlaps
+        scheduler
+            .schedule(
+                &graph(runs.clone(), Duration::from_secs(90)).await,
+                Trigger::Interval(minutes(1)),
+                GraphState::new,
+            )
+            .await
+            .unwrap();
+
+        sleep_until_minute(start, 3, 20).await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        let info = only_schedule(&scheduler).await;
+        assert_eq!(info.runs, 2);
+        assert_eq!(info.skipped, 1);
+        assert_eq!(info.last_run.unwrap().status, RunStatus::Running);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_overlapping_run_is_queued() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = GraphScheduler::new(Arc::new(InMemoryStorage::new()));
+        let start = Instant::now();
+        scheduler
+            .schedule_with(
+                &graph(runs.clone(), Duration::from_secs(90)).await,
+                Trigger::Interval(minutes(1)),
+                GraphState::new,
+                ScheduleConfig {
+                    overlap: OverlapPolicy::Queue,
+                    ..ScheduleConfig::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // The run due at minute 2 starts when the first ends at 2:30
+        sleep_until_minute(start, 2, 20).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        sleep_until_minute(start, 2, 40).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(only_schedule(&scheduler).await.skipped, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_last_run_is_stored() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let runs = Arc::new(AtomicUsize::new(0));
+        let start = Instant::now();
+        {
+            let scheduler = GraphScheduler::new(storage.clone());
+            scheduler
+                .schedule(
+                    &graph(runs.clone(), Duration::ZERO).await,
+                    Trigger::Interval(minutes(1)),
+                    GraphState::new,
+                )
+                .await
+                .unwrap();
+            sleep_until_minute(start, 1, 1).await;
+        }
+
+        // A scheduler of the next process sees the schedule before running it
+        let info = only_schedule(&GraphScheduler::new(storage)).await;
+        assert_eq!(info.schedule_id, "report");
+        assert_eq!(info.graph_id, "report");
+        assert_eq!(info.trigger, Trigger::Interval(minutes(1)));
+        assert_eq!(info.runs, 1);
+        assert!(info.next_run_at.is_some());
+        let last_run = info.last_run.unwrap();
+        assert_eq!(last_run.status, RunStatus::Succeeded);
+        assert!(last_run.run_id.is_some());
+        assert!(last_run.finished_at.is_some());
+
+        // Dropping the scheduler stopped the schedule
+        sleep_until_minute(start, 3, 1).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_missed_runs_follow_catch_up_policy() {
+        for (catch_up, expected) in [
+            (CatchUpPolicy::Skip, 0),
+            (CatchUpPolicy::RunOnce, 1),
+            (CatchUpPolicy::RunAll { max: 10 }, 3),
+            (CatchUpPolicy::RunAll { max: 2 }, 2),
+        ] {
+            let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+            let runs = Arc::new(AtomicUsize::new(0));
+            let graph = graph(runs.clone(), Duration::ZERO).await;
+            let config = ScheduleConfig {
+                catch_up,
+                ..ScheduleConfig::default()
+            };
+
+            // The last process stopped with a run due 150 seconds ago, so
+            // runs were missed then and 90 and 30 seconds ago
+            let scheduler = GraphScheduler::new(storage.clone());
+            scheduler
+                .schedule_with(
+                    &graph,
+                    Trigger::Interval(minutes(1)),
+                    GraphState::new,
+                    config.clone(),
+                )
+                .await
+                .unwrap();
+            drop(scheduler);
+            let mut stored = only_schedule(&GraphScheduler::new(storage.clone())).await;
+            stored.next_run_at = Some(Utc::now() - chrono::Duration::seconds(150));
+            save(storage.as_ref(), &stored).await.unwrap();
+
+            let start = Instant::now();
+            let scheduler = GraphScheduler::new(storage.clone());
+            scheduler
+                .schedule_with(
+                    &graph,
+                    Trigger::Interval(minutes(1)),
+                    GraphState::new,
+                    config,
+                )
+                .await
+                .unwrap();
+            sleep_until_minute(start, 0, 30).await;
+
+            assert_eq!(runs.load(Ordering::SeqCst), expected, "{:?}", catch_up);
+            assert!(only_schedule(&scheduler).await.next_run_at.unwrap() > Utc::now());
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_paused_schedule_runs_only_when_triggered() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let scheduler = GraphScheduler::new(Arc::new(InMemoryStorage::new()));
+        let start = Instant::now();
+        let id = scheduler
+            .schedule(
+                &graph(runs.clone(), Duration::ZERO).await,
+                Trigger::Interval(minutes(1)),
+                GraphState::new,
+            )
+            .await
+            .unwrap();
+
+        scheduler.pause(&id).await.unwrap();
+        sleep_until_minute(start, 2, 30).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        assert!(only_schedule(&scheduler).await.paused);
+
+        scheduler.trigger_now(&id).await.unwrap();
+        sleep_until_minute(start, 2, 31).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        scheduler.resume(&id).await.unwrap();
+        sleep_until_minute(start, 3, 1).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        scheduler.unschedule(&id).await.unwrap();
+        assert!(scheduler.list_schedules().await.unwrap().is_empty());
+        assert!(scheduler.trigger_now(&id).await.is_err());
+    }
+
+    #[test]
+    fn test_cron_finds_next_matching_minute() {
+        let at = |day, hour, minute| Utc.with_ymd_and_hms(2024, 6, day, hour, minute, 0).unwrap();
+        let weekdays = CronExpr::parse("*/15 9-17 * * 1-5").unwrap();
+
+        // June 1st 2024 is a Saturday
+        assert_eq!(weekdays.next_after(at(1, 10, 7)), Some(at(3, 9, 0)));
+        assert_eq!(weekdays.next_after(at(3, 9, 0)), Some(at(3, 9, 15)));
+        assert_eq!(weekdays.next_after(at(3, 17, 45)), Some(at(4, 9, 0)));
+
+        // Restricting both day fields matches either
+        let thirteenth_or_friday = CronExpr::parse("0 0 13 * 5").unwrap();
+        assert_eq!(
+            thirteenth_or_friday.next_after(at(3, 0, 0)),
+            Some(at(7, 0, 0))
+        );
+        assert_eq!(
+            thirteenth_or_friday.next_after(at(7, 0, 0)),
+            Some(at(13, 0, 0))
+        );
+
+        let sundays = CronExpr::parse("30 6 * * 7").unwrap();
+        assert_eq!(sundays.next_after(at(3, 0, 0)), Some(at(9, 6, 30)));
+        assert!(CronExpr::parse("0 0 30 2 *")
+            .unwrap()
+            .next_after(at(1, 0, 0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_triggers_are_rejected() {
+        for (trigger, message) in [
+            (
+                Trigger::Cron("* * *".to_string()),
+                "expected 5 fields, found 3",
+            ),
+            (Trigger::Cron("60 * * * *".to_string()), "field '60'"),
+            (Trigger::Cron("*/0 * * * *".to_string()), "field '*/0'"),
+            (Trigger::Cron("0 5-1 * * *".to_string()), "field '5-1'"),
+            (Trigger::Interval(Duration::ZERO), "longer than zero"),
+        ] {
+            let err = Timer::new(&trigger).err().unwrap();
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! # State Schemas
+//!
+//! A [`StateSchema`] declares the state keys a graph works with and the type
+//! of their values. With a schema on the graph, building it fails when a node
+//! reads or writes an undeclared key, runs fail when a required key is
+//! missing from the initial state, and writes of mistyped values are caught
+//! as they happen.
+
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Type of the values a state key holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StateType {
+    String,
+    Integer,
+    /// Floats, and integers, which convert to floats without loss of meaning
+    Float,
+    Boolean,
+    Array,
+    Object,
+    Bytes,
+    /// Any value, null included
+    Any,
+}
+
+impl StateType {
+    /// Whether `value` is of this type
+    pub fn matches(&self, value: &StateValue) -> bool {
+        matches!(
+            (self, value),
+            (StateType::Any, _)
+                | (StateType::String, StateValue::String(_))
+                | (StateType::Integer, StateValue::Integer(_))
+                | (
+                    StateType::Float,
+                    StateValue::Float(_) | StateValue::Integer(_)
+                )
+                | (StateType::Boolean, StateValue::Boolean(_))
+                | (StateType::Array, StateValue::Array(_))
+                | (StateType::Object, StateValue::Object(_))
+                | (StateType::Bytes, StateValue::Bytes(_))
+        )
+    }
+}
+
+impl fmt::Display for StateType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StateType::String => "string",
+            StateType::Integer => "integer",
+            StateType::Float => "float",
+            StateType::Boolean => "boolean",
+            StateType::Array => "array",
+            StateType::Object => "object",
+            StateType::Bytes => "bytes",
+            StateType::Any => "any",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How mistyped writes to the state are handled at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SchemaMode {
+    /// Reject the write and fail the node that made it
+    #[default]
+    Strict,
+    /// Keep the write and log a warning
+    Warn,
+}
+
+/// A key declared by a [`StateSchema`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StateKey {
+    pub value_type: StateType,
+    /// Whether the initial state of a run must hold the key
+    pub required: bool,
+}
+
+/// Declared state keys of a graph and the types of their values
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StateSchema {
+    keys: HashMap<String, StateKey>,
+    mode: SchemaMode,
+}
+
+impl StateSchema {
+    /// Create a strict schema without keys
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a key the initial state of every run must hold
+    pub fn required(mut self, key: impl Into<String>, value_type: StateType) -> Self {
+        self.keys.insert(
+            key.into(),
+            StateKey {
+                value_type,
+                required: true,
+            },
+        );
+        self
+    }
+
+    /// Declare a key that nodes may set during the run
+    pub fn optional(mut self, key: impl Into<String>, value_type: StateType) -> Self {
+        self.keys.insert(
+            key.into(),
+            StateKey {
+                value_type,
+                required: false,
+            },
+        );
+        self
+    }
+
+    /// Set how mistyped writes are handled
+    pub fn with_mode(mut self, mode: SchemaMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> SchemaMode {
+        self.mode
+    }
+
+    /// Get the declaration of a key
+    pub fn key(&self, key: &str) -> Option<&StateKey> {
+        self.keys.get(key)
+    }
+
+    /// Whether the schema declares the top-level key of `path`, so that
+    /// nested paths like `"user.name"` are covered by `"user"`
+    pub fn declares(&self, path: &str) -> bool {
+        let key = path.split('.').next().unwrap_or(path);
+        self.keys.contains_key(key)
+    }
+
+    /// Check a value written to `key`; undeclared keys are not checked
+    pub fn check_value(&self, key: &str, value: &StateValue) -> RGraphResult<()> {
+        match self.keys.get(key) {
+            Some(declared) if !declared.value_type.matches(value) => {
+                Err(RGraphError::validation(format!(
+                    "State key '{}' must hold {}, not {}",
+                    key,
+                    declared.value_type,
+                    value.type_name()
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that `state` holds every required key and that declared keys
+    /// hold values of their type
+    pub fn check_state(&self, state: &GraphState) -> RGraphResult<()> {
+        let values = state.snapshot();
+        let mut problems = Vec::new();
+        let mut keys: Vec<_> = self.keys.iter().collect();
+        keys.sort_by_key(|(key, _)| key.as_str());
+
+        for (key, declared) in keys {
+            match values.get(key) {
+                Some(value) => {
+                    if let Err(e) = self.check_value(key, value) {
+                        problems.push(e.to_string());
+                    }
+                }
+                None if declared.required => {
+                    problems.push(format!("Required state key '{}' is missing", key));
+                }
+                None => {}
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(RGraphError::validation(problems.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeId};
+    use crate::execution::ExecutionEngine;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    // Node writing the length of its input string to its output key
+    struct LengthNode {
+        id: NodeId,
+        input: &'static str,
+        output: &'static str,
+    }
+
+    #[async_trait]
+    impl Node for LengthNode {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let input: String = state.get_as(self.input)?;
+            state.set(self.output, input.len() as i64);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn input_keys(&self) -> Vec<&str> {
+            vec![self.input]
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            vec![self.output]
+        }
+    }
+
+    async fn length_graph(input: &'static str, schema: StateSchema) -> RGraphResult<GraphBuilder> {
+        let node = LengthNode {
+            id: NodeId::new("length"),
+            input,
+            output: "answer",
+        };
+        GraphBuilder::new("length")
+            .state_schema(schema)
+            .add_node("length", Arc::new(node))
+            .await
+    }
+
+    fn answer_schema() -> StateSchema {
+        StateSchema::new()
+            .required("user_input", StateType::String)
+            .optional("answer", StateType::String)
+    }
+
+    fn input() -> GraphState {
+        GraphState::new().with_input("user_input", "hello")
+    }
+
+    fn schema() -> StateSchema {
+        StateSchema::new()
+            .required("user_input", StateType::String)
+            .optional("score", StateType::Float)
+            .optional("user", StateType::Object)
+    }
+
+    #[test]
+    fn test_types_match_their_values() {
+        assert!(StateType::Float.matches(&StateValue::Integer(3)));
+        assert!(!StateType::Integer.matches(&StateValue::Float(3.5)));
+        assert!(StateType::Any.matches(&StateValue::Null));
+        assert!(!StateType::String.matches(&StateValue::Null));
+    }
+
+    #[test]
+    fn test_nested_paths_are_declared_by_their_key() {
+        let schema = schema();
+
+        assert!(schema.declares("user.name"));
+        assert!(!schema.declares("user_imput"));
+    }
+
+    #[test]
+    fn test_check_state_reports_every_problem() {
+        let state = GraphState::new().with_input("score", "high");
+
+        let err = schema().check_state(&state).unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains("'score' must hold float, not string"));
+        assert!(message.contains("Required state key 'user_input' is missing"));
+    }
+
+    #[tokio::test]
+    async fn test_build_rejects_undeclared_keys() {
+        let err = length_graph("user_imput", answer_schema())
+            .await
+            .unwrap()
+            .build()
+            .err()
+            .expect("undeclared keys are rejected");
+
+        assert!(matches!(err, RGraphError::Validation { .. }));
+        assert!(err.to_string().contains("reads state key 'user_imput'"));
+    }
+
+    #[tokio::test]
+    async fn test_strict_schema_fails_mistyped_writes() {
+        let graph = length_graph("user_input", answer_schema())
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, input())
+            .await
+            .unwrap();
+
+        assert!(!results.metrics.success);
+        let error = &results.errors[0];
+        assert_eq!(error.node_id, "length");
+        assert!(error
+            .error_message
+            .contains("'answer' must hold string, not integer"));
+        assert!(!results.final_state.contains_key("answer"));
+    }
+
+    #[tokio::test]
+    async fn test_warn_schema_keeps_mistyped_writes() {
+        let schema = answer_schema().with_mode(SchemaMode::Warn);
+        let graph = length_graph("user_input", schema)
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let results = ExecutionEngine::new()
+            .execute(&graph, input())
+            .await
+            .unwrap();
+
+        assert!(results.metrics.success);
+        assert_eq!(results.final_state.get_as::<i64>("answer").unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_required_keys_are_checked_before_the_run() {
+        let graph = length_graph("user_input", answer_schema())
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let err = ExecutionEngine::new()
+            .execute(&graph, GraphState::new())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("'user_input' is missing"));
+    }
+}
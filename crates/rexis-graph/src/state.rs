@@ -4,13 +4,17 @@
 //! The state flows through the graph execution, accumulating results and
 //! providing context for decision-making.
 
+use crate::reducer::Reducer;
+use crate::schema::{SchemaMode, StateSchema};
 use crate::{RGraphError, RGraphResult};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::collections::HashMap;
 use std::sync::Arc;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
 
 /// Path to a value in the graph state (supports nested access)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -52,8 +56,12 @@ impl From<&str> for StatePath {
 }
 
 /// Values that can be stored in the graph state
+///
+/// With serde, values take their plain form: strings, numbers, booleans,
+/// sequences, maps, unit for null and byte strings for bytes. In JSON, a
+/// value reads back as it was written, except for bytes and floats that are
+/// not finite, which JSON cannot represent.
 #[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum StateValue {
     /// String value
     String(String),
@@ -257,19 +265,190 @@ impl From<StateValue> for serde_json::Value {
     }
 }
 
+#[cfg(feature = "serde")]
+impl Serialize for StateValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            StateValue::String(s) => serializer.serialize_str(s),
+            StateValue::Integer(i) => serializer.serialize_i64(*i),
+            StateValue::Float(f) => serializer.serialize_f64(*f),
+            StateValue::Boolean(b) => serializer.serialize_bool(*b),
+            StateValue::Array(items) => serializer.collect_seq(items),
+            // Sorted, so that the same value always serializes the same
+            StateValue::Object(fields) => {
+                serializer.collect_map(fields.iter().collect::<BTreeMap<_, _>>())
+            }
+            StateValue::Null => serializer.serialize_unit(),
+            StateValue::Bytes(bytes) => serializer.serialize_bytes(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for StateValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StateValueVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct StateValueVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for StateValueVisitor {
+    type Value = StateValue;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a state value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<StateValue, E> {
+        Ok(StateValue::Boolean(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<StateValue, E> {
+        Ok(StateValue::Integer(i))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> Result<StateValue, E> {
+        Ok(i64::try_from(u)
+            .map(StateValue::Integer)
+            .unwrap_or(StateValue::Float(u as f64)))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<StateValue, E> {
+        Ok(StateValue::Float(f))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<StateValue, E> {
+        Ok(StateValue::String(s.to_string()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<StateValue, E> {
+        Ok(StateValue::String(s))
+    }
+
+    fn visit_bytes<E>(self, bytes: &[u8]) -> Result<StateValue, E> {
+        Ok(StateValue::Bytes(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> Result<StateValue, E> {
+        Ok(StateValue::Bytes(bytes))
+    }
+
+    fn visit_none<E>(self) -> Result<StateValue, E> {
+        Ok(StateValue::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<StateValue, E> {
+        Ok(StateValue::Null)
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<StateValue, D::Error> {
+        StateValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<StateValue, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(StateValue::Array(items))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<StateValue, A::Error> {
+        let mut fields = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((name, field)) = map.next_entry()? {
+            fields.insert(name, field);
+        }
+        Ok(StateValue::Object(fields))
+    }
+}
+
+/// How [`GraphState::to_json_with`] handles values JSON cannot represent:
+/// bytes, and floats that are not finite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnrepresentableValues {
+    /// Fail, naming the path of the value
+    #[default]
+    Error,
+    /// Leave the value out and log a warning
+    Skip,
+}
+
 /// The shared state that flows through the graph execution
+///
+/// With serde, a state serializes as its data, metadata and history, with
+/// keys in order. The schema a run checks writes against is not included.
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GraphState {
     /// The state data
-    #[cfg_attr(feature = "serde", serde(skip, default = "default_data"))]
     data: Arc<RwLock<HashMap<String, StateValue>>>,
     /// Metadata about the state
-    #[cfg_attr(feature = "serde", serde(skip, default = "default_metadata"))]
     metadata: Arc<RwLock<HashMap<String, StateValue>>>,
     /// Execution history
-    #[cfg_attr(feature = "serde", serde(skip, default = "default_execution_log"))]
     execution_log: Arc<RwLock<Vec<StateHistoryEntry>>>,
+    /// Schema writes are checked against
+    schema: Option<Arc<StateSchema>>,
+    /// Writes a strict schema or a reducer rejected since they were last
+    /// checked
+    rejected_writes: Arc<Mutex<Vec<String>>>,
+    /// Reducers writes to their keys are combined with
+    reducers: Arc<HashMap<String, Reducer>>,
+    /// Updates written to keys with reducers, when they are recorded
+    updates: Option<RecordedUpdates>,
+}
+
+/// Updates written to keys with reducers, in the order they were written
+type RecordedUpdates = Arc<Mutex<Vec<(String, StateValue)>>>;
+
+/// Serialized form of a [`GraphState`]
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct StateDocument {
+    data: BTreeMap<String, StateValue>,
+    #[serde(default)]
+    metadata: BTreeMap<String, StateValue>,
+    #[serde(default)]
+    history: Vec<StateHistoryEntry>,
+}
+
+#[cfg(feature = "serde")]
+impl From<&GraphState> for StateDocument {
+    fn from(state: &GraphState) -> Self {
+        Self {
+            data: state.data.read().clone().into_iter().collect(),
+            metadata: state.metadata.read().clone().into_iter().collect(),
+            history: state.execution_history(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<StateDocument> for GraphState {
+    fn from(document: StateDocument) -> Self {
+        let state = GraphState::with_data(document.data.into_iter().collect());
+        *state.metadata.write() = document.metadata.into_iter().collect();
+        *state.execution_log.write() = document.history;
+        state
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for GraphState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateDocument::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for GraphState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        StateDocument::deserialize(deserializer).map(GraphState::from)
+    }
 }
 
 /// Entry in the state execution history
@@ -301,6 +480,10 @@ impl GraphState {
             data: Arc::new(RwLock::new(HashMap::new())),
             metadata: Arc::new(RwLock::new(HashMap::new())),
             execution_log: Arc::new(RwLock::new(Vec::new())),
+            schema: None,
+            rejected_writes: Arc::default(),
+            reducers: Arc::default(),
+            updates: None,
         }
     }
 
@@ -310,13 +493,28 @@ impl GraphState {
             data: Arc::new(RwLock::new(data)),
             metadata: Arc::new(RwLock::new(HashMap::new())),
             execution_log: Arc::new(RwLock::new(Vec::new())),
+            schema: None,
+            rejected_writes: Arc::default(),
+            reducers: Arc::default(),
+            updates: None,
         }
     }
 
     /// Set a value in the state
+    ///
+    /// When the graph declares a reducer for `key`, the value is an update
+    /// combined with the one the key holds. With a strict schema, a value of
+    /// the wrong type is not stored; the node that wrote it fails once it
+    /// returns.
     pub fn set(&self, key: impl Into<String>, value: impl Into<StateValue>) -> &Self {
         let key = key.into();
         let value = value.into();
+        let Some(value) = self.reduced(&key, value) else {
+            return self;
+        };
+        if !self.accepts(&key, &value) {
+            return self;
+        }
 
         // Log the operation
         self.log_operation(
@@ -343,6 +541,12 @@ impl GraphState {
     ) -> &Self {
         let key = key.into();
         let value = value.into();
+        let Some(value) = self.reduced(&key, value) else {
+            return self;
+        };
+        if !self.accepts(&key, &value) {
+            return self;
+        }
 
         // Get old value for logging
         let old_value = self.data.read().get(&key).cloned();
@@ -363,6 +567,124 @@ impl GraphState {
         self
     }
 
+    /// Set a value, failing when the schema declares another type for `key`
+    pub fn try_set(
+        &self,
+        key: impl Into<String>,
+        value: impl Into<StateValue>,
+    ) -> RGraphResult<&Self> {
+        let key = key.into();
+        let value = value.into();
+        if let Some(schema) = &self.schema {
+            schema.check_value(&key, &value)?;
+        }
+        Ok(self.set(key, value))
+    }
+
+    /// Whether a write passes the schema; strict schemas record rejected ones
+    fn accepts(&self, key: &str, value: &StateValue) -> bool {
+        let Some(schema) = &self.schema else {
+            return true;
+        };
+        let Err(e) = schema.check_value(key, value) else {
+            return true;
+        };
+        match schema.mode() {
+            SchemaMode::Strict => {
+                self.rejected_writes.lock().push(e.to_string());
+                false
+            }
+            SchemaMode::Warn => {
+                tracing::warn!("{}", e);
+                true
+            }
+        }
+    }
+
+    /// Combine a write to a key with a reducer with the key's value; writes
+    /// the reducer cannot combine are recorded as rejected
+    fn reduced(&self, key: &str, value: StateValue) -> Option<StateValue> {
+        let Some(reducer) = self.reducers.get(key) else {
+            return Some(value);
+        };
+        let current = self.data.read().get(key).cloned();
+        match reducer.reduce(key, current.as_ref(), value.clone()) {
+            Ok(reduced) => {
+                if let Some(updates) = &self.updates {
+                    updates.lock().push((key.to_string(), value));
+                }
+                Some(reduced)
+            }
+            Err(e) => {
+                self.rejected_writes.lock().push(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Combine writes to keys with reducers from now on
+    pub(crate) fn set_reducers(&mut self, reducers: Arc<HashMap<String, Reducer>>) {
+        self.reducers = reducers;
+    }
+
+    /// Record the updates written to keys with reducers, so that they can be
+    /// applied to another state; forks record their own
+    pub(crate) fn record_updates(&mut self) {
+        self.updates = Some(Arc::default());
+    }
+
+    /// Take the updates recorded since [`GraphState::record_updates`]
+    pub(crate) fn take_updates(&self) -> Vec<(String, StateValue)> {
+        self.updates
+            .as_ref()
+            .map(|updates| std::mem::take(&mut *updates.lock()))
+            .unwrap_or_default()
+    }
+
+    /// Copy the updates recorded since [`GraphState::record_updates`],
+    /// leaving them in place
+    #[cfg_attr(
+        not(all(feature = "rexis-rag-integration", feature = "serde")),
+        allow(dead_code)
+    )]
+    pub(crate) fn recorded_updates(&self) -> Vec<(String, StateValue)> {
+        self.updates
+            .as_ref()
+            .map(|updates| updates.lock().clone())
+            .unwrap_or_default()
+    }
+
+    /// Record updates as if they had been written here, when recording
+    #[cfg_attr(
+        not(all(feature = "rexis-rag-integration", feature = "serde")),
+        allow(dead_code)
+    )]
+    pub(crate) fn extend_updates(&self, recorded: Vec<(String, StateValue)>) {
+        if let Some(updates) = &self.updates {
+            updates.lock().extend(recorded);
+        }
+    }
+
+    /// Check writes against `schema` from now on
+    pub(crate) fn set_schema(&mut self, schema: Option<Arc<StateSchema>>) {
+        self.schema = schema;
+    }
+
+    /// Fail when the schema rejected writes `node_id` made, forgetting them
+    pub(crate) fn check_writes(&self, node_id: &str) -> RGraphResult<()> {
+        let rejected = std::mem::take(&mut *self.rejected_writes.lock());
+        if rejected.is_empty() {
+            return Ok(());
+        }
+        Err(RGraphError::node(
+            node_id,
+            format!(
+                "wrote to the state against its schema or reducers: {}",
+                rejected.join("; ")
+            ),
+        ))
+    }
+
     /// Get a value from the state
     pub fn get(&self, key: &str) -> RGraphResult<StateValue> {
         let path = StatePath::new(key);
@@ -415,6 +737,18 @@ impl GraphState {
         T::try_from(value).map_err(|e| RGraphError::state(e.to_string()))
     }
 
+    /// Get the value of `key` as `T`, with an error naming the key when it is
+    /// missing or holds another type
+    pub fn get_as<T>(&self, key: &str) -> RGraphResult<T>
+    where
+        T: TryFrom<StateValue>,
+        T::Error: std::fmt::Display,
+    {
+        let value = self.get(key)?;
+        T::try_from(value)
+            .map_err(|e| RGraphError::state(format!("Cannot read state key '{}': {}", key, e)))
+    }
+
     /// Check if a key exists in the state
     pub fn contains_key(&self, key: &str) -> bool {
         self.data.read().contains_key(key)
@@ -476,6 +810,20 @@ impl GraphState {
         self.data.read().clone()
     }
 
+    /// Copy the data and metadata into an independent state; unlike `clone`,
+    /// writes to the copy do not show up here
+    pub fn fork(&self) -> Self {
+        Self {
+            data: Arc::new(RwLock::new(self.data.read().clone())),
+            metadata: Arc::new(RwLock::new(self.metadata.read().clone())),
+            execution_log: Arc::new(RwLock::new(Vec::new())),
+            schema: self.schema.clone(),
+            rejected_writes: Arc::default(),
+            reducers: self.reducers.clone(),
+            updates: self.updates.as_ref().map(|_| Arc::default()),
+        }
+    }
+
     /// Set metadata
     pub fn set_metadata(&self, key: impl Into<String>, value: impl Into<StateValue>) {
         let mut metadata = self.metadata.write();
@@ -507,6 +855,57 @@ impl GraphState {
         self.get_typed(key)
     }
 
+    /// Serialize the state to pretty-printed JSON, failing on values JSON
+    /// cannot represent
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> RGraphResult<String> {
+        self.to_json_with(UnrepresentableValues::Error)
+    }
+
+    /// Serialize the state to pretty-printed JSON, handling values JSON cannot
+    /// represent according to `unrepresentable`
+    #[cfg(feature = "serde")]
+    pub fn to_json_with(&self, unrepresentable: UnrepresentableValues) -> RGraphResult<String> {
+        let document = StateDocument::from(self);
+        let mut json_safe = StateDocument {
+            data: BTreeMap::new(),
+            metadata: BTreeMap::new(),
+            history: Vec::with_capacity(document.history.len()),
+        };
+        for (key, value) in document.data {
+            if let Some(value) = json_safe_value(&key, value, unrepresentable)? {
+                json_safe.data.insert(key, value);
+            }
+        }
+        for (key, value) in document.metadata {
+            let path = format!("metadata.{}", key);
+            if let Some(value) = json_safe_value(&path, value, unrepresentable)? {
+                json_safe.metadata.insert(key, value);
+            }
+        }
+        for (i, mut entry) in document.history.into_iter().enumerate() {
+            let path = format!("history[{}]", i);
+            entry.old_value = match entry.old_value {
+                Some(value) => json_safe_value(&path, value, unrepresentable)?,
+                None => None,
+            };
+            entry.new_value = match entry.new_value {
+                Some(value) => json_safe_value(&path, value, unrepresentable)?,
+                None => None,
+            };
+            json_safe.history.push(entry);
+        }
+
+        Ok(serde_json::to_string_pretty(&json_safe)?)
+    }
+
+    /// Read a state written by [`GraphState::to_json`]
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> RGraphResult<Self> {
+        let document: StateDocument = serde_json::from_str(json)?;
+        Ok(document.into())
+    }
+
     /// Log a state operation
     fn log_operation(
         &self,
@@ -535,6 +934,53 @@ impl Default for GraphState {
     }
 }
 
+/// Strip `value`, found at `path`, of what JSON cannot represent, or fail
+#[cfg(feature = "serde")]
+fn json_safe_value(
+    path: &str,
+    value: StateValue,
+    unrepresentable: UnrepresentableValues,
+) -> RGraphResult<Option<StateValue>> {
+    let problem = match value {
+        StateValue::Array(items) => {
+            let mut kept = Vec::with_capacity(items.len());
+            for (i, item) in items.into_iter().enumerate() {
+                let item_path = format!("{}[{}]", path, i);
+                kept.extend(json_safe_value(&item_path, item, unrepresentable)?);
+            }
+            return Ok(Some(StateValue::Array(kept)));
+        }
+        StateValue::Object(fields) => {
+            let mut kept = HashMap::with_capacity(fields.len());
+            for (name, field) in fields {
+                let field_path = format!("{}.{}", path, name);
+                if let Some(field) = json_safe_value(&field_path, field, unrepresentable)? {
+                    kept.insert(name, field);
+                }
+            }
+            return Ok(Some(StateValue::Object(kept)));
+        }
+        StateValue::Float(f) if !f.is_finite() => format!("{} is not a finite number", f),
+        StateValue::Bytes(_) => "bytes have no JSON form".to_string(),
+        value => return Ok(Some(value)),
+    };
+
+    match unrepresentable {
+        UnrepresentableValues::Error => Err(RGraphError::state(format!(
+            "Cannot write state value '{}' as JSON: {}",
+            path, problem
+        ))),
+        UnrepresentableValues::Skip => {
+            tracing::warn!(
+                "Leaving state value '{}' out of the JSON: {}",
+                path,
+                problem
+            );
+            Ok(None)
+        }
+    }
+}
+
 // Implement TryFrom for common types from StateValue
 impl TryFrom<StateValue> for String {
     type Error = RGraphError;
@@ -668,6 +1114,159 @@ mod tests {
         assert_eq!(state.get("age").unwrap().as_integer(), Some(30));
     }
 
+    #[test]
+    fn test_get_as_names_the_key() {
+        let state = GraphState::new()
+            .with_input("name", "Alice")
+            .with_input("age", "forty");
+
+        assert_eq!(state.get_as::<String>("name").unwrap(), "Alice");
+        let err = state.get_as::<i64>("age").unwrap_err();
+        assert!(err.to_string().contains("Cannot read state key 'age'"));
+        assert!(err.to_string().contains("string to i64"));
+        assert!(state.get_as::<i64>("height").is_err());
+    }
+
+    #[test]
+    fn test_strict_schema_rejects_mistyped_values() {
+        let mut state = GraphState::new();
+        state.set_schema(Some(Arc::new(
+            StateSchema::new().optional("age", crate::schema::StateType::Integer),
+        )));
+
+        state.set("age", "forty");
+        assert!(!state.contains_key("age"));
+        assert!(state.try_set("age", "forty").is_err());
+        assert!(state.check_writes("profile").is_err());
+        // Rejected writes are reported once
+        assert!(state.check_writes("profile").is_ok());
+
+        state.set("age", 40);
+        assert_eq!(state.get_as::<i64>("age").unwrap(), 40);
+    }
+
+    #[cfg(feature = "serde")]
+    fn fixture() -> GraphState {
+        let address = HashMap::from([
+            ("city".to_string(), StateValue::from("London")),
+            ("zip".to_string(), StateValue::Null),
+        ]);
+        let profile = HashMap::from([
+            ("address".to_string(), StateValue::from(address)),
+            (
+                "languages".to_string(),
+                StateValue::Array(vec![StateValue::from("en"), StateValue::from("fr")]),
+            ),
+            ("verified".to_string(), StateValue::from(true)),
+        ]);
+        let state = GraphState::new()
+            .with_input("name", "Ada Lovelace")
+            .with_input("age", 36)
+            .with_input("balance", -12)
+            .with_input("score", 0.87)
+            .with_input("ratio", 2.0)
+            .with_input("active", true)
+            .with_input("retired", false)
+            .with_input(
+                "tags",
+                vec![StateValue::from("math"), StateValue::from("poetry")],
+            )
+            .with_input("scores", vec![1.into(), 2.5.into(), StateValue::Null])
+            .with_input("profile", profile);
+        state.set_metadata("source", "fixture");
+        state.set_metadata("version", 2);
+        // Writes are logged with the time they were made
+        state.execution_log.write().clear();
+        state
+    }
+
+    #[cfg(feature = "serde")]
+    fn golden_path() -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/state/round_trip.json")
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_state_json_matches_golden_file() {
+        let json = fixture().to_json().unwrap();
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(golden_path(), &json).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(golden_path()).unwrap();
+        assert_eq!(
+            json, expected,
+            "round_trip.json is out of date; rerun with UPDATE_GOLDEN=1"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_state_json_round_trips() {
+        let golden = std::fs::read_to_string(golden_path()).unwrap();
+
+        let state = GraphState::from_json(&golden).unwrap();
+
+        let expected = fixture();
+        assert_eq!(state.snapshot(), expected.snapshot());
+        assert_eq!(state.get_metadata("version"), Some(StateValue::Integer(2)));
+        assert_eq!(state.get("ratio").unwrap(), StateValue::Float(2.0));
+        assert_eq!(state.to_json().unwrap(), golden);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_state_history_round_trips() {
+        let state = GraphState::new();
+        state.set_with_context("draft", "summary", "first");
+        state.set_with_context("review", "summary", "second");
+
+        let restored = GraphState::from_json(&state.to_json().unwrap()).unwrap();
+
+        let history = restored.execution_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].node_id, "review");
+        assert_eq!(history[1].old_value, Some(StateValue::from("first")));
+        assert_eq!(history[1].timestamp, state.execution_history()[1].timestamp);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_unrepresentable_values_fail_or_are_skipped() {
+        let stats = HashMap::from([
+            ("mean".to_string(), StateValue::Float(f64::NAN)),
+            ("count".to_string(), StateValue::Integer(0)),
+        ]);
+        let state = GraphState::new()
+            .with_input("stats", stats)
+            .with_input("thumbnail", vec![0x89u8, 0x50])
+            .with_input("title", "empty");
+
+        let err = state.to_json().unwrap_err();
+        assert!(err.to_string().contains("'stats.mean'"));
+
+        let json = state.to_json_with(UnrepresentableValues::Skip).unwrap();
+        let restored = GraphState::from_json(&json).unwrap();
+        assert!(!restored.contains_key("thumbnail"));
+        assert_eq!(restored.get("title").unwrap().as_string(), Some("empty"));
+        let stats = restored.get("stats").unwrap();
+        assert_eq!(stats.as_object().unwrap().len(), 1);
+        assert_eq!(restored.get("stats.count").unwrap().as_integer(), Some(0));
+    }
+
+    #[test]
+    fn test_state_fork_is_independent() {
+        let state = GraphState::new().with_input("topic", "rust");
+        let fork = state.fork();
+
+        fork.set("summary", "done");
+        state.set("topic", "go");
+
+        assert!(!state.contains_key("summary"));
+        assert_eq!(fork.get("topic").unwrap().as_string(), Some("rust"));
+    }
+
     #[test]
     fn test_state_merge() {
         let state1 = GraphState::new();
@@ -694,19 +1293,3 @@ mod tests {
         assert_eq!(history[1].node_id, "node2");
     }
 }
-
-// Default functions for serde skipped fields
-#[cfg(feature = "serde")]
-fn default_data() -> Arc<RwLock<HashMap<String, StateValue>>> {
-    Arc::new(RwLock::new(HashMap::new()))
-}
-
-#[cfg(feature = "serde")]
-fn default_metadata() -> Arc<RwLock<HashMap<String, StateValue>>> {
-    Arc::new(RwLock::new(HashMap::new()))
-}
-
-#[cfg(feature = "serde")]
-fn default_execution_log() -> Arc<RwLock<Vec<StateHistoryEntry>>> {
-    Arc::new(RwLock::new(Vec::new()))
-}
@@ -0,0 +1,1043 @@
+//! # Supervisor Pattern
+//!
+//! [`SupervisorBuilder`] generates the supervisor/worker topology: a
+//! supervisor that delegates tasks to specialist workers, round after round,
+//! and a synthesis step that turns their results into the final answer.
+//!
+//! ```text
+//! supervisor ──> worker ──> supervisor ──> ... ──> synthesis
+//!      └──────> delegate (several workers at once) ──┘
+//! ```
+//!
+//! The result is an ordinary [`WorkflowGraph`], so limits, checkpointing,
+//! events and every other execution feature apply to it.
+//!
+//! Each round the supervisor answers with a JSON plan naming the workers to
+//! run and their tasks, one after another or all at once. A worker sees its
+//! task at [`TASK_KEY`] in a copy of the state and leaves its result at
+//! [`RESULT_KEY`]; results accumulate at `workers.<name>` as a list of
+//! `{task, result}` objects. The supervisor ends delegation with an empty plan,
+//! or the builder's maximum number of rounds ends it.
+
+use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node, NodeId, WorkflowGraph};
+use crate::execution::{acquire_slot, USAGE_KEY};
+use crate::nodes::LlmAgentNode;
+use crate::report::NodeUsage;
+use crate::state::{GraphState, StateValue};
+use crate::{RGraphError, RGraphResult};
+use async_trait::async_trait;
+use rexis_rag::rexis_llm::{ChatMessage, Client, RequestOptions};
+use rexis_rag::{Agent, RunControl};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Key a worker reads its task from
+pub const TASK_KEY: &str = "task";
+
+/// Key a worker writes its result to
+pub const RESULT_KEY: &str = "result";
+
+/// Key the results of all workers accumulate under, by worker name
+pub const WORKERS_KEY: &str = "workers";
+
+/// Key counting the supervisor's delegation rounds
+pub const ROUND_KEY: &str = "supervisor_round";
+
+const SUPERVISOR: &str = "supervisor";
+const DELEGATE: &str = "delegate";
+const SYNTHESIS: &str = "synthesis";
+const ASSIGNMENT_KEY: &str = "supervisor_task";
+const QUEUE_KEY: &str = "supervisor_queue";
+
+/// The model behind the supervisor and synthesis steps
+pub enum SupervisorModel {
+    /// Chat with a client directly
+    Client(Box<Client>),
+    /// Run an agent, whose own system prompt and tools apply
+    Agent(Arc<Agent>),
+}
+
+impl From<Client> for SupervisorModel {
+    fn from(client: Client) -> Self {
+        Self::Client(Box::new(client))
+    }
+}
+
+impl From<Arc<Agent>> for SupervisorModel {
+    fn from(agent: Arc<Agent>) -> Self {
+        Self::Agent(agent)
+    }
+}
+
+impl SupervisorModel {
+    /// Answer `input` under `instructions`, with the usage it took
+    async fn ask(
+        &self,
+        node: &NodeId,
+        instructions: &str,
+        input: String,
+        context: &ExecutionContext,
+    ) -> RGraphResult<(String, Option<NodeUsage>)> {
+        let failed = |e: String| match context.deadline {
+            Some(deadline) if deadline.passed() => deadline.exceeded(),
+            _ if context.is_cancelled() => {
+                RGraphError::node(node.as_str(), "model call was cancelled")
+            }
+            _ => RGraphError::node(node.as_str(), format!("model call failed: {}", e)),
+        };
+
+        match self {
+            Self::Client(client) => {
+                let mut options =
+                    RequestOptions::new().with_cancellation(context.cancellation().clone());
+                if let Some(remaining) = context.remaining_time() {
+                    options = options.with_timeout(remaining);
+                }
+                let messages = vec![ChatMessage::system(instructions), ChatMessage::user(input)];
+                let response = client
+                    .chat_completion_with(messages, options)
+                    .await
+                    .map_err(|e| failed(e.to_string()))?;
+                let usage = response.usage.as_ref().map(|usage| {
+                    let node_usage =
+                        NodeUsage::new(usage.prompt_tokens as u64, usage.completion_tokens as u64);
+                    match client.pricing().cost(&response.model, usage) {
+                        Some(cost) => node_usage.with_cost(cost),
+                        None => node_usage,
+                    }
+                });
+                Ok((response.content, usage))
+            }
+            Self::Agent(agent) => {
+                let mut control = RunControl::new().with_cancel(context.cancellation().clone());
+                if let Some(remaining) = context.remaining_time() {
+                    control = control.with_deadline(remaining);
+                }
+                let result = agent
+                    .run_detailed_with(format!("{}\n\n{}", instructions, input), control)
+                    .await
+                    .map_err(|e| failed(e.to_string()))?;
+                let mut usage =
+                    NodeUsage::new(result.usage.prompt_tokens, result.usage.completion_tokens);
+                if let Some(cost) = result.usage.estimated_cost_usd() {
+                    usage = usage.with_cost(cost);
+                }
+                Ok((result.output, Some(usage)))
+            }
+        }
+    }
+}
+
+/// A worker the supervisor can delegate to
+pub enum Worker {
+    /// A node reading its task from [`TASK_KEY`] and writing its result to
+    /// [`RESULT_KEY`]
+    Node(Arc<dyn Node>),
+    /// An agent answering its task
+    Agent(Arc<Agent>),
+}
+
+impl From<Arc<dyn Node>> for Worker {
+    fn from(node: Arc<dyn Node>) -> Self {
+        Self::Node(node)
+    }
+}
+
+impl From<Arc<Agent>> for Worker {
+    fn from(agent: Arc<Agent>) -> Self {
+        Self::Agent(agent)
+    }
+}
+
+/// Builder for a supervisor graph
+///
+/// The graph reads the request from `user_input` and writes the answer to
+/// `answer` unless configured otherwise; the supervisor has three rounds of
+/// delegation by default.
+pub struct SupervisorBuilder {
+    id: String,
+    model: Arc<SupervisorModel>,
+    workers: Vec<(String, String, Worker)>,
+    synthesis_prompt: String,
+    max_rounds: usize,
+    input_key: String,
+    output_key: String,
+}
+
+impl SupervisorBuilder {
+    pub fn new(model: impl Into<SupervisorModel>) -> Self {
+        Self {
+            id: SUPERVISOR.to_string(),
+            model: Arc::new(model.into()),
+            workers: Vec::new(),
+            synthesis_prompt: "Answer the request using the results of the workers.".to_string(),
+            max_rounds: 3,
+            input_key: "user_input".to_string(),
+            output_key: "answer".to_string(),
+        }
+    }
+
+    /// Set the ID of the generated graph, `supervisor` by default
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Add a worker, described to the supervisor by `description`
+    pub fn add_worker(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        worker: impl Into<Worker>,
+    ) -> Self {
+        self.workers
+            .push((name.into(), description.into(), worker.into()));
+        self
+    }
+
+    /// Set the instructions the final answer is synthesized under
+    pub fn with_synthesis_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.synthesis_prompt = prompt.into();
+        self
+    }
+
+    /// Bound how many rounds the supervisor delegates before the synthesis
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Set the key the request is read from, `user_input` by default
+    pub fn with_input_key(mut self, key: impl Into<String>) -> Self {
+        self.input_key = key.into();
+        self
+    }
+
+    /// Set the key the answer is written to, `answer` by default
+    pub fn with_output_key(mut self, key: impl Into<String>) -> Self {
+        self.output_key = key.into();
+        self
+    }
+
+    /// Generate the graph
+    pub async fn build(self) -> RGraphResult<WorkflowGraph> {
+        if self.workers.is_empty() {
+            return Err(RGraphError::config(
+                "a supervisor needs at least one worker",
+            ));
+        }
+        let mut names = HashSet::new();
+        for (name, _, _) in &self.workers {
+            if [SUPERVISOR, DELEGATE, SYNTHESIS].contains(&name.as_str()) {
+                return Err(RGraphError::config(format!(
+                    "worker name '{}' is reserved",
+                    name
+                )));
+            }
+            if !names.insert(name.as_str()) {
+                return Err(RGraphError::config(format!(
+                    "worker '{}' was added twice",
+                    name
+                )));
+            }
+        }
+
+        let workers: Vec<(String, String, Arc<dyn Node>)> = self
+            .workers
+            .into_iter()
+            .map(|(name, description, worker)| {
+                let node: Arc<dyn Node> = match worker {
+                    Worker::Node(node) => node,
+                    Worker::Agent(agent) => Arc::new(
+                        LlmAgentNode::new(name.as_str(), agent)
+                            .with_input_key(TASK_KEY)
+                            .with_output_key(RESULT_KEY)
+                            .with_metadata_key(None),
+                    ),
+                };
+                (name, description, node)
+            })
+            .collect();
+        let roster: Vec<(String, String)> = workers
+            .iter()
+            .map(|(name, description, _)| (name.clone(), description.clone()))
+            .collect();
+
+        let supervisor = SupervisorNode {
+            id: NodeId::new(SUPERVISOR),
+            model: self.model.clone(),
+            workers: roster,
+            max_rounds: self.max_rounds,
+            input_key: self.input_key.clone(),
+        };
+        let synthesis = SynthesisNode {
+            id: NodeId::new(SYNTHESIS),
+            model: self.model,
+            prompt: self.synthesis_prompt,
+            input_key: self.input_key,
+            output_key: self.output_key,
+        };
+        let delegate = DelegateNode {
+            id: NodeId::new(DELEGATE),
+            workers: workers
+                .iter()
+                .map(|(name, _, node)| (name.clone(), node.clone()))
+                .collect(),
+        };
+
+        // Each round follows a loop back to the supervisor at most once
+        let rounds = self.max_rounds.max(1);
+        let mut builder = GraphBuilder::new(self.id)
+            .add_node(SUPERVISOR, Arc::new(supervisor))
+            .await?
+            .add_node(DELEGATE, Arc::new(delegate))
+            .await?
+            .add_node(SYNTHESIS, Arc::new(synthesis))
+            .await?
+            .add_edge(SUPERVISOR, DELEGATE)?
+            .add_edge(SUPERVISOR, SYNTHESIS)?
+            .add_edge_cyclic(DELEGATE, SUPERVISOR, rounds)?;
+        for (name, _, inner) in workers {
+            let worker = WorkerNode {
+                id: NodeId::new(name.as_str()),
+                name: name.clone(),
+                inner,
+            };
+            builder = builder
+                .add_node(name.as_str(), Arc::new(worker))
+                .await?
+                .add_edge(SUPERVISOR, name.as_str())?
+                .add_edge_cyclic(name.as_str(), SUPERVISOR, rounds)?;
+        }
+        builder.build()
+    }
+}
+
+/// A task the supervisor gave a worker
+#[derive(Debug, Clone, PartialEq)]
+struct Assignment {
+    worker: String,
+    task: String,
+}
+
+impl Assignment {
+    fn to_value(&self) -> StateValue {
+        StateValue::Object(HashMap::from([
+            ("worker".to_string(), StateValue::from(self.worker.as_str())),
+            ("task".to_string(), StateValue::from(self.task.as_str())),
+        ]))
+    }
+
+    fn from_value(value: &StateValue) -> Option<Self> {
+        let StateValue::Object(fields) = value else {
+            return None;
+        };
+        Some(Self {
+            worker: fields.get("worker")?.as_string()?.to_string(),
+            task: fields.get("task")?.as_string()?.to_string(),
+        })
+    }
+}
+
+/// Read the assignments at `key`, which are gone when it is absent
+fn assignments(state: &GraphState, key: &str) -> Vec<Assignment> {
+    match state.get(key) {
+        Ok(StateValue::Array(items)) => items.iter().filter_map(Assignment::from_value).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn read_request(state: &GraphState, node: &NodeId, key: &str) -> RGraphResult<String> {
+    match state.get(key) {
+        Ok(StateValue::String(text)) => Ok(text),
+        Ok(_) => Err(RGraphError::node(
+            node.as_str(),
+            format!("'{}' must be a string", key),
+        )),
+        Err(_) => Err(RGraphError::node(
+            node.as_str(),
+            format!("input '{}' is missing from the state", key),
+        )),
+    }
+}
+
+/// The request with the results of the workers so far
+fn briefing(state: &GraphState, request: &str) -> String {
+    let mut lines = Vec::new();
+    if let Ok(StateValue::Object(workers)) = state.get(WORKERS_KEY) {
+        let mut names: Vec<&String> = workers.keys().collect();
+        names.sort();
+        for name in names {
+            let StateValue::Array(results) = &workers[name] else {
+                continue;
+            };
+            for entry in results {
+                let StateValue::Object(entry) = entry else {
+                    continue;
+                };
+                let task = entry.get("task").and_then(StateValue::as_string);
+                let result = match entry.get("result") {
+                    Some(StateValue::String(text)) => text.clone(),
+                    Some(value) => serde_json::Value::from(value.clone()).to_string(),
+                    None => continue,
+                };
+                lines.push(format!(
+                    "- {} (task: {}): {}",
+                    name,
+                    task.unwrap_or_default(),
+                    result
+                ));
+            }
+        }
+    }
+
+    let results = if lines.is_empty() {
+        "No results yet.".to_string()
+    } else {
+        lines.join("\n")
+    };
+    format!("Request:\n{}\n\nResults so far:\n{}", request, results)
+}
+
+fn record_usage(state: &GraphState, node: &str, usage: Option<NodeUsage>) {
+    if let Some(usage) = usage {
+        state.set_with_context(node, USAGE_KEY, usage);
+    }
+}
+
+/// Decides which workers run next
+struct SupervisorNode {
+    id: NodeId,
+    model: Arc<SupervisorModel>,
+    workers: Vec<(String, String)>,
+    max_rounds: usize,
+    input_key: String,
+}
+
+impl SupervisorNode {
+    fn instructions(&self) -> String {
+        let workers: Vec<String> = self
+            .workers
+            .iter()
+            .map(|(name, description)| format!("- {}: {}", name, description))
+            .collect();
+        format!(
+            "You are a supervisor delegating a request to workers. The workers are:\n{}\n\n\
+             Reply with JSON only, in the form \
+             {{\"delegate\": [{{\"worker\": \"<name>\", \"task\": \"<task>\"}}], \"parallel\": false}}. \
+             Set \"parallel\" to true to run the workers at once rather than one after another. \
+             Reply with an empty \"delegate\" list once the results answer the request.",
+            workers.join("\n")
+        )
+    }
+
+    /// The assignments in the supervisor's answer, and whether they run at
+    /// once; a worker named twice runs once
+    fn parse(&self, answer: &str, request: &str) -> RGraphResult<(Vec<Assignment>, bool)> {
+        let invalid = |reason: String| {
+            RGraphError::node(
+                self.id.as_str(),
+                format!("invalid delegation plan: {}", reason),
+            )
+        };
+        let json = match (answer.find('{'), answer.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &answer[start..=end],
+            _ => return Err(invalid(format!("no JSON object in '{}'", answer))),
+        };
+        let plan: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+
+        let mut assignments = Vec::new();
+        for entry in plan["delegate"].as_array().into_iter().flatten() {
+            let worker = entry["worker"]
+                .as_str()
+                .ok_or_else(|| invalid("an entry names no worker".to_string()))?;
+            if !self.workers.iter().any(|(name, _)| name == worker) {
+                return Err(invalid(format!("unknown worker '{}'", worker)));
+            }
+            if assignments
+                .iter()
+                .any(|assignment: &Assignment| assignment.worker == worker)
+            {
+                continue;
+            }
+            assignments.push(Assignment {
+                worker: worker.to_string(),
+                task: entry["task"].as_str().unwrap_or(request).to_string(),
+            });
+        }
+        let parallel = plan["parallel"].as_bool().unwrap_or(false);
+        Ok((assignments, parallel))
+    }
+
+    /// Hand the first assignment to its worker and queue the rest
+    fn dispatch(&self, state: &GraphState, mut queue: Vec<Assignment>) -> ExecutionResult {
+        let next = queue.remove(0);
+        let node = self.id.as_str();
+        state.set_with_context(node, ASSIGNMENT_KEY, next.to_value());
+        state.set_with_context(
+            node,
+            QUEUE_KEY,
+            queue.iter().map(Assignment::to_value).collect::<Vec<_>>(),
+        );
+        ExecutionResult::Route(next.worker)
+    }
+}
+
+#[async_trait]
+impl Node for SupervisorNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let queue = assignments(state, QUEUE_KEY);
+        if !queue.is_empty() {
+            return Ok(self.dispatch(state, queue));
+        }
+
+        let round = match state.get(ROUND_KEY) {
+            Ok(StateValue::Integer(round)) => round as usize,
+            _ => 0,
+        };
+        if round >= self.max_rounds {
+            return Ok(ExecutionResult::Route(SYNTHESIS.to_string()));
+        }
+
+        let request = read_request(state, &self.id, &self.input_key)?;
+        let (answer, usage) = self
+            .model
+            .ask(
+                &self.id,
+                &self.instructions(),
+                briefing(state, &request),
+                context,
+            )
+            .await?;
+        let node = context.current_node.as_str();
+        record_usage(state, node, usage);
+        state.set_with_context(node, ROUND_KEY, (round + 1) as i64);
+
+        let (plan, parallel) = self.parse(&answer, &request)?;
+        if plan.is_empty() {
+            Ok(ExecutionResult::Route(SYNTHESIS.to_string()))
+        } else if parallel && plan.len() > 1 {
+            state.set_with_context(
+                node,
+                ASSIGNMENT_KEY,
+                plan.iter().map(Assignment::to_value).collect::<Vec<_>>(),
+            );
+            Ok(ExecutionResult::Route(DELEGATE.to_string()))
+        } else {
+            Ok(self.dispatch(state, plan))
+        }
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        "Supervisor"
+    }
+
+    fn node_type(&self) -> &str {
+        "Supervisor"
+    }
+
+    fn calls_llm(&self) -> bool {
+        true
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        vec![&self.input_key, WORKERS_KEY]
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        vec![ROUND_KEY, ASSIGNMENT_KEY, QUEUE_KEY]
+    }
+}
+
+/// Run `inner` on its task in a copy of `state`, returning its result and
+/// the usage it reported
+async fn run_worker(
+    inner: &dyn Node,
+    state: &GraphState,
+    task: &str,
+    context: &ExecutionContext,
+) -> RGraphResult<(StateValue, Option<NodeUsage>)> {
+    let mut worker_state = state.fork();
+    worker_state.set(TASK_KEY, task);
+    worker_state.remove(RESULT_KEY);
+
+    let _slot = acquire_slot(inner, context).await?;
+    let result = inner.execute(&mut worker_state, context).await?;
+    worker_state.check_writes(inner.id().as_str())?;
+    if let ExecutionResult::Interrupted { .. } = result {
+        return Err(RGraphError::node(
+            inner.id().as_str(),
+            "workers cannot wait for input",
+        ));
+    }
+
+    let output = worker_state.get(RESULT_KEY).map_err(|_| {
+        RGraphError::node(
+            inner.id().as_str(),
+            format!("did not produce result '{}'", RESULT_KEY),
+        )
+    })?;
+    let usage = worker_state
+        .get(USAGE_KEY)
+        .ok()
+        .and_then(|usage| NodeUsage::from_state_value(&usage));
+    Ok((output, usage))
+}
+
+/// Add a worker's result to its list under [`WORKERS_KEY`]
+fn record_result(state: &GraphState, node: &str, worker: &str, task: &str, result: StateValue) {
+    let mut workers = match state.get(WORKERS_KEY) {
+        Ok(StateValue::Object(workers)) => workers,
+        _ => HashMap::new(),
+    };
+    let results = workers
+        .entry(worker.to_string())
+        .or_insert_with(|| StateValue::Array(Vec::new()));
+    if let StateValue::Array(results) = results {
+        results.push(StateValue::Object(HashMap::from([
+            ("task".to_string(), StateValue::from(task)),
+            ("result".to_string(), result),
+        ])));
+    }
+    state.set_with_context(node, WORKERS_KEY, StateValue::Object(workers));
+}
+
+/// Runs one worker on the task the supervisor handed it
+struct WorkerNode {
+    id: NodeId,
+    name: String,
+    inner: Arc<dyn Node>,
+}
+
+#[async_trait]
+impl Node for WorkerNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let assignment = state
+            .get(ASSIGNMENT_KEY)
+            .ok()
+            .as_ref()
+            .and_then(Assignment::from_value)
+            .filter(|assignment| assignment.worker == self.name)
+            .ok_or_else(|| RGraphError::node(self.id.as_str(), "no task was assigned"))?;
+
+        let (result, usage) =
+            run_worker(self.inner.as_ref(), state, &assignment.task, context).await?;
+        let node = context.current_node.as_str();
+        record_usage(state, node, usage);
+        record_result(state, node, &self.name, &assignment.task, result);
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> &str {
+        "Worker"
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.inner.is_side_effecting()
+    }
+
+    fn occupies_slot(&self) -> bool {
+        false
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        vec![ASSIGNMENT_KEY]
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        vec![WORKERS_KEY]
+    }
+}
+
+/// Runs several workers at once
+struct DelegateNode {
+    id: NodeId,
+    workers: HashMap<String, Arc<dyn Node>>,
+}
+
+#[async_trait]
+impl Node for DelegateNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let plan = assignments(state, ASSIGNMENT_KEY);
+        let shared: &GraphState = state;
+        let runs = plan.iter().map(|assignment| async move {
+            let inner = self.workers.get(&assignment.worker).ok_or_else(|| {
+                RGraphError::node(
+                    self.id.as_str(),
+                    format!("unknown worker '{}'", assignment.worker),
+                )
+            })?;
+            run_worker(inner.as_ref(), shared, &assignment.task, context).await
+        });
+        let results = futures::future::try_join_all(runs).await?;
+
+        let node = context.current_node.as_str();
+        let mut total: Option<NodeUsage> = None;
+        for (assignment, (result, usage)) in plan.iter().zip(results) {
+            if let Some(usage) = usage {
+                total.get_or_insert_with(NodeUsage::default).add(&usage);
+            }
+            record_result(state, node, &assignment.worker, &assignment.task, result);
+        }
+        record_usage(state, node, total);
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        "Delegate"
+    }
+
+    fn node_type(&self) -> &str {
+        "Delegate"
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.workers
+            .values()
+            .any(|worker| worker.is_side_effecting())
+    }
+
+    fn occupies_slot(&self) -> bool {
+        false
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        vec![ASSIGNMENT_KEY]
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        vec![WORKERS_KEY]
+    }
+}
+
+/// Writes the final answer from the results of the workers
+struct SynthesisNode {
+    id: NodeId,
+    model: Arc<SupervisorModel>,
+    prompt: String,
+    input_key: String,
+    output_key: String,
+}
+
+#[async_trait]
+impl Node for SynthesisNode {
+    async fn execute(
+        &self,
+        state: &mut GraphState,
+        context: &ExecutionContext,
+    ) -> RGraphResult<ExecutionResult> {
+        let request = read_request(state, &self.id, &self.input_key)?;
+        let (answer, usage) = self
+            .model
+            .ask(&self.id, &self.prompt, briefing(state, &request), context)
+            .await?;
+        let node = context.current_node.as_str();
+        record_usage(state, node, usage);
+        state.set_with_context(node, &self.output_key, answer);
+        Ok(ExecutionResult::Continue)
+    }
+
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        "Synthesis"
+    }
+
+    fn node_type(&self) -> &str {
+        "Synthesis"
+    }
+
+    fn calls_llm(&self) -> bool {
+        true
+    }
+
+    fn input_keys(&self) -> Vec<&str> {
+        vec![&self.input_key, WORKERS_KEY]
+    }
+
+    fn output_keys(&self) -> Vec<&str> {
+        vec![&self.output_key]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::{ExecutionEngine, ExecutionResults};
+    use parking_lot::Mutex;
+    use rexis_llm::testing::{respond_text, MockClient, MockRequest};
+    use rexis_rag::{AgentConfig, ToolExecutor};
+
+    // Worker answering "<name> did <task>", recording its tasks
+    struct EchoWorker {
+        id: NodeId,
+        tasks: Mutex<Vec<String>>,
+    }
+
+    fn echo(name: &str) -> Arc<EchoWorker> {
+        Arc::new(EchoWorker {
+            id: NodeId::new(name),
+            tasks: Mutex::new(Vec::new()),
+        })
+    }
+
+    #[async_trait]
+    impl Node for EchoWorker {
+        async fn execute(
+            &self,
+            state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            let task = state.get(TASK_KEY)?.as_string().unwrap().to_string();
+            state.set(RESULT_KEY, format!("{} did {}", self.id.as_str(), task));
+            self.tasks.lock().push(task);
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            vec![RESULT_KEY]
+        }
+    }
+
+    fn system_prompt(request: &MockRequest) -> &str {
+        request.messages[0].text().unwrap_or_default()
+    }
+
+    fn first_round(request: &MockRequest) -> bool {
+        system_prompt(request).starts_with("You are a supervisor")
+            && request
+                .last_message()
+                .and_then(|message| message.text())
+                .is_some_and(|text| text.contains("No results yet"))
+    }
+
+    // Supervisor delegating `plan` in its first round, then finishing
+    fn mock(plan: impl Into<String>) -> MockClient {
+        MockClient::builder()
+            .on(
+                |request| system_prompt(request).starts_with("Combine"),
+                respond_text("Final answer"),
+            )
+            .on(first_round, respond_text(plan))
+            .on(
+                |request| system_prompt(request).starts_with("You are a supervisor"),
+                respond_text(r#"{"delegate": []}"#),
+            )
+            .otherwise(respond_text("Drafted the summary"))
+            .build()
+    }
+
+    async fn run(graph: &WorkflowGraph) -> ExecutionResults {
+        let results = ExecutionEngine::new()
+            .execute(
+                graph,
+                GraphState::new().with_input("user_input", "Compare Rust and Go"),
+            )
+            .await
+            .unwrap();
+        assert!(results.errors.is_empty(), "{:?}", results.errors);
+        results
+    }
+
+    fn path(results: &ExecutionResults) -> Vec<&str> {
+        results
+            .trace
+            .iter()
+            .map(|step| step.node_id.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_delegates_to_a_single_worker() {
+        let mock =
+            mock(r#"Plan: {"delegate": [{"worker": "research", "task": "Find benchmarks"}]}"#);
+        let research = echo("research");
+        let writer = echo("writer");
+        let graph = SupervisorBuilder::new(mock.client())
+            .add_worker(
+                "research",
+                "Looks up facts",
+                research.clone() as Arc<dyn Node>,
+            )
+            .add_worker("writer", "Writes prose", writer.clone() as Arc<dyn Node>)
+            .with_synthesis_prompt("Combine the results into one answer.")
+            .build()
+            .await
+            .unwrap();
+
+        let results = run(&graph).await;
+
+        assert_eq!(
+            path(&results),
+            ["supervisor", "research", "supervisor", "synthesis"]
+        );
+        assert_eq!(*research.tasks.lock(), ["Find benchmarks"]);
+        assert!(writer.tasks.lock().is_empty());
+        let state = &results.final_state;
+        assert_eq!(
+            state.get("answer").unwrap().as_string(),
+            Some("Final answer")
+        );
+        assert_eq!(
+            state.get("workers.research").unwrap(),
+            StateValue::Array(vec![StateValue::Object(HashMap::from([
+                ("task".to_string(), StateValue::from("Find benchmarks")),
+                (
+                    "result".to_string(),
+                    StateValue::from("research did Find benchmarks")
+                ),
+            ]))])
+        );
+
+        // The second round saw the first round's result
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        let briefing = requests[1].last_message().unwrap().text().unwrap();
+        assert!(
+            briefing.contains("- research (task: Find benchmarks): research did Find benchmarks")
+        );
+        assert!(system_prompt(&requests[0]).contains("- writer: Writes prose"));
+    }
+
+    #[tokio::test]
+    async fn test_delegates_to_several_workers() {
+        let plan = r#"{"delegate": [
+            {"worker": "research", "task": "Find benchmarks"},
+            {"worker": "writer", "task": "Summarize the trade-offs"}
+        ], "parallel": PARALLEL}"#;
+        for parallel in [false, true] {
+            let mock = mock(plan.replace("PARALLEL", &parallel.to_string()));
+            let research = echo("research");
+            let agent =
+                Agent::new(mock.client(), ToolExecutor::empty(), AgentConfig::default()).unwrap();
+            let graph = SupervisorBuilder::new(mock.client())
+                .add_worker(
+                    "research",
+                    "Looks up facts",
+                    research.clone() as Arc<dyn Node>,
+                )
+                .add_worker("writer", "Writes prose", Arc::new(agent))
+                .with_synthesis_prompt("Combine the results into one answer.")
+                .build()
+                .await
+                .unwrap();
+
+            let results = run(&graph).await;
+
+            let expected: &[&str] = if parallel {
+                &["supervisor", "delegate", "supervisor", "synthesis"]
+            } else {
+                &[
+                    "supervisor",
+                    "research",
+                    "supervisor",
+                    "writer",
+                    "supervisor",
+                    "synthesis",
+                ]
+            };
+            assert_eq!(path(&results), expected);
+            let state = &results.final_state;
+            assert_eq!(
+                state.get("workers.writer").unwrap(),
+                StateValue::Array(vec![StateValue::Object(HashMap::from([
+                    (
+                        "task".to_string(),
+                        StateValue::from("Summarize the trade-offs")
+                    ),
+                    (
+                        "result".to_string(),
+                        StateValue::from("Drafted the summary")
+                    ),
+                ]))])
+            );
+            assert_eq!(*research.tasks.lock(), ["Find benchmarks"]);
+            assert_eq!(state.get(ROUND_KEY).unwrap(), StateValue::from(2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_rounds_end_delegation() {
+        let mock = MockClient::builder()
+            .on(
+                |request| system_prompt(request).starts_with("You are a supervisor"),
+                respond_text(r#"{"delegate": [{"worker": "research", "task": "Dig deeper"}]}"#),
+            )
+            .otherwise(respond_text("Final answer"))
+            .build();
+        let research = echo("research");
+        let graph = SupervisorBuilder::new(mock.client())
+            .add_worker(
+                "research",
+                "Looks up facts",
+                research.clone() as Arc<dyn Node>,
+            )
+            .with_max_rounds(2)
+            .build()
+            .await
+            .unwrap();
+
+        let results = run(&graph).await;
+
+        assert_eq!(research.tasks.lock().len(), 2);
+        assert_eq!(path(&results).last(), Some(&"synthesis"));
+        assert_eq!(
+            results.final_state.get("answer").unwrap().as_string(),
+            Some("Final answer")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_workers() {
+        let mock = mock("{}");
+        let build = |name: &str| {
+            SupervisorBuilder::new(mock.client())
+                .add_worker(name, "Looks up facts", echo("a") as Arc<dyn Node>)
+                .add_worker("writer", "Writes prose", echo("b") as Arc<dyn Node>)
+                .build()
+        };
+
+        assert!(build("synthesis").await.is_err());
+        assert!(build("writer").await.is_err());
+        assert!(SupervisorBuilder::new(mock.client()).build().await.is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! # Graph Validation
+//!
+//! Building a graph checks its structure and fails on the first
+//! [`Severity::Error`]; problems that may only show at runtime are reported
+//! as warnings and logged.
+//! [`WorkflowGraph::validate_only`](crate::WorkflowGraph::validate_only)
+//! returns every issue, for tooling that shows them all.
+
+use crate::core::NodeId;
+use std::fmt;
+
+/// How serious a validation issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The graph cannot be built
+    Error,
+    /// The graph builds, but may not run as intended
+    Warning,
+}
+
+/// What a validation issue is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueKind {
+    /// The graph has no nodes
+    NoNodes,
+    /// The graph has no entry points
+    NoEntryPoints,
+    /// An entry point, exit point or route names a node that does not exist
+    UnknownNode,
+    /// A node has a conditional edge or parallel branches next to other edges
+    ConflictingEdges,
+    /// A node reads or writes a key the state schema does not declare
+    UndeclaredStateKey,
+    /// A parallel branch has outgoing edges of its own
+    BranchWithEdges,
+    /// Parallel branches write the same key without a reducer
+    OverlappingBranchWrites,
+    /// A cycle has no loop limit
+    UnboundedCycle,
+    /// No path leads from the entry points to a node
+    Unreachable,
+    /// A node has no way out and is not an exit point
+    DeadEnd,
+    /// A node reads a key that nothing before it provides
+    MissingInput,
+}
+
+/// A problem found in a graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    /// The nodes involved, in the order the message names them
+    pub nodes: Vec<NodeId>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    /// Create an issue that fails the build
+    pub fn error(
+        kind: IssueKind,
+        nodes: impl IntoIterator<Item = NodeId>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Error, kind, nodes, message)
+    }
+
+    /// Create an issue that is logged when the graph is built
+    pub fn warning(
+        kind: IssueKind,
+        nodes: impl IntoIterator<Item = NodeId>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Warning, kind, nodes, message)
+    }
+
+    fn new(
+        severity: Severity,
+        kind: IssueKind,
+        nodes: impl IntoIterator<Item = NodeId>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            kind,
+            nodes: nodes.into_iter().collect(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ExecutionContext, ExecutionResult, GraphBuilder, Node};
+    use crate::routing::when;
+    use crate::schema::{StateSchema, StateType};
+    use crate::state::GraphState;
+    use crate::RGraphResult;
+    use async_trait::async_trait;
+    use std::sync::Arc;
+
+    // Node declaring the keys it reads and writes, without touching them
+    struct KeysNode {
+        id: NodeId,
+        reads: Vec<&'static str>,
+        writes: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl Node for KeysNode {
+        async fn execute(
+            &self,
+            _state: &mut GraphState,
+            _context: &ExecutionContext,
+        ) -> RGraphResult<ExecutionResult> {
+            Ok(ExecutionResult::Continue)
+        }
+
+        fn id(&self) -> &NodeId {
+            &self.id
+        }
+
+        fn name(&self) -> &str {
+            self.id.as_str()
+        }
+
+        fn input_keys(&self) -> Vec<&str> {
+            self.reads.clone()
+        }
+
+        fn output_keys(&self) -> Vec<&str> {
+            self.writes.clone()
+        }
+    }
+
+    fn node(id: &str, reads: &[&'static str], writes: &[&'static str]) -> Arc<KeysNode> {
+        Arc::new(KeysNode {
+            id: NodeId::new(id),
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+        })
+    }
+
+    async fn chain(ids: &[&str]) -> GraphBuilder {
+        let mut builder = GraphBuilder::new("validation");
+        for id in ids {
+            builder = builder.add_node(*id, node(id, &[], &[])).await.unwrap();
+        }
+        for pair in ids.windows(2) {
+            builder = builder.add_edge(pair[0], pair[1]).unwrap();
+        }
+        builder
+    }
+
+    fn ids(names: &[&str]) -> Vec<NodeId> {
+        names.iter().map(|name| NodeId::new(*name)).collect()
+    }
+
+    fn find(issues: &[ValidationIssue], kind: IssueKind) -> Vec<&ValidationIssue> {
+        issues.iter().filter(|issue| issue.kind == kind).collect()
+    }
+
+    #[tokio::test]
+    async fn test_valid_chain_has_no_issues() {
+        let builder = chain(&["a", "b", "c"]).await;
+        assert!(builder.validate_only().is_empty());
+        assert!(builder.build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_route_to_unknown_node_is_an_error() {
+        let builder = GraphBuilder::new("validation")
+            .add_node("review", node("review", &[], &[]))
+            .await
+            .unwrap()
+            .add_node("publish", node("publish", &[], &[]))
+            .await
+            .unwrap()
+            .add_conditional_edge(
+                "review",
+                when("approved")
+                    .equals(true)
+                    .goto("publish")
+                    .otherwise("revise"),
+            )
+            .unwrap()
+            .entry_points(ids(&["review", "draft"]));
+        let issues = builder.validate_only();
+
+        let unknown = find(&issues, IssueKind::UnknownNode);
+        assert_eq!(unknown.len(), 2);
+        assert!(unknown.iter().all(|issue| issue.is_error()));
+        assert_eq!(unknown[0].nodes, ids(&["draft"]));
+        assert_eq!(unknown[1].nodes, ids(&["review", "revise"]));
+        assert!(builder.build().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_node_is_a_warning() {
+        let builder = chain(&["a", "b"])
+            .await
+            .add_node("orphan", node("orphan", &[], &[]))
+            .await
+            .unwrap()
+            .add_node("handler", node("handler", &[], &[]))
+            .await
+            .unwrap()
+            .on_error("b", "handler")
+            .unwrap();
+        let issues = builder.validate_only();
+
+        let unreachable = find(&issues, IssueKind::Unreachable);
+        assert_eq!(unreachable.len(), 1);
+        assert_eq!(unreachable[0].severity, Severity::Warning);
+        assert_eq!(unreachable[0].nodes, ids(&["orphan"]));
+        assert!(builder.build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dead_end_outside_exit_points_is_a_warning() {
+        let builder = chain(&["a", "b"])
+            .await
+            .add_node("stuck", node("stuck", &[], &[]))
+            .await
+            .unwrap()
+            .add_edge("a", "stuck")
+            .unwrap();
+        // Without exit points, every node without a way out ends the run
+        assert!(find(&builder.validate_only(), IssueKind::DeadEnd).is_empty());
+
+        let builder = builder.exit_points(ids(&["b"]));
+        let issues = builder.validate_only();
+
+        let dead_ends = find(&issues, IssueKind::DeadEnd);
+        assert_eq!(dead_ends.len(), 1);
+        assert_eq!(dead_ends[0].nodes, ids(&["stuck"]));
+        assert!(builder.build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_input_written_only_downstream_is_a_warning() {
+        let builder = GraphBuilder::new("validation")
+            .add_node("draft", node("draft", &["question", "summary"], &["draft"]))
+            .await
+            .unwrap()
+            .add_node("summarize", node("summarize", &["draft"], &["summary"]))
+            .await
+            .unwrap()
+            .add_edge("draft", "summarize")
+            .unwrap();
+        let issues = builder.validate_only();
+
+        // `question` is written by no node, so it is taken to come from the
+        // initial state
+        let missing = find(&issues, IssueKind::MissingInput);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].nodes, ids(&["draft"]));
+        assert!(missing[0].message.contains("'summary'"));
+    }
+
+    #[tokio::test]
+    async fn test_schema_required_keys_are_provided() {
+        let schema = StateSchema::new()
+            .required("question", StateType::String)
+            .optional("notes", StateType::String)
+            .optional("answer", StateType::String);
+        let builder = GraphBuilder::new("validation")
+            .state_schema(schema)
+            .add_node(
+                "answer",
+                node("answer", &["question", "notes"], &["answer"]),
+            )
+            .await
+            .unwrap();
+        let issues = builder.validate_only();
+
+        let missing = find(&issues, IssueKind::MissingInput);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].nodes, ids(&["answer"]));
+        assert!(missing[0].message.contains("'notes'"));
+    }
+
+    #[tokio::test]
+    async fn test_cycle_without_limit_is_an_error() {
+        let builder = chain(&["plan", "act", "check"])
+            .await
+            .add_edge("check", "act")
+            .unwrap();
+        let issues = builder.validate_only();
+
+        let cycles = find(&issues, IssueKind::UnboundedCycle);
+        assert_eq!(cycles.len(), 1);
+        assert!(cycles[0].is_error());
+        assert_eq!(cycles[0].nodes, ids(&["act", "check"]));
+        assert!(builder.build().is_err());
+
+        let limited = chain(&["plan", "act", "check"])
+            .await
+            .add_edge_cyclic("check", "act", 3)
+            .unwrap();
+        assert!(find(&limited.validate_only(), IssueKind::UnboundedCycle).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_reported_before_warnings() {
+        let builder = chain(&["a", "b"])
+            .await
+            .add_node("orphan", node("orphan", &[], &[]))
+            .await
+            .unwrap()
+            .add_edge("b", "a")
+            .unwrap();
+        let issues = builder.validate_only();
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].kind, IssueKind::UnboundedCycle);
+        assert_eq!(issues[1].kind, IssueKind::Unreachable);
+    }
+}
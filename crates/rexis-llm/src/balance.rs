@@ -0,0 +1,707 @@
+//! # Load Balancing
+//!
+//! A [`LoadBalancedClient`] spreads requests across equally capable clients,
+//! typically several API keys or endpoints for the same provider. Keys that are
+//! rate limited are deprioritized for a cooldown that grows with repeated 429s,
+//! and the request moves on to the next key.
+//!
+//! Balance within a provider and fall back across providers by placing the
+//! balanced client in a [`FallbackClient`](crate::FallbackClient) chain via
+//! [`LoadBalancedClient::into_client`].
+
+use crate::client::Client;
+use crate::options::RequestOptions;
+use crate::params::GenerationParams;
+use crate::provider::{LLMProvider, Provider};
+use crate::streaming::{ChatStream, ToolAwareStream};
+use crate::tools::ToolDefinition;
+use crate::{ChatMessage, ChatResponse, RsllmError, RsllmResult, StreamChunk};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Cooldown applied after a key's first 429 by default
+const DEFAULT_THROTTLE_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Upper bound for the growing cooldown
+const MAX_THROTTLE_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// How the next client is chosen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BalanceStrategy {
+    /// Cycle through clients in order
+    #[default]
+    RoundRobin,
+    /// Pick the client that has gone unused the longest
+    LeastRecentlyUsed,
+    /// Smooth weighted round robin over the configured weights
+    Weighted,
+}
+
+/// Request counters for one client
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyStats {
+    /// Position of the client in the balancer
+    pub index: usize,
+
+    /// Requests sent through this client
+    pub requests: u64,
+
+    /// Requests that succeeded
+    pub successes: u64,
+
+    /// Requests rejected with a rate limit (429)
+    pub rate_limited: u64,
+
+    /// Requests that failed for any other reason
+    pub failures: u64,
+
+    /// Whether the client is currently deprioritized
+    pub throttled: bool,
+}
+
+impl KeyStats {
+    /// Fraction of requests that were rate limited
+    pub fn rate_limit_ratio(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.rate_limited as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Selection and throttle state for one client
+#[derive(Debug)]
+struct Slot {
+    weight: u32,
+    current_weight: i64,
+    last_used: Option<Instant>,
+    throttled_until: Option<Instant>,
+    consecutive_throttles: u32,
+    stats: KeyStats,
+}
+
+impl Slot {
+    fn throttled(&self, now: Instant) -> bool {
+        self.throttled_until.is_some_and(|until| now < until)
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    slots: Vec<Slot>,
+    cursor: usize,
+}
+
+/// Client that spreads requests across equally capable clients
+///
+/// Inner clients should not retry rate limits themselves (see
+/// [`RetryableClasses`](crate::RetryableClasses)) so a throttled key hands the
+/// request to the next one straight away. Responses record the client that
+/// served them in the `key_index` metadata entry.
+#[derive(Clone)]
+pub struct LoadBalancedClient {
+    clients: Arc<Vec<Client>>,
+    state: Arc<Mutex<State>>,
+    strategy: BalanceStrategy,
+    throttle_cooldown: Duration,
+}
+
+impl LoadBalancedClient {
+    /// Balance across `clients` with round robin and equal weights
+    pub fn new(clients: Vec<Client>) -> Self {
+        let slots = (0..clients.len())
+            .map(|index| Slot {
+                weight: 1,
+                current_weight: 0,
+                last_used: None,
+                throttled_until: None,
+                consecutive_throttles: 0,
+                stats: KeyStats {
+                    index,
+                    ..KeyStats::default()
+                },
+            })
+            .collect();
+
+        Self {
+            clients: Arc::new(clients),
+            state: Arc::new(Mutex::new(State { slots, cursor: 0 })),
+            strategy: BalanceStrategy::default(),
+            throttle_cooldown: DEFAULT_THROTTLE_COOLDOWN,
+        }
+    }
+
+    /// Set the selection strategy
+    pub fn strategy(mut self, strategy: BalanceStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set per-client weights for [`BalanceStrategy::Weighted`]
+    ///
+    /// Missing entries keep weight 1; a weight of 0 is treated as 1.
+    pub fn weights(self, weights: Vec<u32>) -> Self {
+        {
+            let mut state = self.state.lock().unwrap();
+            for (slot, weight) in state.slots.iter_mut().zip(weights) {
+                slot.weight = weight.max(1);
+            }
+        }
+        self
+    }
+
+    /// Cooldown after a key's first 429; it doubles with each consecutive 429
+    pub fn throttle_cooldown(mut self, cooldown: Duration) -> Self {
+        self.throttle_cooldown = cooldown;
+        self
+    }
+
+    /// Balanced clients in order
+    pub fn clients(&self) -> impl Iterator<Item = &Client> {
+        self.clients.iter()
+    }
+
+    /// Per-client request counters
+    pub fn stats(&self) -> Vec<KeyStats> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .slots
+            .iter()
+            .map(|slot| KeyStats {
+                throttled: slot.throttled(now),
+                ..slot.stats.clone()
+            })
+            .collect()
+    }
+
+    /// Wrap the balancer in a [`Client`] so it can be used wherever one is expected
+    ///
+    /// Each balanced client keeps its own model, retries, rate limits and usage
+    /// tracking; the wrapper adds none of its own.
+    pub fn into_client(self) -> Client {
+        let mut config = self
+            .clients
+            .first()
+            .map(|client| client.config().clone())
+            .unwrap_or_default();
+        config.retry.max_retries = 0;
+        config.rate_limit = None;
+
+        Client::with_provider(config, Arc::new(self))
+    }
+
+    /// Chat completion (non-streaming)
+    pub async fn chat_completion(&self, messages: Vec<ChatMessage>) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_options(messages, None, None)
+            .await
+    }
+
+    /// Chat completion with custom sampling options
+    ///
+    /// Every client uses its own configured model.
+    pub async fn chat_completion_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.dispatch("chat_completion", |client| {
+            client.chat_completion_with_options(messages.clone(), None, temperature, max_tokens)
+        })
+        .await
+        .map(|(response, index)| response.with_metadata("key_index", index.into()))
+    }
+
+    /// Chat completion with tool calling support
+    pub async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_tools_and_options(messages, tools, None, None)
+            .await
+    }
+
+    /// Chat completion with tools and custom sampling options
+    pub async fn chat_completion_with_tools_and_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.dispatch("chat_completion_with_tools", |client| {
+            client.chat_completion_with_tools_and_options(
+                messages.clone(),
+                tools.clone(),
+                None,
+                temperature,
+                max_tokens,
+            )
+        })
+        .await
+        .map(|(response, index)| response.with_metadata("key_index", index.into()))
+    }
+
+    /// Chat completion (streaming)
+    pub async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> RsllmResult<ChatStream> {
+        self.dispatch("chat_completion_stream", |client| {
+            client.chat_completion_stream(messages.clone())
+        })
+        .await
+        .map(|(stream, _)| stream)
+    }
+
+    /// Streaming chat completion with tool calling support
+    pub async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+    ) -> RsllmResult<ToolAwareStream> {
+        self.dispatch("chat_completion_with_tools_stream", |client| {
+            client.chat_completion_with_tools_stream(messages.clone(), tools.clone())
+        })
+        .await
+        .map(|(stream, _)| stream)
+    }
+
+    /// Send through selected clients, moving on when a key is rate limited
+    ///
+    /// Returns the result with the index of the client that produced it.
+    async fn dispatch<'a, T, F, Fut>(
+        &'a self,
+        operation: &str,
+        mut call: F,
+    ) -> RsllmResult<(T, usize)>
+    where
+        F: FnMut(&'a Client) -> Fut,
+        Fut: std::future::Future<Output = RsllmResult<T>>,
+    {
+        if self.clients.is_empty() {
+            return Err(RsllmError::configuration("Load balancer has no clients"));
+        }
+
+        let mut tried = vec![false; self.clients.len()];
+        let mut last_error = None;
+
+        while let Some(index) = self.select(&tried) {
+            tried[index] = true;
+            match call(&self.clients[index]).await {
+                Ok(value) => {
+                    self.record(index, Outcome::Success);
+                    return Ok((value, index));
+                }
+                Err(error) if Self::is_rate_limit(&error) => {
+                    self.record(index, Outcome::RateLimited(Self::retry_after(&error)));
+                    tracing::warn!(
+                        operation,
+                        index,
+                        error = %error,
+                        "LLM key rate limited, trying next key"
+                    );
+                    last_error = Some(error);
+                }
+                Err(error) => {
+                    self.record(index, Outcome::Failure);
+                    return Err(error);
+                }
+            }
+        }
+
+        Err(last_error.expect("at least one client was tried"))
+    }
+
+    /// Pick the next untried client, preferring those that are not throttled
+    fn select(&self, tried: &[bool]) -> Option<usize> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let candidates: Vec<usize> = (0..state.slots.len()).filter(|&i| !tried[i]).collect();
+        let available: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&i| !state.slots[i].throttled(now))
+            .collect();
+
+        let index = if available.is_empty() {
+            // Every remaining key is throttled: use the one that recovers first
+            candidates
+                .into_iter()
+                .min_by_key(|&i| state.slots[i].throttled_until)?
+        } else {
+            match self.strategy {
+                BalanceStrategy::RoundRobin => {
+                    let len = state.slots.len();
+                    let start = state.cursor;
+                    let index = (0..len)
+                        .map(|offset| (start + offset) % len)
+                        .find(|i| available.contains(i))?;
+                    state.cursor = (index + 1) % len;
+                    index
+                }
+                BalanceStrategy::LeastRecentlyUsed => available
+                    .into_iter()
+                    .min_by_key(|&i| state.slots[i].last_used)?,
+                BalanceStrategy::Weighted => {
+                    let total: i64 = available
+                        .iter()
+                        .map(|&i| i64::from(state.slots[i].weight))
+                        .sum();
+                    for &i in &available {
+                        let slot = &mut state.slots[i];
+                        slot.current_weight += i64::from(slot.weight);
+                    }
+                    let index = available
+                        .into_iter()
+                        .max_by_key(|&i| (state.slots[i].current_weight, std::cmp::Reverse(i)))?;
+                    state.slots[index].current_weight -= total;
+                    index
+                }
+            }
+        };
+
+        let slot = &mut state.slots[index];
+        slot.last_used = Some(now);
+        slot.stats.requests += 1;
+        Some(index)
+    }
+
+    fn record(&self, index: usize, outcome: Outcome) {
+        let mut state = self.state.lock().unwrap();
+        let slot = &mut state.slots[index];
+        match outcome {
+            Outcome::Success => {
+                slot.stats.successes += 1;
+                slot.consecutive_throttles = 0;
+            }
+            Outcome::RateLimited(retry_after) => {
+                slot.stats.rate_limited += 1;
+                slot.consecutive_throttles += 1;
+                let backoff = self
+                    .throttle_cooldown
+                    .saturating_mul(1 << (slot.consecutive_throttles - 1).min(16))
+                    .min(MAX_THROTTLE_COOLDOWN);
+                let cooldown = retry_after.map_or(backoff, |hint| hint.max(backoff));
+                slot.throttled_until = Some(Instant::now() + cooldown);
+            }
+            Outcome::Failure => slot.stats.failures += 1,
+        }
+    }
+
+    fn is_rate_limit(error: &RsllmError) -> bool {
+        error.status_code() == Some(429)
+    }
+
+    /// `Retry-After` hint, looking through exhausted retries
+    fn retry_after(error: &RsllmError) -> Option<Duration> {
+        match error {
+            RsllmError::RetriesExhausted { source, .. } => Self::retry_after(source),
+            RsllmError::RateLimit { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Result of one request for bookkeeping
+enum Outcome {
+    Success,
+    RateLimited(Option<Duration>),
+    Failure,
+}
+
+/// Lets a [`Client`] be built around the balancer (see [`LoadBalancedClient::into_client`])
+///
+/// The `model` argument is ignored: every client uses its own configured model.
+#[async_trait]
+impl LLMProvider for LoadBalancedClient {
+    fn name(&self) -> &str {
+        self.clients
+            .first()
+            .map(|client| client.provider().name())
+            .unwrap_or("LoadBalanced")
+    }
+
+    fn provider_type(&self) -> Provider {
+        self.clients
+            .first()
+            .map(|client| client.provider().provider_type())
+            .unwrap_or(Provider::OpenAI)
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.clients
+            .first()
+            .map(|client| client.supported_models())
+            .unwrap_or_default()
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        for client in self.clients() {
+            if client.health_check().await.unwrap_or(false) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        _model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_options(messages, temperature, max_tokens)
+            .await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        _model: Option<String>,
+        _temperature: Option<f32>,
+        _max_tokens: Option<u32>,
+    ) -> RsllmResult<Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>>
+    {
+        let stream = LoadBalancedClient::chat_completion_stream(self, messages).await?;
+        Ok(Box::new(stream))
+    }
+
+    async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        _model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_tools_and_options(messages, tools, temperature, max_tokens)
+            .await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        _model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let operation = if tools.is_empty() {
+            "chat_completion"
+        } else {
+            "chat_completion_with_tools"
+        };
+        self.dispatch(operation, |client| {
+            client.chat_completion_with_tools_with(
+                messages.clone(),
+                tools.clone(),
+                RequestOptions::new().with_params(params.clone()),
+            )
+        })
+        .await
+        .map(|(response, index)| response.with_metadata("key_index", index.into()))
+    }
+
+    async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        _model: Option<String>,
+        _temperature: Option<f32>,
+        _max_tokens: Option<u32>,
+    ) -> RsllmResult<ToolAwareStream> {
+        LoadBalancedClient::chat_completion_with_tools_stream(self, messages, tools).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientConfig;
+    use crate::FallbackClient;
+    use std::collections::VecDeque;
+
+    /// Provider that replays scripted outcomes, then succeeds
+    struct KeyProvider {
+        name: &'static str,
+        script: Mutex<VecDeque<Option<u16>>>,
+    }
+
+    impl KeyProvider {
+        fn new(name: &'static str, script: &[Option<u16>]) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                script: Mutex::new(script.iter().copied().collect()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for KeyProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn provider_type(&self) -> Provider {
+            Provider::OpenAI
+        }
+
+        fn supported_models(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        async fn health_check(&self) -> RsllmResult<bool> {
+            Ok(true)
+        }
+
+        async fn chat_completion(
+            &self,
+            _messages: Vec<ChatMessage>,
+            model: Option<&str>,
+            _temperature: Option<f32>,
+            _max_tokens: Option<u32>,
+        ) -> RsllmResult<ChatResponse> {
+            match self.script.lock().unwrap().pop_front().flatten() {
+                Some(429) => Err(RsllmError::rate_limit("Too Many Requests", None)),
+                Some(status) => Err(RsllmError::api(
+                    self.name,
+                    "Service Unavailable",
+                    status.to_string(),
+                )),
+                None => Ok(ChatResponse::new(self.name, model.unwrap_or("stub"))),
+            }
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _model: Option<String>,
+            _temperature: Option<f32>,
+            _max_tokens: Option<u32>,
+        ) -> RsllmResult<
+            Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>,
+        > {
+            Ok(Box::new(futures_util::stream::empty()))
+        }
+    }
+
+    fn client(provider: Arc<KeyProvider>) -> Client {
+        let mut config = ClientConfig::default();
+        config.retry.max_retries = 0;
+        Client::with_provider(config, provider)
+    }
+
+    fn keys(scripts: &[&[Option<u16>]]) -> Vec<Client> {
+        const NAMES: [&str; 3] = ["key-a", "key-b", "key-c"];
+        scripts
+            .iter()
+            .zip(NAMES)
+            .map(|(script, name)| client(KeyProvider::new(name, script)))
+            .collect()
+    }
+
+    async fn serve(balancer: &LoadBalancedClient, requests: usize) -> Vec<String> {
+        let mut served = Vec::new();
+        for _ in 0..requests {
+            let response = balancer
+                .chat_completion(vec![ChatMessage::user("hi")])
+                .await
+                .unwrap();
+            served.push(response.content);
+        }
+        served
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_round_robin_distribution() {
+        let balancer = LoadBalancedClient::new(keys(&[&[], &[], &[]]));
+
+        let served = serve(&balancer, 6).await;
+        assert_eq!(
+            served,
+            ["key-a", "key-b", "key-c", "key-a", "key-b", "key-c"]
+        );
+        assert!(balancer.stats().iter().all(|stats| stats.requests == 2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_weighted_distribution() {
+        let balancer = LoadBalancedClient::new(keys(&[&[], &[]]))
+            .strategy(BalanceStrategy::Weighted)
+            .weights(vec![3, 1]);
+
+        let served = serve(&balancer, 8).await;
+        assert_eq!(served.iter().filter(|key| *key == "key-a").count(), 6);
+        assert_eq!(served.iter().filter(|key| *key == "key-b").count(), 2);
+        // Smooth weighting interleaves rather than bursting
+        assert_eq!(&served[..4], ["key-a", "key-a", "key-b", "key-a"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_least_recently_used() {
+        let balancer = LoadBalancedClient::new(keys(&[&[], &[], &[]]))
+            .strategy(BalanceStrategy::LeastRecentlyUsed);
+
+        let mut served = Vec::new();
+        for _ in 0..4 {
+            served.extend(serve(&balancer, 1).await);
+            tokio::time::advance(Duration::from_millis(10)).await;
+        }
+        assert_eq!(served, ["key-a", "key-b", "key-c", "key-a"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttled_key_is_avoided() {
+        let balancer = LoadBalancedClient::new(keys(&[&[Some(429)], &[], &[]]))
+            .throttle_cooldown(Duration::from_secs(10));
+
+        // key-a is rate limited and the request moves on to key-b
+        let served = serve(&balancer, 4).await;
+        assert_eq!(served, ["key-b", "key-c", "key-b", "key-c"]);
+
+        let stats = balancer.stats();
+        assert_eq!(stats[0].rate_limited, 1);
+        assert!(stats[0].throttled);
+        assert_eq!(stats[0].rate_limit_ratio(), 1.0);
+
+        // After the cooldown key-a rejoins the rotation
+        tokio::time::advance(Duration::from_secs(11)).await;
+        let served = serve(&balancer, 3).await;
+        assert!(served.contains(&"key-a".to_string()));
+        assert!(!balancer.stats()[0].throttled);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_all_keys_throttled_returns_rate_limit() {
+        let balancer = LoadBalancedClient::new(keys(&[&[Some(429)], &[Some(429)]]));
+
+        let err = balancer
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), Some(429));
+        assert!(balancer.stats().iter().all(|stats| stats.throttled));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_composes_with_fallback_chain() {
+        let primary = LoadBalancedClient::new(keys(&[&[Some(429)], &[Some(503)]])).into_client();
+        let backup = client(KeyProvider::new("backup", &[]));
+        let chain = FallbackClient::new(vec![primary, backup]);
+
+        let response = chain
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.content, "backup");
+        assert_eq!(response.metadata["fallback_index"], 1);
+    }
+}
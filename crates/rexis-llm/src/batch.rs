@@ -0,0 +1,211 @@
+//! # Batch Requests
+//!
+//! Offline chat completions through a provider's batch API. Requests are
+//! submitted together, processed asynchronously at a discount and outside the
+//! realtime rate limits, and their results are matched back by the
+//! caller-supplied `custom_id`.
+
+use crate::params::GenerationParams;
+use crate::provider::LLMProvider;
+use crate::{ChatMessage, ChatResponse, RsllmError, RsllmResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A chat completion request submitted as part of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchChatRequest {
+    /// Caller-supplied identifier used to match the result
+    pub custom_id: String,
+
+    /// Conversation to complete
+    pub messages: Vec<ChatMessage>,
+
+    /// Model override (defaults to the client's model)
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Generation parameters; unset fields fall back to the client's model configuration
+    #[serde(default)]
+    pub params: GenerationParams,
+}
+
+impl BatchChatRequest {
+    /// Create a batch request
+    pub fn new(custom_id: impl Into<String>, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            messages,
+            model: None,
+            params: GenerationParams::default(),
+        }
+    }
+
+    /// Set the model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set the generation parameters
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+/// Lifecycle state of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchState {
+    /// Input is being validated
+    Validating,
+    /// Input failed validation
+    Failed,
+    /// Requests are being processed
+    InProgress,
+    /// Results are being prepared
+    Finalizing,
+    /// Results are available
+    Completed,
+    /// The completion window passed before all requests finished
+    Expired,
+    /// Cancellation was requested
+    Cancelling,
+    /// The batch was cancelled
+    Cancelled,
+}
+
+impl BatchState {
+    /// Whether the batch will not change state anymore
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Failed | Self::Completed | Self::Expired | Self::Cancelled
+        )
+    }
+}
+
+impl fmt::Display for BatchState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Validating => "validating",
+            Self::Failed => "failed",
+            Self::InProgress => "in_progress",
+            Self::Finalizing => "finalizing",
+            Self::Completed => "completed",
+            Self::Expired => "expired",
+            Self::Cancelling => "cancelling",
+            Self::Cancelled => "cancelled",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Progress of a batch as reported by the provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchStatus {
+    /// Provider batch ID
+    pub id: String,
+
+    /// Lifecycle state
+    pub state: BatchState,
+
+    /// Number of requests in the batch
+    pub total: u32,
+
+    /// Number of requests that succeeded
+    pub completed: u32,
+
+    /// Number of requests that failed
+    pub failed: u32,
+
+    /// File holding the successful results
+    pub output_file_id: Option<String>,
+
+    /// File holding the failed results
+    pub error_file_id: Option<String>,
+}
+
+/// Outcome of a single request in a batch
+#[derive(Debug)]
+pub struct BatchResult {
+    /// Identifier supplied with the request
+    pub custom_id: String,
+
+    /// Response, or the error the provider reported for this request
+    pub response: RsllmResult<ChatResponse>,
+}
+
+/// Handle to a submitted batch
+///
+/// Handles only carry the batch ID, so a job can be resumed after a restart
+/// with [`Client::batch`](crate::Client::batch).
+#[derive(Clone)]
+pub struct BatchHandle {
+    provider: Arc<dyn LLMProvider>,
+    id: String,
+}
+
+impl BatchHandle {
+    /// Create a handle for an existing batch
+    pub(crate) fn new(provider: Arc<dyn LLMProvider>, id: impl Into<String>) -> Self {
+        Self {
+            provider,
+            id: id.into(),
+        }
+    }
+
+    /// Provider batch ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Fetch the current progress
+    pub async fn status(&self) -> RsllmResult<BatchStatus> {
+        self.provider.batch_status(&self.id).await
+    }
+
+    /// Poll until the batch reaches a terminal state
+    pub async fn wait(&self, poll_interval: Duration) -> RsllmResult<BatchStatus> {
+        loop {
+            let status = self.status().await?;
+            if status.state.is_terminal() {
+                return Ok(status);
+            }
+            tracing::debug!(
+                batch = %self.id,
+                state = %status.state,
+                completed = status.completed,
+                total = status.total,
+                "Batch not finished yet"
+            );
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Download the results, one per request that finished
+    ///
+    /// Fails with [`RsllmError::InvalidState`] while the batch is still running.
+    /// Requests that never ran (e.g. in an expired batch) have no result.
+    pub async fn results(&self) -> RsllmResult<Vec<BatchResult>> {
+        let status = self.status().await?;
+        if !status.state.is_terminal() {
+            return Err(RsllmError::invalid_state(format!(
+                "batch {} is {}",
+                self.id, status.state
+            )));
+        }
+        self.provider.batch_results(&status).await
+    }
+}
+
+impl fmt::Debug for BatchHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchHandle")
+            .field("provider", &self.provider.name())
+            .field("id", &self.id)
+            .finish()
+    }
+}
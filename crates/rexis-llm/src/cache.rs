@@ -0,0 +1,358 @@
+//! # Response Caching
+//!
+//! Caches chat completions for deterministic requests. Entries are keyed by a
+//! stable hash of provider, model, messages, tools and sampling parameters, and
+//! stored through a pluggable [`CacheStore`] so they can live in any backend.
+
+use crate::params::GenerationParams;
+use crate::tools::ToolDefinition;
+use crate::{ChatMessage, ChatResponse, RsllmResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Key-value storage backing a response cache
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Get a serialized entry
+    async fn get(&self, key: &str) -> RsllmResult<Option<String>>;
+
+    /// Store a serialized entry
+    async fn set(&self, key: &str, value: String) -> RsllmResult<()>;
+
+    /// Remove a single entry
+    async fn delete(&self, key: &str) -> RsllmResult<()>;
+
+    /// Remove all entries
+    async fn clear(&self) -> RsllmResult<()>;
+}
+
+/// Process-local cache store
+#[derive(Debug, Default)]
+pub struct InMemoryCacheStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl InMemoryCacheStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CacheStore for InMemoryCacheStore {
+    async fn get(&self, key: &str) -> RsllmResult<Option<String>> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned())
+    }
+
+    async fn set(&self, key: &str, value: String) -> RsllmResult<()> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> RsllmResult<()> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> RsllmResult<()> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        Ok(())
+    }
+}
+
+/// A cached response with its expiry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    response: ChatResponse,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Cache hit/miss counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+
+    /// Lookups that went to the provider
+    pub misses: u64,
+
+    /// Responses written to the cache
+    pub writes: u64,
+}
+
+impl CacheStats {
+    /// Fraction of lookups served from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// The parts of a request that determine its response
+#[derive(Debug, Clone, Copy)]
+pub struct CacheKeyParts<'a> {
+    /// Provider name
+    pub provider: &'a str,
+
+    /// Model name
+    pub model: &'a str,
+
+    /// Conversation messages
+    pub messages: &'a [ChatMessage],
+
+    /// Tool definitions offered to the model
+    pub tools: &'a [ToolDefinition],
+
+    /// Generation parameters
+    pub params: &'a GenerationParams,
+}
+
+impl CacheKeyParts<'_> {
+    /// Stable cache key for these request parts
+    ///
+    /// Message timestamps and metadata are excluded so identical conversations
+    /// built at different times share a key.
+    pub fn key(&self) -> String {
+        let messages: Vec<serde_json::Value> = self
+            .messages
+            .iter()
+            .map(|message| {
+                serde_json::json!({
+                    "role": message.role,
+                    "content": message.content,
+                    "name": message.name,
+                    "tool_calls": message.tool_calls,
+                    "tool_call_id": message.tool_call_id,
+                })
+            })
+            .collect();
+
+        let mut canonical = serde_json::json!({
+            "provider": self.provider,
+            "model": self.model,
+            "messages": messages,
+            "tools": self.tools,
+            "temperature": self.params.temperature,
+            "max_tokens": self.params.max_tokens,
+        });
+
+        // Only set parameters join the key, so existing keys stay stable
+        let extra = [
+            ("top_p", serde_json::json!(self.params.top_p)),
+            ("stop", serde_json::json!(self.params.stop)),
+            (
+                "presence_penalty",
+                serde_json::json!(self.params.presence_penalty),
+            ),
+            (
+                "frequency_penalty",
+                serde_json::json!(self.params.frequency_penalty),
+            ),
+            ("seed", serde_json::json!(self.params.seed)),
+            ("logprobs", serde_json::json!(self.params.logprobs)),
+        ];
+        for (name, value) in extra {
+            if !value.is_null() {
+                canonical[name] = value;
+            }
+        }
+
+        format!("{:016x}", fnv1a_64(canonical.to_string().as_bytes()))
+    }
+}
+
+/// FNV-1a, chosen for stability across processes and Rust versions
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Cache of chat completion responses with a fixed time-to-live
+pub struct ResponseCache {
+    store: Arc<dyn CacheStore>,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    writes: AtomicU64,
+}
+
+impl ResponseCache {
+    /// Create a cache over a store
+    pub fn new(store: Arc<dyn CacheStore>, ttl: Duration) -> Self {
+        Self {
+            store,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a process-local cache
+    pub fn in_memory(ttl: Duration) -> Self {
+        Self::new(Arc::new(InMemoryCacheStore::new()), ttl)
+    }
+
+    /// Entry time-to-live
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Look up a cached response
+    ///
+    /// Expired or unreadable entries count as misses.
+    pub async fn get(&self, key: &str) -> RsllmResult<Option<ChatResponse>> {
+        let entry = self
+            .store
+            .get(key)
+            .await?
+            .and_then(|raw| serde_json::from_str::<CacheEntry>(&raw).ok());
+
+        match entry {
+            Some(entry) if entry.expires_at > chrono::Utc::now() => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(Some(entry.response))
+            }
+            Some(_) => {
+                self.store.delete(key).await?;
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Store a response
+    pub async fn put(&self, key: &str, response: &ChatResponse) -> RsllmResult<()> {
+        let ttl = chrono::Duration::from_std(self.ttl).unwrap_or(chrono::Duration::MAX);
+        let entry = CacheEntry {
+            response: response.clone(),
+            expires_at: chrono::Utc::now()
+                .checked_add_signed(ttl)
+                .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC),
+        };
+
+        self.store.set(key, serde_json::to_string(&entry)?).await?;
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Hit/miss counters since creation or the last clear
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Remove all entries and reset counters
+    pub async fn clear(&self) -> RsllmResult<()> {
+        self.store.clear().await?;
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.writes.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("ttl", &self.ttl)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DETERMINISTIC: GenerationParams = GenerationParams {
+        temperature: Some(0.0),
+        top_p: None,
+        max_tokens: None,
+        stop: None,
+        presence_penalty: None,
+        frequency_penalty: None,
+        seed: None,
+        logprobs: None,
+        headers: std::collections::BTreeMap::new(),
+    };
+
+    fn parts(messages: &[ChatMessage]) -> CacheKeyParts<'_> {
+        CacheKeyParts {
+            provider: "OpenAI",
+            model: "gpt-4",
+            messages,
+            tools: &[],
+            params: &DETERMINISTIC,
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_timestamps_and_tracks_content() {
+        let first = vec![ChatMessage::user("hello")];
+        let mut second = vec![ChatMessage::user("hello")];
+        second[0].timestamp = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+
+        assert_eq!(parts(&first).key(), parts(&second).key());
+
+        let changed = vec![ChatMessage::user("hello!")];
+        assert_ne!(parts(&first).key(), parts(&changed).key());
+
+        let mut other_model = parts(&first);
+        other_model.model = "gpt-4o";
+        assert_ne!(parts(&first).key(), other_model.key());
+
+        let seeded = DETERMINISTIC.clone().with_seed(7);
+        let mut other_seed = parts(&first);
+        other_seed.params = &seeded;
+        assert_ne!(parts(&first).key(), other_seed.key());
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_miss() {
+        let cache = ResponseCache::in_memory(Duration::ZERO);
+        let response = ChatResponse::new("cached", "gpt-4");
+
+        cache.put("key", &response).await.unwrap();
+        assert!(cache.get("key").await.unwrap().is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.writes, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 0);
+    }
+}
@@ -3,23 +3,51 @@
 //! High-level client interface for RSLLM with multi-provider support.
 //! Provides unified API for chat completions, embeddings, and streaming.
 
+use crate::streaming::ToolAwareStream;
 use crate::{
-    ChatMessage, ChatResponse, ChatStream, ClientConfig, EmbeddingResponse, Provider, RsllmError,
-    RsllmResult,
+    ChatMessage, ChatResponse, ChatStream, ClientConfig, EmbeddingResponse, Provider, RetryPolicy,
+    RsllmError, RsllmResult,
 };
 
 #[cfg(feature = "openai")]
 use crate::provider::OpenAIProvider;
 
+#[cfg(feature = "claude")]
+use crate::provider::ClaudeProvider;
+
 #[cfg(feature = "ollama")]
 use crate::provider::OllamaProvider;
 
+#[cfg(feature = "gemini")]
+use crate::provider::GeminiProvider;
+
+#[cfg(feature = "bedrock")]
+use crate::provider::BedrockProvider;
+
+use crate::batch::{BatchChatRequest, BatchHandle};
+use crate::cache::{CacheKeyParts, CacheStats, ResponseCache};
+use crate::config::{AzureOpenAIConfig, BedrockConfig, ProxyConfig};
+use crate::middleware::{ClientMiddleware, RequestContext, ResponseContext};
+use crate::models::ModelInfo;
+use crate::options::RequestOptions;
+use crate::params::GenerationParams;
+use crate::pricing::PricingTable;
 use crate::provider::LLMProvider;
+use crate::rate_limit::{RateLimitConfig, RateLimiter};
+use crate::structured::{
+    parse_structured, repair_message, OutputSchema, StructuredOptions, StructuredResponse,
+};
+use crate::tokens::{HeuristicTokenCounter, TokenCounter, DEFAULT_CONTEXT_WINDOW};
+use crate::usage::UsageTracker;
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// High-level RSLLM client
+///
+/// Clones share the provider and rate limiter.
+#[derive(Clone)]
 pub struct Client {
     /// Client configuration
     config: ClientConfig,
@@ -29,8 +57,43 @@ pub struct Client {
 
     /// Client metadata
     metadata: HashMap<String, serde_json::Value>,
+
+    /// Shared client-side rate limiter
+    rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// Token counter used for budgeting and context fitting
+    token_counter: Arc<dyn TokenCounter>,
+
+    /// Shared response cache
+    response_cache: Option<Arc<ResponseCache>>,
+
+    /// Shared usage tracker
+    usage_tracker: Option<Arc<UsageTracker>>,
+
+    /// Label recorded with this client's usage
+    usage_label: Option<String>,
+
+    /// Prices used to estimate the cost of tracked usage
+    pricing: Arc<PricingTable>,
+
+    /// Deadline applied to requests that do not set their own
+    request_timeout: Option<Duration>,
+
+    /// Hooks run around every chat completion, in order
+    middleware: Vec<Arc<dyn ClientMiddleware>>,
 }
 
+/// Generation parameters streaming requests cannot carry yet
+const STREAM_DROPPED_PARAMS: &[&str] = &[
+    "top_p",
+    "stop",
+    "presence_penalty",
+    "frequency_penalty",
+    "seed",
+    "logprobs",
+    "headers",
+];
+
 impl Client {
     /// Create a new client with configuration
     pub fn new(config: ClientConfig) -> RsllmResult<Self> {
@@ -38,11 +101,29 @@ impl Client {
 
         let provider = Self::create_provider(&config)?;
 
-        Ok(Self {
+        Ok(Self::with_provider(config, provider))
+    }
+
+    /// Create a client around an existing provider instance
+    pub fn with_provider(config: ClientConfig, provider: Arc<dyn LLMProvider>) -> Self {
+        let rate_limiter = config
+            .rate_limit
+            .clone()
+            .map(|limits| Arc::new(RateLimiter::new(limits)));
+
+        Self {
             config,
             provider,
             metadata: HashMap::new(),
-        })
+            rate_limiter,
+            token_counter: Arc::new(HeuristicTokenCounter),
+            response_cache: None,
+            usage_tracker: None,
+            usage_label: None,
+            pricing: Arc::new(PricingTable::default()),
+            request_timeout: None,
+            middleware: Vec::new(),
+        }
     }
 
     /// Create a client builder
@@ -50,6 +131,26 @@ impl Client {
         ClientBuilder::new()
     }
 
+    /// Create a client for an OpenAI-compatible server (vLLM, LM Studio, LiteLLM, ...)
+    ///
+    /// `base_url` is the API root that `chat/completions` is resolved against, e.g.
+    /// `http://localhost:8000/v1`. Use [`ClientBuilder`] for extra headers, TLS
+    /// options or to disable the tools field.
+    pub fn openai_compatible(
+        base_url: impl AsRef<str>,
+        api_key: Option<String>,
+        model: impl Into<String>,
+    ) -> RsllmResult<Self> {
+        let mut builder = Self::builder()
+            .provider(Provider::OpenAICompatible)
+            .base_url(base_url)?
+            .model(model);
+        if let Some(api_key) = api_key {
+            builder = builder.api_key(api_key);
+        }
+        builder.build()
+    }
+
     /// Create a client from environment variables
     pub fn from_env() -> RsllmResult<Self> {
         let config = ClientConfig::from_env()?;
@@ -71,23 +172,115 @@ impl Client {
                     api_key.clone(),
                     config.provider.base_url.clone(),
                     config.provider.organization_id.clone(),
-                )?;
+                )?
+                .with_http_config(&config.http)?;
+
+                Ok(Arc::new(provider))
+            }
+
+            #[cfg(feature = "openai")]
+            Provider::Groq | Provider::Mistral => {
+                let provider_type = config.provider.provider;
+                let api_key = config.provider.api_key.as_ref().ok_or_else(|| {
+                    RsllmError::configuration(format!("{:?} API key required", provider_type))
+                })?;
+
+                let provider = OpenAIProvider::compatible(
+                    provider_type,
+                    api_key.clone(),
+                    config.provider.base_url.clone(),
+                )?
+                .with_http_config(&config.http)?;
+
+                Ok(Arc::new(provider))
+            }
+
+            #[cfg(feature = "openai")]
+            Provider::OpenAICompatible => {
+                let base_url = config.provider.base_url.clone().ok_or_else(|| {
+                    RsllmError::configuration("OpenAI-compatible provider requires a base URL")
+                })?;
+
+                let provider = OpenAIProvider::compatible(
+                    Provider::OpenAICompatible,
+                    config.provider.api_key.clone().unwrap_or_default(),
+                    Some(base_url),
+                )?
+                .with_http_config(&config.http)?
+                .with_headers(&config.headers)?
+                .with_tools_enabled(config.provider.tools_enabled);
+
+                Ok(Arc::new(provider))
+            }
+
+            #[cfg(feature = "openai")]
+            Provider::AzureOpenAI => {
+                let api_key =
+                    config.provider.api_key.as_ref().ok_or_else(|| {
+                        RsllmError::configuration("Azure OpenAI API key required")
+                    })?;
+                let azure = config.provider.azure.as_ref().ok_or_else(|| {
+                    RsllmError::configuration("Azure OpenAI requires a resource and deployment")
+                })?;
+
+                let provider = OpenAIProvider::azure(
+                    api_key.clone(),
+                    azure.endpoint(config.provider.base_url.as_ref())?,
+                    azure.deployment.clone(),
+                    azure.api_version.clone(),
+                )?
+                .with_http_config(&config.http)?;
 
                 Ok(Arc::new(provider))
             }
 
             #[cfg(feature = "ollama")]
             Provider::Ollama => {
-                let provider = OllamaProvider::new(config.provider.base_url.clone())?;
+                let provider = OllamaProvider::new(config.provider.base_url.clone())?
+                    .with_http_config(&config.http)?;
+                Ok(Arc::new(provider))
+            }
+
+            #[cfg(feature = "gemini")]
+            Provider::Gemini => {
+                let api_key = config
+                    .provider
+                    .api_key
+                    .as_ref()
+                    .ok_or_else(|| RsllmError::configuration("Gemini API key required"))?;
+
+                let provider =
+                    GeminiProvider::new(api_key.clone(), config.provider.base_url.clone())?
+                        .with_http_config(&config.http)?;
+
+                Ok(Arc::new(provider))
+            }
+
+            #[cfg(feature = "bedrock")]
+            Provider::Bedrock => {
+                let bedrock = config.provider.bedrock.as_ref().ok_or_else(|| {
+                    RsllmError::configuration("Bedrock requires a region and AWS credentials")
+                })?;
+
+                let provider = BedrockProvider::new(bedrock, config.provider.base_url.clone())?
+                    .with_http_config(&config.http)?;
+
                 Ok(Arc::new(provider))
             }
 
             #[cfg(feature = "claude")]
             Provider::Claude => {
-                // Claude provider implementation would go here
-                Err(RsllmError::configuration(
-                    "Claude provider not yet implemented",
-                ))
+                let api_key = config
+                    .provider
+                    .api_key
+                    .as_ref()
+                    .ok_or_else(|| RsllmError::configuration("Claude API key required"))?;
+
+                let provider =
+                    ClaudeProvider::new(api_key.clone(), config.provider.base_url.clone())?
+                        .with_http_config(&config.http)?;
+
+                Ok(Arc::new(provider))
             }
 
             #[allow(unreachable_patterns)]
@@ -113,6 +306,11 @@ impl Client {
         self.metadata.insert(key.into(), value);
     }
 
+    /// Get the shared rate limiter, if one is configured
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
     /// Get client metadata
     pub fn metadata(&self) -> &HashMap<String, serde_json::Value> {
         &self.metadata
@@ -128,12 +326,99 @@ impl Client {
         self.provider.supported_models()
     }
 
+    /// List the models available from the provider with best-effort capabilities
+    pub async fn list_models(&self) -> RsllmResult<Vec<ModelInfo>> {
+        self.provider.list_models().await
+    }
+
+    /// Fail fast on bad credentials or URLs
+    ///
+    /// Makes a cheap authenticated call (a model listing or a one-token completion)
+    /// and returns its error, so misconfiguration surfaces at startup rather than
+    /// on the first real request.
+    pub async fn validate(&self) -> RsllmResult<()> {
+        self.provider.validate().await
+    }
+
+    /// Submit chat completions to the provider's batch API
+    ///
+    /// Batches run asynchronously at a discount and outside the realtime rate
+    /// limits, which suits offline workloads. Requests without a model or
+    /// generation parameters use the client's model configuration. Results are
+    /// matched back by `custom_id`, which must be unique within the batch.
+    pub async fn create_batch(&self, requests: Vec<BatchChatRequest>) -> RsllmResult<BatchHandle> {
+        if requests.is_empty() {
+            return Err(RsllmError::validation(
+                "requests",
+                "Batch must contain at least one request",
+            ));
+        }
+
+        let defaults = self.config.model.generation_params();
+        let mut custom_ids = std::collections::HashSet::new();
+        let mut prepared = Vec::with_capacity(requests.len());
+        for mut request in requests {
+            if !custom_ids.insert(request.custom_id.clone()) {
+                return Err(RsllmError::validation(
+                    "custom_id",
+                    format!("Duplicate custom_id '{}' in batch", request.custom_id),
+                ));
+            }
+            if request.messages.is_empty() {
+                return Err(RsllmError::validation(
+                    "messages",
+                    format!("Request '{}' has no messages", request.custom_id),
+                ));
+            }
+
+            request
+                .model
+                .get_or_insert_with(|| self.config.model.model.clone());
+            request.params = request.params.or(&defaults);
+            request.params.validate()?;
+            prepared.push(request);
+        }
+
+        let status = self.provider.create_batch(prepared).await?;
+        tracing::info!(
+            provider = self.provider.name(),
+            batch = %status.id,
+            requests = status.total,
+            "Batch submitted"
+        );
+
+        Ok(BatchHandle::new(self.provider.clone(), status.id))
+    }
+
+    /// Handle for a previously submitted batch
+    pub fn batch(&self, id: impl Into<String>) -> BatchHandle {
+        BatchHandle::new(self.provider.clone(), id)
+    }
+
     /// Chat completion (non-streaming)
     pub async fn chat_completion(&self, messages: Vec<ChatMessage>) -> RsllmResult<ChatResponse> {
         self.chat_completion_with_options(messages, None, None, None)
             .await
     }
 
+    /// Chat completion served from the response cache when possible
+    ///
+    /// Marks the request cacheable regardless of temperature.
+    pub async fn chat_completion_cached(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> RsllmResult<ChatResponse> {
+        self.send_completion(
+            messages,
+            Vec::new(),
+            None,
+            GenerationParams::default(),
+            true,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
     /// Chat completion with custom options
     pub async fn chat_completion_with_options(
         &self,
@@ -142,6 +427,149 @@ impl Client {
         temperature: Option<f32>,
         max_tokens: Option<u32>,
     ) -> RsllmResult<ChatResponse> {
+        self.send_completion(
+            messages,
+            Vec::new(),
+            model,
+            GenerationParams::sampling(temperature, max_tokens),
+            false,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
+    /// Chat completion with per-request generation parameters, a deadline or
+    /// cancellation token
+    ///
+    /// Unset generation parameters fall back to the client's model configuration.
+    /// Returns [`RsllmError::Timeout`] when the deadline passes and
+    /// [`RsllmError::Cancelled`] when the token is cancelled.
+    pub async fn chat_completion_with(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: RequestOptions,
+    ) -> RsllmResult<ChatResponse> {
+        self.send_completion(
+            messages,
+            Vec::new(),
+            None,
+            options.params.clone(),
+            false,
+            &options,
+        )
+        .await
+    }
+
+    /// Chat completion with tool calling support
+    pub async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_tools_and_options(messages, tools, None, None, None)
+            .await
+    }
+
+    /// Chat completion with tools and custom options
+    pub async fn chat_completion_with_tools_and_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.send_completion(
+            messages,
+            tools,
+            model,
+            GenerationParams::sampling(temperature, max_tokens),
+            false,
+            &RequestOptions::default(),
+        )
+        .await
+    }
+
+    /// Chat completion with tools, per-request generation parameters, a deadline
+    /// or cancellation token
+    pub async fn chat_completion_with_tools_with(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        options: RequestOptions,
+    ) -> RsllmResult<ChatResponse> {
+        self.send_completion(
+            messages,
+            tools,
+            None,
+            options.params.clone(),
+            false,
+            &options,
+        )
+        .await
+    }
+
+    async fn send_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<&str>,
+        params: GenerationParams,
+        force_cache: bool,
+        options: &RequestOptions,
+    ) -> RsllmResult<ChatResponse> {
+        let mut request = RequestContext {
+            operation: if tools.is_empty() {
+                "chat_completion"
+            } else {
+                "chat_completion_with_tools"
+            },
+            provider: self.provider.name().to_string(),
+            // Use configured model if not specified
+            model: model.unwrap_or(&self.config.model.model).to_string(),
+            messages,
+            tools,
+            // Use configured generation parameters where not specified
+            params: params.or(&self.config.model.generation_params()),
+            metadata: HashMap::new(),
+        };
+
+        if self.middleware.is_empty() {
+            return self.dispatch(&request, force_cache, options).await;
+        }
+
+        let started = tokio::time::Instant::now();
+        for middleware in &self.middleware {
+            middleware.on_request(&mut request).await;
+        }
+
+        let result = self.dispatch(&request, force_cache, options).await;
+
+        let response = ResponseContext {
+            latency: started.elapsed(),
+            result: result.as_ref(),
+        };
+        for middleware in self.middleware.iter().rev() {
+            middleware.on_response(&request, &response).await;
+        }
+
+        result
+    }
+
+    /// Validate a prepared request and send it through the cache, retry policy
+    /// and request deadline
+    async fn dispatch(
+        &self,
+        request: &RequestContext,
+        force_cache: bool,
+        options: &RequestOptions,
+    ) -> RsllmResult<ChatResponse> {
+        let operation = request.operation;
+        let model = request.model.as_str();
+        let messages = &request.messages;
+        let tools = &request.tools;
+        let params = &request.params;
+
         // Validate messages
         if messages.is_empty() {
             return Err(RsllmError::validation(
@@ -149,40 +577,154 @@ impl Client {
                 "Messages cannot be empty",
             ));
         }
+        params.validate()?;
+
+        let estimated_tokens = self.count_tokens(messages) as u32 + params.max_tokens.unwrap_or(0);
+
+        let parts = CacheKeyParts {
+            provider: self.provider.name(),
+            model,
+            messages,
+            tools,
+            params,
+        };
+
+        let call = self.with_cache(parts, force_cache, || {
+            self.with_retry(operation, estimated_tokens, || {
+                self.provider.chat_completion_with_params(
+                    messages.clone(),
+                    tools.clone(),
+                    Some(model),
+                    params,
+                )
+            })
+        });
+        options.guard(operation, self.request_timeout, call).await
+    }
 
-        // Use configured model if not specified
-        let model = model.unwrap_or(&self.config.model.model);
+    /// Chat completion parsed into a typed value
+    ///
+    /// Uses the provider's native structured output where available and repairs
+    /// unparseable responses by feeding the parse error back to the model.
+    #[cfg(feature = "json-schema")]
+    pub async fn chat_completion_structured<T>(&self, messages: Vec<ChatMessage>) -> RsllmResult<T>
+    where
+        T: serde::de::DeserializeOwned + schemars::JsonSchema,
+    {
+        let response = self
+            .chat_completion_structured_with_options(messages, StructuredOptions::default())
+            .await?;
+        Ok(response.value)
+    }
 
-        // Use configured temperature if not specified
-        let temperature = temperature.or(self.config.model.temperature);
+    /// Chat completion parsed into a typed value, returning the raw text as well
+    #[cfg(feature = "json-schema")]
+    pub async fn chat_completion_structured_with_options<T>(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: StructuredOptions,
+    ) -> RsllmResult<StructuredResponse<T>>
+    where
+        T: serde::de::DeserializeOwned + schemars::JsonSchema,
+    {
+        let schema = OutputSchema::for_type::<T>();
+        self.chat_completion_with_schema(messages, &schema, options)
+            .await
+    }
 
-        // Use configured max_tokens if not specified
-        let max_tokens = max_tokens.or(self.config.model.max_tokens);
+    /// Chat completion constrained to an explicit JSON schema
+    pub async fn chat_completion_with_schema<T: serde::de::DeserializeOwned>(
+        &self,
+        messages: Vec<ChatMessage>,
+        schema: &OutputSchema,
+        options: StructuredOptions,
+    ) -> RsllmResult<StructuredResponse<T>> {
+        // Validate messages
+        if messages.is_empty() {
+            return Err(RsllmError::validation(
+                "messages",
+                "Messages cannot be empty",
+            ));
+        }
 
-        self.provider
-            .chat_completion(messages, Some(model), temperature, max_tokens)
-            .await
+        let model = self.config.model.model.as_str();
+        let temperature = self.config.model.temperature;
+        let max_tokens = self.config.model.max_tokens;
+
+        let mut conversation = messages;
+        let mut repairs = 0;
+
+        loop {
+            let estimated_tokens =
+                self.count_tokens(&conversation) as u32 + max_tokens.unwrap_or(0);
+            let response = self
+                .with_retry("chat_completion_structured", estimated_tokens, || {
+                    self.provider.chat_completion_structured(
+                        conversation.clone(),
+                        schema,
+                        Some(model),
+                        temperature,
+                        max_tokens,
+                    )
+                })
+                .await?;
+
+            match parse_structured::<T>(&response.content) {
+                Ok(value) => {
+                    return Ok(StructuredResponse {
+                        value,
+                        raw: response.content,
+                        repairs,
+                    })
+                }
+                Err(error) if repairs < options.max_repairs => {
+                    repairs += 1;
+                    tracing::warn!(
+                        schema = %schema.name,
+                        repair = repairs,
+                        error = %error,
+                        raw = %response.content,
+                        "Structured response failed to parse, requesting repair"
+                    );
+                    conversation.push(ChatMessage::assistant(response.content));
+                    conversation.push(repair_message(&error));
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        schema = %schema.name,
+                        repairs,
+                        raw = %response.content,
+                        "Structured response failed to parse after repairs"
+                    );
+                    return Err(error);
+                }
+            }
+        }
     }
 
-    /// Chat completion with tool calling support
-    pub async fn chat_completion_with_tools(
+    /// Chat completion with tool calling support (streaming)
+    ///
+    /// Yields text and tool call fragments as they arrive; feed them into a
+    /// [`ToolCallAccumulator`](crate::streaming::ToolCallAccumulator) to assemble
+    /// complete tool calls.
+    pub async fn chat_completion_with_tools_stream(
         &self,
         messages: Vec<ChatMessage>,
         tools: Vec<crate::tools::ToolDefinition>,
-    ) -> RsllmResult<ChatResponse> {
-        self.chat_completion_with_tools_and_options(messages, tools, None, None, None)
+    ) -> RsllmResult<ToolAwareStream> {
+        self.chat_completion_with_tools_stream_with_options(messages, tools, None, None, None)
             .await
     }
 
-    /// Chat completion with tools and custom options
-    pub async fn chat_completion_with_tools_and_options(
+    /// Streaming chat completion with tools and custom options
+    pub async fn chat_completion_with_tools_stream_with_options(
         &self,
         messages: Vec<ChatMessage>,
         tools: Vec<crate::tools::ToolDefinition>,
         model: Option<&str>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
-    ) -> RsllmResult<ChatResponse> {
+    ) -> RsllmResult<ToolAwareStream> {
         // Validate messages
         if messages.is_empty() {
             return Err(RsllmError::validation(
@@ -201,7 +743,42 @@ impl Client {
         let max_tokens = max_tokens.or(self.config.model.max_tokens);
 
         self.provider
-            .chat_completion_with_tools(messages, tools, Some(model), temperature, max_tokens)
+            .chat_completion_with_tools_stream(
+                messages,
+                tools,
+                Some(model.to_string()),
+                temperature,
+                max_tokens,
+            )
+            .await
+    }
+
+    /// Streaming chat completion with tools, a deadline or cancellation token
+    ///
+    /// The deadline covers the whole stream: once it passes, or the token is
+    /// cancelled, the stream yields a final error and ends. The client's default
+    /// request timeout does not apply to streams. Streams honor the temperature
+    /// and token limit of the generation parameters only.
+    pub async fn chat_completion_with_tools_stream_with(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        options: RequestOptions,
+    ) -> RsllmResult<ToolAwareStream> {
+        let params = &options.params;
+        params.warn_dropped(self.provider.name(), STREAM_DROPPED_PARAMS);
+        options
+            .guard_stream(
+                "chat_completion_with_tools_stream",
+                None,
+                self.chat_completion_with_tools_stream_with_options(
+                    messages,
+                    tools,
+                    None,
+                    params.temperature,
+                    params.max_tokens,
+                ),
+            )
             .await
     }
 
@@ -248,6 +825,218 @@ impl Client {
         Ok(Box::pin(stream) as ChatStream)
     }
 
+    /// Streaming chat completion with a deadline or cancellation token
+    ///
+    /// See [`chat_completion_with_tools_stream_with`](Self::chat_completion_with_tools_stream_with)
+    /// for how the deadline applies.
+    pub async fn chat_completion_stream_with(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: RequestOptions,
+    ) -> RsllmResult<ChatStream> {
+        let params = &options.params;
+        params.warn_dropped(self.provider.name(), STREAM_DROPPED_PARAMS);
+        options
+            .guard_stream(
+                "chat_completion_stream",
+                None,
+                self.chat_completion_stream_with_options(
+                    messages,
+                    None,
+                    params.temperature,
+                    params.max_tokens,
+                ),
+            )
+            .await
+    }
+
+    /// Serve a request from the response cache when it is cacheable
+    ///
+    /// Requests are cacheable when forced by the caller or sent at temperature 0.
+    /// Cache failures are logged and never fail the request.
+    async fn with_cache<F, Fut>(
+        &self,
+        parts: CacheKeyParts<'_>,
+        force: bool,
+        call: F,
+    ) -> RsllmResult<ChatResponse>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = RsllmResult<ChatResponse>>,
+    {
+        let cache = match &self.response_cache {
+            Some(cache) if force || parts.params.temperature == Some(0.0) => cache,
+            _ => return call().await,
+        };
+
+        let key = parts.key();
+        match cache.get(&key).await {
+            Ok(Some(mut response)) => {
+                tracing::debug!(key = %key, "Serving chat completion from cache");
+                response
+                    .metadata
+                    .insert("cache_hit".to_string(), serde_json::Value::Bool(true));
+                return Ok(response);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(key = %key, error = %e, "Response cache lookup failed"),
+        }
+
+        let response = call().await?;
+        if let Err(e) = cache.put(&key, &response).await {
+            tracing::warn!(key = %key, error = %e, "Response cache write failed");
+        }
+        Ok(response)
+    }
+
+    /// Response cache statistics, if a cache is configured
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.response_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Usage tracker this client records into, if any
+    pub fn usage_tracker(&self) -> Option<&Arc<UsageTracker>> {
+        self.usage_tracker.as_ref()
+    }
+
+    /// Accumulate this client's token usage into a shared tracker
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.usage_tracker = Some(tracker);
+        self
+    }
+
+    /// Copy of this client that records its usage under `label`
+    ///
+    /// Clones share the provider, caches and tracker, so one tracker can break
+    /// usage down per agent or task.
+    pub fn with_usage_label(mut self, label: impl Into<String>) -> Self {
+        self.usage_label = Some(label.into());
+        self
+    }
+
+    /// Label recorded with this client's usage
+    pub fn usage_label(&self) -> Option<&str> {
+        self.usage_label.as_deref()
+    }
+
+    /// Deadline applied to requests that do not set their own
+    pub fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout
+    }
+
+    /// Apply a deadline to requests that do not set their own
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Append a middleware to the chain run around every chat completion
+    ///
+    /// Request hooks run in the order middleware was added and response hooks
+    /// in reverse. Hooks see chat and tool-calling requests, including ones that
+    /// fail validation, time out or exhaust their retries. Streaming requests
+    /// do not pass through middleware.
+    pub fn with_middleware(mut self, middleware: Arc<dyn ClientMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Prices used to estimate the cost of tracked usage
+    pub fn pricing(&self) -> &PricingTable {
+        &self.pricing
+    }
+
+    /// Replace the prices used to estimate the cost of tracked usage
+    pub fn set_pricing(&mut self, pricing: PricingTable) {
+        self.pricing = Arc::new(pricing);
+    }
+
+    /// Clear the response cache, if one is configured
+    pub async fn clear_cache(&self) -> RsllmResult<()> {
+        match &self.response_cache {
+            Some(cache) => cache.clear().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Run a provider call under the configured rate limit and retry policy
+    ///
+    /// Every attempt waits for rate limit budget first. Retryable failures are
+    /// retried with exponential backoff; once retries are exhausted the last error
+    /// is wrapped with the number of attempts made.
+    async fn with_retry<F, Fut>(
+        &self,
+        operation: &str,
+        estimated_tokens: u32,
+        mut call: F,
+    ) -> RsllmResult<ChatResponse>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = RsllmResult<ChatResponse>>,
+    {
+        let policy = &self.config.retry;
+        let mut attempt: u32 = 0;
+
+        loop {
+            attempt += 1;
+
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(estimated_tokens).await?;
+            }
+
+            let error = match call().await {
+                Ok(response) => {
+                    if let (Some(limiter), Some(usage)) = (&self.rate_limiter, &response.usage) {
+                        limiter.record_usage(estimated_tokens, usage.total_tokens);
+                    }
+                    if let (Some(tracker), Some(usage)) = (&self.usage_tracker, &response.usage) {
+                        let cost = self.pricing.cost(&response.model, usage);
+                        tracker.record(&response.model, self.usage_label.as_deref(), usage, cost);
+                    }
+                    if attempt > 1 {
+                        tracing::debug!(operation, attempt, "LLM request succeeded after retry");
+                    }
+                    return Ok(response);
+                }
+                Err(error) => error,
+            };
+
+            if !policy.should_retry(&error) {
+                if attempt > 1 {
+                    tracing::warn!(
+                        operation,
+                        attempts = attempt,
+                        error = %error,
+                        "LLM request failed with a non-retryable error after retrying"
+                    );
+                    return Err(RsllmError::retry_aborted(attempt, error));
+                }
+                return Err(error);
+            }
+
+            if attempt > policy.max_retries {
+                tracing::warn!(
+                    operation,
+                    attempts = attempt,
+                    error = %error,
+                    "LLM request failed, retries exhausted"
+                );
+                return Err(RsllmError::retries_exhausted(attempt, error));
+            }
+
+            let delay = policy.delay_for(attempt, &error);
+            tracing::warn!(
+                operation,
+                attempt,
+                max_retries = policy.max_retries,
+                delay_ms = delay.as_millis() as u64,
+                error = %error,
+                "LLM request failed, retrying"
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
     /// Simple text completion
     pub async fn complete(&self, prompt: impl Into<String>) -> RsllmResult<String> {
         let messages = vec![ChatMessage::user(prompt.into())];
@@ -267,12 +1056,32 @@ impl Client {
         Err(RsllmError::configuration("Embeddings not yet implemented"))
     }
 
-    /// Count tokens in text (placeholder - would need tokenizer)
-    pub fn count_tokens(&self, _text: &str) -> RsllmResult<u32> {
-        // TODO: Implement tokenization
-        Err(RsllmError::configuration(
-            "Token counting not yet implemented",
-        ))
+    /// Count the prompt tokens a message list will consume
+    pub fn count_tokens(&self, messages: &[ChatMessage]) -> usize {
+        self.token_counter.count_messages(messages)
+    }
+
+    /// Context window of the configured model
+    ///
+    /// Uses the configured override, then the model registry, then a conservative default.
+    pub fn context_window(&self) -> usize {
+        self.config
+            .model
+            .context_window
+            .or_else(|| crate::tokens::context_window(&self.config.model.model))
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+    }
+
+    /// Trim messages so the prompt fits the context window with room for the reply
+    ///
+    /// See [`fit_messages`](crate::tokens::fit_messages) for the trimming rules.
+    pub fn fit_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+        reserve_for_output: usize,
+    ) -> Vec<ChatMessage> {
+        let budget = self.context_window().saturating_sub(reserve_for_output);
+        crate::tokens::fit_messages(messages, self.token_counter.as_ref(), budget)
     }
 }
 
@@ -288,6 +1097,12 @@ impl std::fmt::Debug for Client {
 /// Client builder for fluent configuration
 pub struct ClientBuilder {
     config: ClientConfig,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    token_counter: Option<Arc<dyn TokenCounter>>,
+    response_cache: Option<Arc<ResponseCache>>,
+    usage_tracker: Option<Arc<UsageTracker>>,
+    pricing: Option<PricingTable>,
+    request_timeout: Option<Duration>,
 }
 
 impl ClientBuilder {
@@ -295,6 +1110,12 @@ impl ClientBuilder {
     pub fn new() -> Self {
         Self {
             config: ClientConfig::default(),
+            rate_limiter: None,
+            token_counter: None,
+            response_cache: None,
+            usage_tracker: None,
+            pricing: None,
+            request_timeout: None,
         }
     }
 
@@ -328,6 +1149,64 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the Azure OpenAI resource name and select the Azure provider
+    pub fn azure_resource(mut self, resource: impl Into<String>) -> Self {
+        self.azure_config().resource = Some(resource.into());
+        self
+    }
+
+    /// Set the Azure OpenAI deployment, which also serves as the model name
+    pub fn azure_deployment(mut self, deployment: impl Into<String>) -> Self {
+        let deployment = deployment.into();
+        self.config.model.model = deployment.clone();
+        self.azure_config().deployment = deployment;
+        self
+    }
+
+    /// Set the Azure OpenAI REST API version
+    pub fn azure_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.azure_config().api_version = api_version.into();
+        self
+    }
+
+    /// Set the AWS region and select the Bedrock provider
+    pub fn bedrock_region(mut self, region: impl Into<String>) -> Self {
+        self.bedrock_config().region = region.into();
+        self
+    }
+
+    /// Set static AWS credentials for Bedrock
+    pub fn aws_credentials(
+        mut self,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        let bedrock = self.bedrock_config();
+        bedrock.access_key_id = Some(access_key_id.into());
+        bedrock.secret_access_key = Some(secret_access_key.into());
+        bedrock.session_token = session_token;
+        self
+    }
+
+    /// Bedrock settings, switching the provider to Bedrock
+    fn bedrock_config(&mut self) -> &mut BedrockConfig {
+        self.config.provider.provider = Provider::Bedrock;
+        self.config
+            .provider
+            .bedrock
+            .get_or_insert_with(Default::default)
+    }
+
+    /// Azure settings, switching the provider to Azure OpenAI
+    fn azure_config(&mut self) -> &mut AzureOpenAIConfig {
+        self.config.provider.provider = Provider::AzureOpenAI;
+        self.config
+            .provider
+            .azure
+            .get_or_insert_with(Default::default)
+    }
+
     /// Set the temperature
     pub fn temperature(mut self, temperature: f32) -> Self {
         self.config.model.temperature = Some(temperature);
@@ -358,15 +1237,124 @@ impl ClientBuilder {
         self
     }
 
+    /// Enable or disable sending tool definitions to the provider
+    pub fn tools_enabled(mut self, enabled: bool) -> Self {
+        self.config.provider.tools_enabled = enabled;
+        self
+    }
+
+    /// Accept invalid TLS certificates, e.g. for self-signed local servers
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.config.http.verify_tls = !accept;
+        self
+    }
+
+    /// Trust an additional root certificate (PEM)
+    pub fn root_certificate_pem(mut self, pem: impl Into<String>) -> Self {
+        self.config.http.root_certificate_pem = Some(pem.into());
+        self
+    }
+
+    /// Trust the root certificates in a PEM file, e.g. a TLS-intercepting proxy's CA
+    ///
+    /// The file is read by [`build`](Self::build), which fails if it is unreadable.
+    pub fn root_certificate_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config.http.root_certificate_files.push(path.into());
+        self
+    }
+
+    /// Route requests through a proxy, overriding `HTTPS_PROXY`/`ALL_PROXY`
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.config.http.proxy = Some(proxy);
+        self
+    }
+
+    /// Whether proxy environment variables are honored when no proxy is set (default: true)
+    pub fn use_env_proxy(mut self, enabled: bool) -> Self {
+        self.config.http.use_env_proxy = enabled;
+        self
+    }
+
     /// Set retry configuration
     pub fn max_retries(mut self, max_retries: u32) -> Self {
         self.config.retry.max_retries = max_retries;
         self
     }
 
+    /// Set the retry policy for transient provider errors
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = policy;
+        self
+    }
+
+    /// Set client-side rate limit budgets
+    pub fn rate_limit(mut self, limits: RateLimitConfig) -> Self {
+        self.config.rate_limit = Some(limits);
+        self
+    }
+
+    /// Use an existing rate limiter, sharing its budget with other clients
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.config.rate_limit = Some(limiter.config().clone());
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Override the model's context window
+    pub fn context_window(mut self, tokens: usize) -> Self {
+        self.config.model.context_window = Some(tokens);
+        self
+    }
+
+    /// Use a custom token counter
+    pub fn token_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = Some(counter);
+        self
+    }
+
+    /// Cache responses for cacheable requests, sharing the cache with other clients
+    pub fn response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(cache);
+        self
+    }
+
+    /// Accumulate token usage into a shared tracker
+    pub fn usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.usage_tracker = Some(tracker);
+        self
+    }
+
+    /// Use custom prices when estimating the cost of tracked usage
+    pub fn pricing(mut self, pricing: PricingTable) -> Self {
+        self.pricing = Some(pricing);
+        self
+    }
+
+    /// Default deadline for each request, including retries
+    ///
+    /// Unlike [`timeout`](Self::timeout), which bounds a single HTTP attempt, this
+    /// bounds the whole call. Per-request [`RequestOptions`] take precedence.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
     /// Build the client
     pub fn build(self) -> RsllmResult<Client> {
-        Client::new(self.config)
+        let mut client = Client::new(self.config)?;
+        if let Some(limiter) = self.rate_limiter {
+            client.rate_limiter = Some(limiter);
+        }
+        if let Some(counter) = self.token_counter {
+            client.token_counter = counter;
+        }
+        client.response_cache = self.response_cache;
+        client.usage_tracker = self.usage_tracker;
+        if let Some(pricing) = self.pricing {
+            client.set_pricing(pricing);
+        }
+        client.request_timeout = self.request_timeout;
+        Ok(client)
     }
 }
 
@@ -523,4 +1511,1194 @@ mod tests {
         // This will fail due to missing implementation, but we can test the validation logic
         assert!(config.is_err() || config.is_ok()); // Either way is fine for structure test
     }
+
+    #[cfg(feature = "openai")]
+    fn retry_test_client(server: &wiremock::MockServer) -> Client {
+        ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .base_url(server.uri())
+            .unwrap()
+            .retry_policy(
+                RetryPolicy::default()
+                    .with_max_retries(3)
+                    .with_base_delay(std::time::Duration::from_millis(5))
+                    .with_jitter(false),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_retries_rate_limited_requests() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("slow down"))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = retry_test_client(&server);
+        let response = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "ok");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_does_not_retry_invalid_requests() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("invalid request"))
+            .mount(&server)
+            .await;
+
+        let client = retry_test_client(&server);
+        let error = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, RsllmError::Api { ref code, .. } if code == "400"));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // A 400 after rate limiting stops retrying but reports the attempts made
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("slow down"))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("invalid request"))
+            .mount(&server)
+            .await;
+
+        let client = retry_test_client(&server);
+        let error = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            RsllmError::RetryAborted { attempts: 3, ref source }
+                if matches!(**source, RsllmError::Api { ref code, .. } if code == "400")
+        ));
+        assert_eq!(error.status_code(), Some(400));
+        assert!(!error.is_retryable());
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_reports_attempts_when_retries_exhausted() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("overloaded"))
+            .mount(&server)
+            .await;
+
+        let client = retry_test_client(&server);
+        let error = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            RsllmError::RetriesExhausted { attempts: 4, .. }
+        ));
+        assert_eq!(error.status_code(), Some(503));
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_clones_share_rate_limiter() {
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .rate_limit(RateLimitConfig::new().with_requests_per_minute(10))
+            .build()
+            .unwrap();
+        let clone = client.clone();
+
+        assert!(Arc::ptr_eq(
+            client.rate_limiter().unwrap(),
+            clone.rate_limiter().unwrap()
+        ));
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_fit_messages_uses_context_window() {
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .model("gpt-4")
+            .build()
+            .unwrap();
+        assert_eq!(client.context_window(), 8_192);
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .context_window(600)
+            .build()
+            .unwrap();
+
+        let mut messages = vec![ChatMessage::system("Be brief.")];
+        for i in 0..40 {
+            messages.push(ChatMessage::user(format!(
+                "message {} {}",
+                i,
+                "x".repeat(200)
+            )));
+        }
+
+        let fitted = client.fit_messages(messages, 100);
+        assert!(client.count_tokens(&fitted) <= 500);
+        assert_eq!(fitted[0].role, MessageRole::System);
+    }
+
+    /// Provider that replays scripted responses and records requests
+    struct ScriptedProvider {
+        responses: std::sync::Mutex<std::collections::VecDeque<String>>,
+        requests: std::sync::Mutex<Vec<Vec<ChatMessage>>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(responses: &[&str]) -> Self {
+            Self {
+                responses: std::sync::Mutex::new(responses.iter().map(|r| r.to_string()).collect()),
+                requests: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "Scripted"
+        }
+
+        fn provider_type(&self) -> Provider {
+            Provider::OpenAI
+        }
+
+        fn supported_models(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        async fn health_check(&self) -> RsllmResult<bool> {
+            Ok(true)
+        }
+
+        async fn chat_completion(
+            &self,
+            messages: Vec<ChatMessage>,
+            model: Option<&str>,
+            _temperature: Option<f32>,
+            _max_tokens: Option<u32>,
+        ) -> RsllmResult<ChatResponse> {
+            self.requests.lock().unwrap().push(messages);
+            let content = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_default();
+            Ok(ChatResponse::new(content, model.unwrap_or("scripted")))
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _model: Option<String>,
+            _temperature: Option<f32>,
+            _max_tokens: Option<u32>,
+        ) -> RsllmResult<
+            Box<dyn futures_util::Stream<Item = RsllmResult<crate::StreamChunk>> + Send + Unpin>,
+        > {
+            Ok(Box::new(futures_util::stream::empty()))
+        }
+    }
+
+    /// Middleware that logs its hook calls into a shared list
+    struct RecordingMiddleware {
+        name: &'static str,
+        events: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ClientMiddleware for RecordingMiddleware {
+        async fn on_request(&self, request: &mut RequestContext) {
+            let org = request.params.headers.get("x-org").cloned();
+            self.events.lock().unwrap().push(format!(
+                "{} request {} org={:?}",
+                self.name, request.operation, org
+            ));
+            if self.name == "outer" {
+                request.insert_header("x-org", "acme");
+            }
+        }
+
+        async fn on_response(&self, _request: &RequestContext, response: &ResponseContext<'_>) {
+            let outcome = match response.result {
+                Ok(chat) => format!("ok {}", chat.content),
+                Err(error) => format!("error {}", error.category()),
+            };
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{} response {}", self.name, outcome));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order_and_errors() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = |name| {
+            Arc::new(RecordingMiddleware {
+                name,
+                events: events.clone(),
+            })
+        };
+        let client = Client::with_provider(
+            ClientConfig::default(),
+            Arc::new(ScriptedProvider::new(&["first", "second"])),
+        )
+        .with_middleware(recorder("outer"))
+        .with_middleware(recorder("inner"));
+
+        client
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        client
+            .chat_completion_with_tools(
+                vec![ChatMessage::user("hi")],
+                vec![crate::tools::ToolDefinition::new(
+                    "lookup",
+                    "Look something up",
+                    serde_json::json!({"type": "object"}),
+                )],
+            )
+            .await
+            .unwrap();
+        let err = client
+            .chat_completion_with(
+                vec![ChatMessage::user("hi")],
+                RequestOptions::new().with_params(GenerationParams::new().with_temperature(3.0)),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Validation { .. }));
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "outer request chat_completion org=None",
+                "inner request chat_completion org=Some(\"acme\")",
+                "inner response ok first",
+                "outer response ok first",
+                "outer request chat_completion_with_tools org=None",
+                "inner request chat_completion_with_tools org=Some(\"acme\")",
+                "inner response ok second",
+                "outer response ok second",
+                "outer request chat_completion org=None",
+                "inner request chat_completion org=Some(\"acme\")",
+                "inner response error validation",
+                "outer response error validation",
+            ]
+        );
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[tokio::test]
+    async fn test_structured_output_repairs_once() {
+        #[derive(Debug, serde::Deserialize, schemars::JsonSchema, PartialEq)]
+        struct Sentiment {
+            label: String,
+            score: f32,
+        }
+
+        let provider = Arc::new(ScriptedProvider::new(&[
+            "Sure! The sentiment is positive.",
+            r#"{"label": "positive", "score": 0.9}"#,
+        ]));
+        let client = Client::with_provider(ClientConfig::default(), provider.clone());
+
+        let response = client
+            .chat_completion_structured_with_options::<Sentiment>(
+                vec![ChatMessage::user("I love this!")],
+                StructuredOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.value,
+            Sentiment {
+                label: "positive".to_string(),
+                score: 0.9
+            }
+        );
+        assert_eq!(response.repairs, 1);
+        assert_eq!(response.raw, r#"{"label": "positive", "score": 0.9}"#);
+
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        // The schema instruction is injected and the repair request carries the bad output
+        assert!(requests[0][0].text().unwrap().contains("JSON schema"));
+        let repair = &requests[1];
+        assert_eq!(
+            repair[repair.len() - 2].text(),
+            Some("Sure! The sentiment is positive.")
+        );
+        assert!(repair[repair.len() - 1]
+            .text()
+            .unwrap()
+            .contains("could not be parsed"));
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_gives_up_after_max_repairs() {
+        let provider = Arc::new(ScriptedProvider::new(&["nope", "still nope"]));
+        let client = Client::with_provider(ClientConfig::default(), provider.clone());
+        let schema = OutputSchema::new("anything", serde_json::json!({"type": "object"}));
+
+        let result = client
+            .chat_completion_with_schema::<serde_json::Map<String, serde_json::Value>>(
+                vec![ChatMessage::user("hi")],
+                &schema,
+                StructuredOptions::default().with_max_repairs(1),
+            )
+            .await;
+
+        assert!(matches!(result, Err(RsllmError::Validation { .. })));
+        assert_eq!(provider.requests.lock().unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_response_cache_skips_http_for_identical_requests() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "positive"}}]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .base_url(server.uri())
+            .unwrap()
+            .temperature(0.0)
+            .response_cache(Arc::new(ResponseCache::in_memory(
+                std::time::Duration::from_secs(60),
+            )))
+            .build()
+            .unwrap();
+
+        let messages = vec![
+            ChatMessage::system("Classify the sentiment."),
+            ChatMessage::user("I love it"),
+        ];
+
+        let first = client.chat_completion(messages.clone()).await.unwrap();
+        let second = client.chat_completion(messages.clone()).await.unwrap();
+        assert_eq!(first.content, second.content);
+        assert_eq!(
+            second.metadata.get("cache_hit"),
+            Some(&serde_json::json!(true))
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // Changing one message busts the cache
+        let mut changed = messages;
+        changed[1] = ChatMessage::user("I hate it");
+        client.chat_completion(changed).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        let stats = client.cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.writes), (1, 2, 2));
+
+        client.clear_cache().await.unwrap();
+        assert_eq!(client.cache_stats().unwrap(), CacheStats::default());
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_response_cache_requires_cacheable_request() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "hi"}}]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .base_url(server.uri())
+            .unwrap()
+            .temperature(0.7)
+            .response_cache(Arc::new(ResponseCache::in_memory(
+                std::time::Duration::from_secs(60),
+            )))
+            .build()
+            .unwrap();
+
+        let messages = vec![ChatMessage::user("hello")];
+        client.chat_completion(messages.clone()).await.unwrap();
+        client.chat_completion(messages.clone()).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        // An explicit per-call flag caches regardless of temperature
+        client
+            .chat_completion_cached(messages.clone())
+            .await
+            .unwrap();
+        client.chat_completion_cached(messages).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "openai")]
+    fn azure_test_client(server: &wiremock::MockServer) -> Client {
+        ClientBuilder::new()
+            .azure_resource("contoso")
+            .azure_deployment("gpt4o-prod")
+            .azure_api_version("2024-06-01")
+            .api_key("azure-key")
+            .base_url(server.uri())
+            .unwrap()
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap()
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_azure_openai_url_and_headers() {
+        use wiremock::matchers::{header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt4o-prod/chat/completions"))
+            .and(query_param("api-version", "2024-06-01"))
+            .and(header("api-key", "azure-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "lookup", "arguments": "{\"q\":\"rust\"}"}
+                    }]
+                }}]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = azure_test_client(&server);
+        assert_eq!(client.provider().provider_type(), Provider::AzureOpenAI);
+
+        client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap();
+
+        let response = client
+            .chat_completion_with_tools(
+                vec![ChatMessage::user("search for rust")],
+                vec![crate::tools::ToolDefinition::new(
+                    "lookup",
+                    "Look something up",
+                    serde_json::json!({"type": "object"}),
+                )],
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.tool_calls.unwrap()[0].function.name, "lookup");
+
+        // Azure authenticates with `api-key` only, never a bearer token
+        for request in server.received_requests().await.unwrap() {
+            assert!(!request.headers.contains_key("authorization"));
+        }
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_azure_openai_distinguishes_missing_deployment_from_auth() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt4o-prod/chat/completions"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "error": {"code": "DeploymentNotFound", "message": "The API deployment for this resource does not exist."}
+            })))
+            .mount(&server)
+            .await;
+
+        let err = azure_test_client(&server)
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::NotFound { .. }));
+        assert!(err.to_string().contains("gpt4o-prod"));
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid subscription key"))
+            .mount(&server)
+            .await;
+
+        let err = azure_test_client(&server)
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Authentication { .. }));
+    }
+
+    #[test]
+    fn test_azure_config_validation() {
+        let mut config = ClientConfig::default();
+        config.provider.provider = Provider::AzureOpenAI;
+        config.provider.api_key = Some("azure-key".to_string());
+        assert!(config.provider.validate().is_err());
+
+        config.provider.azure = Some(crate::AzureOpenAIConfig::new("contoso", "gpt4o-prod"));
+        assert!(config.provider.validate().is_ok());
+        assert_eq!(
+            config
+                .provider
+                .azure
+                .as_ref()
+                .unwrap()
+                .endpoint(None)
+                .unwrap()
+                .as_str(),
+            "https://contoso.openai.azure.com/"
+        );
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_compatible_injects_headers() {
+        use wiremock::matchers::{body_partial_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("HTTP-Referer", "https://example.com"))
+            .and(header("X-Title", "Rexis Tests"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "meta-llama/llama-3.1-8b"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "hi"}}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAICompatible)
+            .base_url(format!("{}/v1", server.uri()))
+            .unwrap()
+            .model("meta-llama/llama-3.1-8b")
+            .header("HTTP-Referer", "https://example.com")
+            .header("X-Title", "Rexis Tests")
+            .build()
+            .unwrap();
+
+        let response = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap();
+        assert_eq!(response.content, "hi");
+
+        // No API key configured, so no bearer token is sent
+        let requests = server.received_requests().await.unwrap();
+        assert!(!requests[0].headers.contains_key("authorization"));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_requests_routed_through_proxy() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // The mock server plays the proxy; the upstream host does not resolve
+        let proxy = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("proxy-authorization", "Basic dXNlcjpzZWNyZXQ="))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "via proxy"}}]
+            })))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAICompatible)
+            .base_url("http://llm.invalid/v1")
+            .unwrap()
+            .model("local-model")
+            .proxy(ProxyConfig::new(proxy.uri()).with_credentials("user", "secret"))
+            .build()
+            .unwrap();
+
+        let response = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap();
+        assert_eq!(response.content, "via proxy");
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_per_request_generation_params() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let reply = |content: &str| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": content}}]
+            }))
+        };
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "temperature": 0.0,
+                "max_tokens": 64,
+                "stop": ["###"],
+                "seed": 7,
+            })))
+            .respond_with(reply("routed"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "temperature": 0.5,
+                "max_tokens": 64,
+            })))
+            .respond_with(reply("creative"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAICompatible)
+            .base_url(format!("{}/v1", server.uri()))
+            .unwrap()
+            .model("local-model")
+            .temperature(0.5)
+            .max_tokens(64)
+            .build()
+            .unwrap();
+
+        // Per-call parameters override the configured ones; the rest fall back
+        let params = GenerationParams::new()
+            .with_temperature(0.0)
+            .with_stop(["###"])
+            .with_seed(7);
+        let routed = client
+            .chat_completion_with(
+                vec![ChatMessage::user("route")],
+                RequestOptions::new().with_params(params),
+            )
+            .await
+            .unwrap();
+        assert_eq!(routed.content, "routed");
+
+        let creative = client
+            .chat_completion(vec![ChatMessage::user("write")])
+            .await
+            .unwrap();
+        assert_eq!(creative.content, "creative");
+
+        let err = client
+            .chat_completion_with(
+                vec![ChatMessage::user("hot")],
+                RequestOptions::new().with_params(GenerationParams::new().with_temperature(3.0)),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Validation { .. }));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_batch_upload_poll_and_results() {
+        use crate::BatchState;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let batch = |status: &str, output: Option<&str>, error: Option<&str>| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "batch_1",
+                "object": "batch",
+                "status": status,
+                "request_counts": {"total": 3, "completed": 1, "failed": 2},
+                "output_file_id": output,
+                "error_file_id": error,
+            }))
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/v1/files"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "file-in", "object": "file", "purpose": "batch"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/batches"))
+            .and(body_partial_json(serde_json::json!({
+                "input_file_id": "file-in",
+                "endpoint": "/v1/chat/completions",
+                "completion_window": "24h",
+            })))
+            .respond_with(batch("validating", None, None))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/batches/batch_1"))
+            .respond_with(batch("in_progress", None, None))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/batches/batch_1"))
+            .respond_with(batch("completed", Some("file-out"), Some("file-err")))
+            .mount(&server)
+            .await;
+
+        let output = serde_json::json!({
+            "id": "batch_req_a",
+            "custom_id": "a",
+            "response": {"status_code": 200, "body": {
+                "model": "local-model",
+                "choices": [{"message": {"role": "assistant", "content": "0.9"}}],
+                "usage": {"prompt_tokens": 20, "completion_tokens": 2, "total_tokens": 22}
+            }},
+            "error": null
+        });
+        let errors = [
+            serde_json::json!({
+                "id": "batch_req_b",
+                "custom_id": "b",
+                "response": {"status_code": 400, "body": {
+                    "error": {"message": "Invalid 'max_tokens'", "code": "invalid_value"}
+                }},
+                "error": null
+            }),
+            serde_json::json!({
+                "id": "batch_req_c",
+                "custom_id": "c",
+                "response": null,
+                "error": {"code": "batch_expired", "message": "Not executed before the window expired"}
+            }),
+        ];
+        Mock::given(method("GET"))
+            .and(path("/v1/files/file-out/content"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("{}\n", output)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/files/file-err/content"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("{}\n{}\n", errors[0], errors[1])),
+            )
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAICompatible)
+            .base_url(format!("{}/v1", server.uri()))
+            .unwrap()
+            .model("local-model")
+            .temperature(0.0)
+            .build()
+            .unwrap();
+
+        let request = |id: &str| BatchChatRequest::new(id, vec![ChatMessage::user("Rate 0-1")]);
+
+        let err = client
+            .create_batch(vec![request("a"), request("a")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Validation { .. }));
+
+        let handle = client
+            .create_batch(vec![request("a"), request("b"), request("c")])
+            .await
+            .unwrap();
+        assert_eq!(handle.id(), "batch_1");
+
+        // One JSONL line per request, with the client's model and defaults applied
+        let upload = &server.received_requests().await.unwrap()[0];
+        let upload = String::from_utf8_lossy(&upload.body);
+        assert!(upload.contains("name=\"purpose\""));
+        for id in ["a", "b", "c"] {
+            assert!(upload.contains(&format!("\"custom_id\":\"{}\"", id)));
+        }
+        assert!(upload.contains("\"model\":\"local-model\""));
+        assert!(upload.contains("\"temperature\":0.0"));
+
+        // Results are only available once the batch has finished
+        let err = handle.results().await.unwrap_err();
+        assert!(matches!(err, RsllmError::InvalidState { .. }));
+
+        let status = handle.wait(Duration::from_millis(10)).await.unwrap();
+        assert_eq!(status.state, BatchState::Completed);
+        assert_eq!((status.total, status.completed, status.failed), (3, 1, 2));
+
+        let mut results = client.batch("batch_1").results().await.unwrap();
+        results.sort_by(|a, b| a.custom_id.cmp(&b.custom_id));
+        assert_eq!(results.len(), 3);
+
+        let scored = results[0].response.as_ref().unwrap();
+        assert_eq!(scored.content, "0.9");
+        assert_eq!(scored.usage.as_ref().unwrap().total_tokens, 22);
+        assert!(matches!(
+            &results[1].response,
+            Err(RsllmError::Api { code, .. }) if code == "invalid_value"
+        ));
+        assert!(matches!(
+            &results[2].response,
+            Err(RsllmError::Api { code, .. }) if code == "batch_expired"
+        ));
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_builder_rejects_bad_proxy_and_certificates() {
+        let builder = || {
+            ClientBuilder::new()
+                .provider(Provider::OpenAICompatible)
+                .base_url("http://localhost:8000/v1")
+                .unwrap()
+                .model("local-model")
+        };
+
+        let err = builder()
+            .proxy(ProxyConfig::new("ftp://proxy.internal"))
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Configuration { .. }));
+
+        let err = builder()
+            .root_certificate_file("/nonexistent/corporate-ca.pem")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Configuration { .. }));
+        assert!(err.to_string().contains("corporate-ca.pem"));
+
+        let err = builder()
+            .root_certificate_pem("not a certificate")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Configuration { .. }));
+
+        assert!(builder()
+            .proxy(ProxyConfig::new("proxy.internal:3128").with_no_proxy("localhost"))
+            .use_env_proxy(false)
+            .danger_accept_invalid_certs(true)
+            .build()
+            .is_ok());
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_compatible_no_tools_mode() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "plain answer"}}]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAICompatible)
+            .base_url(server.uri())
+            .unwrap()
+            .api_key("local-key")
+            .tools_enabled(false)
+            .build()
+            .unwrap();
+
+        let response = client
+            .chat_completion_with_tools(
+                vec![ChatMessage::user("search for rust")],
+                vec![crate::tools::ToolDefinition::new(
+                    "lookup",
+                    "Look something up",
+                    serde_json::json!({"type": "object"}),
+                )],
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.content, "plain answer");
+
+        let request = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = request.body_json().unwrap();
+        assert!(body.get("tools").is_none());
+        assert_eq!(
+            request.headers["authorization"].to_str().unwrap(),
+            "Bearer local-key"
+        );
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_openai_compatible_requires_base_url() {
+        let mut config = ClientConfig::default();
+        config.provider.provider = Provider::OpenAICompatible;
+        assert!(config.provider.validate().is_err());
+
+        let client = Client::openai_compatible("http://localhost:1234/v1", None, "local-model");
+        assert_eq!(client.unwrap().config().model.model, "local-model");
+    }
+
+    #[cfg(feature = "openai")]
+    async fn slow_server(delay: Duration) -> wiremock::MockServer {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "choices": [{"message": {"role": "assistant", "content": "late"}}]
+                    }))
+                    .set_delay(delay),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_request_timeout() {
+        use crate::options::RequestOptions;
+
+        let server = slow_server(Duration::from_secs(5)).await;
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .base_url(server.uri())
+            .unwrap()
+            .request_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+
+        // The per-request deadline takes precedence over the client default
+        let started = std::time::Instant::now();
+        let err = client
+            .chat_completion_with(
+                vec![ChatMessage::user("hi")],
+                RequestOptions::new().with_timeout(Duration::from_millis(100)),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            RsllmError::Timeout {
+                timeout_ms: 100,
+                ..
+            }
+        ));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // The client default applies to calls without options
+        let client = client.with_request_timeout(Duration::from_millis(100));
+        let err = client
+            .chat_completion_with_tools(vec![ChatMessage::user("hi")], Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.category(), "timeout");
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_cancellation_aborts_promptly() {
+        use crate::options::{CancellationToken, RequestOptions};
+
+        let server = slow_server(Duration::from_secs(5)).await;
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .base_url(server.uri())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let err = client
+            .chat_completion_with_tools_with(
+                vec![ChatMessage::user("hi")],
+                Vec::new(),
+                RequestOptions::new().with_cancellation(token),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RsllmError::Cancelled { .. }));
+        assert!(!err.is_retryable());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_validate_rejects_bad_key() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "error": {"message": "Incorrect API key provided", "code": "invalid_api_key"}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("bad-key")
+            .base_url(server.uri())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let err = client.validate().await.unwrap_err();
+        assert!(matches!(err, RsllmError::Authentication { .. }));
+        assert!(err.to_string().contains("Incorrect API key"));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_usage_tracker_records_per_label() {
+        use crate::usage::UsageTracker;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}],
+                "usage": {"prompt_tokens": 40, "completion_tokens": 2, "total_tokens": 42}
+            })))
+            .mount(&server)
+            .await;
+
+        let tracker = Arc::new(UsageTracker::new());
+        let client = ClientBuilder::new()
+            .provider(Provider::OpenAI)
+            .api_key("test-key")
+            .base_url(server.uri())
+            .unwrap()
+            .model("gpt-4o-mini")
+            .usage_tracker(tracker.clone())
+            .build()
+            .unwrap();
+        let research = client.clone().with_usage_label("researcher");
+
+        let response = client
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.usage.unwrap().total_tokens, 42);
+        research
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+
+        let report = tracker.snapshot();
+        assert_eq!(report.total.requests, 2);
+        assert_eq!(report.total.total_tokens, 84);
+        assert_eq!(report.by_model["gpt-4o-mini"].prompt_tokens, 80);
+        assert_eq!(report.by_label.len(), 1);
+        assert_eq!(report.by_label["researcher"].total_tokens, 42);
+        let expected = 2.0 * (40.0 * 0.15 + 2.0 * 0.60) / 1e6;
+        assert!((report.estimated_cost_usd().unwrap() - expected).abs() < 1e-12);
+
+        tracker.reset();
+        assert_eq!(tracker.snapshot().total.requests, 0);
+    }
 }
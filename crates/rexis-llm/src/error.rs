@@ -78,6 +78,10 @@ pub enum RsllmError {
     #[error("Operation timed out after {timeout_ms}ms: {operation}")]
     Timeout { operation: String, timeout_ms: u64 },
 
+    /// Request aborted through its cancellation token
+    #[error("Operation cancelled: {operation}")]
+    Cancelled { operation: String },
+
     /// Validation errors
     #[error("Validation error: {field} - {message}")]
     Validation { field: String, message: String },
@@ -97,6 +101,38 @@ pub enum RsllmError {
         #[source]
         source: Option<Box<dyn std::error::Error + Send + Sync>>,
     },
+
+    /// The provider refused or withheld content for safety reasons
+    #[error("Content blocked by {provider}: {reason}")]
+    ContentFiltered { provider: String, reason: String },
+
+    /// The provider or model cannot handle part of the request
+    #[error("{provider} does not support {feature}")]
+    Unsupported { provider: String, feature: String },
+
+    /// A retryable error persisted after all retry attempts
+    #[error("Request failed after {attempts} attempts: {source}")]
+    RetriesExhausted {
+        attempts: u32,
+        #[source]
+        source: Box<RsllmError>,
+    },
+
+    /// A non-retryable error ended a request that had already been retried
+    #[error("Request failed after {attempts} attempts: {source}")]
+    RetryAborted {
+        attempts: u32,
+        #[source]
+        source: Box<RsllmError>,
+    },
+
+    /// Tool call arguments that could not be assembled or parsed
+    #[error("Invalid arguments for tool call '{tool_call_id}' ({tool}): {message}")]
+    InvalidToolArguments {
+        tool_call_id: String,
+        tool: String,
+        message: String,
+    },
 }
 
 impl RsllmError {
@@ -215,6 +251,13 @@ impl RsllmError {
         }
     }
 
+    /// Create a cancellation error
+    pub fn cancelled(operation: impl Into<String>) -> Self {
+        Self::Cancelled {
+            operation: operation.into(),
+        }
+    }
+
     /// Create a validation error
     pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
         Self::Validation {
@@ -237,6 +280,65 @@ impl RsllmError {
         }
     }
 
+    /// Create an invalid tool arguments error
+    pub fn invalid_tool_arguments(
+        tool_call_id: impl Into<String>,
+        tool: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::InvalidToolArguments {
+            tool_call_id: tool_call_id.into(),
+            tool: tool.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Create a content filtered error
+    pub fn content_filtered(provider: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::ContentFiltered {
+            provider: provider.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Create an unsupported feature error
+    pub fn unsupported(provider: impl Into<String>, feature: impl Into<String>) -> Self {
+        Self::Unsupported {
+            provider: provider.into(),
+            feature: feature.into(),
+        }
+    }
+
+    /// Create a retries exhausted error
+    pub fn retries_exhausted(attempts: u32, source: RsllmError) -> Self {
+        Self::RetriesExhausted {
+            attempts,
+            source: Box::new(source),
+        }
+    }
+
+    /// Create an error for a non-retryable failure after retries
+    pub fn retry_aborted(attempts: u32, source: RsllmError) -> Self {
+        Self::RetryAborted {
+            attempts,
+            source: Box::new(source),
+        }
+    }
+
+    /// HTTP status code associated with the error, if any
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            Self::Network { status_code, .. } => *status_code,
+            Self::Api { code, .. } => code.parse().ok(),
+            Self::RateLimit { .. } => Some(429),
+            Self::Authentication { .. } => Some(401),
+            Self::RetriesExhausted { source, .. } | Self::RetryAborted { source, .. } => {
+                source.status_code()
+            }
+            _ => None,
+        }
+    }
+
     /// Get error category for metrics/logging
     pub fn category(&self) -> &'static str {
         match self {
@@ -249,10 +351,16 @@ impl RsllmError {
             Self::Serialization { .. } => "serialization",
             Self::Streaming { .. } => "streaming",
             Self::Timeout { .. } => "timeout",
+            Self::Cancelled { .. } => "cancelled",
             Self::Validation { .. } => "validation",
             Self::NotFound { .. } => "not_found",
             Self::InvalidState { .. } => "invalid_state",
             Self::Tool { .. } => "tool",
+            Self::ContentFiltered { .. } => "content_filter",
+            Self::Unsupported { .. } => "unsupported",
+            Self::RetriesExhausted { .. } => "retries_exhausted",
+            Self::RetryAborted { source, .. } => source.category(),
+            Self::InvalidToolArguments { .. } => "tool",
         }
     }
 
@@ -263,7 +371,7 @@ impl RsllmError {
             Self::RateLimit { .. } => true,
             Self::Timeout { .. } => true,
             Self::Provider { .. } => false, // Depends on specific provider error
-            Self::Api { .. } => false,      // Depends on specific API error
+            Self::Api { .. } => matches!(self.status_code(), Some(429 | 500 | 502 | 503 | 504)),
             _ => false,
         }
     }
@@ -292,7 +400,13 @@ impl From<url::ParseError> for RsllmError {
     }
 }
 
-#[cfg(feature = "openai")]
+#[cfg(any(
+    feature = "openai",
+    feature = "claude",
+    feature = "ollama",
+    feature = "gemini",
+    feature = "bedrock"
+))]
 impl From<reqwest::Error> for RsllmError {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
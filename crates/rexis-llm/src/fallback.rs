@@ -0,0 +1,617 @@
+//! # Provider Fallback
+//!
+//! A [`FallbackClient`] tries an ordered chain of clients, moving to the next one
+//! when a request fails with a retryable error after the current client's own
+//! retry policy is exhausted. A per-client circuit breaker skips providers that
+//! keep failing so an outage does not add its timeout to every request.
+
+use crate::client::Client;
+use crate::options::RequestOptions;
+use crate::params::GenerationParams;
+use crate::provider::{LLMProvider, Provider};
+use crate::streaming::{ChatStream, ToolAwareStream};
+use crate::tools::ToolDefinition;
+use crate::{ChatMessage, ChatResponse, RsllmError, RsllmResult, StreamChunk};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Consecutive failures that open a client's circuit by default
+const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+/// How long an open circuit skips its client by default
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Failure tracking for one client in the chain
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// A client in the chain with its circuit state
+struct Member {
+    client: Client,
+    breaker: Mutex<Breaker>,
+}
+
+/// Client that falls back through an ordered chain of clients
+///
+/// Responses record the provider that served them in the `served_by` and
+/// `fallback_index` metadata entries. Clients whose circuit is open are only
+/// tried after every other client has failed.
+#[derive(Clone)]
+pub struct FallbackClient {
+    members: Arc<Vec<Member>>,
+    require_tool_support: bool,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl FallbackClient {
+    /// Create a chain that tries `clients` in order
+    pub fn new(clients: Vec<Client>) -> Self {
+        Self {
+            members: Arc::new(
+                clients
+                    .into_iter()
+                    .map(|client| Member {
+                        client,
+                        breaker: Mutex::new(Breaker::default()),
+                    })
+                    .collect(),
+            ),
+            require_tool_support: false,
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+
+    /// Skip clients that cannot call tools when a request carries tools
+    ///
+    /// A client qualifies when tools are enabled in its configuration and its
+    /// model is not known to lack tool support.
+    pub fn require_tool_support(mut self, require: bool) -> Self {
+        self.require_tool_support = require;
+        self
+    }
+
+    /// Skip a client for `cooldown` after `failure_threshold` consecutive failures
+    pub fn circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Clients in fallback order
+    pub fn clients(&self) -> impl Iterator<Item = &Client> {
+        self.members.iter().map(|member| &member.client)
+    }
+
+    /// Whether a client's circuit is currently open
+    pub fn is_circuit_open(&self, index: usize) -> bool {
+        self.members.get(index).is_some_and(Self::open)
+    }
+
+    /// Wrap the chain in a [`Client`] so it can be used wherever one is expected
+    ///
+    /// Each client in the chain keeps its own model, retries, rate limits and
+    /// usage tracking; the wrapper adds none of its own.
+    pub fn into_client(self) -> Client {
+        let mut config = self
+            .members
+            .first()
+            .map(|member| member.client.config().clone())
+            .unwrap_or_default();
+        config.retry.max_retries = 0;
+        config.rate_limit = None;
+
+        Client::with_provider(config, Arc::new(self))
+    }
+
+    /// Chat completion (non-streaming)
+    pub async fn chat_completion(&self, messages: Vec<ChatMessage>) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_options(messages, None, None)
+            .await
+    }
+
+    /// Chat completion with custom sampling options
+    ///
+    /// Every client uses its own configured model.
+    pub async fn chat_completion_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.attempt("chat_completion", false, |client| {
+            client.chat_completion_with_options(messages.clone(), None, temperature, max_tokens)
+        })
+        .await
+        .map(|(response, index)| self.annotate(response, index))
+    }
+
+    /// Chat completion with tool calling support
+    pub async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_tools_and_options(messages, tools, None, None)
+            .await
+    }
+
+    /// Chat completion with tools and custom sampling options
+    pub async fn chat_completion_with_tools_and_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.attempt("chat_completion_with_tools", !tools.is_empty(), |client| {
+            client.chat_completion_with_tools_and_options(
+                messages.clone(),
+                tools.clone(),
+                None,
+                temperature,
+                max_tokens,
+            )
+        })
+        .await
+        .map(|(response, index)| self.annotate(response, index))
+    }
+
+    /// Chat completion (streaming)
+    ///
+    /// Falls back only while opening the stream; errors mid-stream are returned
+    /// to the caller.
+    pub async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> RsllmResult<ChatStream> {
+        self.attempt("chat_completion_stream", false, |client| {
+            client.chat_completion_stream(messages.clone())
+        })
+        .await
+        .map(|(stream, _)| stream)
+    }
+
+    /// Streaming chat completion with tool calling support
+    ///
+    /// Falls back only while opening the stream; errors mid-stream are returned
+    /// to the caller.
+    pub async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+    ) -> RsllmResult<ToolAwareStream> {
+        self.attempt(
+            "chat_completion_with_tools_stream",
+            !tools.is_empty(),
+            |client| client.chat_completion_with_tools_stream(messages.clone(), tools.clone()),
+        )
+        .await
+        .map(|(stream, _)| stream)
+    }
+
+    /// Run `call` against each eligible client until one succeeds
+    ///
+    /// Returns the result with the index of the client that produced it.
+    async fn attempt<'a, T, F, Fut>(
+        &'a self,
+        operation: &str,
+        needs_tools: bool,
+        mut call: F,
+    ) -> RsllmResult<(T, usize)>
+    where
+        F: FnMut(&'a Client) -> Fut,
+        Fut: std::future::Future<Output = RsllmResult<T>>,
+    {
+        let eligible: Vec<usize> = (0..self.members.len())
+            .filter(|&index| {
+                !(needs_tools && self.require_tool_support)
+                    || Self::supports_tools(&self.members[index].client)
+            })
+            .collect();
+
+        if self.members.is_empty() {
+            return Err(RsllmError::configuration("Fallback chain has no clients"));
+        }
+        if eligible.is_empty() {
+            return Err(RsllmError::unsupported(
+                "Fallback chain",
+                "tool calling with any configured client",
+            ));
+        }
+
+        // Clients with an open circuit are kept as a last resort
+        let (closed, open): (Vec<usize>, Vec<usize>) = eligible
+            .into_iter()
+            .partition(|&index| !Self::open(&self.members[index]));
+
+        let mut last_error = None;
+        for index in closed.into_iter().chain(open) {
+            let member = &self.members[index];
+            match call(&member.client).await {
+                Ok(value) => {
+                    member.breaker.lock().unwrap().consecutive_failures = 0;
+                    if index > 0 {
+                        tracing::info!(
+                            operation,
+                            index,
+                            provider = member.client.provider().name(),
+                            "LLM request served by fallback"
+                        );
+                    }
+                    return Ok((value, index));
+                }
+                Err(error) if Self::should_fall_back(&error) => {
+                    self.record_failure(member);
+                    tracing::warn!(
+                        operation,
+                        index,
+                        provider = member.client.provider().name(),
+                        error = %error,
+                        "LLM request failed, trying next provider"
+                    );
+                    last_error = Some(error);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(last_error.expect("at least one client was tried"))
+    }
+
+    /// Record which client served a response
+    fn annotate(&self, response: ChatResponse, index: usize) -> ChatResponse {
+        let provider = self.members[index].client.provider().name().to_string();
+        response
+            .with_metadata("served_by", provider.into())
+            .with_metadata("fallback_index", index.into())
+    }
+
+    fn record_failure(&self, member: &Member) {
+        let mut breaker = member.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= self.failure_threshold {
+            breaker.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    fn open(member: &Member) -> bool {
+        member
+            .breaker
+            .lock()
+            .unwrap()
+            .open_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    fn supports_tools(client: &Client) -> bool {
+        client.config().provider.tools_enabled
+            && crate::models::supports_tools(&client.config().model.model) != Some(false)
+    }
+
+    /// Outages and overload move on to the next client; request errors do not
+    fn should_fall_back(error: &RsllmError) -> bool {
+        matches!(error, RsllmError::RetriesExhausted { .. }) || error.is_retryable()
+    }
+}
+
+impl Client {
+    /// Chain this client with fallbacks tried in order when it fails
+    pub fn with_fallbacks(self, fallbacks: Vec<Client>) -> FallbackClient {
+        FallbackClient::new(std::iter::once(self).chain(fallbacks).collect())
+    }
+}
+
+/// Lets a [`Client`] be built around the chain (see [`FallbackClient::into_client`])
+///
+/// The `model` argument is ignored: every client uses its own configured model.
+#[async_trait]
+impl LLMProvider for FallbackClient {
+    fn name(&self) -> &str {
+        "Fallback"
+    }
+
+    fn provider_type(&self) -> Provider {
+        self.members
+            .first()
+            .map(|member| member.client.provider().provider_type())
+            .unwrap_or(Provider::OpenAI)
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.clients()
+            .flat_map(|client| client.supported_models())
+            .collect()
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        for client in self.clients() {
+            if client.health_check().await.unwrap_or(false) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        _model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_options(messages, temperature, max_tokens)
+            .await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        _model: Option<String>,
+        _temperature: Option<f32>,
+        _max_tokens: Option<u32>,
+    ) -> RsllmResult<Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>>
+    {
+        let stream = FallbackClient::chat_completion_stream(self, messages).await?;
+        Ok(Box::new(stream))
+    }
+
+    async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        _model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_tools_and_options(messages, tools, temperature, max_tokens)
+            .await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        _model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let operation = if tools.is_empty() {
+            "chat_completion"
+        } else {
+            "chat_completion_with_tools"
+        };
+        self.attempt(operation, !tools.is_empty(), |client| {
+            client.chat_completion_with_tools_with(
+                messages.clone(),
+                tools.clone(),
+                RequestOptions::new().with_params(params.clone()),
+            )
+        })
+        .await
+        .map(|(response, index)| self.annotate(response, index))
+    }
+
+    async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        _model: Option<String>,
+        _temperature: Option<f32>,
+        _max_tokens: Option<u32>,
+    ) -> RsllmResult<ToolAwareStream> {
+        FallbackClient::chat_completion_with_tools_stream(self, messages, tools).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientConfig;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider that answers every request with a fixed result
+    struct StubProvider {
+        name: &'static str,
+        fail_with: Option<u16>,
+        calls: AtomicUsize,
+    }
+
+    impl StubProvider {
+        fn ok(name: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                fail_with: None,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn failing(name: &'static str, status: u16) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                fail_with: Some(status),
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for StubProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn provider_type(&self) -> Provider {
+            Provider::OpenAI
+        }
+
+        fn supported_models(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        async fn health_check(&self) -> RsllmResult<bool> {
+            Ok(self.fail_with.is_none())
+        }
+
+        async fn chat_completion(
+            &self,
+            _messages: Vec<ChatMessage>,
+            model: Option<&str>,
+            _temperature: Option<f32>,
+            _max_tokens: Option<u32>,
+        ) -> RsllmResult<ChatResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.fail_with {
+                Some(status) => Err(RsllmError::api(
+                    self.name,
+                    "Service Unavailable",
+                    status.to_string(),
+                )),
+                None => Ok(ChatResponse::new(
+                    format!("from {}", self.name),
+                    model.unwrap_or("stub"),
+                )),
+            }
+        }
+
+        async fn chat_completion_stream(
+            &self,
+            _messages: Vec<ChatMessage>,
+            _model: Option<String>,
+            _temperature: Option<f32>,
+            _max_tokens: Option<u32>,
+        ) -> RsllmResult<
+            Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>,
+        > {
+            Ok(Box::new(futures_util::stream::empty()))
+        }
+    }
+
+    fn client(provider: Arc<StubProvider>, model: &str) -> Client {
+        let mut config = ClientConfig::default();
+        config.retry.max_retries = 0;
+        config.model.model = model.to_string();
+        Client::with_provider(config, provider)
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_outage() {
+        let primary = StubProvider::failing("Primary", 503);
+        let backup = StubProvider::ok("Backup");
+        let chain = client(primary.clone(), "gpt-4o")
+            .with_fallbacks(vec![client(backup.clone(), "claude-3-5-haiku-20241022")]);
+
+        let response = chain
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "from Backup");
+        assert_eq!(response.model, "claude-3-5-haiku-20241022");
+        assert_eq!(response.metadata["served_by"], "Backup");
+        assert_eq!(response.metadata["fallback_index"], 1);
+        assert_eq!(primary.calls(), 1);
+        assert_eq!(backup.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_errors_do_not_fall_back() {
+        let primary = StubProvider::failing("Primary", 400);
+        let backup = StubProvider::ok("Backup");
+        let chain = FallbackClient::new(vec![
+            client(primary.clone(), "gpt-4o"),
+            client(backup.clone(), "gpt-4o"),
+        ]);
+
+        let err = chain
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status_code(), Some(400));
+        assert_eq!(backup.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_skips_failing_provider() {
+        let primary = StubProvider::failing("Primary", 503);
+        let backup = StubProvider::ok("Backup");
+        let chain = FallbackClient::new(vec![
+            client(primary.clone(), "gpt-4o"),
+            client(backup.clone(), "gpt-4o"),
+        ])
+        .circuit_breaker(2, Duration::from_secs(60));
+
+        for _ in 0..4 {
+            let response = chain
+                .chat_completion(vec![ChatMessage::user("hi")])
+                .await
+                .unwrap();
+            assert_eq!(response.content, "from Backup");
+        }
+
+        // Two failures opened the circuit; later requests go straight to the backup
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(backup.calls(), 4);
+        assert!(chain.is_circuit_open(0));
+        assert!(!chain.is_circuit_open(1));
+    }
+
+    #[tokio::test]
+    async fn test_tool_parity_skips_clients_without_tools() {
+        let primary = StubProvider::failing("Primary", 503);
+        let no_tools = StubProvider::ok("NoTools");
+        let backup = StubProvider::ok("Backup");
+        let chain = FallbackClient::new(vec![
+            client(primary.clone(), "gpt-4o"),
+            client(no_tools.clone(), "o1-mini"),
+            client(backup.clone(), "claude-3-5-sonnet-20241022"),
+        ])
+        .require_tool_support(true);
+
+        let tools = vec![ToolDefinition::new(
+            "lookup",
+            "Look something up",
+            serde_json::json!({"type": "object"}),
+        )];
+        let response = chain
+            .chat_completion_with_tools(vec![ChatMessage::user("hi")], tools)
+            .await
+            .unwrap();
+
+        assert_eq!(response.metadata["served_by"], "Backup");
+        assert_eq!(no_tools.calls(), 0);
+
+        // Without tools every client is eligible
+        let response = chain
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.metadata["served_by"], "NoTools");
+    }
+
+    #[tokio::test]
+    async fn test_into_client_serves_through_chain() {
+        let primary = StubProvider::failing("Primary", 503);
+        let backup = StubProvider::ok("Backup");
+        let client = client(primary, "gpt-4o")
+            .with_fallbacks(vec![client(backup, "claude-3-5-haiku-20241022")])
+            .into_client();
+
+        let response = client
+            .chat_completion(vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+
+        assert_eq!(response.model, "claude-3-5-haiku-20241022");
+        assert_eq!(response.metadata["served_by"], "Backup");
+    }
+}
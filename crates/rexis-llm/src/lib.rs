@@ -90,27 +90,65 @@
 //! ```
 
 // Core modules
+pub mod balance;
+pub mod batch;
+pub mod cache;
 pub mod client;
 pub mod config;
 pub mod error;
+pub mod fallback;
 pub mod message;
+pub mod middleware;
+pub mod models;
+pub mod options;
+pub mod params;
+pub mod pricing;
 pub mod provider;
+pub mod rate_limit;
 pub mod response;
 pub mod streaming;
+pub mod structured;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tokens;
 pub mod tools;
+pub mod usage;
 
 // Re-export proc macros
 #[cfg(feature = "macros")]
 pub use rexis_macros::{arg, context, tool};
 
 // Re-exports for convenience
+pub use balance::{BalanceStrategy, KeyStats, LoadBalancedClient};
+pub use batch::{BatchChatRequest, BatchHandle, BatchResult, BatchState, BatchStatus};
+pub use cache::{CacheStats, CacheStore, InMemoryCacheStore, ResponseCache};
 pub use client::{Client, ClientBuilder};
-pub use config::{ClientConfig, ModelConfig};
+pub use config::{
+    AzureOpenAIConfig, BedrockConfig, ClientConfig, ModelConfig, ProxyConfig, RetryPolicy,
+    RetryableClasses,
+};
 pub use error::{RsllmError, RsllmResult};
-pub use message::{ChatMessage, MessageContent, MessageRole, ToolCall};
+pub use fallback::FallbackClient;
+pub use message::{ChatMessage, ImageSource, MessageContent, MessageRole, ToolCall};
+pub use middleware::{
+    ClientMiddleware, RedactionMiddleware, RequestContext, ResponseContext, TracingMiddleware,
+};
+pub use models::ModelInfo;
+pub use options::{CancellationToken, RequestOptions};
+pub use params::{GenerationParams, LogprobOptions};
+pub use pricing::{ModelPricing, PricingTable};
 pub use provider::{LLMProvider, Provider, ProviderConfig};
-pub use response::{ChatResponse, CompletionResponse, EmbeddingResponse, StreamChunk, Usage};
-pub use streaming::{ChatStream, CompletionStream};
+pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use response::{
+    ChatResponse, CompletionResponse, EmbeddingResponse, StreamChunk, TokenLogprob, ToolCallDelta,
+    ToolFunctionDelta, TopLogprob, Usage,
+};
+pub use streaming::{
+    ChatStream, CompletionStream, ToolAwareDelta, ToolAwareStream, ToolCallAccumulator,
+};
+pub use structured::{OutputSchema, StructuredOptions, StructuredResponse};
+pub use tokens::{fit_messages, HeuristicTokenCounter, TokenCounter};
+pub use usage::{UsageReport, UsageTotals, UsageTracker};
 
 /// Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -104,6 +104,13 @@ impl MessageContent {
         }
     }
 
+    /// Check if the content carries any image attachments
+    pub fn has_images(&self) -> bool {
+        self.attachments()
+            .iter()
+            .any(|attachment| attachment.attachment_type == AttachmentType::Image)
+    }
+
     /// Check if content is empty
     pub fn is_empty(&self) -> bool {
         match self {
@@ -127,6 +134,27 @@ impl From<&str> for MessageContent {
     }
 }
 
+/// Source of an image in a multi-modal message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageSource {
+    /// Publicly reachable image URL
+    Url(String),
+
+    /// Inline base64-encoded image data
+    Base64 { media_type: String, data: String },
+}
+
+impl From<ImageSource> for ContentAttachment {
+    fn from(source: ImageSource) -> Self {
+        match source {
+            ImageSource::Url(url) => ContentAttachment::image_url(url),
+            ImageSource::Base64 { media_type, data } => {
+                ContentAttachment::image_base64(media_type, data)
+            }
+        }
+    }
+}
+
 /// Attachment within message content
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentAttachment {
@@ -197,9 +225,29 @@ pub enum AttachmentContent {
     /// URL reference
     Url { url: String },
 
-    /// Raw bytes (for internal use)
-    #[serde(skip)]
-    Bytes { mime_type: String, data: Vec<u8> },
+    /// Raw bytes, serialized as base64
+    Bytes {
+        mime_type: String,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+}
+
+/// Serialize raw attachment bytes as a base64 string
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 /// A chat message in a conversation
@@ -226,6 +274,13 @@ pub struct ChatMessage {
     /// Message timestamp
     #[serde(with = "chrono::serde::ts_seconds_option")]
     pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Mark this message as a prompt-cache breakpoint
+    ///
+    /// Providers with explicit prompt caching (Anthropic) cache the prompt up to and
+    /// including this message; other providers ignore it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache: bool,
 }
 
 impl ChatMessage {
@@ -239,6 +294,7 @@ impl ChatMessage {
             tool_call_id: None,
             metadata: HashMap::new(),
             timestamp: Some(chrono::Utc::now()),
+            cache: false,
         }
     }
 
@@ -247,11 +303,21 @@ impl ChatMessage {
         Self::new(MessageRole::System, content)
     }
 
+    /// Create a system message marked for prompt caching
+    pub fn system_cached(content: impl Into<MessageContent>) -> Self {
+        Self::system(content).with_cache(true)
+    }
+
     /// Create a user message
     pub fn user(content: impl Into<MessageContent>) -> Self {
         Self::new(MessageRole::User, content)
     }
 
+    /// Create a user message with text and an image
+    pub fn user_with_image(text: impl Into<String>, image: ImageSource) -> Self {
+        Self::user(MessageContent::multi_modal(text).with_attachment(image.into()))
+    }
+
     /// Create an assistant message
     pub fn assistant(content: impl Into<MessageContent>) -> Self {
         Self::new(MessageRole::Assistant, content)
@@ -267,6 +333,7 @@ impl ChatMessage {
             tool_call_id: Some(tool_call_id.into()),
             metadata: HashMap::new(),
             timestamp: Some(chrono::Utc::now()),
+            cache: false,
         }
     }
 
@@ -282,6 +349,12 @@ impl ChatMessage {
         self
     }
 
+    /// Mark or unmark the message as a prompt-cache breakpoint
+    pub fn with_cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
+
     /// Add metadata to the message
     pub fn with_metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.metadata.insert(key.into(), value);
@@ -371,6 +444,7 @@ impl MessageBuilder {
                 tool_call_id: None,
                 metadata: HashMap::new(),
                 timestamp: Some(chrono::Utc::now()),
+                cache: false,
             },
         }
     }
@@ -399,6 +473,12 @@ impl MessageBuilder {
         self
     }
 
+    /// Mark as a prompt-cache breakpoint
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.message.cache = cache;
+        self
+    }
+
     /// Add metadata
     pub fn metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.message.metadata.insert(key.into(), value);
@@ -443,6 +523,60 @@ mod tests {
         assert!(msg.metadata.contains_key("source"));
     }
 
+    #[test]
+    fn test_cache_flag_serialization() {
+        let plain = serde_json::to_value(ChatMessage::system("rules")).unwrap();
+        assert!(plain.get("cache").is_none());
+
+        let cached = ChatMessage::system_cached("rules");
+        assert!(cached.cache);
+        let value = serde_json::to_value(&cached).unwrap();
+        assert_eq!(value["cache"], true);
+
+        let round_trip: ChatMessage = serde_json::from_value(value).unwrap();
+        assert!(round_trip.cache);
+    }
+
+    #[test]
+    fn test_user_with_image() {
+        let msg = ChatMessage::user_with_image(
+            "What is in this picture?",
+            ImageSource::Url("https://example.com/cat.png".to_string()),
+        );
+
+        assert_eq!(msg.role, MessageRole::User);
+        assert_eq!(msg.text(), Some("What is in this picture?"));
+        assert!(msg.content.has_images());
+        assert!(matches!(
+            &msg.content.attachments()[0].content,
+            AttachmentContent::Url { url } if url == "https://example.com/cat.png"
+        ));
+    }
+
+    #[test]
+    fn test_bytes_attachment_round_trip() {
+        let content = MessageContent::multi_modal("raw").with_attachment(ContentAttachment {
+            attachment_type: AttachmentType::Image,
+            content: AttachmentContent::Bytes {
+                mime_type: "image/png".to_string(),
+                data: vec![0x89, 0x50, 0x4e, 0x47],
+            },
+            metadata: None,
+        });
+
+        let value = serde_json::to_value(ChatMessage::user(content)).unwrap();
+        assert_eq!(
+            value["content"]["attachments"][0]["content"]["type"],
+            "bytes"
+        );
+
+        let restored: ChatMessage = serde_json::from_value(value).unwrap();
+        assert!(matches!(
+            &restored.content.attachments()[0].content,
+            AttachmentContent::Bytes { data, .. } if data == &[0x89, 0x50, 0x4e, 0x47]
+        ));
+    }
+
     #[test]
     fn test_multi_modal_content() {
         let content = MessageContent::multi_modal("Check this image").with_attachment(
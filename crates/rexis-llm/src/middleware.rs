@@ -0,0 +1,306 @@
+//! # Client Middleware
+//!
+//! Hooks that run around every chat completion a [`Client`](crate::Client)
+//! sends, with and without tools. Middleware can observe and adjust the
+//! request (messages, parameters, headers) before it is sent and observe the
+//! outcome — response or error — afterwards.
+//!
+//! Middleware is installed as an ordered chain with
+//! [`Client::with_middleware`](crate::Client::with_middleware): request hooks
+//! run in installation order and response hooks in reverse, so the first
+//! middleware installed wraps all the others.
+
+use crate::params::GenerationParams;
+use crate::tools::ToolDefinition;
+use crate::{ChatMessage, ChatResponse, MessageContent, RsllmError, Usage};
+use async_trait::async_trait;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A request about to be sent to the provider
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// Client operation (`chat_completion` or `chat_completion_with_tools`)
+    pub operation: &'static str,
+
+    /// Provider name
+    pub provider: String,
+
+    /// Model the request is sent to
+    pub model: String,
+
+    /// Conversation sent to the model
+    pub messages: Vec<ChatMessage>,
+
+    /// Tools offered to the model
+    pub tools: Vec<ToolDefinition>,
+
+    /// Generation parameters, with the client's defaults applied
+    pub params: GenerationParams,
+
+    /// Values shared between the hooks of one request
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+impl RequestContext {
+    /// Add an HTTP header sent with the request
+    pub fn insert_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.params.headers.insert(name.into(), value.into());
+    }
+}
+
+/// Outcome of a request
+#[derive(Debug)]
+pub struct ResponseContext<'a> {
+    /// Time from the first request hook to the outcome, including retries
+    pub latency: Duration,
+
+    /// Response or error
+    pub result: Result<&'a ChatResponse, &'a RsllmError>,
+}
+
+impl ResponseContext<'_> {
+    /// Response, if the request succeeded
+    pub fn response(&self) -> Option<&ChatResponse> {
+        self.result.ok()
+    }
+
+    /// Error, if the request failed
+    pub fn error(&self) -> Option<&RsllmError> {
+        self.result.err()
+    }
+
+    /// Token usage reported with the response
+    pub fn usage(&self) -> Option<&Usage> {
+        self.response().and_then(|response| response.usage.as_ref())
+    }
+}
+
+/// Hooks around the requests a client sends
+#[async_trait]
+pub trait ClientMiddleware: Send + Sync {
+    /// Inspect or adjust a request before it is sent
+    async fn on_request(&self, request: &mut RequestContext) {
+        let _ = request;
+    }
+
+    /// Observe the outcome of a request, successful or not
+    async fn on_response(&self, request: &RequestContext, response: &ResponseContext<'_>) {
+        let _ = (request, response);
+    }
+}
+
+/// Middleware that records every request as a structured tracing span
+///
+/// Spans are named `llm_request` and carry the operation, provider, model,
+/// latency, token usage and error. Prompt and response text are only recorded
+/// when enabled with [`with_content`](Self::with_content); wrap the middleware
+/// in a [`RedactionMiddleware`] to mask sensitive values first.
+#[derive(Debug, Clone, Default)]
+pub struct TracingMiddleware {
+    include_content: bool,
+}
+
+impl TracingMiddleware {
+    /// Create a tracing middleware that records metadata only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also record prompt and response text
+    pub fn with_content(mut self, include_content: bool) -> Self {
+        self.include_content = include_content;
+        self
+    }
+}
+
+#[async_trait]
+impl ClientMiddleware for TracingMiddleware {
+    async fn on_response(&self, request: &RequestContext, response: &ResponseContext<'_>) {
+        let usage = response.usage();
+        let span = tracing::info_span!(
+            "llm_request",
+            operation = request.operation,
+            provider = %request.provider,
+            model = %request.model,
+            messages = request.messages.len(),
+            tools = request.tools.len(),
+            latency_ms = response.latency.as_millis() as u64,
+            prompt_tokens = usage.map(|usage| usage.prompt_tokens),
+            completion_tokens = usage.map(|usage| usage.completion_tokens),
+            error = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+
+        match response.result {
+            Ok(chat) if self.include_content => {
+                let prompt = serde_json::to_string(&request.messages).unwrap_or_default();
+                tracing::info!(prompt = %prompt, response = %chat.content, "LLM request completed");
+            }
+            Ok(_) => tracing::info!("LLM request completed"),
+            Err(error) => {
+                span.record("error", tracing::field::display(error));
+                tracing::warn!(category = error.category(), "LLM request failed");
+            }
+        }
+    }
+}
+
+/// Middleware that masks sensitive text before another middleware sees it
+///
+/// The wrapped middleware receives copies of the request and response with
+/// every match of the configured patterns replaced; the request actually sent
+/// is unchanged, and changes the wrapped middleware makes to its copy are
+/// discarded.
+pub struct RedactionMiddleware {
+    inner: Arc<dyn ClientMiddleware>,
+    patterns: Vec<Regex>,
+    replacement: String,
+}
+
+impl RedactionMiddleware {
+    /// Wrap `inner`, masking matches of `patterns` with `[REDACTED]`
+    pub fn new(inner: Arc<dyn ClientMiddleware>, patterns: Vec<Regex>) -> Self {
+        Self {
+            inner,
+            patterns,
+            replacement: "[REDACTED]".to_string(),
+        }
+    }
+
+    /// Set the replacement text
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Mask every pattern match in `text`
+    pub fn redact(&self, text: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(text.to_string(), |text, pattern| {
+                pattern
+                    .replace_all(&text, self.replacement.as_str())
+                    .into_owned()
+            })
+    }
+
+    fn redact_request(&self, request: &RequestContext) -> RequestContext {
+        let mut redacted = request.clone();
+        for message in &mut redacted.messages {
+            match &mut message.content {
+                MessageContent::Text(text) => *text = self.redact(text),
+                MessageContent::MultiModal {
+                    text: Some(text), ..
+                } => *text = self.redact(text),
+                MessageContent::MultiModal { text: None, .. } => {}
+            }
+        }
+        redacted
+    }
+}
+
+#[async_trait]
+impl ClientMiddleware for RedactionMiddleware {
+    async fn on_request(&self, request: &mut RequestContext) {
+        let mut redacted = self.redact_request(request);
+        self.inner.on_request(&mut redacted).await;
+    }
+
+    async fn on_response(&self, request: &RequestContext, response: &ResponseContext<'_>) {
+        let request = self.redact_request(request);
+        match response.result {
+            Ok(chat) => {
+                let mut chat = chat.clone();
+                chat.content = self.redact(&chat.content);
+                let redacted = ResponseContext {
+                    latency: response.latency,
+                    result: Ok(&chat),
+                };
+                self.inner.on_response(&request, &redacted).await;
+            }
+            Err(_) => self.inner.on_response(&request, response).await,
+        }
+    }
+}
+
+impl std::fmt::Debug for RedactionMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedactionMiddleware")
+            .field("patterns", &self.patterns)
+            .field("replacement", &self.replacement)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Middleware that records the text it is shown
+    #[derive(Default)]
+    struct Capture {
+        seen: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ClientMiddleware for Capture {
+        async fn on_request(&self, request: &mut RequestContext) {
+            let text = request.messages[0].text().unwrap_or_default().to_string();
+            self.seen.lock().unwrap().push(text);
+            request.messages.clear();
+        }
+
+        async fn on_response(&self, _request: &RequestContext, response: &ResponseContext<'_>) {
+            let text = response.response().unwrap().content.clone();
+            self.seen.lock().unwrap().push(text);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redaction_masks_copies_only() {
+        let capture = Arc::new(Capture::default());
+        let redaction = RedactionMiddleware::new(
+            capture.clone(),
+            vec![
+                Regex::new(r"sk-[A-Za-z0-9]+").unwrap(),
+                Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap(),
+            ],
+        );
+
+        let mut request = RequestContext {
+            operation: "chat_completion",
+            provider: "OpenAI".to_string(),
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatMessage::user("key sk-abc123, ssn 123-45-6789")],
+            tools: Vec::new(),
+            params: GenerationParams::default(),
+            metadata: HashMap::new(),
+        };
+        redaction.on_request(&mut request).await;
+
+        let response = ChatResponse::new("Stored sk-abc123", "gpt-4o");
+        redaction
+            .on_response(
+                &request,
+                &ResponseContext {
+                    latency: Duration::from_millis(5),
+                    result: Ok(&response),
+                },
+            )
+            .await;
+
+        assert_eq!(
+            *capture.seen.lock().unwrap(),
+            vec!["key [REDACTED], ssn [REDACTED]", "Stored [REDACTED]"]
+        );
+        // The request that is sent keeps its content
+        assert_eq!(
+            request.messages[0].text(),
+            Some("key sk-abc123, ssn 123-45-6789")
+        );
+    }
+}
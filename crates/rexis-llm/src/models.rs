@@ -0,0 +1,169 @@
+//! # Model Discovery
+//!
+//! Models reported by a provider, annotated with best-effort capability flags
+//! from a built-in registry. Flags are `None` when the registry does not know
+//! the model.
+
+use crate::provider::Provider;
+use serde::{Deserialize, Serialize};
+
+/// A model available from a provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// Model identifier as accepted in requests
+    pub id: String,
+
+    /// Provider serving the model
+    pub provider: Provider,
+
+    /// Owner reported by the provider, if any
+    pub owned_by: Option<String>,
+
+    /// Whether the model accepts tool definitions
+    pub supports_tools: Option<bool>,
+
+    /// Whether the model accepts image input
+    pub supports_vision: Option<bool>,
+
+    /// Context window in tokens
+    pub context_window: Option<usize>,
+}
+
+impl ModelInfo {
+    /// Describe a model, filling capability flags from the registry
+    pub fn new(id: impl Into<String>, provider: Provider) -> Self {
+        let id = id.into();
+        Self {
+            supports_tools: supports_tools(&id),
+            supports_vision: supports_vision(&id),
+            context_window: crate::tokens::context_window(&id),
+            id,
+            provider,
+            owned_by: None,
+        }
+    }
+
+    /// Set the owner reported by the provider
+    pub fn with_owned_by(mut self, owned_by: impl Into<String>) -> Self {
+        self.owned_by = Some(owned_by.into());
+        self
+    }
+}
+
+/// Whether a model accepts tool definitions, if known
+///
+/// Matches on the longest known prefix, like
+/// [`context_window`](crate::tokens::context_window).
+pub fn supports_tools(model: &str) -> Option<bool> {
+    const TOOLS: &[(&str, bool)] = &[
+        ("gpt-4", true),
+        ("gpt-3.5-turbo", true),
+        ("gpt-3.5-turbo-instruct", false),
+        ("o1", true),
+        ("o1-mini", false),
+        ("o1-preview", false),
+        ("o3", true),
+        ("text-embedding", false),
+        ("dall-e", false),
+        ("whisper", false),
+        ("tts", false),
+        ("claude", true),
+        ("anthropic.claude", true),
+        ("gemini", true),
+        ("llama-3", true),
+        ("llama3.1", true),
+        ("llama3.2", true),
+        ("llama3", false),
+        ("llama2", false),
+        ("llava", false),
+        ("gemma", false),
+        ("nomic-embed", false),
+        ("qwen2.5", true),
+        ("mistral", true),
+        ("open-mistral", true),
+        ("mixtral", true),
+        ("codestral", true),
+        ("pixtral", true),
+        ("command-r", true),
+    ];
+
+    longest_prefix(TOOLS, model)
+}
+
+/// Whether a model accepts image input, if known
+///
+/// Matches on the longest known prefix, like
+/// [`context_window`](crate::tokens::context_window).
+pub fn supports_vision(model: &str) -> Option<bool> {
+    const VISION: &[(&str, bool)] = &[
+        ("gpt-4o", true),
+        ("gpt-4.1", true),
+        ("gpt-4-turbo", true),
+        ("gpt-4-vision", true),
+        ("gpt-4", false),
+        ("gpt-4-0613", false),
+        ("gpt-4-32k", false),
+        ("gpt-3.5", false),
+        ("o1", true),
+        ("o1-mini", false),
+        ("o3-mini", false),
+        ("claude-3", true),
+        ("claude-2", false),
+        ("anthropic.claude-3", true),
+        ("gemini", true),
+        ("llava", true),
+        ("bakllava", true),
+        ("moondream", true),
+        ("llama3.2-vision", true),
+        ("llama3.2", false),
+        ("llama3.1", false),
+        ("llama-3.1", false),
+        ("llama-3.3", false),
+        ("pixtral", true),
+        ("mixtral", false),
+        ("codestral", false),
+        ("open-mistral", false),
+        ("mistral-large", false),
+    ];
+
+    longest_prefix(VISION, model)
+}
+
+fn longest_prefix(table: &[(&str, bool)], model: &str) -> Option<bool> {
+    table
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, supported)| *supported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_registry() {
+        assert_eq!(supports_vision("gpt-4o-2024-08-06"), Some(true));
+        assert_eq!(supports_vision("gpt-4-0613"), Some(false));
+        assert_eq!(supports_vision("o1-mini"), Some(false));
+        assert_eq!(supports_vision("llama3.2:3b"), Some(false));
+        assert_eq!(supports_vision("llama3.2-vision:11b"), Some(true));
+        assert_eq!(supports_vision("my-finetune"), None);
+
+        assert_eq!(supports_tools("gpt-4o-mini"), Some(true));
+        assert_eq!(supports_tools("text-embedding-3-small"), Some(false));
+        assert_eq!(supports_tools("llama3.1:8b"), Some(true));
+        assert_eq!(supports_tools("llama3:8b"), Some(false));
+    }
+
+    #[test]
+    fn test_model_info_from_registry() {
+        let info = ModelInfo::new("claude-3-5-sonnet-20241022", Provider::Claude)
+            .with_owned_by("anthropic");
+
+        assert_eq!(info.supports_tools, Some(true));
+        assert_eq!(info.supports_vision, Some(true));
+        assert_eq!(info.context_window, Some(200_000));
+        assert_eq!(info.owned_by.as_deref(), Some("anthropic"));
+    }
+}
@@ -0,0 +1,162 @@
+//! # Request Options
+//!
+//! Per-request controls that are not part of the prompt: generation
+//! parameters, a deadline for the whole request (including retries) and a
+//! cancellation token that aborts it promptly.
+
+use crate::params::GenerationParams;
+use crate::{RsllmError, RsllmResult};
+use futures_util::{Stream, StreamExt};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Options applied to a single request
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Deadline for the whole request, including retries and backoff
+    ///
+    /// Falls back to the client's default request timeout when unset.
+    pub timeout: Option<Duration>,
+
+    /// Token that aborts the request when cancelled
+    pub cancellation: Option<CancellationToken>,
+
+    /// Generation parameters; unset fields fall back to the client's model configuration
+    pub params: GenerationParams,
+}
+
+impl RequestOptions {
+    /// Create empty options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the request deadline
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the cancellation token
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Set the generation parameters
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Request token log probabilities with `top_k` alternatives per token
+    ///
+    /// Providers that cannot return logprobs fail with [`RsllmError::Unsupported`].
+    pub fn with_logprobs(mut self, top_k: u8) -> Self {
+        self.params.logprobs = Some(crate::params::LogprobOptions::new(top_k));
+        self
+    }
+
+    /// Run `future` under the deadline and cancellation token
+    pub(crate) async fn guard<T, F>(
+        &self,
+        operation: &str,
+        default_timeout: Option<Duration>,
+        future: F,
+    ) -> RsllmResult<T>
+    where
+        F: Future<Output = RsllmResult<T>>,
+    {
+        let timeout = self.timeout.or(default_timeout);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        tokio::select! {
+            biased;
+            _ = cancelled(self.cancellation.as_ref()) => Err(RsllmError::cancelled(operation)),
+            _ = expired(deadline) => Err(timeout_error(operation, timeout)),
+            result = future => result,
+        }
+    }
+
+    /// Open a stream under the deadline and cancellation token
+    ///
+    /// The deadline covers the whole stream; once it passes or the token is
+    /// cancelled, the stream yields a final error and ends.
+    pub(crate) async fn guard_stream<T, S, F>(
+        &self,
+        operation: &'static str,
+        default_timeout: Option<Duration>,
+        open: F,
+    ) -> RsllmResult<Pin<Box<dyn Stream<Item = RsllmResult<T>> + Send>>>
+    where
+        T: Send + 'static,
+        S: Stream<Item = RsllmResult<T>> + Send + 'static,
+        F: Future<Output = RsllmResult<S>>,
+    {
+        let timeout = self.timeout.or(default_timeout);
+        if timeout.is_none() && self.cancellation.is_none() {
+            return Ok(Box::pin(open.await?));
+        }
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let stream = self
+            .guard(operation, None, async {
+                tokio::select! {
+                    _ = expired(deadline) => Err(timeout_error(operation, timeout)),
+                    stream = open => stream,
+                }
+            })
+            .await?;
+
+        let cancellation = self.cancellation.clone();
+        let state = (Box::pin(stream), false);
+        Ok(Box::pin(futures_util::stream::unfold(
+            state,
+            move |(mut stream, done)| {
+                let cancellation = cancellation.clone();
+                async move {
+                    if done {
+                        return None;
+                    }
+                    tokio::select! {
+                        biased;
+                        _ = cancelled(cancellation.as_ref()) => {
+                            Some((Err(RsllmError::cancelled(operation)), (stream, true)))
+                        }
+                        _ = expired(deadline) => {
+                            Some((Err(timeout_error(operation, timeout)), (stream, true)))
+                        }
+                        item = stream.next() => item.map(|item| (item, (stream, false))),
+                    }
+                }
+            },
+        )))
+    }
+}
+
+/// Resolves when the token is cancelled; never without a token
+async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves at the deadline; never without one
+async fn expired(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn timeout_error(operation: &str, timeout: Option<Duration>) -> RsllmError {
+    RsllmError::timeout(
+        operation,
+        timeout.map_or(0, |timeout| timeout.as_millis() as u64),
+    )
+}
@@ -0,0 +1,275 @@
+//! # Generation Parameters
+//!
+//! Sampling and length controls for a single request. Unset fields fall back
+//! to the client's [`ModelConfig`](crate::ModelConfig); fields a provider
+//! cannot express are dropped with a warning.
+
+use crate::{RsllmError, RsllmResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Generation parameters for a request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationParams {
+    /// Sampling temperature (0.0 to 2.0)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+
+    /// Nucleus sampling probability mass (0.0 to 1.0)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+
+    /// Maximum tokens to generate
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+
+    /// Sequences that end generation
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+
+    /// Penalty for tokens already present (-2.0 to 2.0)
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+
+    /// Penalty proportional to token frequency (-2.0 to 2.0)
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+
+    /// Seed for best-effort deterministic sampling
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Return token log probabilities with the response
+    #[serde(default)]
+    pub logprobs: Option<LogprobOptions>,
+
+    /// Extra HTTP headers sent with the request
+    ///
+    /// Transport-level only: headers do not take part in response caching.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Token log probability request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogprobOptions {
+    /// Number of most likely alternatives returned per token (0 to 20)
+    pub top_k: u8,
+}
+
+impl LogprobOptions {
+    /// Request logprobs with `top_k` alternatives per token
+    pub fn new(top_k: u8) -> Self {
+        Self { top_k }
+    }
+}
+
+impl GenerationParams {
+    /// Create empty parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parameters carrying only temperature and token limit
+    pub(crate) fn sampling(temperature: Option<f32>, max_tokens: Option<u32>) -> Self {
+        Self {
+            temperature,
+            max_tokens,
+            ..Self::default()
+        }
+    }
+
+    /// Set the sampling temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set top-p
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the token limit
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set the stop sequences
+    pub fn with_stop<I, S>(mut self, stop: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop = Some(stop.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the presence penalty
+    pub fn with_presence_penalty(mut self, penalty: f32) -> Self {
+        self.presence_penalty = Some(penalty);
+        self
+    }
+
+    /// Set the frequency penalty
+    pub fn with_frequency_penalty(mut self, penalty: f32) -> Self {
+        self.frequency_penalty = Some(penalty);
+        self
+    }
+
+    /// Set the sampling seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Request token log probabilities with `top_k` alternatives per token
+    pub fn with_logprobs(mut self, top_k: u8) -> Self {
+        self.logprobs = Some(LogprobOptions::new(top_k));
+        self
+    }
+
+    /// Add an HTTP header sent with the request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Fill unset fields from `defaults`
+    pub fn or(self, defaults: &GenerationParams) -> Self {
+        Self {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            stop: self.stop.or_else(|| defaults.stop.clone()),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            seed: self.seed.or(defaults.seed),
+            logprobs: self.logprobs.or(defaults.logprobs),
+            headers: defaults
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .chain(self.headers)
+                .collect(),
+        }
+    }
+
+    /// Validate parameter ranges
+    pub fn validate(&self) -> RsllmResult<()> {
+        let ranges = [
+            ("temperature", self.temperature, 0.0..=2.0),
+            ("top_p", self.top_p, 0.0..=1.0),
+            ("presence_penalty", self.presence_penalty, -2.0..=2.0),
+            ("frequency_penalty", self.frequency_penalty, -2.0..=2.0),
+        ];
+
+        for (field, value, range) in ranges {
+            if let Some(value) = value {
+                if !range.contains(&value) {
+                    return Err(RsllmError::validation(
+                        field,
+                        format!(
+                            "{} must be between {} and {}",
+                            field,
+                            range.start(),
+                            range.end()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(logprobs) = self.logprobs {
+            if logprobs.top_k > 20 {
+                return Err(RsllmError::validation(
+                    "logprobs.top_k",
+                    "logprobs.top_k must be between 0 and 20",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fail for providers that cannot return logprobs
+    pub(crate) fn reject_logprobs(&self, provider: &str) -> RsllmResult<()> {
+        if self.logprobs.is_some() {
+            return Err(RsllmError::unsupported(provider, "logprobs"));
+        }
+        Ok(())
+    }
+
+    /// Stop sequences, if any were given
+    pub(crate) fn stop_sequences(&self) -> Option<&[String]> {
+        self.stop.as_deref().filter(|stop| !stop.is_empty())
+    }
+
+    /// Warn about set fields that `provider` cannot send
+    pub(crate) fn warn_dropped(&self, provider: &str, dropped: &[&str]) {
+        let set = [
+            ("top_p", self.top_p.is_some()),
+            ("stop", self.stop_sequences().is_some()),
+            ("presence_penalty", self.presence_penalty.is_some()),
+            ("frequency_penalty", self.frequency_penalty.is_some()),
+            ("seed", self.seed.is_some()),
+            ("logprobs", self.logprobs.is_some()),
+            ("headers", !self.headers.is_empty()),
+        ];
+
+        for (field, is_set) in set {
+            if is_set && dropped.contains(&field) {
+                tracing::warn!(
+                    provider,
+                    field,
+                    "Generation parameter not supported by provider, dropped"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_fall_back_to_defaults() {
+        let defaults = GenerationParams::new()
+            .with_temperature(0.7)
+            .with_max_tokens(512)
+            .with_stop(["END"]);
+
+        let params = GenerationParams::new()
+            .with_temperature(0.0)
+            .with_seed(7)
+            .or(&defaults);
+
+        assert_eq!(params.temperature, Some(0.0));
+        assert_eq!(params.max_tokens, Some(512));
+        assert_eq!(params.stop, Some(vec!["END".to_string()]));
+        assert_eq!(params.seed, Some(7));
+        assert_eq!(params.top_p, None);
+    }
+
+    #[test]
+    fn test_params_validation() {
+        assert!(GenerationParams::new().validate().is_ok());
+        assert!(GenerationParams::new()
+            .with_temperature(2.5)
+            .validate()
+            .is_err());
+        assert!(GenerationParams::new().with_top_p(1.5).validate().is_err());
+        assert!(GenerationParams::new()
+            .with_presence_penalty(-2.0)
+            .with_frequency_penalty(2.0)
+            .validate()
+            .is_ok());
+        assert!(GenerationParams::new()
+            .with_logprobs(21)
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,291 @@
+//! # Model Pricing
+//!
+//! Per-model token prices used to estimate request cost from reported usage.
+//! Built-in defaults cover common OpenAI, Anthropic and Gemini models and can be
+//! overridden in code or from a TOML/JSON file.
+
+use crate::response::Usage;
+use crate::{RsllmError, RsllmResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Token prices for one model, in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Price of uncached prompt tokens
+    pub input_per_million: f64,
+
+    /// Price of completion tokens
+    pub output_per_million: f64,
+
+    /// Price of prompt tokens read from the cache (defaults to the input price)
+    #[serde(default)]
+    pub cached_input_per_million: Option<f64>,
+
+    /// Price of prompt tokens written to the cache (defaults to the input price)
+    #[serde(default)]
+    pub cache_write_per_million: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Create pricing from input and output prices
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+            cached_input_per_million: None,
+            cache_write_per_million: None,
+        }
+    }
+
+    /// Set the cache read price
+    pub fn with_cached_input(mut self, per_million: f64) -> Self {
+        self.cached_input_per_million = Some(per_million);
+        self
+    }
+
+    /// Set the cache write price
+    pub fn with_cache_write(mut self, per_million: f64) -> Self {
+        self.cache_write_per_million = Some(per_million);
+        self
+    }
+
+    /// Cost of one response's usage in USD
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let cached = usage.cached_tokens.unwrap_or(0);
+        let written = usage.cache_creation_tokens.unwrap_or(0);
+        let uncached = usage.prompt_tokens.saturating_sub(cached + written);
+
+        let micros = f64::from(uncached) * self.input_per_million
+            + f64::from(cached)
+                * self
+                    .cached_input_per_million
+                    .unwrap_or(self.input_per_million)
+            + f64::from(written)
+                * self
+                    .cache_write_per_million
+                    .unwrap_or(self.input_per_million)
+            + f64::from(usage.completion_tokens) * self.output_per_million;
+
+        micros / 1_000_000.0
+    }
+}
+
+/// Prices keyed by model name
+///
+/// A model matches its own entry or the longest entry it extends with a `-`, `:`
+/// or `@` suffix, so dated snapshots such as `gpt-4o-2024-08-06` use the
+/// `gpt-4o` price.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingTable {
+    /// Prices per model
+    #[serde(default)]
+    pub models: HashMap<String, ModelPricing>,
+}
+
+impl PricingTable {
+    /// Create a table with no prices
+    pub fn empty() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    /// Load prices from a TOML or JSON file on top of the built-in defaults
+    ///
+    /// The format follows the file extension:
+    ///
+    /// ```toml
+    /// [models.gpt-4o]
+    /// input_per_million = 2.5
+    /// output_per_million = 10.0
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> RsllmResult<Self> {
+        let path = path.as_ref();
+        let overrides: PricingTable = ::config::Config::builder()
+            .add_source(::config::File::from(path))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .map_err(|e| {
+                RsllmError::configuration_with_source(
+                    format!("Failed to load pricing from {}", path.display()),
+                    e,
+                )
+            })?;
+
+        Ok(Self::default().merge(overrides))
+    }
+
+    /// Set the price for a model
+    pub fn with_model(mut self, model: impl Into<String>, pricing: ModelPricing) -> Self {
+        self.models.insert(model.into(), pricing);
+        self
+    }
+
+    /// Override entries with those from another table
+    pub fn merge(mut self, other: PricingTable) -> Self {
+        self.models.extend(other.models);
+        self
+    }
+
+    /// Pricing for a model, if known
+    pub fn get(&self, model: &str) -> Option<&ModelPricing> {
+        if let Some(pricing) = self.models.get(model) {
+            return Some(pricing);
+        }
+
+        self.models
+            .iter()
+            .filter(|(name, _)| {
+                model.strip_prefix(name.as_str()).is_some_and(|rest| {
+                    rest.starts_with('-') || rest.starts_with(':') || rest.starts_with('@')
+                })
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, pricing)| pricing)
+    }
+
+    /// Estimated cost of a response in USD, or `None` for an unknown model
+    pub fn cost(&self, model: &str, usage: &Usage) -> Option<f64> {
+        self.get(model).map(|pricing| pricing.cost(usage))
+    }
+}
+
+impl Default for PricingTable {
+    /// Built-in list prices for common models
+    fn default() -> Self {
+        let prices = [
+            // OpenAI
+            (
+                "gpt-4o",
+                ModelPricing::new(2.50, 10.00).with_cached_input(1.25),
+            ),
+            (
+                "gpt-4o-mini",
+                ModelPricing::new(0.15, 0.60).with_cached_input(0.075),
+            ),
+            ("gpt-4-turbo", ModelPricing::new(10.00, 30.00)),
+            ("gpt-4", ModelPricing::new(30.00, 60.00)),
+            ("gpt-3.5-turbo", ModelPricing::new(0.50, 1.50)),
+            (
+                "o1",
+                ModelPricing::new(15.00, 60.00).with_cached_input(7.50),
+            ),
+            (
+                "o1-mini",
+                ModelPricing::new(1.10, 4.40).with_cached_input(0.55),
+            ),
+            (
+                "o3-mini",
+                ModelPricing::new(1.10, 4.40).with_cached_input(0.55),
+            ),
+            // Anthropic
+            (
+                "claude-3-5-sonnet",
+                ModelPricing::new(3.00, 15.00)
+                    .with_cached_input(0.30)
+                    .with_cache_write(3.75),
+            ),
+            (
+                "claude-3-5-haiku",
+                ModelPricing::new(0.80, 4.00)
+                    .with_cached_input(0.08)
+                    .with_cache_write(1.00),
+            ),
+            (
+                "claude-3-opus",
+                ModelPricing::new(15.00, 75.00)
+                    .with_cached_input(1.50)
+                    .with_cache_write(18.75),
+            ),
+            ("claude-3-sonnet", ModelPricing::new(3.00, 15.00)),
+            (
+                "claude-3-haiku",
+                ModelPricing::new(0.25, 1.25)
+                    .with_cached_input(0.03)
+                    .with_cache_write(0.30),
+            ),
+            // Google Gemini
+            ("gemini-1.5-pro", ModelPricing::new(1.25, 5.00)),
+            ("gemini-1.5-flash", ModelPricing::new(0.075, 0.30)),
+            ("gemini-2.0-flash", ModelPricing::new(0.10, 0.40)),
+            ("gemini-2.0-flash-lite", ModelPricing::new(0.075, 0.30)),
+        ];
+
+        Self {
+            models: prices
+                .into_iter()
+                .map(|(model, pricing)| (model.to_string(), pricing))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_model_lookup() {
+        let table = PricingTable::default();
+
+        assert_eq!(table.get("gpt-4o").unwrap().input_per_million, 2.50);
+        assert_eq!(
+            table.get("gpt-4o-2024-08-06").unwrap().input_per_million,
+            2.50
+        );
+        assert_eq!(table.get("gpt-4o-mini").unwrap().input_per_million, 0.15);
+        assert_eq!(
+            table
+                .get("claude-3-5-sonnet-20241022")
+                .unwrap()
+                .output_per_million,
+            15.00
+        );
+        assert!(table.get("gpt-4.1").is_none());
+        assert!(table.get("llama3.1").is_none());
+    }
+
+    #[test]
+    fn test_cost_with_cache_tokens() {
+        let table = PricingTable::default();
+
+        // 1,000 uncached + 8,000 cache reads + 1,000 cache writes, 500 output tokens
+        let usage = Usage::new(10_000, 500)
+            .with_cached_tokens(8_000)
+            .with_cache_creation_tokens(1_000);
+        let cost = table.cost("claude-3-5-sonnet-20241022", &usage).unwrap();
+
+        assert_close(
+            cost,
+            (1_000.0 * 3.00 + 8_000.0 * 0.30 + 1_000.0 * 3.75 + 500.0 * 15.00) / 1e6,
+        );
+        assert!(table.cost("my-finetune", &usage).is_none());
+    }
+
+    #[test]
+    fn test_from_file_overrides_defaults() {
+        let path = std::env::temp_dir().join(format!("pricing-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "[models.gpt-4o]\ninput_per_million = 2.0\noutput_per_million = 8.0\n\n[models.my-finetune]\ninput_per_million = 3.0\noutput_per_million = 12.0\n",
+        )
+        .unwrap();
+
+        let table = PricingTable::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(table.get("gpt-4o").unwrap().input_per_million, 2.0);
+        assert_eq!(table.get("my-finetune").unwrap().output_per_million, 12.0);
+        // Untouched defaults remain
+        assert_eq!(table.get("gpt-4o-mini").unwrap().input_per_million, 0.15);
+    }
+}
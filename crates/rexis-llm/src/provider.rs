@@ -1,8 +1,14 @@
 //! # RSLLM Provider Abstraction
 //!
 //! Multi-provider support for different LLM APIs with unified interface.
-//! Supports OpenAI, Claude (Anthropic), Ollama, and custom providers.
-
+//! Supports OpenAI (including Azure), Claude (Anthropic), Ollama, Google Gemini,
+//! AWS Bedrock, Groq, Mistral, and any OpenAI-compatible server.
+
+use crate::batch::{BatchChatRequest, BatchResult, BatchStatus};
+use crate::models::ModelInfo;
+use crate::params::GenerationParams;
+use crate::streaming::{ToolAwareDelta, ToolAwareStream};
+use crate::structured::OutputSchema;
 use crate::{ChatMessage, ChatResponse, RsllmError, RsllmResult, StreamChunk};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -10,6 +16,24 @@ use std::fmt;
 use std::str::FromStr;
 use url::Url;
 
+#[cfg(feature = "claude")]
+mod claude;
+
+#[cfg(feature = "claude")]
+pub use claude::ClaudeProvider;
+
+#[cfg(feature = "gemini")]
+mod gemini;
+
+#[cfg(feature = "gemini")]
+pub use gemini::GeminiProvider;
+
+#[cfg(feature = "bedrock")]
+mod bedrock;
+
+#[cfg(feature = "bedrock")]
+pub use bedrock::BedrockProvider;
+
 /// Normalize URL to ensure it has a trailing slash for proper path joining
 /// This allows users to provide URLs with or without trailing slashes
 fn normalize_base_url(url: &Url) -> Url {
@@ -24,6 +48,80 @@ fn normalize_base_url(url: &Url) -> Url {
     }
 }
 
+/// Build a provider's HTTP client from the client's HTTP settings
+///
+/// The configured request timeout applies as given, replacing the default a
+/// provider's own constructor sets. Proxy and certificate problems surface
+/// here, when the client is built, rather than on the first request.
+#[cfg(any(
+    feature = "openai",
+    feature = "claude",
+    feature = "ollama",
+    feature = "gemini",
+    feature = "bedrock"
+))]
+pub(crate) fn http_client(http: &crate::config::HttpConfig) -> RsllmResult<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(http.timeout)
+        .connect_timeout(http.connect_timeout)
+        .user_agent(&http.user_agent)
+        .redirect(reqwest::redirect::Policy::limited(
+            http.max_redirects as usize,
+        ))
+        .danger_accept_invalid_certs(!http.verify_tls);
+
+    if let Some(pem) = &http.root_certificate_pem {
+        let certificate = reqwest::Certificate::from_pem(pem.as_bytes())
+            .map_err(|e| RsllmError::configuration_with_source("Invalid root certificate", e))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    for path in &http.root_certificate_files {
+        let pem = std::fs::read(path).map_err(|e| {
+            RsllmError::configuration_with_source(
+                format!("Failed to read root certificate {}", path.display()),
+                e,
+            )
+        })?;
+        let certificate = reqwest::Certificate::from_pem(&pem).map_err(|e| {
+            RsllmError::configuration_with_source(
+                format!("Invalid root certificate {}", path.display()),
+                e,
+            )
+        })?;
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    match http.resolve_proxy()? {
+        Some(proxy) => {
+            let url = proxy.parsed_url()?;
+            let mut scheme = if proxy.https_only {
+                reqwest::Proxy::https(url)
+            } else {
+                reqwest::Proxy::all(url)
+            }
+            .map_err(|e| RsllmError::configuration_with_source("Invalid proxy", e))?;
+
+            if let Some(username) = &proxy.username {
+                scheme = scheme.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+            }
+            scheme = scheme.no_proxy(
+                proxy
+                    .no_proxy
+                    .as_deref()
+                    .and_then(reqwest::NoProxy::from_string),
+            );
+            builder = builder.proxy(scheme);
+        }
+        None if !http.use_env_proxy => builder = builder.no_proxy(),
+        None => {}
+    }
+
+    builder
+        .build()
+        .map_err(|e| RsllmError::configuration_with_source("Failed to create HTTP client", e))
+}
+
 /// Supported LLM providers
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Provider {
@@ -33,6 +131,18 @@ pub enum Provider {
     Claude,
     /// Ollama (local models)
     Ollama,
+    /// Google Gemini
+    Gemini,
+    /// Azure-hosted OpenAI deployments
+    AzureOpenAI,
+    /// AWS Bedrock (Converse API)
+    Bedrock,
+    /// Groq (OpenAI-compatible)
+    Groq,
+    /// Mistral AI (OpenAI-compatible)
+    Mistral,
+    /// Any OpenAI-compatible server (vLLM, LM Studio, LiteLLM, OpenRouter, ...)
+    OpenAICompatible,
 }
 
 impl Provider {
@@ -42,13 +152,26 @@ impl Provider {
             Provider::OpenAI => "https://api.openai.com/v1/".parse().unwrap(),
             Provider::Claude => "https://api.anthropic.com/v1/".parse().unwrap(),
             Provider::Ollama => "http://localhost:11434/api/".parse().unwrap(),
+            Provider::Gemini => "https://generativelanguage.googleapis.com/v1beta/"
+                .parse()
+                .unwrap(),
+            // Azure endpoints are per resource, see `AzureOpenAIConfig::endpoint`
+            Provider::AzureOpenAI => "https://openai.azure.com/".parse().unwrap(),
+            // Bedrock endpoints are per region, see `BedrockConfig::endpoint`
+            Provider::Bedrock => "https://bedrock-runtime.us-east-1.amazonaws.com/"
+                .parse()
+                .unwrap(),
+            Provider::Groq => "https://api.groq.com/openai/v1/".parse().unwrap(),
+            Provider::Mistral => "https://api.mistral.ai/v1/".parse().unwrap(),
+            // No canonical endpoint; a base URL is required in configuration
+            Provider::OpenAICompatible => "http://localhost:8000/v1/".parse().unwrap(),
         }
     }
 
     /// Get the default models for this provider
     pub fn default_models(&self) -> Vec<&'static str> {
         match self {
-            Provider::OpenAI => vec![
+            Provider::OpenAI | Provider::AzureOpenAI => vec![
                 "gpt-4o",
                 "gpt-4o-mini",
                 "gpt-4-turbo",
@@ -71,15 +194,48 @@ impl Provider {
                 "codellama",
                 "vicuna",
             ],
+            Provider::Gemini => vec![
+                "gemini-2.0-flash",
+                "gemini-2.0-flash-lite",
+                "gemini-1.5-pro",
+                "gemini-1.5-flash",
+                "gemini-1.5-flash-8b",
+            ],
+            Provider::Bedrock => vec![
+                "anthropic.claude-3-5-sonnet-20240620-v1:0",
+                "anthropic.claude-3-5-haiku-20241022-v1:0",
+                "anthropic.claude-3-haiku-20240307-v1:0",
+                "meta.llama3-1-70b-instruct-v1:0",
+                "mistral.mistral-large-2407-v1:0",
+                "amazon.nova-pro-v1:0",
+            ],
+            Provider::Groq => vec![
+                "llama-3.3-70b-versatile",
+                "llama-3.1-8b-instant",
+                "mixtral-8x7b-32768",
+                "gemma2-9b-it",
+            ],
+            Provider::Mistral => vec![
+                "mistral-large-latest",
+                "mistral-small-latest",
+                "open-mistral-nemo",
+                "codestral-latest",
+                "pixtral-large-latest",
+            ],
+            Provider::OpenAICompatible => Vec::new(),
         }
     }
 
     /// Get the recommended model for this provider
     pub fn default_model(&self) -> &'static str {
         match self {
-            Provider::OpenAI => "gpt-4o-mini",
+            Provider::OpenAI | Provider::AzureOpenAI | Provider::OpenAICompatible => "gpt-4o-mini",
             Provider::Claude => "claude-3-5-haiku-20241022",
             Provider::Ollama => "llama3.1",
+            Provider::Gemini => "gemini-2.0-flash",
+            Provider::Bedrock => "anthropic.claude-3-5-haiku-20241022-v1:0",
+            Provider::Groq => "llama-3.3-70b-versatile",
+            Provider::Mistral => "mistral-small-latest",
         }
     }
 
@@ -89,6 +245,12 @@ impl Provider {
             Provider::OpenAI => true,
             Provider::Claude => true,
             Provider::Ollama => true,
+            Provider::Gemini => true,
+            Provider::AzureOpenAI => true,
+            Provider::Bedrock => false,
+            Provider::Groq => true,
+            Provider::Mistral => true,
+            Provider::OpenAICompatible => true,
         }
     }
 
@@ -98,6 +260,29 @@ impl Provider {
             Provider::OpenAI => true,
             Provider::Claude => true,
             Provider::Ollama => false, // Local deployment typically doesn't need auth
+            Provider::Gemini => true,
+            Provider::AzureOpenAI => true,
+            Provider::Bedrock => true,
+            Provider::Groq => true,
+            Provider::Mistral => true,
+            Provider::OpenAICompatible => false,
+        }
+    }
+
+    /// Provider-specific environment variable holding the API key
+    ///
+    /// Consulted by `ClientConfig::from_env` when `RSLLM_API_KEY` is unset.
+    pub fn api_key_env_var(&self) -> Option<&'static str> {
+        match self {
+            Provider::Gemini => Some("GEMINI_API_KEY"),
+            Provider::AzureOpenAI => Some("AZURE_OPENAI_API_KEY"),
+            Provider::Groq => Some("GROQ_API_KEY"),
+            Provider::Mistral => Some("MISTRAL_API_KEY"),
+            Provider::OpenAI
+            | Provider::Claude
+            | Provider::Ollama
+            | Provider::Bedrock
+            | Provider::OpenAICompatible => None,
         }
     }
 }
@@ -108,6 +293,12 @@ impl fmt::Display for Provider {
             Provider::OpenAI => write!(f, "openai"),
             Provider::Claude => write!(f, "claude"),
             Provider::Ollama => write!(f, "ollama"),
+            Provider::Gemini => write!(f, "gemini"),
+            Provider::AzureOpenAI => write!(f, "azure"),
+            Provider::Bedrock => write!(f, "bedrock"),
+            Provider::Groq => write!(f, "groq"),
+            Provider::Mistral => write!(f, "mistral"),
+            Provider::OpenAICompatible => write!(f, "openai_compatible"),
         }
     }
 }
@@ -120,6 +311,14 @@ impl FromStr for Provider {
             "openai" | "gpt" => Ok(Provider::OpenAI),
             "claude" | "anthropic" => Ok(Provider::Claude),
             "ollama" => Ok(Provider::Ollama),
+            "gemini" | "google" => Ok(Provider::Gemini),
+            "azure" | "azure_openai" | "azure-openai" => Ok(Provider::AzureOpenAI),
+            "bedrock" | "aws" => Ok(Provider::Bedrock),
+            "groq" => Ok(Provider::Groq),
+            "mistral" | "mistralai" => Ok(Provider::Mistral),
+            "openai_compatible" | "openai-compatible" | "compatible" => {
+                Ok(Provider::OpenAICompatible)
+            }
             _ => Err(RsllmError::configuration(format!(
                 "Unknown provider: {}",
                 s
@@ -202,15 +401,470 @@ pub trait LLMProvider: Send + Sync {
         self.chat_completion(messages, model, temperature, max_tokens)
             .await
     }
+
+    /// Chat completion with the full set of generation parameters
+    ///
+    /// The default implementation forwards temperature and token limit only,
+    /// drops the other parameters with a warning and rejects logprobs; providers
+    /// override it to map every parameter they support.
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        params.reject_logprobs(self.name())?;
+        params.warn_dropped(
+            self.name(),
+            &[
+                "top_p",
+                "stop",
+                "presence_penalty",
+                "frequency_penalty",
+                "seed",
+                "headers",
+            ],
+        );
+        if tools.is_empty() {
+            self.chat_completion(messages, model, params.temperature, params.max_tokens)
+                .await
+        } else {
+            self.chat_completion_with_tools(
+                messages,
+                tools,
+                model,
+                params.temperature,
+                params.max_tokens,
+            )
+            .await
+        }
+    }
+
+    /// Chat completion constrained to a JSON schema
+    ///
+    /// The default implementation describes the schema in a system instruction;
+    /// providers with native structured output override it.
+    async fn chat_completion_structured(
+        &self,
+        messages: Vec<ChatMessage>,
+        schema: &OutputSchema,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion(
+            schema.apply_instruction(messages),
+            model,
+            temperature,
+            max_tokens,
+        )
+        .await
+    }
+
+    /// Chat completion with tool calling support (streaming)
+    async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ToolAwareStream> {
+        // Default implementation: replay a non-streaming response as deltas
+        let response = self
+            .chat_completion_with_tools(messages, tools, model.as_deref(), temperature, max_tokens)
+            .await?;
+
+        let deltas = ToolAwareDelta::from_chat_response(&response);
+        Ok(Box::pin(futures_util::stream::iter(
+            deltas.into_iter().map(Ok),
+        )))
+    }
+
+    /// Models available from the provider
+    ///
+    /// The default implementation describes [`supported_models`](Self::supported_models);
+    /// providers with a models endpoint override it.
+    async fn list_models(&self) -> RsllmResult<Vec<ModelInfo>> {
+        let provider = self.provider_type();
+        Ok(self
+            .supported_models()
+            .into_iter()
+            .map(|id| ModelInfo::new(id, provider))
+            .collect())
+    }
+
+    /// Make a cheap authenticated call that fails on bad credentials or URLs
+    ///
+    /// The default implementation requests a one-token completion.
+    async fn validate(&self) -> RsllmResult<()> {
+        self.chat_completion(vec![ChatMessage::user("ping")], None, None, Some(1))
+            .await
+            .map(|_| ())
+    }
+
+    /// Submit requests to the provider's batch API
+    ///
+    /// Requests arrive with the model and generation defaults already applied.
+    /// The default implementation reports batching as unsupported.
+    async fn create_batch(&self, requests: Vec<BatchChatRequest>) -> RsllmResult<BatchStatus> {
+        let _ = requests;
+        Err(RsllmError::unsupported(self.name(), "batch requests"))
+    }
+
+    /// Fetch the progress of a batch
+    async fn batch_status(&self, id: &str) -> RsllmResult<BatchStatus> {
+        let _ = id;
+        Err(RsllmError::unsupported(self.name(), "batch requests"))
+    }
+
+    /// Download the results of a finished batch
+    async fn batch_results(&self, status: &BatchStatus) -> RsllmResult<Vec<BatchResult>> {
+        let _ = status;
+        Err(RsllmError::unsupported(self.name(), "batch requests"))
+    }
+}
+
+/// Build OpenAI-format tool definitions
+#[cfg(any(feature = "openai", feature = "ollama"))]
+fn openai_tools_json(tools: &[crate::tools::ToolDefinition]) -> Vec<serde_json::Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters
+                }
+            })
+        })
+        .collect()
+}
+
+/// Add the per-request headers of `params` to a provider's headers
+#[cfg(any(
+    feature = "openai",
+    feature = "claude",
+    feature = "ollama",
+    feature = "gemini",
+    feature = "bedrock"
+))]
+pub(crate) fn with_request_headers(
+    mut headers: reqwest::header::HeaderMap,
+    params: &GenerationParams,
+) -> RsllmResult<reqwest::header::HeaderMap> {
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    for (name, value) in &params.headers {
+        let header = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+            RsllmError::validation("headers", format!("Invalid header name '{}'", name))
+        })?;
+        let value = HeaderValue::from_str(value).map_err(|_| {
+            RsllmError::validation("headers", format!("Invalid value for header '{}'", name))
+        })?;
+        headers.insert(header, value);
+    }
+    Ok(headers)
+}
+
+/// Convert a non-success HTTP response into a classified error
+///
+/// 401/403 become authentication errors and 429 becomes a rate limit error carrying
+/// the `Retry-After` hint (in seconds) when the provider sends one.
+#[cfg(any(
+    feature = "openai",
+    feature = "claude",
+    feature = "ollama",
+    feature = "gemini"
+))]
+async fn error_from_response(provider: &str, response: reqwest::Response) -> RsllmError {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(std::time::Duration::from_secs);
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    let message = format!("API request failed: {}", error_text);
+
+    match status.as_u16() {
+        401 | 403 => RsllmError::authentication(message),
+        429 => RsllmError::rate_limit(message, retry_after),
+        _ => RsllmError::api(provider, message, status.as_str()),
+    }
+}
+
+/// Parse a single OpenAI streaming event (the JSON payload of a `data:` line)
+#[cfg(feature = "openai")]
+fn parse_openai_stream_event(data: &str) -> RsllmResult<Vec<ToolAwareDelta>> {
+    use crate::response::{ToolCallDelta, ToolFunctionDelta};
+
+    let event: serde_json::Value = serde_json::from_str(data)?;
+    let delta = &event["choices"][0]["delta"];
+    let mut deltas = Vec::new();
+
+    if let Some(content) = delta["content"].as_str() {
+        if !content.is_empty() {
+            deltas.push(ToolAwareDelta::content(content));
+        }
+    }
+
+    if let Some(calls) = delta["tool_calls"].as_array() {
+        for (position, call) in calls.iter().enumerate() {
+            let index = call["index"].as_u64().unwrap_or(position as u64) as u32;
+            deltas.push(ToolAwareDelta::tool_call(ToolCallDelta {
+                index,
+                id: call["id"].as_str().map(String::from),
+                call_type: call["type"].as_str().map(String::from),
+                function: call.get("function").map(|function| ToolFunctionDelta {
+                    name: function["name"].as_str().map(String::from),
+                    arguments: function["arguments"].as_str().map(String::from),
+                }),
+            }));
+        }
+    }
+
+    Ok(deltas)
+}
+
+/// Turn an OpenAI Server-Sent Events response into a tool-aware delta stream
+#[cfg(feature = "openai")]
+fn openai_tool_stream(response: reqwest::Response) -> ToolAwareStream {
+    sse_tool_stream(response, parse_openai_stream_event)
+}
+
+/// Turn a Server-Sent Events response into a tool-aware delta stream
+///
+/// Each `data:` payload is handed to `parse_event`. A `[DONE]` sentinel or the end
+/// of the body finishes the stream.
+#[cfg(any(feature = "openai", feature = "gemini"))]
+fn sse_tool_stream<P>(response: reqwest::Response, parse_event: P) -> ToolAwareStream
+where
+    P: FnMut(&str) -> RsllmResult<Vec<ToolAwareDelta>> + Send + 'static,
+{
+    use futures_util::StreamExt;
+    use std::collections::VecDeque;
+
+    struct SseState<P> {
+        bytes: std::pin::Pin<
+            Box<dyn futures_util::Stream<Item = reqwest::Result<bytes::Bytes>> + Send>,
+        >,
+        buffer: Vec<u8>,
+        pending: VecDeque<RsllmResult<ToolAwareDelta>>,
+        finished: bool,
+        parse_event: P,
+    }
+
+    let state = SseState {
+        bytes: Box::pin(response.bytes_stream()),
+        buffer: Vec::new(),
+        pending: VecDeque::new(),
+        finished: false,
+        parse_event,
+    };
+
+    Box::pin(futures_util::stream::unfold(
+        state,
+        |mut state| async move {
+            loop {
+                if let Some(item) = state.pending.pop_front() {
+                    return Some((item, state));
+                }
+                if state.finished {
+                    return None;
+                }
+
+                // Process the next complete line, if any
+                if let Some(newline) = state.buffer.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = state.buffer.drain(..=newline).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    let Some(data) = line.trim().strip_prefix("data:") else {
+                        continue;
+                    };
+                    let data = data.trim();
+
+                    if data == "[DONE]" {
+                        state.pending.push_back(Ok(ToolAwareDelta::done()));
+                        state.finished = true;
+                        continue;
+                    }
+
+                    match (state.parse_event)(data) {
+                        Ok(deltas) => state.pending.extend(deltas.into_iter().map(Ok)),
+                        Err(e) => {
+                            state.pending.push_back(Err(e));
+                            state.finished = true;
+                        }
+                    }
+                    continue;
+                }
+
+                match state.bytes.next().await {
+                    Some(Ok(chunk)) => state.buffer.extend_from_slice(&chunk),
+                    Some(Err(e)) => {
+                        state.pending.push_back(Err(e.into()));
+                        state.finished = true;
+                    }
+                    None if !state.buffer.is_empty() => {
+                        // Flush a trailing line without a newline terminator
+                        state.buffer.push(b'\n');
+                    }
+                    None => {
+                        // Stream closed without [DONE]
+                        state.pending.push_back(Ok(ToolAwareDelta::done()));
+                        state.finished = true;
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// Reject image input for models known to be text-only
+#[cfg(any(feature = "openai", feature = "ollama"))]
+fn ensure_image_support(provider: &str, model: &str, messages: &[ChatMessage]) -> RsllmResult<()> {
+    if !messages.iter().any(|message| message.content.has_images()) {
+        return Ok(());
+    }
+
+    if crate::models::supports_vision(model) == Some(false) {
+        return Err(RsllmError::unsupported(
+            provider,
+            format!("image input with model '{}'", model),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parse the `usage` object of a chat completions response
+#[cfg(feature = "openai")]
+fn openai_usage(response: &serde_json::Value) -> Option<crate::response::Usage> {
+    let usage = response.get("usage")?;
+    let count = |value: &serde_json::Value| value.as_u64().map(|tokens| tokens as u32);
+
+    let mut parsed = crate::response::Usage::new(
+        count(&usage["prompt_tokens"]).unwrap_or(0),
+        count(&usage["completion_tokens"]).unwrap_or(0),
+    );
+    if let Some(total) = count(&usage["total_tokens"]) {
+        parsed.total_tokens = total;
+    }
+    parsed.cached_tokens = count(&usage["prompt_tokens_details"]["cached_tokens"]);
+    parsed.reasoning_tokens = count(&usage["completion_tokens_details"]["reasoning_tokens"]);
+    Some(parsed)
+}
+
+/// Parse the `logprobs` block of the first choice of a chat completions response
+#[cfg(feature = "openai")]
+fn openai_logprobs(response: &serde_json::Value) -> Option<Vec<crate::response::TokenLogprob>> {
+    let content = response["choices"][0]["logprobs"].get("content")?;
+    serde_json::from_value(content.clone()).ok()
+}
+
+/// Reduce a serialized `ChatMessage` to the chat-completions wire fields
+///
+/// Drops client-side fields (metadata, timestamp), encodes tool call arguments as
+/// JSON strings and converts multi-modal content into content parts.
+#[cfg(feature = "openai")]
+fn wire_message(message: &serde_json::Value) -> serde_json::Value {
+    use serde_json::{json, Value};
+
+    let content = match &message["content"] {
+        Value::Object(multi_modal) => {
+            let mut parts = Vec::new();
+            if let Some(text) = multi_modal.get("text").and_then(|text| text.as_str()) {
+                parts.push(json!({ "type": "text", "text": text }));
+            }
+            for attachment in multi_modal
+                .get("attachments")
+                .and_then(|attachments| attachments.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let source = &attachment["content"];
+                let url = match source["type"].as_str() {
+                    Some("base64" | "bytes") => format!(
+                        "data:{};base64,{}",
+                        source["mime_type"].as_str().unwrap_or("image/png"),
+                        source["data"].as_str().unwrap_or_default()
+                    ),
+                    Some("url") => source["url"].as_str().unwrap_or_default().to_string(),
+                    _ => continue,
+                };
+                parts.push(json!({ "type": "image_url", "image_url": { "url": url } }));
+            }
+            Value::Array(parts)
+        }
+        other => other.clone(),
+    };
+
+    let mut wire = json!({ "role": message["role"], "content": content });
+
+    for field in ["name", "tool_call_id"] {
+        if !message[field].is_null() {
+            wire[field] = message[field].clone();
+        }
+    }
+
+    if let Some(calls) = message["tool_calls"].as_array() {
+        let calls: Vec<Value> = calls
+            .iter()
+            .map(|call| {
+                let mut call = call.clone();
+                let arguments = &call["function"]["arguments"];
+                if !arguments.is_string() {
+                    call["function"]["arguments"] = arguments.to_string().into();
+                }
+                call
+            })
+            .collect();
+        wire["tool_calls"] = calls.into();
+    }
+
+    wire
 }
 
 /// OpenAI provider implementation
+///
+/// Also serves Azure OpenAI deployments and OpenAI-compatible services (Groq,
+/// Mistral), which share the wire format but differ in URLs, authentication and
+/// accepted parameters.
 #[cfg(feature = "openai")]
 pub struct OpenAIProvider {
     client: reqwest::Client,
     api_key: String,
     base_url: Url,
     organization_id: Option<String>,
+    azure: Option<AzureDeployment>,
+    kind: Provider,
+    extra_headers: reqwest::header::HeaderMap,
+    tools_enabled: bool,
+}
+
+/// Request parameters Groq rejects instead of ignoring
+#[cfg(feature = "openai")]
+const GROQ_UNSUPPORTED_PARAMS: &[&str] = &[
+    "logprobs",
+    "top_logprobs",
+    "logit_bias",
+    "n",
+    "functions",
+    "function_call",
+];
+
+/// Azure deployment addressing
+#[cfg(feature = "openai")]
+struct AzureDeployment {
+    deployment: String,
+    api_version: String,
 }
 
 #[cfg(feature = "openai")]
@@ -236,17 +890,239 @@ impl OpenAIProvider {
             api_key,
             base_url: normalized_base_url,
             organization_id,
+            azure: None,
+            kind: Provider::OpenAI,
+            extra_headers: reqwest::header::HeaderMap::new(),
+            tools_enabled: true,
         })
     }
 
+    /// Apply timeouts, user agent, proxy and TLS settings from the client configuration
+    pub fn with_http_config(mut self, http: &crate::config::HttpConfig) -> RsllmResult<Self> {
+        self.client = http_client(http)?;
+        Ok(self)
+    }
+
+    /// Send additional headers with every request
+    ///
+    /// Some proxies require attribution headers such as `HTTP-Referer` or `X-Title`.
+    pub fn with_headers<'a>(
+        mut self,
+        headers: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> RsllmResult<Self> {
+        for (name, value) in headers {
+            let name = reqwest::header::HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                RsllmError::configuration_with_source(format!("Invalid header name: {}", name), e)
+            })?;
+            let value = reqwest::header::HeaderValue::from_str(value).map_err(|e| {
+                RsllmError::configuration_with_source(
+                    format!("Invalid value for header {}", name),
+                    e,
+                )
+            })?;
+            self.extra_headers.insert(name, value);
+        }
+        Ok(self)
+    }
+
+    /// Stop sending tool definitions, for servers that reject the `tools` field
+    pub fn with_tools_enabled(mut self, enabled: bool) -> Self {
+        self.tools_enabled = enabled;
+        self
+    }
+
+    /// Create a provider for an OpenAI-compatible service such as Groq or Mistral
+    ///
+    /// The service's default base URL is used unless `base_url` is given.
+    pub fn compatible(kind: Provider, api_key: String, base_url: Option<Url>) -> RsllmResult<Self> {
+        let base_url = base_url.unwrap_or_else(|| kind.default_base_url());
+        let mut provider = Self::new(api_key, Some(base_url), None)?;
+        provider.kind = kind;
+        Ok(provider)
+    }
+
+    /// Create a provider for an Azure OpenAI deployment
+    ///
+    /// `endpoint` is the resource endpoint, e.g. `https://{resource}.openai.azure.com/`.
+    pub fn azure(
+        api_key: String,
+        endpoint: Url,
+        deployment: impl Into<String>,
+        api_version: impl Into<String>,
+    ) -> RsllmResult<Self> {
+        let mut provider = Self::new(api_key, Some(endpoint), None)?;
+        provider.azure = Some(AzureDeployment {
+            deployment: deployment.into(),
+            api_version: api_version.into(),
+        });
+        provider.kind = Provider::AzureOpenAI;
+        Ok(provider)
+    }
+
+    /// Resolve the model, which names the deployment on Azure
+    fn resolve_model<'a>(&'a self, model: Option<&'a str>) -> Option<&'a str> {
+        model
+            .or_else(|| self.azure.as_ref().map(|azure| azure.deployment.as_str()))
+            .or_else(|| Some(self.kind.default_model()))
+    }
+
+    /// Adapt a request body to the target service
+    ///
+    /// Messages are always reduced to the chat-completions wire fields. Groq and
+    /// Mistral validate strictly, so parameters they reject are also removed or
+    /// translated.
+    fn apply_quirks(&self, body: &mut serde_json::Value) {
+        if let Some(messages) = body["messages"].as_array_mut() {
+            for message in messages.iter_mut() {
+                *message = wire_message(message);
+            }
+        }
+
+        if !matches!(self.kind, Provider::Groq | Provider::Mistral) {
+            return;
+        }
+
+        if let Some(messages) = body["messages"].as_array_mut() {
+            let mut call_names = std::collections::HashMap::new();
+            for message in messages.iter_mut() {
+                for call in message["tool_calls"].as_array().into_iter().flatten() {
+                    if let (Some(id), Some(name)) =
+                        (call["id"].as_str(), call["function"]["name"].as_str())
+                    {
+                        call_names.insert(id.to_string(), name.to_string());
+                    }
+                }
+
+                match self.kind {
+                    Provider::Groq => {
+                        if let Some(object) = message.as_object_mut() {
+                            object.remove("name");
+                        }
+                    }
+                    // Mistral requires the function name on tool results
+                    Provider::Mistral if message["role"] == "tool" => {
+                        if let Some(name) = message["tool_call_id"]
+                            .as_str()
+                            .and_then(|id| call_names.get(id))
+                        {
+                            message["name"] = name.clone().into();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let Some(object) = body.as_object_mut() else {
+            return;
+        };
+
+        // Both reject an empty tools array
+        if object
+            .get("tools")
+            .and_then(|tools| tools.as_array())
+            .is_some_and(|tools| tools.is_empty())
+        {
+            object.remove("tools");
+        }
+
+        match self.kind {
+            Provider::Groq => {
+                for param in GROQ_UNSUPPORTED_PARAMS {
+                    if object.remove(*param).is_some() {
+                        tracing::debug!(param, "Removed parameter unsupported by Groq");
+                    }
+                }
+                // Groq supports JSON mode but not schema-constrained output
+                if object
+                    .get("response_format")
+                    .and_then(|format| format["type"].as_str())
+                    == Some("json_schema")
+                {
+                    object.insert(
+                        "response_format".to_string(),
+                        serde_json::json!({ "type": "json_object" }),
+                    );
+                }
+            }
+            Provider::Mistral => {
+                // Mistral names the sampling seed differently
+                if let Some(seed) = object.remove("seed") {
+                    object.insert("random_seed".to_string(), seed);
+                }
+                // Mistral spells "required" as "any"
+                if object.get("tool_choice").and_then(|choice| choice.as_str()) == Some("required")
+                {
+                    object.insert("tool_choice".to_string(), "any".into());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Chat completions URL for a model
+    fn chat_url(&self, model: Option<&str>) -> RsllmResult<Url> {
+        match &self.azure {
+            Some(azure) => {
+                let deployment = model.unwrap_or(&azure.deployment);
+                let mut url = self.base_url.join(&format!(
+                    "openai/deployments/{}/chat/completions",
+                    deployment
+                ))?;
+                url.query_pairs_mut()
+                    .append_pair("api-version", &azure.api_version);
+                Ok(url)
+            }
+            None => Ok(self.base_url.join("chat/completions")?),
+        }
+    }
+
+    /// URL of an account-level endpoint (models, files, batches)
+    fn api_url(&self, path: &str) -> RsllmResult<Url> {
+        match &self.azure {
+            Some(azure) => {
+                let mut url = self.base_url.join(&format!("openai/{}", path))?;
+                url.query_pairs_mut()
+                    .append_pair("api-version", &azure.api_version);
+                Ok(url)
+            }
+            None => Ok(self.base_url.join(path)?),
+        }
+    }
+
+    /// Models listing URL
+    fn models_url(&self) -> RsllmResult<Url> {
+        self.api_url("models")
+    }
+
+    /// Convert a failed response into an error
+    ///
+    /// Azure answers 404 for unknown deployments, which is reported as not found
+    /// rather than a generic API error.
+    async fn request_error(&self, model: Option<&str>, response: reqwest::Response) -> RsllmError {
+        match &self.azure {
+            Some(azure) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+                RsllmError::not_found(format!(
+                    "Azure OpenAI deployment '{}'",
+                    model.unwrap_or(&azure.deployment)
+                ))
+            }
+            _ => error_from_response(self.name(), response).await,
+        }
+    }
+
     /// Build request headers
     fn build_headers(&self) -> reqwest::header::HeaderMap {
         let mut headers = reqwest::header::HeaderMap::new();
 
-        headers.insert(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {}", self.api_key).parse().unwrap(),
-        );
+        if self.azure.is_some() {
+            headers.insert("api-key", self.api_key.parse().unwrap());
+        } else if !self.api_key.is_empty() {
+            headers.insert(
+                reqwest::header::AUTHORIZATION,
+                format!("Bearer {}", self.api_key).parse().unwrap(),
+            );
+        }
 
         headers.insert(
             reqwest::header::CONTENT_TYPE,
@@ -257,96 +1133,456 @@ impl OpenAIProvider {
             headers.insert("OpenAI-Organization", org_id.parse().unwrap());
         }
 
-        headers
-    }
-}
-
-#[cfg(feature = "openai")]
-#[async_trait]
-impl LLMProvider for OpenAIProvider {
-    fn name(&self) -> &str {
-        "OpenAI"
-    }
-
-    fn provider_type(&self) -> Provider {
-        Provider::OpenAI
-    }
-
-    fn supported_models(&self) -> Vec<String> {
-        Provider::OpenAI
-            .default_models()
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
-    }
-
-    async fn health_check(&self) -> RsllmResult<bool> {
-        let url = self.base_url.join("models")?;
-        let response = self
-            .client
-            .get(url)
-            .headers(self.build_headers())
-            .send()
-            .await?;
+        headers.extend(self.extra_headers.clone());
 
-        Ok(response.status().is_success())
+        headers
     }
 
-    async fn chat_completion(
-        &self,
+    /// Build a chat completions request body
+    fn chat_request_body(
         messages: Vec<ChatMessage>,
         model: Option<&str>,
-        temperature: Option<f32>,
-        max_tokens: Option<u32>,
-    ) -> RsllmResult<ChatResponse> {
-        let url = self.base_url.join("chat/completions")?;
-
+        params: &GenerationParams,
+    ) -> serde_json::Value {
         let mut request_body = serde_json::json!({
             "model": model.unwrap_or(Provider::OpenAI.default_model()),
             "messages": messages,
         });
 
-        if let Some(temp) = temperature {
+        if let Some(temp) = params.temperature {
             request_body["temperature"] = temp.into();
         }
 
-        if let Some(max_tokens) = max_tokens {
+        if let Some(top_p) = params.top_p {
+            request_body["top_p"] = top_p.into();
+        }
+
+        if let Some(max_tokens) = params.max_tokens {
             request_body["max_tokens"] = max_tokens.into();
         }
 
+        if let Some(stop) = params.stop_sequences() {
+            request_body["stop"] = stop.into();
+        }
+
+        if let Some(penalty) = params.presence_penalty {
+            request_body["presence_penalty"] = penalty.into();
+        }
+
+        if let Some(penalty) = params.frequency_penalty {
+            request_body["frequency_penalty"] = penalty.into();
+        }
+
+        if let Some(seed) = params.seed {
+            request_body["seed"] = seed.into();
+        }
+
+        if let Some(logprobs) = params.logprobs {
+            request_body["logprobs"] = true.into();
+            request_body["top_logprobs"] = logprobs.top_k.into();
+        }
+
+        request_body
+    }
+
+    /// Send a non-streaming chat completions request
+    async fn send_chat_request(
+        &self,
+        mut request_body: serde_json::Value,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let url = self.chat_url(model)?;
+        self.apply_quirks(&mut request_body);
+
         let response = self
             .client
             .post(url)
-            .headers(self.build_headers())
+            .headers(with_request_headers(self.build_headers(), params)?)
             .json(&request_body)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(RsllmError::api(
-                "OpenAI",
-                format!("API request failed: {}", error_text),
-                status.as_str(),
-            ));
+            return Err(self.request_error(model, response).await);
         }
 
         let response_data: serde_json::Value = response.json().await?;
 
-        // Extract the response content
-        let content = response_data["choices"][0]["message"]["content"]
+        Ok(openai_chat_response(
+            &response_data,
+            model.unwrap_or(Provider::OpenAI.default_model()),
+        ))
+    }
+
+    /// Upload a JSONL file of batch requests and return its file ID
+    async fn upload_batch_file(&self, jsonl: String) -> RsllmResult<String> {
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", "batch")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(jsonl.into_bytes())
+                    .file_name("batch.jsonl")
+                    .mime_str("application/jsonl")?,
+            );
+
+        // The multipart body sets its own content type
+        let mut headers = self.build_headers();
+        headers.remove(reqwest::header::CONTENT_TYPE);
+
+        let response = self
+            .client
+            .post(self.api_url("files")?)
+            .headers(headers)
+            .multipart(form)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response(self.name(), response).await);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        body["id"]
             .as_str()
-            .unwrap_or("")
-            .to_string();
+            .map(String::from)
+            .ok_or_else(|| RsllmError::serialization("File upload response has no id"))
+    }
 
-        Ok(
-            ChatResponse::new(content, model.unwrap_or(Provider::OpenAI.default_model()))
-                .with_finish_reason("stop"),
+    /// Download and parse a batch output or error file
+    async fn batch_file_results(&self, file_id: &str) -> RsllmResult<Vec<BatchResult>> {
+        let response = self
+            .client
+            .get(self.api_url(&format!("files/{}/content", file_id))?)
+            .headers(self.build_headers())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response(self.name(), response).await);
+        }
+
+        let content = response.text().await?;
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| -> RsllmResult<BatchResult> {
+                let line: serde_json::Value = serde_json::from_str(line)?;
+                Ok(openai_batch_result(self.name(), &line))
+            })
+            .collect()
+    }
+}
+
+/// Convert a chat completions response body into a [`ChatResponse`]
+#[cfg(feature = "openai")]
+fn openai_chat_response(response_data: &serde_json::Value, model: &str) -> ChatResponse {
+    // Extract the response content
+    let content = response_data["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or("")
+        .to_string();
+
+    // Parse tool calls if present (OpenAI format)
+    let tool_calls: Vec<crate::message::ToolCall> = response_data["choices"][0]["message"]
+        ["tool_calls"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|call| {
+            Some(crate::message::ToolCall {
+                id: call["id"].as_str()?.to_string(),
+                call_type: crate::message::ToolCallType::Function,
+                function: crate::message::ToolFunction {
+                    name: call["function"]["name"].as_str()?.to_string(),
+                    arguments: serde_json::from_str(call["function"]["arguments"].as_str()?)
+                        .ok()?,
+                },
+            })
+        })
+        .collect();
+
+    let mut response = ChatResponse::new(content, model).with_finish_reason("stop");
+
+    if let Some(usage) = openai_usage(response_data) {
+        response = response.with_usage(usage);
+    }
+
+    if !tool_calls.is_empty() {
+        response = response.with_tool_calls(tool_calls);
+    }
+
+    if let Some(logprobs) = openai_logprobs(response_data) {
+        response = response.with_logprobs(logprobs);
+    }
+
+    response
+}
+
+/// Parse a batch object
+#[cfg(feature = "openai")]
+fn openai_batch_status(body: &serde_json::Value) -> RsllmResult<BatchStatus> {
+    let id = body["id"]
+        .as_str()
+        .ok_or_else(|| RsllmError::serialization("Batch response has no id"))?;
+    let state = serde_json::from_value(body["status"].clone()).map_err(|e| {
+        RsllmError::serialization(format!("Unknown batch status {}: {}", body["status"], e))
+    })?;
+    let count = |key: &str| body["request_counts"][key].as_u64().unwrap_or(0) as u32;
+    let file = |key: &str| body[key].as_str().map(String::from);
+
+    Ok(BatchStatus {
+        id: id.to_string(),
+        state,
+        total: count("total"),
+        completed: count("completed"),
+        failed: count("failed"),
+        output_file_id: file("output_file_id"),
+        error_file_id: file("error_file_id"),
+    })
+}
+
+/// Parse one line of a batch output or error file
+///
+/// Failures are reported either as a top-level `error` or as a non-200
+/// response whose body carries the API error.
+#[cfg(feature = "openai")]
+fn openai_batch_result(provider: &str, line: &serde_json::Value) -> BatchResult {
+    let custom_id = line["custom_id"].as_str().unwrap_or_default().to_string();
+    let error = &line["error"];
+    let status_code = line["response"]["status_code"].as_u64();
+    let body = &line["response"]["body"];
+
+    let response = if !error.is_null() {
+        Err(RsllmError::api(
+            provider,
+            error["message"].as_str().unwrap_or("Batch request failed"),
+            error["code"].as_str().unwrap_or("batch_error"),
+        ))
+    } else if status_code == Some(200) {
+        Ok(openai_chat_response(
+            body,
+            body["model"]
+                .as_str()
+                .unwrap_or(Provider::OpenAI.default_model()),
+        ))
+    } else {
+        Err(RsllmError::api(
+            provider,
+            body["error"]["message"]
+                .as_str()
+                .unwrap_or("Batch request failed"),
+            body["error"]["code"]
+                .as_str()
+                .map_or_else(|| status_code.unwrap_or_default().to_string(), String::from),
+        ))
+    };
+
+    BatchResult {
+        custom_id,
+        response,
+    }
+}
+
+#[cfg(feature = "openai")]
+#[async_trait]
+impl LLMProvider for OpenAIProvider {
+    fn name(&self) -> &str {
+        match self.kind {
+            Provider::AzureOpenAI => "AzureOpenAI",
+            Provider::Groq => "Groq",
+            Provider::Mistral => "Mistral",
+            _ => "OpenAI",
+        }
+    }
+
+    fn provider_type(&self) -> Provider {
+        self.kind
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.kind
+            .default_models()
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        let response = self
+            .client
+            .get(self.models_url()?)
+            .headers(self.build_headers())
+            .send()
+            .await?;
+
+        Ok(response.status().is_success())
+    }
+
+    async fn list_models(&self) -> RsllmResult<Vec<ModelInfo>> {
+        let response = self
+            .client
+            .get(self.models_url()?)
+            .headers(self.build_headers())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response(self.name(), response).await);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        Ok(body["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| {
+                let info = ModelInfo::new(model["id"].as_str()?, self.kind);
+                Some(match model["owned_by"].as_str() {
+                    Some(owner) => info.with_owned_by(owner),
+                    None => info,
+                })
+            })
+            .collect())
+    }
+
+    async fn validate(&self) -> RsllmResult<()> {
+        self.list_models().await.map(|_| ())
+    }
+
+    async fn create_batch(&self, requests: Vec<BatchChatRequest>) -> RsllmResult<BatchStatus> {
+        // Azure routes by deployment, so its batch lines omit the version prefix
+        let endpoint = if self.azure.is_some() {
+            "/chat/completions"
+        } else {
+            "/v1/chat/completions"
+        };
+
+        let mut jsonl = String::new();
+        for request in requests {
+            let model = self.resolve_model(request.model.as_deref());
+            let mut body = Self::chat_request_body(request.messages, model, &request.params);
+            self.apply_quirks(&mut body);
+
+            let line = serde_json::json!({
+                "custom_id": request.custom_id,
+                "method": "POST",
+                "url": endpoint,
+                "body": body,
+            });
+            jsonl.push_str(&line.to_string());
+            jsonl.push('\n');
+        }
+
+        let input_file_id = self.upload_batch_file(jsonl).await?;
+
+        let response = self
+            .client
+            .post(self.api_url("batches")?)
+            .headers(self.build_headers())
+            .json(&serde_json::json!({
+                "input_file_id": input_file_id,
+                "endpoint": endpoint,
+                "completion_window": "24h",
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response(self.name(), response).await);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        openai_batch_status(&body)
+    }
+
+    async fn batch_status(&self, id: &str) -> RsllmResult<BatchStatus> {
+        let response = self
+            .client
+            .get(self.api_url(&format!("batches/{}", id))?)
+            .headers(self.build_headers())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response(self.name(), response).await);
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        openai_batch_status(&body)
+    }
+
+    async fn batch_results(&self, status: &BatchStatus) -> RsllmResult<Vec<BatchResult>> {
+        let mut results = Vec::new();
+        for file_id in [&status.output_file_id, &status.error_file_id]
+            .into_iter()
+            .flatten()
+        {
+            results.extend(self.batch_file_results(file_id).await?);
+        }
+        Ok(results)
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_params(
+            messages,
+            Vec::new(),
+            model,
+            &GenerationParams::sampling(temperature, max_tokens),
         )
+        .await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let model = self.resolve_model(model);
+        ensure_image_support(self.name(), model.unwrap_or_default(), &messages)?;
+        let mut request_body = Self::chat_request_body(messages, model, params);
+
+        if !tools.is_empty() && self.tools_enabled {
+            request_body["tools"] = openai_tools_json(&tools).into();
+        }
+
+        self.send_chat_request(request_body, model, params).await
+    }
+
+    async fn chat_completion_structured(
+        &self,
+        messages: Vec<ChatMessage>,
+        schema: &OutputSchema,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let model = self.resolve_model(model);
+        ensure_image_support(self.name(), model.unwrap_or_default(), &messages)?;
+        // Groq only offers JSON mode, so the schema travels as an instruction
+        let messages = if self.kind == Provider::Groq {
+            schema.apply_instruction(messages)
+        } else {
+            messages
+        };
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let mut request_body = Self::chat_request_body(messages, model, &params);
+        request_body["response_format"] = serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": schema.name,
+                "schema": schema.schema,
+            }
+        });
+        self.send_chat_request(request_body, model, &params).await
     }
 
     async fn chat_completion_stream(
@@ -361,9 +1597,12 @@ impl LLMProvider for OpenAIProvider {
 
         // For now, implement a simple mock stream
         // In production, this would handle Server-Sent Events (SSE) from OpenAI
-        let _url = self.base_url.join("chat/completions")?;
+        let model_name = self
+            .resolve_model(model.as_deref())
+            .unwrap_or(Provider::OpenAI.default_model())
+            .to_string();
+        let _url = self.chat_url(Some(&model_name))?;
 
-        let model_name = model.unwrap_or_else(|| Provider::OpenAI.default_model().to_string());
         let mut _request_body = serde_json::json!({
             "model": &model_name,
             "messages": messages,
@@ -413,133 +1652,304 @@ impl LLMProvider for OpenAIProvider {
         temperature: Option<f32>,
         max_tokens: Option<u32>,
     ) -> RsllmResult<ChatResponse> {
-        let url = self.base_url.join("chat/completions")?;
+        self.chat_completion_with_params(
+            messages,
+            tools,
+            model,
+            &GenerationParams::sampling(temperature, max_tokens),
+        )
+        .await
+    }
 
-        // Build tools in OpenAI format
-        let tools_json: Vec<serde_json::Value> = tools
-            .iter()
-            .map(|tool| {
-                serde_json::json!({
-                    "type": "function",
-                    "function": {
-                        "name": tool.name,
-                        "description": tool.description,
-                        "parameters": tool.parameters
+    async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ToolAwareStream> {
+        let model = self.resolve_model(model.as_deref());
+        ensure_image_support(self.name(), model.unwrap_or_default(), &messages)?;
+        let url = self.chat_url(model)?;
+
+        let mut request_body = Self::chat_request_body(
+            messages,
+            model,
+            &GenerationParams::sampling(temperature, max_tokens),
+        );
+        request_body["stream"] = true.into();
+
+        if !tools.is_empty() && self.tools_enabled {
+            request_body["tools"] = openai_tools_json(&tools).into();
+        }
+
+        self.apply_quirks(&mut request_body);
+
+        let response = self
+            .client
+            .post(url)
+            .headers(self.build_headers())
+            .json(&request_body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(self.request_error(model, response).await);
+        }
+
+        Ok(openai_tool_stream(response))
+    }
+}
+
+/// Usage from Ollama's eval counters
+///
+/// Ollama omits `prompt_eval_count` when the prompt was served from its cache, so
+/// missing counts are estimated from the request and reply text.
+#[cfg(feature = "ollama")]
+fn ollama_usage(
+    request: &serde_json::Value,
+    response: &serde_json::Value,
+    content: &str,
+) -> crate::response::Usage {
+    use crate::tokens::{HeuristicTokenCounter, TokenCounter};
+
+    let counter = HeuristicTokenCounter;
+    let prompt_tokens = response["prompt_eval_count"]
+        .as_u64()
+        .map(|tokens| tokens as u32)
+        .unwrap_or_else(|| {
+            request["messages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|message| message["content"].as_str())
+                .map(|text| counter.count_text(text))
+                .sum::<usize>() as u32
+        });
+    let completion_tokens = response["eval_count"]
+        .as_u64()
+        .map(|tokens| tokens as u32)
+        .unwrap_or_else(|| counter.count_text(content) as u32);
+
+    crate::response::Usage::new(prompt_tokens, completion_tokens)
+}
+
+/// Convert messages to Ollama's chat format
+///
+/// Ollama takes images as a base64 `images` array next to plain-text content and
+/// cannot fetch image URLs.
+#[cfg(feature = "ollama")]
+fn ollama_messages(model: &str, messages: &[ChatMessage]) -> RsllmResult<Vec<serde_json::Value>> {
+    use crate::message::{AttachmentContent, AttachmentType};
+    use base64::Engine;
+
+    ensure_image_support("Ollama", model, messages)?;
+
+    messages
+        .iter()
+        .map(|message| {
+            let mut wire = serde_json::to_value(message)?;
+            if let crate::MessageContent::MultiModal { text, attachments } = &message.content {
+                let mut images = Vec::new();
+                for attachment in attachments {
+                    if attachment.attachment_type != AttachmentType::Image {
+                        return Err(RsllmError::unsupported(
+                            "Ollama",
+                            format!("{:?} attachments", attachment.attachment_type),
+                        ));
                     }
-                })
-            })
-            .collect();
+                    match &attachment.content {
+                        AttachmentContent::Base64 { data, .. } => images.push(data.clone()),
+                        AttachmentContent::Bytes { data, .. } => {
+                            images.push(base64::engine::general_purpose::STANDARD.encode(data))
+                        }
+                        AttachmentContent::Url { .. } => {
+                            return Err(RsllmError::unsupported(
+                                "Ollama",
+                                "image URLs (send the image as base64 data instead)",
+                            ))
+                        }
+                    }
+                }
+                wire["content"] = text.clone().unwrap_or_default().into();
+                wire["images"] = images.into();
+            }
+            Ok(wire)
+        })
+        .collect()
+}
+
+/// Ollama provider implementation  
+#[cfg(feature = "ollama")]
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    base_url: Url,
+}
+
+#[cfg(feature = "ollama")]
+impl OllamaProvider {
+    /// Create a new Ollama provider
+    pub fn new(base_url: Option<Url>) -> RsllmResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60)) // Ollama can be slower
+            .build()
+            .map_err(|e| {
+                RsllmError::configuration_with_source("Failed to create HTTP client", e)
+            })?;
+
+        let base = base_url.unwrap_or_else(|| Provider::Ollama.default_base_url());
+        let normalized_base_url = normalize_base_url(&base);
+
+        Ok(Self {
+            client,
+            base_url: normalized_base_url,
+        })
+    }
 
+    /// Apply timeouts, user agent, proxy and TLS settings from the client configuration
+    pub fn with_http_config(mut self, http: &crate::config::HttpConfig) -> RsllmResult<Self> {
+        self.client = http_client(http)?;
+        Ok(self)
+    }
+
+    /// Build a chat request body
+    ///
+    /// Generation parameters travel in `options`, with the token limit as `num_predict`.
+    fn chat_request_body(
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<serde_json::Value> {
+        let model = model.unwrap_or(Provider::Ollama.default_model());
         let mut request_body = serde_json::json!({
-            "model": model.unwrap_or(Provider::OpenAI.default_model()),
-            "messages": messages,
-            "tools": tools_json,
+            "model": model,
+            "messages": ollama_messages(model, &messages)?,
+            "stream": false,
         });
 
-        if let Some(temp) = temperature {
-            request_body["temperature"] = temp.into();
+        let mut options = serde_json::Map::new();
+        if let Some(temp) = params.temperature {
+            options.insert("temperature".to_string(), temp.into());
         }
-
-        if let Some(max_tokens) = max_tokens {
-            request_body["max_tokens"] = max_tokens.into();
+        if let Some(top_p) = params.top_p {
+            options.insert("top_p".to_string(), top_p.into());
+        }
+        if let Some(max_tokens) = params.max_tokens {
+            options.insert("num_predict".to_string(), max_tokens.into());
+        }
+        if let Some(stop) = params.stop_sequences() {
+            options.insert("stop".to_string(), stop.into());
+        }
+        if let Some(penalty) = params.presence_penalty {
+            options.insert("presence_penalty".to_string(), penalty.into());
+        }
+        if let Some(penalty) = params.frequency_penalty {
+            options.insert("frequency_penalty".to_string(), penalty.into());
+        }
+        if let Some(seed) = params.seed {
+            options.insert("seed".to_string(), seed.into());
+        }
+        if !options.is_empty() {
+            request_body["options"] = serde_json::Value::Object(options);
         }
 
+        Ok(request_body)
+    }
+
+    /// Send a non-streaming chat request
+    async fn send_chat_request(
+        &self,
+        request_body: serde_json::Value,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let url = self.base_url.join("chat")?;
+
         let response = self
             .client
             .post(url)
-            .headers(self.build_headers())
+            .headers(with_request_headers(
+                reqwest::header::HeaderMap::new(),
+                params,
+            )?)
             .json(&request_body)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(RsllmError::api(
-                "OpenAI",
-                format!("API request failed: {}", error_text),
-                status.as_str(),
-            ));
+            return Err(error_from_response("Ollama", response).await);
         }
 
         let response_data: serde_json::Value = response.json().await?;
 
-        // Extract content
-        let content = response_data["choices"][0]["message"]["content"]
+        let content = response_data["message"]["content"]
             .as_str()
             .unwrap_or("")
             .to_string();
 
-        // Parse tool calls if present (OpenAI format)
-        let tool_calls = if let Some(calls_array) =
-            response_data["choices"][0]["message"]["tool_calls"].as_array()
-        {
-            let parsed_calls: Vec<crate::message::ToolCall> = calls_array
-                .iter()
-                .filter_map(|call| {
-                    Some(crate::message::ToolCall {
-                        id: call["id"].as_str()?.to_string(),
-                        call_type: crate::message::ToolCallType::Function,
-                        function: crate::message::ToolFunction {
-                            name: call["function"]["name"].as_str()?.to_string(),
-                            arguments: serde_json::from_str(
-                                call["function"]["arguments"].as_str()?,
-                            )
-                            .ok()?,
-                        },
-                    })
-                })
-                .collect();
-
-            if parsed_calls.is_empty() {
-                None
-            } else {
-                Some(parsed_calls)
-            }
-        } else {
-            None
-        };
+        // Parse tool calls if present
+        let tool_calls =
+            if let Some(calls_array) = response_data["message"]["tool_calls"].as_array() {
+                let parsed_calls: Vec<crate::message::ToolCall> = calls_array
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, call)| {
+                        let function_name = call["function"]["name"].as_str()?;
 
-        let mut response =
-            ChatResponse::new(content, model.unwrap_or(Provider::OpenAI.default_model()))
-                .with_finish_reason("stop");
+                        // Ollama returns arguments as an object, sometimes with string values
+                        // Convert string numbers to actual numbers for compatibility
+                        let mut arguments = call["function"]["arguments"].clone();
+                        if let serde_json::Value::Object(ref mut args_obj) = arguments {
+                            for (_key, value) in args_obj.iter_mut() {
+                                if let serde_json::Value::String(s) = value {
+                                    // Try to parse as number
+                                    if let Ok(num) = s.parse::<f64>() {
+                                        *value = serde_json::json!(num);
+                                    } else if let Ok(int_num) = s.parse::<i64>() {
+                                        *value = serde_json::json!(int_num);
+                                    }
+                                }
+                            }
+                        }
 
-        if let Some(calls) = tool_calls {
-            response = response.with_tool_calls(calls);
-        }
+                        // Ollama doesn't provide an ID, so generate one
+                        let id = call["id"]
+                            .as_str()
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| format!("call_{}", idx));
 
-        Ok(response)
-    }
-}
+                        Some(crate::message::ToolCall {
+                            id,
+                            call_type: crate::message::ToolCallType::Function,
+                            function: crate::message::ToolFunction {
+                                name: function_name.to_string(),
+                                arguments,
+                            },
+                        })
+                    })
+                    .collect();
 
-/// Ollama provider implementation  
-#[cfg(feature = "ollama")]
-pub struct OllamaProvider {
-    client: reqwest::Client,
-    base_url: Url,
-}
+                if parsed_calls.is_empty() {
+                    None
+                } else {
+                    Some(parsed_calls)
+                }
+            } else {
+                None
+            };
 
-#[cfg(feature = "ollama")]
-impl OllamaProvider {
-    /// Create a new Ollama provider
-    pub fn new(base_url: Option<Url>) -> RsllmResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(60)) // Ollama can be slower
-            .build()
-            .map_err(|e| {
-                RsllmError::configuration_with_source("Failed to create HTTP client", e)
-            })?;
+        let usage = ollama_usage(&request_body, &response_data, &content);
+        let mut response =
+            ChatResponse::new(content, model.unwrap_or(Provider::Ollama.default_model()))
+                .with_finish_reason("stop")
+                .with_usage(usage);
 
-        let base = base_url.unwrap_or_else(|| Provider::Ollama.default_base_url());
-        let normalized_base_url = normalize_base_url(&base);
+        if let Some(calls) = tool_calls {
+            response = response.with_tool_calls(calls);
+        }
 
-        Ok(Self {
-            client,
-            base_url: normalized_base_url,
-        })
+        Ok(response)
     }
 }
 
@@ -568,53 +1978,58 @@ impl LLMProvider for OllamaProvider {
         Ok(response.status().is_success())
     }
 
-    async fn chat_completion(
-        &self,
-        messages: Vec<ChatMessage>,
-        model: Option<&str>,
-        temperature: Option<f32>,
-        _max_tokens: Option<u32>,
-    ) -> RsllmResult<ChatResponse> {
-        let url = self.base_url.join("chat")?;
-
-        let mut request_body = serde_json::json!({
-            "model": model.unwrap_or(Provider::Ollama.default_model()),
-            "messages": messages,
-            "stream": false,
-        });
-
-        if let Some(temp) = temperature {
-            request_body["options"] = serde_json::json!({
-                "temperature": temp
-            });
-        }
-
-        let response = self.client.post(url).json(&request_body).send().await?;
+    async fn list_models(&self) -> RsllmResult<Vec<ModelInfo>> {
+        let url = self.base_url.join("tags")?;
+        let response = self.client.get(url).send().await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(RsllmError::api(
-                "Ollama",
-                format!("API request failed: {}", error_text),
-                status.as_str(),
-            ));
+            return Err(error_from_response("Ollama", response).await);
         }
 
-        let response_data: serde_json::Value = response.json().await?;
+        let body: serde_json::Value = response.json().await?;
+        Ok(body["models"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["name"].as_str())
+            .map(|name| ModelInfo::new(name, Provider::Ollama))
+            .collect())
+    }
 
-        let content = response_data["message"]["content"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
+    async fn validate(&self) -> RsllmResult<()> {
+        self.list_models().await.map(|_| ())
+    }
 
-        Ok(
-            ChatResponse::new(content, model.unwrap_or(Provider::Ollama.default_model()))
-                .with_finish_reason("stop"),
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        self.chat_completion_with_params(
+            messages,
+            Vec::new(),
+            model,
+            &GenerationParams::sampling(temperature, max_tokens),
         )
+        .await
+    }
+
+    async fn chat_completion_structured(
+        &self,
+        messages: Vec<ChatMessage>,
+        schema: &OutputSchema,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        // JSON mode guarantees valid JSON; the instruction carries the schema
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let mut request_body =
+            Self::chat_request_body(schema.apply_instruction(messages), model, &params)?;
+        request_body["format"] = "json".into();
+        self.send_chat_request(request_body, model, &params).await
     }
 
     async fn chat_completion_stream(
@@ -675,120 +2090,32 @@ impl LLMProvider for OllamaProvider {
         tools: Vec<crate::tools::ToolDefinition>,
         model: Option<&str>,
         temperature: Option<f32>,
-        _max_tokens: Option<u32>,
+        max_tokens: Option<u32>,
     ) -> RsllmResult<ChatResponse> {
-        let url = self.base_url.join("chat")?;
-
-        // Build tools in Ollama/OpenAI format
-        let tools_json: Vec<serde_json::Value> = tools
-            .iter()
-            .map(|tool| {
-                serde_json::json!({
-                    "type": "function",
-                    "function": {
-                        "name": tool.name,
-                        "description": tool.description,
-                        "parameters": tool.parameters
-                    }
-                })
-            })
-            .collect();
-
-        let mut request_body = serde_json::json!({
-            "model": model.unwrap_or(Provider::Ollama.default_model()),
-            "messages": messages,
-            "stream": false,
-            "tools": tools_json,
-        });
-
-        if let Some(temp) = temperature {
-            request_body["options"] = serde_json::json!({
-                "temperature": temp
-            });
-        }
-
-        let response = self.client.post(url).json(&request_body).send().await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(RsllmError::api(
-                "Ollama",
-                format!("API request failed: {}", error_text),
-                status.as_str(),
-            ));
-        }
-
-        let response_data: serde_json::Value = response.json().await?;
-
-        let content = response_data["message"]["content"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
-
-        // Parse tool calls if present
-        let tool_calls =
-            if let Some(calls_array) = response_data["message"]["tool_calls"].as_array() {
-                let parsed_calls: Vec<crate::message::ToolCall> = calls_array
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(idx, call)| {
-                        let function_name = call["function"]["name"].as_str()?;
-
-                        // Ollama returns arguments as an object, sometimes with string values
-                        // Convert string numbers to actual numbers for compatibility
-                        let mut arguments = call["function"]["arguments"].clone();
-                        if let serde_json::Value::Object(ref mut args_obj) = arguments {
-                            for (_key, value) in args_obj.iter_mut() {
-                                if let serde_json::Value::String(s) = value {
-                                    // Try to parse as number
-                                    if let Ok(num) = s.parse::<f64>() {
-                                        *value = serde_json::json!(num);
-                                    } else if let Ok(int_num) = s.parse::<i64>() {
-                                        *value = serde_json::json!(int_num);
-                                    }
-                                }
-                            }
-                        }
-
-                        // Ollama doesn't provide an ID, so generate one
-                        let id = call["id"]
-                            .as_str()
-                            .map(|s| s.to_string())
-                            .unwrap_or_else(|| format!("call_{}", idx));
-
-                        Some(crate::message::ToolCall {
-                            id,
-                            call_type: crate::message::ToolCallType::Function,
-                            function: crate::message::ToolFunction {
-                                name: function_name.to_string(),
-                                arguments,
-                            },
-                        })
-                    })
-                    .collect();
-
-                if parsed_calls.is_empty() {
-                    None
-                } else {
-                    Some(parsed_calls)
-                }
-            } else {
-                None
-            };
+        self.chat_completion_with_params(
+            messages,
+            tools,
+            model,
+            &GenerationParams::sampling(temperature, max_tokens),
+        )
+        .await
+    }
 
-        let mut response =
-            ChatResponse::new(content, model.unwrap_or(Provider::Ollama.default_model()))
-                .with_finish_reason("stop");
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<crate::tools::ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        params.reject_logprobs(self.name())?;
+        let mut request_body = Self::chat_request_body(messages, model, params)?;
 
-        if let Some(calls) = tool_calls {
-            response = response.with_tool_calls(calls);
+        if !tools.is_empty() {
+            request_body["tools"] = openai_tools_json(&tools).into();
         }
 
-        Ok(response)
+        self.send_chat_request(request_body, model, params).await
     }
 }
 
@@ -830,4 +2157,719 @@ mod tests {
         let joined2 = normalized2.join("chat").unwrap();
         assert_eq!(joined2.as_str(), "http://localhost:11434/api/chat");
     }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_parse_openai_stream_event() {
+        let deltas = parse_openai_stream_event(
+            r#"{"choices":[{"delta":{"content":"Hi","tool_calls":[{"index":1,"id":"call_1","type":"function","function":{"name":"search","arguments":""}}]}}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].content.as_deref(), Some("Hi"));
+        let call = deltas[1].tool_call_delta.as_ref().unwrap();
+        assert_eq!(call.index, 1);
+        assert_eq!(call.id.as_deref(), Some("call_1"));
+        assert_eq!(
+            call.function.as_ref().unwrap().name.as_deref(),
+            Some("search")
+        );
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_tool_stream_fixture() {
+        use crate::streaming::ToolCallAccumulator;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let events = [
+            r#"{"choices":[{"delta":{"role":"assistant","content":"Let me "}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_a","type":"function","function":{"name":"get_weather","arguments":""}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"city\":"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"content":"check both."}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":1,"id":"call_b","type":"function","function":{"name":"get_time","arguments":"{\"tz\":"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"Paris\"}"}}]}}]}"#,
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":1,"function":{"arguments":"\"CET\"}"}}]}}]}"#,
+            r#"{"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+            "[DONE]",
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {}\n\n", event))
+            .collect();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+            None,
+        )
+        .unwrap();
+
+        let stream = provider
+            .chat_completion_with_tools_stream(
+                vec![ChatMessage::user("Weather and time in Paris?")],
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let accumulator = ToolCallAccumulator::collect(stream).await.unwrap();
+        assert!(accumulator.is_done());
+        assert_eq!(accumulator.content(), "Let me check both.");
+
+        let calls = accumulator.tool_calls().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(
+            calls[0].function.arguments,
+            serde_json::json!({"city": "Paris"})
+        );
+        assert_eq!(calls[1].id, "call_b");
+        assert_eq!(
+            calls[1].function.arguments,
+            serde_json::json!({"tz": "CET"})
+        );
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_groq_quirks_strip_unsupported_params() {
+        let provider =
+            OpenAIProvider::compatible(Provider::Groq, "test-key".to_string(), None).unwrap();
+        let mut body = serde_json::json!({
+            "model": "llama-3.3-70b-versatile",
+            "messages": [ChatMessage::user("hi").with_name("alice")],
+            "logprobs": true,
+            "top_logprobs": 3,
+            "logit_bias": {"50256": -100},
+            "n": 2,
+            "temperature": 0.2,
+        });
+
+        provider.apply_quirks(&mut body);
+
+        for param in GROQ_UNSUPPORTED_PARAMS {
+            assert!(body.get(*param).is_none(), "{} should be stripped", param);
+        }
+        assert_eq!(body["temperature"], 0.2);
+        assert_eq!(
+            body["messages"][0],
+            serde_json::json!({"role": "user", "content": "hi"})
+        );
+    }
+
+    #[cfg(any(feature = "openai", feature = "ollama"))]
+    fn full_params() -> GenerationParams {
+        GenerationParams::new()
+            .with_temperature(0.5)
+            .with_top_p(0.75)
+            .with_max_tokens(256)
+            .with_stop(["\n\n", "END"])
+            .with_presence_penalty(0.5)
+            .with_frequency_penalty(-1.5)
+            .with_seed(42)
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_openai_generation_params_body() {
+        let body = OpenAIProvider::chat_request_body(
+            vec![ChatMessage::user("hi")],
+            Some("gpt-4o"),
+            &full_params(),
+        );
+
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [ChatMessage::user("hi")],
+                "temperature": 0.5,
+                "top_p": 0.75,
+                "max_tokens": 256,
+                "stop": ["\n\n", "END"],
+                "presence_penalty": 0.5,
+                "frequency_penalty": -1.5,
+                "seed": 42,
+            })
+        );
+
+        // Mistral takes the seed as `random_seed`
+        let mistral =
+            OpenAIProvider::compatible(Provider::Mistral, "test-key".to_string(), None).unwrap();
+        let mut body = body;
+        mistral.apply_quirks(&mut body);
+        assert!(body.get("seed").is_none());
+        assert_eq!(body["random_seed"], 42);
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_per_request_headers() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("x-org-id", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+            None,
+        )
+        .unwrap();
+        let response = provider
+            .chat_completion_with_params(
+                vec![ChatMessage::user("hi")],
+                vec![],
+                None,
+                &GenerationParams::new().with_header("x-org-id", "acme"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.content, "ok");
+
+        let err = provider
+            .chat_completion_with_params(
+                vec![ChatMessage::user("hi")],
+                vec![],
+                None,
+                &GenerationParams::new().with_header("bad header", "x"),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Validation { .. }));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_logprobs_fixture() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "logprobs": true,
+                "top_logprobs": 3,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {"role": "assistant", "content": "refund"},
+                    "logprobs": {"content": [{
+                        "token": "refund",
+                        "logprob": -0.5,
+                        "bytes": [114, 101, 102, 117, 110, 100],
+                        "top_logprobs": [
+                            {"token": "refund", "logprob": -0.5, "bytes": null},
+                            {"token": " billing", "logprob": -1.5, "bytes": null},
+                            {"token": "other", "logprob": -4.0, "bytes": null}
+                        ]
+                    }]}
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+            None,
+        )
+        .unwrap();
+
+        let response = provider
+            .chat_completion_with_params(
+                vec![ChatMessage::user("Route this ticket")],
+                vec![],
+                None,
+                &GenerationParams::new().with_logprobs(3),
+            )
+            .await
+            .unwrap();
+
+        let logprobs = response.logprobs.as_ref().unwrap();
+        assert_eq!(logprobs.len(), 1);
+        assert_eq!(logprobs[0].token, "refund");
+        assert_eq!(logprobs[0].logprob, -0.5);
+        assert_eq!(logprobs[0].top_logprobs[1].token, " billing");
+
+        let confidence = response
+            .label_confidence(&["refund", "billing", "shipping"])
+            .unwrap();
+        let expected = 1.0 / (1.0 + (-1.0f64).exp());
+        assert!((confidence["refund"] - expected).abs() < 1e-9);
+        assert!((confidence["billing"] - (1.0 - expected)).abs() < 1e-9);
+        assert_eq!(confidence["shipping"], 0.0);
+    }
+
+    #[cfg(feature = "ollama")]
+    #[test]
+    fn test_ollama_generation_params_options() {
+        let body = OllamaProvider::chat_request_body(
+            vec![ChatMessage::user("hi")],
+            Some("llama3.2:3b"),
+            &full_params(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            body["options"],
+            serde_json::json!({
+                "temperature": 0.5,
+                "top_p": 0.75,
+                "num_predict": 256,
+                "stop": ["\n\n", "END"],
+                "presence_penalty": 0.5,
+                "frequency_penalty": -1.5,
+                "seed": 42,
+            })
+        );
+
+        let body = OllamaProvider::chat_request_body(
+            vec![ChatMessage::user("hi")],
+            None,
+            &GenerationParams::default(),
+        )
+        .unwrap();
+        assert!(body.get("options").is_none());
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_groq_structured_output_uses_json_mode() {
+        use wiremock::matchers::{body_partial_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("authorization", "Bearer groq-key"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "llama-3.3-70b-versatile",
+                "response_format": {"type": "json_object"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "{\"x\": 1}"}}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::compatible(
+            Provider::Groq,
+            "groq-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+        )
+        .unwrap();
+        let schema = OutputSchema::new("point", serde_json::json!({"type": "object"}));
+
+        let response = provider
+            .chat_completion_structured(vec![ChatMessage::user("x?")], &schema, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "{\"x\": 1}");
+
+        let request: serde_json::Value = server.received_requests().await.unwrap()[0]
+            .body_json()
+            .unwrap();
+        let messages = request["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["role"], "system");
+        assert!(messages
+            .iter()
+            .all(|message| message.get("timestamp").is_none()));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_mistral_tool_calls_round_trip() {
+        use crate::message::ToolCall;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "mistral-small-latest",
+                "messages": [
+                    {"role": "user", "content": "Weather in Paris?"},
+                    {
+                        "role": "assistant",
+                        "tool_calls": [{
+                            "id": "abc123xyz",
+                            "type": "function",
+                            "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                        }]
+                    },
+                    {"role": "tool", "tool_call_id": "abc123xyz", "name": "get_weather", "content": "18C"}
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{
+                        "id": "def456uvw",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\": \"Lyon\"}"}
+                    }]
+                }}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::compatible(
+            Provider::Mistral,
+            "mistral-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+        )
+        .unwrap();
+        assert_eq!(provider.name(), "Mistral");
+
+        let mut assistant = ChatMessage::assistant("");
+        assistant.tool_calls = Some(vec![ToolCall::function(
+            "abc123xyz",
+            "get_weather",
+            serde_json::json!({"city": "Paris"}),
+        )]);
+        let messages = vec![
+            ChatMessage::user("Weather in Paris?"),
+            assistant,
+            ChatMessage::tool("abc123xyz", "18C"),
+        ];
+
+        let response = provider
+            .chat_completion_with_tools(messages, vec![], None, None, None)
+            .await
+            .unwrap();
+
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls[0].id, "def456uvw");
+        assert_eq!(
+            calls[0].function.arguments,
+            serde_json::json!({"city": "Lyon"})
+        );
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_image_parts() {
+        use crate::ImageSource;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [
+                    {
+                        "role": "user",
+                        "content": [
+                            {"type": "text", "text": "Compare these"},
+                            {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+                        ]
+                    },
+                    {
+                        "role": "user",
+                        "content": [
+                            {"type": "text", "text": "and this"},
+                            {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,/9j/4AAQ"}}
+                        ]
+                    }
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "Two cats."}}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+            None,
+        )
+        .unwrap();
+        let messages = vec![
+            ChatMessage::user_with_image(
+                "Compare these",
+                ImageSource::Url("https://example.com/a.png".to_string()),
+            ),
+            ChatMessage::user_with_image(
+                "and this",
+                ImageSource::Base64 {
+                    media_type: "image/jpeg".to_string(),
+                    data: "/9j/4AAQ".to_string(),
+                },
+            ),
+        ];
+
+        let response = provider
+            .chat_completion(messages, Some("gpt-4o"), None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Two cats.");
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_text_only_model_rejects_images() {
+        use crate::ImageSource;
+
+        let provider = OpenAIProvider::new("test-key".to_string(), None, None).unwrap();
+        let err = provider
+            .chat_completion(
+                vec![ChatMessage::user_with_image(
+                    "What is this?",
+                    ImageSource::Url("https://example.com/a.png".to_string()),
+                )],
+                Some("gpt-3.5-turbo"),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RsllmError::Unsupported { .. }));
+        assert_eq!(err.category(), "unsupported");
+        assert!(err.to_string().contains("gpt-3.5-turbo"));
+    }
+
+    #[cfg(feature = "ollama")]
+    #[tokio::test]
+    async fn test_ollama_images_array() {
+        use crate::ImageSource;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "llava",
+                "messages": [{
+                    "role": "user",
+                    "content": "Describe this",
+                    "images": ["iVBORw0KGgo="]
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "message": {"role": "assistant", "content": "A chart."}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OllamaProvider::new(Some(Url::parse(&server.uri()).unwrap())).unwrap();
+        let response = provider
+            .chat_completion(
+                vec![ChatMessage::user_with_image(
+                    "Describe this",
+                    ImageSource::Base64 {
+                        media_type: "image/png".to_string(),
+                        data: "iVBORw0KGgo=".to_string(),
+                    },
+                )],
+                Some("llava"),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.content, "A chart.");
+
+        // Ollama cannot fetch URLs
+        let err = provider
+            .chat_completion(
+                vec![ChatMessage::user_with_image(
+                    "Describe this",
+                    ImageSource::Url("https://example.com/chart.png".to_string()),
+                )],
+                Some("llava"),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Unsupported { .. }));
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn test_openai_list_models() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [
+                    {"id": "gpt-4o-mini", "object": "model", "owned_by": "system"},
+                    {"id": "text-embedding-3-small", "object": "model", "owned_by": "system"},
+                    {"id": "ft:custom", "object": "model"}
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+            None,
+        )
+        .unwrap();
+        let models = provider.list_models().await.unwrap();
+
+        assert_eq!(models.len(), 3);
+        assert_eq!(models[0].id, "gpt-4o-mini");
+        assert_eq!(models[0].provider, Provider::OpenAI);
+        assert_eq!(models[0].owned_by.as_deref(), Some("system"));
+        assert_eq!(models[0].supports_vision, Some(true));
+        assert_eq!(models[0].context_window, Some(128_000));
+        assert_eq!(models[1].supports_tools, Some(false));
+        assert_eq!(models[2].owned_by, None);
+        assert_eq!(models[2].supports_tools, None);
+    }
+
+    #[cfg(feature = "ollama")]
+    #[tokio::test]
+    async fn test_ollama_list_models() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [
+                    {"name": "llama3.1:8b", "model": "llama3.1:8b", "size": 4661224676u64},
+                    {"name": "llava:latest", "model": "llava:latest", "size": 4733363377u64}
+                ]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let provider =
+            OllamaProvider::new(Some(Url::parse(&format!("{}/api", server.uri())).unwrap()))
+                .unwrap();
+        let models = provider.list_models().await.unwrap();
+
+        let ids: Vec<_> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(ids, ["llama3.1:8b", "llava:latest"]);
+        assert_eq!(models[0].provider, Provider::Ollama);
+        assert_eq!(models[0].supports_tools, Some(true));
+        assert_eq!(models[0].context_window, Some(128_000));
+        assert_eq!(models[1].supports_vision, Some(true));
+        provider.validate().await.unwrap();
+    }
+
+    #[cfg(feature = "openai")]
+    #[test]
+    fn test_openai_usage_parsing() {
+        let usage = openai_usage(&serde_json::json!({
+            "usage": {
+                "prompt_tokens": 120,
+                "completion_tokens": 30,
+                "total_tokens": 150,
+                "prompt_tokens_details": {"cached_tokens": 100},
+                "completion_tokens_details": {"reasoning_tokens": 12}
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(usage.prompt_tokens, 120);
+        assert_eq!(usage.completion_tokens, 30);
+        assert_eq!(usage.total_tokens, 150);
+        assert_eq!(usage.cached_tokens, Some(100));
+        assert_eq!(usage.reasoning_tokens, Some(12));
+        assert!(openai_usage(&serde_json::json!({"choices": []})).is_none());
+    }
+
+    #[cfg(feature = "ollama")]
+    #[tokio::test]
+    async fn test_ollama_usage_counts_and_estimate() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "message": {"role": "assistant", "content": "Hello there"},
+                "prompt_eval_count": 26,
+                "eval_count": 8
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "message": {"role": "assistant", "content": "Hello there"}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OllamaProvider::new(Some(Url::parse(&server.uri()).unwrap())).unwrap();
+        let messages = vec![ChatMessage::user("Say hello to the whole team")];
+
+        let reported = provider
+            .chat_completion(messages.clone(), None, None, None)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert_eq!(reported.prompt_tokens, 26);
+        assert_eq!(reported.completion_tokens, 8);
+
+        // Without eval counters the usage is estimated from the text
+        let estimated = provider
+            .chat_completion(messages, None, None, None)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert!(estimated.prompt_tokens > 0);
+        assert!(estimated.completion_tokens > 0);
+        assert_eq!(
+            estimated.total_tokens,
+            estimated.prompt_tokens + estimated.completion_tokens
+        );
+    }
 }
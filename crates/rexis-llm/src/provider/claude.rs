@@ -0,0 +1,796 @@
+//! Anthropic Claude provider
+//!
+//! Speaks the Messages API. System messages are sent as top-level `system` blocks,
+//! tool results travel as `tool_result` blocks in user turns, and messages marked
+//! with [`ChatMessage::cache`] become `cache_control` breakpoints. Structured
+//! output forces a single tool whose input schema is the output schema.
+
+use super::{
+    error_from_response, http_client, normalize_base_url, with_request_headers, LLMProvider,
+    Provider,
+};
+use crate::message::{AttachmentContent, ToolCall};
+use crate::models::ModelInfo;
+use crate::params::GenerationParams;
+use crate::response::Usage;
+use crate::structured::OutputSchema;
+use crate::tools::ToolDefinition;
+use crate::{
+    ChatMessage, ChatResponse, MessageContent, MessageRole, RsllmError, RsllmResult, StreamChunk,
+};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use url::Url;
+
+/// Messages API version sent with every request
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// The Messages API requires `max_tokens`; used when the caller sets none
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Anthropic Claude provider implementation
+pub struct ClaudeProvider {
+    client: reqwest::Client,
+    api_key: String,
+    base_url: Url,
+}
+
+impl ClaudeProvider {
+    /// Create a new Claude provider
+    pub fn new(api_key: String, base_url: Option<Url>) -> RsllmResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| {
+                RsllmError::configuration_with_source("Failed to create HTTP client", e)
+            })?;
+
+        let base = base_url.unwrap_or_else(|| Provider::Claude.default_base_url());
+
+        Ok(Self {
+            client,
+            api_key,
+            base_url: normalize_base_url(&base),
+        })
+    }
+
+    /// Apply timeouts, user agent, proxy and TLS settings from the client configuration
+    pub fn with_http_config(mut self, http: &crate::config::HttpConfig) -> RsllmResult<Self> {
+        self.client = http_client(http)?;
+        Ok(self)
+    }
+
+    /// Build request headers
+    fn build_headers(&self) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+
+        headers.insert("x-api-key", self.api_key.parse().unwrap());
+        headers.insert("anthropic-version", ANTHROPIC_VERSION.parse().unwrap());
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            "application/json".parse().unwrap(),
+        );
+
+        headers
+    }
+
+    /// Build a Messages API request body
+    fn request_body(
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        model: &str,
+        params: &GenerationParams,
+    ) -> Value {
+        let mut system = Vec::new();
+        let mut turns: Vec<(&'static str, Vec<Value>)> = Vec::new();
+
+        for message in messages {
+            let (role, mut blocks) = match message.role {
+                MessageRole::System => {
+                    let mut blocks = content_blocks(&message.content);
+                    if message.cache {
+                        mark_cached(&mut blocks);
+                    }
+                    system.extend(blocks);
+                    continue;
+                }
+                MessageRole::User => ("user", content_blocks(&message.content)),
+                MessageRole::Assistant => {
+                    let mut blocks = content_blocks(&message.content);
+                    for call in message.tool_calls.iter().flatten() {
+                        blocks.push(json!({
+                            "type": "tool_use",
+                            "id": call.id,
+                            "name": call.function.name,
+                            "input": call.function.arguments,
+                        }));
+                    }
+                    ("assistant", blocks)
+                }
+                MessageRole::Tool => (
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": message.tool_call_id.as_deref().unwrap_or_default(),
+                        "content": message.content.text_content().unwrap_or_default(),
+                    })],
+                ),
+            };
+
+            if blocks.is_empty() {
+                continue;
+            }
+
+            if message.cache {
+                mark_cached(&mut blocks);
+            }
+
+            // The Messages API requires alternating roles, so merge consecutive turns
+            match turns.last_mut() {
+                Some((last_role, last_blocks)) if *last_role == role => last_blocks.extend(blocks),
+                _ => turns.push((role, blocks)),
+            }
+        }
+
+        let messages: Vec<Value> = turns
+            .into_iter()
+            .map(|(role, content)| json!({ "role": role, "content": content }))
+            .collect();
+
+        let mut body = json!({
+            "model": model,
+            "messages": messages,
+            "max_tokens": params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        });
+
+        if !system.is_empty() {
+            body["system"] = Value::Array(system);
+        }
+
+        if let Some(temp) = params.temperature {
+            body["temperature"] = temp.into();
+        }
+
+        if let Some(top_p) = params.top_p {
+            body["top_p"] = top_p.into();
+        }
+
+        if let Some(stop) = params.stop_sequences() {
+            body["stop_sequences"] = stop.into();
+        }
+
+        params.warn_dropped("Claude", &["presence_penalty", "frequency_penalty", "seed"]);
+
+        if !tools.is_empty() {
+            let tools: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters,
+                    })
+                })
+                .collect();
+            body["tools"] = Value::Array(tools);
+        }
+
+        body
+    }
+
+    /// Send a Messages API request
+    async fn send_request(
+        &self,
+        body: Value,
+        model: &str,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let url = self.base_url.join("messages")?;
+
+        let response = self
+            .client
+            .post(url)
+            .headers(with_request_headers(self.build_headers(), params)?)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response("Claude", response).await);
+        }
+
+        let response_data: Value = response.json().await?;
+        parse_response(&response_data, model)
+    }
+}
+
+/// Convert message content into Messages API content blocks
+fn content_blocks(content: &MessageContent) -> Vec<Value> {
+    use base64::Engine;
+
+    let mut blocks = Vec::new();
+
+    if let Some(text) = content.text_content() {
+        if !text.is_empty() {
+            blocks.push(json!({ "type": "text", "text": text }));
+        }
+    }
+
+    for attachment in content.attachments() {
+        let source = match &attachment.content {
+            AttachmentContent::Base64 { mime_type, data } => json!({
+                "type": "base64",
+                "media_type": mime_type,
+                "data": data,
+            }),
+            AttachmentContent::Bytes { mime_type, data } => json!({
+                "type": "base64",
+                "media_type": mime_type,
+                "data": base64::engine::general_purpose::STANDARD.encode(data),
+            }),
+            AttachmentContent::Url { url } => json!({ "type": "url", "url": url }),
+        };
+        blocks.push(json!({ "type": "image", "source": source }));
+    }
+
+    blocks
+}
+
+/// Put a `cache_control` breakpoint on the last block of a message
+fn mark_cached(blocks: &mut [Value]) {
+    if let Some(last) = blocks.last_mut() {
+        last["cache_control"] = json!({ "type": "ephemeral" });
+    }
+}
+
+/// Parse a Messages API response
+fn parse_response(response: &Value, model: &str) -> RsllmResult<ChatResponse> {
+    let stop_reason = response["stop_reason"].as_str().unwrap_or("end_turn");
+    if stop_reason == "refusal" {
+        return Err(RsllmError::content_filtered(
+            "Claude",
+            "the model declined to respond",
+        ));
+    }
+
+    let mut content = String::new();
+    let mut tool_calls = Vec::new();
+
+    for block in response["content"].as_array().into_iter().flatten() {
+        match block["type"].as_str() {
+            Some("text") => content.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("tool_use") => {
+                let (Some(id), Some(name)) = (block["id"].as_str(), block["name"].as_str()) else {
+                    continue;
+                };
+                tool_calls.push(ToolCall::function(
+                    id,
+                    name,
+                    block.get("input").cloned().unwrap_or_else(|| json!({})),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let finish_reason = match stop_reason {
+        "tool_use" => "tool_calls",
+        "max_tokens" => "length",
+        _ => "stop",
+    };
+    let model = response["model"].as_str().unwrap_or(model);
+    let mut chat_response = ChatResponse::new(content, model).with_finish_reason(finish_reason);
+
+    if let Some(id) = response["id"].as_str() {
+        chat_response = chat_response.with_id(id);
+    }
+
+    if let Some(usage) = parse_usage(response) {
+        chat_response = chat_response.with_usage(usage);
+    }
+
+    if !tool_calls.is_empty() {
+        chat_response = chat_response.with_tool_calls(tool_calls);
+    }
+
+    Ok(chat_response)
+}
+
+/// Turn the forced tool call of a structured response into its JSON content
+fn structured_content(mut response: ChatResponse, tool: &str) -> ChatResponse {
+    let input = response
+        .tool_calls
+        .iter()
+        .flatten()
+        .find(|call| call.function.name == tool)
+        .map(|call| call.function.arguments.to_string());
+
+    if let Some(input) = input {
+        response.content = input;
+        response.tool_calls = None;
+        response.finish_reason = Some("stop".to_string());
+    }
+    response
+}
+
+/// Parse `usage`, including prompt-cache reads and writes
+///
+/// Anthropic reports `input_tokens` excluding cached tokens, so cache reads and
+/// writes are added back to give the full prompt size.
+fn parse_usage(response: &Value) -> Option<Usage> {
+    let usage = response.get("usage")?;
+    let count = |field: &str| usage[field].as_u64().map(|tokens| tokens as u32);
+
+    let input = count("input_tokens").unwrap_or(0);
+    let output = count("output_tokens").unwrap_or(0);
+    let cache_read = count("cache_read_input_tokens");
+    let cache_creation = count("cache_creation_input_tokens");
+
+    let mut parsed = Usage::new(
+        input + cache_read.unwrap_or(0) + cache_creation.unwrap_or(0),
+        output,
+    );
+    parsed.cached_tokens = cache_read;
+    parsed.cache_creation_tokens = cache_creation;
+    Some(parsed)
+}
+
+#[async_trait]
+impl LLMProvider for ClaudeProvider {
+    fn name(&self) -> &str {
+        "Claude"
+    }
+
+    fn provider_type(&self) -> Provider {
+        Provider::Claude
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        Provider::Claude
+            .default_models()
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        let url = self.base_url.join("models")?;
+        let response = self
+            .client
+            .get(url)
+            .headers(self.build_headers())
+            .send()
+            .await?;
+
+        Ok(response.status().is_success())
+    }
+
+    async fn list_models(&self) -> RsllmResult<Vec<ModelInfo>> {
+        // Anthropic's catalogue is small and stable; serve the static list
+        Ok(self
+            .supported_models()
+            .into_iter()
+            .map(|id| ModelInfo::new(id, Provider::Claude).with_owned_by("anthropic"))
+            .collect())
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let model = model.unwrap_or(Provider::Claude.default_model());
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let body = Self::request_body(&messages, &[], model, &params);
+        self.send_request(body, model, &params).await
+    }
+
+    async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let model = model.unwrap_or(Provider::Claude.default_model());
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let body = Self::request_body(&messages, &tools, model, &params);
+        self.send_request(body, model, &params).await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        params.reject_logprobs(self.name())?;
+        let model = model.unwrap_or(Provider::Claude.default_model());
+        let body = Self::request_body(&messages, &tools, model, params);
+        self.send_request(body, model, params).await
+    }
+
+    async fn chat_completion_structured(
+        &self,
+        messages: Vec<ChatMessage>,
+        schema: &OutputSchema,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        // Tool inputs must be objects; other schemas travel as an instruction
+        if schema.schema["type"] != "object" {
+            return self
+                .chat_completion(
+                    schema.apply_instruction(messages),
+                    model,
+                    temperature,
+                    max_tokens,
+                )
+                .await;
+        }
+
+        let model = model.unwrap_or(Provider::Claude.default_model());
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let tool = ToolDefinition::new(
+            &schema.name,
+            "Respond with output matching this schema",
+            schema.schema.clone(),
+        );
+        let mut body = Self::request_body(&messages, &[tool], model, &params);
+        body["tool_choice"] = json!({ "type": "tool", "name": schema.name });
+
+        let response = self.send_request(body, model, &params).await?;
+        Ok(structured_content(response, &schema.name))
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>>
+    {
+        // The complete response is replayed as a single chunk
+        let response = self
+            .chat_completion(messages, model.as_deref(), temperature, max_tokens)
+            .await?;
+
+        let chunks = vec![
+            Ok(StreamChunk::delta(response.content, &response.model)),
+            Ok(StreamChunk::done(&response.model)
+                .with_finish_reason(response.finish_reason.unwrap_or_else(|| "stop".into()))),
+        ];
+
+        Ok(Box::new(futures_util::stream::iter(chunks)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(server: &MockServer) -> ClaudeProvider {
+        ClaudeProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cache_control_request_mapping() {
+        let messages = vec![
+            ChatMessage::system_cached("You are a long-lived agent with a long system prompt."),
+            ChatMessage::system("Today is Tuesday."),
+            ChatMessage::user("Remembered facts: ...").with_cache(true),
+            ChatMessage::user("What's next?"),
+        ];
+
+        let body = ClaudeProvider::request_body(
+            &messages,
+            &[],
+            "claude-3-5-sonnet-20241022",
+            &GenerationParams::default(),
+        );
+
+        assert_eq!(
+            body["system"],
+            json!([
+                {
+                    "type": "text",
+                    "text": "You are a long-lived agent with a long system prompt.",
+                    "cache_control": {"type": "ephemeral"}
+                },
+                {"type": "text", "text": "Today is Tuesday."}
+            ])
+        );
+
+        // Consecutive user messages merge, keeping the breakpoint on its own block
+        let content = body["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(content[0]["cache_control"], json!({"type": "ephemeral"}));
+        assert!(content[1].get("cache_control").is_none());
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_generation_params_mapping() {
+        let params = GenerationParams::new()
+            .with_temperature(0.5)
+            .with_top_p(0.75)
+            .with_max_tokens(256)
+            .with_stop(["END"])
+            .with_presence_penalty(0.5)
+            .with_seed(42);
+        let body = ClaudeProvider::request_body(
+            &[ChatMessage::user("hi")],
+            &[],
+            "claude-3-5-sonnet-20241022",
+            &params,
+        );
+
+        assert_eq!(body["temperature"], 0.5);
+        assert_eq!(body["top_p"], 0.75);
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["stop_sequences"], json!(["END"]));
+        // No penalties or seed in the Messages API
+        assert!(body.get("presence_penalty").is_none());
+        assert!(body.get("seed").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_logprobs_unsupported() {
+        let server = MockServer::start().await;
+
+        let err = provider(&server)
+            .chat_completion_with_params(
+                vec![ChatMessage::user("hi")],
+                vec![],
+                None,
+                &GenerationParams::new().with_logprobs(5),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(err, RsllmError::Unsupported { ref feature, .. } if feature == "logprobs")
+        );
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_image_source_blocks() {
+        use crate::ImageSource;
+
+        let messages = vec![
+            ChatMessage::user_with_image(
+                "Compare",
+                ImageSource::Base64 {
+                    media_type: "image/png".to_string(),
+                    data: "iVBORw0KGgo=".to_string(),
+                },
+            ),
+            ChatMessage::user_with_image(
+                "with",
+                ImageSource::Url("https://example.com/b.jpg".to_string()),
+            ),
+        ];
+
+        let body = ClaudeProvider::request_body(
+            &messages,
+            &[],
+            "claude-3-5-sonnet-20241022",
+            &GenerationParams::default(),
+        );
+
+        assert_eq!(
+            body["messages"][0]["content"],
+            json!([
+                {"type": "text", "text": "Compare"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}},
+                {"type": "text", "text": "with"},
+                {"type": "image", "source": {"type": "url", "url": "https://example.com/b.jpg"}}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_model_list_and_validate() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(header("x-api-key", "test-key"))
+            .and(body_partial_json(json!({"max_tokens": 1})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-5-sonnet-20241022",
+                "content": [{"type": "text", "text": "Hi"}],
+                "stop_reason": "max_tokens",
+                "usage": {"input_tokens": 8, "output_tokens": 1}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server);
+        let models = provider.list_models().await.unwrap();
+        assert!(!models.is_empty());
+        assert!(models.iter().all(|model| {
+            model.provider == Provider::Claude
+                && model.owned_by.as_deref() == Some("anthropic")
+                && model.context_window == Some(200_000)
+        }));
+
+        // Listing is offline, so validation spends a one-token completion
+        provider.validate().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cache_usage_parsed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(header("x-api-key", "test-key"))
+            .and(header("anthropic-version", ANTHROPIC_VERSION))
+            .and(body_partial_json(json!({
+                "system": [{"type": "text", "text": "Long instructions", "cache_control": {"type": "ephemeral"}}],
+                "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-3-5-haiku-20241022",
+                "content": [{"type": "text", "text": "Hello!"}],
+                "stop_reason": "end_turn",
+                "usage": {
+                    "input_tokens": 10,
+                    "output_tokens": 5,
+                    "cache_read_input_tokens": 1800,
+                    "cache_creation_input_tokens": 200
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = provider(&server)
+            .chat_completion(
+                vec![
+                    ChatMessage::system_cached("Long instructions"),
+                    ChatMessage::user("Hi"),
+                ],
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "Hello!");
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 2010);
+        assert_eq!(usage.completion_tokens, 5);
+        assert_eq!(usage.cached_tokens, Some(1800));
+        assert_eq!(usage.cache_creation_tokens, Some(200));
+        assert_eq!(usage.effective_prompt_tokens(), 210);
+    }
+
+    #[tokio::test]
+    async fn test_tool_use_round_trip() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(json!({
+                "messages": [
+                    {"role": "user", "content": [{"type": "text", "text": "Weather in Paris?"}]},
+                    {"role": "assistant", "content": [
+                        {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+                    ]},
+                    {"role": "user", "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_1", "content": "18C"}
+                    ]}
+                ],
+                "tools": [{"name": "get_weather", "input_schema": {"type": "object"}}]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [
+                    {"type": "tool_use", "id": "toolu_2", "name": "get_weather", "input": {"city": "Lyon"}}
+                ],
+                "stop_reason": "tool_use",
+                "usage": {"input_tokens": 40, "output_tokens": 12}
+            })))
+            .mount(&server)
+            .await;
+
+        let messages = vec![
+            ChatMessage::user("Weather in Paris?"),
+            ChatMessage::assistant("").with_tool_calls(vec![ToolCall::function(
+                "toolu_1",
+                "get_weather",
+                json!({"city": "Paris"}),
+            )]),
+            ChatMessage::tool("toolu_1", "18C"),
+        ];
+        let tools = vec![ToolDefinition::new(
+            "get_weather",
+            "Get the weather",
+            json!({"type": "object"}),
+        )];
+
+        let response = provider(&server)
+            .chat_completion_with_tools(messages, tools, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.finish_reason.as_deref(), Some("tool_calls"));
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls[0].id, "toolu_2");
+        assert_eq!(calls[0].function.arguments, json!({"city": "Lyon"}));
+
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.cached_tokens, None);
+        assert_eq!(usage.cache_creation_tokens, None);
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_forces_schema_tool() {
+        let schema = OutputSchema::new(
+            "Sentiment",
+            json!({
+                "type": "object",
+                "properties": {"label": {"type": "string"}, "score": {"type": "number"}},
+                "required": ["label", "score"]
+            }),
+        );
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(json!({
+                "tools": [{"name": "Sentiment", "input_schema": schema.schema}],
+                "tool_choice": {"type": "tool", "name": "Sentiment"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [
+                    {"type": "tool_use", "id": "toolu_1", "name": "Sentiment", "input": {"label": "positive", "score": 0.9}}
+                ],
+                "stop_reason": "tool_use",
+                "usage": {"input_tokens": 30, "output_tokens": 10}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = provider(&server)
+            .chat_completion_structured(
+                vec![ChatMessage::user("I love this!")],
+                &schema,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let content: Value = serde_json::from_str(&response.content).unwrap();
+        assert_eq!(content, json!({"label": "positive", "score": 0.9}));
+        assert!(response.tool_calls.is_none());
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+
+        // The schema is not repeated as an instruction
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body.get("system").is_none());
+    }
+}
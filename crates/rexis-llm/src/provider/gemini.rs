@@ -0,0 +1,716 @@
+//! Google Gemini provider
+//!
+//! Speaks the `generateContent` / `streamGenerateContent` API. System messages are
+//! sent as `systemInstruction`, assistant turns use the `model` role, and tool
+//! results are sent back as `functionResponse` parts.
+
+use super::{
+    error_from_response, http_client, normalize_base_url, sse_tool_stream, with_request_headers,
+    LLMProvider, Provider,
+};
+use crate::message::{AttachmentContent, ToolCall};
+use crate::params::GenerationParams;
+use crate::response::{ToolCallDelta, ToolFunctionDelta, Usage};
+use crate::streaming::{ToolAwareDelta, ToolAwareStream};
+use crate::structured::OutputSchema;
+use crate::tools::ToolDefinition;
+use crate::{
+    ChatMessage, ChatResponse, MessageContent, MessageRole, RsllmError, RsllmResult, StreamChunk,
+};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use url::Url;
+
+/// Finish reasons that mean the candidate was withheld
+const BLOCKED_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+    "RECITATION",
+];
+
+/// Google Gemini provider implementation
+pub struct GeminiProvider {
+    client: reqwest::Client,
+    api_key: String,
+    base_url: Url,
+}
+
+impl GeminiProvider {
+    /// Create a new Gemini provider
+    pub fn new(api_key: String, base_url: Option<Url>) -> RsllmResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| {
+                RsllmError::configuration_with_source("Failed to create HTTP client", e)
+            })?;
+
+        let base = base_url.unwrap_or_else(|| Provider::Gemini.default_base_url());
+
+        Ok(Self {
+            client,
+            api_key,
+            base_url: normalize_base_url(&base),
+        })
+    }
+
+    /// Apply timeouts, user agent, proxy and TLS settings from the client configuration
+    pub fn with_http_config(mut self, http: &crate::config::HttpConfig) -> RsllmResult<Self> {
+        self.client = http_client(http)?;
+        Ok(self)
+    }
+
+    /// Build request headers
+    fn build_headers(&self) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+
+        headers.insert("x-goog-api-key", self.api_key.parse().unwrap());
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            "application/json".parse().unwrap(),
+        );
+
+        headers
+    }
+
+    /// Build a `generateContent` request body
+    fn request_body(
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Value {
+        // Tool results only carry the call id; Gemini wants the function name
+        let call_names: HashMap<&str, &str> = messages
+            .iter()
+            .flat_map(|message| message.tool_calls.iter().flatten())
+            .map(|call| (call.id.as_str(), call.function.name.as_str()))
+            .collect();
+
+        let mut system_parts = Vec::new();
+        let mut contents = Vec::new();
+
+        for message in messages {
+            match message.role {
+                MessageRole::System => system_parts.extend(content_parts(&message.content)),
+                MessageRole::User => contents.push(json!({
+                    "role": "user",
+                    "parts": content_parts(&message.content),
+                })),
+                MessageRole::Assistant => {
+                    let mut parts = content_parts(&message.content);
+                    for call in message.tool_calls.iter().flatten() {
+                        parts.push(json!({
+                            "functionCall": {
+                                "name": call.function.name,
+                                "args": call.function.arguments,
+                            }
+                        }));
+                    }
+                    contents.push(json!({ "role": "model", "parts": parts }));
+                }
+                MessageRole::Tool => {
+                    let name = message
+                        .tool_call_id
+                        .as_deref()
+                        .and_then(|id| call_names.get(id).copied())
+                        .or(message.name.as_deref())
+                        .unwrap_or("tool");
+                    contents.push(json!({
+                        "role": "user",
+                        "parts": [{
+                            "functionResponse": {
+                                "name": name,
+                                "response": function_response(&message.content),
+                            }
+                        }],
+                    }));
+                }
+            }
+        }
+
+        let mut body = json!({ "contents": contents });
+
+        if !system_parts.is_empty() {
+            body["systemInstruction"] = json!({ "parts": system_parts });
+        }
+
+        if !tools.is_empty() {
+            let declarations: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": tool.parameters,
+                    })
+                })
+                .collect();
+            body["tools"] = json!([{ "functionDeclarations": declarations }]);
+        }
+
+        let mut generation_config = serde_json::Map::new();
+        if let Some(temp) = params.temperature {
+            generation_config.insert("temperature".to_string(), temp.into());
+        }
+        if let Some(top_p) = params.top_p {
+            generation_config.insert("topP".to_string(), top_p.into());
+        }
+        if let Some(max_tokens) = params.max_tokens {
+            generation_config.insert("maxOutputTokens".to_string(), max_tokens.into());
+        }
+        if let Some(stop) = params.stop_sequences() {
+            generation_config.insert("stopSequences".to_string(), stop.into());
+        }
+        if let Some(penalty) = params.presence_penalty {
+            generation_config.insert("presencePenalty".to_string(), penalty.into());
+        }
+        if let Some(penalty) = params.frequency_penalty {
+            generation_config.insert("frequencyPenalty".to_string(), penalty.into());
+        }
+        if let Some(seed) = params.seed {
+            generation_config.insert("seed".to_string(), seed.into());
+        }
+        if !generation_config.is_empty() {
+            body["generationConfig"] = Value::Object(generation_config);
+        }
+
+        body
+    }
+
+    /// Send a non-streaming `generateContent` request
+    async fn send_request(
+        &self,
+        body: Value,
+        model: &str,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let url = self
+            .base_url
+            .join(&format!("models/{}:generateContent", model))?;
+
+        let response = self
+            .client
+            .post(url)
+            .headers(with_request_headers(self.build_headers(), params)?)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response("Gemini", response).await);
+        }
+
+        let response_data: Value = response.json().await?;
+        parse_response(&response_data, model)
+    }
+
+    /// Start a `streamGenerateContent` request
+    async fn send_stream_request(&self, body: Value, model: &str) -> RsllmResult<ToolAwareStream> {
+        let url = self
+            .base_url
+            .join(&format!("models/{}:streamGenerateContent?alt=sse", model))?;
+
+        let response = self
+            .client
+            .post(url)
+            .headers(self.build_headers())
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(error_from_response("Gemini", response).await);
+        }
+
+        let mut next_index = 0u32;
+        Ok(sse_tool_stream(response, move |data| {
+            parse_stream_event(data, &mut next_index)
+        }))
+    }
+}
+
+/// Convert message content into Gemini parts
+fn content_parts(content: &MessageContent) -> Vec<Value> {
+    use base64::Engine;
+
+    let mut parts = Vec::new();
+
+    if let Some(text) = content.text_content() {
+        if !text.is_empty() {
+            parts.push(json!({ "text": text }));
+        }
+    }
+
+    for attachment in content.attachments() {
+        match &attachment.content {
+            AttachmentContent::Base64 { mime_type, data } => parts.push(json!({
+                "inlineData": { "mimeType": mime_type, "data": data }
+            })),
+            AttachmentContent::Bytes { mime_type, data } => parts.push(json!({
+                "inlineData": {
+                    "mimeType": mime_type,
+                    "data": base64::engine::general_purpose::STANDARD.encode(data),
+                }
+            })),
+            AttachmentContent::Url { url } => parts.push(json!({
+                "fileData": { "fileUri": url }
+            })),
+        }
+    }
+
+    parts
+}
+
+/// Tool output as a `functionResponse.response` object
+fn function_response(content: &MessageContent) -> Value {
+    let text = content.text_content().unwrap_or("");
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(object)) => Value::Object(object),
+        Ok(value) => json!({ "result": value }),
+        Err(_) => json!({ "result": text }),
+    }
+}
+
+/// Return a typed error when the prompt or candidate was blocked
+fn check_blocked(response: &Value) -> RsllmResult<()> {
+    if let Some(reason) = response["promptFeedback"]["blockReason"].as_str() {
+        return Err(RsllmError::content_filtered(
+            "Gemini",
+            format!("prompt blocked ({})", reason),
+        ));
+    }
+
+    if let Some(reason) = response["candidates"][0]["finishReason"].as_str() {
+        if BLOCKED_FINISH_REASONS.contains(&reason) {
+            return Err(RsllmError::content_filtered(
+                "Gemini",
+                format!("response blocked ({})", reason),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a `generateContent` response
+fn parse_response(response: &Value, model: &str) -> RsllmResult<ChatResponse> {
+    check_blocked(response)?;
+
+    let mut content = String::new();
+    let mut tool_calls = Vec::new();
+
+    for part in response["candidates"][0]["content"]["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let Some(text) = part["text"].as_str() {
+            content.push_str(text);
+        }
+        if let Some(call) = part.get("functionCall") {
+            let Some(name) = call["name"].as_str() else {
+                continue;
+            };
+            let id = call["id"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+            tool_calls.push(ToolCall::function(
+                id,
+                name,
+                call.get("args").cloned().unwrap_or_else(|| json!({})),
+            ));
+        }
+    }
+
+    let finish_reason = if tool_calls.is_empty() {
+        "stop"
+    } else {
+        "tool_calls"
+    };
+    let mut chat_response = ChatResponse::new(content, model).with_finish_reason(finish_reason);
+
+    if let Some(usage) = parse_usage(response) {
+        chat_response = chat_response.with_usage(usage);
+    }
+
+    if !tool_calls.is_empty() {
+        chat_response = chat_response.with_tool_calls(tool_calls);
+    }
+
+    Ok(chat_response)
+}
+
+/// Parse `usageMetadata`
+fn parse_usage(response: &Value) -> Option<Usage> {
+    let metadata = response.get("usageMetadata")?;
+    let prompt = metadata["promptTokenCount"].as_u64().unwrap_or(0) as u32;
+    let completion = metadata["candidatesTokenCount"].as_u64().unwrap_or(0) as u32;
+    let mut usage = Usage::new(prompt, completion);
+    usage.cached_tokens = metadata["cachedContentTokenCount"]
+        .as_u64()
+        .map(|tokens| tokens as u32);
+    Some(usage)
+}
+
+/// Parse one `streamGenerateContent` SSE event
+///
+/// Gemini sends function calls whole, so each becomes a single tool call delta.
+fn parse_stream_event(data: &str, next_index: &mut u32) -> RsllmResult<Vec<ToolAwareDelta>> {
+    let event: Value = serde_json::from_str(data)?;
+    check_blocked(&event)?;
+
+    let mut deltas = Vec::new();
+
+    for part in event["candidates"][0]["content"]["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let Some(text) = part["text"].as_str() {
+            if !text.is_empty() {
+                deltas.push(ToolAwareDelta::content(text));
+            }
+        }
+        if let Some(call) = part.get("functionCall") {
+            let id = call["id"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+            deltas.push(ToolAwareDelta::tool_call(ToolCallDelta {
+                index: *next_index,
+                id: Some(id),
+                call_type: Some("function".to_string()),
+                function: Some(ToolFunctionDelta {
+                    name: call["name"].as_str().map(String::from),
+                    arguments: Some(call.get("args").cloned().unwrap_or(json!({})).to_string()),
+                }),
+            }));
+            *next_index += 1;
+        }
+    }
+
+    Ok(deltas)
+}
+
+#[async_trait]
+impl LLMProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        "Gemini"
+    }
+
+    fn provider_type(&self) -> Provider {
+        Provider::Gemini
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        Provider::Gemini
+            .default_models()
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        let url = self.base_url.join("models")?;
+        let response = self
+            .client
+            .get(url)
+            .headers(self.build_headers())
+            .send()
+            .await?;
+
+        Ok(response.status().is_success())
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let model = model.unwrap_or(Provider::Gemini.default_model());
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let body = Self::request_body(&messages, &[], &params);
+        self.send_request(body, model, &params).await
+    }
+
+    async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let model = model.unwrap_or(Provider::Gemini.default_model());
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let body = Self::request_body(&messages, &tools, &params);
+        self.send_request(body, model, &params).await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        params.reject_logprobs(self.name())?;
+        let model = model.unwrap_or(Provider::Gemini.default_model());
+        let body = Self::request_body(&messages, &tools, params);
+        self.send_request(body, model, params).await
+    }
+
+    async fn chat_completion_structured(
+        &self,
+        messages: Vec<ChatMessage>,
+        schema: &OutputSchema,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        // Gemini's responseSchema is an OpenAPI subset, so the schema itself
+        // travels as an instruction and only the JSON mime type is enforced
+        let model = model.unwrap_or(Provider::Gemini.default_model());
+        let messages = schema.apply_instruction(messages);
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        let mut body = Self::request_body(&messages, &[], &params);
+        body["generationConfig"]["responseMimeType"] = "application/json".into();
+        self.send_request(body, model, &params).await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>>
+    {
+        use futures_util::StreamExt;
+
+        let model = model.unwrap_or_else(|| Provider::Gemini.default_model().to_string());
+        let body = Self::request_body(
+            &messages,
+            &[],
+            &GenerationParams::sampling(temperature, max_tokens),
+        );
+        let deltas = self.send_stream_request(body, &model).await?;
+
+        let chunks = deltas.map(move |delta| {
+            delta.map(|delta| {
+                if delta.done {
+                    StreamChunk::done(&model).with_finish_reason("stop")
+                } else {
+                    StreamChunk::delta(delta.content.unwrap_or_default(), &model)
+                }
+            })
+        });
+
+        Ok(Box::new(chunks))
+    }
+
+    async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ToolAwareStream> {
+        let model = model.unwrap_or_else(|| Provider::Gemini.default_model().to_string());
+        let body = Self::request_body(
+            &messages,
+            &tools,
+            &GenerationParams::sampling(temperature, max_tokens),
+        );
+        self.send_stream_request(body, &model).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::ContentAttachment;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(server: &MockServer) -> GeminiProvider {
+        GeminiProvider::new(
+            "test-key".to_string(),
+            Some(Url::parse(&server.uri()).unwrap()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_mapping() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-2.0-flash:generateContent"))
+            .and(header("x-goog-api-key", "test-key"))
+            .and(body_partial_json(json!({
+                "systemInstruction": {"parts": [{"text": "Be brief."}]},
+                "contents": [
+                    {
+                        "role": "user",
+                        "parts": [
+                            {"text": "What is this?"},
+                            {"inlineData": {"mimeType": "image/png", "data": "aGk="}}
+                        ]
+                    },
+                    {
+                        "role": "model",
+                        "parts": [{"functionCall": {"name": "lookup", "args": {"q": "cat"}}}]
+                    },
+                    {
+                        "role": "user",
+                        "parts": [{"functionResponse": {"name": "lookup", "response": {"result": "a cat"}}}]
+                    }
+                ],
+                "tools": [{"functionDeclarations": [{"name": "lookup"}]}],
+                "generationConfig": {"maxOutputTokens": 64}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [{"text": "A cat."}]},
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": {"promptTokenCount": 12, "candidatesTokenCount": 3}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut assistant = ChatMessage::assistant("");
+        assistant.tool_calls = Some(vec![ToolCall::function(
+            "call_1",
+            "lookup",
+            json!({"q": "cat"}),
+        )]);
+
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user(
+                MessageContent::multi_modal("What is this?")
+                    .with_attachment(ContentAttachment::image_base64("image/png", "aGk=")),
+            ),
+            assistant,
+            ChatMessage::tool("call_1", "a cat"),
+        ];
+        let tools = vec![ToolDefinition::new(
+            "lookup",
+            "Look something up",
+            json!({"type": "object", "properties": {"q": {"type": "string"}}}),
+        )];
+
+        let response = provider(&server)
+            .chat_completion_with_tools(messages, tools, None, None, Some(64))
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "A cat.");
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-1.5-pro:generateContent"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": {
+                        "role": "model",
+                        "parts": [{"functionCall": {"name": "get_weather", "args": {"city": "Paris"}}}]
+                    },
+                    "finishReason": "STOP"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let response = provider(&server)
+            .chat_completion_with_tools(
+                vec![ChatMessage::user("Weather in Paris?")],
+                vec![],
+                Some("gemini-1.5-pro"),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.finish_reason.as_deref(), Some("tool_calls"));
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].function.arguments, json!({"city": "Paris"}));
+        assert!(calls[0].id.starts_with("call_"));
+    }
+
+    #[tokio::test]
+    async fn test_safety_blocked_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-2.0-flash:generateContent"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "finishReason": "SAFETY",
+                    "safetyRatings": [{"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH"}]
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let err = provider(&server)
+            .chat_completion(vec![ChatMessage::user("...")], None, None, None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RsllmError::ContentFiltered { .. }));
+        assert_eq!(err.category(), "content_filter");
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_generation_params_mapping() {
+        let params = GenerationParams::new()
+            .with_temperature(0.5)
+            .with_top_p(0.75)
+            .with_max_tokens(256)
+            .with_stop(["END"])
+            .with_presence_penalty(0.5)
+            .with_seed(42)
+            .with_frequency_penalty(-1.5);
+        let body = GeminiProvider::request_body(&[ChatMessage::user("hi")], &[], &params);
+
+        assert_eq!(
+            body["generationConfig"],
+            json!({
+                "temperature": 0.5,
+                "topP": 0.75,
+                "maxOutputTokens": 256,
+                "stopSequences": ["END"],
+                "presencePenalty": 0.5,
+                "frequencyPenalty": -1.5,
+                "seed": 42,
+            })
+        );
+    }
+
+    #[test]
+    fn test_prompt_block_reason() {
+        let err = parse_response(
+            &json!({"promptFeedback": {"blockReason": "OTHER"}}),
+            "gemini-2.0-flash",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("prompt blocked"));
+    }
+}
@@ -0,0 +1,253 @@
+//! # Client-side Rate Limiting
+//!
+//! Token-bucket limiter for requests-per-minute and tokens-per-minute budgets.
+//! Callers wait asynchronously for budget instead of failing; an optional
+//! `max_wait` turns an over-long wait into a rate limit error.
+
+use crate::{RsllmError, RsllmResult};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Rate limit budgets for a client
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Maximum requests per minute
+    pub requests_per_minute: Option<u32>,
+
+    /// Maximum tokens per minute (prompt and completion combined)
+    pub tokens_per_minute: Option<u32>,
+
+    /// Longest a request may wait for budget before failing
+    pub max_wait: Option<Duration>,
+}
+
+impl RateLimitConfig {
+    /// Create an unlimited configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the requests-per-minute budget
+    pub fn with_requests_per_minute(mut self, rpm: u32) -> Self {
+        self.requests_per_minute = Some(rpm);
+        self
+    }
+
+    /// Set the tokens-per-minute budget
+    pub fn with_tokens_per_minute(mut self, tpm: u32) -> Self {
+        self.tokens_per_minute = Some(tpm);
+        self
+    }
+
+    /// Set the maximum wait before returning a rate limit error
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+}
+
+/// A bucket that refills continuously up to its per-minute capacity
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+}
+
+impl Bucket {
+    fn per_minute(limit: u32) -> Self {
+        let capacity = limit.max(1) as f64;
+        Self {
+            capacity,
+            available: capacity,
+            refill_per_sec: capacity / 60.0,
+        }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.available =
+            (self.available + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+    }
+
+    /// Time until `amount` is available (requests larger than the bucket wait for a full bucket)
+    fn wait_for(&self, amount: f64) -> Duration {
+        let needed = amount.min(self.capacity);
+        if self.available >= needed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - self.available) / self.refill_per_sec)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+    last_refill: Instant,
+}
+
+impl LimiterState {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.last_refill = now;
+
+        if let Some(bucket) = self.requests.as_mut() {
+            bucket.refill(elapsed);
+        }
+        if let Some(bucket) = self.tokens.as_mut() {
+            bucket.refill(elapsed);
+        }
+    }
+}
+
+/// Token-bucket rate limiter shared by clients through an `Arc`
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter from budgets
+    pub fn new(config: RateLimitConfig) -> Self {
+        let state = LimiterState {
+            requests: config.requests_per_minute.map(Bucket::per_minute),
+            tokens: config.tokens_per_minute.map(Bucket::per_minute),
+            last_refill: Instant::now(),
+        };
+
+        Self {
+            config,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Get the limiter configuration
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Wait until one request and `estimated_tokens` fit in the budget, then reserve them
+    ///
+    /// Returns a rate limit error if the wait would exceed `max_wait`.
+    pub async fn acquire(&self, estimated_tokens: u32) -> RsllmResult<()> {
+        let started = Instant::now();
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                state.refill();
+
+                let request_wait = state
+                    .requests
+                    .as_ref()
+                    .map_or(Duration::ZERO, |bucket| bucket.wait_for(1.0));
+                let token_wait = state.tokens.as_ref().map_or(Duration::ZERO, |bucket| {
+                    bucket.wait_for(estimated_tokens as f64)
+                });
+                let wait = request_wait.max(token_wait);
+
+                if wait.is_zero() {
+                    if let Some(bucket) = state.requests.as_mut() {
+                        bucket.available -= 1.0;
+                    }
+                    if let Some(bucket) = state.tokens.as_mut() {
+                        bucket.available -= (estimated_tokens as f64).min(bucket.capacity);
+                    }
+                    return Ok(());
+                }
+
+                wait
+            };
+
+            if let Some(max_wait) = self.config.max_wait {
+                if started.elapsed() + wait > max_wait {
+                    return Err(RsllmError::rate_limit(
+                        format!(
+                            "Client-side rate limit: budget not available within {:?}",
+                            max_wait
+                        ),
+                        Some(wait),
+                    ));
+                }
+            }
+
+            tracing::debug!(
+                wait_ms = wait.as_millis() as u64,
+                "Waiting for rate limit budget"
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Correct a reservation once the provider reports actual usage
+    ///
+    /// Over-estimates are returned to the bucket; under-estimates are charged and
+    /// may push the bucket into debt, delaying later requests.
+    pub fn record_usage(&self, estimated_tokens: u32, actual_tokens: u32) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.refill();
+
+        if let Some(bucket) = state.tokens.as_mut() {
+            let reserved = (estimated_tokens as f64).min(bucket.capacity);
+            bucket.available =
+                (bucket.available + reserved - actual_tokens as f64).min(bucket.capacity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_paces_requests() {
+        let limiter = RateLimiter::new(RateLimitConfig::new().with_requests_per_minute(2));
+        let start = Instant::now();
+
+        limiter.acquire(0).await.unwrap();
+        limiter.acquire(0).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Bucket is empty; the third request waits for one refill (30s at 2 rpm)
+        limiter.acquire(0).await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(29), "elapsed {:?}", elapsed);
+        assert!(elapsed <= Duration::from_secs(31), "elapsed {:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_budget_and_usage_correction() {
+        let limiter = RateLimiter::new(RateLimitConfig::new().with_tokens_per_minute(600));
+        let start = Instant::now();
+
+        limiter.acquire(500).await.unwrap();
+        // Actual usage was much lower, so the budget is returned
+        limiter.record_usage(500, 100);
+        limiter.acquire(400).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // 100 tokens left, 300 needed: 200 tokens at 10/s
+        limiter.acquire(300).await.unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(19), "elapsed {:?}", elapsed);
+        assert!(elapsed <= Duration::from_secs(21), "elapsed {:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_max_wait_returns_error() {
+        let limiter = RateLimiter::new(
+            RateLimitConfig::new()
+                .with_requests_per_minute(1)
+                .with_max_wait(Duration::from_secs(5)),
+        );
+
+        limiter.acquire(0).await.unwrap();
+        let error = limiter.acquire(0).await.unwrap_err();
+        assert!(matches!(error, RsllmError::RateLimit { .. }));
+    }
+}
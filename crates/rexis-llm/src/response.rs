@@ -34,6 +34,10 @@ pub struct ChatResponse {
 
     /// Response ID (if provided by provider)
     pub id: Option<String>,
+
+    /// Token log probabilities (if requested and supported by the provider)
+    #[serde(default)]
+    pub logprobs: Option<Vec<TokenLogprob>>,
 }
 
 impl ChatResponse {
@@ -48,6 +52,7 @@ impl ChatResponse {
             metadata: HashMap::new(),
             timestamp: Some(chrono::Utc::now()),
             id: None,
+            logprobs: None,
         }
     }
 
@@ -63,6 +68,12 @@ impl ChatResponse {
         self
     }
 
+    /// Set token log probabilities
+    pub fn with_logprobs(mut self, logprobs: Vec<TokenLogprob>) -> Self {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
     /// Set tool calls
     pub fn with_tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
         self.tool_calls = Some(tool_calls);
@@ -108,6 +119,81 @@ impl ChatResponse {
     pub fn content_length(&self) -> usize {
         self.content.len()
     }
+
+    /// Normalized probability of each label in a closed label set
+    ///
+    /// Uses the first generated token and its top alternatives: a candidate
+    /// counts towards every label it is a (case-insensitive, trimmed) prefix of,
+    /// split evenly when it matches several. Probabilities are normalized over
+    /// the labels, so the values sum to 1.0. Returns `None` without logprobs or
+    /// when no candidate matches any label.
+    pub fn label_confidence(&self, labels: &[&str]) -> Option<HashMap<String, f64>> {
+        let first = self.logprobs.as_ref()?.first()?;
+
+        let mut candidates: HashMap<String, f64> = HashMap::new();
+        for (token, logprob) in std::iter::once((&first.token, first.logprob)).chain(
+            first
+                .top_logprobs
+                .iter()
+                .map(|alternative| (&alternative.token, alternative.logprob)),
+        ) {
+            let token = token.trim().to_lowercase();
+            if !token.is_empty() {
+                candidates.entry(token).or_insert(logprob);
+            }
+        }
+
+        let normalized: Vec<String> = labels.iter().map(|label| label.to_lowercase()).collect();
+        let mut mass = vec![0.0; labels.len()];
+        for (token, logprob) in &candidates {
+            let matches: Vec<usize> = normalized
+                .iter()
+                .enumerate()
+                .filter(|(_, label)| label.starts_with(token.as_str()))
+                .map(|(index, _)| index)
+                .collect();
+            for &index in &matches {
+                mass[index] += logprob.exp() / matches.len() as f64;
+            }
+        }
+
+        let total: f64 = mass.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        Some(
+            labels
+                .iter()
+                .zip(mass)
+                .map(|(label, mass)| (label.to_string(), mass / total))
+                .collect(),
+        )
+    }
+}
+
+/// Log probability of a generated token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenLogprob {
+    /// Generated token
+    pub token: String,
+
+    /// Natural log probability of the token
+    pub logprob: f64,
+
+    /// Most likely alternatives at this position
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// Alternative token at a generated position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLogprob {
+    /// Candidate token
+    pub token: String,
+
+    /// Natural log probability of the token
+    pub logprob: f64,
 }
 
 /// Response from a completion request (non-chat)
@@ -334,9 +420,13 @@ pub struct Usage {
     /// Total number of tokens used
     pub total_tokens: u32,
 
-    /// Number of cached tokens (if applicable)
+    /// Number of prompt tokens read from the provider's cache (if applicable)
     pub cached_tokens: Option<u32>,
 
+    /// Number of prompt tokens written to the provider's cache (if applicable)
+    #[serde(default)]
+    pub cache_creation_tokens: Option<u32>,
+
     /// Reasoning tokens (for models with reasoning capabilities)
     pub reasoning_tokens: Option<u32>,
 }
@@ -349,6 +439,7 @@ impl Usage {
             completion_tokens,
             total_tokens: prompt_tokens + completion_tokens,
             cached_tokens: None,
+            cache_creation_tokens: None,
             reasoning_tokens: None,
         }
     }
@@ -359,6 +450,12 @@ impl Usage {
         self
     }
 
+    /// Set cache creation tokens
+    pub fn with_cache_creation_tokens(mut self, cache_creation_tokens: u32) -> Self {
+        self.cache_creation_tokens = Some(cache_creation_tokens);
+        self
+    }
+
     /// Set reasoning tokens
     pub fn with_reasoning_tokens(mut self, reasoning_tokens: u32) -> Self {
         self.reasoning_tokens = Some(reasoning_tokens);
@@ -461,6 +558,49 @@ mod tests {
         assert!(response.is_finished());
     }
 
+    fn first_token(token: &str, alternatives: &[(&str, f64)]) -> ChatResponse {
+        let top_logprobs = alternatives
+            .iter()
+            .map(|(token, probability)| TopLogprob {
+                token: token.to_string(),
+                logprob: probability.ln(),
+            })
+            .collect();
+        ChatResponse::new(token, "gpt-4").with_logprobs(vec![TokenLogprob {
+            token: token.to_string(),
+            logprob: alternatives[0].1.ln(),
+            top_logprobs,
+        }])
+    }
+
+    #[test]
+    fn test_label_confidence() {
+        let response = first_token(
+            "Yes",
+            &[("Yes", 0.6), (" no", 0.2), ("yes", 0.1), ("Maybe", 0.1)],
+        );
+
+        let confidence = response.label_confidence(&["yes", "no"]).unwrap();
+        // "Yes" and "yes" collapse to one candidate; "Maybe" matches nothing
+        assert!((confidence["yes"] - 0.75).abs() < 1e-9);
+        assert!((confidence["no"] - 0.25).abs() < 1e-9);
+
+        // A shared prefix splits its mass between the labels it starts
+        let response = first_token("bill", &[("bill", 0.5), ("bug", 0.5)]);
+        let confidence = response
+            .label_confidence(&["billing", "bill_dispute", "bug"])
+            .unwrap();
+        assert!((confidence["billing"] - 0.25).abs() < 1e-9);
+        assert!((confidence["bug"] - 0.5).abs() < 1e-9);
+
+        assert!(first_token("Hello", &[("Hello", 1.0)])
+            .label_confidence(&["yes", "no"])
+            .is_none());
+        assert!(ChatResponse::new("yes", "gpt-4")
+            .label_confidence(&["yes"])
+            .is_none());
+    }
+
     #[test]
     fn test_stream_chunk() {
         let chunk = StreamChunk::delta("Hello", "gpt-4").with_role(MessageRole::Assistant);
@@ -3,7 +3,8 @@
 //! Streaming response handling with proper async Stream traits.
 //! Supports real-time token streaming with backpressure and error handling.
 
-use crate::{ChatResponse, CompletionResponse, RsllmError, RsllmResult, StreamChunk};
+use crate::response::{ToolCallDelta, ToolFunctionDelta};
+use crate::{ChatResponse, CompletionResponse, RsllmError, RsllmResult, StreamChunk, ToolCall};
 use futures_util::Future;
 use futures_util::Stream;
 use pin_project_lite::pin_project;
@@ -16,6 +17,9 @@ pub type ChatStream = Pin<Box<dyn Stream<Item = RsllmResult<StreamChunk>> + Send
 /// Type alias for completion streaming responses
 pub type CompletionStream = Pin<Box<dyn Stream<Item = RsllmResult<StreamChunk>> + Send>>;
 
+/// Type alias for streaming responses that may contain tool call fragments
+pub type ToolAwareStream = Pin<Box<dyn Stream<Item = RsllmResult<ToolAwareDelta>> + Send>>;
+
 pin_project! {
     /// Stream collector for assembling complete responses from chunks
     pub struct StreamCollector<S> {
@@ -449,6 +453,211 @@ pub trait RsllmStreamExt: Stream<Item = RsllmResult<StreamChunk>> + Sized {
 
 impl<S> RsllmStreamExt for S where S: Stream<Item = RsllmResult<StreamChunk>> {}
 
+/// A streamed fragment of a tool-enabled chat completion
+///
+/// Providers emit text and tool call fragments interleaved: a tool call first
+/// arrives with its id and name, followed by argument chunks. Feed every delta
+/// into a [`ToolCallAccumulator`] to assemble complete tool calls.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ToolAwareDelta {
+    /// Text content delta
+    pub content: Option<String>,
+
+    /// Tool call fragment
+    pub tool_call_delta: Option<ToolCallDelta>,
+
+    /// Whether this is the final delta
+    pub done: bool,
+}
+
+impl ToolAwareDelta {
+    /// Create a text content delta
+    pub fn content(content: impl Into<String>) -> Self {
+        Self {
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Create a tool call fragment delta
+    pub fn tool_call(delta: ToolCallDelta) -> Self {
+        Self {
+            tool_call_delta: Some(delta),
+            ..Default::default()
+        }
+    }
+
+    /// Create the final delta
+    pub fn done() -> Self {
+        Self {
+            done: true,
+            ..Default::default()
+        }
+    }
+
+    /// Replay a complete response as a sequence of deltas
+    ///
+    /// Used by providers without native streamed tool calls.
+    pub fn from_chat_response(response: &ChatResponse) -> Vec<Self> {
+        let mut deltas = Vec::new();
+
+        if !response.content.is_empty() {
+            deltas.push(Self::content(response.content.clone()));
+        }
+
+        for (index, call) in response.tool_calls.iter().flatten().enumerate() {
+            deltas.push(Self::tool_call(ToolCallDelta {
+                index: index as u32,
+                id: Some(call.id.clone()),
+                call_type: Some("function".to_string()),
+                function: Some(ToolFunctionDelta {
+                    name: Some(call.function.name.clone()),
+                    arguments: Some(call.function.arguments.to_string()),
+                }),
+            }));
+        }
+
+        deltas.push(Self::done());
+        deltas
+    }
+}
+
+/// Tool call being assembled from stream fragments
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: Option<String>,
+    name: String,
+    arguments: String,
+}
+
+/// Accumulates [`ToolAwareDelta`]s into text content and complete tool calls
+#[derive(Debug, Default)]
+pub struct ToolCallAccumulator {
+    content: String,
+    calls: std::collections::BTreeMap<u32, PartialToolCall>,
+    done: bool,
+}
+
+impl ToolCallAccumulator {
+    /// Create an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drain a tool-aware stream into a new accumulator
+    pub async fn collect<S>(mut stream: S) -> RsllmResult<Self>
+    where
+        S: Stream<Item = RsllmResult<ToolAwareDelta>> + Unpin,
+    {
+        use futures_util::StreamExt;
+
+        let mut accumulator = Self::new();
+        while let Some(delta) = stream.next().await {
+            accumulator.push(&delta?);
+        }
+        Ok(accumulator)
+    }
+
+    /// Add a delta
+    pub fn push(&mut self, delta: &ToolAwareDelta) {
+        if let Some(content) = &delta.content {
+            self.content.push_str(content);
+        }
+
+        if let Some(tool_call_delta) = &delta.tool_call_delta {
+            self.push_tool_call_delta(tool_call_delta);
+        }
+
+        if delta.done {
+            self.done = true;
+        }
+    }
+
+    /// Add a tool call fragment
+    pub fn push_tool_call_delta(&mut self, delta: &ToolCallDelta) {
+        let call = self.calls.entry(delta.index).or_default();
+
+        if let Some(id) = &delta.id {
+            call.id = Some(id.clone());
+        }
+
+        if let Some(function) = &delta.function {
+            if let Some(name) = &function.name {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = &function.arguments {
+                call.arguments.push_str(arguments);
+            }
+        }
+    }
+
+    /// Text content accumulated so far
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Whether the final delta has been seen
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Whether any tool call fragments have been seen
+    pub fn has_tool_calls(&self) -> bool {
+        !self.calls.is_empty()
+    }
+
+    /// Assemble complete tool calls, ordered by their stream index
+    ///
+    /// Fails with [`RsllmError::InvalidToolArguments`] if a call has no name or
+    /// its arguments are not valid JSON (e.g. the stream was cut off mid-call).
+    pub fn tool_calls(&self) -> RsllmResult<Vec<ToolCall>> {
+        self.calls
+            .iter()
+            .map(|(index, call)| {
+                let id = call.id.clone().unwrap_or_else(|| format!("call_{}", index));
+
+                if call.name.is_empty() {
+                    return Err(RsllmError::invalid_tool_arguments(
+                        id,
+                        "<unknown>",
+                        "tool call has no function name",
+                    ));
+                }
+
+                let arguments = if call.arguments.trim().is_empty() {
+                    serde_json::Value::Object(serde_json::Map::new())
+                } else {
+                    serde_json::from_str(&call.arguments).map_err(|e| {
+                        RsllmError::invalid_tool_arguments(
+                            id.clone(),
+                            call.name.clone(),
+                            format!("arguments are not valid JSON: {}", e),
+                        )
+                    })?
+                };
+
+                Ok(ToolCall::function(id, call.name.clone(), arguments))
+            })
+            .collect()
+    }
+
+    /// Build a complete chat response from the accumulated deltas
+    pub fn into_chat_response(self, model: impl Into<String>) -> RsllmResult<ChatResponse> {
+        let tool_calls = self.tool_calls()?;
+
+        let mut response = ChatResponse::new(self.content, model);
+        if tool_calls.is_empty() {
+            response = response.with_finish_reason("stop");
+        } else {
+            response = response
+                .with_finish_reason("tool_calls")
+                .with_tool_calls(tool_calls);
+        }
+
+        Ok(response)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -509,4 +718,92 @@ mod tests {
         assert_eq!(collected[0].content, "HELLO");
         assert_eq!(collected[1].content, " WORLD");
     }
+
+    fn call_delta(index: u32, id: Option<&str>, name: Option<&str>, args: &str) -> ToolAwareDelta {
+        ToolAwareDelta::tool_call(ToolCallDelta {
+            index,
+            id: id.map(String::from),
+            call_type: id.map(|_| "function".to_string()),
+            function: Some(ToolFunctionDelta {
+                name: name.map(String::from),
+                arguments: Some(args.to_string()),
+            }),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_accumulator_interleaved() {
+        let deltas = vec![
+            ToolAwareDelta::content("Checking "),
+            call_delta(0, Some("call_a"), Some("get_weather"), ""),
+            call_delta(0, None, None, "{\"city\":"),
+            ToolAwareDelta::content("both."),
+            call_delta(1, Some("call_b"), Some("get_time"), "{\"tz\""),
+            call_delta(0, None, None, "\"Paris\"}"),
+            call_delta(1, None, None, ":\"CET\"}"),
+            ToolAwareDelta::done(),
+        ];
+
+        let stream = Box::pin(tokio_stream::iter(deltas.into_iter().map(Ok)));
+        let accumulator = ToolCallAccumulator::collect(stream).await.unwrap();
+
+        assert!(accumulator.is_done());
+        assert_eq!(accumulator.content(), "Checking both.");
+
+        let calls = accumulator.tool_calls().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(
+            calls[0].function.arguments,
+            serde_json::json!({"city": "Paris"})
+        );
+        assert_eq!(calls[1].id, "call_b");
+        assert_eq!(
+            calls[1].function.arguments,
+            serde_json::json!({"tz": "CET"})
+        );
+
+        let response = accumulator.into_chat_response("gpt-4").unwrap();
+        assert!(response.has_tool_calls());
+        assert_eq!(response.finish_reason, Some("tool_calls".to_string()));
+    }
+
+    #[test]
+    fn test_tool_call_accumulator_truncated_arguments() {
+        let mut accumulator = ToolCallAccumulator::new();
+        accumulator.push(&call_delta(
+            0,
+            Some("call_a"),
+            Some("search"),
+            "{\"query\": \"ru",
+        ));
+
+        let err = accumulator.tool_calls().unwrap_err();
+        assert!(matches!(
+            err,
+            RsllmError::InvalidToolArguments { ref tool_call_id, ref tool, .. }
+                if tool_call_id == "call_a" && tool == "search"
+        ));
+    }
+
+    #[test]
+    fn test_tool_aware_delta_replay() {
+        let response =
+            ChatResponse::new("Sure", "gpt-4").with_tool_calls(vec![ToolCall::function(
+                "call_1",
+                "lookup",
+                serde_json::json!({"id": 7}),
+            )]);
+
+        let mut accumulator = ToolCallAccumulator::new();
+        for delta in ToolAwareDelta::from_chat_response(&response) {
+            accumulator.push(&delta);
+        }
+
+        let calls = accumulator.tool_calls().unwrap();
+        assert_eq!(accumulator.content(), "Sure");
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.arguments, serde_json::json!({"id": 7}));
+    }
 }
@@ -0,0 +1,173 @@
+//! # Structured Output
+//!
+//! JSON-schema constrained responses. Providers with native support (OpenAI
+//! `response_format`, Ollama `format`, Claude forced tool use) enforce the
+//! schema server-side; others receive the schema as a system instruction. Responses are parsed client-side
+//! and repaired by feeding parse errors back to the model.
+
+use crate::{ChatMessage, MessageRole, RsllmError, RsllmResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// A named JSON schema the response must conform to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputSchema {
+    /// Schema name (letters, digits, `_` and `-` only)
+    pub name: String,
+
+    /// JSON schema document
+    pub schema: serde_json::Value,
+}
+
+impl OutputSchema {
+    /// Create an output schema from a raw JSON schema
+    pub fn new(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        let name: String = name
+            .into()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Self { name, schema }
+    }
+
+    /// Generate the output schema for a type
+    #[cfg(feature = "json-schema")]
+    pub fn for_type<T: schemars::JsonSchema>() -> Self {
+        let schema = schemars::schema_for!(T);
+        Self::new(
+            T::schema_name(),
+            serde_json::to_value(&schema).unwrap_or(serde_json::Value::Null),
+        )
+    }
+
+    /// Instruction text describing the expected output
+    pub fn instruction(&self) -> String {
+        format!(
+            "Respond only with a JSON value that conforms to this JSON schema. \
+             Do not include explanations or markdown.\n\nSchema:\n{}",
+            self.schema
+        )
+    }
+
+    /// Add the schema instruction to a conversation
+    ///
+    /// Appended to the first system message, or inserted as a new one.
+    pub fn apply_instruction(&self, mut messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+        let instruction = self.instruction();
+        match messages
+            .iter_mut()
+            .find(|message| message.role == MessageRole::System)
+        {
+            Some(system) => {
+                let text = system.text().unwrap_or("").to_string();
+                *system = ChatMessage::system(format!("{}\n\n{}", text, instruction));
+            }
+            None => messages.insert(0, ChatMessage::system(instruction)),
+        }
+        messages
+    }
+}
+
+/// Options for structured completions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StructuredOptions {
+    /// Repair round-trips allowed after the first response fails to parse
+    pub max_repairs: u32,
+}
+
+impl Default for StructuredOptions {
+    fn default() -> Self {
+        Self { max_repairs: 2 }
+    }
+}
+
+impl StructuredOptions {
+    /// Set the number of repair round-trips
+    pub fn with_max_repairs(mut self, max_repairs: u32) -> Self {
+        self.max_repairs = max_repairs;
+        self
+    }
+}
+
+/// A parsed structured response with the raw model output
+#[derive(Debug, Clone)]
+pub struct StructuredResponse<T> {
+    /// Parsed value
+    pub value: T,
+
+    /// Raw text the value was parsed from
+    pub raw: String,
+
+    /// Number of repair round-trips that were needed
+    pub repairs: u32,
+}
+
+/// Parse model output as JSON, tolerating markdown code fences
+pub fn parse_structured<T: DeserializeOwned>(raw: &str) -> RsllmResult<T> {
+    let trimmed = raw.trim();
+    let body = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(trimmed)
+        .trim();
+
+    serde_json::from_str(body).map_err(|e| {
+        RsllmError::validation(
+            "structured_output",
+            format!("Response is not valid JSON for the schema: {}", e),
+        )
+    })
+}
+
+/// Message asking the model to fix an unparseable response
+pub(crate) fn repair_message(error: &RsllmError) -> ChatMessage {
+    ChatMessage::user(format!(
+        "Your previous response could not be parsed: {}. \
+         Respond again with only the corrected JSON.",
+        error
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[test]
+    fn test_parse_structured_strips_fences() {
+        let point: Point = parse_structured("```json\n{\"x\": 1, \"y\": 2}\n```").unwrap();
+        assert_eq!(point, Point { x: 1, y: 2 });
+
+        assert!(parse_structured::<Point>("{\"x\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_apply_instruction() {
+        let schema = OutputSchema::new("point type", serde_json::json!({"type": "object"}));
+        assert_eq!(schema.name, "point_type");
+
+        let messages = schema.apply_instruction(vec![ChatMessage::user("hi")]);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, MessageRole::System);
+
+        let messages = schema.apply_instruction(vec![
+            ChatMessage::system("Be terse."),
+            ChatMessage::user("hi"),
+        ]);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].text().unwrap().starts_with("Be terse."));
+        assert!(messages[0].text().unwrap().contains("JSON schema"));
+    }
+}
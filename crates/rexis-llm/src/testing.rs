@@ -0,0 +1,860 @@
+//! # Testing Utilities
+//!
+//! Deterministic providers for testing code built on a [`Client`], available
+//! with the `testing` feature.
+//!
+//! [`MockClient`] answers requests from scripted rules matched against the
+//! conversation, so agent loops can be exercised without network access or API
+//! keys. [`CassetteProvider`] records real responses to a JSON cassette once and
+//! replays them afterwards, keyed by a hash of the request.
+//!
+//! ```rust,ignore
+//! use rsllm::testing::{respond_text, respond_with_tool_call, MockClient};
+//!
+//! let mock = MockClient::builder()
+//!     .on_user_message_containing("weather", respond_with_tool_call("get_weather", json!({"city": "Paris"})))
+//!     .on_tool_result("get_weather", respond_text("It is sunny in Paris."))
+//!     .otherwise(respond_text("ok"))
+//!     .build();
+//!
+//! let client = mock.client();
+//! ```
+
+use crate::cache::CacheKeyParts;
+use crate::params::GenerationParams;
+use crate::provider::{LLMProvider, Provider};
+use crate::streaming::{ToolAwareDelta, ToolAwareStream};
+use crate::tools::ToolDefinition;
+use crate::{
+    ChatMessage, ChatResponse, Client, ClientConfig, MessageRole, RsllmError, RsllmResult,
+    StreamChunk, TokenLogprob, ToolCall, Usage,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Environment variable that switches [`CassetteProvider::from_env`] to recording
+pub const RECORD_ENV: &str = "RSLLM_RECORD";
+
+/// Model name reported by mock responses
+const MOCK_MODEL: &str = "mock";
+
+type ChunkStream = Box<dyn futures_util::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>;
+
+/// A request received by a [`MockProvider`]
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    /// Conversation sent to the model
+    pub messages: Vec<ChatMessage>,
+
+    /// Tools offered to the model
+    pub tools: Vec<ToolDefinition>,
+
+    /// Model the request was sent to
+    pub model: Option<String>,
+
+    /// Generation parameters
+    pub params: GenerationParams,
+}
+
+impl MockRequest {
+    /// The last message of the conversation
+    pub fn last_message(&self) -> Option<&ChatMessage> {
+        self.messages.last()
+    }
+
+    /// Name of the tool whose result ends the conversation, if any
+    ///
+    /// The name is resolved through the assistant message that issued the call.
+    pub fn tool_result_name(&self) -> Option<&str> {
+        let last = self.last_message()?;
+        if last.role != MessageRole::Tool {
+            return None;
+        }
+        let call_id = last.tool_call_id.as_deref()?;
+        self.messages
+            .iter()
+            .rev()
+            .filter_map(|message| message.tool_calls.as_ref())
+            .flatten()
+            .find(|call| call.id == call_id)
+            .map(|call| call.function.name.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MockReply {
+    Text(String),
+    ToolCalls(Vec<(String, serde_json::Value)>),
+    Error(String),
+    TransientError(String),
+}
+
+/// A scripted reply returned by a [`MockProvider`]
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    reply: MockReply,
+    usage: Option<Usage>,
+    delay: Option<Duration>,
+    logprobs: Option<Vec<TokenLogprob>>,
+}
+
+impl MockResponse {
+    /// Report token usage with the response
+    pub fn with_usage(mut self, usage: Usage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Wait before answering, to simulate a slow model
+    ///
+    /// The delay uses the tokio clock, so paused-time tests do not actually wait.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Return token log probabilities with the response
+    pub fn with_logprobs(mut self, logprobs: Vec<TokenLogprob>) -> Self {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
+    fn to_response(
+        &self,
+        model: &str,
+        mut next_call_id: impl FnMut() -> String,
+    ) -> RsllmResult<ChatResponse> {
+        let response = match &self.reply {
+            MockReply::Text(text) => {
+                ChatResponse::new(text.clone(), model).with_finish_reason("stop")
+            }
+            MockReply::ToolCalls(calls) => ChatResponse::new("", model)
+                .with_tool_calls(
+                    calls
+                        .iter()
+                        .map(|(name, arguments)| {
+                            ToolCall::function(next_call_id(), name.clone(), arguments.clone())
+                        })
+                        .collect(),
+                )
+                .with_finish_reason("tool_calls"),
+            MockReply::Error(message) => return Err(RsllmError::provider("Mock", message.clone())),
+            MockReply::TransientError(message) => return Err(RsllmError::network(message.clone())),
+        };
+        let response = match &self.usage {
+            Some(usage) => response.with_usage(usage.clone()),
+            None => response,
+        };
+        Ok(match &self.logprobs {
+            Some(logprobs) => response.with_logprobs(logprobs.clone()),
+            None => response,
+        })
+    }
+}
+
+/// Reply with assistant text
+pub fn respond_text(text: impl Into<String>) -> MockResponse {
+    MockResponse {
+        reply: MockReply::Text(text.into()),
+        usage: None,
+        delay: None,
+        logprobs: None,
+    }
+}
+
+/// Reply with a single tool call
+pub fn respond_with_tool_call(
+    name: impl Into<String>,
+    arguments: serde_json::Value,
+) -> MockResponse {
+    respond_with_tool_calls(vec![(name.into(), arguments)])
+}
+
+/// Reply with several tool calls, issued in order
+pub fn respond_with_tool_calls(calls: Vec<(String, serde_json::Value)>) -> MockResponse {
+    MockResponse {
+        reply: MockReply::ToolCalls(calls),
+        usage: None,
+        delay: None,
+        logprobs: None,
+    }
+}
+
+/// Fail the request with a provider error
+pub fn respond_with_error(message: impl Into<String>) -> MockResponse {
+    MockResponse {
+        reply: MockReply::Error(message.into()),
+        usage: None,
+        delay: None,
+        logprobs: None,
+    }
+}
+
+/// Fail the request with a network error, which retry policies retry
+pub fn respond_with_transient_error(message: impl Into<String>) -> MockResponse {
+    MockResponse {
+        reply: MockReply::TransientError(message.into()),
+        usage: None,
+        delay: None,
+        logprobs: None,
+    }
+}
+
+type Matcher = Box<dyn Fn(&MockRequest) -> bool + Send + Sync>;
+
+/// Provider that answers from scripted rules and records every request
+///
+/// Rules are checked in the order they were added and the first match answers;
+/// requests no rule matches get the fallback response, or an error when there
+/// is none. Tool call IDs are numbered `call_1`, `call_2`, ... per provider.
+/// Streamed responses are replayed word by word.
+pub struct MockProvider {
+    rules: Vec<(Matcher, MockResponse)>,
+    fallback: Option<MockResponse>,
+    requests: Mutex<Vec<MockRequest>>,
+    call_ids: AtomicUsize,
+}
+
+impl MockProvider {
+    /// Requests received so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    async fn respond(&self, request: MockRequest) -> RsllmResult<ChatResponse> {
+        let reply = self
+            .rules
+            .iter()
+            .find(|(matches, _)| matches(&request))
+            .map(|(_, response)| response)
+            .or(self.fallback.as_ref())
+            .cloned();
+        let model = request
+            .model
+            .clone()
+            .unwrap_or_else(|| MOCK_MODEL.to_string());
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(request);
+
+        let Some(reply) = reply else {
+            return Err(RsllmError::not_found("mock response matching the request"));
+        };
+        if let Some(delay) = reply.delay {
+            tokio::time::sleep(delay).await;
+        }
+        reply.to_response(&model, || {
+            format!("call_{}", self.call_ids.fetch_add(1, Ordering::Relaxed) + 1)
+        })
+    }
+}
+
+impl std::fmt::Debug for MockProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockProvider")
+            .field("rules", &self.rules.len())
+            .field("fallback", &self.fallback)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl LLMProvider for MockProvider {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn provider_type(&self) -> Provider {
+        Provider::OpenAICompatible
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        vec![MOCK_MODEL.to_string()]
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        Ok(true)
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        self.chat_completion_with_params(messages, Vec::new(), model, &params)
+            .await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChunkStream> {
+        let response = self
+            .chat_completion(messages, model.as_deref(), temperature, max_tokens)
+            .await?;
+        Ok(replay_stream(response))
+    }
+
+    async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        self.chat_completion_with_params(messages, tools, model, &params)
+            .await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        self.respond(MockRequest {
+            messages,
+            tools,
+            model: model.map(str::to_string),
+            params: params.clone(),
+        })
+        .await
+    }
+
+    async fn chat_completion_with_tools_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ToolAwareStream> {
+        let response = self
+            .chat_completion_with_tools(messages, tools, model.as_deref(), temperature, max_tokens)
+            .await?;
+        Ok(replay_tool_stream(response))
+    }
+}
+
+/// A client backed by a [`MockProvider`]
+#[derive(Debug, Clone)]
+pub struct MockClient {
+    provider: Arc<MockProvider>,
+}
+
+impl MockClient {
+    /// Create a mock client builder
+    pub fn builder() -> MockClientBuilder {
+        MockClientBuilder::default()
+    }
+
+    /// A client that sends its requests to the mock
+    pub fn client(&self) -> Client {
+        Client::with_provider(ClientConfig::default(), self.provider.clone())
+    }
+
+    /// The underlying provider
+    pub fn provider(&self) -> Arc<MockProvider> {
+        self.provider.clone()
+    }
+
+    /// Requests received so far, from every client created from this mock
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.provider.requests()
+    }
+}
+
+impl From<MockClient> for Client {
+    fn from(mock: MockClient) -> Self {
+        mock.client()
+    }
+}
+
+/// Builder for [`MockClient`]
+#[derive(Default)]
+pub struct MockClientBuilder {
+    rules: Vec<(Matcher, MockResponse)>,
+    fallback: Option<MockResponse>,
+}
+
+impl MockClientBuilder {
+    /// Answer requests matching `predicate`
+    pub fn on(
+        mut self,
+        predicate: impl Fn(&MockRequest) -> bool + Send + Sync + 'static,
+        response: MockResponse,
+    ) -> Self {
+        self.rules.push((Box::new(predicate), response));
+        self
+    }
+
+    /// Answer requests whose last message is a user message containing `text`
+    pub fn on_user_message_containing(
+        self,
+        text: impl Into<String>,
+        response: MockResponse,
+    ) -> Self {
+        let text = text.into();
+        self.on(
+            move |request| {
+                request.last_message().is_some_and(|message| {
+                    message.role == MessageRole::User
+                        && message
+                            .text()
+                            .is_some_and(|content| content.contains(&text))
+                })
+            },
+            response,
+        )
+    }
+
+    /// Answer requests whose last message is a result of the tool `name`
+    pub fn on_tool_result(self, name: impl Into<String>, response: MockResponse) -> Self {
+        let name = name.into();
+        self.on(
+            move |request| request.tool_result_name() == Some(name.as_str()),
+            response,
+        )
+    }
+
+    /// Answer requests no rule matches
+    pub fn otherwise(mut self, response: MockResponse) -> Self {
+        self.fallback = Some(response);
+        self
+    }
+
+    /// Build the mock client
+    pub fn build(self) -> MockClient {
+        MockClient {
+            provider: Arc::new(MockProvider {
+                rules: self.rules,
+                fallback: self.fallback,
+                requests: Mutex::new(Vec::new()),
+                call_ids: AtomicUsize::new(0),
+            }),
+        }
+    }
+}
+
+/// Whether a [`CassetteProvider`] calls the real provider or replays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Forward requests to the real provider and save the responses
+    Record,
+    /// Answer from the cassette only
+    Replay,
+}
+
+/// A recorded request and its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    model: String,
+    messages: Vec<ChatMessage>,
+    response: ChatResponse,
+}
+
+/// Cassette file contents
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cassette {
+    interactions: BTreeMap<String, Interaction>,
+}
+
+/// Provider that records real responses to a JSON cassette and replays them
+///
+/// Interactions are keyed by the same request hash as the response cache
+/// (model, messages, tools and sampling parameters), so a replayed test has to
+/// send exactly the requests that were recorded. Replaying a request that is
+/// not on the cassette fails with [`RsllmError::NotFound`].
+pub struct CassetteProvider {
+    mode: CassetteMode,
+    inner: Option<Arc<dyn LLMProvider>>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl CassetteProvider {
+    /// Record through `inner`, adding to the cassette at `path` if it exists
+    pub fn record(inner: Arc<dyn LLMProvider>, path: impl AsRef<Path>) -> RsllmResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let cassette = if path.exists() {
+            load_cassette(&path)?
+        } else {
+            Cassette::default()
+        };
+        Ok(Self {
+            mode: CassetteMode::Record,
+            inner: Some(inner),
+            path,
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    /// Replay the cassette at `path`
+    pub fn replay(path: impl AsRef<Path>) -> RsllmResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let cassette = load_cassette(&path)?;
+        Ok(Self {
+            mode: CassetteMode::Replay,
+            inner: None,
+            path,
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    /// Record when [`RECORD_ENV`] is set to `1` or `true`, replay otherwise
+    ///
+    /// `inner` is only called when recording, so it can be built without
+    /// credentials in CI.
+    pub fn from_env(
+        inner: impl FnOnce() -> RsllmResult<Arc<dyn LLMProvider>>,
+        path: impl AsRef<Path>,
+    ) -> RsllmResult<Self> {
+        let record = std::env::var(RECORD_ENV)
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if record {
+            Self::record(inner()?, path)
+        } else {
+            Self::replay(path)
+        }
+    }
+
+    /// Whether the provider records or replays
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// Number of interactions on the cassette
+    pub fn len(&self) -> usize {
+        self.lock().interactions.len()
+    }
+
+    /// Whether the cassette holds no interactions
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A client that sends its requests through this provider
+    pub fn into_client(self) -> Client {
+        Client::with_provider(ClientConfig::default(), Arc::new(self))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Cassette> {
+        self.cassette.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, cassette: &Cassette) -> RsllmResult<()> {
+        let json = serde_json::to_string_pretty(cassette)
+            .map_err(|e| RsllmError::serialization(format!("Failed to encode cassette: {}", e)))?;
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                RsllmError::configuration(format!("Failed to create {}: {}", parent.display(), e))
+            })?;
+        }
+        std::fs::write(&self.path, json).map_err(|e| {
+            RsllmError::configuration(format!("Failed to write {}: {}", self.path.display(), e))
+        })
+    }
+}
+
+impl std::fmt::Debug for CassetteProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CassetteProvider")
+            .field("mode", &self.mode)
+            .field("inner", &self.inner.as_ref().map(|inner| inner.name()))
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+fn load_cassette(path: &Path) -> RsllmResult<Cassette> {
+    let json = std::fs::read_to_string(path).map_err(|e| {
+        RsllmError::configuration(format!("Failed to read cassette {}: {}", path.display(), e))
+    })?;
+    serde_json::from_str(&json).map_err(|e| {
+        RsllmError::serialization(format!("Invalid cassette {}: {}", path.display(), e))
+    })
+}
+
+#[async_trait]
+impl LLMProvider for CassetteProvider {
+    fn name(&self) -> &str {
+        "Cassette"
+    }
+
+    fn provider_type(&self) -> Provider {
+        self.inner
+            .as_ref()
+            .map_or(Provider::OpenAICompatible, |inner| inner.provider_type())
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        match &self.inner {
+            Some(inner) => inner.supported_models(),
+            None => Vec::new(),
+        }
+    }
+
+    async fn health_check(&self) -> RsllmResult<bool> {
+        match &self.inner {
+            Some(inner) => inner.health_check().await,
+            None => Ok(true),
+        }
+    }
+
+    async fn chat_completion(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        self.chat_completion_with_params(messages, Vec::new(), model, &params)
+            .await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        model: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChunkStream> {
+        let response = self
+            .chat_completion(messages, model.as_deref(), temperature, max_tokens)
+            .await?;
+        Ok(replay_stream(response))
+    }
+
+    async fn chat_completion_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> RsllmResult<ChatResponse> {
+        let params = GenerationParams::sampling(temperature, max_tokens);
+        self.chat_completion_with_params(messages, tools, model, &params)
+            .await
+    }
+
+    async fn chat_completion_with_params(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        model: Option<&str>,
+        params: &GenerationParams,
+    ) -> RsllmResult<ChatResponse> {
+        let model_name = model.unwrap_or_default().to_string();
+        let key = CacheKeyParts {
+            provider: "cassette",
+            model: &model_name,
+            messages: &messages,
+            tools: &tools,
+            params,
+        }
+        .key();
+
+        let inner = match (self.mode, &self.inner) {
+            (CassetteMode::Record, Some(inner)) => inner,
+            _ => {
+                return self
+                    .lock()
+                    .interactions
+                    .get(&key)
+                    .map(|interaction| interaction.response.clone())
+                    .ok_or_else(|| {
+                        RsllmError::not_found(format!(
+                            "cassette interaction {} in {}",
+                            key,
+                            self.path.display()
+                        ))
+                    });
+            }
+        };
+
+        let response = inner
+            .chat_completion_with_params(messages.clone(), tools, model, params)
+            .await?;
+
+        let mut cassette = self.lock();
+        cassette.interactions.insert(
+            key,
+            Interaction {
+                model: model_name,
+                messages,
+                response: response.clone(),
+            },
+        );
+        self.save(&cassette)?;
+        Ok(response)
+    }
+}
+
+/// Split response text into word-sized pieces, keeping the whitespace
+fn words(content: &str) -> impl Iterator<Item = &str> {
+    content.split_inclusive(char::is_whitespace)
+}
+
+/// Replay a complete response word by word, followed by the final chunk
+fn replay_stream(response: ChatResponse) -> ChunkStream {
+    let mut chunks: Vec<RsllmResult<StreamChunk>> = words(&response.content)
+        .map(|word| {
+            Ok(StreamChunk::delta(word, response.model.clone()).with_role(MessageRole::Assistant))
+        })
+        .collect();
+    let mut done = StreamChunk::done(response.model.clone());
+    done.finish_reason = response.finish_reason.clone();
+    done.usage = response.usage.clone();
+    chunks.push(Ok(done));
+    Box::new(futures_util::stream::iter(chunks))
+}
+
+/// Replay a complete response word by word, then its tool calls
+fn replay_tool_stream(response: ChatResponse) -> ToolAwareStream {
+    let mut deltas: Vec<ToolAwareDelta> = words(&response.content)
+        .map(ToolAwareDelta::content)
+        .collect();
+    deltas.extend(ToolAwareDelta::from_chat_response(&ChatResponse {
+        content: String::new(),
+        ..response
+    }));
+    Box::pin(futures_util::stream::iter(deltas.into_iter().map(Ok)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn weather_mock() -> MockClient {
+        MockClient::builder()
+            .on_user_message_containing(
+                "weather",
+                respond_with_tool_call("get_weather", json!({"city": "Paris"})),
+            )
+            .on_tool_result("get_weather", respond_text("It is sunny in Paris."))
+            .otherwise(respond_text("ok"))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_mock_rules_and_recorded_requests() {
+        let mock = weather_mock();
+        let client = mock.client();
+
+        let mut conversation = vec![ChatMessage::user("What's the weather in Paris?")];
+        let response = client
+            .chat_completion_with_tools(conversation.clone(), Vec::new())
+            .await
+            .unwrap();
+        let calls = response.tool_calls.clone().unwrap();
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].function.arguments, json!({"city": "Paris"}));
+
+        let mut assistant = ChatMessage::assistant("");
+        assistant.tool_calls = Some(calls);
+        conversation.push(assistant);
+        conversation.push(ChatMessage::tool("call_1", "{\"temp\": 21}"));
+        let response = client.chat_completion(conversation).await.unwrap();
+        assert_eq!(response.content, "It is sunny in Paris.");
+
+        let response = client
+            .chat_completion(vec![ChatMessage::user("hello")])
+            .await
+            .unwrap();
+        assert_eq!(response.content, "ok");
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_mock_without_fallback_fails() {
+        let mock = MockClient::builder()
+            .on_user_message_containing("boom", respond_with_error("overloaded"))
+            .build();
+        let client = mock.client();
+
+        let err = client
+            .chat_completion(vec![ChatMessage::user("boom")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::Provider { .. }));
+
+        let err = client
+            .chat_completion(vec![ChatMessage::user("anything else")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::NotFound { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_cassette_record_then_replay() {
+        let path =
+            std::env::temp_dir().join(format!("rsllm-cassette-{}.json", uuid::Uuid::new_v4()));
+        let mock = weather_mock();
+
+        let recorder = CassetteProvider::record(mock.provider(), &path).unwrap();
+        let recorded = recorder
+            .chat_completion(
+                vec![ChatMessage::user("hello")],
+                Some("gpt-4o"),
+                Some(0.0),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(recorder.len(), 1);
+
+        let replayer = CassetteProvider::replay(&path).unwrap();
+        let replayed = replayer
+            .chat_completion(
+                vec![ChatMessage::user("hello")],
+                Some("gpt-4o"),
+                Some(0.0),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(replayed.content, recorded.content);
+        // The real provider is not called again
+        assert_eq!(mock.requests().len(), 1);
+
+        let err = replayer
+            .chat_completion(
+                vec![ChatMessage::user("hello")],
+                Some("gpt-4o"),
+                Some(0.7),
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RsllmError::NotFound { .. }));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
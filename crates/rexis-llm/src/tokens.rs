@@ -0,0 +1,301 @@
+//! # Token Counting
+//!
+//! Token estimation, per-model context windows, and trimming of message lists
+//! so requests fit within a model's context window.
+
+use crate::{ChatMessage, MessageContent, MessageRole};
+
+/// Context window assumed for models missing from the registry
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8192;
+
+/// Tokens charged per message for role and formatting
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Tokens charged once per request for reply priming
+const REQUEST_OVERHEAD: usize = 3;
+
+/// Counts tokens for text and message lists
+pub trait TokenCounter: Send + Sync {
+    /// Count tokens in a piece of text
+    fn count_text(&self, text: &str) -> usize;
+
+    /// Count tokens a message list will consume as a prompt
+    fn count_messages(&self, messages: &[ChatMessage]) -> usize {
+        let content: usize = messages
+            .iter()
+            .map(|message| {
+                let tool_calls = message.tool_calls.as_ref().map_or(0, |calls| {
+                    calls
+                        .iter()
+                        .map(|call| {
+                            self.count_text(&call.function.name)
+                                + self.count_text(&call.function.arguments.to_string())
+                        })
+                        .sum()
+                });
+                MESSAGE_OVERHEAD + self.count_text(message.text().unwrap_or("")) + tool_calls
+            })
+            .sum();
+        content + REQUEST_OVERHEAD
+    }
+}
+
+/// Heuristic counter assuming about four characters per token
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenCounter;
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count_text(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Exact counter backed by tiktoken BPE tables
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenCounter {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenCounter {
+    /// Counter using the `cl100k_base` encoding
+    pub fn cl100k() -> crate::RsllmResult<Self> {
+        let bpe = tiktoken_rs::cl100k_base()
+            .map_err(|e| crate::RsllmError::configuration(format!("tiktoken: {}", e)))?;
+        Ok(Self { bpe })
+    }
+
+    /// Counter using the encoding for a specific model
+    pub fn for_model(model: &str) -> crate::RsllmResult<Self> {
+        let bpe = tiktoken_rs::get_bpe_from_model(model)
+            .map_err(|e| crate::RsllmError::configuration(format!("tiktoken: {}", e)))?;
+        Ok(Self { bpe })
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for TiktokenCounter {
+    fn count_text(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+/// Look up the context window for a model
+///
+/// Matches on the longest known prefix, so dated snapshots such as
+/// `gpt-4o-2024-08-06` resolve to their family.
+pub fn context_window(model: &str) -> Option<usize> {
+    const WINDOWS: &[(&str, usize)] = &[
+        ("gpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-4-32k", 32_768),
+        ("gpt-4", 8_192),
+        ("gpt-3.5-turbo", 16_385),
+        ("o1", 128_000),
+        ("claude-3", 200_000),
+        ("claude", 200_000),
+        ("anthropic.claude", 200_000),
+        ("gemini-1.5-pro", 2_097_152),
+        ("gemini-1.5-flash", 1_048_576),
+        ("gemini-2.0", 1_048_576),
+        ("llama-3.3", 131_072),
+        ("llama-3.1", 131_072),
+        ("llama3.1", 128_000),
+        ("llama3.2", 128_000),
+        ("llama3", 8_192),
+        ("llama2", 4_096),
+        ("mistral-large", 131_072),
+        ("open-mistral-nemo", 131_072),
+        ("mistral", 32_768),
+        ("mixtral", 32_768),
+    ];
+
+    WINDOWS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| *window)
+}
+
+/// Trim a message list to fit within `max_prompt_tokens`
+///
+/// System messages are always kept. The oldest non-system messages are dropped
+/// first, together with any tool results orphaned by dropping their assistant
+/// message. If the newest message alone still does not fit, its text is truncated.
+pub fn fit_messages(
+    messages: Vec<ChatMessage>,
+    counter: &dyn TokenCounter,
+    max_prompt_tokens: usize,
+) -> Vec<ChatMessage> {
+    if counter.count_messages(&messages) <= max_prompt_tokens {
+        return messages;
+    }
+
+    let (system, mut rest): (Vec<_>, Vec<_>) = messages
+        .into_iter()
+        .partition(|message| message.role == MessageRole::System);
+
+    let fits = |system: &[ChatMessage], rest: &[ChatMessage]| {
+        let combined: Vec<ChatMessage> = system.iter().chain(rest.iter()).cloned().collect();
+        counter.count_messages(&combined) <= max_prompt_tokens
+    };
+
+    let original = rest.len();
+    while rest.len() > 1 && !fits(&system, &rest) {
+        rest.remove(0);
+        while rest.len() > 1 && rest[0].role == MessageRole::Tool {
+            rest.remove(0);
+        }
+    }
+
+    if !fits(&system, &rest) {
+        if let Some(last) = rest.pop() {
+            let budget = max_prompt_tokens.saturating_sub(counter.count_messages(&system));
+            rest.push(truncate_message(last, counter, budget));
+        }
+    }
+
+    if rest.len() < original {
+        tracing::debug!(
+            dropped = original - rest.len(),
+            kept = rest.len(),
+            max_prompt_tokens,
+            "Dropped oldest messages to fit context window"
+        );
+    }
+
+    system.into_iter().chain(rest).collect()
+}
+
+/// Truncate a message's text so it fits in `budget` tokens
+fn truncate_message(
+    mut message: ChatMessage,
+    counter: &dyn TokenCounter,
+    budget: usize,
+) -> ChatMessage {
+    let Some(text) = message.text().map(str::to_string) else {
+        return message;
+    };
+    let chars: Vec<char> = text.chars().collect();
+
+    // Binary search for the longest prefix that fits
+    let (mut low, mut high) = (0, chars.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        message.content = MessageContent::text(chars[..mid].iter().collect::<String>());
+        if counter.count_messages(std::slice::from_ref(&message)) <= budget {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    tracing::debug!(
+        original_chars = chars.len(),
+        kept_chars = low,
+        "Truncated message to fit context window"
+    );
+    message.content = MessageContent::text(chars[..low].iter().collect::<String>());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long_conversation(turns: usize) -> Vec<ChatMessage> {
+        let mut messages = vec![ChatMessage::system("You are a helpful assistant.")];
+        for i in 0..turns {
+            messages.push(ChatMessage::user(format!(
+                "Question {} {}",
+                i,
+                "x".repeat(400)
+            )));
+            messages.push(ChatMessage::assistant(format!(
+                "Answer {} {}",
+                i,
+                "y".repeat(400)
+            )));
+        }
+        messages.push(ChatMessage::user("What was my first question?"));
+        messages
+    }
+
+    #[test]
+    fn test_heuristic_counter() {
+        let counter = HeuristicTokenCounter;
+        assert_eq!(counter.count_text(""), 0);
+        assert_eq!(counter.count_text("abcd"), 1);
+        assert_eq!(counter.count_text("abcde"), 2);
+
+        let messages = vec![ChatMessage::user("abcdefgh")];
+        assert_eq!(
+            counter.count_messages(&messages),
+            2 + MESSAGE_OVERHEAD + REQUEST_OVERHEAD
+        );
+    }
+
+    #[test]
+    fn test_context_window_registry() {
+        assert_eq!(context_window("gpt-4o-2024-08-06"), Some(128_000));
+        assert_eq!(context_window("gpt-4"), Some(8_192));
+        assert_eq!(context_window("gpt-4-32k-0613"), Some(32_768));
+        assert_eq!(context_window("llama3.1:8b"), Some(128_000));
+        assert_eq!(context_window("my-custom-model"), None);
+    }
+
+    #[test]
+    fn test_fit_messages_keeps_system_and_fits() {
+        let counter = HeuristicTokenCounter;
+        let messages = long_conversation(50);
+        assert!(counter.count_messages(&messages) > 1000);
+
+        let fitted = fit_messages(messages, &counter, 1000);
+
+        assert!(counter.count_messages(&fitted) <= 1000);
+        assert_eq!(fitted[0].role, MessageRole::System);
+        assert_eq!(fitted[0].text(), Some("You are a helpful assistant."));
+        assert_eq!(
+            fitted.last().unwrap().text(),
+            Some("What was my first question?")
+        );
+        // Most recent history is kept, oldest is dropped
+        assert!(fitted
+            .iter()
+            .any(|m| m.text().unwrap().starts_with("Answer 49")));
+        assert!(!fitted
+            .iter()
+            .any(|m| m.text().unwrap().starts_with("Question 0 ")));
+    }
+
+    #[test]
+    fn test_fit_messages_drops_orphaned_tool_results() {
+        let counter = HeuristicTokenCounter;
+        let messages = vec![
+            ChatMessage::system("sys"),
+            ChatMessage::assistant("x".repeat(400)),
+            ChatMessage::tool("call_1", "y".repeat(40)),
+            ChatMessage::user("latest"),
+        ];
+
+        let fitted = fit_messages(messages, &counter, 40);
+
+        assert!(!fitted.iter().any(|m| m.role == MessageRole::Tool));
+        assert_eq!(fitted.last().unwrap().text(), Some("latest"));
+    }
+
+    #[test]
+    fn test_fit_messages_truncates_oversized_last_message() {
+        let counter = HeuristicTokenCounter;
+        let messages = vec![
+            ChatMessage::system("sys"),
+            ChatMessage::user("z".repeat(10_000)),
+        ];
+
+        let fitted = fit_messages(messages, &counter, 500);
+
+        assert!(counter.count_messages(&fitted) <= 500);
+        assert_eq!(fitted.len(), 2);
+        assert!(fitted[1].len() > 1000);
+    }
+}
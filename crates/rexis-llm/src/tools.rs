@@ -348,6 +348,11 @@ impl ToolRegistry {
         tool_calls.iter().map(|tc| self.execute(tc)).collect()
     }
 
+    /// Take the registered tools out of the registry
+    pub fn into_tools(self) -> Vec<Box<dyn Tool>> {
+        self.tools.into_values().collect()
+    }
+
     /// Number of registered tools
     pub fn len(&self) -> usize {
         self.tools.len()
@@ -0,0 +1,190 @@
+//! # Usage Tracking
+//!
+//! Cumulative token usage across requests, broken down by model and by an
+//! optional caller-supplied label such as an agent id. A tracker is shared via
+//! `Arc` between any number of clients.
+
+use crate::response::Usage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Accumulated token counts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Number of requests that reported usage
+    pub requests: u64,
+
+    /// Prompt tokens
+    pub prompt_tokens: u64,
+
+    /// Completion tokens
+    pub completion_tokens: u64,
+
+    /// Prompt and completion tokens combined
+    pub total_tokens: u64,
+
+    /// Prompt tokens read from the provider's cache
+    pub cached_tokens: u64,
+
+    /// Prompt tokens written to the provider's cache
+    pub cache_creation_tokens: u64,
+
+    /// Estimated cost of the priced requests in USD
+    pub cost_usd: f64,
+
+    /// Requests whose model has no known price
+    pub unpriced_requests: u64,
+}
+
+impl UsageTotals {
+    /// Add one response's usage and its estimated cost, if the model is priced
+    pub fn add(&mut self, usage: &Usage, cost_usd: Option<f64>) {
+        match cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+        self.requests += 1;
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.total_tokens += u64::from(usage.total_tokens);
+        self.cached_tokens += u64::from(usage.cached_tokens.unwrap_or(0));
+        self.cache_creation_tokens += u64::from(usage.cache_creation_tokens.unwrap_or(0));
+    }
+
+    /// Estimated cost in USD
+    ///
+    /// `None` when any request used a model without a known price, so partial
+    /// totals are never mistaken for complete ones.
+    pub fn estimated_cost_usd(&self) -> Option<f64> {
+        (self.unpriced_requests == 0).then_some(self.cost_usd)
+    }
+}
+
+/// Point-in-time view of a [`UsageTracker`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Usage across all requests
+    pub total: UsageTotals,
+
+    /// Usage per model
+    pub by_model: HashMap<String, UsageTotals>,
+
+    /// Usage per label; unlabeled requests only count towards the totals
+    pub by_label: HashMap<String, UsageTotals>,
+}
+
+impl UsageReport {
+    /// Estimated total cost in USD, or `None` if any model was unpriced
+    pub fn estimated_cost_usd(&self) -> Option<f64> {
+        self.total.estimated_cost_usd()
+    }
+}
+
+/// Thread-safe accumulator of token usage
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    report: Mutex<UsageReport>,
+}
+
+impl UsageTracker {
+    /// Create an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record usage reported for a response, with its estimated cost if priced
+    pub fn record(&self, model: &str, label: Option<&str>, usage: &Usage, cost_usd: Option<f64>) {
+        let mut report = self.report.lock().unwrap();
+        report.total.add(usage, cost_usd);
+        report
+            .by_model
+            .entry(model.to_string())
+            .or_default()
+            .add(usage, cost_usd);
+        if let Some(label) = label {
+            report
+                .by_label
+                .entry(label.to_string())
+                .or_default()
+                .add(usage, cost_usd);
+        }
+    }
+
+    /// Copy of the usage recorded so far
+    pub fn snapshot(&self) -> UsageReport {
+        self.report.lock().unwrap().clone()
+    }
+
+    /// Discard all recorded usage
+    pub fn reset(&self) {
+        *self.report.lock().unwrap() = UsageReport::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulates_by_model_and_label() {
+        let tracker = UsageTracker::new();
+        tracker.record("gpt-4o", Some("planner"), &Usage::new(100, 20), None);
+        tracker.record(
+            "gpt-4o",
+            Some("writer"),
+            &Usage::new(50, 10).with_cached_tokens(30),
+            None,
+        );
+        tracker.record("claude-3-5-haiku-20241022", None, &Usage::new(7, 3), None);
+
+        let report = tracker.snapshot();
+        assert_eq!(report.total.requests, 3);
+        assert_eq!(report.total.prompt_tokens, 157);
+        assert_eq!(report.total.completion_tokens, 33);
+        assert_eq!(report.total.total_tokens, 190);
+        assert_eq!(report.total.cached_tokens, 30);
+
+        assert_eq!(report.by_model["gpt-4o"].total_tokens, 180);
+        assert_eq!(report.by_model["claude-3-5-haiku-20241022"].requests, 1);
+        assert_eq!(report.by_label["planner"].prompt_tokens, 100);
+        assert_eq!(report.by_label["writer"].completion_tokens, 10);
+        assert_eq!(report.by_label.len(), 2);
+    }
+
+    #[test]
+    fn test_mixed_model_cost_with_unknown_model() {
+        use crate::pricing::PricingTable;
+
+        let pricing = PricingTable::default();
+        let tracker = UsageTracker::new();
+        for (model, usage) in [
+            ("gpt-4o-mini", Usage::new(1_000_000, 0)),
+            ("claude-3-5-haiku-20241022", Usage::new(0, 1_000_000)),
+            ("my-local-model", Usage::new(500, 50)),
+        ] {
+            tracker.record(model, None, &usage, pricing.cost(model, &usage));
+        }
+
+        let report = tracker.snapshot();
+        assert_eq!(report.total.unpriced_requests, 1);
+        assert_eq!(report.estimated_cost_usd(), None);
+
+        let mini = report.by_model["gpt-4o-mini"].estimated_cost_usd().unwrap();
+        let haiku = report.by_model["claude-3-5-haiku-20241022"]
+            .estimated_cost_usd()
+            .unwrap();
+        assert!((mini - 0.15).abs() < 1e-9);
+        assert!((haiku - 4.00).abs() < 1e-9);
+        assert_eq!(report.by_model["my-local-model"].estimated_cost_usd(), None);
+        assert!((report.total.cost_usd - 4.15).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reset() {
+        let tracker = UsageTracker::new();
+        tracker.record("gpt-4o", None, &Usage::new(1, 1), Some(0.01));
+        tracker.reset();
+        assert_eq!(tracker.snapshot(), UsageReport::default());
+    }
+}
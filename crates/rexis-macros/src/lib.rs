@@ -1,15 +1,45 @@
 //! Procedural macros for RSLLM tool calling
 //!
-//! This crate provides the `#[tool]` and `#[arg]` attribute macros for easy tool definition.
+//! This crate provides the `#[tool]` and `#[arg]` attribute macros for easy tool definition,
+//! and `#[derive(ToolArgs)]` for the argument types of typed agent tools.
+
+mod tool_args;
 
 use convert_case::{Case, Casing};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
     parse::{Parse, ParseStream},
-    parse_macro_input, ItemFn, ItemStruct, LitStr, Token,
+    parse_macro_input, DeriveInput, ItemFn, ItemStruct, LitStr, Token,
 };
 
+/// Derive `rexis_rag::agent::ToolArgs`, the JSON Schema of a tool's arguments
+///
+/// Fields become properties, doc comments become descriptions and `Option`
+/// fields (or fields with `#[serde(default)]`) are not required. Enums,
+/// `Vec`s and nested `ToolArgs` types are supported; the schema follows the
+/// type's serde attributes.
+///
+/// ```rust,ignore
+/// #[derive(Deserialize, ToolArgs)]
+/// struct ForecastArgs {
+///     /// City to forecast
+///     city: String,
+///     /// Days ahead, 1 by default
+///     days: Option<u32>,
+/// }
+/// ```
+///
+/// The implementation refers to `::rexis_rag`; when the crate is reached
+/// through another path, name it with `#[tool_args(crate = "rexis::rag")]`.
+#[proc_macro_derive(ToolArgs, attributes(tool_args))]
+pub fn derive_tool_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    tool_args::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 /// The `#[arg]` attribute for marking individual tool parameters
 ///
 /// Usage: `#[arg(description = "Parameter description")]`
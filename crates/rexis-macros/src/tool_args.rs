@@ -0,0 +1,519 @@
+//! `#[derive(ToolArgs)]`: JSON Schema for typed tool arguments
+//!
+//! The generated schema follows the type's serde representation, so it reads
+//! the `rename`, `rename_all`, `rename_all_fields`, `tag`, `default`, `skip`
+//! and `deny_unknown_fields` serde attributes. Representations the schema
+//! cannot express (`flatten`, `untagged`, adjacently tagged enums) are
+//! rejected at compile time.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    ext::IdentExt, meta::ParseNestedMeta, Attribute, Data, DataEnum, DataStruct, DeriveInput,
+    Fields, FieldsNamed, LitStr, Token,
+};
+
+/// Expand `#[derive(ToolArgs)]`
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let container = ContainerAttrs::parse(&input.attrs)?;
+    let krate = &container.krate;
+    let name = &input.ident;
+    let description = doc_string(&input.attrs);
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(syn::parse_quote!(#krate::agent::ToolArgs));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => struct_schema(data, &container, &description)?,
+        Data::Enum(data) => enum_schema(data, &container, &description)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "ToolArgs cannot be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics #krate::agent::ToolArgs for #name #ty_generics #where_clause {
+            fn json_schema() -> ::serde_json::Value {
+                #body
+            }
+        }
+    })
+}
+
+/// Schema of a struct: an object, or the inner schema of a newtype
+fn struct_schema(
+    data: &DataStruct,
+    container: &ContainerAttrs,
+    description: &str,
+) -> syn::Result<TokenStream> {
+    let krate = &container.krate;
+    match &data.fields {
+        Fields::Named(fields) => {
+            let calls = field_calls(fields, container.rename_all, container.default)?;
+            let deny = container
+                .deny_unknown_fields
+                .then(|| quote!(.deny_unknown_fields()));
+            Ok(quote! {
+                #krate::agent::ObjectSchema::new()
+                    .description(#description)
+                    #(#calls)*
+                    #deny
+                    .build()
+            })
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            Ok(quote!(<#ty as #krate::agent::ToolArgs>::json_schema()))
+        }
+        _ => Err(syn::Error::new_spanned(
+            &data.fields,
+            "ToolArgs needs a struct with named fields or a newtype struct",
+        )),
+    }
+}
+
+/// Schema of an enum, following its serde tagging
+fn enum_schema(
+    data: &DataEnum,
+    container: &ContainerAttrs,
+    description: &str,
+) -> syn::Result<TokenStream> {
+    let krate = &container.krate;
+    let mut calls = Vec::new();
+
+    for variant in &data.variants {
+        let attrs = FieldAttrs::parse(&variant.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let key = attrs.rename.unwrap_or_else(|| {
+            container
+                .rename_all
+                .apply_to_variant(&variant.ident.unraw().to_string())
+        });
+        let doc = doc_string(&variant.attrs);
+        let rename_fields = attrs.rename_all.unwrap_or(container.rename_all_fields);
+
+        let call = match (&container.tag, &variant.fields) {
+            (None, Fields::Unit) => quote!(.unit(#key)),
+            (None, Fields::Unnamed(fields)) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                quote! {
+                    .variant(
+                        #krate::agent::ObjectSchema::new()
+                            .description(#doc)
+                            .property(
+                                #key,
+                                <#ty as #krate::agent::ToolArgs>::json_schema(),
+                                "",
+                                true,
+                            )
+                            .deny_unknown_fields()
+                            .build(),
+                    )
+                }
+            }
+            (None, Fields::Named(fields)) => {
+                let field_calls = field_calls(fields, rename_fields, false)?;
+                quote! {
+                    .variant(
+                        #krate::agent::ObjectSchema::new()
+                            .property(
+                                #key,
+                                #krate::agent::ObjectSchema::new()
+                                    #(#field_calls)*
+                                    .build(),
+                                #doc,
+                                true,
+                            )
+                            .deny_unknown_fields()
+                            .build(),
+                    )
+                }
+            }
+            (Some(tag), Fields::Unit) => quote! {
+                .variant(
+                    #krate::agent::ObjectSchema::new()
+                        .description(#doc)
+                        .constant(#tag, #key)
+                        .build(),
+                )
+            },
+            (Some(tag), Fields::Named(fields)) => {
+                let field_calls = field_calls(fields, rename_fields, false)?;
+                quote! {
+                    .variant(
+                        #krate::agent::ObjectSchema::new()
+                            .description(#doc)
+                            .constant(#tag, #key)
+                            #(#field_calls)*
+                            .build(),
+                    )
+                }
+            }
+            (Some(_), Fields::Unnamed(_)) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "ToolArgs needs unit or struct variants in tagged enums",
+                ))
+            }
+            (None, Fields::Unnamed(_)) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "ToolArgs does not support tuple variants with several fields",
+                ))
+            }
+        };
+        calls.push(call);
+    }
+
+    Ok(quote! {
+        #krate::agent::EnumSchema::new()
+            .description(#description)
+            #(#calls)*
+            .build()
+    })
+}
+
+/// Builder calls adding the fields of a struct or struct variant
+fn field_calls(
+    fields: &FieldsNamed,
+    rename_all: RenameRule,
+    all_default: bool,
+) -> syn::Result<Vec<TokenStream>> {
+    let mut calls = Vec::new();
+    for field in &fields.named {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let key = attrs
+            .rename
+            .unwrap_or_else(|| rename_all.apply_to_field(&ident.unraw().to_string()));
+        let doc = doc_string(&field.attrs);
+        let ty = &field.ty;
+
+        calls.push(if attrs.default || all_default {
+            quote!(.optional_field::<#ty>(#key, #doc))
+        } else {
+            quote!(.field::<#ty>(#key, #doc))
+        });
+    }
+    Ok(calls)
+}
+
+/// Doc comment text; lines of a paragraph are joined with spaces
+fn doc_string(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(meta) => match &meta.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(text),
+                    ..
+                }) => Some(text.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    lines
+        .split(|line| line.is_empty())
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| paragraph.join(" "))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Attributes on the deriving type
+struct ContainerAttrs {
+    /// Path of the crate providing `ToolArgs`
+    krate: syn::Path,
+    rename_all: RenameRule,
+    rename_all_fields: RenameRule,
+    tag: Option<String>,
+    default: bool,
+    deny_unknown_fields: bool,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = ContainerAttrs {
+            krate: syn::parse_quote!(::rexis_rag),
+            rename_all: RenameRule::None,
+            rename_all_fields: RenameRule::None,
+            tag: None,
+            default: false,
+            deny_unknown_fields: false,
+        };
+
+        for attr in attrs {
+            if attr.path().is_ident("tool_args") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("crate") {
+                        container.krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unknown tool_args attribute, expected `crate`"))
+                    }
+                })?;
+            } else if attr.path().is_ident("serde") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename_all") {
+                        if let Some(rule) = deserialize_name(&meta)? {
+                            container.rename_all = RenameRule::parse(&rule)?;
+                        }
+                    } else if meta.path.is_ident("rename_all_fields") {
+                        if let Some(rule) = deserialize_name(&meta)? {
+                            container.rename_all_fields = RenameRule::parse(&rule)?;
+                        }
+                    } else if meta.path.is_ident("tag") {
+                        container.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                    } else if meta.path.is_ident("default") {
+                        container.default = true;
+                        skip_meta(&meta)?;
+                    } else if meta.path.is_ident("deny_unknown_fields") {
+                        container.deny_unknown_fields = true;
+                    } else if meta.path.is_ident("untagged") || meta.path.is_ident("content") {
+                        return Err(meta.error(
+                            "ToolArgs supports externally and internally tagged enums only",
+                        ));
+                    } else {
+                        skip_meta(&meta)?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+
+        Ok(container)
+    }
+}
+
+/// Serde attributes on a field or variant
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+    default: bool,
+    skip: bool,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut field = FieldAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Some(name) = deserialize_name(&meta)? {
+                        field.rename = Some(name.value());
+                    }
+                } else if meta.path.is_ident("rename_all") {
+                    if let Some(rule) = deserialize_name(&meta)? {
+                        field.rename_all = Some(RenameRule::parse(&rule)?);
+                    }
+                } else if meta.path.is_ident("default") {
+                    field.default = true;
+                    skip_meta(&meta)?;
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    field.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    return Err(meta.error("ToolArgs does not support flattened fields"));
+                } else {
+                    skip_meta(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(field)
+    }
+}
+
+/// Value of `name = "..."` or the `deserialize` half of `name(deserialize = "...")`
+fn deserialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<LitStr>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+
+    let mut name = None;
+    meta.parse_nested_meta(|nested| {
+        let value: LitStr = nested.value()?.parse()?;
+        if nested.path.is_ident("deserialize") {
+            name = Some(value);
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consume the value of an attribute the schema does not depend on
+fn skip_meta(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.input.parse::<proc_macro2::TokenTree>()?;
+    }
+    Ok(())
+}
+
+/// Serde's `rename_all` rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenameRule {
+    None,
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(rule: &LitStr) -> syn::Result<Self> {
+        match rule.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lower),
+            "UPPERCASE" => Ok(RenameRule::Upper),
+            "PascalCase" => Ok(RenameRule::Pascal),
+            "camelCase" => Ok(RenameRule::Camel),
+            "snake_case" => Ok(RenameRule::Snake),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnake),
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "SCREAMING-KEBAB-CASE" => Ok(RenameRule::ScreamingKebab),
+            other => Err(syn::Error::new(
+                rule.span(),
+                format!("unknown rename rule `{}`", other),
+            )),
+        }
+    }
+
+    /// Rename a PascalCase variant name
+    fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            RenameRule::None | RenameRule::Pascal => variant.to_string(),
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Camel => lowercase_first(variant),
+            RenameRule::Snake => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            RenameRule::ScreamingSnake => RenameRule::Snake
+                .apply_to_variant(variant)
+                .to_ascii_uppercase(),
+            RenameRule::Kebab => RenameRule::Snake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+            RenameRule::ScreamingKebab => RenameRule::ScreamingSnake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+
+    /// Rename a snake_case field name
+    fn apply_to_field(self, field: &str) -> String {
+        match self {
+            RenameRule::None | RenameRule::Lower | RenameRule::Snake => field.to_string(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+            RenameRule::Pascal => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            RenameRule::Camel => lowercase_first(&RenameRule::Pascal.apply_to_field(field)),
+            RenameRule::Kebab => field.replace('_', "-"),
+            RenameRule::ScreamingKebab => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+}
+
+fn lowercase_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_rules_match_serde() {
+        let cases = [
+            (
+                RenameRule::Snake,
+                "HomeGarden",
+                "home_garden",
+                "home_garden",
+            ),
+            (RenameRule::Camel, "HomeGarden", "homeGarden", "homeGarden"),
+            (RenameRule::Pascal, "HomeGarden", "HomeGarden", "HomeGarden"),
+            (
+                RenameRule::Kebab,
+                "HomeGarden",
+                "home-garden",
+                "home-garden",
+            ),
+            (
+                RenameRule::ScreamingSnake,
+                "HomeGarden",
+                "HOME_GARDEN",
+                "HOME_GARDEN",
+            ),
+            (RenameRule::Lower, "HomeGarden", "homegarden", "home_garden"),
+        ];
+
+        for (rule, variant, renamed_variant, renamed_field) in cases {
+            assert_eq!(rule.apply_to_variant(variant), renamed_variant);
+            assert_eq!(rule.apply_to_field("home_garden"), renamed_field);
+        }
+    }
+
+    #[test]
+    fn test_doc_string_joins_paragraph_lines() {
+        let input: DeriveInput = syn::parse_quote! {
+            /// Search the catalog
+            /// by keyword.
+            ///
+            /// Results are ranked.
+            struct Search;
+        };
+
+        assert_eq!(
+            doc_string(&input.attrs),
+            "Search the catalog by keyword.\n\nResults are ranked."
+        );
+    }
+}
@@ -122,7 +122,7 @@ async fn demo_vector_search(storage: Arc<dyn Memory>) -> Result<(), Box<dyn std:
     // Search for similar facts
     info!("\nSearching for facts similar to 'Rust development'...");
     let results = semantic
-        .find_similar("Rust development", &provider, 3, 0.0)
+        .find_similar("Rust development", &provider, 3, 0.0, None)
         .await?;
 
     for (i, result) in results.iter().enumerate() {
@@ -0,0 +1,146 @@
+//! Human-in-the-loop approval of tool calls
+
+use async_trait::async_trait;
+use rexis_llm::ToolCall;
+use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
+
+/// Decision on a tool call awaiting approval
+#[derive(Debug, Clone, PartialEq)]
+pub enum Approval {
+    /// Execute the call as requested
+    Approve,
+
+    /// Skip the call; the reason is sent back to the model as the tool result
+    Deny {
+        /// Why the call was refused
+        reason: String,
+    },
+
+    /// Execute the call with different arguments
+    Edit {
+        /// Arguments to execute the tool with
+        new_args: serde_json::Value,
+    },
+}
+
+/// Decides whether the agent may execute a tool call
+///
+/// Consulted for the tools listed in
+/// [`AgentConfig::approval_required`](super::AgentConfig::approval_required)
+/// before each of their calls. The run waits for the decision, bounded by its
+/// deadline and cancellation token.
+#[async_trait]
+pub trait ApprovalHook: Send + Sync {
+    /// Approve, deny or edit `call`
+    async fn approve(&self, call: &ToolCall) -> Approval;
+}
+
+/// Tool call waiting for a decision from a [`ChannelApprovalHook`] receiver
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    /// Call the model requested
+    pub call: ToolCall,
+
+    respond: oneshot::Sender<Approval>,
+}
+
+impl ApprovalRequest {
+    /// Answer the request
+    ///
+    /// Answering after the run stopped waiting is not an error; the decision is
+    /// discarded.
+    pub fn respond(self, approval: Approval) {
+        let _ = self.respond.send(approval);
+    }
+
+    /// Execute the call as requested
+    pub fn approve(self) {
+        self.respond(Approval::Approve);
+    }
+
+    /// Skip the call, telling the model why
+    pub fn deny(self, reason: impl Into<String>) {
+        self.respond(Approval::Deny {
+            reason: reason.into(),
+        });
+    }
+
+    /// Execute the call with different arguments
+    pub fn edit(self, new_args: serde_json::Value) {
+        self.respond(Approval::Edit { new_args });
+    }
+}
+
+/// Approval hook that forwards each call over a channel, e.g. to a UI
+///
+/// Calls are denied when the receiver has been dropped or a request is dropped
+/// without an answer.
+#[derive(Debug, Clone)]
+pub struct ChannelApprovalHook {
+    requests: mpsc::Sender<ApprovalRequest>,
+}
+
+impl ChannelApprovalHook {
+    /// Create a hook and the receiver its approval requests arrive on
+    pub fn new(buffer: usize) -> (Self, mpsc::Receiver<ApprovalRequest>) {
+        let (requests, receiver) = mpsc::channel(buffer);
+        (Self { requests }, receiver)
+    }
+}
+
+#[async_trait]
+impl ApprovalHook for ChannelApprovalHook {
+    async fn approve(&self, call: &ToolCall) -> Approval {
+        let (respond, decision) = oneshot::channel();
+        let request = ApprovalRequest {
+            call: call.clone(),
+            respond,
+        };
+
+        if self.requests.send(request).await.is_err() {
+            warn!(tool = %call.function.name, "Approval receiver dropped, denying tool call");
+            return Approval::Deny {
+                reason: "No approver is available".to_string(),
+            };
+        }
+
+        decision.await.unwrap_or_else(|_| {
+            warn!(tool = %call.function.name, "Approval request dropped, denying tool call");
+            Approval::Deny {
+                reason: "The approval request was dropped".to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_channel_hook_forwards_decisions() {
+        let (hook, mut requests) = ChannelApprovalHook::new(1);
+        let call = ToolCall::function("call_1", "send_email", serde_json::json!({"to": "a@b.c"}));
+
+        let approver = tokio::spawn(async move {
+            let request = requests.recv().await.unwrap();
+            assert_eq!(request.call.function.name, "send_email");
+            request.deny("not today");
+            let request = requests.recv().await.unwrap();
+            drop(request);
+        });
+
+        assert_eq!(
+            hook.approve(&call).await,
+            Approval::Deny {
+                reason: "not today".to_string()
+            }
+        );
+        assert!(matches!(hook.approve(&call).await, Approval::Deny { .. }));
+        approver.await.unwrap();
+
+        // Receiver gone
+        assert!(matches!(hook.approve(&call).await, Approval::Deny { .. }));
+    }
+}
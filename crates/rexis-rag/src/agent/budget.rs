@@ -0,0 +1,137 @@
+//! Spending limits for agent runs
+
+use rexis_llm::UsageTotals;
+use serde::{Deserialize, Serialize};
+
+/// Limit of a [`RunBudget`] that stopped a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    /// [`RunBudget::max_total_tokens`]
+    TotalTokens,
+    /// [`RunBudget::max_cost_usd`]
+    CostUsd,
+    /// [`RunBudget::max_tool_calls`]
+    ToolCalls,
+}
+
+/// Token, cost and tool call limits for a single run
+///
+/// The limits apply differently because of when their usage is known:
+///
+/// - Tokens and cost are reported with each model response, so they are
+///   checked after every LLM step. The step that reaches a limit completes and
+///   may take the run past it; no step starts after that, and tools it
+///   requested are not run.
+/// - Tool calls are checked before they run. A step whose calls would take the
+///   run past [`max_tool_calls`](Self::max_tool_calls) runs none of them, so
+///   this limit is never exceeded.
+///
+/// Either way the run returns with
+/// [`StopReason::BudgetExceeded`](super::StopReason::BudgetExceeded). When it
+/// stopped on tool calls with an iteration to spare, the model is first asked,
+/// without tools and within the tokens left, for a final answer; otherwise the
+/// output is the last model text. Cost counts only models with a known price.
+/// Budgets apply to non-streaming runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunBudget {
+    /// Prompt and completion tokens for the whole run
+    pub max_total_tokens: Option<u64>,
+
+    /// Estimated cost of the run in USD
+    pub max_cost_usd: Option<f64>,
+
+    /// Tool calls made during the run
+    pub max_tool_calls: Option<usize>,
+}
+
+impl RunBudget {
+    /// Create a budget with no limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the tokens the run may use
+    pub fn with_max_total_tokens(mut self, tokens: u64) -> Self {
+        self.max_total_tokens = Some(tokens);
+        self
+    }
+
+    /// Limit the estimated cost of the run
+    pub fn with_max_cost_usd(mut self, cost_usd: f64) -> Self {
+        self.max_cost_usd = Some(cost_usd);
+        self
+    }
+
+    /// Limit the tool calls the run may make
+    pub fn with_max_tool_calls(mut self, calls: usize) -> Self {
+        self.max_tool_calls = Some(calls);
+        self
+    }
+
+    /// Token or cost limit reached by `usage`, if any
+    pub fn usage_limit_reached(&self, usage: &UsageTotals) -> Option<BudgetLimit> {
+        if self
+            .max_total_tokens
+            .is_some_and(|max| usage.total_tokens >= max)
+        {
+            Some(BudgetLimit::TotalTokens)
+        } else if self.max_cost_usd.is_some_and(|max| usage.cost_usd >= max) {
+            Some(BudgetLimit::CostUsd)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `requested` more tool calls fit after `made`
+    pub fn allows_tool_calls(&self, made: usize, requested: usize) -> bool {
+        self.max_tool_calls
+            .map_or(true, |max| made.saturating_add(requested) <= max)
+    }
+
+    /// Tokens left under the token limit, if there is one
+    pub fn remaining_tokens(&self, usage: &UsageTotals) -> Option<u64> {
+        self.max_total_tokens
+            .map(|max| max.saturating_sub(usage.total_tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_limits_reached_at_the_limit() {
+        let budget = RunBudget::new()
+            .with_max_total_tokens(100)
+            .with_max_cost_usd(0.10);
+        let mut usage = UsageTotals {
+            total_tokens: 99,
+            cost_usd: 0.05,
+            ..UsageTotals::default()
+        };
+        assert_eq!(budget.usage_limit_reached(&usage), None);
+        assert_eq!(budget.remaining_tokens(&usage), Some(1));
+
+        usage.total_tokens = 100;
+        assert_eq!(
+            budget.usage_limit_reached(&usage),
+            Some(BudgetLimit::TotalTokens)
+        );
+        usage.total_tokens = 10;
+        usage.cost_usd = 0.25;
+        assert_eq!(
+            budget.usage_limit_reached(&usage),
+            Some(BudgetLimit::CostUsd)
+        );
+        assert_eq!(RunBudget::new().usage_limit_reached(&usage), None);
+    }
+
+    #[test]
+    fn test_tool_calls_stop_before_exceeding() {
+        let budget = RunBudget::new().with_max_tool_calls(3);
+        assert!(budget.allows_tool_calls(1, 2));
+        assert!(!budget.allows_tool_calls(2, 2));
+        assert!(RunBudget::new().allows_tool_calls(usize::MAX, 1));
+    }
+}
@@ -1,20 +1,71 @@
 //! Agent builder pattern
 
-use super::memory::{AgentMemoryManager, MemoryConfig};
-use super::{Agent, AgentConfig, ConversationMode, ToolExecutor};
-use crate::error::RragResult;
+use super::agent::DEFAULT_AGENT_ID;
+use super::memory::{AgentMemoryManager, MemoryConfig, WorkingMemory};
+use super::{
+    Agent, AgentConfig, AgentHooks, AgentRegistry, ApprovalHook, ConversationMode, Guardrail,
+    HookMode, Profile, PromptTemplate, RunBudget, StopCondition, SyncTool, Tool, ToolCache,
+    ToolErrorPolicy, ToolExecutor, ToolRetryPolicy,
+};
+use crate::error::{RragError, RragResult};
+use crate::storage::{InMemoryStorage, Memory};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
 
 #[cfg(feature = "rexis-llm-client")]
-use rexis_llm::tools::{Tool, ToolRegistry};
+use rexis_llm::tools::Tool as LlmTool;
 #[cfg(feature = "rexis-llm-client")]
-use rexis_llm::Client;
+use rexis_llm::{Client, GenerationParams, RetryPolicy};
+
+/// Memory settings given to the builder piece by piece
+#[derive(Default)]
+struct MemoryOptions {
+    storage: Option<Arc<dyn Memory>>,
+    agent_id: Option<String>,
+    session_id: Option<String>,
+    persist_conversations: bool,
+    enable_semantic: bool,
+    enable_episodic: bool,
+    enable_working: bool,
+}
+
+impl MemoryOptions {
+    fn is_set(&self) -> bool {
+        self.storage.is_some()
+            || self.agent_id.is_some()
+            || self.session_id.is_some()
+            || self.persist_conversations
+            || self.enable_semantic
+            || self.enable_episodic
+            || self.enable_working
+    }
+}
 
 /// Builder for creating agents
 pub struct AgentBuilder {
     llm_client: Option<Client>,
     tools: Vec<Box<dyn Tool>>,
+    tool_allowlist: Option<Vec<String>>,
     config: AgentConfig,
     memory_config: Option<MemoryConfig>,
+    memory: MemoryOptions,
+    approval_hook: Option<Arc<dyn ApprovalHook>>,
+    hooks: Vec<Arc<dyn AgentHooks>>,
+    guardrails: Vec<Arc<dyn Guardrail>>,
+    handoffs: Option<Arc<AgentRegistry>>,
+    default_tool_timeout: Option<Duration>,
+    tool_timeouts: HashMap<String, Duration>,
+    tool_retry_policy: ToolRetryPolicy,
+    max_parallel_tools: Option<usize>,
+    tool_cache: Option<Arc<dyn Memory>>,
+    validate_tool_arguments: bool,
+    max_tool_output_chars: Option<usize>,
+    tool_output_limits: HashMap<String, usize>,
+    summarize_large_tool_outputs: bool,
+    output_summarizer: Option<Client>,
+    memory_tools: bool,
 }
 
 impl AgentBuilder {
@@ -23,20 +74,45 @@ impl AgentBuilder {
         Self {
             llm_client: None,
             tools: Vec::new(),
+            tool_allowlist: None,
             config: AgentConfig::default(),
             memory_config: None,
+            memory: MemoryOptions::default(),
+            approval_hook: None,
+            hooks: Vec::new(),
+            guardrails: Vec::new(),
+            handoffs: None,
+            default_tool_timeout: None,
+            tool_timeouts: HashMap::new(),
+            tool_retry_policy: ToolRetryPolicy::default(),
+            max_parallel_tools: None,
+            tool_cache: None,
+            validate_tool_arguments: true,
+            max_tool_output_chars: None,
+            tool_output_limits: HashMap::new(),
+            summarize_large_tool_outputs: false,
+            output_summarizer: None,
+            memory_tools: false,
         }
     }
 
+    /// Start from an agent profile, given as a file path or as TOML or JSON
+    ///
+    /// See [`Profile`] for the schema. Tools the profile lists still have to
+    /// be added to the builder.
+    pub fn from_profile(path_or_str: impl AsRef<str>) -> RragResult<Self> {
+        Profile::load(path_or_str)?.to_builder()
+    }
+
     /// Set the LLM client
     pub fn with_llm(mut self, client: Client) -> Self {
         self.llm_client = Some(client);
         self
     }
 
-    /// Add a single tool
-    pub fn with_tool(mut self, tool: Box<dyn Tool>) -> Self {
-        self.tools.push(tool);
+    /// Add a single tool; call repeatedly to add more
+    pub fn with_tool(mut self, tool: impl Tool + 'static) -> Self {
+        self.tools.push(Box::new(tool));
         self
     }
 
@@ -46,9 +122,70 @@ impl AgentBuilder {
         self
     }
 
+    /// Add the tools of a module, alongside tools added before
+    pub fn with_tool_module(mut self, tools: impl IntoIterator<Item = Box<dyn Tool>>) -> Self {
+        self.tools.extend(tools);
+        self
+    }
+
+    /// Add the built-in [`default_tools`](super::tools::default_tools)
+    pub fn with_default_tools(self) -> Self {
+        self.with_tool_module(super::tools::default_tools())
+    }
+
+    /// Add the [`memory_tools`](super::tools::memory_tools), letting the model
+    /// remember and recall facts and keep notes
+    ///
+    /// Building fails unless memory is configured, with
+    /// [`with_memory`](Self::with_memory) or the builder's memory settings.
+    pub fn with_memory_tools(mut self) -> Self {
+        self.memory_tools = true;
+        self
+    }
+
+    /// Connect to an MCP server and add all of its tools
+    ///
+    /// Fails when the server cannot be reached or does not list its tools.
+    #[cfg(feature = "mcp")]
+    pub async fn with_mcp_server(self, config: super::mcp::McpServerConfig) -> RragResult<Self> {
+        let provider = super::mcp::McpToolProvider::connect(config).await?;
+        Ok(self.with_tool_module(provider.tools()))
+    }
+
+    /// Add a synchronous `rexis_llm` tool, run through [`SyncTool`]
+    pub fn with_sync_tool(mut self, tool: Box<dyn LlmTool>) -> Self {
+        self.tools.push(SyncTool::boxed(tool));
+        self
+    }
+
+    /// Add multiple synchronous `rexis_llm` tools
+    pub fn with_sync_tools(mut self, tools: Vec<Box<dyn LlmTool>>) -> Self {
+        self.tools.extend(tools.into_iter().map(SyncTool::boxed));
+        self
+    }
+
+    /// Offer only the named tools to the model
+    ///
+    /// Other tools added to the builder are left out of the agent, and
+    /// building fails when a named tool was not added.
+    pub fn with_tool_allowlist<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tool_allowlist = Some(tools.into_iter().map(Into::into).collect());
+        self
+    }
+
     /// Set system prompt
     pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
-        self.config.system_prompt = prompt.into();
+        self.config = self.config.with_system_prompt(prompt);
+        self
+    }
+
+    /// Render the system prompt from a template at the start of each run
+    pub fn with_system_prompt_template(mut self, template: impl Into<PromptTemplate>) -> Self {
+        self.config = self.config.with_system_prompt_template(template);
         self
     }
 
@@ -83,19 +220,319 @@ impl AgentBuilder {
     }
 
     /// Set max conversation length
+    ///
+    /// Also bounds the stored conversation when the builder assembles the
+    /// memory configuration.
     pub fn with_max_conversation_length(mut self, length: usize) -> Self {
         self.config.max_conversation_length = length;
         self
     }
 
+    /// Trim the conversation to the model's context window before each LLM step
+    pub fn with_context_fitting(mut self, reserve_output_tokens: usize) -> Self {
+        self.config.fit_context_window = true;
+        self.config.reserve_output_tokens = reserve_output_tokens;
+        self
+    }
+
+    /// Bound each run by an overall deadline
+    pub fn with_run_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.run_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the generation parameters for LLM steps
+    pub fn with_generation_params(mut self, params: GenerationParams) -> Self {
+        self.config.generation = params;
+        self
+    }
+
+    /// Limit the tokens, cost and tool calls of each run
+    pub fn with_budget(mut self, budget: RunBudget) -> Self {
+        self.config.budget = budget;
+        self
+    }
+
+    /// Retry failed LLM steps within the run, keeping the work done so far
+    pub fn with_llm_retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.llm_retry = policy;
+        self
+    }
+
+    /// Return a result instead of an error when a run uses up its iterations
+    pub fn with_return_on_max_iterations(mut self, enabled: bool) -> Self {
+        self.config.return_on_max_iterations = enabled;
+        self
+    }
+
+    /// Set the timeout for tools without a timeout of their own
+    pub fn with_default_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.default_tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for one tool
+    pub fn with_tool_timeout(mut self, tool: impl Into<String>, timeout: Duration) -> Self {
+        self.tool_timeouts.insert(tool.into(), timeout);
+        self
+    }
+
+    /// Set the retry policy for failed tool calls
+    pub fn with_tool_retry_policy(mut self, policy: ToolRetryPolicy) -> Self {
+        self.tool_retry_policy = policy;
+        self
+    }
+
+    /// Set how many tool calls of one step run at once
+    pub fn with_max_parallel_tools(mut self, max: usize) -> Self {
+        self.max_parallel_tools = Some(max);
+        self
+    }
+
+    /// Limit tool outputs to `max_chars` characters, unless a tool has a
+    /// limit of its own
+    ///
+    /// Longer outputs are truncated, keeping their start and end.
+    pub fn with_max_tool_output_chars(mut self, max_chars: usize) -> Self {
+        self.max_tool_output_chars = Some(max_chars);
+        self
+    }
+
+    /// Limit the outputs of one tool to `max_chars` characters
+    pub fn with_tool_output_limit(mut self, tool: impl Into<String>, max_chars: usize) -> Self {
+        self.tool_output_limits.insert(tool.into(), max_chars);
+        self
+    }
+
+    /// Summarize tool outputs over their limit with a model instead of
+    /// truncating them
+    ///
+    /// The agent's client summarizes unless
+    /// [`with_output_summarizer`](Self::with_output_summarizer) sets another.
+    /// Originals are kept in the session's working memory, or in process when
+    /// the agent has no persistent memory.
+    pub fn summarize_large_tool_outputs(mut self, enabled: bool) -> Self {
+        self.summarize_large_tool_outputs = enabled;
+        self
+    }
+
+    /// Summarize tool outputs with `client`, such as one for a cheaper model
+    pub fn with_output_summarizer(mut self, client: Client) -> Self {
+        self.output_summarizer = Some(client);
+        self
+    }
+
+    /// Store cached tool results in `storage`
+    ///
+    /// By default results of tools with a
+    /// [`CachePolicy`](super::CachePolicy) are kept in the session's working
+    /// memory, or in process when the agent has no persistent memory.
+    pub fn with_tool_cache(mut self, storage: Arc<dyn Memory>) -> Self {
+        self.tool_cache = Some(storage);
+        self
+    }
+
+    /// Set what happens when a tool call fails
+    pub fn tool_error_policy(mut self, policy: ToolErrorPolicy) -> Self {
+        self.config.tool_errors = policy;
+        self
+    }
+
+    /// Fail the run when a tool call fails instead of reporting the error to the model
+    pub fn fail_run_on_tool_error(mut self, enabled: bool) -> Self {
+        self.config = self.config.with_fail_run_on_tool_error(enabled);
+        self
+    }
+
+    /// Fail the run once the model calls one tool with invalid arguments
+    /// `max` times in a row
+    pub fn with_strict_tool_arguments(mut self, max: u32) -> Self {
+        self.config = self.config.with_strict_tool_arguments(max);
+        self
+    }
+
+    /// Check tool arguments against the tool's parameters schema before
+    /// running it; on by default
+    pub fn validate_tool_arguments(mut self, enabled: bool) -> Self {
+        self.validate_tool_arguments = enabled;
+        self
+    }
+
+    /// Set how many times a typed run may ask the model to repair its answer
+    pub fn with_max_output_repairs(mut self, max: u32) -> Self {
+        self.config.max_output_repairs = max;
+        self
+    }
+
+    /// Set the hook that approves calls to tools requiring approval
+    pub fn with_approval_hook(mut self, hook: Arc<dyn ApprovalHook>) -> Self {
+        self.approval_hook = Some(hook);
+        self
+    }
+
+    /// Add lifecycle hooks; hooks run in the order they are added
+    pub fn with_hooks(mut self, hooks: Arc<dyn AgentHooks>) -> Self {
+        self.hooks.push(hooks);
+        self
+    }
+
+    /// Add a guardrail; guardrails screen text in the order they are added
+    pub fn with_guardrail(mut self, guardrail: Arc<dyn Guardrail>) -> Self {
+        self.guardrails.push(guardrail);
+        self
+    }
+
+    /// Let the agent hand off to the agents in `registry` through the
+    /// built-in `handoff_to` tool
+    pub fn with_handoffs(mut self, registry: &Arc<AgentRegistry>) -> Self {
+        self.handoffs = Some(Arc::clone(registry));
+        self
+    }
+
+    /// Set how deeply handoffs may nest
+    pub fn with_max_handoff_depth(mut self, depth: usize) -> Self {
+        self.config.max_handoff_depth = depth;
+        self
+    }
+
+    /// Add a stop condition, checked after those already added
+    pub fn with_stop_condition(mut self, condition: impl StopCondition + 'static) -> Self {
+        self.config = self.config.with_stop_condition(condition);
+        self
+    }
+
+    /// Set whether hook errors abort the run
+    pub fn with_hook_mode(mut self, mode: HookMode) -> Self {
+        self.config.hook_mode = mode;
+        self
+    }
+
+    /// Record run traces in working memory, keeping the last `max_runs`
+    pub fn with_scratchpad(mut self, max_runs: usize) -> Self {
+        self.config = self.config.with_scratchpad(max_runs);
+        self
+    }
+
+    /// Extract facts into semantic memory every `every_turns` completed turns
+    pub fn with_auto_extract_facts(mut self, every_turns: usize) -> Self {
+        self.config = self.config.with_auto_extract_facts(every_turns);
+        self
+    }
+
+    /// Require approval before calls to the named tools
+    pub fn require_approval_for<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .approval_required
+            .extend(tools.into_iter().map(Into::into));
+        self
+    }
+
     /// Set memory configuration (enables persistent memory)
+    ///
+    /// Memory settings made on the builder, such as
+    /// [`with_session_id`](Self::with_session_id), apply on top of it.
     pub fn with_memory(mut self, memory_config: MemoryConfig) -> Self {
         self.memory_config = Some(memory_config);
         self
     }
 
+    /// Keep the agent's memory in `storage`
+    pub fn with_storage(mut self, storage: Arc<dyn Memory>) -> Self {
+        self.memory.storage = Some(storage);
+        self
+    }
+
+    /// Set the agent id scoping semantic and episodic memory
+    pub fn with_agent_id(mut self, agent_id: impl Into<String>) -> Self {
+        self.memory.agent_id = Some(agent_id.into());
+        self
+    }
+
+    /// Set the memory session, instead of a generated one
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.memory.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Persist the conversation in the memory storage
+    pub fn with_persistence(mut self) -> Self {
+        self.memory.persist_conversations = true;
+        self
+    }
+
+    /// Enable semantic memory (facts)
+    pub fn with_semantic_memory(mut self) -> Self {
+        self.memory.enable_semantic = true;
+        self
+    }
+
+    /// Enable episodic memory (session summaries)
+    pub fn with_episodic_memory(mut self) -> Self {
+        self.memory.enable_episodic = true;
+        self
+    }
+
+    /// Enable working memory (session scratchpad)
+    pub fn with_working_memory(mut self) -> Self {
+        self.memory.enable_working = true;
+        self
+    }
+
+    /// Memory configuration from [`with_memory`](Self::with_memory) and the
+    /// builder's memory settings, if there is any
+    ///
+    /// Without a storage backend memory is kept in process, which is refused
+    /// for a stateful agent asked to persist its conversation.
+    fn memory_config(&mut self) -> RragResult<Option<MemoryConfig>> {
+        let options = std::mem::take(&mut self.memory);
+        let mut memory_config = match self.memory_config.take() {
+            Some(memory_config) => memory_config,
+            None if !options.is_set() => return Ok(None),
+            None => {
+                let backend = match &options.storage {
+                    Some(storage) => storage.clone(),
+                    None if options.persist_conversations
+                        && self.config.conversation_mode.keeps_history() =>
+                    {
+                        return Err(RragError::validation(
+                            "storage",
+                            "must be set with with_storage() to persist a stateful conversation",
+                            "none",
+                        ));
+                    }
+                    None => {
+                        warn!("No memory storage set; agent memory is kept in process");
+                        Arc::new(InMemoryStorage::new())
+                    }
+                };
+                MemoryConfig::new(backend, DEFAULT_AGENT_ID)
+                    .with_max_conversation_length(self.config.max_conversation_length)
+            }
+        };
+
+        if let Some(storage) = options.storage {
+            memory_config.backend = storage;
+        }
+        if let Some(agent_id) = options.agent_id {
+            memory_config.agent_id = agent_id;
+        }
+        if let Some(session_id) = options.session_id {
+            memory_config.session_id = Some(session_id);
+        }
+        memory_config.persist_conversations |= options.persist_conversations;
+        memory_config.enable_semantic |= options.enable_semantic;
+        memory_config.enable_episodic |= options.enable_episodic;
+        memory_config.enable_working |= options.enable_working;
+        Ok(Some(memory_config))
+    }
+
     /// Build the agent
-    pub fn build(self) -> RragResult<Agent> {
+    pub fn build(mut self) -> RragResult<Agent> {
+        let memory_config = self.memory_config()?;
         let llm_client = self
             .llm_client
             .ok_or_else(|| crate::error::RragError::Agent {
@@ -104,10 +541,47 @@ impl AgentBuilder {
                 source: None,
             })?;
 
-        // Create tool registry
-        let mut registry = ToolRegistry::new();
+        let memory_manager = memory_config.map(AgentMemoryManager::new);
+        if self.memory_tools {
+            let Some(memory) = &memory_manager else {
+                return Err(RragError::validation(
+                    "memory",
+                    "must be configured to add memory tools",
+                    "none",
+                ));
+            };
+            self.tools
+                .extend(super::tools::memory_tools(Arc::new(memory.clone())));
+        }
+
+        let mut names = HashSet::new();
+        if let Some(tool) = self.tools.iter().find(|tool| !names.insert(tool.name())) {
+            return Err(RragError::agent(
+                "builder",
+                format!(
+                    "Tool '{}' is added more than once; tool names must be unique",
+                    tool.name()
+                ),
+            ));
+        }
+
+        if let Some(allowlist) = &self.tool_allowlist {
+            if let Some(missing) = allowlist.iter().find(|name| !names.contains(name.as_str())) {
+                return Err(RragError::validation(
+                    "tools",
+                    "must name tools added to the builder",
+                    missing.as_str(),
+                ));
+            }
+            self.tools
+                .retain(|tool| allowlist.iter().any(|name| name == tool.name()));
+        }
+
+        let mut tool_executor = ToolExecutor::empty()
+            .with_retry_policy(self.tool_retry_policy)
+            .with_argument_validation(self.validate_tool_arguments);
         for tool in self.tools {
-            registry
+            tool_executor
                 .register(tool)
                 .map_err(|e| crate::error::RragError::Agent {
                     agent_id: "builder".to_string(),
@@ -116,15 +590,66 @@ impl AgentBuilder {
                 })?;
         }
 
-        let tool_executor = ToolExecutor::new(registry);
+        if let Some(timeout) = self.default_tool_timeout {
+            tool_executor = tool_executor.with_default_timeout(timeout);
+        }
+        if let Some(max) = self.max_parallel_tools {
+            tool_executor = tool_executor.with_max_concurrency(max);
+        }
+        for (tool, timeout) in self.tool_timeouts {
+            tool_executor = tool_executor.with_tool_timeout(tool, timeout);
+        }
+        if let Some(max_chars) = self.max_tool_output_chars {
+            tool_executor = tool_executor.with_max_output_chars(max_chars);
+        }
+        for (tool, max_chars) in self.tool_output_limits {
+            tool_executor = tool_executor.with_tool_max_output_chars(tool, max_chars);
+        }
+
+        if self.summarize_large_tool_outputs {
+            let store = match &memory_manager {
+                Some(memory) => {
+                    WorkingMemory::new_persistent(memory.storage(), memory.session_id().to_string())
+                }
+                None => WorkingMemory::new_persistent(
+                    Arc::new(InMemoryStorage::new()),
+                    "default".to_string(),
+                ),
+            };
+            let summarizer = self.output_summarizer.unwrap_or_else(|| llm_client.clone());
+            tool_executor = tool_executor
+                .with_summarizer(summarizer)
+                .with_summarize_large_outputs(true)
+                .with_output_store(store);
+        }
+        let tool_cache = match (self.tool_cache, &memory_manager) {
+            (Some(storage), Some(memory)) => ToolCache::for_session(storage, memory.session_id()),
+            (Some(storage), None) => ToolCache::new(storage, "tool_cache"),
+            (None, Some(memory)) => ToolCache::for_session(memory.storage(), memory.session_id()),
+            (None, None) => ToolCache::in_memory(),
+        };
+        let tool_executor = tool_executor.with_cache(tool_cache);
 
         // Build with or without persistent memory
-        if let Some(memory_config) = self.memory_config {
-            let memory_manager = AgentMemoryManager::new(memory_config);
-            Agent::new_with_memory(llm_client, tool_executor, memory_manager, self.config)
+        let agent = if let Some(memory_manager) = memory_manager {
+            Agent::new_with_memory(llm_client, tool_executor, memory_manager, self.config)?
         } else {
-            Agent::new(llm_client, tool_executor, self.config)
-        }
+            Agent::new(llm_client, tool_executor, self.config)?
+        };
+
+        let agent = self.hooks.into_iter().fold(agent, Agent::with_hooks);
+        let agent = self
+            .guardrails
+            .into_iter()
+            .fold(agent, Agent::with_guardrail);
+        let agent = match &self.handoffs {
+            Some(registry) => agent.with_handoffs(registry),
+            None => agent,
+        };
+        Ok(match self.approval_hook {
+            Some(hook) => agent.with_approval_hook(hook),
+            None => agent,
+        })
     }
 }
 
@@ -133,3 +658,256 @@ impl Default for AgentBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rexis_llm::testing::{respond_text, respond_with_tool_call, MockClient};
+
+    fn builder(mock: &MockClient) -> AgentBuilder {
+        AgentBuilder::new().with_llm(mock.client())
+    }
+
+    /// Tool known only by its name
+    struct NamedTool(&'static str);
+
+    #[async_trait::async_trait]
+    impl Tool for NamedTool {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "Does nothing"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        async fn call(&self, _args: serde_json::Value) -> RragResult<super::super::ToolOutput> {
+            Ok(super::super::ToolOutput::Text(String::new()))
+        }
+    }
+
+    fn module(names: &[&'static str]) -> Vec<Box<dyn Tool>> {
+        names
+            .iter()
+            .map(|&name| Box::new(NamedTool(name)) as Box<dyn Tool>)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_tools_combined_and_duplicates_rejected() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+
+        let mut agent = builder(&mock)
+            .with_tool(NamedTool("search"))
+            .with_tool_module(module(&["fetch", "parse"]))
+            .with_tool(NamedTool("summarize"))
+            .build()
+            .unwrap();
+        agent.run("hi").await.unwrap();
+        let mut names: Vec<_> = mock.requests()[0]
+            .tools
+            .iter()
+            .map(|tool| tool.name.clone())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["fetch", "parse", "search", "summarize"]);
+
+        let err = builder(&mock)
+            .with_tool_module(module(&["search", "fetch"]))
+            .with_tool(NamedTool("search"))
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            RragError::Agent { ref message, .. } if message.contains("Tool 'search'")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_default_tools_added() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+
+        let mut agent = builder(&mock)
+            .with_tool(NamedTool("search"))
+            .with_default_tools()
+            .build()
+            .unwrap();
+        agent.run("hi").await.unwrap();
+        let mut names: Vec<_> = mock.requests()[0]
+            .tools
+            .iter()
+            .map(|tool| tool.name.clone())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["calculator", "search"]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_tools_round_trip_a_fact() {
+        let mock = MockClient::builder()
+            .on_user_message_containing(
+                "remember",
+                respond_with_tool_call(
+                    "remember_fact",
+                    serde_json::json!({
+                        "subject": "user",
+                        "predicate": "prefers",
+                        "object": "window seats",
+                        "confidence": 0.9
+                    }),
+                ),
+            )
+            .on_tool_result(
+                "remember_fact",
+                respond_with_tool_call("recall_facts", serde_json::json!({"query": "user"})),
+            )
+            .on_tool_result("recall_facts", respond_text("You prefer window seats."))
+            .build();
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+
+        let mut agent = builder(&mock)
+            .with_storage(Arc::clone(&storage))
+            .with_agent_id("concierge")
+            .with_memory_tools()
+            .build()
+            .unwrap();
+        let result = agent
+            .run_detailed("Please remember that I like window seats")
+            .await
+            .unwrap();
+
+        let called: Vec<&str> = result
+            .tool_invocations
+            .iter()
+            .map(|invocation| invocation.name.as_str())
+            .collect();
+        assert_eq!(called, ["remember_fact", "recall_facts"]);
+        let recalled: serde_json::Value =
+            serde_json::from_str(&result.tool_invocations[1].result).unwrap();
+        assert_eq!(recalled["total"], 1);
+        assert_eq!(recalled["facts"][0]["object"], "window seats");
+        assert_eq!(recalled["facts"][0]["source"], "model");
+        assert_eq!(result.output, "You prefer window seats.");
+
+        let semantic = super::super::memory::SemanticMemory::new(storage, "concierge".to_string());
+        let facts = semantic.find_by_subject("user").await.unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].confidence, 0.9);
+        assert_eq!(facts[0].metadata["source"], "model");
+
+        let err = builder(&mock).with_memory_tools().build().err().unwrap();
+        assert!(matches!(err, RragError::Validation { ref field, .. } if field == "memory"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_allowlist_selects_registered_tools() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+
+        let mut agent = builder(&mock)
+            .with_tool_module(module(&["search", "fetch", "parse"]))
+            .with_tool_allowlist(["parse", "search"])
+            .build()
+            .unwrap();
+        agent.run("hi").await.unwrap();
+        let mut names: Vec<_> = mock.requests()[0]
+            .tools
+            .iter()
+            .map(|tool| tool.name.clone())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["parse", "search"]);
+
+        let err = builder(&mock)
+            .with_tool(NamedTool("search"))
+            .with_tool_allowlist(["search", "summarize"])
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            RragError::Validation { ref field, ref value, .. }
+                if field == "tools" && value == "summarize"
+        ));
+    }
+
+    #[test]
+    fn test_memory_assembled_from_builder_settings() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+
+        let agent = builder(&mock).build().unwrap();
+        assert!(agent.memory().is_none());
+
+        let agent = builder(&mock)
+            .with_storage(Arc::new(InMemoryStorage::new()))
+            .with_agent_id("ada")
+            .with_session_id("s1")
+            .with_persistence()
+            .with_semantic_memory()
+            .with_working_memory()
+            .with_max_conversation_length(8)
+            .stateful()
+            .build()
+            .unwrap();
+        let memory = agent.memory().unwrap();
+        assert_eq!(agent.agent_id(), "ada");
+        assert_eq!(memory.session_id(), "s1");
+        let config = memory.config();
+        assert!(config.persist_conversations);
+        assert!(config.enable_semantic && config.enable_working);
+        assert!(!config.enable_episodic);
+        assert_eq!(config.max_conversation_length, 8);
+    }
+
+    #[test]
+    fn test_memory_defaults_to_in_process_storage() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+
+        let agent = builder(&mock).with_episodic_memory().build().unwrap();
+        let memory = agent.memory().unwrap();
+        assert_eq!(agent.agent_id(), "default");
+        assert!(memory.config().enable_episodic);
+        assert!(!memory.session_id().is_empty());
+
+        // Nothing is persisted for a stateless agent, so in-process storage is fine
+        assert!(builder(&mock).with_persistence().build().is_ok());
+    }
+
+    #[test]
+    fn test_stateful_persistence_requires_storage() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+
+        let err = builder(&mock)
+            .stateful()
+            .with_persistence()
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(err, RragError::Validation { ref field, .. } if field == "storage"));
+    }
+
+    #[test]
+    fn test_builder_settings_apply_over_memory_config() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+        let memory_config = MemoryConfig::new(Arc::new(InMemoryStorage::new()), "ada")
+            .with_session_id("s1")
+            .with_semantic_memory(true);
+
+        let agent = builder(&mock)
+            .with_memory(memory_config)
+            .with_session_id("s2")
+            .with_persistence()
+            .stateful()
+            .build()
+            .unwrap();
+        let memory = agent.memory().unwrap();
+        assert_eq!(agent.agent_id(), "ada");
+        assert_eq!(memory.session_id(), "s2");
+        assert!(memory.config().persist_conversations);
+        assert!(memory.config().enable_semantic);
+    }
+}
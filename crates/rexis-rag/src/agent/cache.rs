@@ -0,0 +1,209 @@
+//! Caching of tool results
+//!
+//! Tools opt in through [`Tool::cache_policy`](super::Tool::cache_policy).
+//! Results live in a [`Memory`] namespace, by default the working memory of the
+//! agent's session, keyed by tool name and a hash of the canonicalized
+//! arguments.
+
+use crate::error::RragResult;
+use crate::storage::{InMemoryStorage, Memory, MemoryValue};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// How long results of a tool may be reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachePolicy {
+    /// Always execute the tool
+    #[default]
+    NoCache,
+
+    /// Reuse a result for this many seconds
+    TtlSeconds(u64),
+
+    /// Reuse a result for as long as the cache's session lasts
+    SessionScoped,
+}
+
+impl CachePolicy {
+    /// Whether results are cached at all
+    pub fn is_cacheable(&self) -> bool {
+        !matches!(self, CachePolicy::NoCache)
+    }
+}
+
+/// Stored tool result
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    content: String,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Tool result cache backed by a [`Memory`] namespace
+pub struct ToolCache {
+    storage: Arc<dyn Memory>,
+    namespace: String,
+}
+
+impl ToolCache {
+    /// Create a cache in a custom namespace
+    pub fn new(storage: Arc<dyn Memory>, namespace: impl Into<String>) -> Self {
+        Self {
+            storage,
+            namespace: namespace.into(),
+        }
+    }
+
+    /// Create a cache in the working memory of a session
+    pub fn for_session(storage: Arc<dyn Memory>, session_id: &str) -> Self {
+        Self::new(
+            storage,
+            format!("session::{}::working::tool_cache", session_id),
+        )
+    }
+
+    /// Create a process-local cache
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(InMemoryStorage::new()), "tool_cache")
+    }
+
+    /// Namespace the entries are stored under
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Look up a result
+    ///
+    /// Expired or unreadable entries count as misses.
+    pub async fn get(&self, tool: &str, args: &serde_json::Value) -> RragResult<Option<String>> {
+        let key = self.key(tool, args);
+        let entry = self
+            .storage
+            .get(&key)
+            .await?
+            .and_then(|value| value.as_json().cloned())
+            .and_then(|json| serde_json::from_value::<CacheEntry>(json).ok());
+
+        match entry {
+            Some(entry) if entry.expires_at.map_or(true, |at| at > Utc::now()) => {
+                Ok(Some(entry.content))
+            }
+            Some(_) => {
+                self.storage.delete(&key).await?;
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Store a result under `policy`; uncacheable policies store nothing
+    pub async fn put(
+        &self,
+        tool: &str,
+        args: &serde_json::Value,
+        content: &str,
+        policy: CachePolicy,
+    ) -> RragResult<()> {
+        let expires_at = match policy {
+            CachePolicy::NoCache => return Ok(()),
+            CachePolicy::TtlSeconds(seconds) => {
+                let ttl = chrono::Duration::from_std(std::time::Duration::from_secs(seconds))
+                    .unwrap_or(chrono::Duration::MAX);
+                Some(
+                    Utc::now()
+                        .checked_add_signed(ttl)
+                        .unwrap_or(DateTime::<Utc>::MAX_UTC),
+                )
+            }
+            CachePolicy::SessionScoped => None,
+        };
+        let entry = CacheEntry {
+            content: content.to_string(),
+            expires_at,
+        };
+
+        self.storage
+            .set(
+                &self.key(tool, args),
+                MemoryValue::Json(serde_json::to_value(entry)?),
+            )
+            .await
+    }
+
+    /// Remove all entries
+    pub async fn clear(&self) -> RragResult<()> {
+        self.storage.clear(Some(&self.namespace)).await
+    }
+
+    fn key(&self, tool: &str, args: &serde_json::Value) -> String {
+        let digest = Sha256::digest(canonicalize(args).to_string().as_bytes());
+        format!("{}::{}::{:x}", self.namespace, tool, digest)
+    }
+}
+
+impl std::fmt::Debug for ToolCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolCache")
+            .field("backend", &self.storage.backend_name())
+            .field("namespace", &self.namespace)
+            .finish()
+    }
+}
+
+/// Rebuild `value` with object keys sorted, so equal arguments hash alike
+fn canonicalize(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonicalize(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(canonicalize).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_key_ignores_argument_order() {
+        let cache = ToolCache::in_memory();
+        let args = serde_json::json!({"city": "Paris", "units": {"temp": "c", "wind": "kmh"}});
+        let reordered = serde_json::json!({"units": {"wind": "kmh", "temp": "c"}, "city": "Paris"});
+
+        cache
+            .put("weather", &args, "sunny", CachePolicy::SessionScoped)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            cache.get("weather", &reordered).await.unwrap().as_deref(),
+            Some("sunny")
+        );
+        assert_eq!(cache.get("forecast", &args).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_no_cache_policy_stores_nothing() {
+        let cache = ToolCache::in_memory();
+        let args = serde_json::json!({});
+
+        cache
+            .put("clock", &args, "12:00", CachePolicy::NoCache)
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("clock", &args).await.unwrap(), None);
+    }
+}
@@ -1,6 +1,12 @@
 //! Agent configuration
 
+use super::{HookMode, PromptTemplate, RunBudget, StopCondition};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "rexis-llm-client")]
+use rexis_llm::{GenerationParams, RetryPolicy};
 
 /// Agent conversation mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -9,6 +15,56 @@ pub enum ConversationMode {
     Stateless,
     /// Stateful: Maintains conversation across calls
     Stateful,
+    /// Sends the system prompt and the last `last_n` messages of the
+    /// conversation, which is still stored in full
+    SlidingWindow {
+        /// Messages of the conversation sent with each run
+        last_n: usize,
+    },
+    /// Sends a rolling summary of the conversation in the system prompt, and
+    /// the last `keep_recent` messages as they are
+    ///
+    /// The summary is updated with the model at the start of a run when
+    /// messages have left the recent window; the conversation is still stored
+    /// in full.
+    Summarized {
+        /// Messages of the conversation sent as they are
+        keep_recent: usize,
+    },
+}
+
+impl ConversationMode {
+    /// Whether the conversation is kept across calls; true for every mode but
+    /// [`Stateless`](Self::Stateless)
+    pub fn keeps_history(&self) -> bool {
+        !matches!(self, Self::Stateless)
+    }
+}
+
+/// What happens when a tool call fails
+///
+/// A failed call is reported to the model as a tool result shaped
+/// `{"error": {"tool": ..., "message": ..., "retryable": bool}}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorPolicy {
+    /// Send the error to the model as the tool result
+    #[default]
+    ReturnToModel,
+
+    /// Abort the run with [`RragError::ToolExecution`](crate::RragError::ToolExecution)
+    ///
+    /// Calls with invalid arguments are still sent back to the model;
+    /// [`AgentConfig::max_invalid_tool_calls`] bounds those.
+    FailRun,
+
+    /// Retry a call whose failure is retryable, such as a timeout or a
+    /// network error, up to `attempts` attempts in all, then send the error to
+    /// the model
+    RetryThenReturn {
+        /// Attempts per call, the first included
+        attempts: u32,
+    },
 }
 
 /// Agent configuration
@@ -17,6 +73,10 @@ pub struct AgentConfig {
     /// System prompt that defines agent behavior
     pub system_prompt: String,
 
+    /// Template the system prompt is rendered from at the start of each run
+    #[serde(skip)]
+    pub prompt_template: Option<PromptTemplate>,
+
     /// Maximum iterations before stopping (prevents infinite loops)
     pub max_iterations: usize,
 
@@ -28,16 +88,141 @@ pub struct AgentConfig {
 
     /// Maximum conversation history length (for stateful mode)
     pub max_conversation_length: usize,
+
+    /// Trim the conversation to the model's context window before each LLM step
+    #[serde(default)]
+    pub fit_context_window: bool,
+
+    /// Tokens reserved for the model's reply when fitting the context window
+    #[serde(default = "default_reserve_output_tokens")]
+    pub reserve_output_tokens: usize,
+
+    /// Deadline for a whole run; each LLM step gets the time remaining
+    #[serde(default)]
+    pub run_timeout: Option<Duration>,
+
+    /// Generation parameters for LLM steps; unset fields use the client's defaults
+    #[serde(default)]
+    pub generation: GenerationParams,
+
+    /// Retries of a failed LLM step within the run, on top of the client's own
+    ///
+    /// A retried step is sent again with the conversation built so far, so
+    /// tool results from earlier iterations are kept. Off by default.
+    #[serde(default = "RetryPolicy::none")]
+    pub llm_retry: RetryPolicy,
+
+    /// Finish runs that use up their iterations with the last model text and a
+    /// [`StopReason::MaxIterations`](super::StopReason::MaxIterations) result
+    /// instead of an error
+    #[serde(default)]
+    pub return_on_max_iterations: bool,
+
+    /// Spending limits for each run; see [`RunBudget`] for how they apply
+    #[serde(default)]
+    pub budget: RunBudget,
+
+    /// Tools whose calls must be approved by the agent's
+    /// [`ApprovalHook`](super::ApprovalHook) before they run
+    #[serde(default)]
+    pub approval_required: Vec<String>,
+
+    /// What happens when a tool call fails after the executor's retries
+    #[serde(default)]
+    pub tool_errors: ToolErrorPolicy,
+
+    /// Consecutive calls to one tool with arguments that do not match its
+    /// schema before the run fails; `None` lets the model keep correcting the
+    /// arguments until it runs out of iterations
+    #[serde(default)]
+    pub max_invalid_tool_calls: Option<u32>,
+
+    /// Repair round-trips allowed when the final answer of a
+    /// [`run_typed`](super::Agent::run_typed) run does not match its schema
+    #[serde(default = "default_max_output_repairs")]
+    pub max_output_repairs: u32,
+
+    /// Whether errors from [`AgentHooks`](super::AgentHooks) abort the run
+    #[serde(default)]
+    pub hook_mode: HookMode,
+
+    /// Write each model response and tool result of a run to the session's
+    /// working memory; needs a memory manager
+    #[serde(default)]
+    pub record_scratchpad: bool,
+
+    /// Recorded runs to keep per session; older traces are dropped
+    #[serde(default = "default_scratchpad_max_runs")]
+    pub scratchpad_max_runs: usize,
+
+    /// Extract facts from completed turns into semantic memory; needs stateful
+    /// mode and a memory manager with semantic memory enabled
+    #[serde(default)]
+    pub auto_extract_facts: bool,
+
+    /// Completed turns between fact extractions; each extraction covers every
+    /// turn since the last one
+    #[serde(default = "default_fact_extraction_interval")]
+    pub fact_extraction_interval: usize,
+
+    /// Nesting allowed for handoffs started by this agent; a handoff from a
+    /// run this deep is refused
+    #[serde(default = "default_max_handoff_depth")]
+    pub max_handoff_depth: usize,
+
+    /// Conditions checked after each iteration, in order, before the run's
+    /// usual end on a response without tool calls
+    #[serde(skip)]
+    pub stop_conditions: Vec<Arc<dyn StopCondition>>,
+}
+
+fn default_reserve_output_tokens() -> usize {
+    1024
+}
+
+fn default_max_output_repairs() -> u32 {
+    2
+}
+
+fn default_scratchpad_max_runs() -> usize {
+    10
+}
+
+fn default_fact_extraction_interval() -> usize {
+    1
+}
+
+fn default_max_handoff_depth() -> usize {
+    3
 }
 
 impl Default for AgentConfig {
     fn default() -> Self {
         Self {
             system_prompt: "You are a helpful assistant with access to tools. Use tools when needed to provide accurate information.".to_string(),
+            prompt_template: None,
             max_iterations: 10,
             verbose: false,
             conversation_mode: ConversationMode::Stateless,
             max_conversation_length: 50,
+            fit_context_window: false,
+            reserve_output_tokens: default_reserve_output_tokens(),
+            run_timeout: None,
+            generation: GenerationParams::default(),
+            llm_retry: RetryPolicy::none(),
+            return_on_max_iterations: false,
+            budget: RunBudget::default(),
+            approval_required: Vec::new(),
+            tool_errors: ToolErrorPolicy::ReturnToModel,
+            max_invalid_tool_calls: None,
+            max_output_repairs: default_max_output_repairs(),
+            hook_mode: HookMode::default(),
+            record_scratchpad: false,
+            scratchpad_max_runs: default_scratchpad_max_runs(),
+            auto_extract_facts: false,
+            fact_extraction_interval: default_fact_extraction_interval(),
+            max_handoff_depth: default_max_handoff_depth(),
+            stop_conditions: Vec::new(),
         }
     }
 }
@@ -51,6 +236,15 @@ impl AgentConfig {
     /// Set system prompt
     pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
         self.system_prompt = prompt.into();
+        self.prompt_template = None;
+        self
+    }
+
+    /// Render the system prompt from a template at the start of each run
+    pub fn with_system_prompt_template(mut self, template: impl Into<PromptTemplate>) -> Self {
+        let template = template.into();
+        self.system_prompt = template.source().to_string();
+        self.prompt_template = Some(template);
         self
     }
 
@@ -77,4 +271,112 @@ impl AgentConfig {
         self.max_conversation_length = length;
         self
     }
+
+    /// Trim the conversation to the context window before each LLM step
+    pub fn with_context_fitting(mut self, reserve_output_tokens: usize) -> Self {
+        self.fit_context_window = true;
+        self.reserve_output_tokens = reserve_output_tokens;
+        self
+    }
+
+    /// Bound each run by an overall deadline
+    pub fn with_run_timeout(mut self, timeout: Duration) -> Self {
+        self.run_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the generation parameters for LLM steps
+    pub fn with_generation_params(mut self, params: GenerationParams) -> Self {
+        self.generation = params;
+        self
+    }
+
+    /// Return a result instead of an error when a run uses up its iterations
+    pub fn with_return_on_max_iterations(mut self, enabled: bool) -> Self {
+        self.return_on_max_iterations = enabled;
+        self
+    }
+
+    /// Require approval before calls to the named tools
+    pub fn with_approval_required<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.approval_required = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set what happens when a tool call fails
+    pub fn with_tool_error_policy(mut self, policy: ToolErrorPolicy) -> Self {
+        self.tool_errors = policy;
+        self
+    }
+
+    /// Fail the run when a tool call fails instead of reporting the error to the model
+    pub fn with_fail_run_on_tool_error(mut self, enabled: bool) -> Self {
+        self.tool_errors = if enabled {
+            ToolErrorPolicy::FailRun
+        } else {
+            ToolErrorPolicy::ReturnToModel
+        };
+        self
+    }
+
+    /// Fail the run once the model calls one tool with invalid arguments
+    /// `max` times in a row
+    pub fn with_strict_tool_arguments(mut self, max: u32) -> Self {
+        self.max_invalid_tool_calls = Some(max.max(1));
+        self
+    }
+
+    /// Set how many times a typed run may ask the model to repair its answer
+    pub fn with_max_output_repairs(mut self, max: u32) -> Self {
+        self.max_output_repairs = max;
+        self
+    }
+
+    /// Set whether hook errors abort the run
+    pub fn with_hook_mode(mut self, mode: HookMode) -> Self {
+        self.hook_mode = mode;
+        self
+    }
+
+    /// Record run traces in working memory, keeping the last `max_runs`
+    pub fn with_scratchpad(mut self, max_runs: usize) -> Self {
+        self.record_scratchpad = true;
+        self.scratchpad_max_runs = max_runs;
+        self
+    }
+
+    /// Extract facts into semantic memory every `every_turns` completed turns
+    pub fn with_auto_extract_facts(mut self, every_turns: usize) -> Self {
+        self.auto_extract_facts = true;
+        self.fact_extraction_interval = every_turns.max(1);
+        self
+    }
+
+    /// Set how deeply handoffs may nest
+    pub fn with_max_handoff_depth(mut self, depth: usize) -> Self {
+        self.max_handoff_depth = depth;
+        self
+    }
+
+    /// Add a stop condition, checked after those already added
+    pub fn with_stop_condition(mut self, condition: impl StopCondition + 'static) -> Self {
+        self.stop_conditions.push(Arc::new(condition));
+        self
+    }
+
+    /// Limit the spending of each run
+    pub fn with_budget(mut self, budget: RunBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Retry failed LLM steps within the run under `policy`
+    pub fn with_llm_retry(mut self, policy: RetryPolicy) -> Self {
+        self.llm_retry = policy;
+        self
+    }
 }
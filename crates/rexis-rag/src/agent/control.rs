@@ -0,0 +1,51 @@
+//! Deadlines and cancellation for agent runs
+
+use rexis_llm::{CancellationToken, ChatMessage};
+use std::time::Duration;
+
+/// Limits on a single agent run
+///
+/// The deadline covers the whole run, LLM calls and tool executions alike, and
+/// replaces [`AgentConfig::run_timeout`](super::AgentConfig::run_timeout) for
+/// that run. A tool call cut short by the deadline or the cancellation token
+/// keeps running on the blocking thread pool until it returns.
+#[derive(Debug, Clone, Default)]
+pub struct RunControl {
+    /// Time limit for the run
+    pub deadline: Option<Duration>,
+
+    /// Token that aborts the run when cancelled
+    pub cancel: Option<CancellationToken>,
+}
+
+impl RunControl {
+    /// Create run limits with no deadline and no cancellation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time limit for the run
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the cancellation token
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
+/// Progress of an agent run that timed out or was cancelled
+///
+/// Carried by [`RragError::Timeout`](crate::RragError::Timeout) and
+/// [`RragError::Cancelled`](crate::RragError::Cancelled).
+#[derive(Debug, Clone)]
+pub struct PartialRun {
+    /// Iterations that completed, tool calls included
+    pub iterations: usize,
+
+    /// Conversation up to the point the run stopped
+    pub conversation: Vec<ChatMessage>,
+}
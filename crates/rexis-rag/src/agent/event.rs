@@ -0,0 +1,43 @@
+//! Events emitted by streaming agent runs
+
+use crate::error::RragError;
+
+/// Progress of an agent run, as yielded by [`Agent::run_stream`](super::Agent::run_stream)
+#[derive(Debug)]
+pub enum AgentEvent {
+    /// An LLM step started (1-based)
+    IterationStarted(u32),
+
+    /// Text generated by the model
+    Token(String),
+
+    /// The model requested a tool call; all calls of a step are announced
+    /// before they run
+    ToolCallStarted {
+        /// Tool name
+        name: String,
+        /// Arguments the model supplied
+        args: serde_json::Value,
+    },
+
+    /// A tool call finished
+    ToolCallFinished {
+        /// Tool name
+        name: String,
+        /// Result as sent back to the model
+        result: String,
+    },
+
+    /// The final answer, assembled from the tokens of the last step
+    Final(String),
+
+    /// The run failed; no further events follow
+    Error(RragError),
+}
+
+impl AgentEvent {
+    /// Whether this event ends the run
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Final(_) | Self::Error(_))
+    }
+}
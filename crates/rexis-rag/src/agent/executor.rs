@@ -1,51 +1,1371 @@
 //! Tool execution for agents
 
-use rexis_llm::tools::{ToolCall as ToolExec, ToolRegistry};
-use rexis_llm::{ChatMessage, ToolCall};
+use super::memory::WorkingMemory;
+use super::output_limit::{self, OutputReduction, ORIGINAL_KEY_PREFIX};
+use super::{validate_arguments, ArgumentViolation, CachePolicy, SyncTool, Tool, ToolCache};
+use crate::error::{RragError, RragResult};
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use rexis_llm::tools::{ToolDefinition, ToolRegistry};
+use rexis_llm::{ChatMessage, Client, ToolCall};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinError;
+use tokio::time::Instant;
+use tracing::field::Empty;
+use tracing::{debug, info_span, warn, Instrument};
+
+/// Tool calls run at once unless configured otherwise
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// Why a tool call failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ToolFailure {
+    /// The tool did not finish within its timeout
+    TimedOut {
+        /// Timeout that applied to the attempt
+        timeout: Duration,
+    },
+
+    /// The tool returned an error or panicked
+    Failed {
+        /// Error reported by the tool
+        message: String,
+
+        /// Whether the tool's error was one a retry may get past
+        #[serde(default)]
+        retryable: bool,
+    },
+
+    /// The run does not allow the tool, so the call was blocked
+    NotAllowed,
+
+    /// The arguments do not match the tool's parameters schema, so the tool
+    /// was not run
+    InvalidArguments {
+        /// Every constraint the arguments break
+        violations: Vec<ArgumentViolation>,
+    },
+}
+
+impl ToolFailure {
+    /// Whether a retry may succeed: true for timeouts and for errors the tool
+    /// reported as retryable, such as network errors
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ToolFailure::TimedOut { .. } => true,
+            ToolFailure::Failed { retryable, .. } => *retryable,
+            ToolFailure::NotAllowed | ToolFailure::InvalidArguments { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for ToolFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolFailure::TimedOut { timeout } => {
+                write!(f, "timed out after {}ms", timeout.as_millis())
+            }
+            ToolFailure::Failed { message, .. } => write!(f, "{}", message),
+            ToolFailure::NotAllowed => write!(f, "policy violation: not allowed in this run"),
+            ToolFailure::InvalidArguments { violations } => {
+                let violations: Vec<String> = violations.iter().map(ToString::to_string).collect();
+                write!(f, "invalid arguments: {}", violations.join("; "))
+            }
+        }
+    }
+}
+
+/// Predicate deciding whether a failed tool call is retried
+pub type ToolRetryPredicate = Arc<dyn Fn(&str, &ToolFailure) -> bool + Send + Sync>;
+
+/// Retry policy for failed tool calls
+#[derive(Clone)]
+pub struct ToolRetryPolicy {
+    /// Attempts per call, the first one included
+    pub max_attempts: u32,
+
+    /// Delay before the first retry
+    pub base_delay: Duration,
+
+    /// Maximum delay between retries
+    pub max_delay: Duration,
+
+    /// Backoff multiplier
+    pub backoff_multiplier: f32,
+
+    /// Which failures are retried, given the tool name; `None` retries all
+    pub retry_on: Option<ToolRetryPredicate>,
+}
+
+impl Default for ToolRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            backoff_multiplier: 2.0,
+            retry_on: None,
+        }
+    }
+}
+
+impl fmt::Debug for ToolRetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("backoff_multiplier", &self.backoff_multiplier)
+            .field("retry_on", &self.retry_on.as_ref().map(|_| "<predicate>"))
+            .finish()
+    }
+}
+
+impl ToolRetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// A policy making up to `max_attempts` attempts per call
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Set the delay before the first retry
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Set the maximum delay
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Set the backoff multiplier
+    pub fn with_backoff_multiplier(mut self, multiplier: f32) -> Self {
+        self.backoff_multiplier = multiplier;
+        self
+    }
+
+    /// Only retry failures matching `predicate`, which gets the tool name and the failure
+    pub fn with_retry_on(
+        mut self,
+        predicate: impl Fn(&str, &ToolFailure) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_on = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Check whether a failure of `tool` should be retried under this policy
+    pub fn should_retry(&self, tool: &str, failure: &ToolFailure) -> bool {
+        self.retry_on
+            .as_ref()
+            .map_or(true, |predicate| predicate(tool, failure))
+    }
+
+    /// Delay before the given retry (1-based)
+    pub fn delay_for(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1) as i32;
+        let backoff =
+            self.base_delay.as_secs_f64() * (self.backoff_multiplier as f64).powi(exponent);
+        Duration::from_secs_f64(backoff.min(self.max_delay.as_secs_f64()))
+    }
+}
+
+/// Outcome of executing a tool call under the executor's policies
+#[derive(Debug, Clone)]
+pub struct ToolExecution {
+    /// Tool result message for the model
+    pub message: ChatMessage,
+
+    /// Attempts made; zero when the call was never executed
+    pub attempts: u32,
+
+    /// Time spent executing, retries and backoff included
+    pub duration: Duration,
+
+    /// Failure of the last attempt, if the call did not succeed
+    pub failure: Option<ToolFailure>,
+
+    /// Whether the result came from the tool cache instead of the tool
+    pub cached: bool,
+
+    /// How the output was cut down to the tool's output limit, if it was
+    pub output_reduction: Option<OutputReduction>,
+}
+
+impl ToolExecution {
+    /// A call answered without running the tool, e.g. because it was refused
+    pub fn skipped(message: ChatMessage) -> Self {
+        Self {
+            message,
+            attempts: 0,
+            duration: Duration::ZERO,
+            failure: None,
+            cached: false,
+            output_reduction: None,
+        }
+    }
+
+    /// A call refused with `reason` instead of running the tool
+    pub fn refused(call_id: &str, tool: &str, reason: impl fmt::Display) -> Self {
+        let content = tool_error_result(tool, reason, false);
+        Self::skipped(ChatMessage::tool(call_id, content.to_string()))
+    }
+
+    /// A call to a tool the run does not allow, blocked without running it
+    pub fn not_allowed(call_id: &str, tool: &str) -> Self {
+        let failure = ToolFailure::NotAllowed;
+        let content = tool_error_result(tool, &failure, false);
+        Self {
+            failure: Some(failure),
+            ..Self::skipped(ChatMessage::tool(call_id, content.to_string()))
+        }
+    }
+
+    /// A call whose arguments break the tool's parameters schema, answered
+    /// without running the tool
+    ///
+    /// The result lists the `violations` in the error, so the model can
+    /// correct the arguments and call the tool again.
+    pub fn invalid_arguments(
+        call_id: &str,
+        tool: &str,
+        violations: Vec<ArgumentViolation>,
+    ) -> Self {
+        let mut content = tool_error_result(tool, "the arguments do not match the schema", false);
+        content["error"]["violations"] = serde_json::to_value(&violations).unwrap_or_default();
+        Self {
+            failure: Some(ToolFailure::InvalidArguments { violations }),
+            ..Self::skipped(ChatMessage::tool(call_id, content.to_string()))
+        }
+    }
+
+    /// A call whose last attempt failed with `failure`
+    ///
+    /// The result adds the `failure` and the `attempts` made to the error.
+    pub fn failed(
+        call_id: &str,
+        tool: &str,
+        failure: ToolFailure,
+        attempts: u32,
+        duration: Duration,
+    ) -> Self {
+        let mut content = tool_error_result(tool, &failure, failure.is_retryable());
+        content["error"]["failure"] = serde_json::to_value(&failure).unwrap_or_default();
+        content["error"]["attempts"] = attempts.into();
+        Self {
+            message: ChatMessage::tool(call_id, content.to_string()),
+            attempts,
+            duration,
+            failure: Some(failure),
+            cached: false,
+            output_reduction: None,
+        }
+    }
+}
+
+/// Tool result reporting an error to the model
+///
+/// Every error result has the shape
+/// `{"error": {"tool": ..., "message": ..., "retryable": ...}}`, so prompts can
+/// describe how to react to them.
+pub(crate) fn tool_error_result(
+    tool: &str,
+    message: impl fmt::Display,
+    retryable: bool,
+) -> serde_json::Value {
+    serde_json::json!({
+        "error": {
+            "tool": tool,
+            "message": message.to_string(),
+            "retryable": retryable,
+        }
+    })
+}
 
 /// Handles tool execution for the agent
 pub struct ToolExecutor {
-    registry: ToolRegistry,
+    tools: HashMap<String, Arc<dyn Tool>>,
+    default_timeout: Option<Duration>,
+    tool_timeouts: HashMap<String, Duration>,
+    retry_policy: ToolRetryPolicy,
+    max_concurrency: usize,
+    cache: Option<ToolCache>,
+    validate_arguments: bool,
+    default_max_output_chars: Option<usize>,
+    tool_max_output_chars: HashMap<String, usize>,
+    summarizer: Option<Client>,
+    summarize_large_outputs: bool,
+    output_store: Option<WorkingMemory>,
 }
 
 impl ToolExecutor {
-    /// Create a new tool executor
+    /// Create an executor for the synchronous tools in a registry
+    ///
+    /// Each tool runs through the [`SyncTool`] adapter.
     pub fn new(registry: ToolRegistry) -> Self {
-        Self { registry }
+        let mut executor = Self::empty();
+        for tool in registry.into_tools() {
+            let tool: Arc<dyn Tool> = Arc::new(SyncTool::new(tool));
+            executor.tools.insert(tool.name().to_string(), tool);
+        }
+        executor
+    }
+
+    /// Create an executor with no tools
+    pub fn empty() -> Self {
+        Self {
+            tools: HashMap::new(),
+            default_timeout: None,
+            tool_timeouts: HashMap::new(),
+            retry_policy: ToolRetryPolicy::default(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            cache: None,
+            validate_arguments: true,
+            default_max_output_chars: None,
+            tool_max_output_chars: HashMap::new(),
+            summarizer: None,
+            summarize_large_outputs: false,
+            output_store: None,
+        }
+    }
+
+    /// Register a tool
+    pub fn register(&mut self, tool: Box<dyn Tool>) -> RragResult<()> {
+        let name = tool.name().to_string();
+        if self.tools.contains_key(&name) {
+            return Err(RragError::config(
+                "tools",
+                "unique tool names",
+                format!("'{}' registered twice", name),
+            ));
+        }
+        self.tools.insert(name, Arc::from(tool));
+        Ok(())
+    }
+
+    /// Remove a tool, returning it if it was registered
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.remove(name)
+    }
+
+    /// Look up a tool by name
+    pub fn get(&self, name: &str) -> Option<&dyn Tool> {
+        self.tools.get(name).map(|tool| tool.as_ref())
+    }
+
+    /// Names of the registered tools
+    pub fn tool_names(&self) -> Vec<&str> {
+        self.tools.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Definitions of the registered tools, for the model
+    pub fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.values().map(|tool| tool.definition()).collect()
+    }
+
+    /// Number of registered tools
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Whether no tools are registered
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Set the timeout for tools without a timeout of their own
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for one tool
+    pub fn with_tool_timeout(mut self, tool: impl Into<String>, timeout: Duration) -> Self {
+        self.tool_timeouts.insert(tool.into(), timeout);
+        self
+    }
+
+    /// Set the retry policy for failed tool calls
+    pub fn with_retry_policy(mut self, policy: ToolRetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Retry policy for failed tool calls
+    pub fn retry_policy(&self) -> &ToolRetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Set how many tool calls of one batch run at once
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Cache results of tools that declare a [`CachePolicy`]
+    ///
+    /// Identical calls within one batch may all execute, since none has
+    /// finished when the others look up the cache.
+    pub fn with_cache(mut self, cache: ToolCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Set whether arguments are checked against the tool's parameters schema
+    /// before it runs; on by default
+    ///
+    /// A call whose arguments do not match is answered with
+    /// [`ToolExecution::invalid_arguments`] instead of running the tool.
+    pub fn with_argument_validation(mut self, enabled: bool) -> Self {
+        self.validate_arguments = enabled;
+        self
+    }
+
+    /// Limit outputs of tools without a limit of their own to `max_chars`
+    /// characters
+    ///
+    /// Longer outputs are truncated, keeping their start and end around a
+    /// `[truncated N chars]` marker, or summarized when
+    /// [`with_summarize_large_outputs`](Self::with_summarize_large_outputs) is
+    /// enabled.
+    pub fn with_max_output_chars(mut self, max_chars: usize) -> Self {
+        self.default_max_output_chars = Some(max_chars);
+        self
+    }
+
+    /// Limit outputs of one tool to `max_chars` characters
+    pub fn with_tool_max_output_chars(mut self, tool: impl Into<String>, max_chars: usize) -> Self {
+        self.tool_max_output_chars.insert(tool.into(), max_chars);
+        self
+    }
+
+    /// Output limit that applies to `tool`
+    pub fn max_output_chars_for(&self, tool: &str) -> Option<usize> {
+        self.tool_max_output_chars
+            .get(tool)
+            .copied()
+            .or(self.default_max_output_chars)
+    }
+
+    /// Set the client summarizing outputs over their limit; a cheap model is
+    /// enough
+    pub fn with_summarizer(mut self, client: Client) -> Self {
+        self.summarizer = Some(client);
+        self
+    }
+
+    /// Summarize outputs over their limit instead of truncating them
+    ///
+    /// Needs a [summarizer](Self::with_summarizer) and an
+    /// [output store](Self::with_output_store). The summary names the working
+    /// memory key the original is stored under. Outputs are truncated when
+    /// either is missing or the summary fails.
+    pub fn with_summarize_large_outputs(mut self, enabled: bool) -> Self {
+        self.summarize_large_outputs = enabled;
+        self
+    }
+
+    /// Keep the originals of summarized outputs in `memory`
+    pub fn with_output_store(mut self, memory: WorkingMemory) -> Self {
+        self.output_store = Some(memory);
+        self
+    }
+
+    /// Tool result cache, if caching is enabled
+    pub fn cache(&self) -> Option<&ToolCache> {
+        self.cache.as_ref()
+    }
+
+    /// Timeout that applies to `tool`
+    pub fn timeout_for(&self, tool: &str) -> Option<Duration> {
+        self.tool_timeouts
+            .get(tool)
+            .copied()
+            .or(self.default_timeout)
     }
 
     /// Execute a tool call and return the result message
-    pub fn execute_tool_call(&self, tool_call: &ToolCall) -> ChatMessage {
-        // Convert to ToolExec format
-        let tool_exec = ToolExec::new(
-            &tool_call.id,
-            &tool_call.function.name,
-            tool_call.function.arguments.clone(),
-        );
-
-        // Execute the tool
-        let result = self.registry.execute(&tool_exec);
-
-        // Convert result to chat message
-        let result_content = if result.success {
-            serde_json::to_string(&result.content).unwrap_or_else(|_| "{}".to_string())
-        } else {
-            format!("Error: {}", result.error.unwrap_or_default())
+    pub async fn execute_tool_call(&self, tool_call: &ToolCall) -> ChatMessage {
+        self.execute_with_policy(tool_call).await.message
+    }
+
+    /// Execute a tool call under the configured timeouts and retry policy
+    ///
+    /// Each attempt runs as a task of its own and is aborted when it times
+    /// out; a synchronous tool keeps running on the blocking thread pool until
+    /// it returns. When all attempts fail, the message holds a JSON error the
+    /// model can react to.
+    ///
+    /// Arguments that do not match the tool's parameters schema are refused
+    /// with the constraints they break, without running the tool.
+    ///
+    /// With a cache configured, a stored result of a cacheable tool is
+    /// returned without executing it; its message carries `"cached": true` in
+    /// its metadata.
+    ///
+    /// Outputs over the tool's limit are cut down after caching, so a cached
+    /// result is cut down again when it is served.
+    ///
+    /// The call runs in a `tool_execution` span carrying the tool name, and
+    /// its duration, attempts and success once it completes.
+    pub async fn execute_with_policy(&self, tool_call: &ToolCall) -> ToolExecution {
+        self.execute_with_retry(tool_call, &self.retry_policy).await
+    }
+
+    /// [`execute_with_policy`](Self::execute_with_policy) under `retry_policy`
+    /// instead of the executor's own
+    pub async fn execute_with_retry(
+        &self,
+        tool_call: &ToolCall,
+        retry_policy: &ToolRetryPolicy,
+    ) -> ToolExecution {
+        let span = info_span!(
+            "tool_execution",
+            tool = %tool_call.function.name,
+            call_id = %tool_call.id,
+            duration_ms = Empty,
+            attempts = Empty,
+            cached = Empty,
+            success = Empty,
+        );
+        let execution = self
+            .run_with_policy(tool_call, retry_policy)
+            .instrument(span.clone())
+            .await;
+        span.record("duration_ms", execution.duration.as_millis() as u64);
+        span.record("attempts", execution.attempts);
+        span.record("cached", execution.cached);
+        span.record("success", execution.failure.is_none());
+        execution
+    }
+
+    async fn run_with_policy(
+        &self,
+        tool_call: &ToolCall,
+        retry_policy: &ToolRetryPolicy,
+    ) -> ToolExecution {
+        let name = &tool_call.function.name;
+        let timeout = self.timeout_for(name);
+        let started = Instant::now();
+
+        if let Some(violations) = self.argument_violations(tool_call) {
+            let execution = ToolExecution::invalid_arguments(&tool_call.id, name, violations);
+            if let Some(failure) = &execution.failure {
+                warn!(tool = %name, error = %failure, "Tool call arguments are invalid");
+            }
+            return execution;
+        }
+
+        let policy = self.cache_policy_for(name);
+        if policy.is_cacheable() {
+            if let Some(content) = self.cached_result(tool_call).await {
+                debug!(tool = %name, "Tool result served from cache");
+                let (content, output_reduction) = self.limit_output(name, content).await;
+                return ToolExecution {
+                    message: ChatMessage::tool(&tool_call.id, content)
+                        .with_metadata("cached", serde_json::Value::Bool(true)),
+                    attempts: 0,
+                    duration: started.elapsed(),
+                    failure: None,
+                    cached: true,
+                    output_reduction,
+                };
+            }
+        }
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let failure = match self.attempt(tool_call, timeout).await {
+                Ok(content) => {
+                    if policy.is_cacheable() {
+                        self.store_result(tool_call, &content, policy).await;
+                    }
+                    let (content, output_reduction) = self.limit_output(name, content).await;
+                    return ToolExecution {
+                        message: ChatMessage::tool(&tool_call.id, content),
+                        attempts,
+                        duration: started.elapsed(),
+                        failure: None,
+                        cached: false,
+                        output_reduction,
+                    };
+                }
+                Err(failure) => failure,
+            };
+
+            if attempts < retry_policy.max_attempts && retry_policy.should_retry(name, &failure) {
+                let delay = retry_policy.delay_for(attempts);
+                debug!(
+                    tool = %name,
+                    attempt = attempts,
+                    error = %failure,
+                    delay_ms = delay.as_millis() as u64,
+                    "Retrying tool call"
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            warn!(tool = %name, attempts, error = %failure, "Tool call failed");
+            return ToolExecution::failed(
+                &tool_call.id,
+                name,
+                failure,
+                attempts,
+                started.elapsed(),
+            );
+        }
+    }
+
+    /// Constraints of the tool's schema the call's arguments break, if any
+    fn argument_violations(&self, tool_call: &ToolCall) -> Option<Vec<ArgumentViolation>> {
+        if !self.validate_arguments {
+            return None;
+        }
+        let tool = self.tools.get(&tool_call.function.name)?;
+        let violations =
+            validate_arguments(&tool.parameters_schema(), &tool_call.function.arguments);
+        (!violations.is_empty()).then_some(violations)
+    }
+
+    /// Cut an output over the tool's limit down, summarizing it when enabled
+    /// and possible
+    async fn limit_output(&self, tool: &str, content: String) -> (String, Option<OutputReduction>) {
+        let Some(max_chars) = self.max_output_chars_for(tool) else {
+            return (content, None);
+        };
+        let original_chars = content.chars().count();
+        if original_chars <= max_chars {
+            return (content, None);
+        }
+
+        if self.summarize_large_outputs {
+            if let (Some(client), Some(store)) = (&self.summarizer, &self.output_store) {
+                match self
+                    .summarize(client, store, tool, &content, max_chars)
+                    .await
+                {
+                    Ok((summary, key)) => {
+                        debug!(tool, original_chars, key = %key, "Summarized tool output");
+                        let reduction = OutputReduction::Summarized {
+                            original_chars,
+                            key,
+                        };
+                        return (summary, Some(reduction));
+                    }
+                    Err(e) => warn!(tool, error = %e, "Failed to summarize tool output"),
+                }
+            }
+        }
+
+        match output_limit::truncate_output(&content, max_chars) {
+            Some((truncated, reduction)) => {
+                debug!(tool, original_chars, max_chars, "Truncated tool output");
+                (truncated, Some(reduction))
+            }
+            None => (content, None),
+        }
+    }
+
+    /// Store the original output and summarize it, returning the summary and
+    /// the key the original is stored under
+    async fn summarize(
+        &self,
+        client: &Client,
+        store: &WorkingMemory,
+        tool: &str,
+        content: &str,
+        max_chars: usize,
+    ) -> RragResult<(String, String)> {
+        let summary = output_limit::summarize_output(client, tool, content, max_chars).await?;
+        let key = format!(
+            "{}::{}::{}",
+            ORIGINAL_KEY_PREFIX,
+            tool,
+            uuid::Uuid::new_v4().simple()
+        );
+        store.set(&key, content.to_string()).await?;
+        let original_chars = content.chars().count();
+        let content = output_limit::summary_content(&summary, original_chars, &key);
+        Ok((content, key))
+    }
+
+    /// Cache policy that applies to `tool`; `NoCache` without a cache
+    fn cache_policy_for(&self, tool: &str) -> CachePolicy {
+        match (&self.cache, self.tools.get(tool)) {
+            (Some(_), Some(tool)) => tool.cache_policy(),
+            _ => CachePolicy::NoCache,
+        }
+    }
+
+    /// Look up a stored result; cache errors count as misses
+    async fn cached_result(&self, tool_call: &ToolCall) -> Option<String> {
+        let cache = self.cache.as_ref()?;
+        let name = &tool_call.function.name;
+        match cache.get(name, &tool_call.function.arguments).await {
+            Ok(hit) => hit,
+            Err(e) => {
+                warn!(tool = %name, error = %e, "Tool cache lookup failed");
+                None
+            }
+        }
+    }
+
+    /// Store a successful result; a failed write only costs the next hit
+    async fn store_result(&self, tool_call: &ToolCall, content: &str, policy: CachePolicy) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+        let name = &tool_call.function.name;
+        if let Err(e) = cache
+            .put(name, &tool_call.function.arguments, content, policy)
+            .await
+        {
+            warn!(tool = %name, error = %e, "Failed to cache tool result");
+        }
+    }
+
+    /// Run one attempt of a tool call, returning the serialized result
+    async fn attempt(
+        &self,
+        tool_call: &ToolCall,
+        timeout: Option<Duration>,
+    ) -> Result<String, ToolFailure> {
+        let name = &tool_call.function.name;
+        let Some(tool) = self.tools.get(name) else {
+            return Err(ToolFailure::Failed {
+                message: format!("Tool '{}' not found", name),
+                retryable: false,
+            });
         };
 
-        ChatMessage::tool(&tool_call.id, result_content)
+        // A task of its own keeps a panicking tool from taking the run down
+        let tool = Arc::clone(tool);
+        let args = tool_call.function.arguments.clone();
+        let mut task = tokio::spawn(async move { tool.call(args).await });
+
+        let joined = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, &mut task).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    task.abort();
+                    return Err(ToolFailure::TimedOut { timeout });
+                }
+            },
+            None => task.await,
+        };
+
+        match joined {
+            Ok(Ok(output)) => Ok(output.to_content()),
+            Ok(Err(RragError::ToolExecution { message, .. })) => Err(ToolFailure::Failed {
+                message,
+                retryable: false,
+            }),
+            Ok(Err(e)) => Err(ToolFailure::Failed {
+                retryable: e.is_retryable(),
+                message: e.to_string(),
+            }),
+            Err(e) => Err(ToolFailure::Failed {
+                message: join_error_message(e),
+                retryable: false,
+            }),
+        }
+    }
+
+    /// Execute tool calls concurrently under the configured policies
+    ///
+    /// At most the configured number of calls run at once; results come back in
+    /// the order of `tool_calls`.
+    pub async fn execute_all_with_policy(&self, tool_calls: &[ToolCall]) -> Vec<ToolExecution> {
+        self.execute_all_with_retry(tool_calls, &self.retry_policy)
+            .await
+    }
+
+    /// [`execute_all_with_policy`](Self::execute_all_with_policy) under
+    /// `retry_policy` instead of the executor's own
+    pub async fn execute_all_with_retry(
+        &self,
+        tool_calls: &[ToolCall],
+        retry_policy: &ToolRetryPolicy,
+    ) -> Vec<ToolExecution> {
+        // Boxed up front: a closure building them would not be general enough
+        // over lifetimes for the futures to be `Send` in callers' streams
+        let executions: Vec<BoxFuture<'_, ToolExecution>> = tool_calls
+            .iter()
+            .map(|call| self.execute_with_retry(call, retry_policy).boxed())
+            .collect();
+        futures::stream::iter(executions)
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Execute tool calls concurrently, returning the result messages in call order
+    ///
+    /// A tool that panics produces an error result instead of failing the batch.
+    pub async fn execute_tool_calls_async(&self, tool_calls: &[ToolCall]) -> Vec<ChatMessage> {
+        self.execute_all_with_policy(tool_calls)
+            .await
+            .into_iter()
+            .map(|execution| execution.message)
+            .collect()
+    }
+
+    /// Execute tool calls one after another
+    pub async fn execute_tool_calls(&self, tool_calls: &[ToolCall]) -> Vec<ChatMessage> {
+        let mut messages = Vec::with_capacity(tool_calls.len());
+        for call in tool_calls {
+            messages.push(self.execute_tool_call(call).await);
+        }
+        messages
+    }
+}
+
+/// Describe a tool task that did not return, extracting the panic message if there is one
+fn join_error_message(error: JoinError) -> String {
+    if !error.is_panic() {
+        return format!("Tool task failed: {}", error);
+    }
+
+    let payload = error.into_panic();
+    let detail = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown cause".to_string());
+    format!("Tool panicked: {}", detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rexis_llm::tools::Tool;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Tool that sleeps longer than any test timeout
+    struct SlowTool;
+
+    impl Tool for SlowTool {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "Takes its time"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            std::thread::sleep(Duration::from_millis(300));
+            Ok(serde_json::json!("finally"))
+        }
+    }
+
+    /// Tool that fails a fixed number of times before succeeding
+    struct FlakyTool {
+        failures_left: AtomicU32,
+    }
+
+    impl Tool for FlakyTool {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn description(&self) -> &str {
+            "Fails at first"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            let left = self.failures_left.load(Ordering::SeqCst);
+            if left > 0 {
+                self.failures_left.store(left - 1, Ordering::SeqCst);
+                return Err("service unavailable".into());
+            }
+            Ok(serde_json::json!({"status": "ok"}))
+        }
+    }
+
+    /// Start and end of each tool execution, by tool name
+    type RunLog =
+        Arc<std::sync::Mutex<Vec<(&'static str, std::time::Instant, std::time::Instant)>>>;
+
+    /// Tool that sleeps briefly and logs when it ran
+    struct TimedTool {
+        name: &'static str,
+        log: RunLog,
+    }
+
+    impl Tool for TimedTool {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Sleeps briefly"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            let start = std::time::Instant::now();
+            std::thread::sleep(Duration::from_millis(100));
+            let end = std::time::Instant::now();
+            self.log.lock().unwrap().push((self.name, start, end));
+            Ok(serde_json::json!(self.name))
+        }
+    }
+
+    /// Tool that always panics
+    struct PanickingTool;
+
+    impl Tool for PanickingTool {
+        fn name(&self) -> &str {
+            "explode"
+        }
+
+        fn description(&self) -> &str {
+            "Panics"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            _args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            panic!("boom")
+        }
+    }
+
+    fn timed_executor(log: &RunLog) -> ToolExecutor {
+        let mut registry = ToolRegistry::new();
+        for name in ["first", "second"] {
+            let tool = TimedTool {
+                name,
+                log: Arc::clone(log),
+            };
+            registry.register(Box::new(tool)).unwrap();
+        }
+        ToolExecutor::new(registry)
     }
 
-    /// Execute multiple tool calls
-    pub fn execute_tool_calls(&self, tool_calls: &[ToolCall]) -> Vec<ChatMessage> {
-        tool_calls
+    fn calls(names: &[&str]) -> Vec<ToolCall> {
+        names
             .iter()
-            .map(|call| self.execute_tool_call(call))
+            .enumerate()
+            .map(|(i, name)| {
+                ToolCall::function(format!("call_{}", i + 1), *name, serde_json::json!({}))
+            })
             .collect()
     }
 
-    /// Get the tool registry
-    pub fn registry(&self) -> &ToolRegistry {
-        &self.registry
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tool_calls_run_concurrently_in_order() {
+        let log = Arc::default();
+        let executor = timed_executor(&log);
+
+        let messages = executor
+            .execute_tool_calls_async(&calls(&["first", "second"]))
+            .await;
+
+        assert_eq!(messages[0].text(), Some(r#""first""#));
+        assert_eq!(messages[1].text(), Some(r#""second""#));
+        let log = log.lock().unwrap();
+        let (_, first_start, first_end) = log.iter().find(|(n, ..)| *n == "first").unwrap();
+        let (_, second_start, second_end) = log.iter().find(|(n, ..)| *n == "second").unwrap();
+        assert!(first_start < second_end && second_start < first_end);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrency_limit_of_one_runs_sequentially() {
+        let log = Arc::default();
+        let executor = timed_executor(&log).with_max_concurrency(1);
+
+        executor
+            .execute_tool_calls_async(&calls(&["first", "second"]))
+            .await;
+
+        let log = log.lock().unwrap();
+        assert_eq!(log[0].0, "first");
+        assert!(log[0].2 <= log[1].1);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_tool_becomes_error_result() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(PanickingTool)).unwrap();
+        registry.register(Box::new(SlowTool)).unwrap();
+        let executor = ToolExecutor::new(registry);
+
+        let executions = executor
+            .execute_all_with_policy(&calls(&["explode", "slow"]))
+            .await;
+
+        assert_eq!(
+            executions[0].failure,
+            Some(ToolFailure::Failed {
+                message: "Tool panicked: boom".to_string(),
+                retryable: false,
+            })
+        );
+        assert_eq!(executions[1].failure, None);
+        assert_eq!(
+            executions[1].message.tool_call_id.as_deref(),
+            Some("call_2")
+        );
+    }
+
+    fn executor(tool: Box<dyn Tool>) -> ToolExecutor {
+        let mut registry = ToolRegistry::new();
+        registry.register(tool).unwrap();
+        ToolExecutor::new(registry)
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_tool_reports_structured_error() {
+        let executor =
+            executor(Box::new(SlowTool)).with_tool_timeout("slow", Duration::from_millis(20));
+        let call = ToolCall::function("call_1", "slow", serde_json::json!({}));
+
+        let execution = executor.execute_with_policy(&call).await;
+
+        assert_eq!(execution.attempts, 1);
+        assert_eq!(
+            execution.failure,
+            Some(ToolFailure::TimedOut {
+                timeout: Duration::from_millis(20)
+            })
+        );
+        let content: serde_json::Value =
+            serde_json::from_str(execution.message.text().unwrap()).unwrap();
+        assert_eq!(content["error"]["tool"], "slow");
+        assert_eq!(content["error"]["retryable"], true);
+        assert_eq!(content["error"]["failure"]["kind"], "timed_out");
+        assert_eq!(content["error"]["attempts"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_flaky_tool_succeeds_on_third_attempt() {
+        let executor = executor(Box::new(FlakyTool {
+            failures_left: AtomicU32::new(2),
+        }))
+        .with_retry_policy(ToolRetryPolicy::new(3).with_base_delay(Duration::from_millis(1)));
+        let call = ToolCall::function("call_1", "flaky", serde_json::json!({}));
+
+        let execution = executor.execute_with_policy(&call).await;
+
+        assert_eq!(execution.attempts, 3);
+        assert_eq!(execution.failure, None);
+        assert_eq!(execution.message.text(), Some(r#"{"status":"ok"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_retry_predicate_limits_retries() {
+        let executor = executor(Box::new(FlakyTool {
+            failures_left: AtomicU32::new(2),
+        }))
+        .with_retry_policy(
+            ToolRetryPolicy::new(3)
+                .with_base_delay(Duration::from_millis(1))
+                .with_retry_on(|_, failure| matches!(failure, ToolFailure::TimedOut { .. })),
+        );
+        let call = ToolCall::function("call_1", "flaky", serde_json::json!({}));
+
+        let execution = executor.execute_with_policy(&call).await;
+
+        assert_eq!(execution.attempts, 1);
+        assert!(matches!(
+            execution.failure,
+            Some(ToolFailure::Failed { ref message, .. }) if message == "service unavailable"
+        ));
+    }
+
+    #[test]
+    fn test_register_rejects_duplicate_names() {
+        let mut executor = ToolExecutor::empty();
+        executor
+            .register(SyncTool::boxed(Box::new(SlowTool)))
+            .unwrap();
+
+        assert!(executor
+            .register(SyncTool::boxed(Box::new(SlowTool)))
+            .is_err());
+        assert_eq!(executor.tool_names(), vec!["slow"]);
+    }
+
+    /// Read-only tool counting its executions
+    struct CountingTool {
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Tool for CountingTool {
+        fn name(&self) -> &str {
+            "lookup"
+        }
+
+        fn description(&self) -> &str {
+            "Looks a key up"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+
+        fn execute(
+            &self,
+            args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(serde_json::json!({"value": args["key"]}))
+        }
+    }
+
+    fn caching_executor(policy: CachePolicy, calls: &Arc<AtomicU32>) -> ToolExecutor {
+        let tool = SyncTool::new(Box::new(CountingTool {
+            calls: Arc::clone(calls),
+        }))
+        .with_cache_policy(policy);
+        let mut executor = ToolExecutor::empty().with_cache(ToolCache::in_memory());
+        executor.register(Box::new(tool)).unwrap();
+        executor
+    }
+
+    #[tokio::test]
+    async fn test_identical_calls_execute_once() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor = caching_executor(CachePolicy::SessionScoped, &calls);
+        let args = serde_json::json!({"key": "a", "scope": "user"});
+        let reordered = serde_json::json!({"scope": "user", "key": "a"});
+
+        let first = executor
+            .execute_with_policy(&ToolCall::function("call_1", "lookup", args))
+            .await;
+        let second = executor
+            .execute_with_policy(&ToolCall::function("call_2", "lookup", reordered))
+            .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!first.cached);
+        assert!(second.cached);
+        assert_eq!(second.attempts, 0);
+        assert_eq!(second.message.tool_call_id.as_deref(), Some("call_2"));
+        assert_eq!(second.message.text(), first.message.text());
+        assert_eq!(
+            second.message.metadata.get("cached"),
+            Some(&serde_json::Value::Bool(true))
+        );
+
+        executor
+            .execute_with_policy(&ToolCall::function(
+                "call_3",
+                "lookup",
+                serde_json::json!({"key": "b"}),
+            ))
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments_are_refused_without_running_the_tool() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor = caching_executor(CachePolicy::NoCache, &calls);
+        let call = ToolCall::function("call_1", "lookup", serde_json::json!("a"));
+
+        let execution = executor.execute_with_policy(&call).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(execution.attempts, 0);
+        assert!(matches!(
+            execution.failure,
+            Some(ToolFailure::InvalidArguments { ref violations })
+                if violations[0].to_string() == "$: expected object, got string"
+        ));
+        let content: serde_json::Value =
+            serde_json::from_str(execution.message.text().unwrap()).unwrap();
+        assert_eq!(
+            content["error"]["violations"],
+            serde_json::json!([{"path": "$", "message": "expected object, got string"}])
+        );
+
+        let executor = executor.with_argument_validation(false);
+        assert!(executor.execute_with_policy(&call).await.failure.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cached_result_expires_after_ttl() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor = caching_executor(CachePolicy::TtlSeconds(1), &calls);
+        let call = ToolCall::function("call_1", "lookup", serde_json::json!({"key": "a"}));
+
+        executor.execute_with_policy(&call).await;
+        assert!(executor.execute_with_policy(&call).await.cached);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        assert!(!executor.execute_with_policy(&call).await.cached);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_cache_without_policy() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor = caching_executor(CachePolicy::NoCache, &calls);
+        let call = ToolCall::function("call_1", "lookup", serde_json::json!({"key": "a"}));
+
+        executor.execute_with_policy(&call).await;
+        executor.execute_with_policy(&call).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    fn lookup_call(value: &str) -> ToolCall {
+        ToolCall::function("call_1", "lookup", serde_json::json!({"key": value}))
+    }
+
+    #[tokio::test]
+    async fn test_outputs_over_limit_are_truncated() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor = caching_executor(CachePolicy::NoCache, &calls)
+            .with_max_output_chars(1000)
+            .with_tool_max_output_chars("lookup", 20);
+        assert_eq!(executor.max_output_chars_for("lookup"), Some(20));
+        assert_eq!(executor.max_output_chars_for("other"), Some(1000));
+
+        // {"value":"x...x"} is 112 characters
+        let execution = executor
+            .execute_with_policy(&lookup_call(&"x".repeat(100)))
+            .await;
+        assert_eq!(
+            execution.message.text(),
+            Some("{\"value\":\"\n[truncated 92 chars]\nxxxxxxxx\"}")
+        );
+        assert_eq!(
+            execution.output_reduction,
+            Some(OutputReduction::Truncated {
+                original_chars: 112,
+                removed_chars: 92,
+            })
+        );
+
+        let execution = executor.execute_with_policy(&lookup_call("short")).await;
+        assert_eq!(execution.message.text(), Some("{\"value\":\"short\"}"));
+        assert_eq!(execution.output_reduction, None);
+    }
+
+    fn summarizing_executor(
+        mock: &rexis_llm::testing::MockClient,
+    ) -> (ToolExecutor, WorkingMemory) {
+        let storage: Arc<dyn crate::storage::Memory> =
+            Arc::new(crate::storage::InMemoryStorage::new());
+        let store = WorkingMemory::new_persistent(Arc::clone(&storage), "s1".to_string());
+        let executor = caching_executor(CachePolicy::NoCache, &Arc::new(AtomicU32::new(0)))
+            .with_max_output_chars(20)
+            .with_summarizer(mock.client())
+            .with_summarize_large_outputs(true)
+            .with_output_store(store);
+        (
+            executor,
+            WorkingMemory::new_persistent(storage, "s1".to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_large_outputs_are_summarized_with_the_original_stored() {
+        let mock = rexis_llm::testing::MockClient::builder()
+            .otherwise(rexis_llm::testing::respond_text("A run of 100 x.\n"))
+            .build();
+        let (executor, working) = summarizing_executor(&mock);
+        let original = format!("{{\"value\":\"{}\"}}", "x".repeat(100));
+
+        let execution = executor
+            .execute_with_policy(&lookup_call(&"x".repeat(100)))
+            .await;
+        let Some(OutputReduction::Summarized {
+            original_chars,
+            key,
+        }) = execution.output_reduction
+        else {
+            panic!("expected a summary, got {:?}", execution.output_reduction);
+        };
+        assert_eq!(original_chars, 112);
+        assert!(key.starts_with("tool_output::lookup::"));
+        assert_eq!(
+            execution.message.text().unwrap(),
+            format!(
+                "A run of 100 x.\n[summarized from 112 chars; the full output is in working \
+                 memory under '{}']",
+                key
+            )
+        );
+        let stored = working.get(&key).await.unwrap().unwrap();
+        assert_eq!(stored.as_string(), Some(original.as_str()));
+
+        let prompt = mock.requests()[0]
+            .last_message()
+            .unwrap()
+            .text()
+            .unwrap()
+            .to_string();
+        assert!(prompt.contains("'lookup'"));
+        assert!(prompt.contains(&original));
+
+        // Outputs within the limit are not summarized
+        executor.execute_with_policy(&lookup_call("short")).await;
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_summary_falls_back_to_truncation() {
+        let mock = rexis_llm::testing::MockClient::builder()
+            .otherwise(rexis_llm::testing::respond_with_error("model overloaded"))
+            .build();
+        let (executor, working) = summarizing_executor(&mock);
+
+        let execution = executor
+            .execute_with_policy(&lookup_call(&"x".repeat(100)))
+            .await;
+        assert_eq!(
+            execution.output_reduction,
+            Some(OutputReduction::Truncated {
+                original_chars: 112,
+                removed_chars: 92,
+            })
+        );
+        assert_eq!(working.count().await.unwrap(), 0);
     }
 }
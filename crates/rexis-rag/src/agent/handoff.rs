@@ -0,0 +1,188 @@
+//! Delegation between agents
+//!
+//! An agent given an [`AgentRegistry`] through
+//! [`AgentBuilder::with_handoffs`](super::AgentBuilder::with_handoffs) can call
+//! the built-in `handoff_to` tool to pass a message to another registered agent.
+//! The target runs to completion and its final answer comes back as the tool
+//! result, so the delegating agent can finish or hand off again. Each handoff is
+//! recorded in the shared knowledge base under [`HANDOFF_TAG`].
+
+use super::memory::{KnowledgeEntry, SharedKnowledgeBase};
+use super::{Agent, ToolExecution, ToolFailure};
+use crate::error::RragResult;
+use crate::storage::MemoryValue;
+use rexis_llm::tools::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+/// Name of the built-in handoff tool
+pub const HANDOFF_TOOL: &str = "handoff_to";
+
+/// Shared knowledge tag of handoff records
+pub const HANDOFF_TAG: &str = "handoff";
+
+/// Agents that can be the target of a handoff, by name
+///
+/// Agents hold the registry weakly, so it must be kept alive by its owner for
+/// as long as handoffs are possible.
+#[derive(Default)]
+pub struct AgentRegistry {
+    agents: RwLock<HashMap<String, Arc<Agent>>>,
+}
+
+impl AgentRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `agent` under `name`, returning the agent it replaces
+    pub fn register(&self, name: impl Into<String>, agent: Arc<Agent>) -> Option<Arc<Agent>> {
+        self.agents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name.into(), agent)
+    }
+
+    /// Agent registered under `name`
+    pub fn get(&self, name: &str) -> Option<Arc<Agent>> {
+        self.agents
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .cloned()
+    }
+
+    /// Registered names, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .agents
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl std::fmt::Debug for AgentRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentRegistry")
+            .field("agents", &self.names())
+            .finish()
+    }
+}
+
+/// Audit record of one handoff
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffRecord {
+    /// Unique identifier
+    pub id: String,
+
+    /// Id of the delegating agent
+    pub from: String,
+
+    /// Registered name of the target agent
+    pub to: String,
+
+    /// Message passed to the target
+    pub message: String,
+
+    /// Working memory keys copied to the target
+    pub context_keys: Vec<String>,
+
+    /// Handoff depth of the target's run (1 for a handoff from a top-level run)
+    pub depth: usize,
+
+    /// Final answer of the target, if it completed
+    pub answer: Option<String>,
+
+    /// Why the handoff failed, if it did
+    pub error: Option<String>,
+
+    /// When the handoff started
+    pub started_at: chrono::DateTime<chrono::Utc>,
+
+    /// When the handoff finished
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Handoffs recorded in a shared knowledge base, oldest first
+pub async fn handoff_trail(knowledge: &SharedKnowledgeBase) -> RragResult<Vec<HandoffRecord>> {
+    let mut records = Vec::new();
+    for entry in knowledge.find_by_tag(HANDOFF_TAG).await? {
+        if let Some(json) = entry.value.as_json() {
+            records.push(serde_json::from_value::<HandoffRecord>(json.clone())?);
+        }
+    }
+    records.sort_by_key(|record| record.started_at);
+    Ok(records)
+}
+
+/// Arguments of a `handoff_to` call
+#[derive(Debug, Deserialize)]
+pub(crate) struct HandoffArgs {
+    pub(crate) agent_name: String,
+    pub(crate) message: String,
+    #[serde(default)]
+    pub(crate) context_keys: Vec<String>,
+}
+
+/// Definition of the handoff tool, listing the agents it can reach
+pub(crate) fn definition(agents: &[String]) -> ToolDefinition {
+    ToolDefinition::new(
+        HANDOFF_TOOL,
+        format!(
+            "Hand the task to another agent and get its final answer back. \
+             Available agents: {}.",
+            agents.join(", ")
+        ),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "agent_name": {
+                    "type": "string",
+                    "enum": agents,
+                    "description": "Agent to hand off to"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "What the agent should do, with everything it needs to know"
+                },
+                "context_keys": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Working memory keys to share with the agent"
+                }
+            },
+            "required": ["agent_name", "message"]
+        }),
+    )
+}
+
+/// Result of a handoff that did not complete, shaped like a failed tool call
+pub(crate) fn failed(call_id: &str, message: String, duration: Duration) -> ToolExecution {
+    let failure = ToolFailure::Failed {
+        message,
+        retryable: false,
+    };
+    ToolExecution::failed(call_id, HANDOFF_TOOL, failure, 1, duration)
+}
+
+/// Add a handoff to the trail
+pub(crate) async fn record(
+    knowledge: &SharedKnowledgeBase,
+    record: &HandoffRecord,
+) -> RragResult<()> {
+    let entry = KnowledgeEntry::new(
+        format!("{}::{}", HANDOFF_TAG, record.id),
+        MemoryValue::Json(serde_json::to_value(record)?),
+        record.from.clone(),
+    )
+    .with_tags(vec![HANDOFF_TAG.to_string()]);
+    knowledge.store_entry(entry).await
+}
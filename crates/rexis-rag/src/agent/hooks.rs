@@ -0,0 +1,185 @@
+//! Lifecycle hooks of agent runs
+
+use super::{RunResult, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use rexis_llm::{ChatMessage, ChatResponse, ToolCall};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// What happens when a hook returns an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookMode {
+    /// Log the error and carry on with the run
+    #[default]
+    Advisory,
+
+    /// Abort the run with [`RragError::Hook`]
+    Enforcing,
+}
+
+/// Observer of the stages of an agent run
+///
+/// Every method defaults to doing nothing. Hooks are awaited in the order they
+/// were registered, for runs started with [`Agent::run`](super::Agent::run) and
+/// the other non-streaming entry points. Errors only affect the run under
+/// [`HookMode::Enforcing`].
+#[async_trait]
+pub trait AgentHooks: Send + Sync {
+    /// The run received its input
+    async fn on_run_start(&self, _input: &str) -> RragResult<()> {
+        Ok(())
+    }
+
+    /// An iteration of the agent loop begins (1-based)
+    async fn on_iteration(&self, _iteration: u32) -> RragResult<()> {
+        Ok(())
+    }
+
+    /// Messages are about to be sent to the model
+    async fn on_llm_request(&self, _messages: &[ChatMessage]) -> RragResult<()> {
+        Ok(())
+    }
+
+    /// The model answered
+    async fn on_llm_response(&self, _response: &ChatResponse) -> RragResult<()> {
+        Ok(())
+    }
+
+    /// The model requested a tool call
+    async fn on_tool_start(&self, _call: &ToolCall) -> RragResult<()> {
+        Ok(())
+    }
+
+    /// A tool call finished; `call` holds the arguments it ran with
+    async fn on_tool_end(&self, _call: &ToolCall, _output: &ToolOutput) -> RragResult<()> {
+        Ok(())
+    }
+
+    /// The run completed
+    async fn on_run_end(&self, _result: &RunResult) -> RragResult<()> {
+        Ok(())
+    }
+}
+
+/// Hooks that trace each stage of a run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingHooks;
+
+#[async_trait]
+impl AgentHooks for TracingHooks {
+    async fn on_run_start(&self, input: &str) -> RragResult<()> {
+        info!(input = %input, "Agent run started");
+        Ok(())
+    }
+
+    async fn on_iteration(&self, iteration: u32) -> RragResult<()> {
+        debug!(iteration, "Agent iteration started");
+        Ok(())
+    }
+
+    async fn on_llm_request(&self, messages: &[ChatMessage]) -> RragResult<()> {
+        debug!(message_count = messages.len(), "Agent LLM request");
+        Ok(())
+    }
+
+    async fn on_llm_response(&self, response: &ChatResponse) -> RragResult<()> {
+        debug!(
+            model = %response.model,
+            tool_call_count = response.tool_calls.as_ref().map_or(0, Vec::len),
+            "Agent LLM response"
+        );
+        Ok(())
+    }
+
+    async fn on_tool_start(&self, call: &ToolCall) -> RragResult<()> {
+        info!(tool = %call.function.name, args = %call.function.arguments, "Agent tool started");
+        Ok(())
+    }
+
+    async fn on_tool_end(&self, call: &ToolCall, output: &ToolOutput) -> RragResult<()> {
+        debug!(tool = %call.function.name, output = ?output, "Agent tool finished");
+        Ok(())
+    }
+
+    async fn on_run_end(&self, result: &RunResult) -> RragResult<()> {
+        info!(
+            iterations = result.iterations,
+            tool_calls = result.tool_invocations.len(),
+            duration_ms = result.duration.as_millis() as u64,
+            "Agent run finished"
+        );
+        Ok(())
+    }
+}
+
+/// Registered hooks together with the mode they run under
+pub(crate) struct HookSet<'a> {
+    pub(crate) hooks: &'a [Arc<dyn AgentHooks>],
+    pub(crate) mode: HookMode,
+}
+
+impl HookSet<'_> {
+    pub(crate) async fn run_start(&self, input: &str) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_run_start", hook.on_run_start(input).await)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn iteration(&self, iteration: u32) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_iteration", hook.on_iteration(iteration).await)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn llm_request(&self, messages: &[ChatMessage]) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_llm_request", hook.on_llm_request(messages).await)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn llm_response(&self, response: &ChatResponse) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_llm_response", hook.on_llm_response(response).await)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn tool_start(&self, call: &ToolCall) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_tool_start", hook.on_tool_start(call).await)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn tool_end(&self, call: &ToolCall, output: &ToolOutput) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_tool_end", hook.on_tool_end(call, output).await)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn run_end(&self, result: &RunResult) -> RragResult<()> {
+        for hook in self.hooks {
+            self.settle("on_run_end", hook.on_run_end(result).await)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the hook mode to the outcome of one hook call
+    fn settle(&self, event: &str, outcome: RragResult<()>) -> RragResult<()> {
+        match (outcome, self.mode) {
+            (Ok(()), _) => Ok(()),
+            (Err(e), HookMode::Advisory) => {
+                warn!(hook = event, error = %e, "Agent hook failed; continuing");
+                Ok(())
+            }
+            (Err(e), HookMode::Enforcing) => Err(RragError::hook(event, e.to_string())),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! JSON-RPC connection to an MCP server
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::Child;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Messages buffered in each direction
+const CHANNEL_CAPACITY: usize = 32;
+
+/// JSON-RPC code for a method the client does not handle
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Message pipe to an MCP server, as opened by a
+/// [`McpTransport`](super::McpTransport)
+///
+/// Messages put on the outgoing side are sent to the server, and messages from
+/// the server come out of the incoming side. The server is gone once the
+/// incoming side closes.
+pub struct McpChannel {
+    outgoing: mpsc::Sender<Value>,
+    incoming: mpsc::Receiver<Value>,
+    process: Option<Child>,
+}
+
+impl fmt::Debug for McpChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("McpChannel")
+            .field("process", &self.process.as_ref().and_then(Child::id))
+            .finish_non_exhaustive()
+    }
+}
+
+impl McpChannel {
+    /// Create a channel from the two sides of a message pipe
+    pub fn new(outgoing: mpsc::Sender<Value>, incoming: mpsc::Receiver<Value>) -> Self {
+        Self {
+            outgoing,
+            incoming,
+            process: None,
+        }
+    }
+
+    /// Create a channel exchanging newline-delimited JSON over a pair of byte
+    /// streams, as the stdio transport does
+    pub fn from_streams<R, W>(reader: R, writer: W) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (outgoing, mut to_send) = mpsc::channel::<Value>(CHANNEL_CAPACITY);
+        let (received, incoming) = mpsc::channel(CHANNEL_CAPACITY);
+
+        tokio::spawn(async move {
+            let mut writer = writer;
+            while let Some(message) = to_send.recv().await {
+                let mut line = message.to_string();
+                line.push('\n');
+                if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Value>(&line) {
+                    Ok(message) => {
+                        if received.send(message).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Some servers print logs to stdout
+                    Err(e) => warn!(error = %e, "Ignoring a malformed MCP message"),
+                }
+            }
+        });
+        Self::new(outgoing, incoming)
+    }
+
+    /// Keep `process` alive for as long as the channel, killing it after
+    pub fn with_process(mut self, process: Child) -> Self {
+        self.process = Some(process);
+        self
+    }
+}
+
+/// Why a request got no result
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum McpError {
+    /// The server went away before answering
+    Closed,
+
+    /// The server did not answer in time
+    Timeout(Duration),
+
+    /// The server answered with an error
+    Remote { code: i64, message: String },
+}
+
+impl fmt::Display for McpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            McpError::Closed => write!(f, "the server closed the connection"),
+            McpError::Timeout(timeout) => {
+                write!(f, "the server did not answer within {:?}", timeout)
+            }
+            McpError::Remote { code, message } => write!(f, "error {}: {}", code, message),
+        }
+    }
+}
+
+/// Requests waiting for their response
+#[derive(Default)]
+struct Pending {
+    closed: bool,
+    waiters: HashMap<u64, oneshot::Sender<Result<Value, McpError>>>,
+}
+
+/// Live connection matching responses to requests
+pub(super) struct Connection {
+    server: String,
+    outgoing: mpsc::Sender<Value>,
+    pending: Arc<Mutex<Pending>>,
+    next_id: AtomicU64,
+    timeout: Duration,
+    router: JoinHandle<()>,
+    _process: Mutex<Option<Child>>,
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection")
+            .field("server", &self.server)
+            .field("closed", &self.is_closed())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Connection {
+    /// Start routing the messages of `channel`
+    pub(super) fn new(server: &str, channel: McpChannel, timeout: Duration) -> Self {
+        let McpChannel {
+            outgoing,
+            mut incoming,
+            process,
+        } = channel;
+        let pending = Arc::new(Mutex::new(Pending::default()));
+
+        let router = {
+            let server = server.to_string();
+            let pending = Arc::clone(&pending);
+            let outgoing = outgoing.clone();
+            tokio::spawn(async move {
+                while let Some(message) = incoming.recv().await {
+                    route(&server, message, &pending, &outgoing).await;
+                }
+                debug!(server = %server, "MCP server disconnected");
+                // Dropping the waiters wakes every request still in flight
+                let mut pending = pending.lock().unwrap();
+                pending.closed = true;
+                pending.waiters.clear();
+            })
+        };
+
+        Self {
+            server: server.to_string(),
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout,
+            router,
+            _process: Mutex::new(process),
+        }
+    }
+
+    /// Whether the server went away
+    pub(super) fn is_closed(&self) -> bool {
+        self.pending.lock().unwrap().closed || self.outgoing.is_closed()
+    }
+
+    /// Send a request and wait for its result
+    pub(super) async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.closed {
+                return Err(McpError::Closed);
+            }
+            pending.waiters.insert(id, sender);
+        }
+
+        let message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        debug!(server = %self.server, id, method, "Sending MCP request");
+        if self.outgoing.send(message).await.is_err() {
+            self.pending.lock().unwrap().waiters.remove(&id);
+            return Err(McpError::Closed);
+        }
+
+        match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(McpError::Closed),
+            Err(_) => {
+                self.pending.lock().unwrap().waiters.remove(&id);
+                Err(McpError::Timeout(self.timeout))
+            }
+        }
+    }
+
+    /// Send a notification, which gets no response
+    pub(super) async fn notify(&self, method: &str, params: Value) -> Result<(), McpError> {
+        let message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
+        self.outgoing
+            .send(message)
+            .await
+            .map_err(|_| McpError::Closed)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.router.abort();
+    }
+}
+
+/// Hand a message from the server to whoever waits for it
+async fn route(
+    server: &str,
+    message: Value,
+    pending: &Mutex<Pending>,
+    outgoing: &mpsc::Sender<Value>,
+) {
+    let id = message.get("id").cloned();
+    match (message.get("method").and_then(Value::as_str), id) {
+        // A request from the server; only pings are answered
+        (Some(method), Some(id)) => {
+            let reply = if method == "ping" {
+                serde_json::json!({"jsonrpc": "2.0", "id": id, "result": {}})
+            } else {
+                let message = format!("method '{}' is not supported", method);
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {"code": METHOD_NOT_FOUND, "message": message},
+                })
+            };
+            let _ = outgoing.send(reply).await;
+        }
+        (Some(method), None) => debug!(server, method, "MCP notification"),
+        (None, Some(id)) => {
+            let Some(waiter) = id
+                .as_u64()
+                .and_then(|id| pending.lock().unwrap().waiters.remove(&id))
+            else {
+                debug!(server, %id, "Ignoring a response to no pending request");
+                return;
+            };
+            let result = match message.get("error") {
+                Some(error) => Err(McpError::Remote {
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                }),
+                None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            let _ = waiter.send(result);
+        }
+        (None, None) => debug!(server, "Ignoring an MCP message without id or method"),
+    }
+}
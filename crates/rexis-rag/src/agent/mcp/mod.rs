@@ -0,0 +1,573 @@
+//! Model Context Protocol client
+//!
+//! [`McpToolProvider`] connects to an MCP server, lists its tools and wraps
+//! each as a [`Tool`] whose calls are forwarded to the server. Servers are
+//! reached over stdio ([`StdioTransport`]), server-sent events
+//! ([`SseTransport`]) or any other [`McpTransport`].
+//!
+//! A server that goes away fails the calls in flight with a tool error, and
+//! the next call reconnects. Every request is bounded by the configured
+//! timeout, so a stuck server cannot hang a run.
+
+mod connection;
+mod transport;
+
+pub use connection::McpChannel;
+pub use transport::{McpTransport, SseTransport, StdioTransport};
+
+use super::{Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use connection::{Connection, McpError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// MCP revision the client speaks
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Longest a request may take unless configured otherwise
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Most pages of tools read from one server
+const MAX_TOOL_PAGES: usize = 100;
+
+/// How to reach an MCP server and register its tools
+#[derive(Debug, Clone)]
+pub struct McpServerConfig {
+    /// Server name, used in logs and errors
+    pub name: String,
+
+    /// How the server is reached
+    pub transport: Arc<dyn McpTransport>,
+
+    /// Put before each tool name, joined with `_`, to avoid collisions
+    pub tool_prefix: Option<String>,
+
+    /// Longest a request to the server may take
+    pub request_timeout: Duration,
+}
+
+impl McpServerConfig {
+    /// Reach the server `name` through `transport`
+    pub fn new(name: impl Into<String>, transport: impl McpTransport + 'static) -> Self {
+        Self {
+            name: name.into(),
+            transport: Arc::new(transport),
+            tool_prefix: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Run the server as `command` with `args`, speaking over stdio
+    pub fn stdio<I, S>(name: impl Into<String>, command: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(name, StdioTransport::new(command, args))
+    }
+
+    /// Reach the server over server-sent events at `url`
+    pub fn sse(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self::new(name, SseTransport::new(url))
+    }
+
+    /// Prefix the server's tool names with `prefix`
+    pub fn with_tool_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.tool_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set the longest a request to the server may take
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+}
+
+/// A tool as listed by the server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpToolInfo {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    input_schema: Value,
+}
+
+/// Page of the `tools/list` result
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolsPage {
+    #[serde(default)]
+    tools: Vec<McpToolInfo>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+/// Connection shared by the tools of one server
+#[derive(Debug)]
+struct Server {
+    config: McpServerConfig,
+    connection: tokio::sync::Mutex<Option<Arc<Connection>>>,
+}
+
+impl Server {
+    /// The live connection, reconnecting when the server went away
+    async fn connection(&self) -> RragResult<Arc<Connection>> {
+        let mut current = self.connection.lock().await;
+        if let Some(connection) = current.as_ref().filter(|c| !c.is_closed()) {
+            return Ok(Arc::clone(connection));
+        }
+        if current.is_some() {
+            info!(server = %self.config.name, "Reconnecting to MCP server");
+        }
+        let connection = Arc::new(self.connect().await?);
+        *current = Some(Arc::clone(&connection));
+        Ok(connection)
+    }
+
+    /// Open a connection and go through the initialization handshake
+    async fn connect(&self) -> RragResult<Connection> {
+        let name = &self.config.name;
+        let channel = self.config.transport.open(name).await?;
+        let connection = Connection::new(name, channel, self.config.request_timeout);
+
+        let params = serde_json::json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "rexis", "version": env!("CARGO_PKG_VERSION")},
+        });
+        let initialized = connection
+            .request("initialize", params)
+            .await
+            .map_err(|e| self.error("initialize", e))?;
+        debug!(
+            server = %name,
+            version = initialized["protocolVersion"].as_str().unwrap_or_default(),
+            "Initialized MCP connection"
+        );
+        connection
+            .notify("notifications/initialized", serde_json::json!({}))
+            .await
+            .map_err(|e| self.error("initialize", e))?;
+        Ok(connection)
+    }
+
+    /// Every tool of the server, following pagination
+    async fn list_tools(&self) -> RragResult<Vec<McpToolInfo>> {
+        let connection = self.connection().await?;
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_TOOL_PAGES {
+            let params = match &cursor {
+                Some(cursor) => serde_json::json!({"cursor": cursor}),
+                None => serde_json::json!({}),
+            };
+            let page = connection
+                .request("tools/list", params)
+                .await
+                .map_err(|e| self.error("tools/list", e))?;
+            let page: ToolsPage = serde_json::from_value(page).map_err(|e| {
+                RragError::agent(
+                    &self.config.name,
+                    format!("invalid tools/list result: {}", e),
+                )
+            })?;
+            tools.extend(page.tools);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Error for a request that got no result
+    fn error(&self, method: &str, error: McpError) -> RragError {
+        let operation = format!("MCP server '{}' {}", self.config.name, method);
+        match error {
+            McpError::Closed => RragError::network(
+                operation,
+                io::Error::new(io::ErrorKind::ConnectionAborted, error.to_string()),
+            ),
+            McpError::Timeout(timeout) => RragError::timeout(operation, timeout.as_millis() as u64),
+            McpError::Remote { .. } => {
+                RragError::agent(&self.config.name, format!("{}: {}", method, error))
+            }
+        }
+    }
+}
+
+/// Tools of one MCP server
+#[derive(Debug, Clone)]
+pub struct McpToolProvider {
+    server: Arc<Server>,
+    tools: Vec<McpToolInfo>,
+}
+
+impl McpToolProvider {
+    /// Connect to the server and list its tools
+    pub async fn connect(config: McpServerConfig) -> RragResult<Self> {
+        let server = Arc::new(Server {
+            config,
+            connection: tokio::sync::Mutex::new(None),
+        });
+        let tools = server.list_tools().await?;
+        info!(
+            server = %server.config.name,
+            tools = tools.len(),
+            "Connected to MCP server"
+        );
+        Ok(Self { server, tools })
+    }
+
+    /// Name of the server
+    pub fn server(&self) -> &str {
+        &self.server.config.name
+    }
+
+    /// The server's tools, named with the configured prefix
+    pub fn tools(&self) -> Vec<Box<dyn Tool>> {
+        self.tools
+            .iter()
+            .map(|info| Box::new(McpTool::new(Arc::clone(&self.server), info)) as Box<dyn Tool>)
+            .collect()
+    }
+}
+
+/// A tool of an MCP server
+#[derive(Debug, Clone)]
+pub struct McpTool {
+    server: Arc<Server>,
+    name: String,
+    remote_name: String,
+    description: String,
+    schema: Value,
+}
+
+impl McpTool {
+    fn new(server: Arc<Server>, info: &McpToolInfo) -> Self {
+        let name = match &server.config.tool_prefix {
+            Some(prefix) => tool_name(&format!("{}_{}", prefix, info.name)),
+            None => tool_name(&info.name),
+        };
+        Self {
+            name,
+            remote_name: info.name.clone(),
+            description: info.description.clone().unwrap_or_default(),
+            schema: parameters_schema(&info.input_schema),
+            server,
+        }
+    }
+
+    /// Name of the tool on the server
+    pub fn remote_name(&self) -> &str {
+        &self.remote_name
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn call(&self, args: Value) -> RragResult<ToolOutput> {
+        let connection = self.server.connection().await?;
+        let params = serde_json::json!({"name": self.remote_name, "arguments": args});
+        let result = connection
+            .request("tools/call", params)
+            .await
+            .map_err(|e| match e {
+                McpError::Remote { .. } => RragError::tool_execution(&self.name, e.to_string()),
+                e => self.server.error("tools/call", e),
+            })?;
+
+        let content = result["content"].as_array().cloned().unwrap_or_default();
+        if result["isError"].as_bool().unwrap_or(false) {
+            let message = text_of(&content).unwrap_or_else(|| Value::Array(content).to_string());
+            return Err(RragError::tool_execution(&self.name, message));
+        }
+        Ok(match text_of(&content) {
+            Some(text) => ToolOutput::Text(text),
+            None => ToolOutput::Json(Value::Array(content)),
+        })
+    }
+}
+
+/// The text of content blocks that are all text, joined by newlines
+fn text_of(content: &[Value]) -> Option<String> {
+    content
+        .iter()
+        .map(|block| match block["type"].as_str() {
+            Some("text") => block["text"].as_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|texts| texts.join("\n"))
+}
+
+/// Tool name with characters models reject replaced by `_`
+fn tool_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+/// Input schema of an MCP tool as a parameters schema, which must describe an
+/// object
+fn parameters_schema(input_schema: &Value) -> Value {
+    let mut schema = match input_schema {
+        Value::Object(schema) => schema.clone(),
+        _ => serde_json::Map::new(),
+    };
+    schema.remove("$schema");
+    schema.insert("type".to_string(), "object".into());
+    schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::json!({}));
+    Value::Object(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentBuilder;
+    use rexis_llm::testing::{respond_text, respond_with_tool_call, MockClient};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    /// In-process server speaking the stdio protocol over in-memory pipes
+    #[derive(Debug, Default)]
+    struct MockServer {
+        connections: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl McpTransport for Arc<MockServer> {
+        async fn open(&self, _server: &str) -> RragResult<McpChannel> {
+            self.connections.fetch_add(1, Ordering::SeqCst);
+            let (client_reader, server_writer) = tokio::io::duplex(64 * 1024);
+            let (server_reader, client_writer) = tokio::io::duplex(64 * 1024);
+            tokio::spawn(serve(server_reader, server_writer));
+            Ok(McpChannel::from_streams(client_reader, client_writer))
+        }
+    }
+
+    /// Answer requests until the client goes away or a call to `crash`
+    async fn serve(reader: tokio::io::DuplexStream, mut writer: tokio::io::DuplexStream) {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: Value = serde_json::from_str(&line).unwrap();
+            let Some(id) = request.get("id").cloned() else {
+                continue;
+            };
+            let params = &request["params"];
+            let result = match request["method"].as_str().unwrap() {
+                "initialize" => serde_json::json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {"tools": {}},
+                    "serverInfo": {"name": "mock", "version": "1.0"},
+                }),
+                "tools/list" if params.get("cursor").is_none() => serde_json::json!({
+                    "tools": [{
+                        "name": "echo",
+                        "description": "Echoes the text",
+                        "inputSchema": {
+                            "$schema": "http://json-schema.org/draft-07/schema#",
+                            "type": "object",
+                            "properties": {"text": {"type": "string"}},
+                            "required": ["text"],
+                        },
+                    }],
+                    "nextCursor": "page-2",
+                }),
+                "tools/list" => serde_json::json!({
+                    "tools": [
+                        {"name": "fs.stat", "inputSchema": {}},
+                        {"name": "crash", "inputSchema": {"type": "object"}},
+                        {"name": "hang", "inputSchema": {"type": "object"}},
+                    ],
+                }),
+                "tools/call" => match params["name"].as_str().unwrap() {
+                    "echo" => serde_json::json!({
+                        "content": [{"type": "text", "text": params["arguments"]["text"]}],
+                    }),
+                    "fs.stat" => serde_json::json!({
+                        "content": [
+                            {"type": "text", "text": "stat"},
+                            {"type": "image", "data": "AAAA", "mimeType": "image/png"},
+                        ],
+                    }),
+                    "crash" => return,
+                    "hang" => continue,
+                    _ => serde_json::json!({
+                        "content": [{"type": "text", "text": "unknown tool"}],
+                        "isError": true,
+                    }),
+                },
+                method => {
+                    let response = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": -32601, "message": format!("no method {}", method)},
+                    });
+                    let line = format!("{}\n", response);
+                    writer.write_all(line.as_bytes()).await.unwrap();
+                    continue;
+                }
+            };
+            let response = serde_json::json!({"jsonrpc": "2.0", "id": id, "result": result});
+            let line = format!("{}\n", response);
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn config(server: &Arc<MockServer>) -> McpServerConfig {
+        McpServerConfig::new("mock", Arc::clone(server))
+            .with_request_timeout(Duration::from_secs(5))
+    }
+
+    #[tokio::test]
+    async fn test_tools_are_listed_and_called() {
+        let server = Arc::new(MockServer::default());
+        let provider = McpToolProvider::connect(config(&server).with_tool_prefix("mock"))
+            .await
+            .unwrap();
+        let tools = provider.tools();
+        let names: Vec<_> = tools.iter().map(|tool| tool.name()).collect();
+        assert_eq!(
+            names,
+            ["mock_echo", "mock_fs_stat", "mock_crash", "mock_hang"]
+        );
+
+        let echo = &tools[0];
+        assert_eq!(echo.description(), "Echoes the text");
+        assert_eq!(
+            echo.parameters_schema(),
+            serde_json::json!({
+                "type": "object",
+                "properties": {"text": {"type": "string"}},
+                "required": ["text"],
+            })
+        );
+        assert_eq!(
+            tools[1].parameters_schema(),
+            serde_json::json!({"type": "object", "properties": {}})
+        );
+
+        let output = echo.call(serde_json::json!({"text": "hi"})).await.unwrap();
+        assert_eq!(output, ToolOutput::Text("hi".to_string()));
+        let output = tools[1].call(serde_json::json!({})).await.unwrap();
+        assert_eq!(
+            output,
+            ToolOutput::Json(serde_json::json!([
+                {"type": "text", "text": "stat"},
+                {"type": "image", "data": "AAAA", "mimeType": "image/png"},
+            ]))
+        );
+        assert_eq!(server.connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_server_death_fails_call_and_reconnects() {
+        let server = Arc::new(MockServer::default());
+        let provider = McpToolProvider::connect(config(&server)).await.unwrap();
+        let tools = provider.tools();
+
+        let err = tools[2].call(serde_json::json!({})).await.unwrap_err();
+        assert!(err.is_retryable(), "{:?}", err);
+        assert!(err.to_string().contains("MCP server 'mock' tools/call"));
+
+        let output = tools[0]
+            .call(serde_json::json!({"text": "back"}))
+            .await
+            .unwrap();
+        assert_eq!(output, ToolOutput::Text("back".to_string()));
+        assert_eq!(server.connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_server_times_out() {
+        let server = Arc::new(MockServer::default());
+        let provider = McpToolProvider::connect(
+            config(&server).with_request_timeout(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+
+        let err = provider.tools()[3]
+            .call(serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::Timeout {
+                duration_ms: 50,
+                ..
+            }
+        ));
+        // The connection stays usable
+        let output = provider.tools()[0]
+            .call(serde_json::json!({"text": "still here"}))
+            .await
+            .unwrap();
+        assert_eq!(output, ToolOutput::Text("still here".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_agent_builder_registers_server_tools() {
+        let server = Arc::new(MockServer::default());
+        let mock = MockClient::builder()
+            .on_tool_result("mock_echo", respond_text("Echoed."))
+            .otherwise(respond_with_tool_call(
+                "mock_echo",
+                serde_json::json!({"text": "pong"}),
+            ))
+            .build();
+
+        let mut agent = AgentBuilder::new()
+            .with_llm(mock.client())
+            .with_mcp_server(config(&server).with_tool_prefix("mock"))
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let result = agent.run_detailed("Echo pong").await.unwrap();
+
+        assert_eq!(result.output, "Echoed.");
+        assert_eq!(result.tool_invocations[0].name, "mock_echo");
+        assert_eq!(result.tool_invocations[0].result, "pong");
+        assert_eq!(mock.requests()[0].tools.len(), 4);
+    }
+
+    #[test]
+    fn test_tool_error_content_is_returned_as_message() {
+        let content = [serde_json::json!({"type": "text", "text": "unknown tool"})];
+        assert_eq!(text_of(&content), Some("unknown tool".to_string()));
+        assert_eq!(tool_name("github/search.issues"), "github_search_issues");
+    }
+}
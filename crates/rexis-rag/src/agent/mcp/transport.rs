@@ -0,0 +1,294 @@
+//! Transports reaching MCP servers
+
+use super::McpChannel;
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+/// Longest an SSE server may take to announce where messages go
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Opens message channels to an MCP server
+///
+/// Called again to reconnect after the server went away.
+#[async_trait]
+pub trait McpTransport: fmt::Debug + Send + Sync {
+    /// Open a new channel to the server
+    async fn open(&self, server: &str) -> RragResult<McpChannel>;
+}
+
+/// Runs the server as a child process, speaking over its stdin and stdout
+///
+/// The process is killed when its connection is dropped. Lines it writes to
+/// stderr are logged at debug level.
+#[derive(Debug, Clone)]
+pub struct StdioTransport {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+}
+
+impl StdioTransport {
+    /// Run `command` with `args`
+    pub fn new<I, S>(command: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            command: command.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            env: HashMap::new(),
+        }
+    }
+
+    /// Set an environment variable for the process
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn open(&self, server: &str) -> RragResult<McpChannel> {
+        let mut child = tokio::process::Command::new(&self.command)
+            .args(&self.args)
+            .envs(&self.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| RragError::network(format!("starting MCP server '{}'", server), e))?;
+        debug!(server, command = %self.command, pid = child.id(), "Started MCP server");
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let error = io::Error::new(io::ErrorKind::BrokenPipe, "the process has no stdio");
+            return Err(RragError::network(
+                format!("starting MCP server '{}'", server),
+                error,
+            ));
+        };
+        if let Some(stderr) = child.stderr.take() {
+            let server = server.to_string();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!(server = %server, "{}", line);
+                }
+            });
+        }
+        Ok(McpChannel::from_streams(stdout, stdin).with_process(child))
+    }
+}
+
+/// Reaches the server over HTTP with server-sent events
+///
+/// Messages from the server arrive on an event stream opened with a GET to the
+/// URL; its first `endpoint` event names the URL messages are POSTed to.
+#[derive(Debug, Clone)]
+pub struct SseTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+impl SseTransport {
+    /// Connect to the event stream at `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Send a header with every request, such as an authorization token
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    fn request(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        self.headers.iter().fold(request, |request, (name, value)| {
+            request.header(name, value)
+        })
+    }
+}
+
+#[async_trait]
+impl McpTransport for SseTransport {
+    async fn open(&self, server: &str) -> RragResult<McpChannel> {
+        let operation = format!("connecting to MCP server '{}'", server);
+        let url = Url::parse(&self.url).map_err(|e| {
+            RragError::config("url", "an absolute URL", format!("{}: {}", self.url, e))
+        })?;
+        let mut response = self
+            .request(self.client.get(url.clone()))
+            .header("Accept", "text/event-stream")
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| RragError::network(operation.clone(), e))?;
+
+        let (outgoing, mut to_send) = mpsc::channel::<Value>(32);
+        let (received, incoming) = mpsc::channel(32);
+        let (endpoint_sender, endpoint) = oneshot::channel::<String>();
+
+        // Events are read until the stream ends, which closes the channel
+        let server_name = server.to_string();
+        tokio::spawn(async move {
+            let mut endpoint_sender = Some(endpoint_sender);
+            let mut parser = SseParser::default();
+            loop {
+                // Stop reading once the connection using the channel is gone
+                let chunk = tokio::select! {
+                    chunk = response.chunk() => chunk,
+                    _ = received.closed() => return,
+                };
+                let Ok(Some(chunk)) = chunk else {
+                    break;
+                };
+                for event in parser.push(&chunk) {
+                    match event.event.as_str() {
+                        "endpoint" => {
+                            if let Some(sender) = endpoint_sender.take() {
+                                let _ = sender.send(event.data);
+                            }
+                        }
+                        "message" => match serde_json::from_str::<Value>(&event.data) {
+                            Ok(message) => {
+                                if received.send(message).await.is_err() {
+                                    return;
+                                }
+                            }
+                            Err(e) => warn!(
+                                server = %server_name,
+                                error = %e,
+                                "Ignoring a malformed MCP message"
+                            ),
+                        },
+                        _ => {}
+                    }
+                }
+            }
+            debug!(server = %server_name, "MCP event stream ended");
+        });
+
+        let endpoint = tokio::time::timeout(ENDPOINT_TIMEOUT, endpoint)
+            .await
+            .map_err(|_| RragError::timeout(&operation, ENDPOINT_TIMEOUT.as_millis() as u64))?
+            .map_err(|_| {
+                RragError::stream(&operation, "the event stream ended before an endpoint")
+            })?;
+        let endpoint = url.join(endpoint.trim()).map_err(|e| {
+            RragError::stream(
+                &operation,
+                format!("the server named an invalid endpoint: {}", e),
+            )
+        })?;
+        debug!(server, %endpoint, "Connected to MCP server over SSE");
+
+        let transport = self.clone();
+        let server_name = server.to_string();
+        tokio::spawn(async move {
+            while let Some(message) = to_send.recv().await {
+                let sent = transport
+                    .request(transport.client.post(endpoint.clone()))
+                    .json(&message)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status);
+                if let Err(e) = sent {
+                    // Requests in flight fail once the channel closes
+                    warn!(server = %server_name, error = %e, "Failed to send MCP message");
+                    break;
+                }
+            }
+        });
+
+        Ok(McpChannel::new(outgoing, incoming))
+    }
+}
+
+/// An event of an SSE stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// Splits an SSE byte stream into events
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Add bytes from the stream, returning the events they complete
+    fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        self.buffer
+            .extend(bytes.iter().filter(|&&byte| byte != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|pair| pair == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let block = String::from_utf8_lossy(&block);
+            let mut event = "message".to_string();
+            let mut data = Vec::new();
+            for line in block.lines() {
+                let (field, value) = line.split_once(':').unwrap_or((line, ""));
+                let value = value.strip_prefix(' ').unwrap_or(value);
+                match field {
+                    "event" => event = value.to_string(),
+                    "data" => data.push(value),
+                    _ => {}
+                }
+            }
+            if !data.is_empty() {
+                events.push(SseEvent {
+                    event,
+                    data: data.join("\n"),
+                });
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_splits_events_across_chunks() {
+        let mut parser = SseParser::default();
+        let events =
+            parser.push(b"event: endpoint\r\ndata: /messages?session=1\r\n\r\n: keep-alive\n\nda");
+        assert_eq!(
+            events,
+            [SseEvent {
+                event: "endpoint".to_string(),
+                data: "/messages?session=1".to_string(),
+            }]
+        );
+
+        let events = parser.push(b"ta: {\"id\":1,\ndata: \"result\":{}}\n\n");
+        assert_eq!(
+            events,
+            [SseEvent {
+                event: "message".to_string(),
+                data: "{\"id\":1,\n\"result\":{}}".to_string(),
+            }]
+        );
+    }
+}
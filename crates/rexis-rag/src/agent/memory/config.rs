@@ -3,6 +3,20 @@
 use crate::storage::Memory;
 use std::sync::Arc;
 
+/// What happens to a session's conversation when the session ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConversationRetention {
+    /// Leave the conversation in place
+    #[default]
+    Keep,
+
+    /// Delete the conversation, keeping only the system message
+    Clear,
+
+    /// Copy the conversation to `agent::{agent_id}::archive::{session_id}`, then clear it
+    Archive,
+}
+
 /// Configuration for agent memory system
 #[derive(Clone)]
 pub struct MemoryConfig {
@@ -32,6 +46,9 @@ pub struct MemoryConfig {
 
     /// Auto-generate session IDs if not provided
     pub auto_generate_session_id: bool,
+
+    /// What happens to the conversation when the session ends
+    pub conversation_retention: ConversationRetention,
 }
 
 impl MemoryConfig {
@@ -47,6 +64,7 @@ impl MemoryConfig {
             enable_working: false,
             max_conversation_length: 50,
             auto_generate_session_id: true,
+            conversation_retention: ConversationRetention::Keep,
         }
     }
 
@@ -91,6 +109,12 @@ impl MemoryConfig {
         self.auto_generate_session_id = auto;
         self
     }
+
+    /// Set what happens to the conversation when the session ends
+    pub fn with_conversation_retention(mut self, retention: ConversationRetention) -> Self {
+        self.conversation_retention = retention;
+        self
+    }
 }
 
 impl Default for MemoryConfig {
@@ -107,6 +131,7 @@ impl Default for MemoryConfig {
             enable_working: false,
             max_conversation_length: 50,
             auto_generate_session_id: true,
+            conversation_retention: ConversationRetention::Keep,
         }
     }
 }
@@ -54,6 +54,11 @@ impl ConversationMemoryStore {
 
         self.storage.set(&key, value).await?;
 
+        let count_key = format!("{}::count", self.namespace);
+        self.storage
+            .set(&count_key, MemoryValue::Integer((count + 1) as i64))
+            .await?;
+
         // Prune if exceeded max length
         if count + 1 > self.max_length {
             self.prune_old_messages().await?;
@@ -265,4 +270,52 @@ mod tests {
         assert_eq!(messages.len(), 1);
         assert!(matches!(messages[0].role, rexis_llm::MessageRole::System));
     }
+
+    #[tokio::test]
+    async fn test_multimodal_message_round_trip() {
+        use rexis_llm::message::{AttachmentContent, AttachmentType, ContentAttachment};
+        use rexis_llm::{ImageSource, MessageContent};
+
+        let storage = Arc::new(InMemoryStorage::new());
+        let store = ConversationMemoryStore::new(storage, generate_session_id(), 10, true);
+
+        let raw = ContentAttachment {
+            attachment_type: AttachmentType::Image,
+            content: AttachmentContent::Bytes {
+                mime_type: "image/png".to_string(),
+                data: vec![0x89, 0x50, 0x4e, 0x47],
+            },
+            metadata: None,
+        };
+        store
+            .add_message(ChatMessage::user_with_image(
+                "What is this?",
+                ImageSource::Url("https://example.com/cat.png".to_string()),
+            ))
+            .await
+            .unwrap();
+        store
+            .add_message(ChatMessage::user(
+                MessageContent::multi_modal("And this?").with_attachment(raw),
+            ))
+            .await
+            .unwrap();
+
+        let messages = store.get_messages().await.unwrap();
+        assert_eq!(messages.len(), 2);
+
+        assert_eq!(messages[0].text(), Some("What is this?"));
+        assert!(matches!(
+            &messages[0].content.attachments()[0].content,
+            AttachmentContent::Url { url } if url == "https://example.com/cat.png"
+        ));
+
+        let attachment = &messages[1].content.attachments()[0];
+        assert_eq!(attachment.attachment_type, AttachmentType::Image);
+        assert!(matches!(
+            &attachment.content,
+            AttachmentContent::Bytes { mime_type, data }
+                if mime_type == "image/png" && data == &[0x89, 0x50, 0x4e, 0x47]
+        ));
+    }
 }
@@ -10,7 +10,11 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 #[cfg(feature = "rexis-llm-client")]
-use rexis_llm::{ChatMessage, Client, MessageRole};
+use rexis_llm::{BatchChatRequest, BatchState, ChatMessage, Client, GenerationParams, MessageRole};
+
+/// How often a batched re-score polls the provider
+#[cfg(feature = "rexis-llm-client")]
+const BATCH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
 
 /// An episode (summarized interaction or event)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +90,17 @@ impl Episode {
     }
 }
 
+/// Outcome of a batched importance re-score
+#[cfg(feature = "rexis-llm-client")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RescoreReport {
+    /// Number of episodes whose importance was updated
+    pub rescored: usize,
+
+    /// Episodes left unchanged because their request failed or returned no score
+    pub failed: Vec<String>,
+}
+
 /// Episodic memory for long-term context
 pub struct EpisodicMemory {
     /// Storage backend
@@ -455,6 +470,113 @@ impl EpisodicMemory {
         Ok(insights)
     }
 
+    /// Re-score the importance of every episode through the provider's batch API (requires 'rsllm-client' feature)
+    ///
+    /// Submits one request per episode, waits for the batch to finish and stores
+    /// the new scores. Intended for offline maintenance: batches are cheaper and
+    /// outside the realtime rate limits, but can take hours to complete.
+    #[cfg(feature = "rexis-llm-client")]
+    pub async fn rescore_all_batched(&self, llm_client: &Client) -> RragResult<RescoreReport> {
+        let episodes = self.get_all_episodes().await?;
+        if episodes.is_empty() {
+            return Ok(RescoreReport::default());
+        }
+
+        let requests = episodes
+            .iter()
+            .map(|episode| {
+                let prompt = format!(
+                    "Rate how important this conversation summary is for future interactions, \
+                     from 0.0 (irrelevant) to 1.0 (essential). Reply with the number only.\n\n{}",
+                    episode.summary
+                );
+                BatchChatRequest::new(episode.id.clone(), vec![ChatMessage::user(prompt)])
+                    .with_params(
+                        GenerationParams::new()
+                            .with_temperature(0.0)
+                            .with_max_tokens(8),
+                    )
+            })
+            .collect();
+
+        let to_rrag = |e| crate::error::RragError::rsllm_client("batch_rescore", e);
+        let batch = llm_client.create_batch(requests).await.map_err(to_rrag)?;
+        let status = batch.wait(BATCH_POLL_INTERVAL).await.map_err(to_rrag)?;
+        if status.state != BatchState::Completed {
+            tracing::warn!(
+                batch = batch.id(),
+                state = %status.state,
+                "Importance re-score batch did not complete, keeping partial results"
+            );
+        }
+        let results = batch.results().await.map_err(to_rrag)?;
+
+        let mut pending: std::collections::HashMap<String, Episode> = episodes
+            .into_iter()
+            .map(|episode| (episode.id.clone(), episode))
+            .collect();
+        let mut report = RescoreReport::default();
+
+        for result in results {
+            let Some(episode) = pending.remove(&result.custom_id) else {
+                continue;
+            };
+            match result
+                .response
+                .ok()
+                .and_then(|response| parse_importance(&response.content))
+            {
+                Some(importance) => {
+                    self.store_episode(episode.with_importance(importance))
+                        .await?;
+                    report.rescored += 1;
+                }
+                None => report.failed.push(episode.id),
+            }
+        }
+
+        // Episodes without a result (e.g. in an expired batch) keep their score
+        report.failed.extend(pending.into_keys());
+        report.failed.sort();
+
+        Ok(report)
+    }
+
+    /// Create an episode from conversation messages without an LLM
+    ///
+    /// The summary quotes the first and last user messages.
+    #[cfg(feature = "rexis-llm-client")]
+    pub fn create_episode_heuristic(&self, messages: &[ChatMessage]) -> RragResult<Episode> {
+        let requests: Vec<&str> = messages
+            .iter()
+            .filter(|msg| msg.role == MessageRole::User)
+            .filter_map(|msg| msg.text())
+            .collect();
+
+        let summary = match requests.as_slice() {
+            [] => {
+                return Err(crate::error::RragError::validation(
+                    "messages",
+                    "must contain a user message",
+                    format!("{} messages provided", messages.len()),
+                ))
+            }
+            [only] => format!("User asked: {}", excerpt(only)),
+            [first, .., last] => format!(
+                "User started with: {} Last asked: {}",
+                excerpt(first),
+                excerpt(last)
+            ),
+        };
+
+        let topics = self.extract_topics_from_text(&requests.join("\n"));
+        let importance = self.calculate_importance(messages.len(), &requests.join("\n"));
+
+        Ok(Episode::new(summary)
+            .with_topics(topics)
+            .with_importance(importance))
+    }
+
     /// Simple topic extraction from text (fallback when LLM not available)
     fn extract_topics_from_text(&self, text: &str) -> Vec<String> {
         // Simple keyword extraction - look for capitalized words and common programming terms
@@ -535,6 +657,25 @@ impl EpisodicMemory {
     }
 }
 
+/// First number in `text` within [0.0, 1.0]
+#[cfg(feature = "rexis-llm-client")]
+fn parse_importance(text: &str) -> Option<f64> {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find_map(|token| token.trim_end_matches('.').parse::<f64>().ok())
+        .filter(|score| (0.0..=1.0).contains(score))
+}
+
+/// Start of a message, shortened for heuristic summaries
+#[cfg(feature = "rexis-llm-client")]
+fn excerpt(text: &str) -> String {
+    const MAX_CHARS: usize = 160;
+    let text = text.trim();
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -627,4 +768,185 @@ mod tests {
         assert!(summary.contains("Recent interaction history"));
         assert!(summary.contains("User asked about Rust"));
     }
+
+    #[cfg(feature = "rexis-llm-client")]
+    #[tokio::test]
+    async fn test_create_episode_from_messages_with_mock_client() {
+        use rexis_llm::testing::{respond_text, MockClient};
+
+        let mock = MockClient::builder()
+            .on_user_message_containing(
+                "Summarize this conversation",
+                respond_text("  The user debugged a Rust lifetime error.  "),
+            )
+            .build();
+        let storage = Arc::new(InMemoryStorage::new());
+        let episodic = EpisodicMemory::new(storage, "test-agent".to_string());
+
+        let messages = vec![
+            ChatMessage::user("Why does my borrow not live long enough?"),
+            ChatMessage::assistant("The reference outlives the value it points to."),
+        ];
+        let episode = episodic
+            .create_episode_from_messages(&messages, &mock.client())
+            .await
+            .unwrap();
+
+        assert_eq!(episode.summary, "The user debugged a Rust lifetime error.");
+        assert_eq!(episode.topics, vec!["rust", "error"]);
+
+        let prompt = mock.requests()[0].messages[0].text().unwrap().to_string();
+        assert!(prompt.contains("User: Why does my borrow not live long enough?"));
+        assert!(prompt.contains("Assistant: The reference outlives"));
+    }
+
+    #[cfg(feature = "rexis-llm-client")]
+    mod batched {
+        use super::*;
+        use rexis_llm::{
+            BatchResult, BatchStatus, ChatResponse, ClientConfig, LLMProvider, Provider,
+            RsllmError, RsllmResult, StreamChunk,
+        };
+        use std::sync::Mutex;
+
+        /// Provider whose batches complete immediately, scoring by summary keywords
+        #[derive(Default)]
+        struct BatchProvider {
+            requests: Mutex<Vec<BatchChatRequest>>,
+        }
+
+        #[async_trait::async_trait]
+        impl LLMProvider for BatchProvider {
+            fn name(&self) -> &str {
+                "batch"
+            }
+
+            fn provider_type(&self) -> Provider {
+                Provider::OpenAI
+            }
+
+            fn supported_models(&self) -> Vec<String> {
+                Vec::new()
+            }
+
+            async fn health_check(&self) -> RsllmResult<bool> {
+                Ok(true)
+            }
+
+            async fn chat_completion(
+                &self,
+                _messages: Vec<ChatMessage>,
+                _model: Option<&str>,
+                _temperature: Option<f32>,
+                _max_tokens: Option<u32>,
+            ) -> RsllmResult<ChatResponse> {
+                unreachable!("re-scoring only uses the batch API")
+            }
+
+            async fn chat_completion_stream(
+                &self,
+                _messages: Vec<ChatMessage>,
+                _model: Option<String>,
+                _temperature: Option<f32>,
+                _max_tokens: Option<u32>,
+            ) -> RsllmResult<Box<dyn futures::Stream<Item = RsllmResult<StreamChunk>> + Send + Unpin>>
+            {
+                Ok(Box::new(futures::stream::empty()))
+            }
+
+            async fn create_batch(
+                &self,
+                requests: Vec<BatchChatRequest>,
+            ) -> RsllmResult<BatchStatus> {
+                let total = requests.len() as u32;
+                *self.requests.lock().unwrap() = requests;
+                Ok(self.status(total))
+            }
+
+            async fn batch_status(&self, _id: &str) -> RsllmResult<BatchStatus> {
+                Ok(self.status(self.requests.lock().unwrap().len() as u32))
+            }
+
+            async fn batch_results(&self, _status: &BatchStatus) -> RsllmResult<Vec<BatchResult>> {
+                Ok(self
+                    .requests
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|request| {
+                        let prompt = request.messages[0].text().unwrap_or_default();
+                        let response = if prompt.contains("outage") {
+                            Ok(ChatResponse::new("0.9", "batch"))
+                        } else if prompt.contains("weather") {
+                            Ok(ChatResponse::new("Hard to say.", "batch"))
+                        } else {
+                            Err(RsllmError::api("batch", "Invalid request", "invalid_value"))
+                        };
+                        BatchResult {
+                            custom_id: request.custom_id.clone(),
+                            response,
+                        }
+                    })
+                    .collect())
+            }
+        }
+
+        impl BatchProvider {
+            fn status(&self, total: u32) -> BatchStatus {
+                BatchStatus {
+                    id: "batch_1".to_string(),
+                    state: BatchState::Completed,
+                    total,
+                    completed: total,
+                    failed: 0,
+                    output_file_id: None,
+                    error_file_id: None,
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn test_rescore_all_batched() {
+            let storage = Arc::new(InMemoryStorage::new());
+            let episodic = EpisodicMemory::new(storage, "test-agent".to_string());
+
+            let outage = Episode::new("Resolved a production outage").with_importance(0.2);
+            let weather = Episode::new("Chatted about the weather").with_importance(0.4);
+            let broken = Episode::new("Garbled transcript").with_importance(0.6);
+            for episode in [&outage, &weather, &broken] {
+                episodic.store_episode(episode.clone()).await.unwrap();
+            }
+
+            let provider = Arc::new(BatchProvider::default());
+            let client = Client::with_provider(ClientConfig::default(), provider.clone());
+
+            let report = episodic.rescore_all_batched(&client).await.unwrap();
+
+            assert_eq!(report.rescored, 1);
+            let mut failed = vec![weather.id.clone(), broken.id.clone()];
+            failed.sort();
+            assert_eq!(report.failed, failed);
+
+            for (episode, importance) in [(&outage, 0.9), (&weather, 0.4), (&broken, 0.6)] {
+                let stored = episodic.get_episode(&episode.id).await.unwrap().unwrap();
+                assert_eq!(stored.importance, importance);
+            }
+
+            // Scoring requests are deterministic and short
+            let requests = provider.requests.lock().unwrap();
+            assert_eq!(requests.len(), 3);
+            assert!(requests
+                .iter()
+                .all(|request| request.params.temperature == Some(0.0)
+                    && request.params.max_tokens == Some(8)));
+        }
+
+        #[test]
+        fn test_parse_importance() {
+            assert_eq!(parse_importance("0.75"), Some(0.75));
+            assert_eq!(parse_importance("Score: 1.0."), Some(1.0));
+            assert_eq!(parse_importance("8"), None);
+            assert_eq!(parse_importance("unclear"), None);
+        }
+    }
 }
@@ -1,15 +1,19 @@
 //! Agent memory manager - coordinates all memory types
 
-use super::config::MemoryConfig;
+use super::config::{ConversationRetention, MemoryConfig};
 use super::conversation::{generate_session_id, ConversationMemoryStore};
-use super::episodic::EpisodicMemory;
+use super::episodic::{Episode, EpisodicMemory};
 use super::semantic::SemanticMemory;
 use super::shared::SharedKnowledgeBase;
 use super::working::WorkingMemory;
 use crate::error::RragResult;
-use crate::storage::Memory;
+use crate::storage::{Memory, MemoryValue};
 use rexis_llm::ChatMessage; // Use re-exported rsllm type
 use std::sync::Arc;
+use tracing::debug;
+
+#[cfg(feature = "rexis-llm-client")]
+use rexis_llm::{Client, MessageRole};
 
 /// Manages all memory types for an agent
 pub struct AgentMemoryManager {
@@ -39,6 +43,9 @@ pub struct AgentMemoryManager {
 
     /// Configuration
     config: MemoryConfig,
+
+    /// Agent run the manager serves, reported with its writes
+    run_id: Option<String>,
 }
 
 impl AgentMemoryManager {
@@ -71,6 +78,7 @@ impl AgentMemoryManager {
             episodic: None,
             shared: None,
             config,
+            run_id: None,
         }
     }
 
@@ -128,6 +136,26 @@ impl AgentMemoryManager {
         &self.session_id
     }
 
+    /// Manager for another session of the same agent, sharing storage and configuration
+    pub fn for_session(&self, session_id: impl Into<String>) -> Self {
+        let mut config = self.config.clone();
+        config.session_id = Some(session_id.into());
+        Self::new(config)
+    }
+
+    /// Manager for one run in the current session, whose writes are logged
+    /// with `run_id`
+    pub fn for_run(&self, run_id: impl Into<String>) -> Self {
+        let mut manager = self.for_session(self.session_id.clone());
+        manager.run_id = Some(run_id.into());
+        manager
+    }
+
+    /// Agent run the manager serves, if any
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
     /// Get conversation memory
     pub fn conversation(&self) -> &ConversationMemoryStore {
         &self.conversation
@@ -135,6 +163,12 @@ impl AgentMemoryManager {
 
     /// Add a message to conversation history
     pub async fn add_conversation_message(&self, message: ChatMessage) -> RragResult<()> {
+        debug!(
+            run_id = self.run_id(),
+            session_id = %self.session_id,
+            role = %message.role,
+            "Storing conversation message"
+        );
         self.conversation.add_message(message).await
     }
 
@@ -145,9 +179,107 @@ impl AgentMemoryManager {
 
     /// Clear conversation (keeps system message)
     pub async fn clear_conversation(&self) -> RragResult<()> {
+        debug!(
+            run_id = self.run_id(),
+            session_id = %self.session_id,
+            "Clearing conversation"
+        );
         self.conversation.clear().await
     }
 
+    /// Clear working memory and release it; it is recreated on next use
+    pub async fn close_working(&mut self) -> RragResult<()> {
+        let working = self
+            .working
+            .take()
+            .unwrap_or_else(|| WorkingMemory::new(self.storage.clone(), self.session_id.clone()));
+        working.close().await
+    }
+
+    /// End the session: summarize it into episodic memory and close working memory
+    ///
+    /// With episodic memory enabled, the conversation is summarized by
+    /// `llm_client`, or from its user messages when there is no client or the
+    /// LLM call fails, and stored as an episode tagged with the session id.
+    /// Working memory is then cleared and the conversation handled according to
+    /// [`MemoryConfig::conversation_retention`]. Ending a session again returns
+    /// the episode already stored for it rather than creating another.
+    #[cfg(feature = "rexis-llm-client")]
+    pub async fn end_session(
+        &mut self,
+        llm_client: Option<&Client>,
+    ) -> RragResult<Option<Episode>> {
+        let episode = if self.config.enable_episodic {
+            self.summarize_session(llm_client).await?
+        } else {
+            None
+        };
+
+        self.close_working().await?;
+        match self.config.conversation_retention {
+            ConversationRetention::Keep => {}
+            ConversationRetention::Clear => self.clear_conversation().await?,
+            ConversationRetention::Archive => {
+                let messages = self.get_conversation_messages().await?;
+                if !messages.is_empty() {
+                    let key = format!("archive::{}", self.session_id);
+                    let archived = MemoryValue::Json(serde_json::to_value(&messages)?);
+                    self.set_agent_memory(&key, archived).await?;
+                }
+                self.clear_conversation().await?;
+            }
+        }
+
+        Ok(episode)
+    }
+
+    /// Episode of the current session, creating it if there is none yet
+    #[cfg(feature = "rexis-llm-client")]
+    async fn summarize_session(
+        &mut self,
+        llm_client: Option<&Client>,
+    ) -> RragResult<Option<Episode>> {
+        let session_id = self.session_id.clone();
+        let existing = self
+            .episodic()
+            .get_all_episodes()
+            .await?
+            .into_iter()
+            .find(|episode| episode.session_id.as_deref() == Some(session_id.as_str()));
+        if existing.is_some() {
+            return Ok(existing);
+        }
+
+        let messages: Vec<ChatMessage> = self
+            .get_conversation_messages()
+            .await?
+            .into_iter()
+            .filter(|msg| matches!(msg.role, MessageRole::User | MessageRole::Assistant))
+            .collect();
+        if !messages.iter().any(|msg| msg.role == MessageRole::User) {
+            return Ok(None);
+        }
+
+        let episodic = self.episodic();
+        let episode = match llm_client {
+            Some(client) => match episodic
+                .create_episode_from_messages(&messages, client)
+                .await
+            {
+                Ok(episode) => episode,
+                Err(e) => {
+                    tracing::warn!(error = %e, "LLM session summary failed; using heuristic");
+                    episodic.create_episode_heuristic(&messages)?
+                }
+            },
+            None => episodic.create_episode_heuristic(&messages)?,
+        }
+        .with_session_id(session_id);
+
+        episodic.store_episode(episode.clone()).await?;
+        Ok(Some(episode))
+    }
+
     /// Get the underlying storage backend
     pub fn storage(&self) -> Arc<dyn Memory> {
         self.storage.clone()
@@ -175,6 +307,7 @@ impl AgentMemoryManager {
         value: impl Into<crate::storage::MemoryValue>,
     ) -> RragResult<()> {
         let full_key = self.agent_key(key);
+        debug!(run_id = self.run_id(), key = %full_key, "Storing memory value");
         self.storage.set(&full_key, value.into()).await
     }
 
@@ -194,6 +327,7 @@ impl AgentMemoryManager {
         value: impl Into<crate::storage::MemoryValue>,
     ) -> RragResult<()> {
         let full_key = self.session_key(key);
+        debug!(run_id = self.run_id(), key = %full_key, "Storing memory value");
         self.storage.set(&full_key, value.into()).await
     }
 
@@ -213,6 +347,7 @@ impl AgentMemoryManager {
         value: impl Into<crate::storage::MemoryValue>,
     ) -> RragResult<()> {
         let full_key = Self::global_key(key);
+        debug!(run_id = self.run_id(), key = %full_key, "Storing memory value");
         self.storage.set(&full_key, value.into()).await
     }
 
@@ -248,6 +383,7 @@ impl Clone for AgentMemoryManager {
             episodic: None,
             shared: None,
             config: self.config.clone(),
+            run_id: self.run_id.clone(),
         }
     }
 }
@@ -57,6 +57,7 @@ mod config;
 mod conversation;
 mod episodic;
 mod manager;
+mod rerank;
 mod semantic;
 mod shared;
 mod working;
@@ -65,13 +66,21 @@ mod working;
 pub mod vector;
 
 pub use compression::{CompressionConfig, CompressionStrategy, MemoryCompressor, MemoryStats};
-pub use config::MemoryConfig;
+pub use config::{ConversationRetention, MemoryConfig};
 pub use conversation::{generate_session_id, ConversationMemoryStore};
+#[cfg(feature = "rexis-llm-client")]
+pub use episodic::RescoreReport;
 pub use episodic::{Episode, EpisodicMemory};
 pub use manager::AgentMemoryManager;
+#[cfg(feature = "rexis-llm-client")]
+pub use rerank::LlmReranker;
+pub use rerank::Reranker;
 pub use semantic::{Fact, SemanticMemory};
 pub use shared::{KnowledgeEntry, SharedKnowledgeBase};
 pub use working::WorkingMemory;
 
 #[cfg(feature = "vector-search")]
-pub use vector::{Embedding, EmbeddingProvider, HashEmbeddingProvider, SearchResult};
+pub use vector::{
+    mmr_select, Embedding, EmbeddingProvider, HashEmbeddingProvider, MmrOptions, ReembedReport,
+    SearchFilterable, SearchMeta, SearchResult, VectorSearchFilter,
+};
@@ -0,0 +1,209 @@
+//! Reranking of retrieval candidates
+//!
+//! Bi-encoder similarity is cheap but coarse. A reranker takes the query and the
+//! over-fetched candidate texts and produces a more careful relevance ordering.
+
+use crate::error::RragResult;
+use async_trait::async_trait;
+
+#[cfg(feature = "rexis-llm-client")]
+use crate::error::RragError;
+#[cfg(feature = "rexis-llm-client")]
+use rexis_llm::{ChatMessage, Client};
+#[cfg(feature = "rexis-llm-client")]
+use std::sync::Arc;
+
+/// Trait for reranking retrieval candidates against a query
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// Score candidates against the query
+    ///
+    /// Returns `(candidate_index, score)` pairs ordered from most to least relevant.
+    /// Indices refer to positions in `candidates`; candidates may be omitted.
+    async fn rerank(&self, query: &str, candidates: Vec<String>) -> RragResult<Vec<(usize, f32)>>;
+}
+
+/// Reranker that asks an LLM to score each candidate from 0 to 10
+#[cfg(feature = "rexis-llm-client")]
+pub struct LlmReranker {
+    client: Arc<Client>,
+}
+
+#[cfg(feature = "rexis-llm-client")]
+impl LlmReranker {
+    /// Create a new LLM reranker
+    pub fn new(client: Arc<Client>) -> Self {
+        Self { client }
+    }
+
+    /// Build the scoring prompt
+    fn build_prompt(query: &str, candidates: &[String]) -> String {
+        let mut listing = String::new();
+        for (i, candidate) in candidates.iter().enumerate() {
+            listing.push_str(&format!("[{}] {}\n", i, candidate));
+        }
+
+        format!(
+            "Rate how relevant each candidate is to the query on a scale from 0 (irrelevant) to 10 (directly answers it).\n\
+             Respond with one line per candidate in the form `<index>: <score>` and nothing else.\n\n\
+             Query: {}\n\nCandidates:\n{}",
+            query, listing
+        )
+    }
+}
+
+#[cfg(feature = "rexis-llm-client")]
+#[async_trait]
+impl Reranker for LlmReranker {
+    async fn rerank(&self, query: &str, candidates: Vec<String>) -> RragResult<Vec<(usize, f32)>> {
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let prompt = Self::build_prompt(query, &candidates);
+        let response = self
+            .client
+            .chat_completion(vec![ChatMessage::user(prompt)])
+            .await
+            .map_err(|e| RragError::rsllm_client("rerank", e))?;
+
+        parse_scores(&response.content, candidates.len())
+    }
+}
+
+/// Parse `<index>: <score>` lines from a model response
+///
+/// Tolerates bracketed indices (`[2]`), `=`/`-` separators, trailing text, and
+/// scores written as `7/10`. Scores are normalized to 0.0..=1.0. Out-of-range or
+/// duplicate indices are ignored; a response with no usable line is an error.
+#[cfg(feature = "rexis-llm-client")]
+pub(crate) fn parse_scores(content: &str, candidate_count: usize) -> RragResult<Vec<(usize, f32)>> {
+    let mut scores: Vec<(usize, f32)> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches(['-', '*', ' ']);
+        let Some(split_at) = line.find([':', '=']).or_else(|| line.find(" - ")) else {
+            continue;
+        };
+        let (index_part, score_part) = line.split_at(split_at);
+
+        let index_digits: String = index_part
+            .chars()
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        let Ok(index) = index_digits.parse::<usize>() else {
+            continue;
+        };
+
+        let score_text: String = score_part
+            .trim_start_matches([':', '=', ' ', '-'])
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        let Ok(score) = score_text.parse::<f32>() else {
+            continue;
+        };
+
+        if index >= candidate_count || scores.iter().any(|(i, _)| *i == index) {
+            continue;
+        }
+
+        scores.push((index, (score / 10.0).clamp(0.0, 1.0)));
+    }
+
+    if scores.is_empty() {
+        return Err(RragError::validation(
+            "rerank_response",
+            "expected '<index>: <score>' lines",
+            content.chars().take(200).collect::<String>(),
+        ));
+    }
+
+    scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(scores)
+}
+
+#[cfg(all(test, feature = "rexis-llm-client"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scores_orders_by_score() {
+        let scores = parse_scores("0: 3\n1: 9\n2: 6", 3).unwrap();
+        let order: Vec<usize> = scores.iter().map(|(i, _)| *i).collect();
+        assert_eq!(order, vec![1, 2, 0]);
+        assert!((scores[0].1 - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_scores_is_defensive() {
+        let content = "Sure! Here are the scores:\n\
+                       - [0] = 7/10 (mentions Rust)\n\
+                       [1]: 10\n\
+                       5: 8\n\
+                       1: 2\n\
+                       2: n/a";
+        let scores = parse_scores(content, 3).unwrap();
+
+        // Out-of-range index 5, duplicate index 1 and unparseable index 2 are dropped
+        assert_eq!(scores, vec![(1, 1.0), (0, 0.7)]);
+    }
+
+    #[test]
+    fn test_parse_scores_rejects_garbage() {
+        assert!(parse_scores("I cannot help with that.", 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_llm_reranker_scores_candidates() {
+        use rexis_llm::testing::{respond_text, MockClient};
+
+        let mock = MockClient::builder()
+            .otherwise(respond_text("0: 3\n1: 9\n2: 6"))
+            .build();
+        let reranker = LlmReranker::new(Arc::new(mock.client()));
+
+        let scores = reranker
+            .rerank(
+                "favourite language",
+                vec![
+                    "user likes rust".to_string(),
+                    "user likes go".to_string(),
+                    "user likes c".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(scores, vec![(1, 0.9), (2, 0.6), (0, 0.3)]);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        let prompt = requests[0].last_message().unwrap().text().unwrap();
+        assert!(prompt.contains("Query: favourite language"));
+        assert!(prompt.contains("[0] user likes rust\n[1] user likes go\n[2] user likes c"));
+    }
+
+    #[tokio::test]
+    async fn test_llm_reranker_rejects_unparseable_response() {
+        use rexis_llm::testing::{respond_text, MockClient};
+
+        let mock = MockClient::builder()
+            .otherwise(respond_text("They all look relevant to me!"))
+            .build();
+        let reranker = LlmReranker::new(Arc::new(mock.client()));
+
+        let result = reranker
+            .rerank("query", vec!["a".to_string(), "b".to_string()])
+            .await;
+        assert!(result.is_err());
+
+        // No candidates, no request
+        assert!(reranker
+            .rerank("query", Vec::new())
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(mock.requests().len(), 1);
+    }
+}
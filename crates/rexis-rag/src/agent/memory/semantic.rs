@@ -10,8 +10,16 @@ use crate::storage::{Memory, MemoryValue};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+#[cfg(feature = "rexis-llm-client")]
+use rexis_llm::{ChatMessage, Client, MessageRole};
+
+#[cfg(feature = "vector-search")]
+use super::rerank::Reranker;
 #[cfg(feature = "vector-search")]
-use super::vector::{Embedding, EmbeddingProvider, SearchResult};
+use super::vector::{
+    mmr_select, Embedding, EmbeddingProvider, MmrOptions, ReembedReport, SearchFilterable,
+    SearchMeta, SearchResult, VectorSearchFilter,
+};
 
 /// A semantic fact stored in memory
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +96,29 @@ impl Fact {
     }
 }
 
+#[cfg(feature = "vector-search")]
+impl SearchFilterable for Fact {
+    fn filter_subject(&self) -> Option<&str> {
+        Some(&self.subject)
+    }
+
+    fn filter_predicate(&self) -> Option<&str> {
+        Some(&self.predicate)
+    }
+
+    fn filter_confidence(&self) -> Option<f64> {
+        Some(self.confidence)
+    }
+
+    fn filter_metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    fn filter_created_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        Some(self.created_at)
+    }
+}
+
 /// Semantic memory for agent knowledge
 pub struct SemanticMemory {
     /// Storage backend
@@ -219,6 +250,156 @@ impl SemanticMemory {
         self.storage.clear(Some(&self.namespace)).await
     }
 
+    /// Store a fact, refreshing an existing fact with the same subject, predicate and object
+    ///
+    /// A refreshed fact keeps its id and creation time and takes the confidence and
+    /// metadata of `fact`. Returns the fact as stored.
+    pub async fn upsert_fact(&self, mut fact: Fact) -> RragResult<Fact> {
+        let object = serde_json::to_value(&fact.object)?;
+        let existing = self
+            .find_by_subject_and_predicate(&fact.subject, &fact.predicate)
+            .await?
+            .into_iter()
+            .find(|f| serde_json::to_value(&f.object).ok().as_ref() == Some(&object));
+
+        if let Some(existing) = existing {
+            fact.id = existing.id;
+            fact.created_at = existing.created_at;
+            fact.updated_at = chrono::Utc::now();
+            #[cfg(feature = "vector-search")]
+            if fact.embedding.is_none() {
+                fact.embedding = existing.embedding;
+            }
+        }
+
+        self.store_fact(fact.clone()).await?;
+        Ok(fact)
+    }
+
+    /// Extract facts from conversation messages using the LLM (requires 'rsllm-client' feature)
+    ///
+    /// The model is asked for `subject | predicate | object` lines, with `user` as
+    /// the subject for the user; lines in any other shape are skipped. The facts
+    /// are returned without being stored.
+    #[cfg(feature = "rexis-llm-client")]
+    pub async fn extract_facts(
+        &self,
+        messages: &[ChatMessage],
+        llm_client: &Client,
+    ) -> RragResult<Vec<Fact>> {
+        let mut conversation = String::new();
+        for msg in messages {
+            let role = match msg.role {
+                MessageRole::User => "User",
+                MessageRole::Assistant => "Assistant",
+                MessageRole::System | MessageRole::Tool => continue,
+            };
+            conversation.push_str(&format!("{}: {}\n", role, msg.text().unwrap_or_default()));
+        }
+
+        let extraction_prompt = format!(
+            "Extract durable facts about the user from this conversation, such as \
+             preferences, personal details and goals. Respond with one fact per line \
+             in the form `subject | predicate | object`, using `user` as the subject \
+             for the user and snake_case predicates. Respond with NONE if there are \
+             no such facts.\n\n{}",
+            conversation
+        );
+
+        let response = llm_client
+            .chat_completion(vec![ChatMessage::user(extraction_prompt)])
+            .await
+            .map_err(|e| crate::error::RragError::rsllm_client("fact_extraction", e))?;
+
+        let facts = response
+            .content
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim().trim_start_matches(['-', '*']).trim();
+                let parts: Vec<&str> = line.split('|').map(|part| part.trim()).collect();
+                match parts.as_slice() {
+                    [subject, predicate, object]
+                        if !subject.is_empty() && !predicate.is_empty() && !object.is_empty() =>
+                    {
+                        Some(Fact::new(*subject, *predicate, *object))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Ok(facts)
+    }
+
+    /// Find similar facts, over-fetching `fetch_k` candidates by vector similarity and
+    /// reordering them with a reranker (requires 'vector-search' feature)
+    ///
+    /// Reranked results carry the reranker's score. Candidates the reranker didn't
+    /// score follow the scored ones in vector order, scored as the lowest reranker
+    /// score or 0.0, whichever is lower, so that all scores are on the reranker's
+    /// scale. If reranking fails, the original vector ordering is returned.
+    #[cfg(feature = "vector-search")]
+    pub async fn find_similar_reranked<P, R>(
+        &self,
+        query: &str,
+        provider: &P,
+        reranker: &R,
+        fetch_k: usize,
+        return_k: usize,
+    ) -> RragResult<Vec<SearchResult<Fact>>>
+    where
+        P: EmbeddingProvider,
+        R: Reranker + ?Sized,
+    {
+        let mut candidates = self
+            .find_similar(query, provider, fetch_k, f32::MIN, None)
+            .await?;
+
+        let texts = candidates
+            .iter()
+            .map(|result| Self::embedding_text(&result.item))
+            .collect();
+
+        let scores = match reranker.rerank(query, texts).await {
+            Ok(scores) => scores,
+            Err(e) => {
+                tracing::warn!("Reranking failed, using vector ordering: {}", e);
+                candidates.truncate(return_k);
+                return Ok(candidates);
+            }
+        };
+
+        let mut slots: Vec<Option<SearchResult<Fact>>> = candidates.into_iter().map(Some).collect();
+        let mut reranked = Vec::with_capacity(slots.len());
+
+        let mut floor = 0.0_f32;
+        for (index, score) in scores {
+            if let Some(mut result) = slots.get_mut(index).and_then(Option::take) {
+                result.score = score;
+                floor = floor.min(score);
+                reranked.push(result);
+            }
+        }
+        reranked.extend(slots.into_iter().flatten().map(|mut result| {
+            result.score = floor;
+            result
+        }));
+        reranked.truncate(return_k);
+
+        Ok(reranked)
+    }
+
+    /// Text representation of a fact used for embedding generation
+    #[cfg(feature = "vector-search")]
+    fn embedding_text(fact: &Fact) -> String {
+        format!(
+            "{} {} {}",
+            fact.subject,
+            fact.predicate,
+            fact.object.as_string().unwrap_or_default()
+        )
+    }
+
     /// Generate fact key
     fn fact_key(&self, fact_id: &str) -> String {
         format!("{}::fact::{}", self.namespace, fact_id)
@@ -242,25 +423,63 @@ impl SemanticMemory {
     }
 
     /// Search for facts using vector similarity (requires 'vector-search' feature)
+    ///
+    /// The optional filter is applied before scoring, so `limit` is the number
+    /// of matching results returned.
     #[cfg(feature = "vector-search")]
     pub async fn vector_search(
         &self,
         query_embedding: &Embedding,
         limit: usize,
         min_similarity: f32,
+        filter: Option<&VectorSearchFilter>,
     ) -> RragResult<Vec<SearchResult<Fact>>> {
+        let (results, meta) = self
+            .vector_search_with_meta(query_embedding, limit, min_similarity, filter)
+            .await?;
+
+        if meta.skipped_incompatible > 0 {
+            tracing::warn!(
+                "Skipped {} of {} facts with embeddings incompatible with the query ({} dims); consider reembed_all",
+                meta.skipped_incompatible,
+                meta.scanned,
+                query_embedding.dimensions
+            );
+        }
+
+        Ok(results)
+    }
+
+    /// Search for facts using vector similarity and report scan bookkeeping
+    /// (requires 'vector-search' feature)
+    #[cfg(feature = "vector-search")]
+    pub async fn vector_search_with_meta(
+        &self,
+        query_embedding: &Embedding,
+        limit: usize,
+        min_similarity: f32,
+        filter: Option<&VectorSearchFilter>,
+    ) -> RragResult<(Vec<SearchResult<Fact>>, SearchMeta)> {
         let all_facts = self.get_all_facts().await?;
         let mut results = Vec::new();
+        let mut meta = SearchMeta::default();
 
         for fact in all_facts {
+            if let Some(filter) = filter {
+                if !filter.matches(&fact) {
+                    continue;
+                }
+            }
+
             if let Some(fact_embedding) = &fact.embedding {
+                meta.scanned += 1;
                 match query_embedding.cosine_similarity(fact_embedding) {
                     Ok(similarity) => {
                         if similarity >= min_similarity {
                             results.push(SearchResult::new(fact, similarity));
                         }
                     }
-                    Err(_) => continue, // Skip facts with incompatible embeddings
+                    Err(_) => meta.skipped_incompatible += 1,
                 }
             }
         }
@@ -271,7 +490,28 @@ impl SemanticMemory {
         // Take top N results
         results.truncate(limit);
 
-        Ok(results)
+        Ok((results, meta))
+    }
+
+    /// Search for facts using vector similarity, diversified with Maximal Marginal
+    /// Relevance so near-duplicate facts don't crowd out the results
+    /// (requires 'vector-search' feature)
+    #[cfg(feature = "vector-search")]
+    pub async fn vector_search_mmr(
+        &self,
+        query_embedding: &Embedding,
+        limit: usize,
+        min_similarity: f32,
+        filter: Option<&VectorSearchFilter>,
+        options: MmrOptions,
+    ) -> RragResult<Vec<SearchResult<Fact>>> {
+        let candidates = self
+            .vector_search(query_embedding, usize::MAX, min_similarity, filter)
+            .await?;
+
+        Ok(mmr_select(candidates, limit, options, |fact| {
+            fact.embedding.as_ref()
+        }))
     }
 
     /// Store a fact with automatic embedding generation (requires 'vector-search' feature)
@@ -280,22 +520,88 @@ impl SemanticMemory {
     where
         P: EmbeddingProvider,
     {
-        // Generate text representation for embedding
-        let text = format!(
-            "{} {} {}",
-            fact.subject,
-            fact.predicate,
-            fact.object.as_string().unwrap_or_default()
-        );
-
         // Generate embedding
-        let embedding = provider.embed(&text).await?;
+        let embedding = provider.embed(&Self::embedding_text(&fact)).await?;
         fact.embedding = Some(embedding);
 
         // Store the fact
         self.store_fact(fact).await
     }
 
+    /// Regenerate fact embeddings with a (new) provider (requires 'vector-search' feature)
+    ///
+    /// Facts are loaded and rewritten `batch_size` at a time. With `only_mismatched`,
+    /// facts that already have an embedding from the provider's model, at its
+    /// dimensionality, are left untouched. Embedding failures are counted in the report rather than aborting.
+    #[cfg(feature = "vector-search")]
+    pub async fn reembed_all<P>(
+        &self,
+        provider: &P,
+        batch_size: usize,
+        only_mismatched: bool,
+    ) -> RragResult<ReembedReport>
+    where
+        P: EmbeddingProvider,
+    {
+        let fact_ids = self.list_fact_keys().await?;
+        let mut report = ReembedReport::default();
+
+        for batch in fact_ids.chunks(batch_size.max(1)) {
+            let keys: Vec<String> = batch.iter().map(|id| self.fact_key(id)).collect();
+            let values = self.storage.mget(&keys).await?;
+            let mut updates = Vec::new();
+
+            for (key, value) in keys.into_iter().zip(values) {
+                let Some(json) = value.as_ref().and_then(|v| v.as_json()) else {
+                    continue;
+                };
+                let mut fact: Fact = serde_json::from_value(json.clone()).map_err(|e| {
+                    crate::error::RragError::storage(
+                        "deserialize_fact",
+                        std::io::Error::new(std::io::ErrorKind::Other, e),
+                    )
+                })?;
+                report.scanned += 1;
+
+                let compatible = fact
+                    .embedding
+                    .as_ref()
+                    .map(|e| {
+                        e.dimensions == provider.dimensions() && e.model == provider.model_name()
+                    })
+                    .unwrap_or(false);
+                if only_mismatched && compatible {
+                    report.unchanged += 1;
+                    continue;
+                }
+
+                match provider.embed(&Self::embedding_text(&fact)).await {
+                    Ok(embedding) => {
+                        fact.embedding = Some(embedding);
+                        let value = serde_json::to_value(&fact).map_err(|e| {
+                            crate::error::RragError::storage(
+                                "serialize_fact",
+                                std::io::Error::new(std::io::ErrorKind::Other, e),
+                            )
+                        })?;
+                        updates.push((key, MemoryValue::Json(value)));
+                        report.reembedded += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to re-embed fact {}: {}", fact.id, e);
+                        report.failed += 1;
+                    }
+                }
+            }
+
+            if !updates.is_empty() {
+                self.storage.mset(&updates).await?;
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Find similar facts to a query text (requires 'vector-search' feature)
     #[cfg(feature = "vector-search")]
     pub async fn find_similar<P>(
@@ -304,6 +610,7 @@ impl SemanticMemory {
         provider: &P,
         limit: usize,
         min_similarity: f32,
+        filter: Option<&VectorSearchFilter>,
     ) -> RragResult<Vec<SearchResult<Fact>>>
     where
         P: EmbeddingProvider,
@@ -312,7 +619,7 @@ impl SemanticMemory {
         let query_embedding = provider.embed(query).await?;
 
         // Search using embedding
-        self.vector_search(&query_embedding, limit, min_similarity)
+        self.vector_search(&query_embedding, limit, min_similarity, filter)
             .await
     }
 }
@@ -435,4 +742,358 @@ mod tests {
         semantic.delete_fact(&fact_id).await.unwrap();
         assert_eq!(semantic.count().await.unwrap(), 0);
     }
+
+    #[tokio::test]
+    async fn test_semantic_memory_upsert_refreshes_matching_fact() {
+        let storage = Arc::new(InMemoryStorage::new());
+        let semantic = SemanticMemory::new(storage, "test-agent".to_string());
+
+        let first = semantic
+            .upsert_fact(Fact::new("user:alice", "likes", "coffee"))
+            .await
+            .unwrap();
+        let refreshed = semantic
+            .upsert_fact(Fact::new("user:alice", "likes", "coffee").with_metadata("source", "chat"))
+            .await
+            .unwrap();
+        semantic
+            .upsert_fact(Fact::new("user:alice", "likes", "tea"))
+            .await
+            .unwrap();
+
+        assert_eq!(refreshed.id, first.id);
+        assert_eq!(refreshed.created_at, first.created_at);
+        assert_eq!(semantic.count().await.unwrap(), 2);
+        let stored = semantic.get_fact(&first.id).await.unwrap().unwrap();
+        assert_eq!(
+            stored.metadata.get("source").map(String::as_str),
+            Some("chat")
+        );
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_semantic_memory_vector_search_mmr() {
+        let storage = Arc::new(InMemoryStorage::new());
+        let semantic = SemanticMemory::new(storage, "test-agent".to_string());
+
+        let facts = [
+            ("dark_mode", vec![1.0, 0.0, 0.0]),
+            ("dark_theme", vec![0.99, 0.05, 0.0]),
+            ("night_mode", vec![0.98, 0.08, 0.0]),
+            ("coffee", vec![0.2, 1.0, 0.0]),
+        ];
+        for (object, vector) in facts {
+            semantic
+                .store_fact(
+                    Fact::new("user:alice", "prefers", MemoryValue::from(object))
+                        .with_embedding(Embedding::new(vector, "test")),
+                )
+                .await
+                .unwrap();
+        }
+
+        let query = Embedding::new(vec![1.0, 0.5, 0.0], "test");
+
+        // Plain top-k returns the near-duplicates
+        let plain = semantic.vector_search(&query, 2, 0.0, None).await.unwrap();
+        assert!(plain
+            .iter()
+            .all(|r| r.item.object.as_string() != Some("coffee")));
+
+        // MMR trades some relevance for breadth
+        let diversified = semantic
+            .vector_search_mmr(&query, 2, 0.0, None, MmrOptions::new(0.5))
+            .await
+            .unwrap();
+        assert_eq!(diversified.len(), 2);
+        assert_eq!(diversified[0].item.object.as_string(), Some("night_mode"));
+        assert_eq!(diversified[1].item.object.as_string(), Some("coffee"));
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_semantic_memory_vector_search_filter() {
+        let storage = Arc::new(InMemoryStorage::new());
+        let semantic = SemanticMemory::new(storage, "test-agent".to_string());
+
+        // Globally most similar, but wrong subject
+        semantic
+            .store_fact(
+                Fact::new("user:bob", "prefers", MemoryValue::from("rust"))
+                    .with_embedding(Embedding::new(vec![1.0, 0.0], "test")),
+            )
+            .await
+            .unwrap();
+        // Right subject, but low confidence
+        semantic
+            .store_fact(
+                Fact::new("user:alice", "prefers", MemoryValue::from("go"))
+                    .with_confidence(0.5)
+                    .with_embedding(Embedding::new(vec![0.95, 0.1], "test")),
+            )
+            .await
+            .unwrap();
+        semantic
+            .store_fact(
+                Fact::new("user:alice", "prefers", MemoryValue::from("python"))
+                    .with_confidence(0.9)
+                    .with_embedding(Embedding::new(vec![0.7, 0.7], "test")),
+            )
+            .await
+            .unwrap();
+
+        let query = Embedding::new(vec![1.0, 0.0], "test");
+        let filter = VectorSearchFilter::new()
+            .with_subject_prefix("user:alice")
+            .with_min_confidence(0.7);
+
+        let results = semantic
+            .vector_search(&query, 1, 0.0, Some(&filter))
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].item.object.as_string(), Some("python"));
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_semantic_memory_reembed_mixed_dimensions() {
+        use crate::agent::memory::vector::HashEmbeddingProvider;
+
+        let storage = Arc::new(InMemoryStorage::new());
+        let semantic = SemanticMemory::new(storage, "test-agent".to_string());
+
+        let old_provider = HashEmbeddingProvider::new(8);
+        let new_provider = HashEmbeddingProvider::new(16);
+
+        for object in ["rust", "go"] {
+            semantic
+                .store_fact_with_embedding(
+                    Fact::new("user:alice", "likes", MemoryValue::from(object)),
+                    &old_provider,
+                )
+                .await
+                .unwrap();
+        }
+        semantic
+            .store_fact_with_embedding(
+                Fact::new("user:alice", "likes", MemoryValue::from("python")),
+                &new_provider,
+            )
+            .await
+            .unwrap();
+
+        let query = new_provider.embed("user:alice likes rust").await.unwrap();
+        let (results, meta) = semantic
+            .vector_search_with_meta(&query, 10, -1.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            meta,
+            SearchMeta {
+                scanned: 3,
+                skipped_incompatible: 2
+            }
+        );
+
+        let report = semantic.reembed_all(&new_provider, 2, true).await.unwrap();
+        assert_eq!(report.scanned, 3);
+        assert_eq!(report.reembedded, 2);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.failed, 0);
+
+        let (results, meta) = semantic
+            .vector_search_with_meta(&query, 10, -1.0, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(meta.skipped_incompatible, 0);
+        // The re-embedded fact matching the query text exactly ranks first
+        assert_eq!(results[0].item.object.as_string(), Some("rust"));
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_semantic_memory_reembed_changed_model_same_dimensions() {
+        let storage = Arc::new(InMemoryStorage::new());
+        let semantic = SemanticMemory::new(storage, "test-agent".to_string());
+
+        let old_provider = FixedEmbedding(Embedding::new(vec![1.0, 0.0], "model-a"));
+        let new_provider = FixedEmbedding(Embedding::new(vec![0.0, 1.0], "model-b"));
+
+        for object in ["rust", "go"] {
+            semantic
+                .store_fact_with_embedding(
+                    Fact::new("user:alice", "likes", MemoryValue::from(object)),
+                    &old_provider,
+                )
+                .await
+                .unwrap();
+        }
+        semantic
+            .store_fact_with_embedding(
+                Fact::new("user:alice", "likes", MemoryValue::from("python")),
+                &new_provider,
+            )
+            .await
+            .unwrap();
+
+        let report = semantic.reembed_all(&new_provider, 2, true).await.unwrap();
+        assert_eq!(report.scanned, 3);
+        assert_eq!(report.reembedded, 2);
+        assert_eq!(report.unchanged, 1);
+
+        // Every fact now carries the new model's vector
+        let results = semantic
+            .vector_search(&new_provider.0, 10, 0.99, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+    }
+
+    #[cfg(feature = "vector-search")]
+    struct FixedReranker(Option<Vec<(usize, f32)>>);
+
+    #[cfg(feature = "vector-search")]
+    #[async_trait::async_trait]
+    impl Reranker for FixedReranker {
+        async fn rerank(
+            &self,
+            _query: &str,
+            _candidates: Vec<String>,
+        ) -> RragResult<Vec<(usize, f32)>> {
+            self.0
+                .clone()
+                .ok_or_else(|| crate::error::RragError::validation("rerank", "mock", "failure"))
+        }
+    }
+
+    #[cfg(feature = "vector-search")]
+    async fn reranking_fixture() -> SemanticMemory {
+        let storage = Arc::new(InMemoryStorage::new());
+        let semantic = SemanticMemory::new(storage, "test-agent".to_string());
+
+        let facts = [
+            ("a", vec![1.0, 0.0]),
+            ("b", vec![0.8, 0.6]),
+            ("c", vec![0.0, 1.0]),
+        ];
+        for (object, vector) in facts {
+            semantic
+                .store_fact(
+                    Fact::new("user:alice", "likes", MemoryValue::from(object))
+                        .with_embedding(Embedding::new(vector, "test")),
+                )
+                .await
+                .unwrap();
+        }
+
+        semantic
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_find_similar_reranked_reorders() {
+        let semantic = reranking_fixture().await;
+        let provider = FixedEmbedding(Embedding::new(vec![1.0, 0.0], "test"));
+
+        // Vector order is a, b, c; the reranker prefers c then a
+        let reranker = FixedReranker(Some(vec![(2, 0.9), (0, 0.5)]));
+        let results = semantic
+            .find_similar_reranked("q", &provider, &reranker, 3, 3)
+            .await
+            .unwrap();
+
+        let order: Vec<_> = results
+            .iter()
+            .map(|r| r.item.object.as_string().unwrap())
+            .collect();
+        assert_eq!(order, vec!["c", "a", "b"]);
+        assert_eq!(results[0].score, 0.9);
+        // "b" was left unscored; its cosine score would have outranked "a"
+        assert_eq!(results[2].score, 0.0);
+    }
+
+    #[cfg(all(feature = "vector-search", feature = "rexis-llm-client"))]
+    #[tokio::test]
+    async fn test_find_similar_reranked_with_llm_reranker() {
+        use super::super::rerank::LlmReranker;
+        use rexis_llm::testing::{respond_text, MockClient};
+
+        let semantic = reranking_fixture().await;
+        let provider = FixedEmbedding(Embedding::new(vec![1.0, 0.0], "test"));
+
+        // The model scores c and b but leaves a unscored
+        let mock = MockClient::builder()
+            .otherwise(respond_text("[2]: 9\n[1]: 4"))
+            .build();
+        let reranker = LlmReranker::new(Arc::new(mock.client()));
+        let results = semantic
+            .find_similar_reranked("q", &provider, &reranker, 3, 3)
+            .await
+            .unwrap();
+
+        let ranked: Vec<_> = results
+            .iter()
+            .map(|r| (r.item.object.as_string().unwrap(), r.score))
+            .collect();
+        assert_eq!(ranked, vec![("c", 0.9), ("b", 0.4), ("a", 0.0)]);
+
+        // A response without scores falls back to the vector ordering
+        let mock = MockClient::builder()
+            .otherwise(respond_text("I cannot rate these."))
+            .build();
+        let reranker = LlmReranker::new(Arc::new(mock.client()));
+        let results = semantic
+            .find_similar_reranked("q", &provider, &reranker, 3, 3)
+            .await
+            .unwrap();
+
+        let order: Vec<_> = results
+            .iter()
+            .map(|r| r.item.object.as_string().unwrap())
+            .collect();
+        assert_eq!(order, vec!["a", "b", "c"]);
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[tokio::test]
+    async fn test_find_similar_reranked_falls_back_on_error() {
+        let semantic = reranking_fixture().await;
+        let provider = FixedEmbedding(Embedding::new(vec![1.0, 0.0], "test"));
+
+        let results = semantic
+            .find_similar_reranked("q", &provider, &FixedReranker(None), 3, 2)
+            .await
+            .unwrap();
+
+        let order: Vec<_> = results
+            .iter()
+            .map(|r| r.item.object.as_string().unwrap())
+            .collect();
+        assert_eq!(order, vec!["a", "b"]);
+    }
+
+    /// Embedding provider that always returns the same embedding
+    #[cfg(feature = "vector-search")]
+    struct FixedEmbedding(Embedding);
+
+    #[cfg(feature = "vector-search")]
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for FixedEmbedding {
+        async fn embed(&self, _text: &str) -> RragResult<Embedding> {
+            Ok(self.0.clone())
+        }
+
+        fn model_name(&self) -> &str {
+            &self.0.model
+        }
+
+        fn dimensions(&self) -> usize {
+            self.0.dimensions
+        }
+    }
 }
@@ -5,6 +5,7 @@
 
 use crate::error::{RragError, RragResult};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// A vector embedding (dense float vector)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,7 +129,7 @@ impl HashEmbeddingProvider {
 impl EmbeddingProvider for HashEmbeddingProvider {
     async fn embed(&self, text: &str) -> RragResult<Embedding> {
         let vector = self.hash_embed(text);
-        Ok(Embedding::new(vector, "hash"))
+        Ok(Embedding::new(vector, self.model_name()))
     }
 
     fn model_name(&self) -> &str {
@@ -170,6 +171,255 @@ impl<T> SearchResult<T> {
     }
 }
 
+/// Bookkeeping about a vector search scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchMeta {
+    /// Number of embedded candidates compared against the query
+    pub scanned: usize,
+
+    /// Candidates skipped because their embedding dimensions don't match the query
+    pub skipped_incompatible: usize,
+}
+
+/// Summary of a re-embedding pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReembedReport {
+    /// Number of items examined
+    pub scanned: usize,
+
+    /// Number of items whose embedding was regenerated
+    pub reembedded: usize,
+
+    /// Number of items left untouched (already compatible)
+    pub unchanged: usize,
+
+    /// Number of items whose embedding generation failed
+    pub failed: usize,
+}
+
+/// Accessors used by [`VectorSearchFilter`] to inspect searchable items
+///
+/// Fields an item doesn't have should return `None`; a filter constraint on a
+/// missing field never matches.
+pub trait SearchFilterable {
+    /// Subject of the item (e.g., "user:alice")
+    fn filter_subject(&self) -> Option<&str> {
+        None
+    }
+
+    /// Predicate/relation of the item
+    fn filter_predicate(&self) -> Option<&str> {
+        None
+    }
+
+    /// Confidence score of the item
+    fn filter_confidence(&self) -> Option<f64> {
+        None
+    }
+
+    /// Metadata value for a key
+    fn filter_metadata(&self, _key: &str) -> Option<&str> {
+        None
+    }
+
+    /// When the item was created
+    fn filter_created_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        None
+    }
+}
+
+/// Metadata constraints applied to candidates before similarity scoring
+///
+/// Because filtering happens before ranking, the search `limit` means
+/// "k matching results" rather than "k results, some of which were dropped".
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VectorSearchFilter {
+    /// Exact subject match
+    pub subject: Option<String>,
+
+    /// Subject prefix match (e.g., "user:alice")
+    pub subject_prefix: Option<String>,
+
+    /// Exact predicate match
+    pub predicate: Option<String>,
+
+    /// Minimum confidence (inclusive)
+    pub min_confidence: Option<f64>,
+
+    /// Metadata entries that must be present with equal values
+    pub metadata: HashMap<String, String>,
+
+    /// Only items created strictly after this instant
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl VectorSearchFilter {
+    /// Create an empty filter (matches everything)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require an exact subject
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Require a subject prefix
+    pub fn with_subject_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.subject_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Require an exact predicate
+    pub fn with_predicate(mut self, predicate: impl Into<String>) -> Self {
+        self.predicate = Some(predicate.into());
+        self
+    }
+
+    /// Require a minimum confidence
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = Some(min_confidence);
+        self
+    }
+
+    /// Require a metadata entry
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Require creation after the given time
+    pub fn with_created_after(mut self, created_after: chrono::DateTime<chrono::Utc>) -> Self {
+        self.created_after = Some(created_after);
+        self
+    }
+
+    /// Check whether the filter has no constraints
+    pub fn is_empty(&self) -> bool {
+        self.subject.is_none()
+            && self.subject_prefix.is_none()
+            && self.predicate.is_none()
+            && self.min_confidence.is_none()
+            && self.metadata.is_empty()
+            && self.created_after.is_none()
+    }
+
+    /// Check whether an item satisfies every constraint
+    pub fn matches<T: SearchFilterable + ?Sized>(&self, item: &T) -> bool {
+        if let Some(subject) = &self.subject {
+            if item.filter_subject() != Some(subject.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(prefix) = &self.subject_prefix {
+            match item.filter_subject() {
+                Some(subject) if subject.starts_with(prefix.as_str()) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(predicate) = &self.predicate {
+            if item.filter_predicate() != Some(predicate.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(min_confidence) = self.min_confidence {
+            match item.filter_confidence() {
+                Some(confidence) if confidence >= min_confidence => {}
+                _ => return false,
+            }
+        }
+
+        for (key, value) in &self.metadata {
+            if item.filter_metadata(key) != Some(value.as_str()) {
+                return false;
+            }
+        }
+
+        if let Some(created_after) = self.created_after {
+            match item.filter_created_at() {
+                Some(created_at) if created_at > created_after => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Options for Maximal Marginal Relevance (MMR) diversification
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MmrOptions {
+    /// Trade-off between relevance and diversity (1.0 = pure relevance, 0.0 = pure diversity)
+    pub lambda: f32,
+}
+
+impl MmrOptions {
+    /// Create MMR options with the given lambda (clamped to 0.0..=1.0)
+    pub fn new(lambda: f32) -> Self {
+        Self {
+            lambda: lambda.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Default for MmrOptions {
+    fn default() -> Self {
+        Self { lambda: 0.5 }
+    }
+}
+
+/// Re-rank search results using Maximal Marginal Relevance
+///
+/// Iteratively picks the candidate maximizing
+/// `lambda * sim(query, doc) - (1 - lambda) * max sim(doc, already_selected)`,
+/// where the query similarity is the candidate's existing `score` and pairwise
+/// similarity is computed from the embeddings returned by `embedding_of`.
+/// Candidates without a (compatible) embedding incur no redundancy penalty.
+pub fn mmr_select<T, F>(
+    candidates: Vec<SearchResult<T>>,
+    limit: usize,
+    options: MmrOptions,
+    embedding_of: F,
+) -> Vec<SearchResult<T>>
+where
+    F: Fn(&T) -> Option<&Embedding>,
+{
+    let lambda = options.lambda.clamp(0.0, 1.0);
+    let mut remaining = candidates;
+    let mut selected: Vec<SearchResult<T>> = Vec::with_capacity(limit.min(remaining.len()));
+
+    while selected.len() < limit && !remaining.is_empty() {
+        let mut best_index = 0;
+        let mut best_score = f32::NEG_INFINITY;
+
+        for (index, candidate) in remaining.iter().enumerate() {
+            let redundancy = embedding_of(&candidate.item)
+                .map(|embedding| {
+                    selected
+                        .iter()
+                        .filter_map(|chosen| embedding_of(&chosen.item))
+                        .filter_map(|other| embedding.cosine_similarity(other).ok())
+                        .fold(0.0_f32, f32::max)
+                })
+                .unwrap_or(0.0);
+
+            let mmr_score = lambda * candidate.score - (1.0 - lambda) * redundancy;
+            if mmr_score > best_score {
+                best_score = mmr_score;
+                best_index = index;
+            }
+        }
+
+        selected.push(remaining.remove(best_index));
+    }
+
+    selected
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,4 +472,91 @@ mod tests {
         let sim = emb1.cosine_similarity(&emb3).unwrap();
         assert!(sim < 1.0);
     }
+
+    fn candidate(id: &str, vector: Vec<f32>, score: f32) -> SearchResult<(String, Embedding)> {
+        SearchResult::new((id.to_string(), Embedding::new(vector, "test")), score)
+    }
+
+    #[test]
+    fn test_mmr_select_skips_near_duplicates() {
+        let candidates = vec![
+            candidate("dup1", vec![1.0, 0.0, 0.0], 0.99),
+            candidate("dup2", vec![0.99, 0.01, 0.0], 0.98),
+            candidate("dup3", vec![0.98, 0.02, 0.0], 0.97),
+            candidate("other", vec![0.0, 1.0, 0.0], 0.60),
+        ];
+
+        let selected = mmr_select(candidates, 2, MmrOptions::new(0.5), |(_, emb)| Some(emb));
+        let ids: Vec<&str> = selected.iter().map(|r| r.item.0.as_str()).collect();
+
+        assert_eq!(ids, vec!["dup1", "other"]);
+    }
+
+    #[test]
+    fn test_mmr_select_lambda_one_is_relevance_order() {
+        let candidates = vec![
+            candidate("dup1", vec![1.0, 0.0, 0.0], 0.99),
+            candidate("dup2", vec![0.99, 0.01, 0.0], 0.98),
+            candidate("other", vec![0.0, 1.0, 0.0], 0.60),
+        ];
+
+        let selected = mmr_select(candidates, 2, MmrOptions::new(1.0), |(_, emb)| Some(emb));
+        let ids: Vec<&str> = selected.iter().map(|r| r.item.0.as_str()).collect();
+
+        assert_eq!(ids, vec!["dup1", "dup2"]);
+    }
+
+    struct Item {
+        subject: String,
+        confidence: f64,
+        tags: HashMap<String, String>,
+    }
+
+    impl SearchFilterable for Item {
+        fn filter_subject(&self) -> Option<&str> {
+            Some(&self.subject)
+        }
+
+        fn filter_confidence(&self) -> Option<f64> {
+            Some(self.confidence)
+        }
+
+        fn filter_metadata(&self, key: &str) -> Option<&str> {
+            self.tags.get(key).map(String::as_str)
+        }
+    }
+
+    #[test]
+    fn test_vector_search_filter_matches() {
+        let item = Item {
+            subject: "user:alice".to_string(),
+            confidence: 0.8,
+            tags: HashMap::from([("source".to_string(), "chat".to_string())]),
+        };
+
+        assert!(VectorSearchFilter::new().is_empty());
+        assert!(VectorSearchFilter::new().matches(&item));
+        assert!(VectorSearchFilter::new()
+            .with_subject_prefix("user:")
+            .with_min_confidence(0.7)
+            .with_metadata("source", "chat")
+            .matches(&item));
+
+        assert!(!VectorSearchFilter::new()
+            .with_subject("user:bob")
+            .matches(&item));
+        assert!(!VectorSearchFilter::new()
+            .with_min_confidence(0.9)
+            .matches(&item));
+        assert!(!VectorSearchFilter::new()
+            .with_metadata("source", "email")
+            .matches(&item));
+        // Item exposes no predicate or timestamp, so constraints on them never match
+        assert!(!VectorSearchFilter::new()
+            .with_predicate("prefers")
+            .matches(&item));
+        assert!(!VectorSearchFilter::new()
+            .with_created_after(chrono::Utc::now())
+            .matches(&item));
+    }
 }
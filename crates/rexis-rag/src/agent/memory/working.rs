@@ -105,6 +105,15 @@ impl WorkingMemory {
         self.storage.mget(&full_keys).await
     }
 
+    /// Clear the working memory and drop it
+    ///
+    /// The explicit way to end a working memory whose auto-clear cannot run
+    /// from `Drop`.
+    pub async fn close(mut self) -> RragResult<()> {
+        self.auto_clear = false;
+        self.clear().await
+    }
+
     /// Get count of items in working memory
     pub async fn count(&self) -> RragResult<usize> {
         self.storage.count(Some(&self.namespace)).await
@@ -16,14 +16,61 @@
 //! ```
 
 mod agent;
+mod approval;
+mod budget;
 mod builder;
+mod cache;
 mod config;
+mod control;
+mod event;
 mod executor;
+mod guardrails;
+mod handoff;
+mod hooks;
 mod legacy_memory;
+#[cfg(feature = "mcp")]
+pub mod mcp; // Model Context Protocol client
 pub mod memory; // New memory system
+mod options;
+mod output_limit;
+mod profile;
+mod prompt;
+mod result;
+mod scratchpad;
+mod stop;
+mod summary;
+mod tool;
+pub mod tools; // Built-in tools
+mod typed;
+mod validation;
 
 pub use agent::Agent;
+pub use approval::{Approval, ApprovalHook, ApprovalRequest, ChannelApprovalHook};
+pub use budget::{BudgetLimit, RunBudget};
 pub use builder::AgentBuilder;
-pub use config::{AgentConfig, ConversationMode};
-pub use executor::ToolExecutor;
+pub use cache::{CachePolicy, ToolCache};
+pub use config::{AgentConfig, ConversationMode, ToolErrorPolicy};
+pub use control::{PartialRun, RunControl};
+pub use event::AgentEvent;
+pub use executor::{ToolExecution, ToolExecutor, ToolFailure, ToolRetryPolicy, ToolRetryPredicate};
+pub use guardrails::{
+    Guardrail, GuardrailDecision, GuardrailRecord, GuardrailStage, MaxLengthGuardrail,
+    RegexDenylistGuardrail,
+};
+pub use handoff::{handoff_trail, AgentRegistry, HandoffRecord, HANDOFF_TAG, HANDOFF_TOOL};
+pub use hooks::{AgentHooks, HookMode, TracingHooks};
 pub use legacy_memory::ConversationMemory; // Keep for backward compatibility
+pub use options::RunOptions;
+pub use output_limit::OutputReduction;
+pub use profile::{LlmProfile, MemoryProfile, Profile};
+pub use prompt::{PromptTemplate, TemplateMode, CURRENT_DATE};
+pub use result::{RunResult, StepUsage, StopReason, ToolInvocation, TypedRunResult};
+pub use scratchpad::RunStep;
+pub use stop::{OnPrefix, OnToolCalled, StopCondition, StopOutcome};
+pub use tool::{SyncTool, Tool, ToolOutput};
+pub use typed::{typed_tool, EnumSchema, ObjectSchema, ToolArgs, TypedTool};
+pub use validation::{validate_arguments, ArgumentViolation};
+
+/// Derive [`ToolArgs`] from a struct or enum
+#[cfg(feature = "macros")]
+pub use rexis_macros::ToolArgs;
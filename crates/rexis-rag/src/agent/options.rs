@@ -0,0 +1,94 @@
+//! Per-run overrides of the agent configuration
+
+use super::RunBudget;
+use rexis_llm::GenerationParams;
+
+/// Settings that apply to a single agent run
+///
+/// Unset fields fall back to the [`AgentConfig`](super::AgentConfig); nothing
+/// here outlives the run it was passed to.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Extra instruction appended to the system prompt for this run
+    ///
+    /// Applied when building each request and never stored in conversation
+    /// history.
+    pub system_prompt_suffix: Option<String>,
+
+    /// Generation parameters; unset fields use the configured ones
+    pub generation: Option<GenerationParams>,
+
+    /// Names of the tools the model may use; `None` allows every registered tool
+    pub allowed_tools: Option<Vec<String>>,
+
+    /// Names of the tools the model may not use, even when allowed
+    pub denied_tools: Vec<String>,
+
+    /// Iteration limit for this run, replacing
+    /// [`AgentConfig::max_iterations`](super::AgentConfig::max_iterations)
+    pub max_iterations_override: Option<usize>,
+
+    /// Spending limits for this run, replacing
+    /// [`AgentConfig::budget`](super::AgentConfig::budget)
+    pub budget: Option<RunBudget>,
+}
+
+impl RunOptions {
+    /// Create options that keep every configured default
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an instruction to the system prompt
+    pub fn with_system_prompt_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.system_prompt_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Set generation parameters
+    pub fn with_generation(mut self, params: GenerationParams) -> Self {
+        self.generation = Some(params);
+        self
+    }
+
+    /// Restrict the run to the named tools
+    pub fn with_allowed_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_tools = Some(tools.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Keep the run from using the named tools
+    pub fn with_denied_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.denied_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Override the iteration limit
+    pub fn with_max_iterations(mut self, max: usize) -> Self {
+        self.max_iterations_override = Some(max);
+        self
+    }
+
+    /// Limit the run's spending
+    pub fn with_budget(mut self, budget: RunBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Whether the model may call `tool` in this run
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        let allowed = self
+            .allowed_tools
+            .as_ref()
+            .map_or(true, |allowed| allowed.iter().any(|name| name == tool));
+        allowed && !self.denied_tools.iter().any(|name| name == tool)
+    }
+}
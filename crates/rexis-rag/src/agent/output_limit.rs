@@ -0,0 +1,150 @@
+//! Limits on the size of tool outputs sent to the model
+//!
+//! An output over its tool's character limit is truncated, keeping its start
+//! and end around a `[truncated N chars]` marker, or summarized by a model with
+//! the original kept in working memory.
+
+use crate::error::{RragError, RragResult};
+use rexis_llm::{ChatMessage, Client};
+use serde::{Deserialize, Serialize};
+
+/// Working memory key prefix of the originals of summarized outputs
+pub(crate) const ORIGINAL_KEY_PREFIX: &str = "tool_output";
+
+/// How an output over its character limit was cut down before it reached the
+/// model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum OutputReduction {
+    /// The start and end were kept around a marker
+    Truncated {
+        /// Characters in the tool's output
+        original_chars: usize,
+
+        /// Characters left out
+        removed_chars: usize,
+    },
+
+    /// A model summarized the output
+    Summarized {
+        /// Characters in the tool's output
+        original_chars: usize,
+
+        /// Working memory key holding the output, named in the summary
+        key: String,
+    },
+}
+
+/// Cut `content` down to `max_chars` characters, keeping its start and end
+///
+/// Returns `None` for content within the limit. The marker between the start
+/// and end does not count toward the limit.
+pub(crate) fn truncate_output(
+    content: &str,
+    max_chars: usize,
+) -> Option<(String, OutputReduction)> {
+    let original_chars = content.chars().count();
+    if original_chars <= max_chars {
+        return None;
+    }
+
+    let removed_chars = original_chars - max_chars;
+    let head_chars = max_chars - max_chars / 2;
+    let head_end = byte_offset(content, head_chars);
+    let tail_start = byte_offset(content, head_chars + removed_chars);
+    let truncated = format!(
+        "{}\n[truncated {} chars]\n{}",
+        &content[..head_end],
+        removed_chars,
+        &content[tail_start..]
+    );
+    Some((
+        truncated,
+        OutputReduction::Truncated {
+            original_chars,
+            removed_chars,
+        },
+    ))
+}
+
+/// Byte offset of the character at `index`, or the end of `text`
+fn byte_offset(text: &str, index: usize) -> usize {
+    text.char_indices()
+        .nth(index)
+        .map_or(text.len(), |(offset, _)| offset)
+}
+
+/// Summarize an output of `tool` with the model, aiming at `max_chars`
+/// characters
+pub(crate) async fn summarize_output(
+    client: &Client,
+    tool: &str,
+    content: &str,
+    max_chars: usize,
+) -> RragResult<String> {
+    let prompt = format!(
+        "Summarize this output of the tool '{}' in at most {} characters. Keep the \
+         facts, figures, names and identifiers a next step may need, and respond \
+         with the summary only.\n\nOutput:\n{}",
+        tool, max_chars, content
+    );
+    let response = client
+        .chat_completion(vec![ChatMessage::user(prompt)])
+        .await
+        .map_err(|e| RragError::rsllm_client("tool_output_summary", e))?;
+    Ok(response.content.trim().to_string())
+}
+
+/// Summary sent in place of an output, naming where the original is kept
+pub(crate) fn summary_content(summary: &str, original_chars: usize, key: &str) -> String {
+    format!(
+        "{}\n[summarized from {} chars; the full output is in working memory under '{}']",
+        summary, original_chars, key
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_within_limit_is_kept() {
+        assert_eq!(truncate_output("0123456789", 10), None);
+        assert_eq!(truncate_output("", 0), None);
+    }
+
+    #[test]
+    fn test_truncation_keeps_head_and_tail() {
+        let (truncated, reduction) = truncate_output("0123456789", 9).unwrap();
+        assert_eq!(truncated, "01234\n[truncated 1 chars]\n6789");
+        assert_eq!(
+            reduction,
+            OutputReduction::Truncated {
+                original_chars: 10,
+                removed_chars: 1,
+            }
+        );
+
+        let (truncated, _) = truncate_output("0123456789", 4).unwrap();
+        assert_eq!(truncated, "01\n[truncated 6 chars]\n89");
+
+        let (truncated, _) = truncate_output("0123456789", 1).unwrap();
+        assert_eq!(truncated, "0\n[truncated 9 chars]\n");
+
+        let (truncated, _) = truncate_output("0123456789", 0).unwrap();
+        assert_eq!(truncated, "\n[truncated 10 chars]\n");
+    }
+
+    #[test]
+    fn test_truncation_counts_characters_not_bytes() {
+        let (truncated, reduction) = truncate_output("héllo wörld", 6).unwrap();
+        assert_eq!(truncated, "hél\n[truncated 5 chars]\nrld");
+        assert_eq!(
+            reduction,
+            OutputReduction::Truncated {
+                original_chars: 11,
+                removed_chars: 5,
+            }
+        );
+    }
+}
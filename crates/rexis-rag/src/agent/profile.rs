@@ -0,0 +1,604 @@
+//! Agent profiles loaded from TOML or JSON
+//!
+//! A profile describes an agent in a file, so prompts, models and limits can
+//! change without a rebuild. Tools are still registered in code; a profile
+//! only lists which of them the agent may use.
+//!
+//! ```toml
+//! name = "support"                 # required; the agent id for memory
+//! system_prompt = "You are a support agent for ${COMPANY}."
+//! mode = "stateful"                # "stateful" or "stateless"
+//! max_iterations = 5
+//! max_conversation_length = 20
+//! temperature = 0.2                # 0.0 to 2.0
+//! max_tokens = 512
+//! tools = ["search_docs", "open_ticket"]
+//!
+//! [llm]                            # optional; otherwise call with_llm()
+//! provider = "openai"
+//! model = "gpt-4o-mini"
+//! api_key = "${OPENAI_API_KEY}"
+//! base_url = "https://api.openai.com/v1"
+//!
+//! [memory]                         # optional; storage is set in code
+//! session_id = "support-main"
+//! persistence = true
+//! semantic = true
+//! episodic = false
+//! working = true
+//! ```
+//!
+//! The same keys make up the JSON form. `${NAME}` in any string is replaced
+//! by the environment variable `NAME`. Problems are reported as
+//! [`RragError::Validation`] errors whose field is the path of the offending
+//! key, such as `llm.provider`.
+
+use super::{AgentBuilder, ConversationMode};
+use crate::error::{RragError, RragResult};
+use rexis_llm::{Client, GenerationParams, Provider};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Agent settings read from a profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    /// Profile name, used as the agent id when memory is configured
+    pub name: String,
+
+    /// System prompt
+    pub system_prompt: Option<String>,
+
+    /// Conversation mode
+    pub mode: Option<ConversationMode>,
+
+    /// Maximum iterations of a run
+    pub max_iterations: Option<usize>,
+
+    /// Maximum conversation history length
+    pub max_conversation_length: Option<usize>,
+
+    /// Sampling temperature for LLM steps
+    pub temperature: Option<f32>,
+
+    /// Maximum tokens of each model reply
+    pub max_tokens: Option<u32>,
+
+    /// Registered tools the agent may use; all of them when unset
+    pub tools: Option<Vec<String>>,
+
+    /// LLM client to build
+    pub llm: Option<LlmProfile>,
+
+    /// Memory settings
+    pub memory: Option<MemoryProfile>,
+}
+
+/// `[llm]` section of a profile
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmProfile {
+    /// Provider, such as `openai` or `ollama`
+    pub provider: Provider,
+
+    /// Model name
+    pub model: Option<String>,
+
+    /// API key
+    pub api_key: Option<String>,
+
+    /// Base URL of the provider's API
+    pub base_url: Option<String>,
+}
+
+/// `[memory]` section of a profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryProfile {
+    /// Memory session, instead of a generated one
+    pub session_id: Option<String>,
+
+    /// Persist the conversation in the memory storage
+    pub persistence: bool,
+
+    /// Enable semantic memory
+    pub semantic: bool,
+
+    /// Enable episodic memory
+    pub episodic: bool,
+
+    /// Enable working memory
+    pub working: bool,
+}
+
+impl Profile {
+    /// Load a profile from a file path or from profile text
+    ///
+    /// `path_or_str` is read as a file when it names one, or when it is a
+    /// single line ending in `.toml` or `.json`.
+    pub fn load(path_or_str: impl AsRef<str>) -> RragResult<Self> {
+        let source = path_or_str.as_ref();
+        let path = Path::new(source.trim());
+        let named_file = !source.contains('\n')
+            && path
+                .extension()
+                .is_some_and(|ext| ext == "toml" || ext == "json");
+        if named_file || path.is_file() {
+            Self::from_file(path)
+        } else {
+            source.parse()
+        }
+    }
+
+    /// Load a profile from a file; `.json` files are read as JSON and any
+    /// other file as TOML
+    pub fn from_file(path: impl AsRef<Path>) -> RragResult<Self> {
+        let path = path.as_ref();
+        let text =
+            std::fs::read_to_string(path).map_err(|e| RragError::storage("read_profile", e))?;
+        let document = if path.extension().is_some_and(|ext| ext == "json") {
+            parse_json(&text)?
+        } else {
+            parse_toml(&text)?
+        };
+        Self::from_document(document)
+    }
+
+    /// Build an agent builder from the profile
+    ///
+    /// The builder can be changed further before it is built: tools listed by
+    /// the profile must be added to it, and so must the LLM client and memory
+    /// storage when the profile does not set them.
+    pub fn to_builder(&self) -> RragResult<AgentBuilder> {
+        let mut builder = AgentBuilder::new();
+        if let Some(llm) = &self.llm {
+            builder = builder.with_llm(llm.client()?);
+        }
+        if let Some(prompt) = &self.system_prompt {
+            builder = builder.with_system_prompt(prompt.as_str());
+        }
+        if let Some(mode) = self.mode {
+            builder = builder.with_conversation_mode(mode);
+        }
+        if let Some(max) = self.max_iterations {
+            builder = builder.with_max_iterations(max);
+        }
+        if let Some(length) = self.max_conversation_length {
+            builder = builder.with_max_conversation_length(length);
+        }
+        if self.temperature.is_some() || self.max_tokens.is_some() {
+            let mut params = GenerationParams::new();
+            params.temperature = self.temperature;
+            params.max_tokens = self.max_tokens;
+            builder = builder.with_generation_params(params);
+        }
+        if let Some(tools) = &self.tools {
+            builder = builder.with_tool_allowlist(tools.iter().cloned());
+        }
+        if let Some(memory) = &self.memory {
+            builder = builder.with_agent_id(self.name.as_str());
+            if let Some(session_id) = &memory.session_id {
+                builder = builder.with_session_id(session_id.as_str());
+            }
+            if memory.persistence {
+                builder = builder.with_persistence();
+            }
+            if memory.semantic {
+                builder = builder.with_semantic_memory();
+            }
+            if memory.episodic {
+                builder = builder.with_episodic_memory();
+            }
+            if memory.working {
+                builder = builder.with_working_memory();
+            }
+        }
+        Ok(builder)
+    }
+
+    fn from_document(mut document: Value) -> RragResult<Self> {
+        interpolate_env(&mut document, "")?;
+        let mut table = Table::new(document, "")?;
+
+        let name: String = table
+            .take("name")?
+            .ok_or_else(|| RragError::validation("name", "is required", "none"))?;
+        if name.trim().is_empty() {
+            return Err(RragError::validation("name", "must not be empty", name));
+        }
+
+        let mode = match table.take::<String>("mode")? {
+            Some(mode) => Some(match mode.to_lowercase().as_str() {
+                "stateful" => ConversationMode::Stateful,
+                "stateless" => ConversationMode::Stateless,
+                _ => {
+                    return Err(RragError::validation(
+                        "mode",
+                        "must be \"stateful\" or \"stateless\"",
+                        mode,
+                    ))
+                }
+            }),
+            None => None,
+        };
+
+        let max_iterations = table.take("max_iterations")?;
+        if max_iterations == Some(0) {
+            return Err(RragError::validation(
+                "max_iterations",
+                "must be at least 1",
+                "0",
+            ));
+        }
+
+        let temperature: Option<f32> = table.take("temperature")?;
+        if let Some(temperature) = temperature.filter(|t| !(0.0..=2.0).contains(t)) {
+            return Err(RragError::validation(
+                "temperature",
+                "must be between 0.0 and 2.0",
+                temperature.to_string(),
+            ));
+        }
+
+        let tools: Option<Vec<String>> = table.take("tools")?;
+        let mut seen = HashSet::new();
+        if let Some(tool) = tools.iter().flatten().find(|tool| !seen.insert(*tool)) {
+            return Err(RragError::validation(
+                "tools",
+                "must not list a tool twice",
+                tool.as_str(),
+            ));
+        }
+
+        let profile = Self {
+            name,
+            system_prompt: table.take("system_prompt")?,
+            mode,
+            max_iterations,
+            max_conversation_length: table.take("max_conversation_length")?,
+            temperature,
+            max_tokens: table.take("max_tokens")?,
+            tools,
+            llm: table
+                .take_table("llm")?
+                .map(LlmProfile::parse)
+                .transpose()?,
+            memory: table
+                .take_table("memory")?
+                .map(MemoryProfile::parse)
+                .transpose()?,
+        };
+        table.finish()?;
+        Ok(profile)
+    }
+}
+
+impl FromStr for Profile {
+    type Err = RragError;
+
+    /// Parse profile text; text starting with `{` is read as JSON and any
+    /// other text as TOML
+    fn from_str(source: &str) -> RragResult<Self> {
+        let document = if source.trim_start().starts_with('{') {
+            parse_json(source)?
+        } else {
+            parse_toml(source)?
+        };
+        Self::from_document(document)
+    }
+}
+
+impl LlmProfile {
+    /// Build the client the section describes
+    pub fn client(&self) -> RragResult<Client> {
+        let mut builder = Client::builder().provider(self.provider);
+        if let Some(model) = &self.model {
+            builder = builder.model(model.as_str());
+        }
+        if let Some(api_key) = &self.api_key {
+            builder = builder.api_key(api_key.as_str());
+        }
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url)?;
+        }
+        Ok(builder.build()?)
+    }
+
+    fn parse(mut table: Table) -> RragResult<Self> {
+        let field = table.field("provider");
+        let provider: String = table
+            .take("provider")?
+            .ok_or_else(|| RragError::validation(field.as_str(), "is required", "none"))?;
+        let provider = Provider::from_str(&provider)
+            .map_err(|_| RragError::validation(field, "must name a known provider", provider))?;
+
+        let llm = Self {
+            provider,
+            model: table.take("model")?,
+            api_key: table.take("api_key")?,
+            base_url: table.take("base_url")?,
+        };
+        table.finish()?;
+        Ok(llm)
+    }
+}
+
+impl MemoryProfile {
+    fn parse(mut table: Table) -> RragResult<Self> {
+        let memory = Self {
+            session_id: table.take("session_id")?,
+            persistence: table.take("persistence")?.unwrap_or_default(),
+            semantic: table.take("semantic")?.unwrap_or_default(),
+            episodic: table.take("episodic")?.unwrap_or_default(),
+            working: table.take("working")?.unwrap_or_default(),
+        };
+        table.finish()?;
+        Ok(memory)
+    }
+}
+
+/// Keys of a profile table, taken one by one so errors can name their path
+struct Table {
+    entries: Map<String, Value>,
+    path: String,
+}
+
+impl Table {
+    fn new(value: Value, path: &str) -> RragResult<Self> {
+        match value {
+            Value::Object(entries) => Ok(Self {
+                entries,
+                path: path.to_string(),
+            }),
+            other => Err(RragError::validation(
+                if path.is_empty() { "profile" } else { path },
+                "must be a table",
+                other.to_string(),
+            )),
+        }
+    }
+
+    fn field(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+
+    fn take<T: DeserializeOwned>(&mut self, key: &str) -> RragResult<Option<T>> {
+        match self.entries.remove(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| {
+                    RragError::validation(self.field(key), e.to_string(), value.to_string())
+                }),
+        }
+    }
+
+    fn take_table(&mut self, key: &str) -> RragResult<Option<Table>> {
+        match self.entries.remove(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => Table::new(value, &self.field(key)).map(Some),
+        }
+    }
+
+    /// Fail on the first key that was not taken
+    fn finish(self) -> RragResult<()> {
+        match self.entries.iter().next() {
+            Some((key, value)) => Err(RragError::validation(
+                self.field(key),
+                "is not a profile setting",
+                value.to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_toml(text: &str) -> RragResult<Value> {
+    toml::from_str(text)
+        .map_err(|e| RragError::validation("profile", format!("must be valid TOML: {}", e), "toml"))
+}
+
+fn parse_json(text: &str) -> RragResult<Value> {
+    serde_json::from_str(text)
+        .map_err(|e| RragError::validation("profile", format!("must be valid JSON: {}", e), "json"))
+}
+
+/// Replace `${NAME}` in every string of `value` with the environment variable
+fn interpolate_env(value: &mut Value, path: &str) -> RragResult<()> {
+    let field = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        Value::String(text) if text.contains("${") => {
+            *text = expand(text, path)?;
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                interpolate_env(item, &format!("{}[{}]", path, index))?;
+            }
+        }
+        Value::Object(entries) => {
+            for (key, item) in entries.iter_mut() {
+                interpolate_env(item, &field(key))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand(text: &str, field: &str) -> RragResult<String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(RragError::validation(field, "has an unterminated ${", text));
+        };
+        let name = &rest[start + 2..start + end];
+        let value = std::env::var(name).map_err(|_| {
+            RragError::validation(
+                field,
+                format!("uses environment variable {} which is not set", name),
+                text,
+            )
+        })?;
+        expanded.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+    use rexis_llm::testing::{respond_text, MockClient};
+    use std::sync::Arc;
+
+    fn field_of(err: RragError) -> String {
+        match err {
+            RragError::Validation { field, .. } => field,
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_toml_and_json_profiles_parse_alike() {
+        let toml = r#"
+            name = "support"
+            mode = "stateful"
+            temperature = 0.5
+            tools = ["search"]
+
+            [memory]
+            semantic = true
+        "#;
+        let json = r#"{
+            "name": "support",
+            "mode": "stateful",
+            "temperature": 0.5,
+            "tools": ["search"],
+            "memory": {"semantic": true}
+        }"#;
+
+        let profile: Profile = toml.parse().unwrap();
+        assert_eq!(profile, json.parse().unwrap());
+        assert_eq!(profile.mode, Some(ConversationMode::Stateful));
+        assert!(profile.memory.unwrap().semantic);
+    }
+
+    #[test]
+    fn test_profile_builds_the_hand_built_config() {
+        let mock = MockClient::builder().otherwise(respond_text("ok")).build();
+        let storage = Arc::new(InMemoryStorage::new());
+        let profile = r#"
+            name = "support"
+            system_prompt = "You answer support questions."
+            mode = "stateful"
+            max_iterations = 4
+            max_conversation_length = 12
+            temperature = 0.3
+            max_tokens = 256
+
+            [memory]
+            session_id = "s1"
+            persistence = true
+            semantic = true
+        "#;
+
+        let from_profile = AgentBuilder::from_profile(profile)
+            .unwrap()
+            .with_llm(mock.client())
+            .with_storage(storage.clone())
+            .build()
+            .unwrap();
+        let by_hand = AgentBuilder::new()
+            .with_llm(mock.client())
+            .with_system_prompt("You answer support questions.")
+            .stateful()
+            .with_max_iterations(4)
+            .with_max_conversation_length(12)
+            .with_generation_params(
+                GenerationParams::new()
+                    .with_temperature(0.3)
+                    .with_max_tokens(256),
+            )
+            .with_storage(storage)
+            .with_agent_id("support")
+            .with_session_id("s1")
+            .with_persistence()
+            .with_semantic_memory()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(from_profile.config()).unwrap(),
+            serde_json::to_value(by_hand.config()).unwrap()
+        );
+        assert_eq!(from_profile.agent_id(), by_hand.agent_id());
+        let (ours, theirs) = (from_profile.memory().unwrap(), by_hand.memory().unwrap());
+        assert_eq!(ours.session_id(), theirs.session_id());
+        let (ours, theirs) = (ours.config(), theirs.config());
+        assert_eq!(ours.persist_conversations, theirs.persist_conversations);
+        assert_eq!(ours.enable_semantic, theirs.enable_semantic);
+        assert_eq!(ours.enable_episodic, theirs.enable_episodic);
+        assert_eq!(ours.enable_working, theirs.enable_working);
+        assert_eq!(ours.max_conversation_length, theirs.max_conversation_length);
+    }
+
+    #[test]
+    fn test_errors_name_the_offending_field() {
+        let cases = [
+            ("temperature = 0.5", "name"),
+            ("name = \"a\"\ntemperature = \"hot\"", "temperature"),
+            ("name = \"a\"\ntemperature = 3.0", "temperature"),
+            ("name = \"a\"\nmode = \"chatty\"", "mode"),
+            ("name = \"a\"\ntools = [\"x\", \"x\"]", "tools"),
+            ("name = \"a\"\n[memory]\nsemantc = true", "memory.semantc"),
+            ("name = \"a\"\n[llm]\nprovider = \"nope\"", "llm.provider"),
+            ("name = \"a\"\n[llm]\nmodel = \"m\"", "llm.provider"),
+            ("name = \"a\"\nmemory = 1", "memory"),
+            ("name = ", "profile"),
+        ];
+        for (source, field) in cases {
+            let err = source.parse::<Profile>().err().unwrap();
+            assert_eq!(field_of(err), field, "for {:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_environment_variables_interpolated() {
+        std::env::set_var("REXIS_PROFILE_TEST_COMPANY", "Acme");
+        let profile: Profile = r#"
+            name = "support"
+            system_prompt = "You help ${REXIS_PROFILE_TEST_COMPANY} customers."
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            profile.system_prompt.as_deref(),
+            Some("You help Acme customers.")
+        );
+
+        let err = r#"
+            name = "support"
+            [llm]
+            provider = "openai"
+            api_key = "${REXIS_PROFILE_TEST_UNSET}"
+        "#
+        .parse::<Profile>()
+        .err()
+        .unwrap();
+        assert_eq!(field_of(err), "llm.api_key");
+    }
+}
@@ -0,0 +1,362 @@
+//! System prompt templates
+//!
+//! A [`PromptTemplate`] fills `{name}` placeholders from registered resolvers
+//! each time a run starts, so values drawn from memory stay current. Literal
+//! braces are written `{{` and `}}`; braces that do not enclose a variable name,
+//! as in JSON examples, are kept as they are.
+
+use super::memory::{AgentMemoryManager, EpisodicMemory, Fact, SemanticMemory};
+use crate::error::{RragError, RragResult};
+use crate::storage::MemoryValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Variable that renders today's date unless a resolver is registered for it
+pub const CURRENT_DATE: &str = "current_date";
+
+/// How variables without a value are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TemplateMode {
+    /// Fail the render
+    #[default]
+    Strict,
+
+    /// Render the variable as an empty string
+    Lenient,
+}
+
+/// Source of a variable's value
+#[derive(Clone)]
+enum Resolver {
+    /// Fixed text
+    Value(String),
+
+    /// Text computed at each render
+    Function(Arc<dyn Fn() -> String + Send + Sync>),
+
+    /// Facts about a subject from semantic memory
+    Facts { subject: String },
+
+    /// Summary of the latest episodes from episodic memory
+    RecentEpisodes { count: usize },
+}
+
+/// Piece of a parsed template
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// Template for an agent's system prompt
+#[derive(Clone)]
+pub struct PromptTemplate {
+    source: String,
+    resolvers: HashMap<String, Resolver>,
+    mode: TemplateMode,
+}
+
+impl PromptTemplate {
+    /// Create a template from text with `{name}` placeholders
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            resolvers: HashMap::new(),
+            mode: TemplateMode::default(),
+        }
+    }
+
+    /// Fill `name` with fixed text
+    pub fn with_value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.resolvers
+            .insert(name.into(), Resolver::Value(value.into()));
+        self
+    }
+
+    /// Fill `name` with the result of `resolver`, called at each render
+    pub fn with_resolver<F>(mut self, name: impl Into<String>, resolver: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.resolvers
+            .insert(name.into(), Resolver::Function(Arc::new(resolver)));
+        self
+    }
+
+    /// Fill `name` with the agent's facts about `subject`, one per line
+    ///
+    /// Facts are read through the agent's memory manager; without one the
+    /// variable has no value.
+    pub fn with_facts(mut self, name: impl Into<String>, subject: impl Into<String>) -> Self {
+        self.resolvers.insert(
+            name.into(),
+            Resolver::Facts {
+                subject: subject.into(),
+            },
+        );
+        self
+    }
+
+    /// Fill `name` with a summary of the agent's `count` latest episodes
+    ///
+    /// Episodes are read through the agent's memory manager; without one the
+    /// variable has no value.
+    pub fn with_recent_episodes(mut self, name: impl Into<String>, count: usize) -> Self {
+        self.resolvers
+            .insert(name.into(), Resolver::RecentEpisodes { count });
+        self
+    }
+
+    /// Set how variables without a value are rendered
+    pub fn with_mode(mut self, mode: TemplateMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Template text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Names of the variables used, in order of first use
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for segment in self.segments() {
+            if let Segment::Variable(name) = segment {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Render the template, reading memory variables through `memory`
+    ///
+    /// Each variable is resolved once per render. In strict mode a variable
+    /// without a value fails with [`RragError::Validation`].
+    pub async fn render(&self, memory: Option<&AgentMemoryManager>) -> RragResult<String> {
+        let mut values: HashMap<&str, String> = HashMap::new();
+        let mut rendered = String::with_capacity(self.source.len());
+        for segment in self.segments() {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Variable(name) => {
+                    if !values.contains_key(name) {
+                        let value = self.resolve(name, memory).await?;
+                        values.insert(name, value);
+                    }
+                    rendered.push_str(&values[name]);
+                }
+            }
+        }
+        Ok(rendered)
+    }
+
+    async fn resolve(&self, name: &str, memory: Option<&AgentMemoryManager>) -> RragResult<String> {
+        let value = match (self.resolvers.get(name), memory) {
+            (Some(Resolver::Value(value)), _) => Some(value.clone()),
+            (Some(Resolver::Function(resolver)), _) => Some(resolver()),
+            (Some(Resolver::Facts { subject }), Some(memory)) => {
+                let semantic = SemanticMemory::new(memory.storage(), memory.agent_id().to_string());
+                Some(render_facts(semantic.find_by_subject(subject).await?))
+            }
+            (Some(Resolver::RecentEpisodes { count }), Some(memory)) => {
+                let episodic = EpisodicMemory::new(memory.storage(), memory.agent_id().to_string());
+                Some(episodic.generate_context_summary(*count).await?)
+            }
+            (None, _) if name == CURRENT_DATE => {
+                Some(chrono::Utc::now().format("%Y-%m-%d").to_string())
+            }
+            _ => None,
+        };
+
+        match (value, self.mode) {
+            (Some(value), _) => Ok(value),
+            (None, TemplateMode::Lenient) => {
+                debug!(variable = %name, "Prompt variable has no value; rendering it empty");
+                Ok(String::new())
+            }
+            (None, TemplateMode::Strict) => Err(RragError::validation(
+                format!("prompt variable '{}'", name),
+                "variable must have a value",
+                name,
+            )),
+        }
+    }
+
+    fn segments(&self) -> Vec<Segment<'_>> {
+        let mut segments = Vec::new();
+        let mut rest = self.source.as_str();
+        while let Some(start) = rest.find(['{', '}']) {
+            segments.push(Segment::Text(&rest[..start]));
+            let tail = &rest[start..];
+
+            // Doubled braces are escapes; a lone `}` is literal
+            if tail.starts_with("{{") || tail.starts_with("}}") || tail.starts_with('}') {
+                segments.push(Segment::Text(&tail[..1]));
+                let skip = if tail[1..].starts_with(&tail[..1]) {
+                    2
+                } else {
+                    1
+                };
+                rest = &tail[skip..];
+                continue;
+            }
+
+            let name = tail[1..].find('}').map(|end| &tail[1..=end]);
+            match name {
+                Some(name) if is_variable_name(name) => {
+                    segments.push(Segment::Variable(name));
+                    rest = &tail[name.len() + 2..];
+                }
+                _ => {
+                    segments.push(Segment::Text("{"));
+                    rest = &tail[1..];
+                }
+            }
+        }
+        segments.push(Segment::Text(rest));
+        segments
+    }
+}
+
+impl From<&str> for PromptTemplate {
+    fn from(source: &str) -> Self {
+        Self::new(source)
+    }
+}
+
+impl From<String> for PromptTemplate {
+    fn from(source: String) -> Self {
+        Self::new(source)
+    }
+}
+
+impl std::fmt::Debug for PromptTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut variables: Vec<_> = self.resolvers.keys().collect();
+        variables.sort();
+        f.debug_struct("PromptTemplate")
+            .field("source", &self.source)
+            .field("resolvers", &variables)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// One `- predicate: object` line per fact, ordered by predicate
+fn render_facts(mut facts: Vec<Fact>) -> String {
+    facts.sort_by(|a, b| {
+        a.predicate
+            .cmp(&b.predicate)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    facts
+        .iter()
+        .map(|fact| format!("- {}: {}", fact.predicate, value_text(&fact.object)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A memory value as plain text
+pub(crate) fn value_text(value: &MemoryValue) -> String {
+    match value {
+        MemoryValue::String(text) => text.clone(),
+        MemoryValue::Integer(number) => number.to_string(),
+        MemoryValue::Float(number) => number.to_string(),
+        MemoryValue::Boolean(flag) => flag.to_string(),
+        MemoryValue::Json(json) => json.to_string(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::{Episode, MemoryConfig};
+    use crate::storage::InMemoryStorage;
+
+    async fn seeded_memory() -> AgentMemoryManager {
+        let config = MemoryConfig::new(Arc::new(InMemoryStorage::new()), "concierge");
+        let mut memory = AgentMemoryManager::new(config);
+        let semantic = memory.semantic();
+        semantic
+            .store_fact(Fact::new("user:alice", "prefers", "window seats"))
+            .await
+            .unwrap();
+        semantic
+            .store_fact(Fact::new("user:alice", "home_city", "Lyon"))
+            .await
+            .unwrap();
+        semantic
+            .store_fact(Fact::new("user:bob", "prefers", "aisle seats"))
+            .await
+            .unwrap();
+        memory
+            .episodic()
+            .store_episode(Episode::new("Booked a train to Paris"))
+            .await
+            .unwrap();
+        memory
+    }
+
+    #[tokio::test]
+    async fn test_render_with_memory_variables() {
+        let memory = seeded_memory().await;
+        let template = PromptTemplate::new(
+            "You are {agent_name} ({mood}).\nKnown facts:\n{user_facts}\n\n{recent_episodes}",
+        )
+        .with_value("agent_name", "Concierge")
+        .with_resolver("mood", || "cheerful".to_string())
+        .with_facts("user_facts", "user:alice")
+        .with_recent_episodes("recent_episodes", 3);
+
+        let rendered = template.render(Some(&memory)).await.unwrap();
+
+        assert!(rendered.starts_with(
+            "You are Concierge (cheerful).\nKnown facts:\n\
+             - home_city: Lyon\n- prefers: window seats\n\n"
+        ));
+        assert!(rendered.contains("Booked a train to Paris"));
+        assert!(!rendered.contains("aisle"));
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_rejects_missing_variable() {
+        let template = PromptTemplate::new("Hello {agent_name}, facts: {user_facts}")
+            .with_value("agent_name", "Concierge")
+            .with_facts("user_facts", "user:alice");
+
+        // Memory variables have no value without a memory manager
+        let err = template.render(None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::Validation { ref value, .. } if value == "user_facts"
+        ));
+
+        let lenient = template.with_mode(TemplateMode::Lenient);
+        assert_eq!(
+            lenient.render(None).await.unwrap(),
+            "Hello Concierge, facts: "
+        );
+    }
+
+    #[tokio::test]
+    async fn test_literal_braces_are_kept() {
+        let template =
+            PromptTemplate::new(r#"Reply as {"answer": "..."} on {{day}} {current_date}"#);
+
+        assert_eq!(template.variables(), vec![CURRENT_DATE]);
+        let rendered = template.render(None).await.unwrap();
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(
+            rendered,
+            format!(r#"Reply as {{"answer": "..."}} on {{day}} {}"#, today)
+        );
+    }
+}
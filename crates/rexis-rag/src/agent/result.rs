@@ -0,0 +1,152 @@
+//! Detailed outcome of an agent run
+
+use super::{BudgetLimit, GuardrailRecord, OutputReduction, ToolFailure};
+use rexis_llm::{Usage, UsageTotals};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Why a run stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The model answered without requesting tools
+    FinalAnswer,
+    /// The run used up its iterations
+    MaxIterations,
+    /// The run reached a limit of its [`RunBudget`](super::RunBudget)
+    BudgetExceeded,
+    /// A [`StopCondition`](super::StopCondition) ended the run
+    StopCondition,
+}
+
+/// A tool call made during a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    /// Tool name
+    pub name: String,
+
+    /// Arguments the model supplied
+    pub args: serde_json::Value,
+
+    /// Result as sent back to the model
+    pub result: String,
+
+    /// Time spent executing the tool, retries included
+    pub duration: Duration,
+
+    /// Attempts made; zero when the call was refused, denied or cached
+    #[serde(default)]
+    pub attempts: u32,
+
+    /// Whether the result came from the tool cache instead of executing the tool
+    #[serde(default)]
+    pub cached: bool,
+
+    /// How the last attempt failed, [`ToolFailure::NotAllowed`] for a call
+    /// the run blocked, or [`ToolFailure::InvalidArguments`] for a call with
+    /// arguments that do not match the schema; `None` when the tool succeeded,
+    /// was cached or was denied approval
+    #[serde(default)]
+    pub failure: Option<ToolFailure>,
+
+    /// How the result was cut down to the tool's output limit, if it was
+    #[serde(default)]
+    pub output_reduction: Option<OutputReduction>,
+}
+
+impl ToolInvocation {
+    /// Whether the tool failed and the model got an error as the result
+    pub fn is_error(&self) -> bool {
+        self.failure.is_some()
+    }
+}
+
+/// Token usage of a single LLM step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepUsage {
+    /// Iteration the step belongs to (1-based)
+    pub iteration: usize,
+
+    /// Model that answered
+    pub model: String,
+
+    /// Usage reported by the provider, if any
+    pub usage: Option<Usage>,
+}
+
+/// Outcome of [`Agent::run_detailed`](super::Agent::run_detailed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    /// Id of the run, for [`Agent::get_run_trace`](super::Agent::get_run_trace)
+    #[serde(default)]
+    pub run_id: String,
+
+    /// Final answer, or the last model text when the run used up its iterations
+    pub output: String,
+
+    /// LLM steps taken
+    pub iterations: usize,
+
+    /// Tool calls in the order they ran
+    pub tool_invocations: Vec<ToolInvocation>,
+
+    /// Names of the tools offered to the model, after the run's allow and
+    /// deny lists
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// Usage per LLM step
+    pub steps: Vec<StepUsage>,
+
+    /// Usage and estimated cost summed over all steps
+    pub usage: UsageTotals,
+
+    /// Wall-clock time of the run
+    pub duration: Duration,
+
+    /// Memory session the run used, if the agent has persistent memory
+    pub session_id: Option<String>,
+
+    /// Why the run stopped
+    pub stop_reason: StopReason,
+
+    /// Times the model was asked to repair a final answer that did not match
+    /// the output schema of a typed run
+    #[serde(default)]
+    pub output_repairs: u32,
+
+    /// Facts stored in semantic memory by automatic extraction after the run
+    #[serde(default)]
+    pub facts_extracted: usize,
+
+    /// Decisions of the agent's guardrails on the input and final answer, in
+    /// the order they were taken
+    #[serde(default)]
+    pub guardrails: Vec<GuardrailRecord>,
+
+    /// Budget limit that stopped the run, for
+    /// [`StopReason::BudgetExceeded`]
+    #[serde(default)]
+    pub budget_limit: Option<BudgetLimit>,
+
+    /// Tool calls refused because their arguments did not match the tool's
+    /// parameters schema
+    #[serde(default)]
+    pub invalid_tool_calls: u32,
+}
+
+/// Outcome of [`Agent::run_typed`](super::Agent::run_typed)
+#[derive(Debug, Clone)]
+pub struct TypedRunResult<T> {
+    /// Final answer parsed into the requested type
+    pub value: T,
+
+    /// Final answer as the model wrote it
+    pub raw: String,
+
+    /// Repairs needed before the answer parsed
+    pub repairs: u32,
+
+    /// Details of the run
+    pub run: RunResult,
+}
@@ -0,0 +1,179 @@
+//! Traces of agent runs kept in working memory
+//!
+//! With [`AgentConfig::record_scratchpad`](super::AgentConfig::record_scratchpad)
+//! set, each model response and tool result of a run is written to the session's
+//! working memory under `run::{run_id}::step::{n}`, so a failed run can be
+//! inspected after the fact through
+//! [`Agent::get_run_trace`](super::Agent::get_run_trace).
+
+use crate::error::RragResult;
+use crate::storage::{Memory, MemoryValue};
+use rexis_llm::ToolCall;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+
+/// One recorded step of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RunStep {
+    /// What the model answered in an iteration
+    LlmResponse {
+        /// Iteration of the response (1-based)
+        iteration: usize,
+
+        /// Text of the response, interim or final
+        content: String,
+
+        /// Tool calls the model requested
+        tool_calls: Vec<ToolCall>,
+    },
+
+    /// Result of a tool call
+    ToolResult {
+        /// Iteration the call was made in (1-based)
+        iteration: usize,
+
+        /// Tool name
+        tool: String,
+
+        /// Id of the call the result answers
+        call_id: String,
+
+        /// Arguments the tool ran with
+        args: serde_json::Value,
+
+        /// Result as sent back to the model
+        result: String,
+    },
+}
+
+/// Run traces in the working memory of a session
+pub(crate) struct Scratchpad {
+    storage: Arc<dyn Memory>,
+    namespace: String,
+}
+
+impl Scratchpad {
+    pub(crate) fn for_session(storage: Arc<dyn Memory>, session_id: &str) -> Self {
+        Self {
+            storage,
+            namespace: format!("session::{}::working", session_id),
+        }
+    }
+
+    /// Start the trace of a run, dropping the oldest traces beyond `keep_runs`
+    pub(crate) async fn start_run(
+        self,
+        run_id: &str,
+        keep_runs: usize,
+    ) -> RragResult<RunTraceWriter> {
+        let index_key = format!("{}::runs", self.namespace);
+        let mut runs: Vec<String> = match self.storage.get(&index_key).await? {
+            Some(MemoryValue::Json(json)) => serde_json::from_value(json).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        runs.push(run_id.to_string());
+
+        let excess = runs.len().saturating_sub(keep_runs.max(1));
+        for old in runs.drain(..excess) {
+            self.storage.clear(Some(&self.run_namespace(&old))).await?;
+        }
+        self.storage
+            .set(&index_key, MemoryValue::Json(serde_json::to_value(&runs)?))
+            .await?;
+
+        Ok(RunTraceWriter {
+            scratchpad: self,
+            run_id: run_id.to_string(),
+            next_step: 0,
+        })
+    }
+
+    /// Steps recorded for a run, in order; empty when the trace is gone
+    pub(crate) async fn trace(&self, run_id: &str) -> RragResult<Vec<RunStep>> {
+        let mut steps = Vec::new();
+        while let Some(value) = self
+            .storage
+            .get(&self.step_key(run_id, steps.len()))
+            .await?
+        {
+            let Some(json) = value.as_json() else {
+                break;
+            };
+            steps.push(serde_json::from_value(json.clone())?);
+        }
+        Ok(steps)
+    }
+
+    fn run_namespace(&self, run_id: &str) -> String {
+        format!("{}::run::{}", self.namespace, run_id)
+    }
+
+    fn step_key(&self, run_id: &str, step: usize) -> String {
+        format!("{}::step::{}", self.run_namespace(run_id), step)
+    }
+}
+
+/// Appends the steps of one run to its trace
+pub(crate) struct RunTraceWriter {
+    scratchpad: Scratchpad,
+    run_id: String,
+    next_step: usize,
+}
+
+impl RunTraceWriter {
+    /// Record the next step; failures are logged rather than failing the run
+    pub(crate) async fn record(&mut self, step: RunStep) {
+        let key = self.scratchpad.step_key(&self.run_id, self.next_step);
+        let written = match serde_json::to_value(&step) {
+            Ok(json) => {
+                self.scratchpad
+                    .storage
+                    .set(&key, MemoryValue::Json(json))
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        match written {
+            Ok(()) => self.next_step += 1,
+            Err(e) => warn!(run_id = %self.run_id, error = %e, "Failed to record run step"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+
+    fn response(iteration: usize) -> RunStep {
+        RunStep::LlmResponse {
+            iteration,
+            content: format!("step {}", iteration),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oldest_traces_are_dropped() {
+        let storage: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        for run_id in ["a", "b", "c"] {
+            let mut writer = Scratchpad::for_session(storage.clone(), "s")
+                .start_run(run_id, 2)
+                .await
+                .unwrap();
+            writer.record(response(1)).await;
+            writer.record(response(2)).await;
+        }
+
+        let scratchpad = Scratchpad::for_session(storage, "s");
+        assert!(scratchpad.trace("a").await.unwrap().is_empty());
+        assert_eq!(scratchpad.trace("b").await.unwrap().len(), 2);
+        let trace = scratchpad.trace("c").await.unwrap();
+        assert!(matches!(
+            &trace[1],
+            RunStep::LlmResponse { iteration: 2, content, .. } if content == "step 2"
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Custom stop conditions for the agent loop
+//!
+//! After each iteration of a run, once its tool calls have run, the stop
+//! conditions of [`AgentConfig::stop_conditions`](super::AgentConfig::stop_conditions)
+//! are asked in order whether the run is done. The first one that stops the run
+//! decides its output; when none does, the run goes on as usual and ends on the
+//! first response without tool calls. Streaming runs do not check them.
+
+use super::ToolInvocation;
+use rexis_llm::ChatResponse;
+use std::fmt;
+
+/// How a stop condition ends a run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopOutcome {
+    /// Output of the run; `None` uses the text of the last response
+    pub answer: Option<String>,
+}
+
+impl StopOutcome {
+    /// Stop with the text of the last response as the output
+    pub fn response() -> Self {
+        Self::default()
+    }
+
+    /// Stop with `answer` as the output
+    pub fn answer(answer: impl Into<String>) -> Self {
+        Self {
+            answer: Some(answer.into()),
+        }
+    }
+}
+
+/// Decides whether an agent run is done
+pub trait StopCondition: fmt::Debug + Send + Sync {
+    /// Check an iteration (1-based) given the model's response and the tool
+    /// calls that ran for it
+    fn should_stop(
+        &self,
+        iteration: usize,
+        response: &ChatResponse,
+        invocations: &[ToolInvocation],
+    ) -> Option<StopOutcome>;
+}
+
+/// Stops the run once the named tool ran successfully, with its argument as
+/// the answer
+///
+/// A string argument is the answer as it is; an object with a single field
+/// gives that field, and any other argument is used as JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnToolCalled(pub String);
+
+impl OnToolCalled {
+    /// Stop once `tool` is called
+    pub fn new(tool: impl Into<String>) -> Self {
+        Self(tool.into())
+    }
+}
+
+impl StopCondition for OnToolCalled {
+    fn should_stop(
+        &self,
+        _iteration: usize,
+        _response: &ChatResponse,
+        invocations: &[ToolInvocation],
+    ) -> Option<StopOutcome> {
+        let invocation = invocations
+            .iter()
+            .find(|invocation| invocation.name == self.0 && !invocation.is_error())?;
+        let answer = match &invocation.args {
+            serde_json::Value::Object(fields) if fields.len() == 1 => {
+                fields.values().next().unwrap_or(&invocation.args)
+            }
+            args => args,
+        };
+        Some(StopOutcome::answer(match answer {
+            serde_json::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        }))
+    }
+}
+
+/// Stops the run on a response with a line starting with the prefix; the
+/// answer is the text after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnPrefix(pub String);
+
+impl OnPrefix {
+    /// Stop on a line starting with `prefix`
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self(prefix.into())
+    }
+}
+
+impl StopCondition for OnPrefix {
+    fn should_stop(
+        &self,
+        _iteration: usize,
+        response: &ChatResponse,
+        _invocations: &[ToolInvocation],
+    ) -> Option<StopOutcome> {
+        let content = &response.content;
+        let start = content
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some((start, line))
+            })
+            .find_map(|(start, line)| {
+                let indent = line.len() - line.trim_start().len();
+                line.trim_start()
+                    .starts_with(&self.0)
+                    .then(|| start + indent + self.0.len())
+            })?;
+        Some(StopOutcome::answer(content[start..].trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn response(content: &str) -> ChatResponse {
+        ChatResponse::new(content, "mock")
+    }
+
+    fn invocation(name: &str, args: serde_json::Value) -> ToolInvocation {
+        ToolInvocation {
+            name: name.to_string(),
+            args,
+            result: String::new(),
+            duration: Duration::ZERO,
+            attempts: 1,
+            cached: false,
+            failure: None,
+            output_reduction: None,
+        }
+    }
+
+    #[test]
+    fn test_on_prefix_takes_text_after_prefix() {
+        let condition = OnPrefix::new("FINAL:");
+
+        let outcome = condition.should_stop(1, &response("Thinking...\n  FINAL: 42\nDone"), &[]);
+        assert_eq!(outcome, Some(StopOutcome::answer("42\nDone")));
+        assert_eq!(
+            condition.should_stop(1, &response("Not FINAL: yet"), &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_on_tool_called_takes_argument_as_answer() {
+        let condition = OnToolCalled::new("submit_answer");
+        let done = |args| {
+            let invocations = [
+                invocation("search", serde_json::json!({"query": "rust"})),
+                invocation("submit_answer", args),
+            ];
+            condition.should_stop(1, &response(""), &invocations)
+        };
+
+        assert_eq!(
+            done(serde_json::json!({"answer": "Paris"})),
+            Some(StopOutcome::answer("Paris"))
+        );
+        assert_eq!(
+            done(serde_json::json!({"answer": 42, "confidence": 0.9})),
+            Some(StopOutcome::answer(r#"{"answer":42,"confidence":0.9}"#))
+        );
+        let searched = [invocation("search", serde_json::json!({}))];
+        assert_eq!(condition.should_stop(1, &response(""), &searched), None);
+    }
+}
@@ -0,0 +1,109 @@
+//! Rolling summary of the conversation in summarized mode
+//!
+//! Under [`ConversationMode::Summarized`](super::ConversationMode::Summarized)
+//! the messages before the recent ones are sent as a summary. The summary is
+//! kept with the conversation, under `session::{session_id}::conversation::summary`
+//! for persistent memory, so resetting the conversation drops it too. Each run
+//! folds only the messages that left the recent window since the last update
+//! into it.
+
+use super::memory::AgentMemoryManager;
+use crate::error::{RragError, RragResult};
+use crate::storage::MemoryValue;
+use rexis_llm::{ChatMessage, Client, MessageRole};
+use serde::{Deserialize, Serialize};
+
+/// Session memory key of the summary
+const SUMMARY_KEY: &str = "conversation::summary";
+
+/// Summary of the conversation up to a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ConversationSummary {
+    /// Summary text
+    pub(crate) text: String,
+
+    /// Role and text of the last message the summary covers
+    last_covered: String,
+}
+
+impl ConversationSummary {
+    /// Summary stored in the session's memory
+    pub(crate) async fn load(memory: &AgentMemoryManager) -> RragResult<Option<Self>> {
+        Ok(memory
+            .get_session_memory(SUMMARY_KEY)
+            .await?
+            .and_then(|value| value.as_json().cloned())
+            .and_then(|json| serde_json::from_value(json).ok()))
+    }
+
+    /// Store the summary in the session's memory
+    pub(crate) async fn save(&self, memory: &AgentMemoryManager) -> RragResult<()> {
+        memory
+            .set_session_memory(SUMMARY_KEY, MemoryValue::Json(serde_json::to_value(self)?))
+            .await
+    }
+
+    /// Summary text still valid for the first `older` messages of `history`,
+    /// and the messages among them it does not cover yet
+    ///
+    /// A summary reaching into the recent messages, as after the window grew,
+    /// is started over; one whose last message was pruned from the history
+    /// covers none of the messages left.
+    pub(crate) fn pending<'a>(
+        summary: Option<&'a Self>,
+        history: &'a [ChatMessage],
+        older: usize,
+    ) -> (Option<&'a str>, &'a [ChatMessage]) {
+        let Some(summary) = summary else {
+            return (None, &history[..older]);
+        };
+        let covered = history
+            .iter()
+            .rposition(|message| fingerprint(message) == summary.last_covered);
+        match covered {
+            Some(index) if index < older => (Some(&summary.text), &history[index + 1..older]),
+            Some(_) => (None, &history[..older]),
+            None => (Some(&summary.text), &history[..older]),
+        }
+    }
+
+    /// Fold `messages` into `previous` with the model
+    pub(crate) async fn update(
+        llm_client: &Client,
+        previous: Option<&str>,
+        messages: &[ChatMessage],
+    ) -> RragResult<Self> {
+        let mut conversation = String::new();
+        for msg in messages {
+            let role = match msg.role {
+                MessageRole::User => "User",
+                MessageRole::Assistant => "Assistant",
+                MessageRole::System | MessageRole::Tool => continue,
+            };
+            conversation.push_str(&format!("{}: {}\n", role, msg.text().unwrap_or_default()));
+        }
+
+        let summary_prompt = format!(
+            "Summarize this conversation so it can be continued without the messages. \
+             Keep names, facts, decisions and open questions, and respond with the \
+             summary only.\n\nSummary so far:\n{}\n\nNew messages:\n{}",
+            previous.unwrap_or("(none)"),
+            conversation
+        );
+
+        let response = llm_client
+            .chat_completion(vec![ChatMessage::user(summary_prompt)])
+            .await
+            .map_err(|e| RragError::rsllm_client("conversation_summary", e))?;
+
+        Ok(Self {
+            text: response.content.trim().to_string(),
+            last_covered: messages.last().map(fingerprint).unwrap_or_default(),
+        })
+    }
+}
+
+/// Identifies a message of the history by role and text
+fn fingerprint(message: &ChatMessage) -> String {
+    format!("{}: {}", message.role, message.text().unwrap_or_default())
+}
@@ -0,0 +1,311 @@
+//! Async tools for agents
+//!
+//! Tools written against [`Tool`] can await I/O directly. Synchronous tools
+//! from `rexis_llm::tools` keep working through the [`SyncTool`] adapter,
+//! which runs them on the blocking thread pool.
+
+use super::CachePolicy;
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use rexis_llm::tools::{Tool as LlmTool, ToolDefinition};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A tool the agent can call
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// The name of the tool (must be unique)
+    fn name(&self) -> &str;
+
+    /// Human-readable description of what the tool does
+    fn description(&self) -> &str;
+
+    /// JSON Schema describing the tool's parameters
+    fn parameters_schema(&self) -> serde_json::Value;
+
+    /// Run the tool with the arguments the model supplied
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput>;
+
+    /// Whether the executor may reuse results of this tool
+    ///
+    /// Only declare a policy for read-only tools whose result depends on their
+    /// arguments alone.
+    fn cache_policy(&self) -> CachePolicy {
+        CachePolicy::NoCache
+    }
+
+    /// Arguments as they appear in logs, run traces and run results
+    ///
+    /// Defaults to the arguments unchanged; tools taking secrets mask them here.
+    fn redact_args(&self, args: &serde_json::Value) -> serde_json::Value {
+        args.clone()
+    }
+
+    /// Definition sent to the model
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(self.name(), self.description(), self.parameters_schema())
+    }
+}
+
+#[async_trait]
+impl<T: Tool + ?Sized> Tool for Box<T> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        (**self).parameters_schema()
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        (**self).call(args).await
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        (**self).cache_policy()
+    }
+
+    fn redact_args(&self, args: &serde_json::Value) -> serde_json::Value {
+        (**self).redact_args(args)
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        (**self).definition()
+    }
+}
+
+/// Result of a tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ToolOutput {
+    /// Plain text, sent to the model as is
+    Text(String),
+
+    /// Structured data, sent to the model as JSON
+    Json(serde_json::Value),
+}
+
+impl ToolOutput {
+    /// Content of the tool result message
+    pub fn to_content(&self) -> String {
+        match self {
+            ToolOutput::Text(text) => text.clone(),
+            ToolOutput::Json(value) => {
+                serde_json::to_string(value).unwrap_or_else(|_| "{}".to_string())
+            }
+        }
+    }
+}
+
+impl From<String> for ToolOutput {
+    fn from(text: String) -> Self {
+        ToolOutput::Text(text)
+    }
+}
+
+impl From<&str> for ToolOutput {
+    fn from(text: &str) -> Self {
+        ToolOutput::Text(text.to_string())
+    }
+}
+
+impl From<serde_json::Value> for ToolOutput {
+    fn from(value: serde_json::Value) -> Self {
+        ToolOutput::Json(value)
+    }
+}
+
+/// Adapter running a synchronous `rexis_llm` tool as an async [`Tool`]
+///
+/// Arguments are validated and the tool executed on the blocking thread pool,
+/// so a slow tool does not stall the runtime. Its result is returned as
+/// [`ToolOutput::Json`].
+#[derive(Clone)]
+pub struct SyncTool {
+    inner: Arc<dyn LlmTool>,
+    cache_policy: CachePolicy,
+}
+
+impl SyncTool {
+    /// Wrap a synchronous tool
+    pub fn new(tool: Box<dyn LlmTool>) -> Self {
+        Self {
+            inner: Arc::from(tool),
+            cache_policy: CachePolicy::NoCache,
+        }
+    }
+
+    /// Set the cache policy of the wrapped tool
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Wrap a synchronous tool, boxed for registration
+    pub fn boxed(tool: Box<dyn LlmTool>) -> Box<dyn Tool> {
+        Box::new(Self::new(tool))
+    }
+}
+
+impl std::fmt::Debug for SyncTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncTool")
+            .field("name", &self.inner.name())
+            .field("cache_policy", &self.cache_policy)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Tool for SyncTool {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn description(&self) -> &str {
+        self.inner.description()
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        self.inner.parameters_schema()
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        self.cache_policy
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let tool = Arc::clone(&self.inner);
+        let outcome = tokio::task::spawn_blocking(move || {
+            tool.validate(&args)
+                .map_err(|e| format!("Validation failed: {}", e))?;
+            tool.execute(args).map_err(|e| e.to_string())
+        })
+        .await;
+
+        match outcome {
+            Ok(Ok(value)) => Ok(ToolOutput::Json(value)),
+            Ok(Err(message)) => Err(RragError::tool_execution(self.name(), message)),
+            // Re-raise so the executor reports the panic like any other tool's
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(RragError::tool_execution(self.name(), e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Legacy synchronous tool
+    struct Doubler;
+
+    impl LlmTool for Doubler {
+        fn name(&self) -> &str {
+            "double"
+        }
+
+        fn description(&self) -> &str {
+            "Doubles a number"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"n": {"type": "number"}}})
+        }
+
+        fn execute(
+            &self,
+            args: serde_json::Value,
+        ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
+            let n = args["n"].as_i64().ok_or("n must be an integer")?;
+            Ok(serde_json::json!({"result": n * 2}))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_tool_shim() {
+        let tool = SyncTool::boxed(Box::new(Doubler));
+
+        assert_eq!(tool.definition().name, "double");
+        let output = tool.call(serde_json::json!({"n": 21})).await.unwrap();
+        assert_eq!(output, ToolOutput::Json(serde_json::json!({"result": 42})));
+        assert_eq!(output.to_content(), r#"{"result":42}"#);
+
+        let err = tool.call(serde_json::json!({})).await.unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::ToolExecution { ref message, .. } if message == "n must be an integer"
+        ));
+    }
+
+    /// Async tool reading a service status over HTTP
+    #[cfg(feature = "http")]
+    struct StatusTool {
+        base_url: String,
+    }
+
+    #[cfg(feature = "http")]
+    #[async_trait]
+    impl Tool for StatusTool {
+        fn name(&self) -> &str {
+            "service_status"
+        }
+
+        fn description(&self) -> &str {
+            "Reports the status of a service"
+        }
+
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"service": {"type": "string"}}})
+        }
+
+        async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+            let service = args["service"].as_str().unwrap_or_default();
+            let url = format!("{}/status/{}", self.base_url, service);
+            let response = reqwest::get(&url)
+                .await
+                .map_err(|e| RragError::tool_execution(self.name(), e.to_string()))?;
+            let body = response
+                .text()
+                .await
+                .map_err(|e| RragError::tool_execution(self.name(), e.to_string()))?;
+            Ok(ToolOutput::Text(body))
+        }
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_async_tool_against_http_server() {
+        use crate::agent::ToolExecutor;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/status/billing"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("billing is up"))
+            .mount(&server)
+            .await;
+
+        let mut executor = ToolExecutor::empty();
+        executor
+            .register(Box::new(StatusTool {
+                base_url: server.uri(),
+            }))
+            .unwrap();
+        let call = rexis_llm::ToolCall::function(
+            "call_1",
+            "service_status",
+            serde_json::json!({"service": "billing"}),
+        );
+
+        let execution = executor.execute_with_policy(&call).await;
+
+        assert_eq!(execution.failure, None);
+        assert_eq!(execution.message.text(), Some("billing is up"));
+    }
+}
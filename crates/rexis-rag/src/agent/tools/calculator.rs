@@ -0,0 +1,813 @@
+//! Arithmetic expression tool
+//!
+//! [`CalculatorTool`] parses expressions with a small recursive-descent parser
+//! and evaluates the tree it builds; nothing is ever executed as code. The
+//! grammar, from loosest to tightest binding:
+//!
+//! ```text
+//! sum     = product (("+" | "-") product)*
+//! product = unary (("*" | "/" | "%") unary)*
+//! unary   = ("-" | "+") unary | power
+//! power   = percent ("^" unary)?
+//! percent = atom "%"*
+//! atom    = number | constant | function "(" sum ("," sum)* ")" | "(" sum ")"
+//! ```
+//!
+//! A `%` followed by a number, name or `(` is the remainder operator; any other
+//! `%` makes a percentage, so `15%` is `0.15` and `200 * 15%` is `30`.
+//!
+//! Expressions longer than 1000 characters or nested deeper than 64 levels are
+//! refused, so that input from the model cannot exhaust the stack.
+
+use crate::agent::{CachePolicy, Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use std::fmt;
+
+/// Name the model calls the tool by
+const TOOL_NAME: &str = "calculator";
+
+/// Longest expression accepted, in characters
+const MAX_LENGTH: usize = 1000;
+
+/// Deepest nesting of parentheses, function arguments, signs and exponents
+const MAX_DEPTH: usize = 64;
+
+/// Named constants
+const CONSTANTS: [(&str, f64); 3] = [
+    ("pi", std::f64::consts::PI),
+    ("e", std::f64::consts::E),
+    ("tau", std::f64::consts::TAU),
+];
+
+/// Functions with the number of arguments they take; `None` takes one or more
+const FUNCTIONS: [(&str, Option<usize>); 18] = [
+    ("sqrt", Some(1)),
+    ("cbrt", Some(1)),
+    ("abs", Some(1)),
+    ("ln", Some(1)),
+    ("log", Some(1)),
+    ("log2", Some(1)),
+    ("exp", Some(1)),
+    ("sin", Some(1)),
+    ("cos", Some(1)),
+    ("tan", Some(1)),
+    ("asin", Some(1)),
+    ("acos", Some(1)),
+    ("atan", Some(1)),
+    ("floor", Some(1)),
+    ("ceil", Some(1)),
+    ("round", Some(1)),
+    ("min", None),
+    ("max", None),
+];
+
+/// Tool evaluating arithmetic expressions
+///
+/// Supports `+ - * / % ^`, parentheses, percentages, the constants `pi`, `e`
+/// and `tau`, and the functions `sqrt`, `cbrt`, `abs`, `ln`, `log` (base 10),
+/// `log2`, `exp`, `sin`, `cos`, `tan`, `asin`, `acos`, `atan`, `floor`,
+/// `ceil`, `round`, `min` and `max`. The result is JSON with the numeric
+/// `result` and the `expression` as parsed, in normalized form. Division by
+/// zero, parse errors, domain errors and overflow fail the call with a message
+/// naming the problem.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CalculatorTool;
+
+impl CalculatorTool {
+    /// Create the tool
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Evaluate `expression`, returning the result and the normalized expression
+    pub fn evaluate(expression: &str) -> RragResult<(f64, String)> {
+        let parsed = parse(expression).map_err(tool_error)?;
+        let result = parsed.eval().map_err(tool_error)?;
+        Ok((result, parsed.to_string()))
+    }
+}
+
+#[async_trait]
+impl Tool for CalculatorTool {
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Evaluates an arithmetic expression exactly. Use it for any calculation. \
+         Supports + - * / % ^, parentheses, percentages like 15%, the constants pi \
+         and e, and sqrt, cbrt, abs, ln, log, log2, exp, sin, cos, tan, asin, acos, \
+         atan, floor, ceil, round, min and max."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to evaluate, such as \"(2 + 3) * sqrt(16)\""
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let expression = args["expression"]
+            .as_str()
+            .ok_or_else(|| RragError::tool_execution(TOOL_NAME, "missing 'expression'"))?;
+        let (result, normalized) = Self::evaluate(expression)?;
+        Ok(ToolOutput::Json(serde_json::json!({
+            "result": result,
+            "expression": normalized,
+        })))
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        CachePolicy::SessionScoped
+    }
+}
+
+fn tool_error(error: CalcError) -> RragError {
+    RragError::tool_execution(TOOL_NAME, error.to_string())
+}
+
+/// Why an expression could not be evaluated
+#[derive(Debug, Clone, PartialEq)]
+enum CalcError {
+    /// The expression is malformed
+    Parse { position: usize, message: String },
+
+    /// A division or remainder by zero
+    DivisionByZero,
+
+    /// A function got an argument outside its domain
+    Domain { function: &'static str, value: f64 },
+
+    /// A result too large to represent
+    Overflow,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Parse { position, message } => {
+                write!(f, "parse error at position {}: {}", position, message)
+            }
+            CalcError::DivisionByZero => write!(f, "division by zero"),
+            CalcError::Domain { function, value } => {
+                write!(f, "domain error: {} is undefined for {}", function, value)
+            }
+            CalcError::Overflow => write!(f, "overflow: the result is too large"),
+        }
+    }
+}
+
+/// Binary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+impl Op {
+    fn symbol(self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+            Op::Rem => '%',
+            Op::Pow => '^',
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div | Op::Rem => 2,
+            Op::Pow => 4,
+        }
+    }
+}
+
+/// Parsed expression
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Constant(&'static str),
+    Neg(Box<Expr>),
+    Percent(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(&'static str, Vec<Expr>),
+}
+
+/// Precedence of negation; binary operators use [`Op::precedence`] and atoms
+/// bind tightest
+const NEG_PRECEDENCE: u8 = 3;
+const ATOM_PRECEDENCE: u8 = 5;
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(op, ..) => op.precedence(),
+            Expr::Neg(_) => NEG_PRECEDENCE,
+            _ => ATOM_PRECEDENCE,
+        }
+    }
+
+    fn eval(&self) -> Result<f64, CalcError> {
+        let value = match self {
+            Expr::Number(value) => *value,
+            Expr::Constant(name) => CONSTANTS
+                .iter()
+                .find(|(constant, _)| constant == name)
+                .map_or(f64::NAN, |(_, value)| *value),
+            Expr::Neg(operand) => -operand.eval()?,
+            Expr::Percent(operand) => operand.eval()? / 100.0,
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.eval()?, right.eval()?);
+                match op {
+                    Op::Add => left + right,
+                    Op::Sub => left - right,
+                    Op::Mul => left * right,
+                    Op::Div | Op::Rem if right == 0.0 => return Err(CalcError::DivisionByZero),
+                    Op::Div => left / right,
+                    Op::Rem => left % right,
+                    Op::Pow if left == 0.0 && right < 0.0 => return Err(CalcError::DivisionByZero),
+                    Op::Pow if left < 0.0 && right.fract() != 0.0 => {
+                        return Err(CalcError::Domain {
+                            function: "^",
+                            value: left,
+                        })
+                    }
+                    Op::Pow => left.powf(right),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(Expr::eval).collect::<Result<Vec<_>, _>>()?;
+                call(name, &args)?
+            }
+        };
+        // Operands are finite and domains checked, so only overflow is left
+        if !value.is_finite() {
+            return Err(CalcError::Overflow);
+        }
+        Ok(value)
+    }
+
+    /// Write `self` as an operand needing at least `precedence`, in
+    /// parentheses if it binds looser
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, precedence: u8) -> fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(value) => write!(f, "{}", value),
+            Expr::Constant(name) => write!(f, "{}", name),
+            Expr::Neg(operand) => {
+                write!(f, "-")?;
+                operand.fmt_operand(f, Op::Pow.precedence())
+            }
+            Expr::Percent(operand) => {
+                operand.fmt_operand(f, ATOM_PRECEDENCE)?;
+                write!(f, "%")
+            }
+            Expr::Binary(op, left, right) => {
+                // Powers group to the right, everything else to the left. A
+                // negation after `%` is parenthesized, or `%` would read as a
+                // percentage.
+                let (left_min, right_min) = match op {
+                    Op::Pow => (ATOM_PRECEDENCE, NEG_PRECEDENCE),
+                    Op::Rem => (op.precedence(), Op::Pow.precedence()),
+                    op => (op.precedence(), op.precedence() + 1),
+                };
+                left.fmt_operand(f, left_min)?;
+                write!(f, " {} ", op.symbol())?;
+                right.fmt_operand(f, right_min)
+            }
+            Expr::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Apply the function `name` to `args`
+fn call(name: &'static str, args: &[f64]) -> Result<f64, CalcError> {
+    let domain = |value: f64| CalcError::Domain {
+        function: name,
+        value,
+    };
+    let x = args[0];
+    Ok(match name {
+        "sqrt" if x < 0.0 => return Err(domain(x)),
+        "sqrt" => x.sqrt(),
+        "cbrt" => x.cbrt(),
+        "abs" => x.abs(),
+        "ln" | "log" | "log2" if x <= 0.0 => return Err(domain(x)),
+        "ln" => x.ln(),
+        "log" => x.log10(),
+        "log2" => x.log2(),
+        "exp" => x.exp(),
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        "asin" | "acos" if !(-1.0..=1.0).contains(&x) => return Err(domain(x)),
+        "asin" => x.asin(),
+        "acos" => x.acos(),
+        "atan" => x.atan(),
+        "floor" => x.floor(),
+        "ceil" => x.ceil(),
+        "round" => x.round(),
+        "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        _ => return Err(domain(x)),
+    })
+}
+
+/// Token of an expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+/// Split `input` into tokens with their character positions
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, as in 1.5e3 or 2E-4
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && matches!(chars[j], '+' | '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse::<f64>().map_err(|_| CalcError::Parse {
+                position: start,
+                message: format!("invalid number '{}'", text),
+            })?;
+            tokens.push((start, Token::Number(value)));
+        } else if c.is_ascii_alphabetic() {
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            tokens.push((start, Token::Name(name.to_ascii_lowercase())));
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push((start, Token::Symbol(c)));
+            i += 1;
+        } else {
+            return Err(CalcError::Parse {
+                position: start,
+                message: format!("unexpected character '{}'", c),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parse `input` into an expression tree
+fn parse(input: &str) -> Result<Expr, CalcError> {
+    let end = input.chars().count();
+    if end > MAX_LENGTH {
+        return Err(CalcError::Parse {
+            position: MAX_LENGTH,
+            message: format!("expression is longer than {} characters", MAX_LENGTH),
+        });
+    }
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        next: 0,
+        end,
+        depth: 0,
+    };
+    let expr = parser.sum()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(parser.error(format!("unexpected {}", describe(token)))),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(value) => format!("number {}", value),
+        Token::Name(name) => format!("'{}'", name),
+        Token::Symbol(c) => format!("'{}'", c),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    fn error(&self, message: impl Into<String>) -> CalcError {
+        CalcError::Parse {
+            position: self.position(),
+            message: message.into(),
+        }
+    }
+
+    /// Consume the next token if it is `symbol`
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), CalcError> {
+        if self.eat(symbol) {
+            return Ok(());
+        }
+        let found = self.peek().map_or("the end".to_string(), describe);
+        Err(self.error(format!("expected '{}', found {}", symbol, found)))
+    }
+
+    /// Run `rule` one nesting level deeper, refusing to go past [`MAX_DEPTH`]
+    fn nested(
+        &mut self,
+        rule: fn(&mut Self) -> Result<Expr, CalcError>,
+    ) -> Result<Expr, CalcError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(format!("nested deeper than {} levels", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let expr = rule(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn sum(&mut self) -> Result<Expr, CalcError> {
+        let mut expr = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, CalcError> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else if self.eat('%') {
+                Op::Rem
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, CalcError> {
+        if self.eat('-') {
+            Ok(Expr::Neg(Box::new(self.nested(Self::unary)?)))
+        } else if self.eat('+') {
+            self.nested(Self::unary)
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, CalcError> {
+        let base = self.percent()?;
+        if self.eat('^') {
+            Ok(Expr::Binary(
+                Op::Pow,
+                Box::new(base),
+                Box::new(self.nested(Self::unary)?),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn percent(&mut self) -> Result<Expr, CalcError> {
+        let mut expr = self.atom()?;
+        while self.peek() == Some(&Token::Symbol('%')) {
+            // A `%` before an operand is the remainder operator
+            let operand_follows = matches!(
+                self.tokens.get(self.next + 1).map(|(_, token)| token),
+                Some(Token::Number(_) | Token::Name(_) | Token::Symbol('('))
+            );
+            if operand_follows {
+                break;
+            }
+            self.next += 1;
+            expr = Expr::Percent(Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    fn atom(&mut self) -> Result<Expr, CalcError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("unexpected end of expression"));
+        };
+        match token {
+            Token::Number(value) => {
+                self.next += 1;
+                Ok(Expr::Number(value))
+            }
+            Token::Symbol('(') => {
+                self.next += 1;
+                let expr = self.nested(Self::sum)?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Token::Name(name) => {
+                if let Some(&(constant, _)) = CONSTANTS.iter().find(|(c, _)| *c == name) {
+                    self.next += 1;
+                    return Ok(Expr::Constant(constant));
+                }
+                let Some(&(function, arity)) = FUNCTIONS.iter().find(|(f, _)| *f == name) else {
+                    return Err(self.error(format!("unknown name '{}'", name)));
+                };
+                self.next += 1;
+                self.expect('(')?;
+                let mut args = vec![self.nested(Self::sum)?];
+                while self.eat(',') {
+                    args.push(self.nested(Self::sum)?);
+                }
+                if let Some(arity) = arity.filter(|arity| *arity != args.len()) {
+                    return Err(self.error(format!(
+                        "{} takes {} argument{}, got {}",
+                        function,
+                        arity,
+                        if arity == 1 { "" } else { "s" },
+                        args.len()
+                    )));
+                }
+                self.expect(')')?;
+                Ok(Expr::Call(function, args))
+            }
+            token => Err(self.error(format!("unexpected {}", describe(&token)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn eval(expression: &str) -> Result<f64, CalcError> {
+        parse(expression)?.eval()
+    }
+
+    #[test]
+    fn test_corpus_evaluates() {
+        let corpus = [
+            ("2 + 2", 4.0),
+            ("2+3*4", 14.0),
+            ("(2+3)*4", 20.0),
+            ("10 - 4 - 3", 3.0),
+            ("2^3^2", 512.0),
+            ("-2^2", -4.0),
+            ("(-2)^2", 4.0),
+            ("2^-1", 0.5),
+            ("7 - -2", 9.0),
+            ("10 % 3", 1.0),
+            ("50%", 0.5),
+            ("200 * 15%", 30.0),
+            ("(50 + 50)%", 1.0),
+            ("max(50%, 2) % 3", 2.0),
+            ("sqrt(16)", 4.0),
+            ("SQRT( 2 ) ^ 2", 2.0000000000000004),
+            ("abs(-3.5)", 3.5),
+            ("ln(e)", 1.0),
+            ("log(1000)", 3.0),
+            ("log2(8)", 3.0),
+            ("min(4, 1, 3)", 1.0),
+            ("max(4, 1, 3)", 4.0),
+            ("round(2.5) + floor(-1.5) + ceil(1.2)", 3.0),
+            ("1.5e3 / 3", 500.0),
+            (".5 + .25", 0.75),
+            ("2E-2", 0.02),
+            ("cos(pi)", -1.0),
+            ("+3", 3.0),
+        ];
+        for (expression, expected) in corpus {
+            assert_eq!(eval(expression), Ok(expected), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_corpus_normalizes() {
+        let corpus = [
+            ("2+3*4", "2 + 3 * 4"),
+            ("(2+3)*4", "(2 + 3) * 4"),
+            ("1-(2-3)", "1 - (2 - 3)"),
+            ("(1-2)-3", "1 - 2 - 3"),
+            ("2^3^2", "2 ^ 3 ^ 2"),
+            ("(2^3)^2", "(2 ^ 3) ^ 2"),
+            ("-2^2", "-2 ^ 2"),
+            ("(-2)^2", "(-2) ^ 2"),
+            ("--2", "-(-2)"),
+            ("50 %", "50%"),
+            ("(1+2)%*3", "(1 + 2)% * 3"),
+            ("10%3", "10 % 3"),
+            ("MAX( 1,2 )", "max(1, 2)"),
+            ("((PI))", "pi"),
+        ];
+        for (expression, normalized) in corpus {
+            assert_eq!(
+                parse(expression).unwrap().to_string(),
+                normalized,
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let corpus = [
+            ("1 / 0", "division by zero"),
+            ("5 % (2 - 2)", "division by zero"),
+            ("0 ^ -1", "division by zero"),
+            ("sqrt(-4)", "domain error: sqrt is undefined for -4"),
+            ("ln(0)", "domain error: ln is undefined for 0"),
+            ("asin(2)", "domain error: asin is undefined for 2"),
+            ("(-8) ^ 0.5", "domain error"),
+            ("10 ^ 400", "overflow"),
+            ("exp(1000)", "overflow"),
+            (
+                "",
+                "parse error at position 0: unexpected end of expression",
+            ),
+            (
+                "2 +",
+                "parse error at position 3: unexpected end of expression",
+            ),
+            (
+                "(1 + 2",
+                "parse error at position 6: expected ')', found the end",
+            ),
+            (
+                "2 $ 3",
+                "parse error at position 2: unexpected character '$'",
+            ),
+            ("foo(1)", "parse error at position 0: unknown name 'foo'"),
+            ("sqrt(1, 2)", "sqrt takes 1 argument, got 2"),
+            ("1 2", "parse error at position 2: unexpected number 2"),
+            ("1..2", "invalid number '1..2'"),
+            ("std::process::exit(1)", "unexpected character ':'"),
+        ];
+        for (expression, message) in corpus {
+            let err = eval(expression).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", expression, err);
+        }
+    }
+
+    #[test]
+    fn test_deep_nesting_is_refused() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(MAX_DEPTH)), Ok(1.0));
+
+        let corpus = [
+            (nested(MAX_DEPTH + 1), "nested deeper than 64 levels"),
+            ("-".repeat(500) + "1", "nested deeper than 64 levels"),
+            ("2^".repeat(100) + "1", "nested deeper than 64 levels"),
+            ("sqrt(".repeat(100) + "1", "nested deeper than 64 levels"),
+            (nested(100_000), "expression is longer than 1000 characters"),
+            (
+                "-".repeat(100_000) + "1",
+                "expression is longer than 1000 characters",
+            ),
+            (
+                "1+".repeat(100_000) + "1",
+                "expression is longer than 1000 characters",
+            ),
+        ];
+        for (expression, message) in corpus {
+            let err = eval(&expression).unwrap_err().to_string();
+            assert!(err.contains(message), "{}", err);
+        }
+    }
+
+    /// Random expression tree of at most `depth` levels, built only from
+    /// shapes the parser produces
+    fn random_expr(rng: &mut StdRng, depth: u32) -> Expr {
+        if depth == 0 || rng.gen_bool(0.25) {
+            return match rng.gen_range(0..4) {
+                0 => Expr::Constant("pi"),
+                _ => Expr::Number(f64::from(rng.gen_range(0..40u8)) / 4.0),
+            };
+        }
+        let shape = rng.gen_range(0..10);
+        let mut operand = || Box::new(random_expr(rng, depth - 1));
+        match shape {
+            0 => Expr::Neg(operand()),
+            1 => Expr::Percent(operand()),
+            2 => Expr::Call("max", vec![*operand(), *operand()]),
+            3 => Expr::Call("abs", vec![*operand()]),
+            n => {
+                let op = [Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Rem, Op::Pow][n - 4];
+                Expr::Binary(op, operand(), operand())
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalized_form_parses_back_to_same_tree() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..2000 {
+            let expr = random_expr(&mut rng, 5);
+            let normalized = expr.to_string();
+            let parsed = parse(&normalized).unwrap();
+            assert_eq!(parsed, expr, "{}", normalized);
+            assert_eq!(parsed.to_string(), normalized);
+
+            // Evaluation never panics, and fails only with an evaluation error
+            if let Err(err) = parsed.eval() {
+                assert!(!matches!(err, CalcError::Parse { .. }), "{}", normalized);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_returns_result_and_expression() {
+        let output = CalculatorTool
+            .call(serde_json::json!({"expression": "(2+3) * 15%"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            output,
+            ToolOutput::Json(serde_json::json!({"result": 0.75, "expression": "(2 + 3) * 15%"}))
+        );
+
+        let err = CalculatorTool
+            .call(serde_json::json!({"expression": "1/0"}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::ToolExecution { ref message, .. } if message == "division by zero"
+        ));
+    }
+}
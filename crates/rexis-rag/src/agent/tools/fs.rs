@@ -0,0 +1,785 @@
+//! Sandboxed filesystem tools
+//!
+//! [`ReadFileTool`], [`WriteFileTool`] and [`ListDirTool`] work inside the root
+//! directory of an [`FsSandbox`]. Paths are taken relative to the root, and
+//! absolute paths must lie under it. A path is first normalized without
+//! touching the disk, so `..` cannot climb above the root, and then resolved
+//! with symlinks followed, so a link cannot lead outside it either.
+
+use crate::agent::{Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::debug;
+
+/// Bytes of a file returned unless configured otherwise
+const DEFAULT_MAX_READ_BYTES: usize = 64 * 1024;
+
+/// Directory entries returned unless configured otherwise
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// Root directory the filesystem tools are confined to
+#[derive(Debug, Clone)]
+pub struct FsSandbox {
+    root: PathBuf,
+    read_only: bool,
+    max_read_bytes: usize,
+    max_entries: usize,
+}
+
+impl FsSandbox {
+    /// Confine the tools to `root`, which must be an existing directory
+    pub fn new(root: impl AsRef<Path>) -> RragResult<Self> {
+        let root = root.as_ref();
+        let canonical = std::fs::canonicalize(root)
+            .ok()
+            .filter(|path| path.is_dir())
+            .ok_or_else(|| {
+                RragError::config("root", "an existing directory", root.display().to_string())
+            })?;
+        Ok(Self {
+            root: canonical,
+            read_only: false,
+            max_read_bytes: DEFAULT_MAX_READ_BYTES,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        })
+    }
+
+    /// Set whether writes are refused; off by default
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Set how many bytes of a file are returned
+    pub fn with_max_read_bytes(mut self, max: usize) -> Self {
+        self.max_read_bytes = max;
+        self
+    }
+
+    /// Set how many entries of a directory are returned
+    pub fn with_max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Canonical root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether writes are refused
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Tools for the sandbox: reading and listing, and writing unless the
+    /// sandbox is read-only
+    pub fn tools(&self) -> Vec<Box<dyn Tool>> {
+        let mut tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(ReadFileTool::new(self.clone())),
+            Box::new(ListDirTool::new(self.clone())),
+        ];
+        if !self.read_only {
+            tools.push(Box::new(WriteFileTool::new(self.clone())));
+        }
+        tools
+    }
+
+    /// `requested` joined to the root with `.` and `..` applied, without
+    /// touching the disk
+    fn normalize(&self, requested: &str) -> Result<PathBuf, FsError> {
+        let outside = || FsError::OutsideSandbox(requested.to_string());
+        let requested_path = Path::new(requested);
+        let relative = if requested_path.is_absolute() {
+            requested_path
+                .strip_prefix(&self.root)
+                .map_err(|_| outside())?
+        } else {
+            requested_path
+        };
+
+        let mut path = self.root.clone();
+        for component in relative.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir if path == self.root => return Err(outside()),
+                Component::ParentDir => {
+                    path.pop();
+                }
+                Component::Normal(name) => path.push(name),
+                Component::RootDir | Component::Prefix(_) => return Err(outside()),
+            }
+        }
+        Ok(path)
+    }
+
+    /// Resolve an existing path, following symlinks
+    pub(super) async fn resolve_existing(&self, requested: &str) -> Result<PathBuf, FsError> {
+        let path = self.normalize(requested)?;
+        let resolved = tokio::fs::canonicalize(&path)
+            .await
+            .map_err(|e| FsError::io(requested, e))?;
+        if !resolved.starts_with(&self.root) {
+            return Err(FsError::OutsideSandbox(requested.to_string()));
+        }
+        Ok(resolved)
+    }
+
+    /// Resolve a path that may not exist yet, such as a file about to be
+    /// written
+    async fn resolve_new(&self, requested: &str) -> Result<PathBuf, FsError> {
+        let path = self.normalize(requested)?;
+        if tokio::fs::symlink_metadata(&path).await.is_ok() {
+            // A link whose target cannot be resolved may point anywhere
+            return self.resolve_existing(requested).await.map_err(|e| match e {
+                FsError::NotFound(path) => FsError::OutsideSandbox(path),
+                e => e,
+            });
+        }
+
+        // The missing part is plain names, so only the existing ancestor can
+        // hold a link
+        let mut existing = path.as_path();
+        let mut missing = Vec::new();
+        while tokio::fs::symlink_metadata(existing).await.is_err() {
+            let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                return Err(FsError::OutsideSandbox(requested.to_string()));
+            };
+            missing.push(name);
+            existing = parent;
+        }
+        let mut resolved = tokio::fs::canonicalize(existing)
+            .await
+            .map_err(|e| FsError::io(requested, e))?;
+        if !resolved.starts_with(&self.root) {
+            return Err(FsError::OutsideSandbox(requested.to_string()));
+        }
+        resolved.extend(missing.into_iter().rev());
+        Ok(resolved)
+    }
+
+    /// `path` relative to the root, as shown to the model
+    pub(super) fn display(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        }
+    }
+}
+
+/// Why a filesystem call failed
+///
+/// The message starts with a fixed label, such as `outside sandbox` or
+/// `not found`, so the model can tell the cases apart.
+#[derive(Debug)]
+pub(super) enum FsError {
+    /// The path leads outside the sandbox root
+    OutsideSandbox(String),
+
+    /// Nothing exists at the path
+    NotFound(String),
+
+    /// The sandbox refuses writes
+    ReadOnly,
+
+    /// The path is a directory where a file was expected
+    NotAFile(String),
+
+    /// The path is not a directory
+    NotADirectory(String),
+
+    /// Any other I/O failure
+    Io(String, io::Error),
+}
+
+impl FsError {
+    fn io(path: &str, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => FsError::NotFound(path.to_string()),
+            _ => FsError::Io(path.to_string(), error),
+        }
+    }
+
+    pub(super) fn into_tool_error(self, tool: &str) -> RragError {
+        RragError::tool_execution(tool, self.to_string())
+    }
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsError::OutsideSandbox(path) => {
+                write!(
+                    f,
+                    "outside sandbox: '{}' is not inside the sandbox root",
+                    path
+                )
+            }
+            FsError::NotFound(path) => write!(f, "not found: '{}' does not exist", path),
+            FsError::ReadOnly => write!(f, "read-only: the sandbox does not allow writes"),
+            FsError::NotAFile(path) => write!(f, "not a file: '{}' is a directory", path),
+            FsError::NotADirectory(path) => {
+                write!(f, "not a directory: '{}' is not a directory", path)
+            }
+            FsError::Io(path, error) => write!(f, "I/O error on '{}': {}", path, error),
+        }
+    }
+}
+
+/// Arguments of [`ReadFileTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReadArgs {
+    path: String,
+}
+
+/// Tool reading a text file inside an [`FsSandbox`]
+///
+/// The result is JSON with the `path`, the file `size` in bytes and its
+/// `content`. Files over the byte limit are cut, with a marker at the end of
+/// the content and `truncated` set.
+#[derive(Debug, Clone)]
+pub struct ReadFileTool {
+    sandbox: FsSandbox,
+}
+
+impl ReadFileTool {
+    /// Create the tool for `sandbox`
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+
+    async fn read(&self, requested: &str) -> Result<serde_json::Value, FsError> {
+        let path = self.sandbox.resolve_existing(requested).await?;
+        let io_error = |e: io::Error| FsError::io(requested, e);
+        let file = tokio::fs::File::open(&path).await.map_err(io_error)?;
+        let metadata = file.metadata().await.map_err(io_error)?;
+        if metadata.is_dir() {
+            return Err(FsError::NotAFile(requested.to_string()));
+        }
+
+        let limit = self.sandbox.max_read_bytes;
+        let mut bytes = Vec::new();
+        file.take(limit as u64)
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(io_error)?;
+        let size = metadata.len();
+        let truncated = size > bytes.len() as u64;
+        let mut content = text(&bytes, truncated);
+        if truncated {
+            content.push_str(&format!(
+                "\n[truncated: showing the first {} of {} bytes]",
+                bytes.len(),
+                size
+            ));
+        }
+        debug!(path = %path.display(), size, truncated, "Read file");
+
+        Ok(serde_json::json!({
+            "path": self.sandbox.display(&path),
+            "size": size,
+            "content": content,
+            "truncated": truncated,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for ReadFileTool {
+    fn name(&self) -> &str {
+        "read_file"
+    }
+
+    fn description(&self) -> &str {
+        "Reads a text file. Paths are relative to the working directory; long files \
+         are cut off with a marker."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, such as \"src/main.rs\""
+                }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: ReadArgs = parse_args(self.name(), args)?;
+        let output = self
+            .read(&args.path)
+            .await
+            .map_err(|e| e.into_tool_error(self.name()))?;
+        Ok(ToolOutput::Json(output))
+    }
+}
+
+/// Arguments of [`WriteFileTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WriteArgs {
+    path: String,
+    content: String,
+    #[serde(default)]
+    append: bool,
+}
+
+/// Tool writing a text file inside an [`FsSandbox`]
+///
+/// Missing parent directories are created. The result is JSON with the `path`
+/// and the number of `bytes_written`. Every call fails when the sandbox is
+/// read-only.
+#[derive(Debug, Clone)]
+pub struct WriteFileTool {
+    sandbox: FsSandbox,
+}
+
+impl WriteFileTool {
+    /// Create the tool for `sandbox`
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+
+    async fn write(&self, args: &WriteArgs) -> Result<serde_json::Value, FsError> {
+        if self.sandbox.read_only {
+            return Err(FsError::ReadOnly);
+        }
+        let requested = args.path.as_str();
+        let path = self.sandbox.resolve_new(requested).await?;
+        if path == self.sandbox.root || tokio::fs::metadata(&path).await.is_ok_and(|m| m.is_dir()) {
+            return Err(FsError::NotAFile(requested.to_string()));
+        }
+        let io_error = |e: io::Error| FsError::io(requested, e);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(args.append)
+            .truncate(!args.append)
+            .open(&path)
+            .await
+            .map_err(io_error)?;
+        file.write_all(args.content.as_bytes())
+            .await
+            .map_err(io_error)?;
+        file.flush().await.map_err(io_error)?;
+        debug!(
+            path = %path.display(),
+            bytes = args.content.len(),
+            append = args.append,
+            "Wrote file"
+        );
+
+        Ok(serde_json::json!({
+            "path": self.sandbox.display(&path),
+            "bytes_written": args.content.len(),
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for WriteFileTool {
+    fn name(&self) -> &str {
+        "write_file"
+    }
+
+    fn description(&self) -> &str {
+        "Writes text to a file, replacing it unless append is set. Paths are \
+         relative to the working directory; missing directories are created."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, such as \"notes/todo.md\""
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Text to write"
+                },
+                "append": {
+                    "type": "boolean",
+                    "description": "Add to the end of the file instead of replacing it"
+                }
+            },
+            "required": ["path", "content"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: WriteArgs = parse_args(self.name(), args)?;
+        let output = self
+            .write(&args)
+            .await
+            .map_err(|e| e.into_tool_error(self.name()))?;
+        Ok(ToolOutput::Json(output))
+    }
+}
+
+/// Arguments of [`ListDirTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListArgs {
+    #[serde(default = "current_dir")]
+    path: String,
+}
+
+fn current_dir() -> String {
+    ".".to_string()
+}
+
+/// Tool listing a directory inside an [`FsSandbox`]
+///
+/// The result is JSON with the `path` and its `entries`, sorted by name, each
+/// with its `name`, `kind` (`file`, `dir` or `symlink`), `size` in bytes for
+/// files and `modified` time in RFC 3339. Entries over the limit are left
+/// out, with `truncated` set.
+#[derive(Debug, Clone)]
+pub struct ListDirTool {
+    sandbox: FsSandbox,
+}
+
+impl ListDirTool {
+    /// Create the tool for `sandbox`
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self { sandbox }
+    }
+
+    async fn list(&self, requested: &str) -> Result<serde_json::Value, FsError> {
+        let path = self.sandbox.resolve_existing(requested).await?;
+        let io_error = |e: io::Error| FsError::io(requested, e);
+        if !tokio::fs::metadata(&path).await.map_err(io_error)?.is_dir() {
+            return Err(FsError::NotADirectory(requested.to_string()));
+        }
+
+        let mut entries = Vec::new();
+        let mut dir = tokio::fs::read_dir(&path).await.map_err(io_error)?;
+        while let Some(entry) = dir.next_entry().await.map_err(io_error)? {
+            // Not following links, so a link's target is never described
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            let kind = if metadata.is_symlink() {
+                "symlink"
+            } else if metadata.is_dir() {
+                "dir"
+            } else {
+                "file"
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .map(|time| DateTime::<Utc>::from(time).to_rfc3339());
+            entries.push(serde_json::json!({
+                "name": entry.file_name().to_string_lossy(),
+                "kind": kind,
+                "size": metadata.is_file().then_some(metadata.len()),
+                "modified": modified,
+            }));
+        }
+        entries.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        let truncated = entries.len() > self.sandbox.max_entries;
+        entries.truncate(self.sandbox.max_entries);
+
+        Ok(serde_json::json!({
+            "path": self.sandbox.display(&path),
+            "entries": entries,
+            "truncated": truncated,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for ListDirTool {
+    fn name(&self) -> &str {
+        "list_dir"
+    }
+
+    fn description(&self) -> &str {
+        "Lists a directory with the name, kind, size and modification time of each \
+         entry. Paths are relative to the working directory."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to list; defaults to the working directory"
+                }
+            },
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: ListArgs = parse_args(self.name(), args)?;
+        let output = self
+            .list(&args.path)
+            .await
+            .map_err(|e| e.into_tool_error(self.name()))?;
+        Ok(ToolOutput::Json(output))
+    }
+}
+
+pub(super) fn parse_args<T: serde::de::DeserializeOwned>(
+    tool: &str,
+    args: serde_json::Value,
+) -> RragResult<T> {
+    serde_json::from_value(args)
+        .map_err(|e| RragError::tool_execution(tool, format!("invalid arguments: {}", e)))
+}
+
+/// File bytes as text, dropping a character cut in half at the limit
+pub(super) fn text(bytes: &[u8], truncated: bool) -> String {
+    match std::str::from_utf8(bytes) {
+        Err(e) if truncated && e.error_len().is_none() => {
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned()
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Sandbox at `<tmp>/root`, with `<tmp>/secret.txt` outside it
+    fn sandbox() -> (TempDir, FsSandbox) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/notes.txt"), "hello").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "top secret").unwrap();
+        let sandbox = FsSandbox::new(&root).unwrap();
+        (dir, sandbox)
+    }
+
+    async fn call(tool: &dyn Tool, args: serde_json::Value) -> RragResult<serde_json::Value> {
+        match tool.call(args).await? {
+            ToolOutput::Json(value) => Ok(value),
+            ToolOutput::Text(text) => panic!("expected JSON output, got {}", text),
+        }
+    }
+
+    fn error_message(result: RragResult<serde_json::Value>) -> String {
+        match result {
+            Err(RragError::ToolExecution { message, .. }) => message,
+            other => panic!("expected a tool error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_paths_outside_root_are_refused() {
+        let (dir, sandbox) = sandbox();
+        let read = ReadFileTool::new(sandbox.clone());
+        let write = WriteFileTool::new(sandbox.clone());
+        let list = ListDirTool::new(sandbox.clone());
+
+        let output = call(
+            &read,
+            serde_json::json!({"path": "docs/../docs/./notes.txt"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["path"], "docs/notes.txt");
+        assert_eq!(output["content"], "hello");
+        let absolute = sandbox.root().join("docs/notes.txt");
+        let output = call(&read, serde_json::json!({"path": absolute}))
+            .await
+            .unwrap();
+        assert_eq!(output["content"], "hello");
+
+        let secret = dir.path().join("secret.txt");
+        for path in [
+            "../secret.txt",
+            "docs/../../secret.txt",
+            "docs/../../root/../secret.txt",
+            secret.to_str().unwrap(),
+        ] {
+            let message = error_message(call(&read, serde_json::json!({"path": path})).await);
+            assert!(
+                message.starts_with("outside sandbox"),
+                "{}: {}",
+                path,
+                message
+            );
+        }
+        let message = error_message(
+            call(
+                &write,
+                serde_json::json!({"path": "../escape.txt", "content": "x"}),
+            )
+            .await,
+        );
+        assert!(message.starts_with("outside sandbox"));
+        assert!(!dir.path().join("escape.txt").exists());
+        let message = error_message(call(&list, serde_json::json!({"path": ".."})).await);
+        assert!(message.starts_with("outside sandbox"));
+
+        let message = error_message(call(&read, serde_json::json!({"path": "missing.txt"})).await);
+        assert!(message.starts_with("not found"), "{}", message);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinks_cannot_break_out() {
+        let (dir, sandbox) = sandbox();
+        let root = sandbox.root().to_path_buf();
+        std::os::unix::fs::symlink(dir.path(), root.join("parent")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret.txt"), root.join("secret")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("new.txt"), root.join("dangling")).unwrap();
+        std::os::unix::fs::symlink(root.join("docs/notes.txt"), root.join("notes")).unwrap();
+        let read = ReadFileTool::new(sandbox.clone());
+        let write = WriteFileTool::new(sandbox.clone());
+
+        for path in ["secret", "parent/secret.txt"] {
+            let message = error_message(call(&read, serde_json::json!({"path": path})).await);
+            assert!(
+                message.starts_with("outside sandbox"),
+                "{}: {}",
+                path,
+                message
+            );
+        }
+        for path in ["parent/new.txt", "secret", "dangling"] {
+            let args = serde_json::json!({"path": path, "content": "pwned"});
+            let message = error_message(call(&write, args).await);
+            assert!(
+                message.starts_with("outside sandbox"),
+                "{}: {}",
+                path,
+                message
+            );
+        }
+        assert!(!dir.path().join("new.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("secret.txt")).unwrap(),
+            "top secret"
+        );
+
+        // Links staying inside the root are followed
+        let output = call(&read, serde_json::json!({"path": "notes"}))
+            .await
+            .unwrap();
+        assert_eq!(output["content"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_read_only_sandbox_refuses_writes() {
+        let (_dir, sandbox) = sandbox();
+        let writable = WriteFileTool::new(sandbox.clone());
+        let output = call(
+            &writable,
+            serde_json::json!({"path": "out/log.txt", "content": "one\n"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["path"], "out/log.txt");
+        call(
+            &writable,
+            serde_json::json!({"path": "out/log.txt", "content": "two\n", "append": true}),
+        )
+        .await
+        .unwrap();
+        let log = sandbox.root().join("out/log.txt");
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "one\ntwo\n");
+
+        let sandbox = sandbox.read_only(true);
+        let names: Vec<_> = sandbox
+            .tools()
+            .iter()
+            .map(|tool| tool.name().to_string())
+            .collect();
+        assert_eq!(names, ["read_file", "list_dir"]);
+
+        let read_only = WriteFileTool::new(sandbox);
+        let message = error_message(
+            call(
+                &read_only,
+                serde_json::json!({"path": "out/log.txt", "content": "three\n"}),
+            )
+            .await,
+        );
+        assert!(message.starts_with("read-only"));
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "one\ntwo\n");
+    }
+
+    #[tokio::test]
+    async fn test_large_files_are_truncated() {
+        let (_dir, sandbox) = sandbox();
+        std::fs::write(sandbox.root().join("big.txt"), "é".repeat(50)).unwrap();
+        let read = ReadFileTool::new(sandbox.with_max_read_bytes(11));
+
+        let output = call(&read, serde_json::json!({"path": "big.txt"}))
+            .await
+            .unwrap();
+        assert_eq!(output["size"], 100);
+        assert_eq!(output["truncated"], true);
+        assert_eq!(
+            output["content"],
+            format!(
+                "{}\n[truncated: showing the first 11 of 100 bytes]",
+                "é".repeat(5)
+            )
+        );
+
+        let output = call(&read, serde_json::json!({"path": "docs/notes.txt"}))
+            .await
+            .unwrap();
+        assert_eq!(output["truncated"], false);
+        assert_eq!(output["content"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_list_returns_names_sizes_and_times() {
+        let (_dir, sandbox) = sandbox();
+        std::fs::write(sandbox.root().join("a.txt"), "abc").unwrap();
+        std::fs::write(sandbox.root().join("z.txt"), "").unwrap();
+        let list = ListDirTool::new(sandbox.with_max_entries(2));
+
+        let output = call(&list, serde_json::json!({})).await.unwrap();
+        assert_eq!(output["path"], ".");
+        let entries = output["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["name"], "a.txt");
+        assert_eq!(entries[0]["kind"], "file");
+        assert_eq!(entries[0]["size"], 3);
+        assert!(entries[0]["modified"].is_string());
+        assert_eq!(entries[1]["name"], "docs");
+        assert_eq!(entries[1]["kind"], "dir");
+        assert!(entries[1]["size"].is_null());
+        assert_eq!(output["truncated"], true);
+
+        let message =
+            error_message(call(&list, serde_json::json!({"path": "docs/notes.txt"})).await);
+        assert!(message.starts_with("not a directory"));
+    }
+}
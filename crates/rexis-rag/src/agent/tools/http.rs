@@ -0,0 +1,773 @@
+//! HTTP request tool
+//!
+//! [`HttpTool`] lets the model call HTTP APIs. Hosts are screened before each
+//! request: denied patterns always lose, a non-empty allowlist admits only its
+//! patterns, and hosts resolving to loopback, private or link-local addresses
+//! are refused unless internal networks are enabled. Names are screened as
+//! the client resolves them and the connection goes to the screened
+//! addresses, so a name cannot pass with a public address and then be
+//! reached on an internal one. Redirects are not followed, so each hop the
+//! model takes goes through the same screening, and proxies set in the
+//! environment are not used, since a proxy would resolve the host itself.
+
+use crate::agent::{Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use base64::Engine;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, Url};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Name the model calls the tool by
+const TOOL_NAME: &str = "http_request";
+
+/// Response bytes kept unless configured otherwise
+const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Longest a request may take unless configured otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Shown in place of a secret header value
+const REDACTED: &str = "[REDACTED]";
+
+/// Request headers redacted unless configured otherwise
+const DEFAULT_SECRET_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+];
+
+/// Response headers returned unless configured otherwise
+const DEFAULT_RESPONSE_HEADERS: [&str; 4] =
+    ["content-type", "content-length", "location", "retry-after"];
+
+/// Arguments the model supplies
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HttpArgs {
+    #[serde(default = "default_method")]
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<serde_json::Value>,
+    #[serde(default)]
+    timeout_secs: Option<f64>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// Tool making HTTP requests for the model
+///
+/// The result is JSON with the `status`, the configured subset of response
+/// `headers`, and the `body` as text, or as base64 when it is not UTF-8 as
+/// given by `body_encoding`. Bodies longer than the byte limit are cut, with
+/// `truncated` set.
+///
+/// Host patterns are exact host names or IP addresses, or `*.example.com` for
+/// every subdomain of `example.com`.
+#[derive(Debug, Clone)]
+pub struct HttpTool {
+    client: reqwest::Client,
+    allowed_hosts: Vec<String>,
+    denied_hosts: Vec<String>,
+    allow_private_networks: bool,
+    max_body_bytes: usize,
+    timeout: Duration,
+    secret_headers: Vec<String>,
+    response_headers: Vec<String>,
+}
+
+impl Default for HttpTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpTool {
+    /// Create a tool reaching any public host
+    pub fn new() -> Self {
+        Self {
+            client: client(Some(PublicResolver(SystemResolver))),
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            allow_private_networks: false,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            timeout: DEFAULT_TIMEOUT,
+            secret_headers: DEFAULT_SECRET_HEADERS.map(String::from).to_vec(),
+            response_headers: DEFAULT_RESPONSE_HEADERS.map(String::from).to_vec(),
+        }
+    }
+
+    /// Only reach hosts matching these patterns
+    pub fn with_allowed_hosts<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_hosts = lowercase(patterns);
+        self
+    }
+
+    /// Never reach hosts matching these patterns
+    pub fn with_denied_hosts<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.denied_hosts = lowercase(patterns);
+        self
+    }
+
+    /// Set whether hosts on loopback, private and link-local addresses may be
+    /// reached; off by default
+    pub fn allow_private_networks(mut self, allow: bool) -> Self {
+        self.client = client((!allow).then_some(PublicResolver(SystemResolver)));
+        self.allow_private_networks = allow;
+        self
+    }
+
+    /// Set how many bytes of a response body are kept
+    pub fn with_max_body_bytes(mut self, max: usize) -> Self {
+        self.max_body_bytes = max;
+        self
+    }
+
+    /// Set the longest a request may take; the model may ask for less
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Also redact these request headers in logs and run results
+    pub fn with_secret_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.secret_headers.extend(lowercase(headers));
+        self
+    }
+
+    /// Set which response headers are returned to the model
+    pub fn with_response_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.response_headers = lowercase(headers);
+        self
+    }
+
+    /// Fail unless the tool may reach `url`
+    ///
+    /// Host names are screened for internal addresses when the client
+    /// resolves them.
+    fn check_url(&self, url: &Url) -> RragResult<()> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(error(format!("scheme '{}' is not supported", url.scheme())));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| error("the URL has no host"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+
+        if self.denied_hosts.iter().any(|p| matches_host(p, &host)) {
+            return Err(blocked(&host));
+        }
+        if !self.allowed_hosts.is_empty()
+            && !self.allowed_hosts.iter().any(|p| matches_host(p, &host))
+        {
+            return Err(blocked(&host));
+        }
+        if self.allow_private_networks {
+            return Ok(());
+        }
+
+        let internal = match host.parse::<IpAddr>() {
+            Ok(ip) => is_internal(ip),
+            Err(_) => host == "localhost" || host.ends_with(".localhost"),
+        };
+        if internal {
+            return Err(blocked(&host));
+        }
+        Ok(())
+    }
+
+    /// Request headers with the secret ones redacted
+    fn redact_headers(&self, headers: &HashMap<String, String>) -> HashMap<String, String> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_secret(name) {
+                    REDACTED.to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    fn is_secret(&self, header: &str) -> bool {
+        self.secret_headers
+            .iter()
+            .any(|secret| secret.eq_ignore_ascii_case(header))
+    }
+}
+
+#[async_trait]
+impl Tool for HttpTool {
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Makes an HTTP request and returns the status, selected response headers and \
+         the body. Redirects are returned, not followed."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "method": {
+                    "type": "string",
+                    "enum": ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"],
+                    "description": "HTTP method; defaults to GET"
+                },
+                "url": {
+                    "type": "string",
+                    "description": "Absolute http:// or https:// URL"
+                },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": {"type": "string"},
+                    "description": "Request headers"
+                },
+                "body": {
+                    "description": "Request body; a string is sent as is, anything else as JSON"
+                },
+                "timeout_secs": {
+                    "type": "number",
+                    "description": "Seconds to wait for the response"
+                }
+            },
+            "required": ["url"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: HttpArgs =
+            serde_json::from_value(args).map_err(|e| error(format!("invalid arguments: {}", e)))?;
+        let method = Method::from_bytes(args.method.to_ascii_uppercase().as_bytes())
+            .map_err(|_| error(format!("invalid method '{}'", args.method)))?;
+        let url = Url::parse(&args.url)
+            .map_err(|e| error(format!("invalid URL '{}': {}", args.url, e)))?;
+        self.check_url(&url)?;
+        let host = url.host_str().unwrap_or_default().to_string();
+
+        let timeout = args
+            .timeout_secs
+            .filter(|secs| secs.is_finite() && *secs > 0.0)
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .map_or(self.timeout, |timeout| timeout.min(self.timeout));
+        debug!(
+            %method,
+            %url,
+            headers = ?self.redact_headers(&args.headers),
+            timeout_ms = timeout.as_millis() as u64,
+            "Sending HTTP request"
+        );
+
+        let mut request = self.client.request(method, url).timeout(timeout);
+        let mut has_content_type = false;
+        for (name, value) in &args.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| error(format!("invalid header name '{}'", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| error(format!("invalid value for header '{}'", name)))?;
+            has_content_type |= name == CONTENT_TYPE;
+            request = request.header(name, value);
+        }
+        match args.body {
+            None => {}
+            Some(serde_json::Value::String(text)) => request = request.body(text),
+            Some(value) => {
+                if !has_content_type {
+                    request = request.header(CONTENT_TYPE, "application/json");
+                }
+                request = request.body(value.to_string());
+            }
+        }
+
+        let request_error = |e: reqwest::Error| {
+            if e.is_timeout() {
+                RragError::timeout(TOOL_NAME, timeout.as_millis() as u64)
+            } else if resolved_internally(&e) {
+                blocked(&host)
+            } else {
+                RragError::network(TOOL_NAME, e)
+            }
+        };
+        let mut response = request.send().await.map_err(request_error)?;
+        let status = response.status().as_u16();
+        let headers: serde_json::Map<String, serde_json::Value> = self
+            .response_headers
+            .iter()
+            .filter_map(|name| {
+                let value = response.headers().get(name)?.to_str().ok()?;
+                Some((name.clone(), value.into()))
+            })
+            .collect();
+
+        // Only the kept part of the body is read
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+            let room = self.max_body_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        debug!(
+            status,
+            bytes = body.len(),
+            truncated,
+            "HTTP response received"
+        );
+
+        let (body, encoding) = body_text(&body, truncated);
+        Ok(ToolOutput::Json(serde_json::json!({
+            "status": status,
+            "headers": headers,
+            "body": body,
+            "body_encoding": encoding,
+            "truncated": truncated,
+        })))
+    }
+
+    fn redact_args(&self, args: &serde_json::Value) -> serde_json::Value {
+        let mut args = args.clone();
+        let headers = args
+            .get_mut("headers")
+            .and_then(serde_json::Value::as_object_mut);
+        if let Some(headers) = headers {
+            for (name, value) in headers.iter_mut() {
+                if self.is_secret(name) {
+                    *value = REDACTED.into();
+                }
+            }
+        }
+        args
+    }
+}
+
+/// Error reported to the model for a request the tool will not make
+fn error(message: impl Into<String>) -> RragError {
+    RragError::tool_execution(TOOL_NAME, message)
+}
+
+/// Error reported to the model for a host the tool may not reach
+fn blocked(host: &str) -> RragError {
+    error(format!("host '{}' is not allowed", host))
+}
+
+/// Client that neither follows redirects nor goes through a proxy, resolving
+/// names with `resolver` when one is given
+fn client<R: Resolve + 'static>(resolver: Option<R>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .no_proxy();
+    if let Some(resolver) = resolver {
+        builder = builder.dns_resolver(Arc::new(resolver));
+    }
+    builder.build().expect("Failed to create HTTP client")
+}
+
+/// Resolution refused because the name has an internal address
+#[derive(Debug)]
+struct InternalAddress;
+
+impl fmt::Display for InternalAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the name resolves to an internal address")
+    }
+}
+
+impl std::error::Error for InternalAddress {}
+
+/// Whether a request failed because its host resolved to an internal address
+fn resolved_internally(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if error.is::<InternalAddress>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Resolver passing on only names whose addresses are all public
+///
+/// The client connects to the addresses returned here, so the screened
+/// addresses are the ones reached.
+struct PublicResolver<R>(R);
+
+impl<R: Resolve> Resolve for PublicResolver<R> {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolving = self.0.resolve(name);
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = resolving.await?.collect();
+            if addresses.iter().any(|address| is_internal(address.ip())) {
+                return Err(InternalAddress.into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Resolver asking the system
+struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, 0)).await?.collect();
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn lowercase<I, S>(items: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    items
+        .into_iter()
+        .map(|item| item.into().to_ascii_lowercase())
+        .collect()
+}
+
+/// Whether `host` matches an exact or `*.`-prefixed pattern
+fn matches_host(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+/// Whether `ip` is on a loopback, private, link-local or otherwise
+/// non-public network
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // "This network", 0.0.0.0/8
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+                // Benchmarking, 198.18.0.0/15
+                || (a == 198 && (b & 0xfe) == 18)
+                // Reserved, 240.0.0.0/4
+                || a >= 240
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            let first = segments[0];
+            // NAT64, 64:ff9b::/96, reaches the IPv4 address in its last 32 bits
+            let nat64 = segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0];
+            let embedded = Ipv4Addr::from(u128::from(ip) as u32);
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7, and link-local, fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (nat64 && is_internal(IpAddr::V4(embedded)))
+                // IPv4-mapped, ::ffff:0:0/96, and IPv4-compatible, ::/96
+                || ip.to_ipv4().is_some_and(|v4| is_internal(IpAddr::V4(v4)))
+        }
+    }
+}
+
+/// Body as text, or base64 when it is not UTF-8
+fn body_text(bytes: &[u8], truncated: bool) -> (String, &'static str) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "text"),
+        // A cut through the last character still leaves text
+        Err(e) if truncated && e.error_len().is_none() => (
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned(),
+            "text",
+        ),
+        Err(_) => (
+            base64::engine::general_purpose::STANDARD.encode(bytes),
+            "base64",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Tool reaching the mock server, which listens on loopback
+    fn local_tool() -> HttpTool {
+        HttpTool::new().allow_private_networks(true)
+    }
+
+    async fn call(tool: &HttpTool, args: serde_json::Value) -> RragResult<serde_json::Value> {
+        match tool.call(args).await? {
+            ToolOutput::Json(value) => Ok(value),
+            ToolOutput::Text(text) => panic!("expected JSON output, got {}", text),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_returns_status_headers_and_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/items/7"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-internal", "hidden")
+                    .set_body_raw(r#"{"id":7}"#, "application/json"),
+            )
+            .mount(&server)
+            .await;
+
+        let output = call(
+            &local_tool(),
+            serde_json::json!({"url": format!("{}/items/7", server.uri())}),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output["status"], 200);
+        assert_eq!(output["headers"]["content-type"], "application/json");
+        assert!(output["headers"].get("x-internal").is_none());
+        assert_eq!(output["body"], r#"{"id":7}"#);
+        assert_eq!(output["body_encoding"], "text");
+        assert_eq!(output["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_post_sends_json_body_and_redacts_secret_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/items"))
+            .and(header("x-api-key", "s3cret"))
+            .and(header("content-type", "application/json"))
+            .and(body_json(serde_json::json!({"name": "pen"})))
+            .respond_with(ResponseTemplate::new(201).set_body_string("created"))
+            .mount(&server)
+            .await;
+
+        let tool = local_tool();
+        let args = serde_json::json!({
+            "method": "post",
+            "url": format!("{}/items", server.uri()),
+            "headers": {"X-Api-Key": "s3cret", "Accept": "text/plain"},
+            "body": {"name": "pen"},
+        });
+        let output = call(&tool, args.clone()).await.unwrap();
+        assert_eq!(output["status"], 201);
+        assert_eq!(output["body"], "created");
+
+        let redacted = tool.redact_args(&args);
+        assert_eq!(redacted["headers"]["X-Api-Key"], REDACTED);
+        assert_eq!(redacted["headers"]["Accept"], "text/plain");
+        assert_eq!(redacted["body"], args["body"]);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_hosts_are_not_requested() {
+        let server = MockServer::start().await;
+        let url = format!("{}/admin", server.uri());
+        let blocked = |tool: HttpTool| {
+            let url = url.clone();
+            async move {
+                let err = call(&tool, serde_json::json!({"url": url}))
+                    .await
+                    .unwrap_err();
+                assert!(matches!(
+                    err,
+                    RragError::ToolExecution { ref message, .. } if message.contains("not allowed")
+                ));
+            }
+        };
+
+        // Internal addresses are refused by default
+        blocked(HttpTool::new()).await;
+        blocked(local_tool().with_denied_hosts(["127.0.0.1"])).await;
+        blocked(local_tool().with_allowed_hosts(["*.example.com"])).await;
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        assert!(matches_host("*.example.com", "api.example.com"));
+        assert!(!matches_host("*.example.com", "example.com"));
+        assert!(!matches_host("*.example.com", "badexample.com"));
+        assert!(is_internal("169.254.169.254".parse().unwrap()));
+        assert!(is_internal("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(!is_internal("93.184.216.34".parse().unwrap()));
+        for internal in [
+            "0.1.2.3",
+            "224.0.0.251",
+            "198.19.0.1",
+            "240.0.0.1",
+            "ff02::1",
+            "64:ff9b::a9fe:a9fe",
+            "::127.0.0.1",
+        ] {
+            assert!(is_internal(internal.parse().unwrap()), "{}", internal);
+        }
+        assert!(!is_internal("64:ff9b::5db8:d822".parse().unwrap()));
+        assert!(!is_internal("2606:4700::1111".parse().unwrap()));
+    }
+
+    /// Resolver answering every name with one address
+    struct FixedResolver(IpAddr);
+
+    impl Resolve for FixedResolver {
+        fn resolve(&self, _name: Name) -> Resolving {
+            let address = SocketAddr::new(self.0, 0);
+            Box::pin(async move { Ok(Box::new(std::iter::once(address)) as Addrs) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_names_resolving_to_internal_addresses_are_not_requested() {
+        let server = MockServer::start().await;
+        let port = server.address().port();
+        let resolver = PublicResolver(FixedResolver("127.0.0.1".parse().unwrap()));
+        let tool = HttpTool {
+            client: client(Some(resolver)),
+            ..HttpTool::new()
+        };
+
+        let err = call(
+            &tool,
+            serde_json::json!({"url": format!("http://rebind.example.com:{}/admin", port)}),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::ToolExecution { ref message, .. }
+                if message.contains("host 'rebind.example.com' is not allowed")
+        ));
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        // Public answers are passed on as resolved
+        let public = PublicResolver(FixedResolver("93.184.216.34".parse().unwrap()));
+        let addresses: Vec<SocketAddr> = public
+            .resolve("api.example.com".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert_eq!(addresses, ["93.184.216.34:0".parse().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn test_body_is_truncated_at_limit() {
+        let server = MockServer::start().await;
+        Mock::given(path("/text"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("a".repeat(100)))
+            .mount(&server)
+            .await;
+        Mock::given(path("/binary"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0xff, 0x00, 0xfe]))
+            .mount(&server)
+            .await;
+        let tool = local_tool().with_max_body_bytes(10);
+
+        let output = call(
+            &tool,
+            serde_json::json!({"url": format!("{}/text", server.uri())}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["body"], "a".repeat(10));
+        assert_eq!(output["truncated"], true);
+
+        let output = call(
+            &tool,
+            serde_json::json!({"url": format!("{}/binary", server.uri())}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["body"], "/wD+");
+        assert_eq!(output["body_encoding"], "base64");
+        assert_eq!(output["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_requested_timeout_is_capped_at_the_tools() {
+        let server = MockServer::start().await;
+        Mock::given(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+        let tool = local_tool().with_timeout(Duration::from_millis(50));
+        let url = format!("{}/slow", server.uri());
+
+        // 1e300 seconds is finite but too long for a Duration
+        for secs in [60.0, 1e300] {
+            let err = call(&tool, serde_json::json!({"url": url, "timeout_secs": secs}))
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    RragError::Timeout {
+                        duration_ms: 50,
+                        ..
+                    }
+                ),
+                "{}: {}",
+                secs,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_environment_proxies_are_not_used() {
+        // The system proxy read from the environment is the only one a client
+        // has unless built with `no_proxy`, and it then shows in the debug output
+        for tool in [HttpTool::new(), local_tool()] {
+            assert!(!format!("{:?}", tool.client).contains("proxies"));
+        }
+        let defaults = reqwest::Client::builder().build().unwrap();
+        assert!(format!("{:?}", defaults).contains("proxies"));
+    }
+}
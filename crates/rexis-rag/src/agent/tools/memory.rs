@@ -0,0 +1,732 @@
+//! Memory tools
+//!
+//! [`memory_tools`] lets the model decide for itself what to remember and when
+//! to recall it. Facts go to semantic memory and are marked as asserted by the
+//! model; episodes are read from episodic memory; notes live in the session's
+//! working memory. Every output is capped, so recalling a large memory cannot
+//! flood the context.
+
+use super::fs::parse_args;
+use crate::agent::memory::{
+    AgentMemoryManager, EpisodicMemory, Fact, SemanticMemory, WorkingMemory,
+};
+use crate::agent::prompt::value_text;
+use crate::agent::{Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use crate::storage::MemoryValue;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Metadata value marking facts the model asserted
+pub const MODEL_SOURCE: &str = "model";
+
+/// Longest subject, predicate or note key
+const MAX_NAME_CHARS: usize = 200;
+
+/// Longest fact object or note value
+const MAX_VALUE_CHARS: usize = 4000;
+
+/// Characters of a recalled object or summary returned to the model
+const MAX_RECALLED_CHARS: usize = 500;
+
+/// Characters of a note returned to the model
+const MAX_NOTE_CHARS: usize = 2000;
+
+/// Results returned unless the model asks for fewer
+const DEFAULT_LIMIT: usize = 5;
+
+/// Most results returned by one call
+const MAX_LIMIT: usize = 20;
+
+/// Confidence of a fact the model states without one
+const DEFAULT_CONFIDENCE: f64 = 0.8;
+
+/// Tools reading and writing the memory of `manager`
+///
+/// `remember_fact` and `recall_facts` work on semantic memory,
+/// `recall_episodes` on episodic memory, and `save_note` and `read_note` on
+/// the working memory of the manager's session.
+pub fn memory_tools(manager: Arc<AgentMemoryManager>) -> Vec<Box<dyn Tool>> {
+    vec![
+        Box::new(RememberFactTool::new(Arc::clone(&manager))),
+        Box::new(RecallFactsTool::new(Arc::clone(&manager))),
+        Box::new(RecallEpisodesTool::new(Arc::clone(&manager))),
+        Box::new(SaveNoteTool::new(Arc::clone(&manager))),
+        Box::new(ReadNoteTool::new(manager)),
+    ]
+}
+
+/// Arguments of [`RememberFactTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RememberArgs {
+    subject: String,
+    predicate: String,
+    object: serde_json::Value,
+    confidence: Option<f64>,
+}
+
+/// Tool storing a fact in semantic memory
+///
+/// The fact's metadata records `source: model`, the session and the run, if
+/// any, so facts the model asserted can be told apart from extracted ones.
+/// Restating a fact refreshes it instead of storing a copy.
+pub struct RememberFactTool {
+    manager: Arc<AgentMemoryManager>,
+}
+
+impl RememberFactTool {
+    /// Create the tool for `manager`
+    pub fn new(manager: Arc<AgentMemoryManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Tool for RememberFactTool {
+    fn name(&self) -> &str {
+        "remember_fact"
+    }
+
+    fn description(&self) -> &str {
+        "Stores a fact in long-term memory as subject, predicate and object, such as \
+         \"user:alice\", \"prefers\", \"window seats\". Use it for durable facts worth \
+         recalling in later conversations."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "subject": {
+                    "type": "string",
+                    "description": "What the fact is about, such as \"user:alice\"",
+                    "minLength": 1,
+                    "maxLength": MAX_NAME_CHARS
+                },
+                "predicate": {
+                    "type": "string",
+                    "description": "The relation, such as \"prefers\" or \"lives_in\"",
+                    "minLength": 1,
+                    "maxLength": MAX_NAME_CHARS
+                },
+                "object": {
+                    "type": ["string", "number", "boolean"],
+                    "description": "The value, such as \"window seats\""
+                },
+                "confidence": {
+                    "type": "number",
+                    "description": "How sure you are, from 0 to 1",
+                    "minimum": 0,
+                    "maximum": 1
+                }
+            },
+            "required": ["subject", "predicate", "object"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: RememberArgs = parse_args(self.name(), args)?;
+        if args.subject.trim().is_empty() || args.predicate.trim().is_empty() {
+            return Err(RragError::tool_execution(
+                self.name(),
+                "'subject' and 'predicate' must not be empty",
+            ));
+        }
+        check_length(self.name(), "subject", &args.subject, MAX_NAME_CHARS)?;
+        check_length(self.name(), "predicate", &args.predicate, MAX_NAME_CHARS)?;
+        let object = fact_object(self.name(), args.object)?;
+        check_length(self.name(), "object", &value_text(&object), MAX_VALUE_CHARS)?;
+
+        let mut fact = Fact::new(args.subject, args.predicate, object)
+            .with_confidence(args.confidence.unwrap_or(DEFAULT_CONFIDENCE))
+            .with_metadata("source", MODEL_SOURCE)
+            .with_metadata("session_id", self.manager.session_id());
+        if let Some(run_id) = self.manager.run_id() {
+            fact = fact.with_metadata("run_id", run_id);
+        }
+        let fact = semantic(&self.manager).upsert_fact(fact).await?;
+        debug!(fact_id = %fact.id, subject = %fact.subject, "Model stored a fact");
+
+        Ok(ToolOutput::Json(serde_json::json!({
+            "remembered": fact_json(&fact),
+        })))
+    }
+}
+
+/// Arguments of [`RecallFactsTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecallFactsArgs {
+    query: String,
+    limit: Option<usize>,
+}
+
+/// Tool looking facts up in semantic memory
+///
+/// A query naming a subject returns that subject's facts; any other query
+/// returns the facts sharing words with it, those sharing the most first.
+/// Facts that match equally well are ordered by confidence, then newest first.
+pub struct RecallFactsTool {
+    manager: Arc<AgentMemoryManager>,
+}
+
+impl RecallFactsTool {
+    /// Create the tool for `manager`
+    pub fn new(manager: Arc<AgentMemoryManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Tool for RecallFactsTool {
+    fn name(&self) -> &str {
+        "recall_facts"
+    }
+
+    fn description(&self) -> &str {
+        "Looks up facts in long-term memory by subject, such as \"user:alice\", or by \
+         words they contain."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "A subject, or words to look for",
+                    "minLength": 1
+                },
+                "limit": limit_schema()
+            },
+            "required": ["query"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: RecallFactsArgs = parse_args(self.name(), args)?;
+        let query = args.query.trim().to_lowercase();
+        let terms = terms(&query);
+
+        let facts = semantic(&self.manager).get_all_facts().await?;
+        let (about_subject, others): (Vec<_>, Vec<_>) = facts
+            .into_iter()
+            .partition(|fact| fact.subject.to_lowercase() == query);
+        let mut matches: Vec<(usize, Fact)> = if about_subject.is_empty() {
+            others
+                .into_iter()
+                .filter_map(|fact| {
+                    let text = format!(
+                        "{} {} {}",
+                        fact.subject,
+                        fact.predicate,
+                        value_text(&fact.object)
+                    );
+                    let score = matching_terms(&terms, &text);
+                    (score > 0).then_some((score, fact))
+                })
+                .collect()
+        } else {
+            about_subject.into_iter().map(|fact| (0, fact)).collect()
+        };
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(b.confidence.total_cmp(&a.confidence))
+                .then(b.updated_at.cmp(&a.updated_at))
+        });
+
+        let total = matches.len();
+        let facts: Vec<_> = matches
+            .iter()
+            .take(limit(args.limit))
+            .map(|(_, fact)| fact_json(fact))
+            .collect();
+        Ok(ToolOutput::Json(serde_json::json!({
+            "facts": facts,
+            "total": total,
+        })))
+    }
+}
+
+/// Arguments of [`RecallEpisodesTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecallEpisodesArgs {
+    topic: String,
+    limit: Option<usize>,
+}
+
+/// Tool looking up summaries of past conversations in episodic memory
+///
+/// Episodes whose topics or summary share words with the topic are returned,
+/// best match first and then newest first.
+pub struct RecallEpisodesTool {
+    manager: Arc<AgentMemoryManager>,
+}
+
+impl RecallEpisodesTool {
+    /// Create the tool for `manager`
+    pub fn new(manager: Arc<AgentMemoryManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Tool for RecallEpisodesTool {
+    fn name(&self) -> &str {
+        "recall_episodes"
+    }
+
+    fn description(&self) -> &str {
+        "Looks up summaries of past conversations about a topic."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "topic": {
+                    "type": "string",
+                    "description": "Topic to look for, such as \"travel plans\"",
+                    "minLength": 1
+                },
+                "limit": limit_schema()
+            },
+            "required": ["topic"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: RecallEpisodesArgs = parse_args(self.name(), args)?;
+        let terms = terms(&args.topic.to_lowercase());
+
+        let episodic =
+            EpisodicMemory::new(self.manager.storage(), self.manager.agent_id().to_string());
+        let mut matches: Vec<_> = episodic
+            .get_all_episodes()
+            .await?
+            .into_iter()
+            .filter_map(|episode| {
+                let text = format!("{} {}", episode.topics.join(" "), episode.summary);
+                let score = matching_terms(&terms, &text);
+                (score > 0).then_some((score, episode))
+            })
+            .collect();
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then(b.timestamp.cmp(&a.timestamp))
+        });
+
+        let total = matches.len();
+        let episodes: Vec<_> = matches
+            .iter()
+            .take(limit(args.limit))
+            .map(|(_, episode)| {
+                serde_json::json!({
+                    "summary": clip(&episode.summary, MAX_RECALLED_CHARS),
+                    "topics": episode.topics,
+                    "importance": episode.importance,
+                    "timestamp": episode.timestamp.to_rfc3339(),
+                })
+            })
+            .collect();
+        Ok(ToolOutput::Json(serde_json::json!({
+            "episodes": episodes,
+            "total": total,
+        })))
+    }
+}
+
+/// Arguments of [`SaveNoteTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SaveNoteArgs {
+    key: String,
+    value: String,
+}
+
+/// Tool keeping a note in the session's working memory
+///
+/// Saving under an existing key replaces the note.
+pub struct SaveNoteTool {
+    manager: Arc<AgentMemoryManager>,
+}
+
+impl SaveNoteTool {
+    /// Create the tool for `manager`
+    pub fn new(manager: Arc<AgentMemoryManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Tool for SaveNoteTool {
+    fn name(&self) -> &str {
+        "save_note"
+    }
+
+    fn description(&self) -> &str {
+        "Saves a note for the rest of this conversation under a key, replacing any \
+         note already under it. Read it back with read_note."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "string",
+                    "description": "Name of the note, such as \"shortlist\"",
+                    "minLength": 1,
+                    "maxLength": MAX_NAME_CHARS
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Text of the note",
+                    "maxLength": MAX_VALUE_CHARS
+                }
+            },
+            "required": ["key", "value"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: SaveNoteArgs = parse_args(self.name(), args)?;
+        if args.key.trim().is_empty() {
+            return Err(RragError::tool_execution(
+                self.name(),
+                "'key' must not be empty",
+            ));
+        }
+        check_length(self.name(), "key", &args.key, MAX_NAME_CHARS)?;
+        check_length(self.name(), "value", &args.value, MAX_VALUE_CHARS)?;
+
+        let chars = args.value.chars().count();
+        working(&self.manager).set(&args.key, args.value).await?;
+        Ok(ToolOutput::Json(serde_json::json!({
+            "saved": args.key,
+            "chars": chars,
+        })))
+    }
+}
+
+/// Arguments of [`ReadNoteTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReadNoteArgs {
+    key: String,
+}
+
+/// Tool reading a note saved with [`SaveNoteTool`]
+///
+/// A missing note is reported with `found: false` rather than as an error.
+pub struct ReadNoteTool {
+    manager: Arc<AgentMemoryManager>,
+}
+
+impl ReadNoteTool {
+    /// Create the tool for `manager`
+    pub fn new(manager: Arc<AgentMemoryManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadNoteTool {
+    fn name(&self) -> &str {
+        "read_note"
+    }
+
+    fn description(&self) -> &str {
+        "Reads a note saved earlier in this conversation with save_note."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "string",
+                    "description": "Name the note was saved under",
+                    "minLength": 1
+                }
+            },
+            "required": ["key"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: ReadNoteArgs = parse_args(self.name(), args)?;
+        let output = match working(&self.manager).get(&args.key).await? {
+            Some(value) => {
+                let text = value_text(&value);
+                let truncated = text.chars().count() > MAX_NOTE_CHARS;
+                serde_json::json!({
+                    "key": args.key,
+                    "found": true,
+                    "value": clip(&text, MAX_NOTE_CHARS),
+                    "truncated": truncated,
+                })
+            }
+            None => serde_json::json!({"key": args.key, "found": false}),
+        };
+        Ok(ToolOutput::Json(output))
+    }
+}
+
+fn semantic(manager: &AgentMemoryManager) -> SemanticMemory {
+    SemanticMemory::new(manager.storage(), manager.agent_id().to_string())
+}
+
+/// The session's working memory, kept when the handle is dropped
+fn working(manager: &AgentMemoryManager) -> WorkingMemory {
+    WorkingMemory::new_persistent(manager.storage(), manager.session_id().to_string())
+}
+
+/// A fact as shown to the model
+fn fact_json(fact: &Fact) -> serde_json::Value {
+    serde_json::json!({
+        "subject": fact.subject,
+        "predicate": fact.predicate,
+        "object": clip(&value_text(&fact.object), MAX_RECALLED_CHARS),
+        "confidence": fact.confidence,
+        "source": fact.metadata.get("source"),
+        "updated_at": fact.updated_at.to_rfc3339(),
+    })
+}
+
+/// The stored value of a fact object the model supplied
+fn fact_object(tool: &str, object: serde_json::Value) -> RragResult<MemoryValue> {
+    match object {
+        serde_json::Value::String(text) => Ok(MemoryValue::String(text)),
+        serde_json::Value::Bool(flag) => Ok(MemoryValue::Boolean(flag)),
+        serde_json::Value::Number(number) => Ok(match number.as_i64() {
+            Some(integer) => MemoryValue::Integer(integer),
+            None => MemoryValue::Float(number.as_f64().unwrap_or_default()),
+        }),
+        _ => Err(RragError::tool_execution(
+            tool,
+            "'object' must be a string, number or boolean",
+        )),
+    }
+}
+
+fn limit_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "integer",
+        "description": format!("Most results to return, {} by default", DEFAULT_LIMIT),
+        "minimum": 1,
+        "maximum": MAX_LIMIT
+    })
+}
+
+fn limit(requested: Option<usize>) -> usize {
+    requested.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+/// Distinct lowercase words of a query
+fn terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    terms.sort_unstable();
+    terms.dedup();
+    terms
+}
+
+/// How many of `terms` occur in `text`
+fn matching_terms(terms: &[String], text: &str) -> usize {
+    let text = text.to_lowercase();
+    terms
+        .iter()
+        .filter(|term| text.contains(term.as_str()))
+        .count()
+}
+
+/// `text` cut to `max_chars` characters, marked with an ellipsis when cut
+fn clip(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Refuse `value` when it is longer than `max_chars` characters
+fn check_length(tool: &str, field: &str, value: &str, max_chars: usize) -> RragResult<()> {
+    let chars = value.chars().count();
+    if chars > max_chars {
+        return Err(RragError::tool_execution(
+            tool,
+            format!(
+                "'{}' has {} characters; the most allowed is {}",
+                field, chars, max_chars
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::memory::{Episode, MemoryConfig};
+    use crate::storage::InMemoryStorage;
+
+    fn manager() -> Arc<AgentMemoryManager> {
+        let config =
+            MemoryConfig::new(Arc::new(InMemoryStorage::new()), "concierge").with_session_id("s1");
+        Arc::new(AgentMemoryManager::new(config))
+    }
+
+    async fn call(tool: &dyn Tool, args: serde_json::Value) -> RragResult<serde_json::Value> {
+        match tool.call(args).await? {
+            ToolOutput::Json(value) => Ok(value),
+            ToolOutput::Text(text) => panic!("expected JSON output, got {}", text),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remembered_facts_are_marked_as_model_asserted() {
+        let manager = manager();
+        let remember = RememberFactTool::new(Arc::clone(&manager));
+
+        let args = serde_json::json!({
+            "subject": "user:alice",
+            "predicate": "prefers",
+            "object": "window seats"
+        });
+        call(&remember, args.clone()).await.unwrap();
+        // Restating a fact refreshes it
+        call(&remember, args).await.unwrap();
+
+        let facts = semantic(&manager).get_all_facts().await.unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].confidence, DEFAULT_CONFIDENCE);
+        assert_eq!(facts[0].metadata["source"], MODEL_SOURCE);
+        assert_eq!(facts[0].metadata["session_id"], "s1");
+
+        let err = call(
+            &remember,
+            serde_json::json!({
+                "subject": "user:alice",
+                "predicate": "x".repeat(MAX_NAME_CHARS + 1),
+                "object": 1
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("'predicate' has 201 characters"));
+    }
+
+    #[tokio::test]
+    async fn test_recall_prefers_subject_then_matching_words() {
+        let manager = manager();
+        let semantic = semantic(&manager);
+        for fact in [
+            Fact::new("user:alice", "prefers", "window seats").with_confidence(0.6),
+            Fact::new("user:alice", "lives_in", "Lisbon").with_confidence(0.9),
+            Fact::new("user:bob", "prefers", "aisle seats"),
+            Fact::new("user:carol", "owns", "a cat"),
+        ] {
+            semantic.store_fact(fact).await.unwrap();
+        }
+        let recall = RecallFactsTool::new(Arc::clone(&manager));
+
+        let output = call(&recall, serde_json::json!({"query": "User:Alice"}))
+            .await
+            .unwrap();
+        let objects: Vec<_> = output["facts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|fact| fact["object"].as_str().unwrap())
+            .collect();
+        assert_eq!(objects, ["Lisbon", "window seats"]);
+
+        let output = call(
+            &recall,
+            serde_json::json!({"query": "aisle seats", "limit": 1}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["total"], 2);
+        assert_eq!(output["facts"][0]["object"], "aisle seats");
+    }
+
+    #[tokio::test]
+    async fn test_recall_episodes_by_topic() {
+        let manager = manager();
+        let episodic = EpisodicMemory::new(manager.storage(), manager.agent_id().to_string());
+        episodic
+            .store_episode(
+                Episode::new("Planned a trip to Porto").with_topics(vec!["travel".to_string()]),
+            )
+            .await
+            .unwrap();
+        episodic
+            .store_episode(Episode::new("Fixed a billing issue"))
+            .await
+            .unwrap();
+        let recall = RecallEpisodesTool::new(Arc::clone(&manager));
+
+        let output = call(&recall, serde_json::json!({"topic": "Travel"}))
+            .await
+            .unwrap();
+        assert_eq!(output["total"], 1);
+        assert_eq!(output["episodes"][0]["summary"], "Planned a trip to Porto");
+    }
+
+    #[tokio::test]
+    async fn test_notes_round_trip_and_are_capped() {
+        let manager = manager();
+        let save = SaveNoteTool::new(Arc::clone(&manager));
+        let read = ReadNoteTool::new(Arc::clone(&manager));
+
+        call(
+            &save,
+            serde_json::json!({"key": "shortlist", "value": "Porto, Lisbon"}),
+        )
+        .await
+        .unwrap();
+        let output = call(&read, serde_json::json!({"key": "shortlist"}))
+            .await
+            .unwrap();
+        assert_eq!(output["value"], "Porto, Lisbon");
+        assert_eq!(output["truncated"], false);
+
+        let output = call(&read, serde_json::json!({"key": "missing"}))
+            .await
+            .unwrap();
+        assert_eq!(output["found"], false);
+
+        // Notes stored another way are capped when read back
+        working(&manager)
+            .set("long", "x".repeat(MAX_NOTE_CHARS + 10))
+            .await
+            .unwrap();
+        let output = call(&read, serde_json::json!({"key": "long"}))
+            .await
+            .unwrap();
+        assert_eq!(output["truncated"], true);
+        assert_eq!(
+            output["value"].as_str().unwrap().chars().count(),
+            MAX_NOTE_CHARS + 1
+        );
+    }
+
+    #[test]
+    fn test_clip_counts_characters() {
+        assert_eq!(clip("héllo", 5), "héllo");
+        assert_eq!(clip("héllo", 2), "hé…");
+    }
+}
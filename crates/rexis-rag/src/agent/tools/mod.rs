@@ -0,0 +1,30 @@
+//! Built-in tools for agents
+
+mod calculator;
+mod fs;
+#[cfg(feature = "http")]
+mod http;
+mod memory;
+pub mod search;
+mod shell;
+
+pub use calculator::CalculatorTool;
+pub use fs::{FsSandbox, ListDirTool, ReadFileTool, WriteFileTool};
+#[cfg(feature = "http")]
+pub use http::HttpTool;
+pub use memory::{
+    memory_tools, ReadNoteTool, RecallEpisodesTool, RecallFactsTool, RememberFactTool,
+    SaveNoteTool, MODEL_SOURCE,
+};
+pub use search::{Freshness, SearchHit, SearchProvider, SearchQuery, WebSearchTool};
+pub use shell::ShellTool;
+
+use super::Tool;
+
+/// Built-in tools that are safe to give any agent: they reach nothing outside
+/// the process
+///
+/// Currently the [`CalculatorTool`].
+pub fn default_tools() -> Vec<Box<dyn Tool>> {
+    vec![Box::new(CalculatorTool::new())]
+}
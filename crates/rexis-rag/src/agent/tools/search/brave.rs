@@ -0,0 +1,222 @@
+//! Brave Search API provider
+
+use super::{
+    fetch_json, plain_text, Freshness, SearchHit, SearchProvider, SearchQuery, REQUEST_TIMEOUT,
+};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fmt;
+
+/// Environment variable holding the subscription key
+const API_KEY_ENV: &str = "BRAVE_SEARCH_API_KEY";
+
+/// Web search endpoint of the Brave Search API
+const DEFAULT_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+
+/// Most results the API returns for one request
+const MAX_COUNT: usize = 20;
+
+/// Searches with the Brave Search API
+#[derive(Clone)]
+pub struct BraveSearch {
+    client: reqwest::Client,
+    api_key: String,
+    endpoint: String,
+}
+
+impl fmt::Debug for BraveSearch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BraveSearch")
+            .field("endpoint", &self.endpoint)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BraveSearch {
+    /// Create a provider with a Brave Search subscription key
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to create HTTP client"),
+            api_key: api_key.into(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Create a provider with the key in `BRAVE_SEARCH_API_KEY`
+    pub fn from_env() -> RragResult<Self> {
+        Self::from_var(&|name| std::env::var(name).ok())
+    }
+
+    pub(super) fn from_var(var: &impl Fn(&str) -> Option<String>) -> RragResult<Self> {
+        match var(API_KEY_ENV).filter(|key| !key.trim().is_empty()) {
+            Some(key) => Ok(Self::new(key.trim())),
+            None => Err(RragError::config(
+                API_KEY_ENV,
+                "a Brave Search API subscription key",
+                "not set",
+            )),
+        }
+    }
+
+    /// Send searches to another endpoint, such as a proxy
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+}
+
+/// Part of the API response the provider reads
+#[derive(Debug, Deserialize)]
+struct BraveResponse {
+    #[serde(default)]
+    web: Option<BraveResults>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveResults {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BraveResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[async_trait]
+impl SearchProvider for BraveSearch {
+    fn name(&self) -> &str {
+        "brave"
+    }
+
+    async fn search(&self, query: &SearchQuery) -> RragResult<Vec<SearchHit>> {
+        let count = query.max_results.clamp(1, MAX_COUNT).to_string();
+        let mut params = vec![("q", query.query.as_str()), ("count", count.as_str())];
+        if let Some(freshness) = query.freshness {
+            params.push(("freshness", freshness_param(freshness)));
+        }
+        let request = self
+            .client
+            .get(&self.endpoint)
+            .query(&params)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key);
+
+        let response: BraveResponse = fetch_json(self.name(), request).await?;
+        Ok(response
+            .web
+            .map(|web| web.results)
+            .unwrap_or_default()
+            .into_iter()
+            .take(query.max_results)
+            .map(|result| SearchHit {
+                title: plain_text(&result.title),
+                url: result.url,
+                snippet: plain_text(&result.description),
+            })
+            .collect())
+    }
+}
+
+fn freshness_param(freshness: Freshness) -> &'static str {
+    match freshness {
+        Freshness::Day => "pd",
+        Freshness::Week => "pw",
+        Freshness::Month => "pm",
+        Freshness::Year => "py",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_search_sends_key_and_parses_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/res/v1/web/search"))
+            .and(header("X-Subscription-Token", "key"))
+            .and(query_param("q", "rust async"))
+            .and(query_param("count", "2"))
+            .and(query_param("freshness", "pw"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "type": "search",
+                "web": {
+                    "results": [
+                        {
+                            "title": "Async <strong>Rust</strong>",
+                            "url": "https://rust-lang.github.io/async-book/",
+                            "description":
+                                "Asynchronous Programming in <strong>Rust</strong> &amp; more"
+                        },
+                        {"title": "Tokio", "url": "https://tokio.rs/", "description": "A runtime"},
+                        {"title": "Extra", "url": "https://example.com/", "description": "Dropped"}
+                    ]
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let brave =
+            BraveSearch::new("key").with_endpoint(format!("{}/res/v1/web/search", server.uri()));
+        let hits = brave
+            .search(&SearchQuery {
+                query: "rust async".to_string(),
+                max_results: 2,
+                freshness: Some(Freshness::Week),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            hits,
+            [
+                SearchHit {
+                    title: "Async Rust".to_string(),
+                    url: "https://rust-lang.github.io/async-book/".to_string(),
+                    snippet: "Asynchronous Programming in Rust & more".to_string(),
+                },
+                SearchHit {
+                    title: "Tokio".to_string(),
+                    url: "https://tokio.rs/".to_string(),
+                    snippet: "A runtime".to_string(),
+                },
+            ]
+        );
+        assert!(!format!("{:?}", brave).contains("key"));
+    }
+
+    #[tokio::test]
+    async fn test_search_reports_api_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid subscription token"))
+            .mount(&server)
+            .await;
+
+        let err = BraveSearch::new("wrong")
+            .with_endpoint(server.uri())
+            .search(&SearchQuery {
+                query: "rust".to_string(),
+                max_results: 5,
+                freshness: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::ToolExecution { ref message, .. }
+                if message.contains("401") && message.contains("invalid subscription token")
+        ));
+    }
+}
@@ -0,0 +1,516 @@
+//! Web search tool
+//!
+//! [`WebSearchTool`] gives the model fresh results from a [`SearchProvider`].
+//! Providers ship behind features: [`BraveSearch`] (`search-brave`) calls the
+//! Brave Search API with a subscription key, and [`SearxngSearch`]
+//! (`search-searxng`) calls a self-hosted SearxNG instance, which needs no key.
+
+#[cfg(feature = "search-brave")]
+mod brave;
+#[cfg(feature = "search-searxng")]
+mod searxng;
+
+#[cfg(feature = "search-brave")]
+pub use brave::BraveSearch;
+#[cfg(feature = "search-searxng")]
+pub use searxng::SearxngSearch;
+
+use crate::agent::{CachePolicy, Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Name the model calls the tool by
+const TOOL_NAME: &str = "web_search";
+
+/// Results returned when the model does not say how many
+const DEFAULT_RESULTS: usize = 5;
+
+/// Most results the model may ask for unless configured otherwise
+const DEFAULT_MAX_RESULTS: usize = 10;
+
+/// Characters of results returned unless configured otherwise
+const DEFAULT_MAX_CHARS: usize = 4000;
+
+/// Results are reused for this long
+const CACHE_TTL_SECS: u64 = 300;
+
+/// Longest a provider request may take
+#[cfg(any(feature = "search-brave", feature = "search-searxng"))]
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Environment variable naming the provider for [`WebSearchTool::from_env`]
+pub const PROVIDER_ENV: &str = "REXIS_SEARCH_PROVIDER";
+
+/// A search result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Page title
+    pub title: String,
+
+    /// Page URL
+    pub url: String,
+
+    /// Excerpt of the page
+    pub snippet: String,
+}
+
+/// How recent results must be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    /// Published in the last day
+    Day,
+
+    /// Published in the last week
+    Week,
+
+    /// Published in the last month
+    Month,
+
+    /// Published in the last year
+    Year,
+}
+
+/// A search request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Search terms
+    pub query: String,
+
+    /// Most results to return
+    pub max_results: usize,
+
+    /// How recent results must be; `None` for any age
+    pub freshness: Option<Freshness>,
+}
+
+/// Backend answering web searches
+#[async_trait]
+pub trait SearchProvider: fmt::Debug + Send + Sync {
+    /// Provider name, used in errors and logs
+    fn name(&self) -> &str;
+
+    /// Search the web, returning at most `query.max_results` hits, best first
+    async fn search(&self, query: &SearchQuery) -> RragResult<Vec<SearchHit>>;
+}
+
+/// Arguments the model supplies
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchArgs {
+    query: String,
+    #[serde(default)]
+    max_results: Option<usize>,
+    #[serde(default)]
+    freshness: Option<Freshness>,
+}
+
+/// Tool searching the web for the model
+///
+/// The result is JSON with the `query` and its `results`, each with a
+/// `title`, `url` and `snippet` the model can cite. Results are kept within a
+/// character budget: the snippet of the first result that does not fit is
+/// cut, later results are dropped, and `truncated` is set.
+#[derive(Debug, Clone)]
+pub struct WebSearchTool {
+    provider: Arc<dyn SearchProvider>,
+    max_results: usize,
+    max_chars: usize,
+}
+
+impl WebSearchTool {
+    /// Create a tool searching with `provider`
+    pub fn new(provider: impl SearchProvider + 'static) -> Self {
+        Self::with_provider(Arc::new(provider))
+    }
+
+    /// Create a tool searching with a shared provider
+    pub fn with_provider(provider: Arc<dyn SearchProvider>) -> Self {
+        Self {
+            provider,
+            max_results: DEFAULT_MAX_RESULTS,
+            max_chars: DEFAULT_MAX_CHARS,
+        }
+    }
+
+    /// Create a tool with the provider configured in the environment
+    ///
+    /// `REXIS_SEARCH_PROVIDER` picks `brave` or `searxng`. When it is not set,
+    /// the first provider whose settings are present is used. Brave reads its
+    /// key from `BRAVE_SEARCH_API_KEY` and SearxNG its base URL from
+    /// `SEARXNG_URL`; each needs its feature enabled.
+    pub fn from_env() -> RragResult<Self> {
+        provider_from(|name| std::env::var(name).ok()).map(Self::with_provider)
+    }
+
+    /// Set the most results the model may ask for
+    pub fn with_max_results(mut self, max: usize) -> Self {
+        self.max_results = max.max(1);
+        self
+    }
+
+    /// Set how many characters of titles, URLs and snippets are returned
+    pub fn with_max_chars(mut self, max: usize) -> Self {
+        self.max_chars = max;
+        self
+    }
+
+    /// The provider searches go to
+    pub fn provider(&self) -> &dyn SearchProvider {
+        self.provider.as_ref()
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Searches the web and returns matching pages with their title, URL and a \
+         snippet. Use it for current events or facts you are unsure of, and cite \
+         the URLs you rely on."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Search terms"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": self.max_results,
+                    "description": "How many results to return"
+                },
+                "freshness": {
+                    "type": "string",
+                    "enum": ["day", "week", "month", "year"],
+                    "description": "Only return pages published within this period"
+                }
+            },
+            "required": ["query"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: SearchArgs = serde_json::from_value(args).map_err(|e| {
+            RragError::tool_execution(TOOL_NAME, format!("invalid arguments: {}", e))
+        })?;
+        if args.query.trim().is_empty() {
+            return Err(RragError::tool_execution(TOOL_NAME, "the query is empty"));
+        }
+        let query = SearchQuery {
+            query: args.query,
+            max_results: args
+                .max_results
+                .unwrap_or(DEFAULT_RESULTS)
+                .clamp(1, self.max_results),
+            freshness: args.freshness,
+        };
+        debug!(
+            provider = self.provider.name(),
+            query = %query.query,
+            max_results = query.max_results,
+            "Searching the web"
+        );
+
+        let mut hits = self.provider.search(&query).await?;
+        hits.truncate(query.max_results);
+        let (results, truncated) = fit_to_budget(hits, self.max_chars);
+        debug!(results = results.len(), truncated, "Web search finished");
+        Ok(ToolOutput::Json(serde_json::json!({
+            "query": query.query,
+            "results": results,
+            "truncated": truncated,
+        })))
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        CachePolicy::TtlSeconds(CACHE_TTL_SECS)
+    }
+}
+
+/// Keep hits within `max_chars` characters, cutting the snippet of the first
+/// hit that does not fit and dropping the rest
+fn fit_to_budget(hits: Vec<SearchHit>, max_chars: usize) -> (Vec<SearchHit>, bool) {
+    let mut room = max_chars;
+    let mut kept = Vec::with_capacity(hits.len());
+    for mut hit in hits {
+        let size = hit.title.chars().count() + hit.url.chars().count();
+        let snippet = hit.snippet.chars().count();
+        if size + snippet <= room {
+            room -= size + snippet;
+            kept.push(hit);
+            continue;
+        }
+        // A hit is only worth citing with its title and URL intact
+        if size < room {
+            hit.snippet = hit.snippet.chars().take(room - size).collect();
+            kept.push(hit);
+        }
+        return (kept, true);
+    }
+    (kept, false)
+}
+
+/// Provider chosen by the variables `var` looks up
+fn provider_from(var: impl Fn(&str) -> Option<String>) -> RragResult<Arc<dyn SearchProvider>> {
+    let configured = var(PROVIDER_ENV).map(|name| name.trim().to_ascii_lowercase());
+    let name = match configured {
+        Some(name) => name,
+        None if var("BRAVE_SEARCH_API_KEY").is_some() => "brave".to_string(),
+        None if var("SEARXNG_URL").is_some() => "searxng".to_string(),
+        None => {
+            return Err(RragError::config(
+                PROVIDER_ENV,
+                "brave or searxng, or BRAVE_SEARCH_API_KEY or SEARXNG_URL set",
+                "not set",
+            ))
+        }
+    };
+    match name.as_str() {
+        "brave" => brave_from(&var),
+        "searxng" => searxng_from(&var),
+        _ => Err(RragError::config(PROVIDER_ENV, "brave or searxng", name)),
+    }
+}
+
+#[cfg(feature = "search-brave")]
+fn brave_from(var: &impl Fn(&str) -> Option<String>) -> RragResult<Arc<dyn SearchProvider>> {
+    Ok(Arc::new(BraveSearch::from_var(var)?))
+}
+
+#[cfg(not(feature = "search-brave"))]
+fn brave_from(_var: &impl Fn(&str) -> Option<String>) -> RragResult<Arc<dyn SearchProvider>> {
+    Err(RragError::config(
+        PROVIDER_ENV,
+        "a provider enabled at build time",
+        "brave, which needs the search-brave feature",
+    ))
+}
+
+#[cfg(feature = "search-searxng")]
+fn searxng_from(var: &impl Fn(&str) -> Option<String>) -> RragResult<Arc<dyn SearchProvider>> {
+    Ok(Arc::new(SearxngSearch::from_var(var)?))
+}
+
+#[cfg(not(feature = "search-searxng"))]
+fn searxng_from(_var: &impl Fn(&str) -> Option<String>) -> RragResult<Arc<dyn SearchProvider>> {
+    Err(RragError::config(
+        PROVIDER_ENV,
+        "a provider enabled at build time",
+        "searxng, which needs the search-searxng feature",
+    ))
+}
+
+/// Send a provider request and decode its JSON response
+#[cfg(any(feature = "search-brave", feature = "search-searxng"))]
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    provider: &str,
+    request: reqwest::RequestBuilder,
+) -> RragResult<T> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| request_error(provider, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let excerpt: String = body.chars().take(200).collect();
+        return Err(RragError::tool_execution(
+            TOOL_NAME,
+            format!(
+                "{} search failed with status {}: {}",
+                provider, status, excerpt
+            ),
+        ));
+    }
+    response.json().await.map_err(|e| {
+        RragError::tool_execution(
+            TOOL_NAME,
+            format!("{} returned an unreadable response: {}", provider, e),
+        )
+    })
+}
+
+#[cfg(any(feature = "search-brave", feature = "search-searxng"))]
+fn request_error(provider: &str, error: reqwest::Error) -> RragError {
+    if error.is_timeout() {
+        RragError::timeout(
+            format!("{} search", provider),
+            REQUEST_TIMEOUT.as_millis() as u64,
+        )
+    } else {
+        RragError::network(format!("{} search", provider), error)
+    }
+}
+
+/// Snippet text with HTML tags removed and common entities decoded
+#[cfg(any(feature = "search-brave", feature = "search-searxng"))]
+fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Provider returning fixed hits and recording queries
+    #[derive(Debug, Default)]
+    struct FixedProvider {
+        hits: Vec<SearchHit>,
+        queries: Mutex<Vec<SearchQuery>>,
+    }
+
+    #[async_trait]
+    impl SearchProvider for FixedProvider {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn search(&self, query: &SearchQuery) -> RragResult<Vec<SearchHit>> {
+            self.queries.lock().unwrap().push(query.clone());
+            Ok(self.hits.clone())
+        }
+    }
+
+    fn hit(n: usize, snippet: &str) -> SearchHit {
+        SearchHit {
+            title: format!("Page {}", n),
+            url: format!("https://example.com/{}", n),
+            snippet: snippet.to_string(),
+        }
+    }
+
+    async fn call(tool: &WebSearchTool, args: serde_json::Value) -> RragResult<serde_json::Value> {
+        match tool.call(args).await? {
+            ToolOutput::Json(value) => Ok(value),
+            ToolOutput::Text(text) => panic!("expected JSON output, got {}", text),
+        }
+    }
+
+    #[test]
+    fn test_results_fit_character_budget() {
+        // Each hit is 6 + 21 characters before its snippet
+        let hits: Vec<_> = (1..=3).map(|n| hit(n, &"é".repeat(20))).collect();
+
+        let (kept, truncated) = fit_to_budget(hits.clone(), 1000);
+        assert_eq!(kept, hits);
+        assert!(!truncated);
+
+        let (kept, truncated) = fit_to_budget(hits.clone(), 47 + 27 + 5);
+        assert!(truncated);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0], hits[0]);
+        assert_eq!(kept[1].snippet, "é".repeat(5));
+
+        // A hit whose title and URL do not fit is dropped
+        let (kept, truncated) = fit_to_budget(hits, 47 + 20);
+        assert!(truncated);
+        assert_eq!(kept.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tool_clamps_results_and_truncates_output() {
+        let provider = Arc::new(FixedProvider {
+            hits: (1..=8).map(|n| hit(n, "Rust 1.80 was released")).collect(),
+            ..FixedProvider::default()
+        });
+        let tool = WebSearchTool::with_provider(provider.clone())
+            .with_max_results(6)
+            .with_max_chars(126);
+
+        let output = call(
+            &tool,
+            serde_json::json!({"query": "rust release", "max_results": 50, "freshness": "week"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            provider.queries.lock().unwrap()[0],
+            SearchQuery {
+                query: "rust release".to_string(),
+                max_results: 6,
+                freshness: Some(Freshness::Week),
+            }
+        );
+        assert_eq!(output["query"], "rust release");
+        assert_eq!(output["truncated"], true);
+        let results = output["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["url"], "https://example.com/1");
+        assert_eq!(results[2]["snippet"], "R");
+
+        let err = call(&tool, serde_json::json!({"query": " "}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RragError::ToolExecution { .. }));
+        assert!(call(
+            &tool,
+            serde_json::json!({"query": "x", "freshness": "decade"})
+        )
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_provider_from_environment() {
+        let provider = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            provider_from(|name| vars.get(name).cloned())
+        };
+
+        let err = provider(&[]).unwrap_err();
+        assert!(err.to_string().contains(PROVIDER_ENV));
+        let err = provider(&[(PROVIDER_ENV, "bing")]).unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::Configuration { ref actual, .. } if actual == "bing"
+        ));
+
+        #[cfg(feature = "search-brave")]
+        {
+            let err = provider(&[(PROVIDER_ENV, "brave")]).unwrap_err();
+            assert!(err.to_string().contains("BRAVE_SEARCH_API_KEY"));
+            let brave = provider(&[("BRAVE_SEARCH_API_KEY", "key")]).unwrap();
+            assert_eq!(brave.name(), "brave");
+        }
+        #[cfg(feature = "search-searxng")]
+        {
+            let err = provider(&[(PROVIDER_ENV, "searxng")]).unwrap_err();
+            assert!(err.to_string().contains("SEARXNG_URL"));
+            let searxng = provider(&[("SEARXNG_URL", "http://localhost:8888")]).unwrap();
+            assert_eq!(searxng.name(), "searxng");
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! SearxNG provider
+
+use super::{
+    fetch_json, plain_text, Freshness, SearchHit, SearchProvider, SearchQuery, REQUEST_TIMEOUT,
+};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// Environment variable holding the base URL of the instance
+const URL_ENV: &str = "SEARXNG_URL";
+
+/// Searches with a SearxNG instance
+///
+/// The instance must have the `json` format enabled under `search.formats` in
+/// its settings.
+#[derive(Debug, Clone)]
+pub struct SearxngSearch {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl SearxngSearch {
+    /// Create a provider for the instance at `base_url`, such as
+    /// `http://localhost:8888`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to create HTTP client"),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Create a provider for the instance in `SEARXNG_URL`
+    pub fn from_env() -> RragResult<Self> {
+        Self::from_var(&|name| std::env::var(name).ok())
+    }
+
+    pub(super) fn from_var(var: &impl Fn(&str) -> Option<String>) -> RragResult<Self> {
+        match var(URL_ENV).filter(|url| !url.trim().is_empty()) {
+            Some(url) => Ok(Self::new(url.trim())),
+            None => Err(RragError::config(
+                URL_ENV,
+                "the base URL of a SearxNG instance",
+                "not set",
+            )),
+        }
+    }
+}
+
+/// Part of the search response the provider reads
+#[derive(Debug, Deserialize)]
+struct SearxngResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearxngResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[async_trait]
+impl SearchProvider for SearxngSearch {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    async fn search(&self, query: &SearchQuery) -> RragResult<Vec<SearchHit>> {
+        let mut params = vec![("q", query.query.as_str()), ("format", "json")];
+        if let Some(freshness) = query.freshness {
+            params.push(("time_range", time_range(freshness)));
+        }
+        let request = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .query(&params);
+
+        // SearxNG pages its results and takes no count, so extra hits are dropped
+        let response: SearxngResponse = fetch_json(self.name(), request).await?;
+        Ok(response
+            .results
+            .into_iter()
+            .take(query.max_results)
+            .map(|result| SearchHit {
+                title: plain_text(&result.title),
+                url: result.url,
+                snippet: plain_text(&result.content),
+            })
+            .collect())
+    }
+}
+
+fn time_range(freshness: Freshness) -> &'static str {
+    match freshness {
+        Freshness::Day => "day",
+        Freshness::Week => "week",
+        Freshness::Month => "month",
+        Freshness::Year => "year",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_search_requests_json_and_parses_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("q", "rexis agents"))
+            .and(query_param("format", "json"))
+            .and(query_param("time_range", "month"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "query": "rexis agents",
+                "number_of_results": 3,
+                "results": [
+                    {
+                        "title": "Rexis",
+                        "url": "https://github.com/0xteamhq/rexis",
+                        "content": "Agents &amp; RAG",
+                        "engine": "duckduckgo"
+                    },
+                    {"url": "https://example.com/untitled"},
+                    {"title": "Third", "url": "https://example.com/3", "content": "Dropped"}
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let hits = SearxngSearch::new(format!("{}/", server.uri()))
+            .search(&SearchQuery {
+                query: "rexis agents".to_string(),
+                max_results: 2,
+                freshness: Some(Freshness::Month),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            hits,
+            [
+                SearchHit {
+                    title: "Rexis".to_string(),
+                    url: "https://github.com/0xteamhq/rexis".to_string(),
+                    snippet: "Agents & RAG".to_string(),
+                },
+                SearchHit {
+                    title: String::new(),
+                    url: "https://example.com/untitled".to_string(),
+                    snippet: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_reports_disabled_json_format() {
+        let server = MockServer::start().await;
+        Mock::given(path("/search"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("Forbidden"))
+            .mount(&server)
+            .await;
+
+        let err = SearxngSearch::new(server.uri())
+            .search(&SearchQuery {
+                query: "rust".to_string(),
+                max_results: 5,
+                freshness: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::ToolExecution { ref message, .. }
+                if message.contains("searxng") && message.contains("403")
+        ));
+    }
+}
@@ -0,0 +1,779 @@
+//! Allowlisted command tool
+//!
+//! [`ShellTool`] runs programs directly, never through a shell, so arguments
+//! cannot smuggle in pipes, redirections or substitutions. Every call is
+//! checked against the allowlist before anything is spawned: the program must
+//! be listed, each argument must match one of the program's patterns if it has
+//! any, and the working directory must lie inside the tool's [`FsSandbox`].
+//!
+//! Processes start with an empty environment plus the variables the tool
+//! passes through, and are killed when they outlive the timeout. On Unix each
+//! command leads its own process group and the whole group is killed, so
+//! processes it started in the background go with it. Programs are
+//! looked up on the `PATH` of the host process; on Windows the extensions in
+//! `PATHEXT` are tried as well, so `git` finds `git.exe`.
+
+use super::fs::{parse_args, text, FsError};
+use super::FsSandbox;
+use crate::agent::{Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tracing::{debug, warn};
+
+/// Name the model calls the tool by
+const TOOL_NAME: &str = "run_command";
+
+/// Time a command may run unless configured otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bytes of each output stream returned unless configured otherwise
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Environment variables passed through unless configured otherwise
+#[cfg(unix)]
+const DEFAULT_ENV: &[&str] = &["PATH"];
+
+/// Environment variables passed through unless configured otherwise; Windows
+/// programs commonly fail to start without `SystemRoot`
+#[cfg(windows)]
+const DEFAULT_ENV: &[&str] = &["PATH", "PATHEXT", "SystemRoot"];
+
+#[cfg(not(any(unix, windows)))]
+const DEFAULT_ENV: &[&str] = &["PATH"];
+
+/// Tool running allowlisted programs inside an [`FsSandbox`]
+///
+/// The result is JSON with the `exit_code`, `stdout` and `stderr`, and
+/// `success` set when the program exited with status zero. A non-zero exit is
+/// reported to the model rather than failing the call. Output streams over the
+/// byte limit are cut, with a marker at the end and `stdout_truncated` or
+/// `stderr_truncated` set. On Unix a process ended by a signal has no exit
+/// code and reports the `signal` instead.
+///
+/// Calls outside the policy fail with a message starting with
+/// `policy violation:` before any process is spawned. Note that the allowlist
+/// does not look inside arguments: give programs that take paths argument
+/// patterns to keep them in the sandbox.
+#[derive(Debug, Clone)]
+pub struct ShellTool {
+    sandbox: FsSandbox,
+    programs: HashMap<String, Vec<Regex>>,
+    env: Vec<String>,
+    timeout: Duration,
+    max_output_bytes: usize,
+}
+
+impl ShellTool {
+    /// Create the tool for `sandbox`, with nothing allowed yet
+    pub fn new(sandbox: FsSandbox) -> Self {
+        Self {
+            sandbox,
+            programs: HashMap::new(),
+            env: DEFAULT_ENV.iter().map(|name| name.to_string()).collect(),
+            timeout: DEFAULT_TIMEOUT,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+
+    /// Allow `program` with any arguments
+    ///
+    /// A bare name is looked up on the `PATH`; a path allows that executable
+    /// only.
+    pub fn allow(mut self, program: impl Into<String>) -> Self {
+        self.programs.insert(program.into(), Vec::new());
+        self
+    }
+
+    /// Allow `program` with arguments that each fully match one of `patterns`
+    ///
+    /// Fails when a pattern is not a valid regular expression.
+    pub fn allow_with_args<I, S>(
+        mut self,
+        program: impl Into<String>,
+        patterns: I,
+    ) -> RragResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+                    RragError::validation(
+                        "pattern",
+                        format!("must be a valid regular expression ({})", e),
+                        pattern,
+                    )
+                })
+            })
+            .collect::<RragResult<Vec<_>>>()?;
+        self.programs.insert(program.into(), patterns);
+        Ok(self)
+    }
+
+    /// Pass the host's value of the environment variable `name` to commands
+    ///
+    /// Only `PATH` is passed by default, along with `PATHEXT` and `SystemRoot`
+    /// on Windows.
+    pub fn with_env_var(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        if !self.env.contains(&name) {
+            self.env.push(name);
+        }
+        self
+    }
+
+    /// Set how long a command may run before it is killed; 30 seconds by
+    /// default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how many bytes of stdout and of stderr are returned
+    pub fn with_max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = max;
+        self
+    }
+
+    /// Check a call against the policy, returning the working directory
+    async fn check(&self, args: &CommandArgs) -> Result<PathBuf, ShellError> {
+        let Some(patterns) = self.programs.get(&args.program) else {
+            return Err(ShellError::NotAllowed(args.program.clone()));
+        };
+        if !patterns.is_empty() {
+            if let Some(argument) = args
+                .args
+                .iter()
+                .find(|argument| !patterns.iter().any(|pattern| pattern.is_match(argument)))
+            {
+                return Err(ShellError::ArgumentNotAllowed {
+                    program: args.program.clone(),
+                    argument: argument.clone(),
+                });
+            }
+        }
+
+        let working_dir = args.working_dir.as_deref().unwrap_or(".");
+        let dir = self
+            .sandbox
+            .resolve_existing(working_dir)
+            .await
+            .map_err(ShellError::WorkingDir)?;
+        if !dir.is_dir() {
+            return Err(ShellError::WorkingDir(FsError::NotADirectory(
+                working_dir.to_string(),
+            )));
+        }
+        Ok(dir)
+    }
+
+    async fn run(&self, args: &CommandArgs) -> Result<serde_json::Value, ShellError> {
+        let dir = self.check(args).await?;
+        let executable = find_executable(&args.program)
+            .ok_or_else(|| ShellError::NotFound(args.program.clone()))?;
+
+        let mut command = tokio::process::Command::new(&executable);
+        command
+            .args(&args.args)
+            .current_dir(&dir)
+            .env_clear()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+        for name in &self.env {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        let mut child = command.spawn().map_err(ShellError::Spawn)?;
+        let pid = child.id();
+        debug!(
+            program = %args.program,
+            pid,
+            dir = %dir.display(),
+            "Started command"
+        );
+
+        // Both streams are drained while the process runs, so it never blocks
+        // on a full pipe
+        let limit = self.max_output_bytes;
+        let stdout = child
+            .stdout
+            .take()
+            .map(|out| tokio::spawn(capture(out, limit)));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|err| tokio::spawn(capture(err, limit)));
+
+        let readers: Vec<_> = stdout
+            .iter()
+            .chain(stderr.iter())
+            .map(|task| task.abort_handle())
+            .collect();
+        let timed_out = || {
+            kill_group(pid);
+            for reader in &readers {
+                reader.abort();
+            }
+            ShellError::TimedOut(self.timeout)
+        };
+
+        // The deadline also covers reading the output, which stays open while
+        // a process the command left in the background holds the pipes
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let status = match tokio::time::timeout_at(deadline, child.wait()).await {
+            Ok(status) => status.map_err(ShellError::Spawn)?,
+            Err(_) => {
+                // The group is signalled while its unreaped leader still holds
+                // the group id; only then is the leader killed and reaped
+                let error = timed_out();
+                if let Err(e) = child.kill().await {
+                    warn!(program = %args.program, error = %e, "Failed to kill command");
+                }
+                return Err(error);
+            }
+        };
+        let outputs = async { (collect(stdout, limit).await, collect(stderr, limit).await) };
+        let ((stdout, stdout_truncated), (stderr, stderr_truncated)) =
+            tokio::time::timeout_at(deadline, outputs)
+                .await
+                .map_err(|_| timed_out())?;
+        debug!(program = %args.program, status = %status, "Command finished");
+
+        let mut output = serde_json::json!({
+            "exit_code": status.code(),
+            "success": status.success(),
+            "stdout": stdout,
+            "stderr": stderr,
+            "stdout_truncated": stdout_truncated,
+            "stderr_truncated": stderr_truncated,
+        });
+        if let Some(signal) = exit_signal(&status) {
+            output["signal"] = signal.into();
+        }
+        Ok(output)
+    }
+}
+
+/// Arguments of [`ShellTool`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CommandArgs {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+    working_dir: Option<String>,
+}
+
+#[async_trait]
+impl Tool for ShellTool {
+    fn name(&self) -> &str {
+        TOOL_NAME
+    }
+
+    fn description(&self) -> &str {
+        "Runs a program with arguments, without a shell: pipes, redirections and \
+         variables are not interpreted. Only allowed programs can be run. Returns \
+         the exit code, stdout and stderr."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        // Listed rather than an enum, so other programs get the policy error
+        let mut programs: Vec<&str> = self.programs.keys().map(String::as_str).collect();
+        programs.sort_unstable();
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "program": {
+                    "type": "string",
+                    "description": format!("Program to run: {}", programs.join(", "))
+                },
+                "args": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Arguments, one per item, such as [\"status\", \"--short\"]"
+                },
+                "working_dir": {
+                    "type": "string",
+                    "description": "Directory to run in, relative to the working directory"
+                }
+            },
+            "required": ["program"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn call(&self, args: serde_json::Value) -> RragResult<ToolOutput> {
+        let args: CommandArgs = parse_args(TOOL_NAME, args)?;
+        let output = self
+            .run(&args)
+            .await
+            .map_err(|e| RragError::tool_execution(TOOL_NAME, e.to_string()))?;
+        Ok(ToolOutput::Json(output))
+    }
+}
+
+/// Why a command was refused or failed
+///
+/// Refusals by the policy start with `policy violation:`, so the model can tell
+/// them apart from failures of the program.
+#[derive(Debug)]
+enum ShellError {
+    /// The program is not on the allowlist
+    NotAllowed(String),
+
+    /// An argument matches none of the program's patterns
+    ArgumentNotAllowed { program: String, argument: String },
+
+    /// The working directory is outside the sandbox or unusable
+    WorkingDir(FsError),
+
+    /// The allowed program is not installed
+    NotFound(String),
+
+    /// The process could not be started or waited for
+    Spawn(std::io::Error),
+
+    /// The process outlived the timeout and was killed
+    TimedOut(Duration),
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellError::NotAllowed(program) => {
+                write!(
+                    f,
+                    "policy violation: '{}' is not an allowed program",
+                    program
+                )
+            }
+            ShellError::ArgumentNotAllowed { program, argument } => write!(
+                f,
+                "policy violation: '{}' is not an allowed argument of '{}'",
+                argument, program
+            ),
+            ShellError::WorkingDir(error @ FsError::OutsideSandbox(_)) => {
+                write!(f, "policy violation: {}", error)
+            }
+            ShellError::WorkingDir(error) => write!(f, "working directory: {}", error),
+            ShellError::NotFound(program) => {
+                write!(f, "not found: '{}' is not installed", program)
+            }
+            ShellError::Spawn(error) => write!(f, "failed to run: {}", error),
+            ShellError::TimedOut(timeout) => {
+                write!(f, "timed out: killed after {}ms", timeout.as_millis())
+            }
+        }
+    }
+}
+
+/// Read a stream to its end, keeping the first `limit` bytes and the total
+async fn capture<R: AsyncRead + Unpin>(mut reader: R, limit: usize) -> (Vec<u8>, usize) {
+    let mut kept = Vec::new();
+    let mut total = 0;
+    let mut buffer = [0u8; 8192];
+    while let Ok(read) = reader.read(&mut buffer).await {
+        if read == 0 {
+            break;
+        }
+        let room = limit.saturating_sub(kept.len());
+        kept.extend_from_slice(&buffer[..read.min(room)]);
+        total += read;
+    }
+    (kept, total)
+}
+
+/// Text of a captured stream, with a marker when it was cut
+async fn collect(
+    task: Option<tokio::task::JoinHandle<(Vec<u8>, usize)>>,
+    limit: usize,
+) -> (String, bool) {
+    let captured = match task {
+        Some(task) => task.await.ok(),
+        None => None,
+    };
+    let Some((bytes, total)) = captured else {
+        return (String::new(), false);
+    };
+    let truncated = total > bytes.len();
+    let mut content = text(&bytes, truncated);
+    if truncated {
+        content.push_str(&format!(
+            "\n[truncated: showing the first {} of {} bytes]",
+            limit, total
+        ));
+    }
+    (content, truncated)
+}
+
+/// Path of `program`: itself when it is a path, otherwise the first match on
+/// the host's `PATH`
+fn find_executable(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 || path.is_absolute() {
+        return executable_at(path);
+    }
+    let search = std::env::var_os("PATH")?;
+    std::env::split_paths(&search).find_map(|dir| executable_at(&dir.join(program)))
+}
+
+/// `path` if an executable exists there
+#[cfg(unix)]
+fn executable_at(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    (metadata.is_file() && metadata.permissions().mode() & 0o111 != 0).then(|| path.to_path_buf())
+}
+
+/// `path`, or `path` with one of the `PATHEXT` extensions, if a file exists
+/// there
+#[cfg(windows)]
+fn executable_at(path: &Path) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    if path.extension().is_some() && path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let extensions =
+        std::env::var_os("PATHEXT").unwrap_or_else(|| OsString::from(".COM;.EXE;.BAT;.CMD"));
+    extensions
+        .to_string_lossy()
+        .split(';')
+        .filter(|extension| !extension.is_empty())
+        .map(|extension| {
+            let mut candidate: OsString = path.as_os_str().to_owned();
+            candidate.push(extension);
+            PathBuf::from(candidate)
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn executable_at(path: &Path) -> Option<PathBuf> {
+    path.is_file().then(|| path.to_path_buf())
+}
+
+/// Kill every process in the group the command leads
+#[cfg(unix)]
+fn kill_group(pid: Option<u32>) {
+    let Some(pid) = pid.and_then(|pid| i32::try_from(pid).ok()) else {
+        return;
+    };
+    // SAFETY: kill has no memory effects; the negative pid addresses the
+    // group the command was spawned into
+    if unsafe { libc::kill(-pid, libc::SIGKILL) } != 0 {
+        let error = std::io::Error::last_os_error();
+        // The group is already gone when all its processes have exited
+        if error.raw_os_error() != Some(libc::ESRCH) {
+            warn!(pid, error = %error, "Failed to kill process group");
+        }
+    }
+}
+
+/// Processes the command started are left running; only the command itself is
+/// killed
+#[cfg(not(unix))]
+fn kill_group(_pid: Option<u32>) {}
+
+/// Signal that ended the process, if any
+#[cfg(unix)]
+fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+/// Signal that ended the process; Windows processes always have an exit code
+#[cfg(not(unix))]
+fn exit_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn shell() -> (TempDir, ShellTool) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("work")).unwrap();
+        let sandbox = FsSandbox::new(dir.path().join("work")).unwrap();
+        (dir, ShellTool::new(sandbox))
+    }
+
+    async fn call(tool: &ShellTool, args: serde_json::Value) -> RragResult<serde_json::Value> {
+        match tool.call(args).await? {
+            ToolOutput::Json(value) => Ok(value),
+            ToolOutput::Text(text) => panic!("expected JSON output, got {}", text),
+        }
+    }
+
+    fn error_message(result: RragResult<serde_json::Value>) -> String {
+        match result {
+            Err(RragError::ToolExecution { message, .. }) => message,
+            other => panic!("expected a tool error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowed_command_runs_in_sandbox() {
+        let (dir, tool) = shell();
+        std::fs::write(dir.path().join("work/notes.txt"), "hello").unwrap();
+        let tool = tool.allow("ls").allow("pwd");
+
+        let output = call(&tool, serde_json::json!({"program": "ls"}))
+            .await
+            .unwrap();
+        assert_eq!(output["stdout"], "notes.txt\n");
+        assert_eq!(output["exit_code"], 0);
+        assert_eq!(output["success"], true);
+
+        let output = call(
+            &tool,
+            serde_json::json!({"program": "ls", "args": ["missing"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["success"], false);
+        assert!(!output["stderr"].as_str().unwrap().is_empty());
+
+        let output = call(&tool, serde_json::json!({"program": "pwd"}))
+            .await
+            .unwrap();
+        let work = std::fs::canonicalize(dir.path().join("work")).unwrap();
+        assert_eq!(output["stdout"], format!("{}\n", work.display()));
+    }
+
+    #[tokio::test]
+    async fn test_calls_outside_policy_are_refused_before_spawning() {
+        let (dir, tool) = shell();
+        let tool = tool
+            .allow_with_args("touch", [r"[a-z]+\.txt"])
+            .unwrap()
+            .allow("ls");
+
+        let message = error_message(
+            call(
+                &tool,
+                serde_json::json!({"program": "rm", "args": ["-rf", "."]}),
+            )
+            .await,
+        );
+        assert_eq!(message, "policy violation: 'rm' is not an allowed program");
+
+        let message = error_message(
+            call(
+                &tool,
+                serde_json::json!({"program": "touch", "args": ["../escape.txt"]}),
+            )
+            .await,
+        );
+        assert!(message.starts_with("policy violation: '../escape.txt'"));
+        assert!(!dir.path().join("escape.txt").exists());
+
+        let message = error_message(
+            call(
+                &tool,
+                serde_json::json!({"program": "ls", "working_dir": ".."}),
+            )
+            .await,
+        );
+        assert!(message.starts_with("policy violation: outside sandbox"));
+
+        let err = ShellTool::new(FsSandbox::new(dir.path()).unwrap())
+            .allow_with_args("ls", ["("])
+            .unwrap_err();
+        assert!(matches!(err, RragError::Validation { ref field, .. } if field == "pattern"));
+    }
+
+    #[tokio::test]
+    async fn test_environment_is_scrubbed() {
+        let (_dir, tool) = shell();
+        let tool = tool.allow("env");
+
+        let output = call(&tool, serde_json::json!({"program": "env"}))
+            .await
+            .unwrap();
+        let names: Vec<&str> = output["stdout"]
+            .as_str()
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.split('=').next())
+            .collect();
+        assert!(names.iter().all(|&name| name == "PATH"), "{:?}", names);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_the_process() {
+        let (_dir, tool) = shell();
+        let tool = tool.allow("sleep").with_timeout(Duration::from_millis(200));
+
+        let started = Instant::now();
+        let message = error_message(
+            call(
+                &tool,
+                serde_json::json!({"program": "sleep", "args": ["10"]}),
+            )
+            .await,
+        );
+        assert_eq!(message, "timed out: killed after 200ms");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_background_processes() {
+        let (dir, tool) = shell();
+        let tool = tool.allow("sh").with_timeout(Duration::from_millis(300));
+
+        // The shell exits at once, leaving a process holding its output open
+        let started = Instant::now();
+        let message = error_message(
+            call(
+                &tool,
+                serde_json::json!({
+                    "program": "sh",
+                    "args": ["-c", "(sleep 1; touch late.txt) & echo started"]
+                }),
+            )
+            .await,
+        );
+        assert_eq!(message, "timed out: killed after 300ms");
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.path().join("work/late.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_the_group_of_a_running_command() {
+        let (dir, tool) = shell();
+        let tool = tool.allow("sh").with_timeout(Duration::from_millis(300));
+
+        // The shell is still running at the deadline, next to its background job
+        let message = error_message(
+            call(
+                &tool,
+                serde_json::json!({
+                    "program": "sh",
+                    "args": ["-c", "(sleep 1; touch late.txt) & sleep 10"]
+                }),
+            )
+            .await,
+        );
+        assert_eq!(message, "timed out: killed after 300ms");
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.path().join("work/late.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_large_output_is_truncated() {
+        let (_dir, tool) = shell();
+        let tool = tool.allow("seq").with_max_output_bytes(20);
+
+        let output = call(
+            &tool,
+            serde_json::json!({"program": "seq", "args": ["1", "20000"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["success"], true);
+        assert_eq!(output["stdout_truncated"], true);
+        let stdout = output["stdout"].as_str().unwrap();
+        assert!(stdout.starts_with("1\n2\n3\n"));
+        assert!(stdout.ends_with("[truncated: showing the first 20 of 108894 bytes]"));
+        assert_eq!(output["stderr_truncated"], false);
+    }
+}
+
+#[cfg(all(test, windows))]
+mod windows_tests {
+    use super::*;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn shell() -> (TempDir, ShellTool) {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = FsSandbox::new(dir.path()).unwrap();
+        (dir, ShellTool::new(sandbox))
+    }
+
+    async fn call(tool: &ShellTool, args: serde_json::Value) -> RragResult<serde_json::Value> {
+        match tool.call(args).await? {
+            ToolOutput::Json(value) => Ok(value),
+            ToolOutput::Text(text) => panic!("expected JSON output, got {}", text),
+        }
+    }
+
+    #[test]
+    fn test_programs_are_found_with_pathext() {
+        let cmd = find_executable("cmd").unwrap();
+        assert!(cmd
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exe")));
+        assert_eq!(find_executable("cmd.exe"), Some(cmd.clone()));
+        assert_eq!(find_executable(cmd.to_str().unwrap()), Some(cmd));
+        assert!(find_executable("surely-not-installed").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exit_code_and_environment_are_reported() {
+        let (_dir, tool) = shell();
+        let tool = tool.allow("cmd");
+
+        let output = call(
+            &tool,
+            serde_json::json!({"program": "cmd", "args": ["/C", "exit", "3"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(output["exit_code"], 3);
+        assert_eq!(output["success"], false);
+        assert!(output.get("signal").is_none());
+
+        // SystemRoot is passed through by default
+        let output = call(
+            &tool,
+            serde_json::json!({"program": "cmd", "args": ["/C", "echo", "%SystemRoot%"]}),
+        )
+        .await
+        .unwrap();
+        let stdout = output["stdout"].as_str().unwrap().trim();
+        assert!(!stdout.is_empty());
+        assert_ne!(stdout, "%SystemRoot%");
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_the_process() {
+        let (_dir, tool) = shell();
+        let tool = tool.allow("ping").with_timeout(Duration::from_millis(200));
+
+        let started = Instant::now();
+        let result = call(
+            &tool,
+            serde_json::json!({"program": "ping", "args": ["-n", "10", "127.0.0.1"]}),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(RragError::ToolExecution { ref message, .. })
+                if message == "timed out: killed after 200ms"
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
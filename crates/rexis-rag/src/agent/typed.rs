@@ -0,0 +1,575 @@
+//! Typed tools
+//!
+//! [`ToolArgs`] describes an argument type as JSON Schema, so a tool's
+//! parameters follow from the struct its arguments deserialize into instead
+//! of being written by hand. With the `macros` feature, `#[derive(ToolArgs)]`
+//! generates the implementation; [`typed_tool`] turns an async function over
+//! such a type into a [`Tool`].
+
+use super::{CachePolicy, Tool, ToolOutput};
+use crate::error::{RragError, RragResult};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::marker::PhantomData;
+
+/// A type the model can supply as tool arguments
+///
+/// Schemas of nested types are inlined, so recursive types are not supported.
+pub trait ToolArgs {
+    /// Whether a field of this type may be left out
+    const OPTIONAL: bool = false;
+
+    /// JSON Schema of the type
+    fn json_schema() -> Value;
+}
+
+macro_rules! impl_tool_args {
+    ($schema:tt => $($ty:ty),+) => {
+        $(
+            impl ToolArgs for $ty {
+                fn json_schema() -> Value {
+                    json!($schema)
+                }
+            }
+        )+
+    };
+}
+
+impl_tool_args!({"type": "string"} => String, char);
+impl_tool_args!({"type": "boolean"} => bool);
+impl_tool_args!({"type": "integer"} => i8, i16, i32, i64, i128, isize);
+impl_tool_args!({"type": "integer", "minimum": 0} => u8, u16, u32, u64, u128, usize);
+impl_tool_args!({"type": "number"} => f32, f64);
+impl_tool_args!({} => Value);
+
+impl<T: ToolArgs> ToolArgs for Option<T> {
+    const OPTIONAL: bool = true;
+
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+}
+
+impl<T: ToolArgs> ToolArgs for Box<T> {
+    const OPTIONAL: bool = T::OPTIONAL;
+
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+}
+
+impl<T: ToolArgs> ToolArgs for Vec<T> {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema()})
+    }
+}
+
+impl<T: ToolArgs> ToolArgs for HashSet<T> {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema(), "uniqueItems": true})
+    }
+}
+
+impl<T: ToolArgs> ToolArgs for BTreeSet<T> {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema(), "uniqueItems": true})
+    }
+}
+
+impl<V: ToolArgs> ToolArgs for HashMap<String, V> {
+    fn json_schema() -> Value {
+        json!({"type": "object", "additionalProperties": V::json_schema()})
+    }
+}
+
+impl<V: ToolArgs> ToolArgs for BTreeMap<String, V> {
+    fn json_schema() -> Value {
+        json!({"type": "object", "additionalProperties": V::json_schema()})
+    }
+}
+
+/// Builder for the JSON Schema of an object
+#[derive(Debug, Clone, Default)]
+pub struct ObjectSchema {
+    description: Option<String>,
+    properties: Map<String, Value>,
+    required: Vec<String>,
+    deny_unknown_fields: bool,
+}
+
+impl ObjectSchema {
+    /// Create an object without properties
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describe the object; an empty description is left out
+    pub fn description(mut self, description: &str) -> Self {
+        if !description.is_empty() {
+            self.description = Some(description.to_string());
+        }
+        self
+    }
+
+    /// Add a field, required unless `T` is optional
+    pub fn field<T: ToolArgs>(self, name: &str, description: &str) -> Self {
+        self.property(name, T::json_schema(), description, !T::OPTIONAL)
+    }
+
+    /// Add a field the arguments type fills in when it is absent
+    pub fn optional_field<T: ToolArgs>(self, name: &str, description: &str) -> Self {
+        self.property(name, T::json_schema(), description, false)
+    }
+
+    /// Add a required field holding a fixed string, such as an enum tag
+    pub fn constant(self, name: &str, value: &str) -> Self {
+        self.property(name, json!({"type": "string", "enum": [value]}), "", true)
+    }
+
+    /// Add a property with an explicit schema
+    pub fn property(
+        mut self,
+        name: &str,
+        mut schema: Value,
+        description: &str,
+        required: bool,
+    ) -> Self {
+        describe(&mut schema, description);
+        self.properties.insert(name.to_string(), schema);
+        if required {
+            self.required.push(name.to_string());
+        }
+        self
+    }
+
+    /// Reject properties that were not declared
+    pub fn deny_unknown_fields(mut self) -> Self {
+        self.deny_unknown_fields = true;
+        self
+    }
+
+    /// Build the schema
+    pub fn build(self) -> Value {
+        let mut schema = json!({"type": "object", "properties": self.properties});
+        if !self.required.is_empty() {
+            schema["required"] = json!(self.required);
+        }
+        if self.deny_unknown_fields {
+            schema["additionalProperties"] = Value::Bool(false);
+        }
+        describe(&mut schema, self.description.as_deref().unwrap_or_default());
+        schema
+    }
+}
+
+/// Builder for the JSON Schema of an enum
+///
+/// Unit variants are plain strings; variants carrying data are added as the
+/// schema of their serialized form and combined with `oneOf`.
+#[derive(Debug, Clone, Default)]
+pub struct EnumSchema {
+    description: Option<String>,
+    units: Vec<String>,
+    variants: Vec<Value>,
+}
+
+impl EnumSchema {
+    /// Create an enum without variants
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describe the enum; an empty description is left out
+    pub fn description(mut self, description: &str) -> Self {
+        if !description.is_empty() {
+            self.description = Some(description.to_string());
+        }
+        self
+    }
+
+    /// Add a variant serialized as the string `name`
+    pub fn unit(mut self, name: &str) -> Self {
+        self.units.push(name.to_string());
+        self
+    }
+
+    /// Add a variant serialized as a value matching `schema`
+    pub fn variant(mut self, schema: Value) -> Self {
+        self.variants.push(schema);
+        self
+    }
+
+    /// Build the schema
+    pub fn build(self) -> Value {
+        let units = json!({"type": "string", "enum": self.units});
+        let mut schema = if self.variants.is_empty() {
+            units
+        } else {
+            let mut one_of = Vec::with_capacity(self.variants.len() + 1);
+            if !self.units.is_empty() {
+                one_of.push(units);
+            }
+            one_of.extend(self.variants);
+            json!({"oneOf": one_of})
+        };
+        describe(&mut schema, self.description.as_deref().unwrap_or_default());
+        schema
+    }
+}
+
+/// Set the description of an object schema unless `description` is empty
+fn describe(schema: &mut Value, description: &str) {
+    if let (Some(schema), false) = (schema.as_object_mut(), description.is_empty()) {
+        schema.insert("description".to_string(), json!(description));
+    }
+}
+
+/// Tool calling an async function with typed arguments
+///
+/// Arguments are deserialized into `Args` before the call and the result is
+/// serialized for the model; a string result is sent as plain text.
+pub struct TypedTool<Args, Out, F> {
+    name: String,
+    description: String,
+    handler: F,
+    cache_policy: CachePolicy,
+    _types: PhantomData<fn(Args) -> Out>,
+}
+
+impl<Args, Out, F> TypedTool<Args, Out, F> {
+    /// Create a tool calling `handler`
+    pub fn new(name: impl Into<String>, description: impl Into<String>, handler: F) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            handler,
+            cache_policy: CachePolicy::NoCache,
+            _types: PhantomData,
+        }
+    }
+
+    /// Set the cache policy of the tool
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+}
+
+impl<Args, Out, F> std::fmt::Debug for TypedTool<Args, Out, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedTool")
+            .field("name", &self.name)
+            .field("cache_policy", &self.cache_policy)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<Args, Out, F, Fut> Tool for TypedTool<Args, Out, F>
+where
+    Args: ToolArgs + DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+    F: Fn(Args) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = RragResult<Out>> + Send + 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        Args::json_schema()
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        self.cache_policy
+    }
+
+    async fn call(&self, args: Value) -> RragResult<ToolOutput> {
+        let args: Args = serde_json::from_value(args).map_err(|e| {
+            RragError::tool_execution(&self.name, format!("Invalid arguments: {}", e))
+        })?;
+        let output = (self.handler)(args).await?;
+        let value = serde_json::to_value(output).map_err(|e| {
+            RragError::tool_execution(&self.name, format!("Unserializable result: {}", e))
+        })?;
+
+        Ok(match value {
+            Value::String(text) => ToolOutput::Text(text),
+            value => ToolOutput::Json(value),
+        })
+    }
+}
+
+/// Create a tool from an async function over typed arguments
+///
+/// ```rust,ignore
+/// let forecast = typed_tool("forecast", "Weather forecast", |args: ForecastArgs| async move {
+///     Ok(format!("Sunny in {}", args.city))
+/// });
+/// ```
+pub fn typed_tool<Args, Out, F, Fut>(
+    name: impl Into<String>,
+    description: impl Into<String>,
+    handler: F,
+) -> Box<dyn Tool>
+where
+    Args: ToolArgs + DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+    F: Fn(Args) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = RragResult<Out>> + Send + 'static,
+{
+    Box::new(TypedTool::new(name, description, handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    impl ToolArgs for Point {
+        fn json_schema() -> Value {
+            ObjectSchema::new()
+                .field::<i64>("x", "Horizontal position")
+                .field::<i64>("y", "")
+                .build()
+        }
+    }
+
+    #[test]
+    fn test_object_schema_builder() {
+        assert_eq!(
+            Point::json_schema(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "x": {"type": "integer", "description": "Horizontal position"},
+                    "y": {"type": "integer"}
+                },
+                "required": ["x", "y"]
+            })
+        );
+        assert_eq!(
+            <Option<Vec<u8>>>::json_schema(),
+            json!({"type": "array", "items": {"type": "integer", "minimum": 0}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool_deserializes_arguments() {
+        let tool = typed_tool("add", "Adds coordinates", |point: Point| async move {
+            Ok(json!({"sum": point.x + point.y}))
+        });
+
+        assert_eq!(tool.definition().parameters, Point::json_schema());
+        assert_eq!(
+            tool.call(json!({"x": 2, "y": 3})).await.unwrap(),
+            ToolOutput::Json(json!({"sum": 5}))
+        );
+
+        let err = tool.call(json!({"x": "two"})).await.unwrap_err();
+        assert!(matches!(
+            err,
+            RragError::ToolExecution { ref message, .. } if message.starts_with("Invalid arguments")
+        ));
+    }
+
+    #[cfg(feature = "macros")]
+    mod derive {
+        use super::super::*;
+        use crate::agent::{AgentBuilder, ToolArgs};
+        use rexis_llm::testing::{respond_text, respond_with_tool_call, MockClient};
+        use serde::{Deserialize, Serialize};
+
+        /// Search the product catalog
+        #[derive(Debug, Deserialize, ToolArgs)]
+        #[tool_args(crate = "crate")]
+        #[allow(dead_code)]
+        struct SearchArgs {
+            /// Words to look for
+            query: String,
+            /// Maximum number of results
+            limit: Option<u32>,
+            /// Categories to search in
+            categories: Vec<Category>,
+            /// Price bounds
+            price: PriceRange,
+            #[serde(default)]
+            in_stock_only: bool,
+            #[serde(skip)]
+            internal: Option<String>,
+        }
+
+        /// Product category
+        #[derive(Debug, Deserialize, ToolArgs)]
+        #[serde(rename_all = "snake_case")]
+        #[tool_args(crate = "crate")]
+        enum Category {
+            HomeGarden,
+            Books,
+            Electronics,
+        }
+
+        #[derive(Debug, Deserialize, ToolArgs)]
+        #[tool_args(crate = "crate")]
+        #[allow(dead_code)]
+        struct PriceRange {
+            /// Lowest price, inclusive
+            min: f64,
+            /// Highest price, inclusive
+            max: Option<f64>,
+        }
+
+        /// Change to apply to a document
+        #[derive(Debug, PartialEq, Deserialize, ToolArgs)]
+        #[serde(tag = "op", rename_all = "snake_case")]
+        #[tool_args(crate = "crate")]
+        enum Edit {
+            /// Insert text at a position
+            Insert { position: usize, text: String },
+            /// Delete a range
+            Delete { start: usize, end: usize },
+            /// Remove everything
+            Clear,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize, ToolArgs)]
+        #[tool_args(crate = "crate")]
+        enum Target {
+            All,
+            Ids(Vec<u64>),
+            Query {
+                text: String,
+                #[serde(rename = "max")]
+                limit: Option<u32>,
+            },
+        }
+
+        fn assert_golden(schema: Value, golden: &str) {
+            let expected: Value = serde_json::from_str(golden).unwrap();
+            assert_eq!(
+                schema,
+                expected,
+                "generated schema:\n{}",
+                serde_json::to_string_pretty(&schema).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_struct_schema_matches_golden() {
+            assert_golden(
+                SearchArgs::json_schema(),
+                include_str!("../../testdata/tool_args/search_args.json"),
+            );
+        }
+
+        #[test]
+        fn test_enum_schemas_match_golden() {
+            assert_golden(
+                Edit::json_schema(),
+                include_str!("../../testdata/tool_args/edit.json"),
+            );
+            assert_golden(
+                Target::json_schema(),
+                include_str!("../../testdata/tool_args/target.json"),
+            );
+        }
+
+        #[test]
+        fn test_schema_follows_serde_representation() {
+            let edit: Edit =
+                serde_json::from_value(json!({"op": "insert", "position": 3, "text": "x"}))
+                    .unwrap();
+            assert_eq!(
+                edit,
+                Edit::Insert {
+                    position: 3,
+                    text: "x".to_string()
+                }
+            );
+
+            let target: Target =
+                serde_json::from_value(json!({"Query": {"text": "rust", "max": 5}})).unwrap();
+            assert_eq!(
+                target,
+                Target::Query {
+                    text: "rust".to_string(),
+                    limit: Some(5)
+                }
+            );
+            assert_eq!(
+                serde_json::from_value::<Target>(json!("All")).unwrap(),
+                Target::All
+            );
+        }
+
+        /// Weather forecast request
+        #[derive(Debug, Deserialize, ToolArgs)]
+        #[tool_args(crate = "crate")]
+        struct ForecastArgs {
+            /// City to forecast
+            city: String,
+            /// Days ahead
+            days: Option<u32>,
+        }
+
+        #[derive(Debug, Serialize)]
+        struct Forecast {
+            city: String,
+            days: u32,
+            sky: &'static str,
+        }
+
+        #[tokio::test]
+        async fn test_agent_runs_typed_tool() {
+            let mock = MockClient::builder()
+                .on_user_message_containing(
+                    "Paris",
+                    respond_with_tool_call("forecast", json!({"city": "Paris", "days": 2})),
+                )
+                .on_tool_result("forecast", respond_text("Sunny for two days."))
+                .build();
+            let forecast = typed_tool(
+                "forecast",
+                "Weather forecast for a city",
+                |args: ForecastArgs| async move {
+                    Ok(Forecast {
+                        city: args.city,
+                        days: args.days.unwrap_or(1),
+                        sky: "sunny",
+                    })
+                },
+            );
+            let mut agent = AgentBuilder::new()
+                .with_llm(mock.client())
+                .with_tool(forecast)
+                .build()
+                .unwrap();
+
+            let result = agent.run_detailed("Forecast for Paris?").await.unwrap();
+
+            assert_eq!(result.output, "Sunny for two days.");
+            assert_eq!(
+                result.tool_invocations[0].result,
+                r#"{"city":"Paris","days":2,"sky":"sunny"}"#
+            );
+            let offered = &mock.requests()[0].tools;
+            assert_eq!(offered.len(), 1);
+            assert_eq!(offered[0].parameters, ForecastArgs::json_schema());
+            assert_eq!(offered[0].parameters["required"], json!(["city"]));
+        }
+    }
+}
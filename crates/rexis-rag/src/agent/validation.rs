@@ -0,0 +1,486 @@
+//! Validation of tool arguments against the tool's parameters schema
+//!
+//! Covers the JSON Schema keywords tool schemas use: `type`, `enum`, `const`,
+//! string length and `pattern`, numeric bounds and `multipleOf`, array
+//! `items` and bounds, object `properties`, `required` and
+//! `additionalProperties`, and `allOf`, `anyOf` and `oneOf`. Other keywords,
+//! `$ref` and `format` included, are not checked, so such a schema accepts more
+//! than it declares rather than refusing valid calls. `oneOf` is checked like
+//! `anyOf`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+/// A constraint of a tool's parameters schema that the arguments break
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArgumentViolation {
+    /// Where the offending value is, such as `$.items[2].name`; `$` stands for
+    /// the arguments as a whole
+    pub path: String,
+
+    /// The constraint that was broken
+    pub message: String,
+}
+
+impl fmt::Display for ArgumentViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Check `args` against the JSON Schema `schema`, returning every constraint
+/// they break; an empty list means the arguments are valid
+///
+/// Optional properties set to `null` count as left out, since models often
+/// send them that way. Messages describe the constraint, never the value, so
+/// secrets in arguments do not end up in logs.
+pub fn validate_arguments(schema: &Value, args: &Value) -> Vec<ArgumentViolation> {
+    let mut violations = Vec::new();
+    check(schema, args, "$", &mut violations);
+    violations
+}
+
+fn check(schema: &Value, value: &Value, path: &str, violations: &mut Vec<ArgumentViolation>) {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(false) => {
+            violate(violations, path, "no value is allowed here");
+            return;
+        }
+        _ => return,
+    };
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(expected)) => vec![expected.as_str()],
+        Some(Value::Array(expected)) => expected.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|expected| has_type(value, expected)) {
+        let message = format!("expected {}, got {}", types.join(" or "), type_name(value));
+        violate(violations, path, message);
+        // Any further check would only restate the mismatch
+        return;
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            violate(
+                violations,
+                path,
+                format!("must be one of {}", allowed.join(", ")),
+            );
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            violate(violations, path, format!("must be {}", expected));
+        }
+    }
+
+    match value {
+        Value::String(text) => check_string(schema, text, path, violations),
+        Value::Number(number) => check_number(schema, number, path, violations),
+        Value::Array(items) => check_array(schema, items, path, violations),
+        Value::Object(object) => check_object(schema, object, path, violations),
+        _ => {}
+    }
+
+    if let Some(Value::Array(schemas)) = schema.get("allOf") {
+        for schema in schemas {
+            check(schema, value, path, violations);
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(Value::Array(schemas)) = schema.get(keyword) {
+            check_alternatives(schemas, value, path, violations);
+        }
+    }
+}
+
+fn check_string(
+    schema: &Map<String, Value>,
+    text: &str,
+    path: &str,
+    violations: &mut Vec<ArgumentViolation>,
+) {
+    let length = text.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+        if length < min {
+            violate(
+                violations,
+                path,
+                format!("must be at least {} characters long", min),
+            );
+        }
+    }
+    if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+        if length > max {
+            violate(
+                violations,
+                path,
+                format!("must be at most {} characters long", max),
+            );
+        }
+    }
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        // A pattern the regex crate cannot compile is not checked
+        if let Ok(regex) = Regex::new(pattern) {
+            if !regex.is_match(text) {
+                violate(
+                    violations,
+                    path,
+                    format!("must match the pattern {}", pattern),
+                );
+            }
+        }
+    }
+}
+
+fn check_number(
+    schema: &Map<String, Value>,
+    number: &Number,
+    path: &str,
+    violations: &mut Vec<ArgumentViolation>,
+) {
+    let Some(number) = number.as_f64() else {
+        return;
+    };
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    // Draft 4 marks an exclusive bound with a boolean next to it
+    let exclusive = |keyword: &str| schema.get(keyword).and_then(Value::as_bool) == Some(true);
+
+    if let Some(min) = bound("minimum") {
+        if exclusive("exclusiveMinimum") && number <= min {
+            violate(violations, path, format!("must be greater than {}", min));
+        } else if number < min {
+            violate(violations, path, format!("must be at least {}", min));
+        }
+    }
+    if let Some(max) = bound("maximum") {
+        if exclusive("exclusiveMaximum") && number >= max {
+            violate(violations, path, format!("must be less than {}", max));
+        } else if number > max {
+            violate(violations, path, format!("must be at most {}", max));
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum") {
+        if number <= min {
+            violate(violations, path, format!("must be greater than {}", min));
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum") {
+        if number >= max {
+            violate(violations, path, format!("must be less than {}", max));
+        }
+    }
+    if let Some(factor) = bound("multipleOf").filter(|factor| *factor > 0.0) {
+        let quotient = number / factor;
+        if (quotient - quotient.round()).abs() > 1e-9 {
+            violate(
+                violations,
+                path,
+                format!("must be a multiple of {}", factor),
+            );
+        }
+    }
+}
+
+fn check_array(
+    schema: &Map<String, Value>,
+    items: &[Value],
+    path: &str,
+    violations: &mut Vec<ArgumentViolation>,
+) {
+    let count = items.len() as u64;
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+        if count < min {
+            violate(
+                violations,
+                path,
+                format!("must have at least {} items", min),
+            );
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+        if count > max {
+            violate(violations, path, format!("must have at most {} items", max));
+        }
+    }
+    if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+        let repeated = items
+            .iter()
+            .enumerate()
+            .any(|(i, item)| items[..i].contains(item));
+        if repeated {
+            violate(violations, path, "items must be unique");
+        }
+    }
+
+    match schema.get("items") {
+        // Tuple form: one schema per position
+        Some(Value::Array(schemas)) => {
+            for (i, (schema, item)) in schemas.iter().zip(items).enumerate() {
+                check(schema, item, &format!("{}[{}]", path, i), violations);
+            }
+        }
+        Some(schema) => {
+            for (i, item) in items.iter().enumerate() {
+                check(schema, item, &format!("{}[{}]", path, i), violations);
+            }
+        }
+        None => {}
+    }
+}
+
+fn check_object(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+    violations: &mut Vec<ArgumentViolation>,
+) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    for name in &required {
+        if !object.contains_key(*name) {
+            violate(violations, &format!("{}.{}", path, name), "is required");
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, value) in object {
+        let property_path = format!("{}.{}", path, name);
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(_) if value.is_null() && !required.contains(&name.as_str()) => {}
+            Some(property) => check(property, value, &property_path, violations),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    violate(violations, &property_path, "is not a known property")
+                }
+                Some(additional) => check(additional, value, &property_path, violations),
+                None => {}
+            },
+        }
+    }
+}
+
+/// Check `value` against alternative schemas, reporting why the closest one
+/// failed when none matches
+fn check_alternatives(
+    schemas: &[Value],
+    value: &Value,
+    path: &str,
+    violations: &mut Vec<ArgumentViolation>,
+) {
+    let mut closest: Option<Vec<ArgumentViolation>> = None;
+    for schema in schemas {
+        let mut attempt = Vec::new();
+        check(schema, value, path, &mut attempt);
+        if attempt.is_empty() {
+            return;
+        }
+        if closest
+            .as_ref()
+            .map_or(true, |closest| attempt.len() < closest.len())
+        {
+            closest = Some(attempt);
+        }
+    }
+    if let Some(closest) = closest {
+        violate(
+            violations,
+            path,
+            format!("must match one of {} allowed shapes", schemas.len()),
+        );
+        violations.extend(closest);
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // Not a type this validator knows, so not one it can rule out
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::String(_) => "string",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn violate(violations: &mut Vec<ArgumentViolation>, path: &str, message: impl Into<String>) {
+    violations.push(ArgumentViolation {
+        path: path.to_string(),
+        message: message.into(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn messages(schema: Value, args: Value) -> Vec<String> {
+        validate_arguments(&schema, &args)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_arguments_pass() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string", "minLength": 1},
+                "days": {"type": "integer", "minimum": 1, "maximum": 14},
+                "units": {"type": "string", "enum": ["metric", "imperial"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "uniqueItems": true}
+            },
+            "required": ["city"],
+            "additionalProperties": false
+        });
+        let args = json!({"city": "Paris", "days": 3, "units": "metric", "tags": ["a", "b"]});
+        assert!(validate_arguments(&schema, &args).is_empty());
+        // An optional property sent as null counts as left out
+        assert!(validate_arguments(&schema, &json!({"city": "Paris", "days": null})).is_empty());
+    }
+
+    #[test]
+    fn test_every_violation_is_listed_with_its_path() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "city": {"type": "string"},
+                "days": {"type": "integer", "minimum": 1},
+                "units": {"type": "string", "enum": ["metric", "imperial"]},
+                "stops": {
+                    "type": "array",
+                    "items": {"type": "object", "properties": {"name": {"type": "string"}}}
+                }
+            },
+            "required": ["city"],
+            "additionalProperties": false
+        });
+        let args = json!({
+            "days": 0,
+            "units": "kelvin",
+            "stops": [{"name": "Lyon"}, {"name": 7}],
+            "country": "FR"
+        });
+        let mut listed = messages(schema, args);
+        listed.sort();
+        assert_eq!(
+            listed,
+            [
+                "$.city: is required",
+                "$.country: is not a known property",
+                "$.days: must be at least 1",
+                "$.stops[1].name: expected string, got integer",
+                "$.units: must be one of \"metric\", \"imperial\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_checks() {
+        assert_eq!(
+            messages(json!({"type": "object"}), json!("Paris")),
+            ["$: expected object, got string"]
+        );
+        assert_eq!(
+            messages(json!({"type": "integer"}), json!(2.5)),
+            ["$: expected integer, got number"]
+        );
+        assert!(messages(json!({"type": "number"}), json!(2)).is_empty());
+        assert!(messages(json!({"type": ["string", "null"]}), json!(null)).is_empty());
+    }
+
+    #[test]
+    fn test_string_number_and_array_bounds() {
+        assert_eq!(
+            messages(
+                json!({"type": "string", "maxLength": 3, "pattern": "^[a-z]+$"}),
+                json!("Abcd")
+            ),
+            [
+                "$: must be at most 3 characters long",
+                "$: must match the pattern ^[a-z]+$",
+            ]
+        );
+        assert_eq!(
+            messages(json!({"exclusiveMinimum": 0, "multipleOf": 0.5}), json!(0)),
+            ["$: must be greater than 0"]
+        );
+        assert_eq!(
+            messages(json!({"multipleOf": 0.5}), json!(1.25)),
+            ["$: must be a multiple of 0.5"]
+        );
+        assert_eq!(
+            messages(
+                json!({"type": "array", "minItems": 2, "uniqueItems": true}),
+                json!([1])
+            ),
+            ["$: must have at least 2 items"]
+        );
+        assert_eq!(
+            messages(json!({"uniqueItems": true}), json!([1, 2, 1])),
+            ["$: items must be unique"]
+        );
+    }
+
+    #[test]
+    fn test_alternatives_report_the_closest_shape() {
+        let schema = json!({
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "op": {"const": "insert"},
+                        "text": {"type": "string"}
+                    },
+                    "required": ["op", "text"]
+                },
+                {
+                    "type": "object",
+                    "properties": {"op": {"const": "clear"}},
+                    "required": ["op"]
+                }
+            ]
+        });
+        assert!(validate_arguments(&schema, &json!({"op": "clear"})).is_empty());
+        assert_eq!(
+            messages(schema, json!({"op": "insert"})),
+            [
+                "$: must match one of 2 allowed shapes",
+                "$.text: is required"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keywords_are_ignored() {
+        let schema = json!({"type": "object", "properties": {"id": {"$ref": "#/defs/id"}}});
+        assert!(validate_arguments(&schema, &json!({"id": [1, 2]})).is_empty());
+        assert!(validate_arguments(&json!({}), &json!(42)).is_empty());
+    }
+}
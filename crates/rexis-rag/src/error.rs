@@ -3,6 +3,7 @@
 //! Comprehensive error handling designed for the Rust ecosystem.
 //! Focuses on providing detailed context while maintaining performance.
 
+use crate::agent::PartialRun;
 use thiserror::Error;
 
 /// Main error type for RRAG operations
@@ -116,6 +117,17 @@ pub enum RragError {
         operation: String,
         /// Duration in milliseconds before timeout
         duration_ms: u64,
+        /// Progress of the agent run that timed out, if any
+        partial: Option<Box<PartialRun>>,
+    },
+
+    /// Cancellation errors
+    #[error("Operation cancelled: {operation}")]
+    Cancelled {
+        /// Operation that was cancelled
+        operation: String,
+        /// Progress of the agent run that was cancelled, if any
+        partial: Option<Box<PartialRun>>,
     },
 
     /// Memory/conversation errors
@@ -158,6 +170,49 @@ pub enum RragError {
         /// Value that failed validation
         value: String,
     },
+
+    /// Agent hook errors, raised under an enforcing hook mode
+    #[error("Agent hook {event} failed: {message}")]
+    Hook {
+        /// Hook method that failed
+        event: String,
+        /// Error message from the hook
+        message: String,
+    },
+
+    /// Structured output errors
+    #[error("Structured output did not match the schema after {repairs} repairs: {message}")]
+    StructuredOutput {
+        /// Why the last response failed to parse
+        message: String,
+        /// Raw text of the last response
+        raw: String,
+        /// Repair round-trips attempted
+        repairs: u32,
+    },
+
+    /// LLM step of an agent run that kept failing under the agent's retry policy
+    #[error("LLM step of agent {agent_id} failed after {attempts} attempts")]
+    LlmStepFailed {
+        /// ID of the agent whose run failed
+        agent_id: String,
+        /// Attempts made at the step
+        attempts: u32,
+        #[source]
+        /// Error of the last attempt
+        source: Box<dyn std::error::Error + Send + Sync>,
+        /// Progress of the run before the failing step
+        partial: Option<Box<PartialRun>>,
+    },
+
+    /// Agent input rejected by a guardrail
+    #[error("Input blocked by guardrail {guardrail}: {message}")]
+    InputBlocked {
+        /// Guardrail that blocked the input
+        guardrail: String,
+        /// Message the guardrail gave for the block
+        message: String,
+    },
 }
 
 impl RragError {
@@ -255,6 +310,54 @@ impl RragError {
         Self::Timeout {
             operation: operation.into(),
             duration_ms,
+            partial: None,
+        }
+    }
+
+    /// Create a cancellation error
+    pub fn cancelled(operation: impl Into<String>) -> Self {
+        Self::Cancelled {
+            operation: operation.into(),
+            partial: None,
+        }
+    }
+
+    /// Attach the progress of an aborted agent run to a timeout, cancellation
+    /// or failed LLM step
+    ///
+    /// Other errors are returned unchanged.
+    pub fn with_partial_run(mut self, run: PartialRun) -> Self {
+        if let Self::Timeout { partial, .. }
+        | Self::Cancelled { partial, .. }
+        | Self::LlmStepFailed { partial, .. } = &mut self
+        {
+            *partial = Some(Box::new(run));
+        }
+        self
+    }
+
+    /// Progress of the aborted agent run, for timeouts, cancellations and
+    /// failed LLM steps
+    pub fn partial_run(&self) -> Option<&PartialRun> {
+        match self {
+            Self::Timeout { partial, .. }
+            | Self::Cancelled { partial, .. }
+            | Self::LlmStepFailed { partial, .. } => partial.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Create an error for an LLM step that failed after `attempts` attempts
+    pub fn llm_step_failed(
+        agent_id: impl Into<String>,
+        attempts: u32,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        Self::LlmStepFailed {
+            agent_id: agent_id.into(),
+            attempts,
+            source: Box::new(source),
+            partial: None,
         }
     }
 
@@ -296,6 +399,35 @@ impl RragError {
         }
     }
 
+    /// Create a hook error
+    pub fn hook(event: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Hook {
+            event: event.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Create a structured output error
+    pub fn structured_output(
+        message: impl Into<String>,
+        raw: impl Into<String>,
+        repairs: u32,
+    ) -> Self {
+        Self::StructuredOutput {
+            message: message.into(),
+            raw: raw.into(),
+            repairs,
+        }
+    }
+
+    /// Create an input blocked error
+    pub fn input_blocked(guardrail: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::InputBlocked {
+            guardrail: guardrail.into(),
+            message: message.into(),
+        }
+    }
+
     /// Create a network error
     pub fn network(
         operation: impl Into<String>,
@@ -363,6 +495,7 @@ impl RragError {
             Self::Network { .. } => "network",
             Self::Serialization { .. } => "serialization",
             Self::Timeout { .. } => "timeout",
+            Self::Cancelled { .. } => "cancelled",
             Self::Memory { .. } => "memory",
             Self::Stream { .. } => "stream",
             Self::Agent { agent_id, .. } => {
@@ -373,6 +506,10 @@ impl RragError {
                 }
             }
             Self::Validation { .. } => "validation",
+            Self::Hook { .. } => "hook",
+            Self::StructuredOutput { .. } => "structured_output",
+            Self::InputBlocked { .. } => "guardrail",
+            Self::LlmStepFailed { .. } => "llm_step",
         }
     }
 
@@ -380,12 +517,18 @@ impl RragError {
     pub fn severity(&self) -> ErrorSeverity {
         match self {
             Self::Configuration { .. } | Self::Validation { .. } => ErrorSeverity::Critical,
-            Self::Storage { .. } | Self::RsllmClient { .. } => ErrorSeverity::High,
+            Self::Storage { .. } | Self::RsllmClient { .. } | Self::LlmStepFailed { .. } => {
+                ErrorSeverity::High
+            }
             Self::DocumentProcessing { .. } | Self::Embedding { .. } | Self::Retrieval { .. } => {
                 ErrorSeverity::Medium
             }
-            Self::ToolExecution { .. } | Self::Agent { .. } => ErrorSeverity::Medium,
+            Self::ToolExecution { .. }
+            | Self::Agent { .. }
+            | Self::Hook { .. }
+            | Self::StructuredOutput { .. } => ErrorSeverity::Medium,
             Self::Network { .. } | Self::Timeout { .. } | Self::Stream { .. } => ErrorSeverity::Low,
+            Self::Cancelled { .. } | Self::InputBlocked { .. } => ErrorSeverity::Low,
             Self::Serialization { .. } | Self::Memory { .. } => ErrorSeverity::Low,
         }
     }
@@ -474,6 +617,7 @@ impl From<tokio::time::error::Elapsed> for RragError {
         Self::Timeout {
             operation: "async_operation".to_string(),
             duration_ms: 0, // Unknown duration
+            partial: None,
         }
     }
 }
@@ -534,9 +678,18 @@ mod tests {
 #[cfg(feature = "rexis-llm-client")]
 impl From<rexis_llm::RsllmError> for RragError {
     fn from(err: rexis_llm::RsllmError) -> Self {
-        RragError::RsllmClient {
-            operation: "LLM operation".to_string(),
-            source: Box::new(err),
+        match err {
+            // Deadlines and cancellation keep their own variants so callers can
+            // tell them apart from provider failures
+            rexis_llm::RsllmError::Timeout {
+                operation,
+                timeout_ms,
+            } => RragError::timeout(operation, timeout_ms),
+            rexis_llm::RsllmError::Cancelled { operation } => RragError::cancelled(operation),
+            err => RragError::RsllmClient {
+                operation: "LLM operation".to_string(),
+                source: Box::new(err),
+            },
         }
     }
 }
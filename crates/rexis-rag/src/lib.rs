@@ -206,8 +206,22 @@ pub mod incremental;
 pub mod observability;
 
 // Re-exports for convenience
+#[cfg(feature = "mcp")]
+pub use agent::mcp::{McpServerConfig, McpToolProvider};
+pub use agent::tools::CalculatorTool as AgentCalculatorTool;
+#[cfg(feature = "http")]
+pub use agent::tools::HttpTool as AgentHttpTool;
+pub use agent::tools::WebSearchTool as AgentWebSearchTool;
 pub use agent::{
-    Agent, AgentBuilder, AgentConfig, ConversationMemory, ConversationMode, ToolExecutor,
+    Agent, AgentBuilder, AgentConfig, AgentEvent, AgentHooks, AgentRegistry, Approval,
+    ApprovalHook, ApprovalRequest, ArgumentViolation, BudgetLimit, CachePolicy,
+    ChannelApprovalHook, ConversationMemory, ConversationMode, Guardrail, GuardrailDecision,
+    GuardrailRecord, GuardrailStage, HandoffRecord, HookMode, MaxLengthGuardrail, OnPrefix,
+    OnToolCalled, OutputReduction, PartialRun, Profile as AgentProfile, PromptTemplate,
+    RegexDenylistGuardrail, RunBudget, RunControl, RunOptions, RunResult, RunStep, StepUsage,
+    StopCondition, StopOutcome, StopReason, SyncTool, TemplateMode, Tool as AgentTool, ToolArgs,
+    ToolCache, ToolErrorPolicy, ToolExecution, ToolExecutor, ToolFailure, ToolInvocation,
+    ToolOutput, ToolRetryPolicy, ToolRetryPredicate, TracingHooks, TypedRunResult,
 };
 pub use document::{ChunkingStrategy, Document, DocumentChunk, DocumentChunker, Metadata};
 pub use embeddings::{
@@ -345,7 +359,9 @@ pub mod prelude {
 
     // Agents and tools
     pub use crate::{
-        Agent, AgentBuilder, AgentConfig, ConversationMemory, ConversationMode, ToolExecutor,
+        Agent, AgentBuilder, AgentConfig, AgentEvent, Approval, ApprovalHook, ConversationMemory,
+        ConversationMode, PartialRun, RunBudget, RunControl, RunOptions, RunResult, StopReason,
+        ToolExecutor,
     };
 
     // HTTP tools when feature is enabled
@@ -177,7 +177,7 @@
 //!             }
 //!         }
 //!     }
-//!     Err(RragError::Timeout { operation, duration_ms }) => {
+//!     Err(RragError::Timeout { operation, duration_ms, .. }) => {
 //!         tracing::debug!("Pipeline timed out in {}: {}ms", operation, duration_ms);
 //!     }
 //!     Err(e) => {
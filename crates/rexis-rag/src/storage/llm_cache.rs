@@ -0,0 +1,99 @@
+//! # LLM Response Cache Store
+//!
+//! Adapts any [`Memory`] backend into a `rexis_llm` [`CacheStore`], so chat
+//! completion caches can persist wherever agent memory lives.
+
+use super::{Memory, MemoryValue};
+use async_trait::async_trait;
+use rexis_llm::{CacheStore, RsllmError, RsllmResult};
+use std::sync::Arc;
+
+/// Response cache store backed by a [`Memory`] namespace
+pub struct MemoryCacheStore {
+    memory: Arc<dyn Memory>,
+    namespace: String,
+}
+
+impl MemoryCacheStore {
+    /// Create a store using the default `llm_cache` namespace
+    pub fn new(memory: Arc<dyn Memory>) -> Self {
+        Self::with_namespace(memory, "llm_cache")
+    }
+
+    /// Create a store in a custom namespace
+    pub fn with_namespace(memory: Arc<dyn Memory>, namespace: impl Into<String>) -> Self {
+        Self {
+            memory,
+            namespace: namespace.into(),
+        }
+    }
+
+    fn scoped_key(&self, key: &str) -> String {
+        format!("{}::{}", self.namespace, key)
+    }
+}
+
+fn storage_error(e: crate::RragError) -> RsllmError {
+    RsllmError::provider("memory_cache", e.to_string())
+}
+
+#[async_trait]
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> RsllmResult<Option<String>> {
+        let value = self
+            .memory
+            .get(&self.scoped_key(key))
+            .await
+            .map_err(storage_error)?;
+        Ok(value.and_then(|v| v.as_string().map(str::to_string)))
+    }
+
+    async fn set(&self, key: &str, value: String) -> RsllmResult<()> {
+        self.memory
+            .set(&self.scoped_key(key), MemoryValue::String(value))
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn delete(&self, key: &str) -> RsllmResult<()> {
+        self.memory
+            .delete(&self.scoped_key(key))
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> RsllmResult<()> {
+        self.memory
+            .clear(Some(&self.namespace))
+            .await
+            .map_err(storage_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+    use rexis_llm::{ChatResponse, ResponseCache};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_response_cache_over_memory() {
+        let memory: Arc<dyn Memory> = Arc::new(InMemoryStorage::new());
+        let store = Arc::new(MemoryCacheStore::new(memory.clone()));
+        let cache = ResponseCache::new(store, Duration::from_secs(60));
+
+        cache
+            .put("abc", &ChatResponse::new("cached", "gpt-4"))
+            .await
+            .unwrap();
+        assert!(memory.exists("llm_cache::abc").await.unwrap());
+
+        let hit = cache.get("abc").await.unwrap().unwrap();
+        assert_eq!(hit.content, "cached");
+
+        cache.clear().await.unwrap();
+        assert_eq!(memory.count(Some("llm_cache")).await.unwrap(), 0);
+    }
+}
@@ -73,6 +73,11 @@ pub mod database;
 #[cfg(feature = "database")]
 pub use database::{DatabaseConfig, DatabaseStorage};
 
+#[cfg(feature = "rexis-llm-client")]
+pub mod llm_cache;
+#[cfg(feature = "rexis-llm-client")]
+pub use llm_cache::MemoryCacheStore;
+
 // Re-export the original storage types for backward compatibility
 pub use crate::storage_legacy::*;
 
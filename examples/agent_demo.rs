@@ -152,7 +152,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let mut stateless_agent = AgentBuilder::new()
         .with_llm(llm_client1)
-        .with_tools(vec![
+        .with_sync_tools(vec![
             Box::new(CalculatorTool) as Box<dyn Tool>,
             Box::new(GetWeatherTool) as Box<dyn Tool>,
         ])
@@ -188,7 +188,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let mut stateful_agent = AgentBuilder::new()
         .with_llm(llm_client2)
-        .with_tools(vec![
+        .with_sync_tools(vec![
             Box::new(CalculatorTool) as Box<dyn Tool>,
             Box::new(GetWeatherTool) as Box<dyn Tool>,
             Box::new(ConvertTemperatureTool) as Box<dyn Tool>,